@@ -91,7 +91,11 @@ fn main() {
             .into();
 
         let src_memory_image = device
-            .allocate_memory(src_image_type, src_image_requirements.size)
+            .allocate_memory(
+                src_image_type,
+                src_image_requirements.size,
+                hal::memory::AllocationFlags::empty(),
+            )
             .unwrap();
         device
             .bind_image_memory(&src_memory_image, 0, &mut src_image)
@@ -123,7 +127,11 @@ fn main() {
             .into();
 
         let mut src_memory_buffer = device
-            .allocate_memory(src_buffer_type, src_buffer_requirements.size)
+            .allocate_memory(
+                src_buffer_type,
+                src_buffer_requirements.size,
+                hal::memory::AllocationFlags::empty(),
+            )
             .unwrap();
         device
             .bind_buffer_memory(&src_memory_buffer, 0, &mut src_buffer)
@@ -169,7 +177,11 @@ fn main() {
             .into();
 
         let dst_memory = device
-            .allocate_memory(dst_type, dst_requirements.size)
+            .allocate_memory(
+                dst_type,
+                dst_requirements.size,
+                hal::memory::AllocationFlags::empty(),
+            )
             .unwrap();
         device
             .bind_image_memory(&dst_memory, 0, &mut dst_image)
@@ -668,6 +668,8 @@ impl<B: Backend> RenderPassState<B> {
                 inputs: &[],
                 resolves: &[],
                 preserves: &[],
+                view_mask: 0,
+                correlation_mask: 0,
             };
 
             device
@@ -749,7 +751,13 @@ impl<B: Backend> BufferState<B> {
                 .unwrap()
                 .into();
 
-            memory = device.allocate_memory(upload_type, mem_req.size).unwrap();
+            memory = device
+                .allocate_memory(
+                    upload_type,
+                    mem_req.size,
+                    hal::memory::AllocationFlags::empty(),
+                )
+                .unwrap();
             device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
             size = mem_req.size;
 
@@ -826,7 +834,13 @@ impl<B: Backend> BufferState<B> {
                 .unwrap()
                 .into();
 
-            memory = device.allocate_memory(upload_type, mem_reqs.size).unwrap();
+            memory = device
+                .allocate_memory(
+                    upload_type,
+                    mem_reqs.size,
+                    hal::memory::AllocationFlags::empty(),
+                )
+                .unwrap();
             device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
             size = mem_reqs.size;
 
@@ -928,7 +942,11 @@ impl<B: Backend> DescSetLayout<B> {
         let desc_set_layout = device
             .borrow()
             .device
-            .create_descriptor_set_layout(bindings.into_iter(), iter::empty())
+            .create_descriptor_set_layout(
+                bindings.into_iter(),
+                iter::empty(),
+                pso::DescriptorSetLayoutCreateFlags::empty(),
+            )
             .ok();
 
         DescSetLayout {
@@ -1054,7 +1072,9 @@ impl<B: Backend> ImageState<B> {
             .unwrap()
             .into();
 
-        let memory = device.allocate_memory(device_type, req.size).unwrap();
+        let memory = device
+            .allocate_memory(device_type, req.size, hal::memory::AllocationFlags::empty())
+            .unwrap();
 
         device.bind_image_memory(&memory, 0, &mut image).unwrap();
         let image_view = device
@@ -1068,6 +1088,7 @@ impl<B: Backend> ImageState<B> {
                     aspects: f::Aspects::COLOR,
                     ..Default::default()
                 },
+                None,
             )
             .unwrap();
 
@@ -1271,7 +1292,7 @@ impl<B: Backend> PipelineState<B> {
 
                 let vertex_buffers = vec![pso::VertexBufferDesc {
                     binding: 0,
-                    stride: size_of::<Vertex>() as u32,
+                    stride: pso::State::Static(size_of::<Vertex>() as u32),
                     rate: pso::VertexInputRate::Vertex,
                 }];
 
@@ -1300,6 +1321,7 @@ impl<B: Backend> PipelineState<B> {
                         attributes: &attributes,
                         input_assembler: pso::InputAssemblerDesc {
                             primitive: pso::Primitive::TriangleList,
+                            dynamic_primitive: false,
                             with_adjacency: false,
                             restart_index: None,
                         },
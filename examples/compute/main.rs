@@ -96,6 +96,7 @@ fn main() {
                     immutable_samplers: false,
                 }),
                 iter::empty(),
+                pso::DescriptorSetLayoutCreateFlags::empty(),
             )
         }
         .expect("Can't create descriptor set layout");
@@ -304,7 +305,9 @@ unsafe fn create_buffer<B: hal::Backend>(
         .unwrap()
         .into();
 
-    let memory = device.allocate_memory(ty, requirements.size).unwrap();
+    let memory = device
+        .allocate_memory(ty, requirements.size, hal::memory::AllocationFlags::empty())
+        .unwrap();
     device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
 
     (memory, buffer, requirements.size)
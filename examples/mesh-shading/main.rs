@@ -236,6 +236,7 @@ where
                         immutable_samplers: false,
                     }),
                     iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
                 )
             }
             .expect("Can't create descriptor set layout"),
@@ -308,7 +309,11 @@ where
         // TODO: check transitions: read/write mapping and vertex buffer read
         let buffer_memory = unsafe {
             let mut memory = device
-                .allocate_memory(upload_type, buffer_req.size)
+                .allocate_memory(
+                    upload_type,
+                    buffer_req.size,
+                    hal::memory::AllocationFlags::empty(),
+                )
                 .unwrap();
             device
                 .bind_buffer_memory(&memory, 0, &mut positions_buffer)
@@ -377,6 +382,8 @@ where
                 inputs: &[],
                 resolves: &[],
                 preserves: &[],
+                view_mask: 0,
+                correlation_mask: 0,
             };
 
             ManuallyDrop::new(
@@ -367,6 +367,7 @@ where
                     ]
                     .into_iter(),
                     iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
                 )
             }
             .expect("Can't create descriptor set layout"),
@@ -439,7 +440,7 @@ where
         // TODO: check transitions: read/write mapping and vertex buffer read
         let buffer_memory = unsafe {
             let mut memory = device
-                .allocate_memory(upload_type, buffer_req.size)
+                .allocate_memory(upload_type, buffer_req.size, m::AllocationFlags::empty())
                 .unwrap();
             device
                 .bind_buffer_memory(&memory, 0, &mut vertex_buffer)
@@ -484,7 +485,11 @@ where
         // copy image data into staging buffer
         let image_upload_memory = unsafe {
             let mut memory = device
-                .allocate_memory(upload_type, image_mem_reqs.size)
+                .allocate_memory(
+                    upload_type,
+                    image_mem_reqs.size,
+                    m::AllocationFlags::empty(),
+                )
                 .unwrap();
             device
                 .bind_buffer_memory(&memory, 0, &mut image_upload_buffer)
@@ -536,7 +541,10 @@ where
             .unwrap()
             .into();
         let image_memory = ManuallyDrop::new(
-            unsafe { device.allocate_memory(device_type, image_req.size) }.unwrap(),
+            unsafe {
+                device.allocate_memory(device_type, image_req.size, m::AllocationFlags::empty())
+            }
+            .unwrap(),
         );
 
         if sparsely_bound {
@@ -586,6 +594,7 @@ where
                         aspects: f::Aspects::COLOR,
                         ..Default::default()
                     },
+                    None,
                 )
             }
             .unwrap(),
@@ -729,6 +738,8 @@ where
                 inputs: &[],
                 resolves: &[],
                 preserves: &[],
+                view_mask: 0,
+                correlation_mask: 0,
             };
 
             ManuallyDrop::new(
@@ -859,7 +870,7 @@ where
 
                 let vertex_buffers = vec![pso::VertexBufferDesc {
                     binding: 0,
-                    stride: mem::size_of::<Vertex>() as u32,
+                    stride: pso::State::Static(mem::size_of::<Vertex>() as u32),
                     rate: VertexInputRate::Vertex,
                 }];
 
@@ -888,6 +899,7 @@ where
                         attributes: &attributes,
                         input_assembler: pso::InputAssemblerDesc {
                             primitive: pso::Primitive::TriangleList,
+                            dynamic_primitive: false,
                             with_adjacency: false,
                             restart_index: None,
                         },
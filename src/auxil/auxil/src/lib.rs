@@ -95,6 +95,52 @@ pub fn read_spirv<R: io::Read + io::Seek>(mut x: R) -> io::Result<Vec<u32>> {
     Ok(result)
 }
 
+/// Read the host clock that calibrated timestamps are matched with, in nanoseconds:
+/// `CLOCK_MONOTONIC` on Unix, and `QueryPerformanceCounter` on Windows.
+#[cfg(unix)]
+pub fn host_monotonic_ns() -> u64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) };
+    time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+}
+
+/// Read the host clock that calibrated timestamps are matched with, in nanoseconds:
+/// `CLOCK_MONOTONIC` on Unix, and `QueryPerformanceCounter` on Windows.
+#[cfg(windows)]
+pub fn host_monotonic_ns() -> u64 {
+    use winapi::um::{profileapi::QueryPerformanceCounter, winnt::LARGE_INTEGER};
+
+    let ticks = unsafe {
+        let mut ticks: LARGE_INTEGER = std::mem::zeroed();
+        QueryPerformanceCounter(&mut ticks);
+        *ticks.QuadPart() as u64
+    };
+    host_ticks_to_ns(ticks)
+}
+
+/// Convert a reading of `QueryPerformanceCounter` into nanoseconds.
+#[cfg(windows)]
+pub fn host_ticks_to_ns(ticks: u64) -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use winapi::um::{profileapi::QueryPerformanceFrequency, winnt::LARGE_INTEGER};
+
+    // The frequency is fixed at boot, so it's only queried once.
+    static FREQUENCY: AtomicU64 = AtomicU64::new(0);
+    let mut frequency = FREQUENCY.load(Ordering::Relaxed);
+    if frequency == 0 {
+        frequency = unsafe {
+            let mut frequency: LARGE_INTEGER = std::mem::zeroed();
+            QueryPerformanceFrequency(&mut frequency);
+            *frequency.QuadPart() as u64
+        };
+        FREQUENCY.store(frequency, Ordering::Relaxed);
+    }
+    (ticks as u128 * 1_000_000_000 / frequency as u128) as u64
+}
+
 #[cfg(feature = "spirv_cross")]
 pub fn spirv_cross_specialize_ast<T>(
     ast: &mut spirv::Ast<T>,
@@ -525,8 +525,9 @@ pub(crate) fn map_rasterizer_desc(
     let multisampled = multisampling_desc.is_some();
     D3D11_RASTERIZER_DESC {
         FillMode: map_fill_mode(desc.polygon_mode),
-        CullMode: map_cull_mode(desc.cull_face),
-        FrontCounterClockwise: match desc.front_face {
+        //TODO: support dynamic cull mode and front face
+        CullMode: map_cull_mode(desc.cull_face.static_or(Face::NONE)),
+        FrontCounterClockwise: match desc.front_face.static_or(FrontFace::CounterClockwise) {
             FrontFace::Clockwise => FALSE,
             FrontFace::CounterClockwise => TRUE,
         },
@@ -697,12 +698,17 @@ pub(crate) fn map_depth_stencil_desc(
     desc: &DepthStencilDesc,
 ) -> (D3D11_DEPTH_STENCIL_DESC, State<StencilValue>, bool) {
     let (depth_on, depth_write, depth_func) = match desc.depth {
-        Some(ref depth) => (TRUE, depth.write, map_comparison(depth.fun)),
-        None => unsafe { mem::zeroed() },
+        State::Static(Some(ref depth)) => (
+            TRUE,
+            depth.write.static_or(false),
+            map_comparison(depth.fun.static_or(Comparison::Always)),
+        ),
+        State::Static(None) | State::Dynamic => unsafe { mem::zeroed() },
     };
 
     let (stencil_on, front, back, read_mask, write_mask, stencil_ref) = match desc.stencil {
         Some(ref stencil) => {
+            let faces = stencil.faces.static_or(Sided::default());
             let read_masks = stencil.read_masks.static_or(Sided::new(!0));
             let write_masks = stencil.read_masks.static_or(Sided::new(!0));
             let reference_value = match stencil.reference_values {
@@ -724,8 +730,8 @@ pub(crate) fn map_depth_stencil_desc(
             }
             (
                 TRUE,
-                map_stencil_side(&stencil.faces.front),
-                map_stencil_side(&stencil.faces.back),
+                map_stencil_side(&faces.front),
+                map_stencil_side(&faces.back),
                 read_masks.front,
                 write_masks.front,
                 reference_value,
@@ -872,3 +878,72 @@ pub fn map_feature_level_to_shader_model(feature: u32) -> hlsl::ShaderModel {
         _ => unimplemented!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::pso::DepthTest;
+
+    #[test]
+    fn test_dynamic_rasterizer_state() {
+        let rasterizer = Rasterizer {
+            cull_face: State::Dynamic,
+            front_face: State::Dynamic,
+            ..Rasterizer::FILL
+        };
+        let desc = map_rasterizer_desc(&rasterizer, &None);
+        assert_eq!(desc.CullMode, D3D11_CULL_NONE);
+        assert_eq!(desc.FrontCounterClockwise, TRUE);
+
+        let rasterizer = Rasterizer {
+            cull_face: State::Static(Face::FRONT),
+            front_face: State::Static(FrontFace::Clockwise),
+            ..Rasterizer::FILL
+        };
+        let desc = map_rasterizer_desc(&rasterizer, &None);
+        assert_eq!(desc.CullMode, D3D11_CULL_FRONT);
+        assert_eq!(desc.FrontCounterClockwise, FALSE);
+    }
+
+    #[test]
+    fn test_dynamic_depth_state() {
+        let cases = [
+            (State::Dynamic, FALSE, D3D11_DEPTH_WRITE_MASK_ZERO, 0, true),
+            (
+                State::Static(None),
+                FALSE,
+                D3D11_DEPTH_WRITE_MASK_ZERO,
+                0,
+                true,
+            ),
+            (
+                State::Static(Some(DepthTest::PASS_WRITE)),
+                TRUE,
+                D3D11_DEPTH_WRITE_MASK_ALL,
+                D3D11_COMPARISON_ALWAYS,
+                false,
+            ),
+            (
+                State::Static(Some(DepthTest {
+                    fun: State::Static(Comparison::Less),
+                    write: State::Dynamic,
+                })),
+                TRUE,
+                D3D11_DEPTH_WRITE_MASK_ZERO,
+                D3D11_COMPARISON_LESS,
+                true,
+            ),
+        ];
+        for (depth, enable, write_mask, func, read_only) in cases.iter().cloned() {
+            let (desc, _, is_read_only) = map_depth_stencil_desc(&DepthStencilDesc {
+                depth,
+                depth_bounds: false,
+                stencil: None,
+            });
+            assert_eq!(desc.DepthEnable, enable);
+            assert_eq!(desc.DepthWriteMask, write_mask);
+            assert_eq!(desc.DepthFunc, func);
+            assert_eq!(is_read_only, read_only);
+        }
+    }
+}
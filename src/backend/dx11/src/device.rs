@@ -192,7 +192,7 @@ impl Device {
                 vertex_strides.push(0);
             }
 
-            vertex_strides[buffer.binding as usize] = buffer.stride;
+            vertex_strides[buffer.binding as usize] = buffer.stride.static_or(0);
         }
 
         // See [`shader::introspect_spirv_vertex_semantic_remapping`] for details of why this is needed.
@@ -864,6 +864,7 @@ impl device::Device<Backend> for Device {
         &self,
         mem_type: hal::MemoryTypeId,
         size: u64,
+        _flags: memory::AllocationFlags,
     ) -> Result<Memory, device::AllocationError> {
         let properties = self.memory_properties.memory_types[mem_type.0].properties;
         let host_ptr = if properties.contains(hal::memory::Properties::CPU_VISIBLE) {
@@ -1428,6 +1429,10 @@ impl device::Device<Backend> for Device {
         Ok(())
     }
 
+    unsafe fn get_buffer_device_address(&self, _buffer: &Buffer) -> buffer::DeviceAddress {
+        unimplemented!()
+    }
+
     unsafe fn create_buffer_view(
         &self,
         _buffer: &Buffer,
@@ -1798,6 +1803,7 @@ impl device::Device<Backend> for Device {
         _swizzle: format::Swizzle,
         usage: image::Usage,
         range: image::SubresourceRange,
+        _ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<ImageView, image::ViewCreationError> {
         let is_array = image.kind.num_layers() > 1;
         let num_levels = range.resolve_level_count(image.mip_levels);
@@ -1968,6 +1974,7 @@ impl device::Device<Backend> for Device {
         &self,
         layout_bindings: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<DescriptorSetLayout, device::OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
@@ -2083,6 +2090,7 @@ impl device::Device<Backend> for Device {
                     s: sampler.sampler_handle.as_raw() as *mut _,
                 },
                 pso::Descriptor::TexelBuffer(_buffer_view) => unimplemented!(),
+                pso::Descriptor::AccelerationStructure(_) => unimplemented!(),
             };
 
             let content = DescriptorContent::from(binding.ty);
@@ -2169,6 +2177,26 @@ impl device::Device<Backend> for Device {
         }*/
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _layout: &DescriptorSetLayout,
+        entries: I,
+    ) -> Result<pso::EmulatedDescriptorUpdateTemplate, device::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut DescriptorSet,
+        template: &pso::EmulatedDescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
+    }
+
     unsafe fn map_memory(
         &self,
         memory: &mut Memory,
@@ -2305,6 +2333,18 @@ impl device::Device<Backend> for Device {
         }
     }
 
+    fn memory_budget(&self) -> Vec<hal::adapter::MemoryHeapBudget> {
+        // The usage isn't tracked, so the whole heaps are available.
+        self.memory_properties
+            .memory_heaps
+            .iter()
+            .map(|heap| hal::adapter::MemoryHeapBudget {
+                budget: heap.size,
+                usage: 0,
+            })
+            .collect()
+    }
+
     unsafe fn create_query_pool(
         &self,
         _query_ty: query::Type,
@@ -2313,6 +2353,24 @@ impl device::Device<Backend> for Device {
         unimplemented!()
     }
 
+    unsafe fn create_performance_query_pool(
+        &self,
+        _family: QueueFamilyId,
+        _counters: &[u32],
+        _count: query::Id,
+    ) -> Result<QueryPool, query::CreationError> {
+        Err(query::CreationError::Unsupported(
+            query::Type::PerformanceCounters,
+        ))
+    }
+
+    unsafe fn acquire_profiling_lock(&self, _timeout_ns: u64) -> Result<bool, device::OutOfMemory> {
+        // Performance queries aren't supported, so there is no lock to hold.
+        Ok(false)
+    }
+
+    unsafe fn release_profiling_lock(&self) {}
+
     unsafe fn destroy_query_pool(&self, _pool: QueryPool) {
         unimplemented!()
     }
@@ -2380,6 +2438,32 @@ impl device::Device<Backend> for Device {
 
     unsafe fn destroy_sampler(&self, _sampler: Sampler) {}
 
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        _desc: &hal::acceleration_structure::GeometryDesc<Backend>,
+        _max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        unimplemented!()
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        _desc: &hal::acceleration_structure::CreateDesc<Backend>,
+    ) -> Result<(), device::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        _accel_struct: &(),
+    ) -> hal::acceleration_structure::DeviceAddress {
+        unimplemented!()
+    }
+
+    unsafe fn destroy_acceleration_structure(&self, _accel_struct: ()) {
+        unimplemented!()
+    }
+
     unsafe fn destroy_descriptor_pool(&self, _pool: DescriptorPool) {
         //unimplemented!()
     }
@@ -2388,6 +2472,12 @@ impl device::Device<Backend> for Device {
         //unimplemented!()
     }
 
+    unsafe fn destroy_descriptor_update_template(
+        &self,
+        _template: pso::EmulatedDescriptorUpdateTemplate,
+    ) {
+    }
+
     unsafe fn destroy_fence(&self, _fence: Fence) {
         // unimplemented!()
     }
@@ -2396,15 +2486,59 @@ impl device::Device<Backend> for Device {
         //unimplemented!()
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: u64,
+    ) -> Result<Semaphore, device::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        _semaphore: &Semaphore,
+    ) -> Result<u64, device::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        _semaphores: I,
+        _wait: device::WaitFor,
+        _timeout_ns: u64,
+    ) -> Result<bool, device::WaitError>
+    where
+        I: Iterator<Item = (&'a Semaphore, u64)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &Semaphore,
+        _value: u64,
+    ) -> Result<(), device::OutOfMemory> {
+        unimplemented!()
+    }
+
     unsafe fn destroy_event(&self, _event: ()) {
         //unimplemented!()
     }
 
-    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+    fn get_calibrated_timestamps(
+        &self,
+    ) -> Result<device::CalibratedTimestamps, device::OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn wait_idle(&self) -> Result<(), device::WaitError> {
         Ok(())
         // unimplemented!()
     }
 
+    unsafe fn get_fault_info(&self) -> Option<device::FaultInfo> {
+        None
+    }
+
     unsafe fn set_image_name(&self, image: &mut Image, name: &str) {
         if !verify_debug_ascii(name) {
             return;
@@ -1013,6 +1013,13 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         self.properties
     }
 
+    fn performance_counters(
+        &self,
+        _family: queue::QueueFamilyId,
+    ) -> Vec<query::PerformanceCounter> {
+        Vec::new()
+    }
+
     unsafe fn enumerate_displays(&self) -> Vec<display::Display<crate::Backend>> {
         unimplemented!();
     }
@@ -1373,7 +1380,7 @@ impl queue::Queue<Backend> for Queue {
         Ok(None)
     }
 
-    fn wait_idle(&mut self) -> Result<(), hal::device::OutOfMemory> {
+    fn wait_idle(&mut self) -> Result<(), hal::device::WaitError> {
         // unimplemented!()
         Ok(())
     }
@@ -2340,6 +2347,21 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         self.render_pass_cache = None;
     }
 
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        _render_area: pso::Rect,
+        _color_attachments: T,
+        _depth_stencil_attachment: Option<command::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = command::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn pipeline_barrier<'a, T>(
         &mut self,
         _stages: Range<pso::PipelineStage>,
@@ -2477,6 +2499,16 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         self.cache.bind_vertex_buffers(&self.context);
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        _first_binding: pso::BufferIndex,
+        _buffers: T,
+    ) where
+        T: Iterator<Item = (&'a Buffer, buffer::SubRange, pso::ElemStride)>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, _first_viewport: u32, viewports: T)
     where
         T: Iterator<Item = pso::Viewport>,
@@ -2532,6 +2564,34 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         // unimplemented!()
     }
 
+    unsafe fn set_cull_mode(&mut self, _faces: pso::Face) {
+        unimplemented!()
+    }
+
+    unsafe fn set_front_face(&mut self, _front_face: pso::FrontFace) {
+        unimplemented!()
+    }
+
+    unsafe fn set_primitive_topology(&mut self, _primitive: pso::Primitive) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, _fun: pso::Comparison) {
+        unimplemented!()
+    }
+
+    unsafe fn set_stencil_op(&mut self, _faces: pso::Face, _ops: pso::StencilFace) {
+        unimplemented!()
+    }
+
     unsafe fn bind_graphics_pipeline(&mut self, pipeline: &GraphicsPipeline) {
         self.cache.set_graphics_pipeline(pipeline.clone());
         self.cache.bind(&self.context);
@@ -2857,6 +2917,32 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.context.Dispatch(count[0], count[1], count[2]);
     }
@@ -3079,6 +3165,22 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
             .copy_image_to_buffer(&self.context, image, buffer, regions);
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, _descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn copy_acceleration_structure(&mut self, _src: &(), _dst: &()) {
+        unimplemented!()
+    }
+
     unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
         self.context.DrawInstanced(
             vertices.end - vertices.start,
@@ -3221,6 +3323,34 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!()
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, _first_binding: u32, _buffers: T)
+    where
+        T: Iterator<Item = (&'a Buffer, buffer::SubRange)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn push_graphics_constants(
         &mut self,
         _layout: &PipelineLayout,
@@ -4334,6 +4464,7 @@ impl From<pso::DescriptorType> for DescriptorContent {
                 ty: Bdt::Storage { read_only: false },
                 ..
             } => DescriptorContent::UAV,
+            Dt::AccelerationStructure => unimplemented!(),
         }
     }
 }
@@ -4508,6 +4639,7 @@ impl hal::Backend for Backend {
     type PipelineLayout = PipelineLayout;
     type PipelineCache = ();
     type DescriptorSetLayout = DescriptorSetLayout;
+    type DescriptorUpdateTemplate = pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = DescriptorPool;
     type DescriptorSet = DescriptorSet;
 
@@ -4518,6 +4650,8 @@ impl hal::Backend for Backend {
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = ();
 }
 
 fn validate_line_width(width: f32) {
@@ -1411,6 +1411,21 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        _render_area: pso::Rect,
+        _color_attachments: T,
+        _depth_stencil_attachment: Option<com::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = com::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn pipeline_barrier<'a, T>(
         &mut self,
         stages: Range<pso::PipelineStage>,
@@ -2003,6 +2018,16 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.set_vertex_buffers();
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        _first_binding: pso::BufferIndex,
+        _buffers: T,
+    ) where
+        T: Iterator<Item = (&'a r::Buffer, buffer::SubRange, pso::ElemStride)>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
     where
         T: Iterator<Item = pso::Viewport>,
@@ -2090,6 +2115,34 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         // unimplemented!()
     }
 
+    unsafe fn set_cull_mode(&mut self, _faces: pso::Face) {
+        unimplemented!()
+    }
+
+    unsafe fn set_front_face(&mut self, _front_face: pso::FrontFace) {
+        unimplemented!()
+    }
+
+    unsafe fn set_primitive_topology(&mut self, _primitive: pso::Primitive) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, _fun: pso::Comparison) {
+        unimplemented!()
+    }
+
+    unsafe fn set_stencil_op(&mut self, _faces: pso::Face, _ops: pso::StencilFace) {
+        unimplemented!()
+    }
+
     unsafe fn bind_graphics_pipeline(&mut self, pipeline: &r::GraphicsPipeline) {
         match self.gr_pipeline.pipeline {
             Some((_, ref shared)) if Arc::ptr_eq(shared, &pipeline.shared) => {
@@ -2186,6 +2239,32 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &r::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &r::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.set_compute_bind_point();
         self.raw.dispatch(count);
@@ -2565,6 +2644,22 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, _descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn copy_acceleration_structure(&mut self, _src: &(), _dst: &()) {
+        unimplemented!()
+    }
+
     unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
         self.set_graphics_bind_point();
         self.raw.draw(
@@ -2737,6 +2832,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
                 self.pipeline_stats_query = Some(query.id);
                 d3d12::D3D12_QUERY_TYPE_PIPELINE_STATISTICS
             }
+            query::Type::PerformanceCounters => unreachable!(),
         };
 
         self.raw
@@ -2793,6 +2889,34 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &r::Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, _first_binding: u32, _buffers: T)
+    where
+        T: Iterator<Item = (&'a r::Buffer, buffer::SubRange)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn push_graphics_constants(
         &mut self,
         _layout: &r::PipelineLayout,
@@ -275,13 +275,13 @@ pub fn map_rasterizer(rasterizer: &pso::Rasterizer, multisample: bool) -> D3D12_
             Line => D3D12_FILL_MODE_WIREFRAME,
             Fill => D3D12_FILL_MODE_SOLID,
         },
-        CullMode: match rasterizer.cull_face {
+        CullMode: match rasterizer.cull_face.static_or(pso::Face::NONE) {
             pso::Face::NONE => D3D12_CULL_MODE_NONE,
             pso::Face::FRONT => D3D12_CULL_MODE_FRONT,
             pso::Face::BACK => D3D12_CULL_MODE_BACK,
             _ => panic!("Culling both front and back faces is not supported"),
         },
-        FrontCounterClockwise: match rasterizer.front_face {
+        FrontCounterClockwise: match rasterizer.front_face.static_or(CounterClockwise) {
             Clockwise => FALSE,
             CounterClockwise => TRUE,
         },
@@ -392,12 +392,17 @@ pub fn map_render_targets(
 
 pub fn map_depth_stencil(dsi: &pso::DepthStencilDesc) -> D3D12_DEPTH_STENCIL_DESC {
     let (depth_on, depth_write, depth_func) = match dsi.depth {
-        Some(ref depth) => (TRUE, depth.write, map_comparison(depth.fun)),
-        None => unsafe { mem::zeroed() },
+        pso::State::Static(Some(ref depth)) => (
+            TRUE,
+            depth.write.static_or(false),
+            map_comparison(depth.fun.static_or(pso::Comparison::Always)),
+        ),
+        pso::State::Static(None) | pso::State::Dynamic => unsafe { mem::zeroed() },
     };
 
     let (stencil_on, front, back, read_mask, write_mask) = match dsi.stencil {
         Some(ref stencil) => {
+            let faces = stencil.faces.static_or(pso::Sided::default());
             let read_masks = stencil.read_masks.static_or(pso::Sided::new(!0));
             let write_masks = stencil.write_masks.static_or(pso::Sided::new(!0));
             if read_masks.front != read_masks.back || write_masks.front != write_masks.back {
@@ -408,8 +413,8 @@ pub fn map_depth_stencil(dsi: &pso::DepthStencilDesc) -> D3D12_DEPTH_STENCIL_DES
             }
             (
                 TRUE,
-                map_stencil_side(&stencil.faces.front),
-                map_stencil_side(&stencil.faces.back),
+                map_stencil_side(&faces.front),
+                map_stencil_side(&faces.back),
                 read_masks.front,
                 write_masks.front,
             )
@@ -1276,6 +1276,7 @@ impl d::Device<B> for Device {
         &self,
         mem_type: hal::MemoryTypeId,
         size: u64,
+        _flags: memory::AllocationFlags,
     ) -> Result<r::Memory, d::AllocationError> {
         let mem_type = mem_type.0;
         let mem_base_id = mem_type % NUM_HEAP_PROPERTIES;
@@ -2036,7 +2037,7 @@ impl d::Device<B> for Device {
         let mut vertex_strides = [0; MAX_VERTEX_BUFFERS];
 
         for buffer in vertex_buffers {
-            vertex_strides[buffer.binding as usize] = buffer.stride;
+            vertex_strides[buffer.binding as usize] = buffer.stride.static_or(0);
         }
         // Fill in identity mapping where we don't need to adjust anything.
         for attrib in attributes {
@@ -2143,8 +2144,11 @@ impl d::Device<B> for Device {
 
         // TODO: check maximum number of rtvs
         // Get associated subpass information
+        let subpass = match desc.target {
+            pass::PipelineTarget::Subpass(ref subpass) => subpass,
+            pass::PipelineTarget::Rendering(_) => unimplemented!(),
+        };
         let pass = {
-            let subpass = &desc.subpass;
             match subpass.main_pass.subpasses.get(subpass.index as usize) {
                 Some(subpass) => subpass,
                 None => return Err(pso::CreationError::InvalidSubpass(subpass.index)),
@@ -2157,7 +2161,7 @@ impl d::Device<B> for Device {
                 d3d12::D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT as usize];
             let mut num_rtvs = 0;
             for (rtv, target) in rtvs.iter_mut().zip(pass.color_attachments.iter()) {
-                let format = desc.subpass.main_pass.attachments[target.0].format;
+                let format = subpass.main_pass.attachments[target.0].format;
                 *rtv = format
                     .and_then(conv::map_format)
                     .unwrap_or(dxgiformat::DXGI_FORMAT_UNKNOWN);
@@ -2225,7 +2229,7 @@ impl d::Device<B> for Device {
             DSVFormat: pass
                 .depth_stencil_attachment
                 .and_then(|att_ref| {
-                    desc.subpass.main_pass.attachments[att_ref.0]
+                    subpass.main_pass.attachments[att_ref.0]
                         .format
                         .and_then(|f| conv::map_format_dsv(f.base_format().0))
                 })
@@ -2499,6 +2503,10 @@ impl d::Device<B> for Device {
         Ok(())
     }
 
+    unsafe fn get_buffer_device_address(&self, _buffer: &r::Buffer) -> buffer::DeviceAddress {
+        unimplemented!()
+    }
+
     unsafe fn create_buffer_view(
         &self,
         buffer: &r::Buffer,
@@ -2794,6 +2802,7 @@ impl d::Device<B> for Device {
         swizzle: format::Swizzle,
         usage: image::Usage,
         range: image::SubresourceRange,
+        _ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<r::ImageView, image::ViewCreationError> {
         let image = image.expect_bound();
         let is_array = image.kind.num_layers() > 1;
@@ -3015,6 +3024,7 @@ impl d::Device<B> for Device {
         &self,
         bindings: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<r::DescriptorSetLayout, d::OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
@@ -3159,6 +3169,7 @@ impl d::Device<B> for Device {
                         src_uav = Some(handle.raw);
                     }
                 }
+                pso::Descriptor::AccelerationStructure(_) => unimplemented!(),
             }
 
             if let Some(handle) = src_cbv {
@@ -3257,6 +3268,26 @@ impl d::Device<B> for Device {
         accum.flush(self.raw.clone());
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _layout: &r::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<pso::EmulatedDescriptorUpdateTemplate, d::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut r::DescriptorSet,
+        template: &pso::EmulatedDescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
+    }
+
     unsafe fn map_memory(
         &self,
         memory: &mut r::Memory,
@@ -3443,6 +3474,17 @@ impl d::Device<B> for Device {
         }
     }
 
+    fn memory_budget(&self) -> Vec<hal::adapter::MemoryHeapBudget> {
+        // The usage isn't tracked, so the whole heaps are available.
+        self.memory_heaps
+            .iter()
+            .map(|heap| hal::adapter::MemoryHeapBudget {
+                budget: heap.size,
+                usage: 0,
+            })
+            .collect()
+    }
+
     unsafe fn create_query_pool(
         &self,
         query_ty: query::Type,
@@ -3452,6 +3494,9 @@ impl d::Device<B> for Device {
             query::Type::Occlusion => native::QueryHeapType::Occlusion,
             query::Type::PipelineStatistics(_) => native::QueryHeapType::PipelineStatistics,
             query::Type::Timestamp => native::QueryHeapType::Timestamp,
+            query::Type::PerformanceCounters => {
+                return Err(query::CreationError::Unsupported(query_ty))
+            }
         };
 
         let (query_heap, hr) = self.raw.create_query_heap(heap_ty, count, 0);
@@ -3463,6 +3508,24 @@ impl d::Device<B> for Device {
         })
     }
 
+    unsafe fn create_performance_query_pool(
+        &self,
+        _family: QueueFamilyId,
+        _counters: &[u32],
+        _count: query::Id,
+    ) -> Result<r::QueryPool, query::CreationError> {
+        Err(query::CreationError::Unsupported(
+            query::Type::PerformanceCounters,
+        ))
+    }
+
+    unsafe fn acquire_profiling_lock(&self, _timeout_ns: u64) -> Result<bool, d::OutOfMemory> {
+        // Performance queries aren't supported, so there is no lock to hold.
+        Ok(false)
+    }
+
+    unsafe fn release_profiling_lock(&self) {}
+
     unsafe fn destroy_query_pool(&self, pool: r::QueryPool) {
         pool.raw.destroy();
     }
@@ -3555,6 +3618,7 @@ impl d::Device<B> for Device {
             query::Type::Occlusion => d3d12::D3D12_QUERY_TYPE_OCCLUSION,
             query::Type::PipelineStatistics(_) => d3d12::D3D12_QUERY_TYPE_PIPELINE_STATISTICS,
             query::Type::Timestamp => d3d12::D3D12_QUERY_TYPE_TIMESTAMP,
+            query::Type::PerformanceCounters => unreachable!(),
         };
         com_list.ResolveQueryData(
             pool.raw.as_mut_ptr(),
@@ -3691,6 +3755,32 @@ impl d::Device<B> for Device {
         // We don't destroy samplers, they are permanently cached
     }
 
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        _desc: &hal::acceleration_structure::GeometryDesc<B>,
+        _max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        unimplemented!()
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        _desc: &hal::acceleration_structure::CreateDesc<B>,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        _accel_struct: &(),
+    ) -> hal::acceleration_structure::DeviceAddress {
+        unimplemented!()
+    }
+
+    unsafe fn destroy_acceleration_structure(&self, _accel_struct: ()) {
+        unimplemented!()
+    }
+
     unsafe fn destroy_descriptor_pool(&self, pool: r::DescriptorPool) {
         let view_range = pool.heap_srv_cbv_uav.range_allocator.initial_range();
         if view_range.start < view_range.end {
@@ -3705,6 +3795,12 @@ impl d::Device<B> for Device {
         // Just drop
     }
 
+    unsafe fn destroy_descriptor_update_template(
+        &self,
+        _template: pso::EmulatedDescriptorUpdateTemplate,
+    ) {
+    }
+
     unsafe fn destroy_fence(&self, fence: r::Fence) {
         fence.raw.destroy();
     }
@@ -3713,17 +3809,59 @@ impl d::Device<B> for Device {
         semaphore.raw.destroy();
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: u64,
+    ) -> Result<r::Semaphore, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        _semaphore: &r::Semaphore,
+    ) -> Result<u64, d::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        _semaphores: I,
+        _wait: d::WaitFor,
+        _timeout_ns: u64,
+    ) -> Result<bool, d::WaitError>
+    where
+        I: Iterator<Item = (&'a r::Semaphore, u64)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &r::Semaphore,
+        _value: u64,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
     unsafe fn destroy_event(&self, _event: ()) {
         unimplemented!()
     }
 
-    fn wait_idle(&self) -> Result<(), d::OutOfMemory> {
+    fn get_calibrated_timestamps(&self) -> Result<d::CalibratedTimestamps, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn wait_idle(&self) -> Result<(), d::WaitError> {
         for queue in &self.queues {
             queue.wait_idle_impl()?;
         }
         Ok(())
     }
 
+    unsafe fn get_fault_info(&self) -> Option<d::FaultInfo> {
+        None
+    }
+
     unsafe fn set_image_name(&self, image: &mut r::Image, name: &str) {
         let cwstr = wide_cstr(name);
         match *image {
@@ -3896,3 +4034,76 @@ impl d::Device<B> for Device {
 fn test_identity_mapping() {
     assert_eq!(conv::map_swizzle(format::Swizzle::NO), IDENTITY_MAPPING);
 }
+
+#[test]
+fn test_dynamic_rasterizer_state() {
+    let rasterizer = pso::Rasterizer {
+        cull_face: pso::State::Dynamic,
+        front_face: pso::State::Dynamic,
+        ..pso::Rasterizer::FILL
+    };
+    let desc = conv::map_rasterizer(&rasterizer, false);
+    assert_eq!(desc.CullMode, d3d12::D3D12_CULL_MODE_NONE);
+    assert_eq!(desc.FrontCounterClockwise, TRUE);
+
+    let rasterizer = pso::Rasterizer {
+        cull_face: pso::State::Static(pso::Face::BACK),
+        front_face: pso::State::Static(pso::FrontFace::Clockwise),
+        ..pso::Rasterizer::FILL
+    };
+    let desc = conv::map_rasterizer(&rasterizer, false);
+    assert_eq!(desc.CullMode, d3d12::D3D12_CULL_MODE_BACK);
+    assert_eq!(desc.FrontCounterClockwise, FALSE);
+}
+
+#[test]
+fn test_dynamic_depth_state() {
+    let cases = [
+        (
+            pso::State::Dynamic,
+            FALSE,
+            d3d12::D3D12_DEPTH_WRITE_MASK_ZERO,
+            0,
+        ),
+        (
+            pso::State::Static(None),
+            FALSE,
+            d3d12::D3D12_DEPTH_WRITE_MASK_ZERO,
+            0,
+        ),
+        (
+            pso::State::Static(Some(pso::DepthTest::PASS_WRITE)),
+            TRUE,
+            d3d12::D3D12_DEPTH_WRITE_MASK_ALL,
+            d3d12::D3D12_COMPARISON_FUNC_ALWAYS,
+        ),
+        (
+            pso::State::Static(Some(pso::DepthTest {
+                fun: pso::State::Static(pso::Comparison::Less),
+                write: pso::State::Dynamic,
+            })),
+            TRUE,
+            d3d12::D3D12_DEPTH_WRITE_MASK_ZERO,
+            d3d12::D3D12_COMPARISON_FUNC_LESS,
+        ),
+        (
+            pso::State::Static(Some(pso::DepthTest {
+                fun: pso::State::Dynamic,
+                write: pso::State::Static(true),
+            })),
+            TRUE,
+            d3d12::D3D12_DEPTH_WRITE_MASK_ALL,
+            d3d12::D3D12_COMPARISON_FUNC_ALWAYS,
+        ),
+    ];
+    for (depth, enable, write_mask, func) in cases.iter().cloned() {
+        let desc = conv::map_depth_stencil(&pso::DepthStencilDesc {
+            depth,
+            depth_bounds: false,
+            stencil: None,
+        });
+        assert_eq!(desc.DepthEnable, enable);
+        assert_eq!(desc.DepthWriteMask, write_mask);
+        assert_eq!(desc.DepthFunc, func);
+    }
+}
@@ -35,8 +35,8 @@ mod window;
 
 use auxil::FastHashMap;
 use hal::{
-    adapter, display, format as f, image, memory, pso::PipelineStage, queue as q, Features, Limits,
-    PhysicalDeviceProperties,
+    adapter, display, format as f, image, memory, pso::PipelineStage, query, queue as q, Features,
+    Limits, PhysicalDeviceProperties,
 };
 use range_alloc::RangeAllocator;
 
@@ -474,6 +474,10 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         self.properties
     }
 
+    fn performance_counters(&self, _family: q::QueueFamilyId) -> Vec<query::PerformanceCounter> {
+        Vec::new()
+    }
+
     unsafe fn enumerate_displays(&self) -> Vec<display::Display<crate::Backend>> {
         unimplemented!();
     }
@@ -728,8 +732,9 @@ impl q::Queue<Backend> for Queue {
         surface.present(image).map(|()| None)
     }
 
-    fn wait_idle(&mut self) -> Result<(), hal::device::OutOfMemory> {
-        self.wait_idle_impl()
+    fn wait_idle(&mut self) -> Result<(), hal::device::WaitError> {
+        self.wait_idle_impl()?;
+        Ok(())
     }
 
     fn timestamp_period(&self) -> f32 {
@@ -804,6 +809,7 @@ pub struct Device {
     features: Features,
     format_properties: Arc<FormatProperties>,
     heap_properties: &'static [HeapProperties],
+    memory_heaps: Vec<adapter::MemoryHeap>,
     // CPU only pools
     rtv_pool: Mutex<DescriptorCpuPool>,
     dsv_pool: Mutex<DescriptorCpuPool>,
@@ -897,6 +903,7 @@ impl Device {
             features: Features::empty(),
             format_properties: physical_device.format_properties.clone(),
             heap_properties: physical_device.heap_properties,
+            memory_heaps: physical_device.memory_properties.memory_heaps.clone(),
             rtv_pool: Mutex::new(rtv_pool),
             dsv_pool: Mutex::new(dsv_pool),
             srv_uav_pool: Mutex::new(srv_uav_pool),
@@ -1593,6 +1600,7 @@ impl hal::Backend for Backend {
     type PipelineLayout = resource::PipelineLayout;
     type PipelineCache = ();
     type DescriptorSetLayout = resource::DescriptorSetLayout;
+    type DescriptorUpdateTemplate = pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = resource::DescriptorPool;
     type DescriptorSet = resource::DescriptorSet;
 
@@ -1603,6 +1611,8 @@ impl hal::Backend for Backend {
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = ();
 }
 
 fn validate_line_width(width: f32) {
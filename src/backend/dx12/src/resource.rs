@@ -521,6 +521,8 @@ impl From<pso::DescriptorType> for DescriptorContent {
                 },
             },
             Dt::InputAttachment => Dc::SRV,
+            // Acceleration structures are bound as shader resource views in DXR
+            Dt::AccelerationStructure => Dc::SRV,
         }
     }
 }
@@ -1,11 +1,75 @@
+use crate::memory::Binding;
+
 #[derive(Debug)]
 pub struct Buffer {
     /// Size of this buffer
     pub(crate) size: u64,
+    /// Memory this buffer is bound to, if any.
+    pub(crate) memory: Option<Binding>,
 }
 
 impl Buffer {
     pub fn new(size: u64) -> Self {
-        Buffer { size }
+        Buffer { size, memory: None }
+    }
+
+    pub(crate) fn binding(&self) -> &Binding {
+        self.memory
+            .as_ref()
+            .expect("Buffer is not bound to any memory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile};
+    use hal::{
+        buffer,
+        device::Device as _,
+        memory::{AllocationFlags, Segment, SparseFlags},
+        MemoryTypeId,
+    };
+    use std::iter;
+
+    #[test]
+    fn device_address_is_host_pointer() {
+        let profile = Profile::default().with_features(hal::Features::BUFFER_DEVICE_ADDRESS);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (_, gpu) = crate::open_device(&instance, hal::Features::BUFFER_DEVICE_ADDRESS);
+        let device = &gpu.device;
+
+        unsafe {
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 128, AllocationFlags::DEVICE_ADDRESS)
+                .unwrap();
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+
+            let buffer = |offset: u64| {
+                let mut buffer = device
+                    .create_buffer(64, buffer::Usage::DEVICE_ADDRESS, SparseFlags::empty())
+                    .unwrap();
+                device
+                    .bind_buffer_memory(&memory, offset, &mut buffer)
+                    .unwrap();
+                buffer
+            };
+            let first = buffer(0);
+            let second = buffer(64);
+
+            let address = device.get_buffer_device_address(&first);
+            assert_eq!(address, ptr as buffer::DeviceAddress);
+            assert_eq!(device.get_buffer_device_address(&second), address + 64);
+
+            // Store a pointer to the second buffer in the first one, and read through it
+            *(ptr.offset(64) as *mut u32) = 0xC0FFEE;
+            *(ptr as *mut buffer::DeviceAddress) = device.get_buffer_device_address(&second);
+            let pointer = *(address as *const buffer::DeviceAddress);
+            assert_eq!(*(pointer as *const u32), 0xC0FFEE);
+
+            device.destroy_buffer(first);
+            device.destroy_buffer(second);
+            device.unmap_memory(&mut memory);
+            device.free_memory(memory);
+        }
     }
 }
@@ -0,0 +1,1011 @@
+//! Acceleration structures, built into bounding volume hierarchies on the CPU.
+//!
+//! The hierarchies are kept on the side rather than in the memory of the storage
+//! buffer, and top level structures find the bottom level ones their instances
+//! refer to through a per-device registry of addresses.
+
+use crate::{memory::Binding, Backend};
+use hal::{
+    acceleration_structure::{self as accel, DeviceAddress},
+    format::Format,
+    IndexType,
+};
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    ops::Range,
+    ptr,
+    sync::{atomic, Arc, Mutex, Weak},
+};
+
+/// Maximum number of primitives in a leaf node.
+const MAX_LEAF_SIZE: usize = 4;
+/// Alignment of acceleration structure addresses and offsets.
+const ALIGNMENT: u64 = 256;
+/// Reported size of a node.
+const NODE_SIZE: u64 = 32;
+/// Reported size of a primitive, which is the size of an instance.
+const PRIMITIVE_SIZE: u64 = 64;
+
+type Vec3 = [f32; 3];
+type Transform = [[f32; 4]; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn transform_vector(m: &Transform, v: Vec3) -> Vec3 {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+fn transform_point(m: &Transform, p: Vec3) -> Vec3 {
+    let v = transform_vector(m, p);
+    [v[0] + m[0][3], v[1] + m[1][3], v[2] + m[2][3]]
+}
+
+/// Inverts an affine transform. Returns `None` if it's singular.
+fn invert(m: &Transform) -> Option<Transform> {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let det = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2)
+        + m[0][2] * cofactor(1, 2, 0, 1);
+    if det == 0.0 {
+        return None;
+    }
+    let inv = 1.0 / det;
+    let mut result = [
+        [
+            cofactor(1, 2, 1, 2) * inv,
+            -cofactor(0, 2, 1, 2) * inv,
+            cofactor(0, 1, 1, 2) * inv,
+            0.0,
+        ],
+        [
+            -cofactor(1, 2, 0, 2) * inv,
+            cofactor(0, 2, 0, 2) * inv,
+            -cofactor(0, 1, 0, 2) * inv,
+            0.0,
+        ],
+        [
+            cofactor(1, 2, 0, 1) * inv,
+            -cofactor(0, 2, 0, 1) * inv,
+            cofactor(0, 1, 0, 1) * inv,
+            0.0,
+        ],
+    ];
+    let translation = transform_vector(&result, [m[0][3], m[1][3], m[2][3]]);
+    for (row, t) in result.iter_mut().zip(&translation) {
+        row[3] = -t;
+    }
+    Some(result)
+}
+
+/// Reads a value from memory, with no alignment requirement.
+fn read<T: Copy>(binding: &Binding, offset: u64) -> T {
+    let size = std::mem::size_of::<T>() as u64;
+    unsafe { ptr::read_unaligned(binding.ptr(offset, size) as *const T) }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Aabb {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Aabb {
+    const EMPTY: Self = Aabb {
+        min: [f32::INFINITY; 3],
+        max: [f32::NEG_INFINITY; 3],
+    };
+
+    fn grow(&mut self, point: Vec3) {
+        for ((min, max), p) in self.min.iter_mut().zip(&mut self.max).zip(&point) {
+            *min = min.min(*p);
+            *max = max.max(*p);
+        }
+    }
+
+    fn union(mut self, other: &Self) -> Self {
+        self.grow(other.min);
+        self.grow(other.max);
+        self
+    }
+
+    fn centroid(&self) -> Vec3 {
+        [
+            0.5 * (self.min[0] + self.max[0]),
+            0.5 * (self.min[1] + self.max[1]),
+            0.5 * (self.min[2] + self.max[2]),
+        ]
+    }
+
+    fn corners(&self) -> impl Iterator<Item = Vec3> + '_ {
+        (0..8).map(move |i| {
+            [
+                if i & 1 == 0 { self.min[0] } else { self.max[0] },
+                if i & 2 == 0 { self.min[1] } else { self.max[1] },
+                if i & 4 == 0 { self.min[2] } else { self.max[2] },
+            ]
+        })
+    }
+
+    fn transform(&self, m: &Transform) -> Self {
+        let mut result = Aabb::EMPTY;
+        for corner in self.corners() {
+            result.grow(transform_point(m, corner));
+        }
+        result
+    }
+
+    /// Returns the distance at which the ray enters the box, if it does within `t`.
+    fn intersect(&self, ray: &Ray, t: &Range<f32>) -> Option<f32> {
+        let (mut near, mut far) = (t.start, t.end);
+        for i in 0..3 {
+            let t0 = (self.min[i] - ray.origin[i]) * ray.inv_direction[i];
+            let t1 = (self.max[i] - ray.origin[i]) * ray.inv_direction[i];
+            // `max` and `min` discard the NaNs of rays parallel to a slab plane
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        if near <= far {
+            Some(near)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Ray {
+    origin: Vec3,
+    direction: Vec3,
+    inv_direction: Vec3,
+}
+
+impl Ray {
+    fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray {
+            origin,
+            direction,
+            inv_direction: [1.0 / direction[0], 1.0 / direction[1], 1.0 / direction[2]],
+        }
+    }
+}
+
+/// Closest intersection of a ray with an acceleration structure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Distance of the hit along the ray, in units of the ray direction.
+    pub t: f32,
+    /// Index of the hit instance, for top level structures.
+    pub instance_index: Option<u32>,
+    /// Custom index of the hit instance, for top level structures.
+    pub instance_custom_index: Option<u32>,
+    /// Index of the hit geometry in its bottom level structure.
+    pub geometry_index: u32,
+    /// Index of the hit primitive in its geometry.
+    pub primitive_index: u32,
+    /// Barycentric coordinates of the hit on a triangle, relative to its
+    /// second and third vertices. Always zero for boxes, which are hit where the ray enters them.
+    pub barycentrics: [f32; 2],
+}
+
+#[derive(Debug)]
+enum Primitive {
+    Triangle {
+        geometry_index: u32,
+        primitive_index: u32,
+        vertices: [Vec3; 3],
+    },
+    Aabb {
+        geometry_index: u32,
+        primitive_index: u32,
+        bounds: Aabb,
+    },
+    Instance {
+        instance_index: u32,
+        custom_index: u32,
+        bounds: Aabb,
+        world_to_object: Transform,
+        bvh: Arc<Bvh>,
+    },
+}
+
+impl Primitive {
+    fn bounds(&self) -> Aabb {
+        match *self {
+            Primitive::Triangle { vertices, .. } => {
+                let mut bounds = Aabb::EMPTY;
+                for &vertex in vertices.iter() {
+                    bounds.grow(vertex);
+                }
+                bounds
+            }
+            Primitive::Aabb { bounds, .. } | Primitive::Instance { bounds, .. } => bounds,
+        }
+    }
+
+    fn intersect(&self, ray: &Ray, t: &Range<f32>) -> Option<RayHit> {
+        match *self {
+            Primitive::Triangle {
+                geometry_index,
+                primitive_index,
+                vertices: [v0, v1, v2],
+            } => {
+                // Möller-Trumbore, without culling
+                let e1 = sub(v1, v0);
+                let e2 = sub(v2, v0);
+                let p = cross(ray.direction, e2);
+                let det = dot(e1, p);
+                if det == 0.0 {
+                    return None;
+                }
+                let inv_det = 1.0 / det;
+                let s = sub(ray.origin, v0);
+                let u = dot(s, p) * inv_det;
+                if !(0.0..=1.0).contains(&u) {
+                    return None;
+                }
+                let q = cross(s, e1);
+                let v = dot(ray.direction, q) * inv_det;
+                if v < 0.0 || u + v > 1.0 {
+                    return None;
+                }
+                let hit_t = dot(e2, q) * inv_det;
+                if !t.contains(&hit_t) {
+                    return None;
+                }
+                Some(RayHit {
+                    t: hit_t,
+                    instance_index: None,
+                    instance_custom_index: None,
+                    geometry_index,
+                    primitive_index,
+                    barycentrics: [u, v],
+                })
+            }
+            Primitive::Aabb {
+                geometry_index,
+                primitive_index,
+                ref bounds,
+            } => bounds.intersect(ray, t).map(|hit_t| RayHit {
+                t: hit_t,
+                instance_index: None,
+                instance_custom_index: None,
+                geometry_index,
+                primitive_index,
+                barycentrics: [0.0; 2],
+            }),
+            Primitive::Instance {
+                instance_index,
+                custom_index,
+                ref world_to_object,
+                ref bvh,
+                ..
+            } => {
+                // Distances are preserved by transforming the direction without normalizing it
+                let object_ray = Ray::new(
+                    transform_point(world_to_object, ray.origin),
+                    transform_vector(world_to_object, ray.direction),
+                );
+                bvh.trace(&object_ray, t.clone()).map(|hit| RayHit {
+                    instance_index: Some(instance_index),
+                    instance_custom_index: Some(custom_index),
+                    ..hit
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        bounds: Aabb,
+        primitives: Range<usize>,
+    },
+    Inner {
+        bounds: Aabb,
+        children: [usize; 2],
+    },
+}
+
+/// Bounding volume hierarchy over the primitives of an acceleration structure.
+#[derive(Debug)]
+pub(crate) struct Bvh {
+    /// Nodes of the hierarchy, starting with the root one.
+    nodes: Vec<Node>,
+    /// Primitives, ordered so that each leaf node refers to a range of them.
+    primitives: Vec<Primitive>,
+}
+
+impl Bvh {
+    fn new(mut primitives: Vec<Primitive>) -> Self {
+        let mut nodes = Vec::new();
+        if !primitives.is_empty() {
+            Self::build_node(&mut nodes, &mut primitives, 0);
+        }
+        Bvh { nodes, primitives }
+    }
+
+    /// Builds the subtree over `primitives`, which start at index `first`,
+    /// by splitting them in halves along the longest axis of their centroids.
+    fn build_node(nodes: &mut Vec<Node>, primitives: &mut [Primitive], first: usize) -> usize {
+        let index = nodes.len();
+        let bounds = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |bounds, p| bounds.union(&p.bounds()));
+        if primitives.len() <= MAX_LEAF_SIZE {
+            nodes.push(Node::Leaf {
+                bounds,
+                primitives: first..first + primitives.len(),
+            });
+            return index;
+        }
+
+        let mut centroids = Aabb::EMPTY;
+        for primitive in primitives.iter() {
+            centroids.grow(primitive.bounds().centroid());
+        }
+        let extent = sub(centroids.max, centroids.min);
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].partial_cmp(&extent[b]).unwrap_or(Ordering::Equal))
+            .unwrap();
+        primitives.sort_unstable_by(|a, b| {
+            let a = a.bounds().centroid()[axis];
+            let b = b.bounds().centroid()[axis];
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        });
+
+        // Reserve the slot of this node before its children take the next ones
+        nodes.push(Node::Leaf {
+            bounds,
+            primitives: 0..0,
+        });
+        let middle = primitives.len() / 2;
+        let (left, right) = primitives.split_at_mut(middle);
+        let children = [
+            Self::build_node(nodes, left, first),
+            Self::build_node(nodes, right, first + middle),
+        ];
+        nodes[index] = Node::Inner { bounds, children };
+        index
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| match *node {
+            Node::Leaf { bounds, .. } | Node::Inner { bounds, .. } => bounds,
+        })
+    }
+
+    /// Finds the closest hit of `ray` within `t`.
+    fn trace(&self, ray: &Ray, mut t: Range<f32>) -> Option<RayHit> {
+        let mut closest = None;
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(index) = stack.pop() {
+            match self.nodes[index] {
+                Node::Leaf {
+                    ref bounds,
+                    ref primitives,
+                } => {
+                    if bounds.intersect(ray, &t).is_none() {
+                        continue;
+                    }
+                    for primitive in &self.primitives[primitives.clone()] {
+                        if let Some(hit) = primitive.intersect(ray, &t) {
+                            t.end = hit.t;
+                            closest = Some(hit);
+                        }
+                    }
+                }
+                Node::Inner {
+                    ref bounds,
+                    children,
+                } => {
+                    if bounds.intersect(ray, &t).is_some() {
+                        stack.extend_from_slice(&children);
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// Acceleration structures of a device, by address.
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    last_address: atomic::AtomicU64,
+    structures: Mutex<HashMap<DeviceAddress, Weak<Storage>>>,
+}
+
+impl Registry {
+    /// Returns the hierarchy of the acceleration structure at `address`, if it's built.
+    fn resolve(&self, address: DeviceAddress) -> Option<Arc<Bvh>> {
+        let structures = self.structures.lock().unwrap();
+        let storage = structures.get(&address)?.upgrade()?;
+        let bvh = storage.bvh.lock().unwrap();
+        bvh.clone()
+    }
+}
+
+#[derive(Debug)]
+struct Storage {
+    address: DeviceAddress,
+    level: accel::Level,
+    registry: Arc<Registry>,
+    /// The hierarchy, once the acceleration structure is built.
+    bvh: Mutex<Option<Arc<Bvh>>>,
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        let mut structures = self.registry.structures.lock().unwrap();
+        structures.remove(&self.address);
+    }
+}
+
+/// An acceleration structure, traversable on the host once built.
+#[derive(Clone, Debug)]
+pub struct AccelerationStructure {
+    storage: Arc<Storage>,
+}
+
+impl AccelerationStructure {
+    pub(crate) fn new(registry: &Arc<Registry>, level: accel::Level) -> Self {
+        let address = ALIGNMENT
+            * (registry
+                .last_address
+                .fetch_add(1, atomic::Ordering::Relaxed)
+                + 1);
+        let storage = Arc::new(Storage {
+            address,
+            level,
+            registry: Arc::clone(registry),
+            bvh: Mutex::new(None),
+        });
+        registry
+            .structures
+            .lock()
+            .unwrap()
+            .insert(address, Arc::downgrade(&storage));
+        AccelerationStructure { storage }
+    }
+
+    pub(crate) fn address(&self) -> DeviceAddress {
+        self.storage.address
+    }
+
+    fn bvh(&self) -> Option<Arc<Bvh>> {
+        self.storage.bvh.lock().unwrap().clone()
+    }
+
+    fn set_bvh(&self, bvh: Option<Arc<Bvh>>) {
+        *self.storage.bvh.lock().unwrap() = bvh;
+    }
+
+    /// Finds the closest intersection of a ray with the acceleration structure,
+    /// within the `t` range of distances along the ray.
+    ///
+    /// Returns `None` if the ray misses all the primitives, or if the acceleration
+    /// structure hasn't been built yet. Face culling and opacity are ignored.
+    pub fn cast_ray(&self, origin: [f32; 3], direction: [f32; 3], t: Range<f32>) -> Option<RayHit> {
+        self.bvh()?.trace(&Ray::new(origin, direction), t)
+    }
+}
+
+/// Returns the memory needed to build an acceleration structure.
+pub(crate) fn size_requirements(
+    desc: &accel::GeometryDesc<Backend>,
+    max_primitive_counts: &[u32],
+) -> accel::SizeRequirements {
+    assert_eq!(
+        desc.geometries.len(),
+        max_primitive_counts.len(),
+        "Expected a maximum primitive count per geometry"
+    );
+    let primitives = max_primitive_counts
+        .iter()
+        .map(|&count| u64::from(count))
+        .sum::<u64>();
+    // A binary tree with up to `MAX_LEAF_SIZE` primitives per leaf has less than `2n` nodes
+    let size = ALIGNMENT + primitives * (PRIMITIVE_SIZE + 2 * NODE_SIZE);
+    let scratch = primitives * PRIMITIVE_SIZE;
+    accel::SizeRequirements {
+        acceleration_structure_size: (size + ALIGNMENT - 1) & !(ALIGNMENT - 1),
+        update_scratch_size: scratch,
+        build_scratch_size: scratch,
+    }
+}
+
+/// Geometry of a build, with the memory of its buffers resolved when recorded.
+#[derive(Debug)]
+enum Source {
+    Triangles {
+        format: Format,
+        vertices: Binding,
+        stride: u64,
+        indices: Option<(Binding, IndexType)>,
+        transform: Option<Binding>,
+    },
+    Aabbs {
+        boxes: Binding,
+        stride: u64,
+    },
+    Instances {
+        instances: Binding,
+    },
+}
+
+/// A recorded acceleration structure build.
+#[derive(Debug)]
+pub(crate) struct Build {
+    dst: AccelerationStructure,
+    geometries: Vec<(Source, accel::BuildRangeDesc)>,
+}
+
+impl Build {
+    pub(crate) fn new(desc: &accel::BuildDesc<Backend>, ranges: &[accel::BuildRangeDesc]) -> Self {
+        assert_eq!(
+            desc.geometry.geometries.len(),
+            ranges.len(),
+            "Expected a build range per geometry"
+        );
+        assert_eq!(
+            desc.geometry.level, desc.dst.storage.level,
+            "Acceleration structure level mismatch"
+        );
+        let geometries = desc
+            .geometry
+            .geometries
+            .iter()
+            .zip(ranges)
+            .map(|(geometry, range)| {
+                let source = match geometry.data {
+                    accel::GeometryData::Triangles(ref triangles) => Source::Triangles {
+                        format: triangles.vertex_format,
+                        vertices: triangles
+                            .vertex_buffer
+                            .binding()
+                            .offset_by(triangles.vertex_buffer_offset),
+                        stride: u64::from(triangles.vertex_buffer_stride),
+                        indices: triangles
+                            .index_buffer
+                            .map(|(buffer, offset, ty)| (buffer.binding().offset_by(offset), ty)),
+                        transform: triangles
+                            .transform
+                            .map(|(buffer, offset)| buffer.binding().offset_by(offset)),
+                    },
+                    accel::GeometryData::Aabbs(ref aabbs) => Source::Aabbs {
+                        boxes: aabbs.buffer.binding().offset_by(aabbs.buffer_offset),
+                        stride: u64::from(aabbs.buffer_stride),
+                    },
+                    accel::GeometryData::Instances(ref instances) => Source::Instances {
+                        instances: instances
+                            .buffer
+                            .binding()
+                            .offset_by(instances.buffer_offset),
+                    },
+                };
+                (source, *range)
+            })
+            .collect();
+        Build {
+            dst: desc.dst.clone(),
+            geometries,
+        }
+    }
+
+    /// Reads the geometry from memory and builds the hierarchy over it.
+    ///
+    /// Updates are full rebuilds, which give the same results.
+    pub(crate) fn execute(&self) {
+        let mut primitives = Vec::new();
+        for (geometry_index, (source, range)) in self.geometries.iter().enumerate() {
+            let geometry_index = geometry_index as u32;
+            let offset = u64::from(range.primitive_offset);
+            match *source {
+                Source::Triangles {
+                    format,
+                    ref vertices,
+                    stride,
+                    ref indices,
+                    ref transform,
+                } => {
+                    let transform = transform.as_ref().map(|binding| {
+                        read::<Transform>(binding, u64::from(range.transform_offset))
+                    });
+                    // Indexed vertices start at the beginning of the vertex data,
+                    // and non-indexed ones at the primitive offset
+                    let vertex_base = if indices.is_some() { 0 } else { offset };
+                    let vertex = |index: u64| {
+                        let offset = vertex_base + (u64::from(range.first_vertex) + index) * stride;
+                        let position = match format {
+                            Format::Rg32Sfloat => {
+                                let [x, y] = read::<[f32; 2]>(vertices, offset);
+                                [x, y, 0.0]
+                            }
+                            Format::Rgb32Sfloat | Format::Rgba32Sfloat => {
+                                read::<Vec3>(vertices, offset)
+                            }
+                            other => panic!(
+                                "Vertex format {:?} is not supported by the empty backend",
+                                other
+                            ),
+                        };
+                        match transform {
+                            Some(ref m) => transform_point(m, position),
+                            None => position,
+                        }
+                    };
+                    let corner = |n: u64| match *indices {
+                        Some((ref binding, IndexType::U16)) => {
+                            u64::from(read::<u16>(binding, offset + 2 * n))
+                        }
+                        Some((ref binding, IndexType::U32)) => {
+                            u64::from(read::<u32>(binding, offset + 4 * n))
+                        }
+                        None => n,
+                    };
+                    for i in 0..range.primitive_count {
+                        let n = 3 * u64::from(i);
+                        primitives.push(Primitive::Triangle {
+                            geometry_index,
+                            primitive_index: i,
+                            vertices: [
+                                vertex(corner(n)),
+                                vertex(corner(n + 1)),
+                                vertex(corner(n + 2)),
+                            ],
+                        });
+                    }
+                }
+                Source::Aabbs { ref boxes, stride } => {
+                    for i in 0..range.primitive_count {
+                        let [min_x, min_y, min_z, max_x, max_y, max_z] =
+                            read::<[f32; 6]>(boxes, offset + u64::from(i) * stride);
+                        primitives.push(Primitive::Aabb {
+                            geometry_index,
+                            primitive_index: i,
+                            bounds: Aabb {
+                                min: [min_x, min_y, min_z],
+                                max: [max_x, max_y, max_z],
+                            },
+                        });
+                    }
+                }
+                Source::Instances { ref instances } => {
+                    let registry = &self.dst.storage.registry;
+                    let size = std::mem::size_of::<accel::Instance>() as u64;
+                    for i in 0..range.primitive_count {
+                        let instance =
+                            read::<accel::Instance>(instances, offset + u64::from(i) * size);
+                        // Instances that can't be hit are left out
+                        if instance.mask() == 0 {
+                            continue;
+                        }
+                        let bvh = match registry.resolve(instance.acceleration_structure_reference)
+                        {
+                            Some(bvh) => bvh,
+                            None => {
+                                log::warn!(
+                                    "Instance {} refers to no built acceleration structure",
+                                    i
+                                );
+                                continue;
+                            }
+                        };
+                        let (bounds, world_to_object) =
+                            match (bvh.bounds(), invert(&instance.transform)) {
+                                (Some(bounds), Some(inverse)) => {
+                                    (bounds.transform(&instance.transform), inverse)
+                                }
+                                _ => continue,
+                            };
+                        primitives.push(Primitive::Instance {
+                            instance_index: i,
+                            custom_index: instance.custom_index(),
+                            bounds,
+                            world_to_object,
+                            bvh,
+                        });
+                    }
+                }
+            }
+        }
+        self.dst.set_bvh(Some(Arc::new(Bvh::new(primitives))));
+    }
+}
+
+/// Copies the hierarchy of `src` to `dst`.
+pub(crate) fn copy(src: &AccelerationStructure, dst: &AccelerationStructure) {
+    dst.set_bvh(src.bvh());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RayHit;
+    use crate::{Instance, Profile};
+    use hal::{
+        acceleration_structure as accel, buffer,
+        command::{CommandBuffer as _, CommandBufferFlags, Level},
+        device::Device as _,
+        memory::{AllocationFlags, Barrier, Dependencies, Segment, SparseFlags},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso::PipelineStage,
+        queue::{Queue as _, QueueFamily as _},
+        MemoryTypeId,
+    };
+    use std::{iter, mem, ptr};
+
+    #[test]
+    fn trace_instanced_triangles() {
+        let profile = Profile::default().with_features(hal::Features::ACCELERATION_STRUCTURE);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) =
+            crate::open_device(&instance, hal::Features::ACCELERATION_STRUCTURE);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        // A unit quad in the XY plane, made of two indexed triangles
+        let vertices: [[f32; 3]; 4] = [
+            [-1.0, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ];
+        let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+        let usage = buffer::Usage::ACCELERATION_STRUCTURE_BUILD_INPUT;
+        let storage_usage = buffer::Usage::ACCELERATION_STRUCTURE_STORAGE;
+
+        unsafe {
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 0x10000, AllocationFlags::empty())
+                .unwrap();
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            let mut offset = 0;
+            let mut buffer = |size: u64, usage: buffer::Usage| {
+                let mut buffer = device
+                    .create_buffer(size, usage, SparseFlags::empty())
+                    .unwrap();
+                device
+                    .bind_buffer_memory(&memory, offset, &mut buffer)
+                    .unwrap();
+                let buffer_offset = offset;
+                offset += (size + 255) & !255;
+                (buffer, buffer_offset)
+            };
+
+            let (vertex_buffer, vertex_offset) = buffer(mem::size_of_val(&vertices) as u64, usage);
+            let (index_buffer, index_offset) = buffer(mem::size_of_val(&indices) as u64, usage);
+            ptr::copy_nonoverlapping(
+                vertices.as_ptr() as *const u8,
+                ptr.offset(vertex_offset as isize),
+                mem::size_of_val(&vertices),
+            );
+            ptr::copy_nonoverlapping(
+                indices.as_ptr() as *const u8,
+                ptr.offset(index_offset as isize),
+                mem::size_of_val(&indices),
+            );
+
+            let blas_geometry = [accel::Geometry {
+                flags: accel::GeometryFlags::OPAQUE,
+                data: accel::GeometryData::Triangles(accel::GeometryTriangles {
+                    vertex_format: hal::format::Format::Rgb32Sfloat,
+                    vertex_buffer: &vertex_buffer,
+                    vertex_buffer_offset: 0,
+                    vertex_buffer_stride: 12,
+                    max_vertex: 3,
+                    index_buffer: Some((&index_buffer, 0, hal::IndexType::U16)),
+                    transform: None,
+                }),
+            }];
+            let blas_desc = accel::GeometryDesc {
+                flags: accel::BuildFlags::PREFER_FAST_TRACE,
+                level: accel::Level::Bottom,
+                geometries: &blas_geometry,
+            };
+            let blas_sizes = device.get_acceleration_structure_build_requirements(&blas_desc, &[2]);
+            let (blas_buffer, _) = buffer(blas_sizes.acceleration_structure_size, storage_usage);
+            let blas = device
+                .create_acceleration_structure(&accel::CreateDesc {
+                    buffer: &blas_buffer,
+                    buffer_offset: 0,
+                    size: blas_sizes.acceleration_structure_size,
+                    level: accel::Level::Bottom,
+                })
+                .unwrap();
+
+            // Two instances of the quad, moved apart along X and scaled
+            let blas_address = device.get_acceleration_structure_address(&blas);
+            let instances = [
+                accel::Instance::new(
+                    [
+                        [1.0, 0.0, 0.0, -5.0],
+                        [0.0, 1.0, 0.0, 0.0],
+                        [0.0, 0.0, 1.0, 0.0],
+                    ],
+                    blas_address,
+                )
+                .with_custom_index(7),
+                accel::Instance::new(
+                    [
+                        [2.0, 0.0, 0.0, 5.0],
+                        [0.0, 2.0, 0.0, 0.0],
+                        [0.0, 0.0, 2.0, 3.0],
+                    ],
+                    blas_address,
+                )
+                .with_custom_index(9),
+            ];
+            let (instance_buffer, instance_offset) =
+                buffer(mem::size_of_val(&instances) as u64, usage);
+            ptr::copy_nonoverlapping(
+                instances.as_ptr() as *const u8,
+                ptr.offset(instance_offset as isize),
+                mem::size_of_val(&instances),
+            );
+
+            let tlas_geometry = [accel::Geometry {
+                flags: accel::GeometryFlags::empty(),
+                data: accel::GeometryData::Instances(accel::GeometryInstances {
+                    buffer: &instance_buffer,
+                    buffer_offset: 0,
+                }),
+            }];
+            let tlas_desc = accel::GeometryDesc {
+                flags: accel::BuildFlags::ALLOW_UPDATE,
+                level: accel::Level::Top,
+                geometries: &tlas_geometry,
+            };
+            let tlas_sizes = device.get_acceleration_structure_build_requirements(&tlas_desc, &[2]);
+            let (tlas_buffer, _) = buffer(tlas_sizes.acceleration_structure_size, storage_usage);
+            let tlas = device
+                .create_acceleration_structure(&accel::CreateDesc {
+                    buffer: &tlas_buffer,
+                    buffer_offset: 0,
+                    size: tlas_sizes.acceleration_structure_size,
+                    level: accel::Level::Top,
+                })
+                .unwrap();
+            let (tlas_copy_buffer, _) =
+                buffer(tlas_sizes.acceleration_structure_size, storage_usage);
+            let tlas_copy = device
+                .create_acceleration_structure(&accel::CreateDesc {
+                    buffer: &tlas_copy_buffer,
+                    buffer_offset: 0,
+                    size: tlas_sizes.acceleration_structure_size,
+                    level: accel::Level::Top,
+                })
+                .unwrap();
+            let scratch_size = blas_sizes
+                .build_scratch_size
+                .max(tlas_sizes.build_scratch_size);
+            let (scratch, _) = buffer(scratch_size, storage_usage);
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            let range = |primitive_count| accel::BuildRangeDesc {
+                primitive_count,
+                ..Default::default()
+            };
+            cmd_buf.build_acceleration_structures(iter::once((
+                &accel::BuildDesc {
+                    src: None,
+                    dst: &blas,
+                    geometry: &blas_desc,
+                    scratch: &scratch,
+                    scratch_offset: 0,
+                },
+                &[range(2)][..],
+            )));
+            cmd_buf.pipeline_barrier(
+                PipelineStage::ACCELERATION_STRUCTURE_BUILD
+                    ..PipelineStage::ACCELERATION_STRUCTURE_BUILD,
+                Dependencies::empty(),
+                iter::once(Barrier::AllBuffers(
+                    buffer::Access::ACCELERATION_STRUCTURE_WRITE
+                        ..buffer::Access::ACCELERATION_STRUCTURE_READ,
+                )),
+            );
+            cmd_buf.build_acceleration_structures(iter::once((
+                &accel::BuildDesc {
+                    src: None,
+                    dst: &tlas,
+                    geometry: &tlas_desc,
+                    scratch: &scratch,
+                    scratch_offset: 0,
+                },
+                &[range(2)][..],
+            )));
+            cmd_buf.copy_acceleration_structure(&tlas, &tlas_copy);
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+
+            let down = [0.0, 0.0, -1.0];
+            // The first triangle of the first instance
+            let hit = tlas.cast_ray([-4.5, -0.5, 10.0], down, 0.0..100.0);
+            assert_eq!(
+                hit.map(|hit| (hit.t, hit.instance_custom_index, hit.primitive_index)),
+                Some((10.0, Some(7), 0))
+            );
+            // The second triangle of the second instance, scaled and moved up
+            let hit = tlas.cast_ray([4.0, 1.0, 10.0], down, 0.0..100.0);
+            let RayHit {
+                t,
+                instance_index,
+                primitive_index,
+                barycentrics,
+                ..
+            } = hit.unwrap();
+            assert_eq!((t, instance_index, primitive_index), (7.0, Some(1), 1));
+            assert_eq!(barycentrics, [0.25, 0.5]);
+            // Between the instances, and out of range
+            assert_eq!(tlas.cast_ray([0.0, 0.0, 10.0], down, 0.0..100.0), None);
+            assert_eq!(tlas.cast_ray([-5.0, 0.0, 10.0], down, 0.0..5.0), None);
+            // The bottom level structure alone, in its own space
+            assert_eq!(
+                blas.cast_ray([0.5, 0.5, -1.0], [0.0, 0.0, 2.0], 0.0..1.0)
+                    .map(|hit| (hit.t, hit.instance_index)),
+                Some((0.5, None))
+            );
+            assert_eq!(
+                tlas_copy.cast_ray([5.0, 0.0, 10.0], down, 0.0..100.0),
+                tlas.cast_ray([5.0, 0.0, 10.0], down, 0.0..100.0)
+            );
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_acceleration_structure(tlas_copy);
+            device.destroy_acceleration_structure(tlas);
+            device.destroy_acceleration_structure(blas);
+            for buffer in [
+                vertex_buffer,
+                index_buffer,
+                instance_buffer,
+                blas_buffer,
+                tlas_buffer,
+                tlas_copy_buffer,
+                scratch,
+            ] {
+                device.destroy_buffer(buffer);
+            }
+            device.unmap_memory(&mut memory);
+            device.free_memory(memory);
+        }
+    }
+}
@@ -0,0 +1,784 @@
+use crate::{
+    bvh::{self, AccelerationStructure},
+    image::Image,
+    interpreter::{self, Resources},
+    memory::Binding,
+    query_pool::{self, Queries},
+    shader::ComputePipeline,
+    sync::Signal,
+};
+use hal::command::{BufferCopy, BufferImageCopy, ImageCopy};
+use hal::format::Aspects;
+use hal::image::{Extent, Layer, Level, Offset};
+
+use std::{ops::Range, ptr, slice, sync::Arc};
+
+/// A command recorded into a command buffer, executed on the CPU
+/// at submission time.
+#[derive(Debug)]
+pub(crate) enum Command {
+    CopyBuffer {
+        src: Binding,
+        dst: Binding,
+        regions: Vec<BufferCopy>,
+    },
+    FillBuffer {
+        dst: Binding,
+        offset: u64,
+        size: u64,
+        data: u32,
+    },
+    UpdateBuffer {
+        dst: Binding,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    CopyImage {
+        src: Image,
+        dst: Image,
+        regions: Vec<ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: Binding,
+        dst: Image,
+        regions: Vec<BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: Image,
+        dst: Binding,
+        regions: Vec<BufferImageCopy>,
+    },
+    /// Writes the `bytes` of `texel` to every texel of a rectangle of an image.
+    ClearImage {
+        image: Image,
+        aspects: Aspects,
+        level: Level,
+        layers: Range<Layer>,
+        rect: hal::pso::Rect,
+        texel: Vec<u8>,
+        bytes: Range<usize>,
+    },
+    Dispatch {
+        pipeline: ComputePipeline,
+        resources: Resources,
+        count: hal::WorkGroupCount,
+    },
+    DispatchIndirect {
+        pipeline: ComputePipeline,
+        resources: Resources,
+        buffer: Binding,
+        offset: u64,
+    },
+    SetEvent(Arc<Signal>),
+    ResetEvent(Arc<Signal>),
+    WaitEvents(Vec<Arc<Signal>>),
+    ResetQueries {
+        queries: Arc<Queries>,
+        range: Range<hal::query::Id>,
+    },
+    EndOcclusionQuery {
+        queries: Arc<Queries>,
+        id: hal::query::Id,
+    },
+    WriteTimestamp {
+        queries: Arc<Queries>,
+        id: hal::query::Id,
+    },
+    BeginPerformanceQuery {
+        queries: Arc<Queries>,
+        id: hal::query::Id,
+    },
+    EndPerformanceQuery {
+        queries: Arc<Queries>,
+        id: hal::query::Id,
+        /// Commands recorded since the start of the query.
+        commands: u32,
+    },
+    CopyQueryResults {
+        queries: Arc<Queries>,
+        range: Range<hal::query::Id>,
+        dst: Binding,
+        offset: u64,
+        stride: hal::buffer::Stride,
+        flags: hal::query::ResultFlags,
+    },
+    BuildAccelerationStructure(bvh::Build),
+    CopyAccelerationStructure {
+        src: AccelerationStructure,
+        dst: AccelerationStructure,
+    },
+    /// A draw or dispatch inside a conditional rendering block.
+    Conditional {
+        condition: Condition,
+        command: Box<Command>,
+    },
+    /// Point reached after a debug marker or a render pass, reported when the
+    /// device is lost. The queue worker keeps track of it.
+    Breadcrumb(hal::device::Breadcrumb),
+}
+
+/// Predicate of a conditional rendering block.
+#[derive(Clone, Debug)]
+pub(crate) struct Condition {
+    pub buffer: Binding,
+    pub offset: u64,
+    pub inverted: bool,
+}
+
+impl Condition {
+    /// Returns whether the predicated commands get executed.
+    fn passes(&self) -> bool {
+        let mut value = [0u8; 4];
+        unsafe {
+            ptr::copy_nonoverlapping(self.buffer.ptr(self.offset, 4), value.as_mut_ptr(), 4);
+        }
+        (u32::from_ne_bytes(value) != 0) != self.inverted
+    }
+}
+
+fn div_ceil(value: u32, divisor: u32) -> u64 {
+    u64::from((value + divisor - 1) / divisor)
+}
+
+/// Which way the data goes in a buffer/image copy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    BufferToImage,
+    ImageToBuffer,
+}
+
+fn copy_buffer_image(buffer: &Binding, image: &Image, region: &BufferImageCopy, dir: Direction) {
+    let aspects = region.image_layers.aspects;
+    let (block_size, (block_width, block_height)) = image.block(aspects);
+    let extent = region.image_extent;
+    let buffer_width = match region.buffer_width {
+        0 => extent.width,
+        width => width,
+    };
+    let buffer_height = match region.buffer_height {
+        0 => extent.height,
+        height => height,
+    };
+    let buffer_row_pitch = div_ceil(buffer_width, block_width) * block_size;
+    let buffer_slice_pitch = div_ceil(buffer_height, block_height) * buffer_row_pitch;
+    let row_size = div_ceil(extent.width, block_width) * block_size;
+    let num_rows = div_ceil(extent.height, block_height);
+
+    let layers = region.image_layers.layers.clone();
+    for (i, layer) in layers.enumerate() {
+        for z in 0..extent.depth {
+            let slice = i as u64 * u64::from(extent.depth) + u64::from(z);
+            for row in 0..num_rows {
+                let buffer_offset =
+                    region.buffer_offset + slice * buffer_slice_pitch + row * buffer_row_pitch;
+                let image_offset = image.texel_offset(
+                    aspects,
+                    region.image_layers.level,
+                    layer,
+                    Offset {
+                        x: region.image_offset.x,
+                        y: region.image_offset.y + (row * u64::from(block_height)) as i32,
+                        z: region.image_offset.z + z as i32,
+                    },
+                );
+                match dir {
+                    Direction::BufferToImage => Binding::copy(
+                        buffer,
+                        buffer_offset,
+                        image.binding(),
+                        image_offset,
+                        row_size,
+                    ),
+                    Direction::ImageToBuffer => Binding::copy(
+                        image.binding(),
+                        image_offset,
+                        buffer,
+                        buffer_offset,
+                        row_size,
+                    ),
+                }
+            }
+        }
+    }
+}
+
+fn copy_image(src: &Image, dst: &Image, region: &ImageCopy) {
+    let (block_size, (block_width, block_height)) = src.block(region.src_subresource.aspects);
+    let Extent {
+        width,
+        height,
+        depth,
+    } = region.extent;
+    let row_size = div_ceil(width, block_width) * block_size;
+    let num_rows = div_ceil(height, block_height);
+
+    let src_layers = region.src_subresource.layers.clone();
+    let dst_layers = region.dst_subresource.layers.clone();
+    for (src_layer, dst_layer) in src_layers.zip(dst_layers) {
+        for z in 0..depth as i32 {
+            for row in 0..num_rows {
+                let y = (row * u64::from(block_height)) as i32;
+                let src_offset = src.texel_offset(
+                    region.src_subresource.aspects,
+                    region.src_subresource.level,
+                    src_layer,
+                    Offset {
+                        x: region.src_offset.x,
+                        y: region.src_offset.y + y,
+                        z: region.src_offset.z + z,
+                    },
+                );
+                let dst_offset = dst.texel_offset(
+                    region.dst_subresource.aspects,
+                    region.dst_subresource.level,
+                    dst_layer,
+                    Offset {
+                        x: region.dst_offset.x,
+                        y: region.dst_offset.y + y,
+                        z: region.dst_offset.z + z,
+                    },
+                );
+                Binding::copy(
+                    src.binding(),
+                    src_offset,
+                    dst.binding(),
+                    dst_offset,
+                    row_size,
+                );
+            }
+        }
+    }
+}
+
+fn clear_image(
+    image: &Image,
+    aspects: Aspects,
+    level: Level,
+    layers: Range<Layer>,
+    rect: hal::pso::Rect,
+    texel: &[u8],
+    bytes: Range<usize>,
+) {
+    let (block_size, _) = image.block(aspects);
+    let samples = block_size as usize / texel.len();
+    for layer in layers {
+        for y in rect.y..rect.y + rect.h {
+            for x in rect.x..rect.x + rect.w {
+                let offset = image.texel_offset(
+                    aspects,
+                    level,
+                    layer,
+                    Offset {
+                        x: i32::from(x),
+                        y: i32::from(y),
+                        z: 0,
+                    },
+                );
+                // Every sample of a texel gets the same value
+                for sample in 0..samples {
+                    unsafe {
+                        let dst = image.binding().ptr(offset, block_size);
+                        ptr::copy_nonoverlapping(
+                            texel[bytes.clone()].as_ptr(),
+                            dst.add(sample * texel.len() + bytes.start),
+                            bytes.len(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Command {
+    /// Runs the command against the host memory of the bound resources.
+    pub(crate) fn execute(&self) {
+        match *self {
+            Command::CopyBuffer {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    Binding::copy(src, region.src, dst, region.dst, region.size);
+                }
+            }
+            Command::FillBuffer {
+                ref dst,
+                offset,
+                size,
+                data,
+            } => {
+                let bytes =
+                    unsafe { slice::from_raw_parts_mut(dst.ptr(offset, size), size as usize) };
+                for chunk in bytes.chunks_exact_mut(4) {
+                    chunk.copy_from_slice(&data.to_ne_bytes());
+                }
+            }
+            Command::UpdateBuffer {
+                ref dst,
+                offset,
+                ref data,
+            } => unsafe {
+                let size = data.len() as u64;
+                ptr::copy_nonoverlapping(data.as_ptr(), dst.ptr(offset, size), data.len());
+            },
+            Command::CopyImage {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_image(src, dst, region);
+                }
+            }
+            Command::CopyBufferToImage {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_buffer_image(src, dst, region, Direction::BufferToImage);
+                }
+            }
+            Command::CopyImageToBuffer {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_buffer_image(dst, src, region, Direction::ImageToBuffer);
+                }
+            }
+            Command::ClearImage {
+                ref image,
+                aspects,
+                level,
+                ref layers,
+                rect,
+                ref texel,
+                ref bytes,
+            } => clear_image(
+                image,
+                aspects,
+                level,
+                layers.clone(),
+                rect,
+                texel,
+                bytes.clone(),
+            ),
+            Command::Dispatch {
+                ref pipeline,
+                ref resources,
+                count,
+            } => interpreter::dispatch(&pipeline.module, pipeline.entry_point, resources, count),
+            Command::DispatchIndirect {
+                ref pipeline,
+                ref resources,
+                ref buffer,
+                offset,
+            } => {
+                let mut count = [0u32; 3];
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        buffer.ptr(offset, 12),
+                        count.as_mut_ptr() as *mut u8,
+                        12,
+                    );
+                }
+                interpreter::dispatch(&pipeline.module, pipeline.entry_point, resources, count);
+            }
+            Command::SetEvent(ref signal) => signal.set(),
+            Command::ResetEvent(ref signal) => signal.reset(),
+            Command::WaitEvents(ref signals) => {
+                for signal in signals {
+                    signal.wait();
+                }
+            }
+            Command::ResetQueries {
+                ref queries,
+                ref range,
+            } => queries.reset(range.clone()),
+            Command::EndOcclusionQuery { ref queries, id } => queries.end_occlusion(id),
+            Command::WriteTimestamp { ref queries, id } => queries.write_timestamp(id),
+            Command::BeginPerformanceQuery { ref queries, id } => queries.begin_performance(id),
+            Command::EndPerformanceQuery {
+                ref queries,
+                id,
+                commands,
+            } => queries.end_performance(id, commands),
+            Command::CopyQueryResults {
+                ref queries,
+                ref range,
+                ref dst,
+                offset,
+                stride,
+                flags,
+            } => {
+                let size = query_pool::results_size(range, stride, flags);
+                let data =
+                    unsafe { slice::from_raw_parts_mut(dst.ptr(offset, size), size as usize) };
+                queries.copy_results(range.clone(), data, stride, flags);
+            }
+            Command::BuildAccelerationStructure(ref build) => build.execute(),
+            Command::CopyAccelerationStructure { ref src, ref dst } => bvh::copy(src, dst),
+            Command::Conditional {
+                ref condition,
+                ref command,
+            } => {
+                if condition.passes() {
+                    command.execute();
+                }
+            }
+            Command::Breadcrumb(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile};
+    use hal::{
+        buffer,
+        command::{
+            BufferCopy, BufferImageCopy, ClearColor, ClearDepthStencil, ClearValue,
+            CommandBuffer as _, CommandBufferFlags, Level, RenderingAttachmentInfo,
+        },
+        device::Device as _,
+        format::{Aspects, Format, Swizzle},
+        image,
+        memory::{AllocationFlags, Segment, SparseFlags},
+        pass::{AttachmentLoadOp, AttachmentOps, AttachmentStoreOp},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso,
+        queue::{Queue as _, QueueFamily as _},
+        Instance as _, MemoryTypeId,
+    };
+    use std::{iter, slice};
+
+    #[test]
+    fn transfer_roundtrip() {
+        let instance = Instance::create("test", 1).unwrap();
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 32, AllocationFlags::empty())
+                .unwrap();
+            let mut src = device
+                .create_buffer(16, buffer::Usage::TRANSFER_SRC, SparseFlags::empty())
+                .unwrap();
+            let mut dst = device
+                .create_buffer(16, buffer::Usage::TRANSFER_DST, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut src).unwrap();
+            device.bind_buffer_memory(&memory, 16, &mut dst).unwrap();
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.fill_buffer(&src, buffer::SubRange::WHOLE, 0x0101_0101);
+            cmd_buf.update_buffer(&src, 4, &[2, 3, 4, 5]);
+            cmd_buf.copy_buffer(
+                &src,
+                &dst,
+                iter::once(BufferCopy {
+                    src: 0,
+                    dst: 8,
+                    size: 8,
+                }),
+            );
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            let data = slice::from_raw_parts(ptr, 32);
+            assert_eq!(data[..8], [1, 1, 1, 1, 2, 3, 4, 5]);
+            assert_eq!(data[16..24], [0; 8]);
+            assert_eq!(data[24..], [1, 1, 1, 1, 2, 3, 4, 5]);
+            device.unmap_memory(&mut memory);
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(src);
+            device.destroy_buffer(dst);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn rendering_clear_and_store() {
+        let profile = Profile::default().with_features(hal::Features::DYNAMIC_RENDERING);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::DYNAMIC_RENDERING);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 128, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(
+                    64,
+                    buffer::Usage::TRANSFER_SRC | buffer::Usage::TRANSFER_DST,
+                    SparseFlags::empty(),
+                )
+                .unwrap();
+            let mut image = device
+                .create_image(
+                    image::Kind::D2(4, 4, 1, 1),
+                    1,
+                    Format::Rgba8Unorm,
+                    image::Tiling::Optimal,
+                    image::Usage::COLOR_ATTACHMENT
+                        | image::Usage::TRANSFER_SRC
+                        | image::Usage::TRANSFER_DST,
+                    SparseFlags::empty(),
+                    image::ViewCapabilities::empty(),
+                )
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            device.bind_image_memory(&memory, 64, &mut image).unwrap();
+            let range = image::SubresourceRange {
+                aspects: Aspects::COLOR,
+                ..Default::default()
+            };
+            let view = device
+                .create_image_view(
+                    &image,
+                    image::ViewKind::D2,
+                    Format::Rgba8Unorm,
+                    Swizzle::NO,
+                    image::Usage::COLOR_ATTACHMENT,
+                    range,
+                    None,
+                )
+                .unwrap();
+            let region = BufferImageCopy {
+                buffer_offset: 0,
+                buffer_width: 4,
+                buffer_height: 4,
+                image_layers: image::SubresourceLayers {
+                    aspects: Aspects::COLOR,
+                    level: 0,
+                    layers: 0..1,
+                },
+                image_offset: image::Offset::ZERO,
+                image_extent: image::Extent {
+                    width: 4,
+                    height: 4,
+                    depth: 1,
+                },
+            };
+            let attachment = |load, color| RenderingAttachmentInfo {
+                image_view: &view,
+                layout: image::Layout::ColorAttachmentOptimal,
+                ops: AttachmentOps::new(load, AttachmentStoreOp::Store),
+                stencil_ops: AttachmentOps::DONT_CARE,
+                clear_value: ClearValue {
+                    color: ClearColor { float32: color },
+                },
+            };
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.fill_buffer(&buffer, buffer::SubRange::WHOLE, 0x1111_1111);
+            cmd_buf.copy_buffer_to_image(
+                &buffer,
+                &image,
+                image::Layout::TransferDstOptimal,
+                iter::once(region.clone()),
+            );
+            // Clears the center of the image
+            cmd_buf.begin_rendering(
+                pso::Rect {
+                    x: 1,
+                    y: 1,
+                    w: 2,
+                    h: 2,
+                },
+                iter::once(attachment(AttachmentLoadOp::Clear, [1.0, 0.0, 0.0, 1.0])),
+                None,
+            );
+            cmd_buf.end_rendering();
+            // Keeps the whole image
+            cmd_buf.begin_rendering(
+                pso::Rect {
+                    x: 0,
+                    y: 0,
+                    w: 4,
+                    h: 4,
+                },
+                iter::once(attachment(AttachmentLoadOp::Load, [0.0, 1.0, 0.0, 1.0])),
+                None,
+            );
+            cmd_buf.end_rendering();
+            cmd_buf.copy_image_to_buffer(
+                &image,
+                image::Layout::TransferSrcOptimal,
+                &buffer,
+                iter::once(region),
+            );
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            let data = slice::from_raw_parts(ptr, 64);
+            for (i, texel) in data.chunks(4).enumerate() {
+                let (x, y) = (i % 4, i / 4);
+                let expected = if (1..3).contains(&x) && (1..3).contains(&y) {
+                    [0xFF, 0, 0, 0xFF]
+                } else {
+                    [0x11; 4]
+                };
+                assert_eq!(texel, expected, "texel ({}, {})", x, y);
+            }
+            device.unmap_memory(&mut memory);
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_image_view(view);
+            device.destroy_image(image);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn rendering_depth_stencil_clears() {
+        let profile = Profile::default().with_features(hal::Features::DYNAMIC_RENDERING);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::DYNAMIC_RENDERING);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 16, AllocationFlags::empty())
+                .unwrap();
+            let mut image = device
+                .create_image(
+                    image::Kind::D2(2, 2, 1, 1),
+                    1,
+                    Format::D24UnormS8Uint,
+                    image::Tiling::Optimal,
+                    image::Usage::DEPTH_STENCIL_ATTACHMENT,
+                    SparseFlags::empty(),
+                    image::ViewCapabilities::empty(),
+                )
+                .unwrap();
+            device.bind_image_memory(&memory, 0, &mut image).unwrap();
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            ptr.write_bytes(0xAA, 16);
+            let view = device
+                .create_image_view(
+                    &image,
+                    image::ViewKind::D2,
+                    Format::D24UnormS8Uint,
+                    Swizzle::NO,
+                    image::Usage::DEPTH_STENCIL_ATTACHMENT,
+                    image::SubresourceRange {
+                        aspects: Aspects::DEPTH | Aspects::STENCIL,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .unwrap();
+            let attachment = |ops, stencil_ops| RenderingAttachmentInfo {
+                image_view: &view,
+                layout: image::Layout::DepthStencilAttachmentOptimal,
+                ops,
+                stencil_ops,
+                clear_value: ClearValue {
+                    depth_stencil: ClearDepthStencil {
+                        depth: 0.5,
+                        stencil: 0x7F,
+                    },
+                },
+            };
+            let clear = AttachmentOps::new(AttachmentLoadOp::Clear, AttachmentStoreOp::Store);
+            let load = AttachmentOps::new(AttachmentLoadOp::Load, AttachmentStoreOp::Store);
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            // Clears the depth of the whole image
+            cmd_buf.begin_rendering(
+                pso::Rect {
+                    x: 0,
+                    y: 0,
+                    w: 2,
+                    h: 2,
+                },
+                iter::empty(),
+                Some(attachment(clear, load)),
+            );
+            cmd_buf.end_rendering();
+            // Clears the stencil of the first column
+            cmd_buf.begin_rendering(
+                pso::Rect {
+                    x: 0,
+                    y: 0,
+                    w: 1,
+                    h: 2,
+                },
+                iter::empty(),
+                Some(attachment(load, clear)),
+            );
+            cmd_buf.end_rendering();
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+
+            // 24-bit depth of 0.5 followed by the stencil
+            let data = slice::from_raw_parts(ptr, 16);
+            for (i, texel) in data.chunks(4).enumerate() {
+                let stencil = if i % 2 == 0 { 0x7F } else { 0xAA };
+                assert_eq!(texel, [0x00, 0x00, 0x80, stencil], "texel {}", i);
+            }
+            device.unmap_memory(&mut memory);
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_image_view(view);
+            device.destroy_image(image);
+            device.free_memory(memory);
+        }
+    }
+}
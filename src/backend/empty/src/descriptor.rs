@@ -1,6 +1,9 @@
+use crate::memory::Binding;
 use hal::pso;
 use log::debug;
 
+use std::collections::BTreeMap;
+
 /// Dummy descriptor pool.
 #[derive(Debug)]
 pub struct DescriptorPool;
@@ -8,10 +11,12 @@ pub struct DescriptorPool;
 impl pso::DescriptorPool<crate::Backend> for DescriptorPool {
     unsafe fn allocate_one(
         &mut self,
-        _layout: &DescriptorSetLayout,
+        layout: &DescriptorSetLayout,
     ) -> Result<DescriptorSet, pso::AllocationError> {
         Ok(DescriptorSet {
             name: String::new(),
+            layout: layout.bindings.clone(),
+            buffers: BTreeMap::new(),
         })
     }
 
@@ -33,10 +38,213 @@ impl pso::DescriptorPool<crate::Backend> for DescriptorPool {
 pub struct DescriptorSetLayout {
     /// User-defined name for this descriptor set layout
     pub(crate) name: String,
+    /// Bindings of the layout, sorted by binding index.
+    pub(crate) bindings: Vec<pso::DescriptorSetLayoutBinding>,
+    pub(crate) flags: pso::DescriptorSetLayoutCreateFlags,
+}
+
+#[derive(Debug)]
+pub struct PipelineLayout {
+    /// Bindings of each set layout created with `PUSH_DESCRIPTOR`, indexed by set.
+    pub(crate) push_sets: Vec<Option<Vec<pso::DescriptorSetLayoutBinding>>>,
+}
+
+/// A buffer range written into a descriptor set.
+#[derive(Clone, Debug)]
+pub(crate) struct BufferDescriptor {
+    /// Memory at the start of the range.
+    pub memory: Binding,
+    pub size: u64,
+    /// Whether a dynamic offset gets added at bind time.
+    pub dynamic: bool,
 }
 
 #[derive(Debug)]
 pub struct DescriptorSet {
     /// User-defined name for this descriptor set
     pub(crate) name: String,
+    /// Bindings of the layout this set was allocated with.
+    pub(crate) layout: Vec<pso::DescriptorSetLayoutBinding>,
+    /// Buffer descriptors, keyed by binding and array index.
+    pub(crate) buffers:
+        BTreeMap<(pso::DescriptorBinding, pso::DescriptorArrayIndex), BufferDescriptor>,
+}
+
+impl DescriptorSet {
+    /// Writes descriptors starting at the given binding and array index,
+    /// spilling over into the following bindings.
+    pub(crate) fn write<'a, I>(
+        &mut self,
+        mut binding: pso::DescriptorBinding,
+        mut array_index: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, crate::Backend>>,
+    {
+        for descriptor in descriptors {
+            while array_index >= self.binding_count(binding) {
+                binding += 1;
+                array_index = 0;
+            }
+            match descriptor {
+                pso::Descriptor::Buffer(buffer, ref sub) => {
+                    let dynamic = matches!(
+                        self.layout_binding(binding).map(|b| b.ty),
+                        Some(pso::DescriptorType::Buffer {
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: true,
+                            },
+                            ..
+                        })
+                    );
+                    let descriptor = BufferDescriptor {
+                        memory: buffer.binding().offset_by(sub.offset),
+                        size: sub.size_to(buffer.size),
+                        dynamic,
+                    };
+                    self.buffers.insert((binding, array_index), descriptor);
+                }
+                _ => {
+                    // Only buffers are accessible to shaders
+                }
+            }
+            array_index += 1;
+        }
+    }
+
+    fn layout_binding(
+        &self,
+        binding: pso::DescriptorBinding,
+    ) -> Option<&pso::DescriptorSetLayoutBinding> {
+        self.layout.iter().find(|b| b.binding == binding)
+    }
+
+    fn binding_count(&self, binding: pso::DescriptorBinding) -> pso::DescriptorArrayIndex {
+        match self.layout_binding(binding) {
+            Some(b) => b.count,
+            None => {
+                assert!(
+                    self.layout.iter().any(|b| b.binding > binding),
+                    "Descriptor write is out of the set layout bounds"
+                );
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Backend, Instance};
+    use hal::{
+        buffer,
+        device::Device as _,
+        memory::{AllocationFlags, SparseFlags},
+        pso::{self, DescriptorPool as _},
+        Instance as _, MemoryTypeId,
+    };
+    use std::{iter, mem};
+
+    /// Descriptors of a material, as stored by the application.
+    #[repr(C)]
+    struct Material<'a> {
+        constants: pso::Descriptor<'a, Backend>,
+        lights: [pso::Descriptor<'a, Backend>; 2],
+    }
+
+    #[test]
+    fn update_with_template() {
+        let instance = Instance::create("test", 1).unwrap();
+        let (_, gpu) = crate::open_device(&instance, hal::Features::empty());
+        let device = &gpu.device;
+
+        unsafe {
+            let memory = device
+                .allocate_memory(MemoryTypeId(0), 64, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(64, buffer::Usage::UNIFORM, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+
+            let binding = |binding, count| pso::DescriptorSetLayoutBinding {
+                binding,
+                ty: pso::DescriptorType::Buffer {
+                    ty: pso::BufferDescriptorType::Uniform,
+                    format: pso::BufferDescriptorFormat::Structured {
+                        dynamic_offset: false,
+                    },
+                },
+                count,
+                stage_flags: pso::ShaderStageFlags::COMPUTE,
+                immutable_samplers: false,
+            };
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    vec![binding(0, 1), binding(1, 1), binding(2, 1)].into_iter(),
+                    iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
+                )
+                .unwrap();
+            // The lights spill over from binding 1 onto binding 2
+            let template = device
+                .create_descriptor_update_template(
+                    &set_layout,
+                    vec![
+                        pso::DescriptorUpdateTemplateEntry {
+                            binding: 0,
+                            array_offset: 0,
+                            count: 1,
+                            offset: 0,
+                            stride: 0,
+                        },
+                        pso::DescriptorUpdateTemplateEntry {
+                            binding: 1,
+                            array_offset: 0,
+                            count: 2,
+                            offset: mem::size_of::<pso::Descriptor<Backend>>(),
+                            stride: mem::size_of::<pso::Descriptor<Backend>>(),
+                        },
+                    ]
+                    .into_iter(),
+                )
+                .unwrap();
+
+            let sub_range = |offset, size| buffer::SubRange {
+                offset,
+                size: Some(size),
+            };
+            let material = Material {
+                constants: pso::Descriptor::Buffer(&buffer, sub_range(0, 16)),
+                lights: [
+                    pso::Descriptor::Buffer(&buffer, sub_range(16, 32)),
+                    pso::Descriptor::Buffer(&buffer, sub_range(48, 16)),
+                ],
+            };
+
+            let mut desc_pool = device
+                .create_descriptor_pool(1, iter::empty(), pso::DescriptorPoolCreateFlags::empty())
+                .unwrap();
+            let mut set = desc_pool.allocate_one(&set_layout).unwrap();
+            device.update_descriptor_set_with_template(
+                &mut set,
+                &template,
+                &material as *const Material as *const u8,
+            );
+
+            let sizes = set
+                .buffers
+                .iter()
+                .map(|(&key, desc)| (key, desc.size))
+                .collect::<Vec<_>>();
+            assert_eq!(sizes, [((0, 0), 16), ((1, 0), 32), ((2, 0), 16)]);
+
+            desc_pool.free(iter::once(set));
+            device.destroy_descriptor_pool(desc_pool);
+            device.destroy_descriptor_update_template(template);
+            device.destroy_descriptor_set_layout(set_layout);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
 }
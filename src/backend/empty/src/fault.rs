@@ -0,0 +1,731 @@
+//! Fault injection, making device, queue and surface calls fail on demand.
+
+use crate::sync::Notifier;
+
+use hal::{buffer, device, image, pso, window};
+
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// Backend calls faults can be injected into.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Operation {
+    AllocateMemory,
+    MapMemory,
+    FlushMappedMemory,
+    InvalidateMappedMemory,
+    CreateBuffer,
+    BindBufferMemory,
+    CreateImage,
+    BindImageMemory,
+    CreateImageView,
+    CreateSampler,
+    CreateCommandPool,
+    CreateDescriptorPool,
+    CreateDescriptorSetLayout,
+    CreateDescriptorUpdateTemplate,
+    CreatePipelineLayout,
+    CreateRenderPass,
+    CreateFramebuffer,
+    CreateComputePipeline,
+    CreateGraphicsPipeline,
+    CreateFence,
+    CreateSemaphore,
+    CreateEvent,
+    CreateQueryPool,
+    CreateAccelerationStructure,
+    AcquireProfilingLock,
+    ResetFence,
+    GetFenceStatus,
+    SetEvent,
+    ResetEvent,
+    GetEventStatus,
+    WaitForFences,
+    GetSemaphoreCounterValue,
+    WaitSemaphores,
+    SignalSemaphore,
+    DeviceWaitIdle,
+    GetQueryPoolResults,
+    GetCalibratedTimestamps,
+    /// Queue submission. Submissions can't fail, so the only supported
+    /// fault is `DeviceLost`, which drops the submitted work.
+    Submit,
+    /// Execution of a breadcrumb by a queue, which only get recorded when
+    /// fault diagnostics are enabled. The only supported fault is `DeviceLost`,
+    /// which stops the queue right before the breadcrumb.
+    Breadcrumb,
+    QueueWaitIdle,
+    ConfigureSwapchain,
+    AcquireImage,
+    Present,
+}
+
+/// Result of a faulty call.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Fault {
+    OutOfHostMemory,
+    OutOfDeviceMemory,
+    TooManyObjects,
+    MappingFailed,
+    /// Loses the device. Every later call able to report it fails with `DeviceLost`.
+    DeviceLost,
+    OutOfDate,
+    SurfaceLost,
+    /// Succeeds, but reports the swapchain as suboptimal.
+    Suboptimal,
+}
+
+/// Calls a fault gets injected into.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Trigger {
+    /// The n-th call from now, counting from 1.
+    Nth(usize),
+    /// Every call after the first `n` ones from now.
+    After(usize),
+}
+
+/// Errors a fault can be reported as.
+pub(crate) trait FromFault: Sized {
+    fn from_fault(fault: Fault) -> Option<Self>;
+}
+
+impl FromFault for device::OutOfMemory {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::OutOfHostMemory => Some(device::OutOfMemory::Host),
+            Fault::OutOfDeviceMemory => Some(device::OutOfMemory::Device),
+            _ => None,
+        }
+    }
+}
+
+impl FromFault for device::DeviceLost {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::DeviceLost => Some(device::DeviceLost),
+            _ => None,
+        }
+    }
+}
+
+impl FromFault for device::AllocationError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::TooManyObjects => Some(device::AllocationError::TooManyObjects),
+            _ => device::OutOfMemory::from_fault(fault).map(Into::into),
+        }
+    }
+}
+
+impl FromFault for device::MapError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::MappingFailed => Some(device::MapError::MappingFailed),
+            _ => device::OutOfMemory::from_fault(fault).map(Into::into),
+        }
+    }
+}
+
+impl FromFault for device::WaitError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        device::OutOfMemory::from_fault(fault)
+            .map(Into::into)
+            .or_else(|| device::DeviceLost::from_fault(fault).map(Into::into))
+    }
+}
+
+macro_rules! impl_from_out_of_memory {
+    ($($ty:ty),*) => {
+        $(
+            impl FromFault for $ty {
+                fn from_fault(fault: Fault) -> Option<Self> {
+                    device::OutOfMemory::from_fault(fault).map(Into::into)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_out_of_memory!(
+    device::BindError,
+    buffer::CreationError,
+    image::CreationError,
+    image::ViewCreationError,
+    pso::CreationError
+);
+
+impl FromFault for window::SwapchainError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::SurfaceLost => Some(window::SurfaceLost.into()),
+            _ => device::WaitError::from_fault(fault).map(|e| match e {
+                device::WaitError::OutOfMemory(e) => e.into(),
+                device::WaitError::DeviceLost(e) => e.into(),
+            }),
+        }
+    }
+}
+
+impl FromFault for window::AcquireError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::OutOfDate => Some(window::OutOfDate.into()),
+            Fault::SurfaceLost => Some(window::SurfaceLost.into()),
+            Fault::DeviceLost => Some(device::DeviceLost.into()),
+            _ => device::OutOfMemory::from_fault(fault).map(Into::into),
+        }
+    }
+}
+
+impl FromFault for window::PresentError {
+    fn from_fault(fault: Fault) -> Option<Self> {
+        match fault {
+            Fault::OutOfDate => Some(window::OutOfDate.into()),
+            Fault::SurfaceLost => Some(window::SurfaceLost.into()),
+            Fault::DeviceLost => Some(device::DeviceLost.into()),
+            _ => device::OutOfMemory::from_fault(fault).map(Into::into),
+        }
+    }
+}
+
+impl Operation {
+    /// Returns true if the call can report the fault.
+    fn supports(self, fault: Fault) -> bool {
+        use self::Operation as Op;
+        fn supports<E: FromFault>(fault: Fault) -> bool {
+            E::from_fault(fault).is_some()
+        }
+        match self {
+            Op::AllocateMemory | Op::CreateSampler => supports::<device::AllocationError>(fault),
+            Op::MapMemory => supports::<device::MapError>(fault),
+            Op::BindBufferMemory | Op::BindImageMemory => supports::<device::BindError>(fault),
+            Op::CreateBuffer => supports::<buffer::CreationError>(fault),
+            Op::CreateImage => supports::<image::CreationError>(fault),
+            Op::CreateImageView => supports::<image::ViewCreationError>(fault),
+            Op::CreateComputePipeline | Op::CreateGraphicsPipeline => {
+                supports::<pso::CreationError>(fault)
+            }
+            Op::GetFenceStatus | Op::GetSemaphoreCounterValue | Op::Submit | Op::Breadcrumb => {
+                supports::<device::DeviceLost>(fault)
+            }
+            Op::WaitForFences
+            | Op::WaitSemaphores
+            | Op::GetEventStatus
+            | Op::GetQueryPoolResults
+            | Op::DeviceWaitIdle
+            | Op::QueueWaitIdle => supports::<device::WaitError>(fault),
+            Op::ConfigureSwapchain => supports::<window::SwapchainError>(fault),
+            Op::AcquireImage => {
+                fault == Fault::Suboptimal || supports::<window::AcquireError>(fault)
+            }
+            Op::Present => fault == Fault::Suboptimal || supports::<window::PresentError>(fault),
+            Op::FlushMappedMemory
+            | Op::InvalidateMappedMemory
+            | Op::CreateCommandPool
+            | Op::CreateDescriptorPool
+            | Op::CreateDescriptorSetLayout
+            | Op::CreateDescriptorUpdateTemplate
+            | Op::CreatePipelineLayout
+            | Op::CreateRenderPass
+            | Op::CreateFramebuffer
+            | Op::CreateFence
+            | Op::CreateSemaphore
+            | Op::CreateEvent
+            | Op::CreateQueryPool
+            | Op::CreateAccelerationStructure
+            | Op::AcquireProfilingLock
+            | Op::ResetFence
+            | Op::SetEvent
+            | Op::ResetEvent
+            | Op::SignalSemaphore
+            | Op::GetCalibratedTimestamps => supports::<device::OutOfMemory>(fault),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    operation: Operation,
+    fault: Fault,
+    /// Number of calls to let through before firing.
+    skip: usize,
+    /// Whether the rule keeps firing after the first time.
+    repeat: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    rules: Vec<Rule>,
+    device_lost: bool,
+    /// Notifiers of the devices, woken up when the device gets lost.
+    notifiers: Vec<Weak<Notifier>>,
+}
+
+/// Last breadcrumb executed by the queues of a device in fault diagnostics mode.
+#[derive(Debug, Default)]
+pub(crate) struct BreadcrumbTrail {
+    last: Mutex<Option<device::Breadcrumb>>,
+}
+
+impl BreadcrumbTrail {
+    pub fn complete(&self, breadcrumb: &device::Breadcrumb) {
+        *self.last.lock().unwrap() = Some(breadcrumb.clone());
+    }
+
+    pub fn last(&self) -> Option<device::Breadcrumb> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+/// Controller scripting the failures of the backend calls.
+///
+/// Shared by an instance and every object created from it, so faults
+/// can be injected at any point of a test.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    state: Arc<Mutex<State>>,
+}
+
+impl FaultInjector {
+    /// Creates a controller without any faults scheduled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Makes the calls selected by `trigger` fail with `fault`.
+    ///
+    /// Panics if the operation can't report this fault.
+    pub fn inject(&self, operation: Operation, trigger: Trigger, fault: Fault) {
+        assert!(
+            operation.supports(fault),
+            "{:?} can't report {:?}",
+            operation,
+            fault
+        );
+        let (skip, repeat) = match trigger {
+            Trigger::Nth(n) => {
+                assert_ne!(n, 0, "Calls are counted from 1");
+                (n - 1, false)
+            }
+            Trigger::After(n) => (n, true),
+        };
+        self.lock().rules.push(Rule {
+            operation,
+            fault,
+            skip,
+            repeat,
+        });
+    }
+
+    /// Removes the scheduled faults. A lost device stays lost.
+    pub fn clear(&self) {
+        self.lock().rules.clear();
+    }
+
+    /// Loses the device immediately.
+    pub fn lose_device(&self) {
+        let mut state = self.lock();
+        state.device_lost = true;
+        Self::wake_waiters(state);
+    }
+
+    /// Registers the notifier of a device, so its host waits are interrupted
+    /// by the loss of the device.
+    pub(crate) fn register(&self, notifier: &Arc<Notifier>) {
+        let mut state = self.lock();
+        state
+            .notifiers
+            .retain(|notifier| notifier.strong_count() != 0);
+        state.notifiers.push(Arc::downgrade(notifier));
+    }
+
+    /// Wakes up the host threads waiting on the devices, to let them observe the loss.
+    ///
+    /// The state is unlocked first, as the waiters check it with the lock of
+    /// their notifier held.
+    fn wake_waiters(state: MutexGuard<'_, State>) {
+        let notifiers = state
+            .notifiers
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        drop(state);
+        for notifier in notifiers {
+            notifier.notify(|| ());
+        }
+    }
+
+    /// Returns true if the device has been lost.
+    pub fn is_device_lost(&self) -> bool {
+        self.lock().device_lost
+    }
+
+    /// Counts a call to `operation` and returns the fault it hits, if any.
+    fn poll(&self, operation: Operation) -> Option<Fault> {
+        let mut state = self.lock();
+        let mut fired = None;
+        for (index, rule) in state.rules.iter_mut().enumerate() {
+            if rule.operation != operation {
+                continue;
+            }
+            if rule.skip > 0 {
+                rule.skip -= 1;
+            } else if fired.is_none() {
+                fired = Some(index);
+            }
+        }
+        let fault = fired.map(|index| {
+            if state.rules[index].repeat {
+                state.rules[index].fault
+            } else {
+                state.rules.remove(index).fault
+            }
+        });
+        let fault = if state.device_lost && operation.supports(Fault::DeviceLost) {
+            Some(Fault::DeviceLost)
+        } else {
+            fault
+        };
+        if fault == Some(Fault::DeviceLost) && !state.device_lost {
+            state.device_lost = true;
+            Self::wake_waiters(state);
+        }
+        fault
+    }
+
+    /// Checks a call returning `E` on failure.
+    pub(crate) fn check<E: FromFault>(&self, operation: Operation) -> Result<(), E> {
+        match self.poll(operation) {
+            Some(fault) => Err(E::from_fault(fault).unwrap()),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a swapchain call, which can also succeed suboptimally.
+    pub(crate) fn check_swapchain<E: FromFault>(
+        &self,
+        operation: Operation,
+    ) -> Result<Option<window::Suboptimal>, E> {
+        match self.poll(operation) {
+            Some(Fault::Suboptimal) => Ok(Some(window::Suboptimal)),
+            Some(fault) => Err(E::from_fault(fault).unwrap()),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fault, Operation, Trigger};
+    use crate::{FrameOutput, Instance, Profile, QueueFamilyProfile};
+    use hal::{
+        command::{CommandBuffer as _, CommandBufferFlags, Level, SubpassContents},
+        device::{AllocationError, Breadcrumb, Device as _, DeviceLost, OutOfMemory, WaitError},
+        format::Format,
+        memory::AllocationFlags,
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso::{PipelineStage, Rect},
+        queue::{Queue as _, QueueFamily as _, QueueType},
+        window::{
+            AcquireError, Extent2D, OutOfDate, PresentError, PresentationSurface as _,
+            SwapchainConfig,
+        },
+        Instance as _, MemoryTypeId,
+    };
+    use std::{iter, sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn scripted_faults() {
+        let instance = Instance::create("test", 1).unwrap();
+        let faults = instance.faults();
+        let (_, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        faults.inject(
+            Operation::AllocateMemory,
+            Trigger::Nth(3),
+            Fault::OutOfDeviceMemory,
+        );
+        faults.inject(
+            Operation::AllocateMemory,
+            Trigger::After(4),
+            Fault::TooManyObjects,
+        );
+        let results = (0..6)
+            .map(|_| {
+                unsafe { device.allocate_memory(MemoryTypeId(0), 4, AllocationFlags::empty()) }
+                    .err()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            [
+                None,
+                None,
+                Some(AllocationError::OutOfMemory(OutOfMemory::Device)),
+                None,
+                Some(AllocationError::TooManyObjects),
+                Some(AllocationError::TooManyObjects),
+            ]
+        );
+
+        let extent = Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let mut surface = instance
+            .create_virtual_surface(extent, Format::Rgba8Srgb, FrameOutput::Discard)
+            .unwrap();
+        faults.inject(Operation::AcquireImage, Trigger::Nth(1), Fault::OutOfDate);
+        faults.inject(Operation::Present, Trigger::Nth(1), Fault::Suboptimal);
+        unsafe {
+            let config = SwapchainConfig::new(4, 4, Format::Rgba8Srgb, 2);
+            surface.configure_swapchain(device, config).unwrap();
+            assert_eq!(
+                surface.acquire_image(0).err(),
+                Some(AcquireError::OutOfDate(OutOfDate))
+            );
+            let (image, _) = surface.acquire_image(0).unwrap();
+            assert!(queue.present(&mut surface, image, None).unwrap().is_some());
+        }
+
+        faults.inject(Operation::Submit, Trigger::After(2), Fault::DeviceLost);
+        let mut fence = device.create_fence(false).unwrap();
+        for _ in 0..2 {
+            unsafe {
+                queue.submit(
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    Some(&mut fence),
+                );
+                assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+                device.reset_fence(&mut fence).unwrap();
+            }
+        }
+        unsafe {
+            queue.submit(
+                std::iter::empty(),
+                std::iter::empty(),
+                std::iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(
+                device.wait_for_fence(&fence, !0),
+                Err(WaitError::DeviceLost(hal::device::DeviceLost))
+            );
+        }
+        assert!(faults.is_device_lost());
+
+        // Polling events reports the loss as well
+        let event = device.create_event().unwrap();
+        assert_eq!(
+            unsafe { device.get_event_status(&event) },
+            Err(WaitError::DeviceLost(DeviceLost))
+        );
+    }
+
+    #[test]
+    fn lost_submission_wakes_waiters() {
+        let instance = Instance::create("test", 1).unwrap();
+        let (_, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let device = Arc::new(gpu.device);
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        let mut fence = device.create_fence(false).unwrap();
+        let signal = Arc::clone(&fence.signal);
+        let waiter = {
+            let device = Arc::clone(&device);
+            thread::spawn(move || {
+                let fence = crate::Fence { signal };
+                unsafe { device.wait_for_fence(&fence, !0) }
+            })
+        };
+
+        // Give the waiter time to block before losing the device
+        thread::sleep(Duration::from_millis(50));
+        device
+            .faults()
+            .inject(Operation::Submit, Trigger::Nth(1), Fault::DeviceLost);
+        unsafe {
+            queue.submit(
+                iter::empty(),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+        }
+        assert_eq!(
+            waiter.join().unwrap(),
+            Err(WaitError::DeviceLost(DeviceLost))
+        );
+        unsafe { device.destroy_fence(fence) };
+    }
+
+    #[test]
+    fn breadcrumbs() {
+        let profile = Profile::default().with_features(hal::Features::DEVICE_FAULT_DIAGNOSTICS);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let faults = instance.faults();
+        let (adapter, mut gpu) =
+            crate::open_device(&instance, hal::Features::DEVICE_FAULT_DIAGNOSTICS);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.insert_debug_marker("upload", 0);
+            let area = Rect {
+                x: 0,
+                y: 0,
+                w: 4,
+                h: 4,
+            };
+            cmd_buf.begin_render_pass(&(), &(), area, iter::empty(), SubpassContents::Inline);
+            cmd_buf.end_render_pass();
+            cmd_buf.begin_debug_marker("lighting", 0);
+            cmd_buf.end_debug_marker();
+            cmd_buf.finish();
+
+            // The device gets lost in the lighting pass
+            faults.inject(Operation::Breadcrumb, Trigger::Nth(3), Fault::DeviceLost);
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(
+                device.wait_for_fence(&fence, !0),
+                Err(WaitError::DeviceLost(DeviceLost))
+            );
+            let info = device.get_fault_info().unwrap();
+            assert!(info.description.is_some());
+            assert_eq!(info.last_breadcrumb, Some(Breadcrumb::EndRenderPass));
+        }
+    }
+
+    #[test]
+    fn lost_work_interrupts_semaphore_waits() {
+        let profile = Profile::default()
+            .with_features(hal::Features::DEVICE_FAULT_DIAGNOSTICS)
+            .with_queue_families(vec![QueueFamilyProfile::new(QueueType::General, 2)]);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let faults = instance.faults();
+        let (adapter, mut gpu) =
+            crate::open_device(&instance, hal::Features::DEVICE_FAULT_DIAGNOSTICS);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let (first, second) = gpu.queue_groups[0].queues.split_at_mut(1);
+        let (first, second) = (&mut first[0], &mut second[0]);
+        let extent = Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let mut surface = instance
+            .create_virtual_surface(extent, Format::Rgba8Srgb, FrameOutput::Discard)
+            .unwrap();
+
+        unsafe {
+            let config = SwapchainConfig::new(4, 4, Format::Rgba8Srgb, 2);
+            surface.configure_swapchain(device, config).unwrap();
+            let (image, _) = surface.acquire_image(0).unwrap();
+
+            // The first queue loses the device once the host event is set, before
+            // signaling the semaphores of the second queue and of the presentation
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut host_event = device.create_event().unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.wait_events(
+                iter::once(&host_event),
+                PipelineStage::HOST..PipelineStage::TRANSFER,
+                iter::empty(),
+            );
+            cmd_buf.insert_debug_marker("lost", 0);
+            cmd_buf.finish();
+            let queue_semaphore = device.create_semaphore().unwrap();
+            let mut present_semaphore = device.create_semaphore().unwrap();
+            let mut fence = device.create_fence(false).unwrap();
+            faults.inject(Operation::Breadcrumb, Trigger::Nth(1), Fault::DeviceLost);
+            first.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                vec![&queue_semaphore, &present_semaphore].into_iter(),
+                None,
+            );
+            second.submit(
+                iter::empty(),
+                iter::once((&queue_semaphore, PipelineStage::TOP_OF_PIPE)),
+                iter::empty(),
+                Some(&mut fence),
+            );
+
+            device.set_event(&mut host_event).unwrap();
+            assert_eq!(
+                device.wait_for_fence(&fence, !0),
+                Err(WaitError::DeviceLost(DeviceLost))
+            );
+            assert!(matches!(
+                first.present(&mut surface, image, Some(&mut present_semaphore)),
+                Err(PresentError::DeviceLost(DeviceLost))
+            ));
+            // The queues report the loss instead of waiting on the lost work
+            assert_eq!(second.wait_idle(), Err(WaitError::DeviceLost(DeviceLost)));
+            assert_eq!(first.wait_idle(), Err(WaitError::DeviceLost(DeviceLost)));
+            assert_eq!(device.wait_idle(), Err(WaitError::DeviceLost(DeviceLost)));
+        }
+    }
+
+    #[test]
+    fn no_breadcrumbs_without_diagnostics() {
+        let instance = Instance::create("test", 1).unwrap();
+        let faults = instance.faults();
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.insert_debug_marker("upload", 0);
+            let area = Rect {
+                x: 0,
+                y: 0,
+                w: 4,
+                h: 4,
+            };
+            cmd_buf.begin_render_pass(&(), &(), area, iter::empty(), SubpassContents::Inline);
+            cmd_buf.end_render_pass();
+            cmd_buf.finish();
+
+            // No breadcrumb gets executed, so the device is never lost
+            faults.inject(Operation::Breadcrumb, Trigger::Nth(1), Fault::DeviceLost);
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+            assert!(device.get_fault_info().is_none());
+        }
+    }
+}
@@ -1,26 +1,153 @@
-use hal::image::Kind;
+use crate::memory::Binding;
+use hal::command::ClearValue;
+use hal::format::{Aspects, BaseFormat, ChannelType, Format, SurfaceType};
+use hal::image::{Kind, Layer, Level, Offset, Subresource, SubresourceFootprint, SubresourceRange};
 use hal::memory::Requirements as MemoryRequirements;
 
-#[derive(Debug)]
+use std::ops::Range;
+
+#[derive(Clone, Debug)]
 pub struct Image {
     /// What type of image this is, as well as its extent.
     kind: Kind,
+    /// Number of mip levels.
+    levels: Level,
+    /// Format of the texels.
+    format: Format,
+    /// Memory this image is bound to, if any.
+    pub(crate) memory: Option<Binding>,
 }
 
 impl Image {
-    pub fn new(kind: Kind) -> Self {
-        Image { kind }
+    pub fn new(kind: Kind, levels: Level, format: Format) -> Self {
+        Image {
+            kind,
+            levels,
+            format,
+            memory: None,
+        }
     }
 
-    pub fn get_requirements(&self) -> MemoryRequirements {
-        let size = match self.kind {
-            Kind::D2(width, height, layers, samples) => {
-                assert_eq!(layers, 1, "Multi-layer images are not supported");
-                assert_eq!(samples, 1, "Multisampled images are not supported");
-                u64::from(width) * u64::from(height)
-            }
-            _ => unimplemented!("Unsupported image kind"),
+    pub(crate) fn binding(&self) -> &Binding {
+        self.memory
+            .as_ref()
+            .expect("Image is not bound to any memory")
+    }
+
+    /// Returns the index, the format and the subsampling of the plane selected by `aspects`.
+    ///
+    /// Images in other formats than the multi-planar ones have a single plane.
+    fn plane(&self, aspects: Aspects) -> (usize, Format, (u32, u32)) {
+        let index = if aspects.contains(Aspects::PLANE_2) {
+            2
+        } else if aspects.contains(Aspects::PLANE_1) {
+            1
+        } else {
+            0
         };
+        match self.format.plane(index) {
+            Some(plane) => (
+                index,
+                plane.format,
+                (
+                    u32::from(plane.subsampling.0),
+                    u32::from(plane.subsampling.1),
+                ),
+            ),
+            None => (0, self.format, (1, 1)),
+        }
+    }
+
+    /// Returns the size in bytes and the dimensions in texels of a texel block
+    /// of the plane selected by `aspects`.
+    ///
+    /// Multisampled images store all the samples of a texel next to each other.
+    pub(crate) fn block(&self, aspects: Aspects) -> (u64, (u32, u32)) {
+        let desc = self.plane(aspects).1.surface_desc();
+        let samples = u64::from(self.kind.num_samples());
+        (
+            u64::from(desc.bits / 8) * samples,
+            (u32::from(desc.dim.0), u32::from(desc.dim.1)),
+        )
+    }
+
+    /// Returns the block size, row pitch, depth pitch and array pitch
+    /// of a mip level of the plane selected by `aspects`, in bytes.
+    fn pitches(&self, aspects: Aspects, level: Level) -> [u64; 4] {
+        let (block_size, (block_width, block_height)) = self.block(aspects);
+        let (_, _, (sub_width, sub_height)) = self.plane(aspects);
+        let extent = self.kind.level_extent(level);
+        let width = (extent.width + sub_width - 1) / sub_width;
+        let height = (extent.height + sub_height - 1) / sub_height;
+        let row_pitch = u64::from((width + block_width - 1) / block_width) * block_size;
+        let depth_pitch = u64::from((height + block_height - 1) / block_height) * row_pitch;
+        let array_pitch = u64::from(extent.depth) * depth_pitch;
+        [block_size, row_pitch, depth_pitch, array_pitch]
+    }
+
+    /// Returns the size in bytes of the first `levels` mip levels of a plane.
+    fn plane_size(&self, aspects: Aspects, levels: Level) -> u64 {
+        let num_layers = u64::from(self.kind.num_layers());
+        (0..levels).fold(0, |size, level| {
+            size + num_layers * self.pitches(aspects, level)[3]
+        })
+    }
+
+    /// Returns the placement of a subresource in memory.
+    ///
+    /// Planes are laid out one after another. In each plane, mip levels are
+    /// laid out one after another, each with all of its layers.
+    pub fn footprint(&self, sub: Subresource) -> SubresourceFootprint {
+        assert!(
+            sub.level < self.levels && sub.layer < self.kind.num_layers(),
+            "Subresource {:?} is out of image bounds",
+            sub
+        );
+        let (plane, _, _) = self.plane(sub.aspects);
+        let plane_offset = [Aspects::PLANE_0, Aspects::PLANE_1][..plane]
+            .iter()
+            .fold(0, |offset, &aspects| {
+                offset + self.plane_size(aspects, self.levels)
+            });
+        let level_offset = plane_offset + self.plane_size(sub.aspects, sub.level);
+        let [_, row_pitch, depth_pitch, array_pitch] = self.pitches(sub.aspects, sub.level);
+        let layer_offset = level_offset + u64::from(sub.layer) * array_pitch;
+        SubresourceFootprint {
+            slice: layer_offset..layer_offset + array_pitch,
+            row_pitch,
+            array_pitch,
+            depth_pitch,
+        }
+    }
+
+    /// Returns the byte offset of the texel block containing `offset`
+    /// in the plane selected by `aspects`, relative to the start of the image memory.
+    pub(crate) fn texel_offset(
+        &self,
+        aspects: Aspects,
+        level: Level,
+        layer: Layer,
+        offset: Offset,
+    ) -> u64 {
+        let (block_size, (block_width, block_height)) = self.block(aspects);
+        let footprint = self.footprint(Subresource {
+            aspects,
+            level,
+            layer,
+        });
+        footprint.slice.start
+            + offset.z as u64 * footprint.depth_pitch
+            + (offset.y as u64 / u64::from(block_height)) * footprint.row_pitch
+            + (offset.x as u64 / u64::from(block_width)) * block_size
+    }
+
+    pub fn get_requirements(&self) -> MemoryRequirements {
+        let planes = [Aspects::PLANE_0, Aspects::PLANE_1, Aspects::PLANE_2];
+        let size = planes[..self.format.plane_count()]
+            .iter()
+            .fold(0, |size, &aspects| {
+                size + self.plane_size(aspects, self.levels)
+            });
         MemoryRequirements {
             size,
             alignment: 1,
@@ -28,3 +155,331 @@ impl Image {
         }
     }
 }
+
+/// View of a range of subresources of an image.
+#[derive(Clone, Debug)]
+pub struct ImageView {
+    pub(crate) image: Image,
+    pub(crate) format: Format,
+    pub(crate) range: SubresourceRange,
+}
+
+impl ImageView {
+    /// Creates a view of the first mip level and layer of `image`.
+    pub(crate) fn of_image(image: &Image) -> Self {
+        ImageView {
+            image: image.clone(),
+            format: image.format,
+            range: SubresourceRange {
+                aspects: image.format.surface_desc().aspects,
+                level_start: 0,
+                level_count: Some(1),
+                layer_start: 0,
+                layer_count: Some(1),
+            },
+        }
+    }
+
+    /// Returns the layers of the image in the view.
+    pub(crate) fn layers(&self) -> Range<Layer> {
+        let count = self.range.resolve_layer_count(self.image.kind.num_layers());
+        self.range.layer_start..self.range.layer_start + count
+    }
+}
+
+/// Returns the order of the channels of a clear value in a texel of a color
+/// format, along with the channel size in bytes.
+///
+/// Only the formats with channels of a single size, and of a type
+/// which can be encoded, are supported.
+fn color_layout(format: Format) -> Option<(&'static [usize], usize)> {
+    let BaseFormat(surface_type, channel_type) = format.base_format();
+    let layout: (&'static [usize], usize) = match surface_type {
+        SurfaceType::R8 => (&[0], 1),
+        SurfaceType::R8_G8 => (&[0, 1], 1),
+        SurfaceType::R8_G8_B8 => (&[0, 1, 2], 1),
+        SurfaceType::B8_G8_R8 => (&[2, 1, 0], 1),
+        SurfaceType::R8_G8_B8_A8 => (&[0, 1, 2, 3], 1),
+        SurfaceType::B8_G8_R8_A8 => (&[2, 1, 0, 3], 1),
+        SurfaceType::R16 => (&[0], 2),
+        SurfaceType::R16_G16 => (&[0, 1], 2),
+        SurfaceType::R16_G16_B16 => (&[0, 1, 2], 2),
+        SurfaceType::R16_G16_B16_A16 => (&[0, 1, 2, 3], 2),
+        SurfaceType::R32 => (&[0], 4),
+        SurfaceType::R32_G32 => (&[0, 1], 4),
+        SurfaceType::R32_G32_B32 => (&[0, 1, 2], 4),
+        SurfaceType::R32_G32_B32_A32 => (&[0, 1, 2, 3], 4),
+        _ => return None,
+    };
+    match channel_type {
+        ChannelType::Unorm
+        | ChannelType::Snorm
+        | ChannelType::Uint
+        | ChannelType::Sint
+        | ChannelType::Srgb => Some(layout),
+        ChannelType::Sfloat if layout.1 != 1 => Some(layout),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the texels of `format` can be [cleared](clear_texel).
+pub(crate) fn can_clear(format: Format) -> bool {
+    let aspects = format.surface_desc().aspects;
+    aspects.intersects(Aspects::DEPTH | Aspects::STENCIL) || color_layout(format).is_some()
+}
+
+/// Encodes `value` as a half float, rounding to the nearest one.
+fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // Infinities stay infinite, and NaNs stay quiet NaNs
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent < -10 {
+        return sign;
+    }
+    // Subnormals spell out the implicit leading bit of the mantissa
+    let (mantissa, shift, base) = if exponent > 0 {
+        (mantissa, 13, (exponent as u32) << 10)
+    } else {
+        (mantissa | 0x80_0000, (14 - exponent) as u32, 0)
+    };
+    let half = base | (mantissa >> shift);
+    let rest = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    // Rounding up may carry into the exponent, up to infinity
+    let round = rest > halfway || (rest == halfway && half & 1 == 1);
+    sign | (half + round as u32) as u16
+}
+
+/// Encodes the aspect of `value` selected by `aspects` into a texel of `format`.
+///
+/// Returns the texel along with the range of its bytes holding the aspect,
+/// so that clearing the depth of a combined depth/stencil format keeps the
+/// stencil in the other bytes, and the other way around. Depth values come
+/// first, and stencil values take the last byte of the texel.
+///
+/// The format must be one that [can be cleared](can_clear).
+pub(crate) fn clear_texel(
+    format: Format,
+    aspects: Aspects,
+    value: ClearValue,
+) -> (Vec<u8>, Range<usize>) {
+    let BaseFormat(surface_type, channel_type) = format.base_format();
+    if aspects.intersects(Aspects::DEPTH | Aspects::STENCIL) {
+        let size = usize::from(format.surface_desc().bits / 8);
+        let mut texel = vec![0; size];
+        if aspects.contains(Aspects::STENCIL) {
+            texel[size - 1] = unsafe { value.depth_stencil.stencil } as u8;
+            return (texel, size - 1..size);
+        }
+        let depth = unsafe { value.depth_stencil.depth };
+        let unorm = |bits: u32| (depth.clamp(0.0, 1.0) * ((1u32 << bits) - 1) as f32).round();
+        let (bytes, depth_size) = match surface_type {
+            SurfaceType::D16 | SurfaceType::D16_S8 => ((unorm(16) as u32).to_le_bytes(), 2),
+            SurfaceType::X8D24 | SurfaceType::D24_S8 => ((unorm(24) as u32).to_le_bytes(), 3),
+            SurfaceType::D32 | SurfaceType::D32_S8 => (depth.to_le_bytes(), 4),
+            _ => unreachable!("{:?} has no depth", format),
+        };
+        texel[..depth_size].copy_from_slice(&bytes[..depth_size]);
+        return (texel, 0..depth_size);
+    }
+
+    let (channels, size) =
+        color_layout(format).unwrap_or_else(|| panic!("Clearing {:?} images", format));
+    // Largest value of a normalized channel
+    let max = ((1u64 << (size * 8)) - 1) as f32;
+    let mut texel = Vec::with_capacity(channels.len() * size);
+    for &channel in channels {
+        let (float, uint, sint) = unsafe {
+            (
+                value.color.float32[channel],
+                value.color.uint32[channel],
+                value.color.sint32[channel],
+            )
+        };
+        let bits = match channel_type {
+            ChannelType::Unorm => (float.clamp(0.0, 1.0) * max).round() as u64,
+            ChannelType::Srgb if channel != 3 => {
+                let encoded = if float <= 0.003_130_8 {
+                    float * 12.92
+                } else {
+                    1.055 * float.powf(1.0 / 2.4) - 0.055
+                };
+                (encoded.clamp(0.0, 1.0) * max).round() as u64
+            }
+            ChannelType::Srgb => (float.clamp(0.0, 1.0) * max).round() as u64,
+            ChannelType::Snorm => {
+                (float.clamp(-1.0, 1.0) * (max / 2.0).floor()).round() as i64 as u64
+            }
+            ChannelType::Uint => u64::from(uint),
+            ChannelType::Sint => sint as i64 as u64,
+            ChannelType::Sfloat if size == 2 => u64::from(f16_bits(float)),
+            ChannelType::Sfloat => u64::from(float.to_bits()),
+            _ => unreachable!(),
+        };
+        texel.extend_from_slice(&bits.to_le_bytes()[..size]);
+    }
+    let len = texel.len();
+    (texel, 0..len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_texel, f16_bits, Image};
+    use crate::Instance;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        command::{ClearColor, ClearDepthStencil, ClearValue},
+        format::{Aspects, Format, ImageFeature},
+        image::{Kind, Subresource, Tiling, Usage, ViewCapabilities},
+        Instance as _,
+    };
+
+    fn footprint(image: &Image, level: u8, layer: u16) -> (std::ops::Range<u64>, [u64; 3]) {
+        let footprint = image.footprint(Subresource {
+            aspects: Aspects::COLOR,
+            level,
+            layer,
+        });
+        (
+            footprint.slice,
+            [
+                footprint.row_pitch,
+                footprint.depth_pitch,
+                footprint.array_pitch,
+            ],
+        )
+    }
+
+    #[test]
+    fn mip_chain_with_layers() {
+        let image = Image::new(Kind::D2(8, 4, 2, 1), 3, Format::Rgba8Unorm);
+        assert_eq!(footprint(&image, 0, 0), (0..128, [32, 128, 128]));
+        assert_eq!(footprint(&image, 0, 1), (128..256, [32, 128, 128]));
+        assert_eq!(footprint(&image, 1, 1), (288..320, [16, 32, 32]));
+        assert_eq!(footprint(&image, 2, 0), (320..328, [8, 8, 8]));
+        assert_eq!(image.get_requirements().size, 336);
+    }
+
+    #[test]
+    fn compressed_volume() {
+        let image = Image::new(Kind::D3(6, 6, 2), 2, Format::Bc1RgbaUnorm);
+        assert_eq!(footprint(&image, 0, 0), (0..64, [16, 32, 64]));
+        assert_eq!(footprint(&image, 1, 0), (64..72, [8, 8, 8]));
+        assert_eq!(image.get_requirements().size, 72);
+    }
+
+    #[test]
+    fn multi_planar() {
+        let plane = |image: &Image, aspects| {
+            let footprint = image.footprint(Subresource {
+                aspects,
+                level: 0,
+                layer: 0,
+            });
+            (footprint.slice, footprint.row_pitch)
+        };
+
+        let nv12 = Image::new(Kind::D2(4, 4, 1, 1), 1, Format::G8b8r8_2Plane420Unorm);
+        assert_eq!(plane(&nv12, Aspects::PLANE_0), (0..16, 4));
+        assert_eq!(plane(&nv12, Aspects::PLANE_1), (16..24, 4));
+        assert_eq!(nv12.get_requirements().size, 24);
+
+        let i420 = Image::new(Kind::D2(4, 4, 1, 1), 1, Format::G8b8r8_3Plane420Unorm);
+        assert_eq!(plane(&i420, Aspects::PLANE_1), (16..20, 2));
+        assert_eq!(plane(&i420, Aspects::PLANE_2), (20..24, 2));
+        assert_eq!(i420.get_requirements().size, 24);
+    }
+
+    #[test]
+    fn half_floats() {
+        assert_eq!(f16_bits(1.0), 0x3C00);
+        assert_eq!(f16_bits(-2.0), 0xC000);
+        assert_eq!(f16_bits(0.1), 0x2E66);
+        assert_eq!(f16_bits(65504.0), 0x7BFF);
+        assert_eq!(f16_bits(1e6), 0x7C00);
+        assert_eq!(f16_bits(f32::NEG_INFINITY), 0xFC00);
+        assert_eq!(f16_bits(2f32.powi(-24)), 0x0001);
+        assert_eq!(f16_bits(2f32.powi(-26)), 0x0000);
+        assert_eq!(f16_bits(-0.0), 0x8000);
+        assert_eq!(f16_bits(f32::NAN) & 0x7E00, 0x7E00);
+
+        let value = ClearValue {
+            color: ClearColor {
+                float32: [1.0, -2.0, 0.0, 0.5],
+            },
+        };
+        assert_eq!(
+            clear_texel(Format::Rgba16Sfloat, Aspects::COLOR, value),
+            (vec![0x00, 0x3C, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x38], 0..8)
+        );
+    }
+
+    #[test]
+    fn depth_stencil_texels() {
+        let value = ClearValue {
+            depth_stencil: ClearDepthStencil {
+                depth: 1.0,
+                stencil: 3,
+            },
+        };
+        assert_eq!(
+            clear_texel(Format::D24UnormS8Uint, Aspects::DEPTH, value),
+            (vec![0xFF, 0xFF, 0xFF, 0], 0..3)
+        );
+        assert_eq!(
+            clear_texel(Format::D24UnormS8Uint, Aspects::STENCIL, value),
+            (vec![0, 0, 0, 3], 3..4)
+        );
+        assert_eq!(
+            clear_texel(Format::D32SfloatS8Uint, Aspects::DEPTH, value),
+            (vec![0x00, 0x00, 0x80, 0x3F, 0], 0..4)
+        );
+        assert_eq!(
+            clear_texel(Format::D32SfloatS8Uint, Aspects::STENCIL, value),
+            (vec![0, 0, 0, 0, 3], 4..5)
+        );
+        assert_eq!(
+            clear_texel(Format::S8Uint, Aspects::STENCIL, value),
+            (vec![3], 0..1)
+        );
+    }
+
+    #[test]
+    fn attachment_formats() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let physical_device = &adapter.physical_device;
+        let attachment_features = |format| {
+            physical_device
+                .format_properties(Some(format))
+                .optimal_tiling
+                & (ImageFeature::COLOR_ATTACHMENT | ImageFeature::DEPTH_STENCIL_ATTACHMENT)
+        };
+        assert!(attachment_features(Format::Rgba16Sfloat).contains(ImageFeature::COLOR_ATTACHMENT));
+        assert!(attachment_features(Format::D24UnormS8Uint)
+            .contains(ImageFeature::DEPTH_STENCIL_ATTACHMENT));
+        // Packed formats can't be cleared
+        assert_eq!(
+            attachment_features(Format::A2b10g10r10Unorm),
+            ImageFeature::empty()
+        );
+        assert!(physical_device
+            .image_format_properties(
+                Format::A2b10g10r10Unorm,
+                2,
+                Tiling::Optimal,
+                Usage::COLOR_ATTACHMENT,
+                ViewCapabilities::empty(),
+            )
+            .is_none());
+    }
+}
@@ -0,0 +1,1991 @@
+//! CPU interpreter for the compute entry points of Naga modules.
+//!
+//! Values live in host memory with the layout described by the module types,
+//! so the shader reads and writes bound buffers in place. The invocations of a
+//! workgroup run one after another, unless the entry point has barriers: then
+//! each invocation gets its own thread, and the threads meet at every barrier.
+//!
+//! Entry points are validated when creating the pipeline, so the
+//! dispatch doesn't run into unsupported operations.
+
+use crate::memory::Binding;
+use naga::{
+    ArraySize, BinaryOperator as Bo, Block, Constant, ConstantInner, Expression, Function, Handle,
+    MathFunction as Mf, Module, RelationalFunction as Rf, ScalarKind, ScalarValue, Statement,
+    StorageClass, Type, TypeInner, UnaryOperator as Uo, VectorSize,
+};
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+/// A range of a buffer bound to a shader resource.
+#[derive(Clone, Debug)]
+pub(crate) struct BufferRange {
+    pub memory: Binding,
+    pub size: u64,
+}
+
+/// Resources visible to a dispatch.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Resources {
+    /// Buffers bound to `(group, binding)` pairs.
+    pub buffers: HashMap<(u32, u32), BufferRange>,
+    /// Contents of the push constant block.
+    pub push_constants: Vec<u32>,
+}
+
+/// Type of a value in memory.
+///
+/// Scalars and vectors are spelled out, since they are also found
+/// within matrices and vectors, which have no type handle for them.
+#[derive(Clone, Copy, Debug)]
+enum Ty {
+    Scalar {
+        kind: ScalarKind,
+        width: u8,
+    },
+    Vector {
+        size: VectorSize,
+        kind: ScalarKind,
+        width: u8,
+    },
+    Handle(Handle<Type>),
+}
+
+impl Ty {
+    fn column(rows: VectorSize, width: u8) -> Self {
+        Ty::Vector {
+            size: rows,
+            kind: ScalarKind::Float,
+            width,
+        }
+    }
+}
+
+/// A location in memory, along with the type found there.
+#[derive(Clone, Debug)]
+struct Pointer {
+    memory: Binding,
+    offset: u64,
+    /// Size of the whole memory region, to resolve runtime-sized arrays.
+    size: u64,
+    ty: Ty,
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Sint(i32),
+    Uint(u32),
+    Float(f32),
+    Bool(bool),
+    /// Vector, matrix (of column vectors), array or structure.
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+}
+
+impl Value {
+    fn as_f32(&self) -> f32 {
+        match *self {
+            Value::Float(value) => value,
+            ref other => panic!("Expected a float, got {:?}", other),
+        }
+    }
+
+    fn as_u32(&self) -> u32 {
+        match *self {
+            Value::Uint(value) => value,
+            Value::Sint(value) => value as u32,
+            ref other => panic!("Expected an integer, got {:?}", other),
+        }
+    }
+
+    fn as_bool(&self) -> bool {
+        match *self {
+            Value::Bool(value) => value,
+            ref other => panic!("Expected a boolean, got {:?}", other),
+        }
+    }
+
+    fn into_components(self) -> Vec<Value> {
+        match self {
+            Value::Composite(components) => components,
+            other => panic!("Expected a composite, got {:?}", other),
+        }
+    }
+
+    fn is_matrix(&self) -> bool {
+        match *self {
+            Value::Composite(ref columns) => matches!(columns.first(), Some(&Value::Composite(_))),
+            _ => false,
+        }
+    }
+
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (&Value::Sint(a), &Value::Sint(b)) => a.partial_cmp(&b),
+            (&Value::Uint(a), &Value::Uint(b)) => a.partial_cmp(&b),
+            (&Value::Float(a), &Value::Float(b)) => a.partial_cmp(&b),
+            (&Value::Bool(a), &Value::Bool(b)) => a.partial_cmp(&b),
+            (a, b) => panic!("Unable to compare {:?} and {:?}", a, b),
+        }
+    }
+}
+
+/// Applies `fun` to each component of the arguments, broadcasting scalars.
+fn componentwise(args: &[Value], fun: &dyn Fn(&[Value]) -> Value) -> Value {
+    let count = args.iter().find_map(|arg| match *arg {
+        Value::Composite(ref components) => Some(components.len()),
+        _ => None,
+    });
+    match count {
+        Some(count) => Value::Composite(
+            (0..count)
+                .map(|i| {
+                    let components = args
+                        .iter()
+                        .map(|arg| match *arg {
+                            Value::Composite(ref components) => components[i].clone(),
+                            ref scalar => scalar.clone(),
+                        })
+                        .collect::<Vec<_>>();
+                    componentwise(&components, fun)
+                })
+                .collect(),
+        ),
+        None => fun(args),
+    }
+}
+
+fn float_fun(arg: Value, fun: fn(f32) -> f32) -> Value {
+    componentwise(&[arg], &|a| Value::Float(fun(a[0].as_f32())))
+}
+
+fn dot(a: &Value, b: &Value) -> Value {
+    match (a, b) {
+        (Value::Composite(a), Value::Composite(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| scalar_binary(Bo::Multiply, a, b))
+            .fold(None, |sum, value| match sum {
+                Some(sum) => Some(scalar_binary(Bo::Add, &sum, &value)),
+                None => Some(value),
+            })
+            .expect("Empty vector"),
+        (a, b) => panic!("Unable to compute dot product of {:?} and {:?}", a, b),
+    }
+}
+
+fn length(value: &Value) -> f32 {
+    dot(value, value).as_f32().sqrt()
+}
+
+fn scalar_unary(op: Uo, value: &Value) -> Value {
+    match (op, value) {
+        (Uo::Negate, &Value::Sint(a)) => Value::Sint(a.wrapping_neg()),
+        (Uo::Negate, &Value::Float(a)) => Value::Float(-a),
+        (Uo::Not, &Value::Sint(a)) => Value::Sint(!a),
+        (Uo::Not, &Value::Uint(a)) => Value::Uint(!a),
+        (Uo::Not, &Value::Bool(a)) => Value::Bool(!a),
+        (op, value) => panic!("Unsupported unary operation {:?} on {:?}", op, value),
+    }
+}
+
+fn scalar_binary(op: Bo, left: &Value, right: &Value) -> Value {
+    match op {
+        Bo::Equal => return Value::Bool(left.compare(right) == Some(Ordering::Equal)),
+        Bo::NotEqual => return Value::Bool(left.compare(right) != Some(Ordering::Equal)),
+        Bo::Less => return Value::Bool(left.compare(right) == Some(Ordering::Less)),
+        Bo::LessEqual => {
+            return Value::Bool(matches!(
+                left.compare(right),
+                Some(Ordering::Less) | Some(Ordering::Equal)
+            ))
+        }
+        Bo::Greater => return Value::Bool(left.compare(right) == Some(Ordering::Greater)),
+        Bo::GreaterEqual => {
+            return Value::Bool(matches!(
+                left.compare(right),
+                Some(Ordering::Greater) | Some(Ordering::Equal)
+            ))
+        }
+        Bo::ShiftLeft | Bo::ShiftRight => {
+            let shift = right.as_u32();
+            return match (op, left) {
+                (Bo::ShiftLeft, &Value::Sint(a)) => Value::Sint(a.wrapping_shl(shift)),
+                (Bo::ShiftLeft, &Value::Uint(a)) => Value::Uint(a.wrapping_shl(shift)),
+                (Bo::ShiftRight, &Value::Sint(a)) => Value::Sint(a.wrapping_shr(shift)),
+                (Bo::ShiftRight, &Value::Uint(a)) => Value::Uint(a.wrapping_shr(shift)),
+                (op, value) => panic!("Unsupported shift {:?} of {:?}", op, value),
+            };
+        }
+        _ => {}
+    }
+
+    match (left, right) {
+        (&Value::Sint(a), &Value::Sint(b)) => Value::Sint(match op {
+            Bo::Add => a.wrapping_add(b),
+            Bo::Subtract => a.wrapping_sub(b),
+            Bo::Multiply => a.wrapping_mul(b),
+            Bo::Divide => a.checked_div(b).unwrap_or(0),
+            Bo::Modulo => a.checked_rem(b).unwrap_or(0),
+            Bo::And => a & b,
+            Bo::ExclusiveOr => a ^ b,
+            Bo::InclusiveOr => a | b,
+            _ => panic!("Unsupported integer operation {:?}", op),
+        }),
+        (&Value::Uint(a), &Value::Uint(b)) => Value::Uint(match op {
+            Bo::Add => a.wrapping_add(b),
+            Bo::Subtract => a.wrapping_sub(b),
+            Bo::Multiply => a.wrapping_mul(b),
+            Bo::Divide => a.checked_div(b).unwrap_or(0),
+            Bo::Modulo => a.checked_rem(b).unwrap_or(0),
+            Bo::And => a & b,
+            Bo::ExclusiveOr => a ^ b,
+            Bo::InclusiveOr => a | b,
+            _ => panic!("Unsupported integer operation {:?}", op),
+        }),
+        (&Value::Float(a), &Value::Float(b)) => Value::Float(match op {
+            Bo::Add => a + b,
+            Bo::Subtract => a - b,
+            Bo::Multiply => a * b,
+            Bo::Divide => a / b,
+            Bo::Modulo => a % b,
+            _ => panic!("Unsupported float operation {:?}", op),
+        }),
+        (&Value::Bool(a), &Value::Bool(b)) => Value::Bool(match op {
+            Bo::LogicalAnd | Bo::And => a & b,
+            Bo::LogicalOr | Bo::InclusiveOr => a | b,
+            Bo::ExclusiveOr => a ^ b,
+            _ => panic!("Unsupported boolean operation {:?}", op),
+        }),
+        (a, b) => panic!("Unsupported operation {:?} on {:?} and {:?}", op, a, b),
+    }
+}
+
+/// Multiplication where at least one of the operands is a matrix.
+fn matrix_multiply(left: Value, right: Value) -> Value {
+    match (left.is_matrix(), right.is_matrix()) {
+        // Linear combination of the matrix columns.
+        (true, false) => left
+            .into_components()
+            .into_iter()
+            .zip(right.into_components())
+            .map(|(column, factor)| binary(Bo::Multiply, column, factor))
+            .fold(None, |sum, value| match sum {
+                Some(sum) => Some(binary(Bo::Add, sum, value)),
+                None => Some(value),
+            })
+            .expect("Empty matrix"),
+        (false, true) => Value::Composite(
+            right
+                .into_components()
+                .iter()
+                .map(|column| dot(&left, column))
+                .collect(),
+        ),
+        (true, true) => Value::Composite(
+            right
+                .into_components()
+                .into_iter()
+                .map(|column| matrix_multiply(left.clone(), column))
+                .collect(),
+        ),
+        (false, false) => unreachable!(),
+    }
+}
+
+fn binary(op: Bo, left: Value, right: Value) -> Value {
+    match (&left, &right) {
+        (&Value::Composite(_), &Value::Composite(_))
+            if op == Bo::Multiply && (left.is_matrix() || right.is_matrix()) =>
+        {
+            matrix_multiply(left, right)
+        }
+        _ => componentwise(&[left, right], &|args| {
+            scalar_binary(op, &args[0], &args[1])
+        }),
+    }
+}
+
+fn convert(value: &Value, kind: ScalarKind) -> Value {
+    match (value, kind) {
+        (&Value::Sint(v), ScalarKind::Uint) => Value::Uint(v as u32),
+        (&Value::Sint(v), ScalarKind::Float) => Value::Float(v as f32),
+        (&Value::Sint(v), ScalarKind::Bool) => Value::Bool(v != 0),
+        (&Value::Uint(v), ScalarKind::Sint) => Value::Sint(v as i32),
+        (&Value::Uint(v), ScalarKind::Float) => Value::Float(v as f32),
+        (&Value::Uint(v), ScalarKind::Bool) => Value::Bool(v != 0),
+        (&Value::Float(v), ScalarKind::Sint) => Value::Sint(v as i32),
+        (&Value::Float(v), ScalarKind::Uint) => Value::Uint(v as u32),
+        (&Value::Float(v), ScalarKind::Bool) => Value::Bool(v != 0.0),
+        (&Value::Bool(v), ScalarKind::Sint) => Value::Sint(v as i32),
+        (&Value::Bool(v), ScalarKind::Uint) => Value::Uint(v as u32),
+        (&Value::Bool(v), ScalarKind::Float) => Value::Float(v as u32 as f32),
+        (value, _) => value.clone(),
+    }
+}
+
+fn bitcast(value: &Value, kind: ScalarKind) -> Value {
+    let bits = match *value {
+        Value::Sint(v) => v as u32,
+        Value::Uint(v) => v,
+        Value::Float(v) => v.to_bits(),
+        ref other => panic!("Unable to bitcast {:?}", other),
+    };
+    match kind {
+        ScalarKind::Sint => Value::Sint(bits as i32),
+        ScalarKind::Uint => Value::Uint(bits),
+        ScalarKind::Float => Value::Float(f32::from_bits(bits)),
+        ScalarKind::Bool => Value::Bool(bits != 0),
+    }
+}
+
+fn column_stride(rows: VectorSize, width: u8) -> u64 {
+    // Three-component columns are aligned like four-component ones.
+    let rows = match rows {
+        VectorSize::Bi => 2,
+        VectorSize::Tri | VectorSize::Quad => 4,
+    };
+    rows * u64::from(width)
+}
+
+/// Identifiers of a single invocation.
+#[derive(Clone, Copy, Debug)]
+struct InvocationIds {
+    global: [u32; 3],
+    local: [u32; 3],
+    local_index: u32,
+    workgroup: [u32; 3],
+    num_workgroups: [u32; 3],
+    workgroup_size: [u32; 3],
+}
+
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+}
+
+/// State of a function being executed.
+struct Frame<'a> {
+    function: &'a Function,
+    arguments: Vec<Value>,
+    locals: Vec<Pointer>,
+    /// Values of the evaluated expressions.
+    values: Vec<Option<Value>>,
+}
+
+#[derive(Debug, Default)]
+struct BarrierState {
+    arrived: usize,
+    generation: u64,
+    broken: bool,
+}
+
+/// Barrier shared by the invocations of a workgroup.
+///
+/// Unlike `std::sync::Barrier`, it can be broken by a panicking invocation,
+/// which releases the others instead of leaving them waiting forever.
+#[derive(Debug)]
+struct Barrier {
+    count: usize,
+    state: Mutex<BarrierState>,
+    condvar: Condvar,
+}
+
+impl Barrier {
+    fn new(count: usize) -> Self {
+        Barrier {
+            count,
+            state: Mutex::default(),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Blocks until all the invocations reach the barrier.
+    ///
+    /// Panics if the barrier gets broken.
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        let generation = state.generation;
+        state.arrived += 1;
+        if state.arrived == self.count {
+            state.arrived = 0;
+            state.generation += 1;
+            self.condvar.notify_all();
+        }
+        while state.generation == generation && !state.broken {
+            state = self.condvar.wait(state).unwrap();
+        }
+        if state.broken {
+            drop(state);
+            panic!("Another invocation of the workgroup panicked");
+        }
+    }
+
+    fn break_all(&self) {
+        self.state.lock().unwrap().broken = true;
+        self.condvar.notify_all();
+    }
+}
+
+struct Invocation<'a> {
+    module: &'a Module,
+    /// Storage of the global variables, or `None` for unsupported ones.
+    globals: Vec<Option<Pointer>>,
+    barrier: Option<&'a Barrier>,
+}
+
+impl<'a> Invocation<'a> {
+    /// Returns the type of values of the given module type.
+    fn ty(&self, handle: Handle<Type>) -> Ty {
+        match self.module.types[handle].inner {
+            TypeInner::Scalar { kind, width } => Ty::Scalar { kind, width },
+            TypeInner::Vector { size, kind, width } => Ty::Vector { size, kind, width },
+            _ => Ty::Handle(handle),
+        }
+    }
+
+    fn type_size(&self, ty: Ty) -> u64 {
+        let handle = match ty {
+            Ty::Scalar { width, .. } => return u64::from(width),
+            Ty::Vector { size, width, .. } => return size as u64 * u64::from(width),
+            Ty::Handle(handle) => handle,
+        };
+        match self.module.types[handle].inner {
+            TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            } => columns as u64 * column_stride(rows, width),
+            TypeInner::Array {
+                size: ArraySize::Constant(count),
+                stride,
+                ..
+            } => self.constant_index(count) * u64::from(stride),
+            TypeInner::Array {
+                size: ArraySize::Dynamic,
+                ..
+            } => 0,
+            TypeInner::Struct { ref members, .. } => members
+                .iter()
+                .map(|member| u64::from(member.offset) + self.type_size(self.ty(member.ty)))
+                .max()
+                .unwrap_or(0),
+            ref other => panic!("Type {:?} can't be stored in memory", other),
+        }
+    }
+
+    fn constant_index(&self, handle: Handle<Constant>) -> u64 {
+        match self.module.constants[handle].inner {
+            ConstantInner::Scalar {
+                value: ScalarValue::Sint(value),
+                ..
+            } => value as u64,
+            ConstantInner::Scalar {
+                value: ScalarValue::Uint(value),
+                ..
+            } => value,
+            ref other => panic!("Constant {:?} is not an index", other),
+        }
+    }
+
+    fn constant(&self, handle: Handle<Constant>) -> Value {
+        match self.module.constants[handle].inner {
+            ConstantInner::Scalar { ref value, .. } => match *value {
+                ScalarValue::Sint(v) => Value::Sint(v as i32),
+                ScalarValue::Uint(v) => Value::Uint(v as u32),
+                ScalarValue::Float(v) => Value::Float(v as f32),
+                ScalarValue::Bool(v) => Value::Bool(v),
+            },
+            ConstantInner::Composite { ref components, .. } => {
+                Value::Composite(components.iter().map(|&c| self.constant(c)).collect())
+            }
+        }
+    }
+
+    /// Allocates zeroed memory for a variable of type `ty`, optionally initialized.
+    fn allocate(&self, ty: Handle<Type>, init: Option<Handle<Constant>>) -> Pointer {
+        let ty = self.ty(ty);
+        let size = self.type_size(ty);
+        let pointer = Pointer {
+            memory: Binding::zeroed(size),
+            offset: 0,
+            size,
+            ty,
+        };
+        if let Some(init) = init {
+            self.store(&pointer, &self.constant(init));
+        }
+        pointer
+    }
+
+    fn read_scalar(&self, memory: &Binding, offset: u64, kind: ScalarKind, width: u8) -> Value {
+        if kind == ScalarKind::Bool {
+            let byte = unsafe { *memory.ptr(offset, u64::from(width)) };
+            return Value::Bool(byte != 0);
+        }
+        assert_eq!(width, 4, "Only 32-bit scalars are supported");
+        let bytes = unsafe { ptr::read_unaligned(memory.ptr(offset, 4) as *const [u8; 4]) };
+        match kind {
+            ScalarKind::Sint => Value::Sint(i32::from_ne_bytes(bytes)),
+            ScalarKind::Uint => Value::Uint(u32::from_ne_bytes(bytes)),
+            ScalarKind::Float => Value::Float(f32::from_ne_bytes(bytes)),
+            ScalarKind::Bool => unreachable!(),
+        }
+    }
+
+    fn write_scalar(&self, memory: &Binding, offset: u64, width: u8, value: &Value) {
+        let bytes = match *value {
+            Value::Bool(v) => {
+                unsafe { ptr::write_bytes(memory.ptr(offset, u64::from(width)), v as u8, 1) };
+                return;
+            }
+            Value::Sint(v) => v.to_ne_bytes(),
+            Value::Uint(v) => v.to_ne_bytes(),
+            Value::Float(v) => v.to_ne_bytes(),
+            ref other => panic!("Expected a scalar, got {:?}", other),
+        };
+        assert_eq!(width, 4, "Only 32-bit scalars are supported");
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), memory.ptr(offset, 4), 4) };
+    }
+
+    fn read(&self, memory: &Binding, offset: u64, ty: Ty) -> Value {
+        let handle = match ty {
+            Ty::Scalar { kind, width } => return self.read_scalar(memory, offset, kind, width),
+            Ty::Vector { size, kind, width } => {
+                return Value::Composite(
+                    (0..size as u64)
+                        .map(|i| {
+                            self.read_scalar(memory, offset + i * u64::from(width), kind, width)
+                        })
+                        .collect(),
+                )
+            }
+            Ty::Handle(handle) => handle,
+        };
+        match self.module.types[handle].inner {
+            TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            } => Value::Composite(
+                (0..columns as u64)
+                    .map(|i| {
+                        let column = Ty::column(rows, width);
+                        self.read(memory, offset + i * column_stride(rows, width), column)
+                    })
+                    .collect(),
+            ),
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(count),
+                stride,
+            } => Value::Composite(
+                (0..self.constant_index(count))
+                    .map(|i| self.read(memory, offset + i * u64::from(stride), self.ty(base)))
+                    .collect(),
+            ),
+            TypeInner::Struct { ref members, .. } => Value::Composite(
+                members
+                    .iter()
+                    .map(|member| {
+                        self.read(
+                            memory,
+                            offset + u64::from(member.offset),
+                            self.ty(member.ty),
+                        )
+                    })
+                    .collect(),
+            ),
+            ref other => panic!("Unable to load a value of type {:?}", other),
+        }
+    }
+
+    fn write(&self, memory: &Binding, offset: u64, ty: Ty, value: &Value) {
+        let components = match (ty, value) {
+            (Ty::Scalar { width, .. }, value) => {
+                return self.write_scalar(memory, offset, width, value)
+            }
+            (_, Value::Composite(components)) => components,
+            (ty, value) => panic!("Unable to store {:?} as {:?}", value, ty),
+        };
+        let handle = match ty {
+            Ty::Vector { width, .. } => {
+                for (i, component) in components.iter().enumerate() {
+                    let component_offset = offset + i as u64 * u64::from(width);
+                    self.write_scalar(memory, component_offset, width, component);
+                }
+                return;
+            }
+            Ty::Handle(handle) => handle,
+            Ty::Scalar { .. } => unreachable!(),
+        };
+        match self.module.types[handle].inner {
+            TypeInner::Matrix { rows, width, .. } => {
+                for (i, column) in components.iter().enumerate() {
+                    let column_offset = offset + i as u64 * column_stride(rows, width);
+                    self.write(memory, column_offset, Ty::column(rows, width), column);
+                }
+            }
+            TypeInner::Array { base, stride, .. } => {
+                for (i, element) in components.iter().enumerate() {
+                    let element_offset = offset + i as u64 * u64::from(stride);
+                    self.write(memory, element_offset, self.ty(base), element);
+                }
+            }
+            TypeInner::Struct { ref members, .. } => {
+                for (member, field) in members.iter().zip(components) {
+                    let member_offset = offset + u64::from(member.offset);
+                    self.write(memory, member_offset, self.ty(member.ty), field);
+                }
+            }
+            ref other => panic!("Unable to store {:?} as {:?}", value, other),
+        }
+    }
+
+    fn load(&self, pointer: &Pointer) -> Value {
+        self.read(&pointer.memory, pointer.offset, pointer.ty)
+    }
+
+    fn store(&self, pointer: &Pointer, value: &Value) {
+        self.write(&pointer.memory, pointer.offset, pointer.ty, value)
+    }
+
+    /// Returns a pointer to the element `index` of the pointed composite.
+    fn access(&self, pointer: Pointer, index: u64) -> Pointer {
+        let (offset, ty) = match pointer.ty {
+            Ty::Vector { kind, width, .. } => {
+                (index * u64::from(width), Ty::Scalar { kind, width })
+            }
+            Ty::Handle(handle) => match self.module.types[handle].inner {
+                TypeInner::Matrix { rows, width, .. } => {
+                    (index * column_stride(rows, width), Ty::column(rows, width))
+                }
+                TypeInner::Array { base, stride, .. } => (index * u64::from(stride), self.ty(base)),
+                TypeInner::Struct { ref members, .. } => {
+                    let member = &members[index as usize];
+                    (u64::from(member.offset), self.ty(member.ty))
+                }
+                ref other => panic!("Unable to index into {:?}", other),
+            },
+            other => panic!("Unable to index into {:?}", other),
+        };
+        Pointer {
+            offset: pointer.offset + offset,
+            ty,
+            ..pointer
+        }
+    }
+
+    fn index(&self, base: Value, index: u64) -> Value {
+        match base {
+            Value::Pointer(pointer) => Value::Pointer(self.access(pointer, index)),
+            Value::Composite(mut components) => components.swap_remove(index as usize),
+            other => panic!("Unable to index into {:?}", other),
+        }
+    }
+
+    fn value(&self, frame: &mut Frame, handle: Handle<Expression>) -> Value {
+        if let Some(ref value) = frame.values[handle.index()] {
+            return value.clone();
+        }
+        let value = self.evaluate(frame, handle);
+        frame.values[handle.index()] = Some(value.clone());
+        value
+    }
+
+    fn evaluate(&self, frame: &mut Frame, handle: Handle<Expression>) -> Value {
+        match frame.function.expressions[handle] {
+            Expression::Access { base, index } => {
+                let base = self.value(frame, base);
+                let index = self.value(frame, index).as_u32();
+                self.index(base, u64::from(index))
+            }
+            Expression::AccessIndex { base, index } => {
+                let base = self.value(frame, base);
+                self.index(base, u64::from(index))
+            }
+            Expression::Constant(constant) => self.constant(constant),
+            Expression::Splat { size, value } => {
+                Value::Composite(vec![self.value(frame, value); size as usize])
+            }
+            Expression::Swizzle {
+                size,
+                vector,
+                pattern,
+            } => {
+                let vector = self.value(frame, vector).into_components();
+                Value::Composite(
+                    pattern[..size as usize]
+                        .iter()
+                        .map(|&component| vector[component as usize].clone())
+                        .collect(),
+                )
+            }
+            Expression::Compose { ty, ref components } => {
+                let values = components.iter().map(|&c| self.value(frame, c));
+                match self.module.types[ty].inner {
+                    // Vectors may be composed out of smaller vectors.
+                    TypeInner::Vector { .. } => Value::Composite(
+                        values
+                            .flat_map(|value| match value {
+                                Value::Composite(components) => components,
+                                scalar => vec![scalar],
+                            })
+                            .collect(),
+                    ),
+                    _ => Value::Composite(values.collect()),
+                }
+            }
+            Expression::FunctionArgument(index) => frame.arguments[index as usize].clone(),
+            Expression::GlobalVariable(global) => match self.globals[global.index()] {
+                Some(ref pointer) => Value::Pointer(pointer.clone()),
+                None => panic!(
+                    "Global variable {:?} is not supported",
+                    self.module.global_variables[global]
+                ),
+            },
+            Expression::LocalVariable(local) => Value::Pointer(frame.locals[local.index()].clone()),
+            Expression::Load { pointer } => match self.value(frame, pointer) {
+                Value::Pointer(ref pointer) => self.load(pointer),
+                other => panic!("Unable to load from {:?}", other),
+            },
+            Expression::Unary { op, expr } => {
+                let value = self.value(frame, expr);
+                componentwise(&[value], &|args| scalar_unary(op, &args[0]))
+            }
+            Expression::Binary { op, left, right } => {
+                let left = self.value(frame, left);
+                let right = self.value(frame, right);
+                binary(op, left, right)
+            }
+            Expression::Select {
+                condition,
+                accept,
+                reject,
+            } => {
+                let condition = self.value(frame, condition);
+                let accept = self.value(frame, accept);
+                let reject = self.value(frame, reject);
+                match condition {
+                    Value::Bool(true) => accept,
+                    Value::Bool(false) => reject,
+                    condition => componentwise(&[condition, accept, reject], &|args| {
+                        if args[0].as_bool() {
+                            args[1].clone()
+                        } else {
+                            args[2].clone()
+                        }
+                    }),
+                }
+            }
+            Expression::Relational { fun, argument } => {
+                let argument = self.value(frame, argument);
+                match fun {
+                    Rf::All => Value::Bool(argument.into_components().iter().all(Value::as_bool)),
+                    Rf::Any => Value::Bool(argument.into_components().iter().any(Value::as_bool)),
+                    Rf::IsNan => {
+                        componentwise(&[argument], &|a| Value::Bool(a[0].as_f32().is_nan()))
+                    }
+                    Rf::IsInf => {
+                        componentwise(&[argument], &|a| Value::Bool(a[0].as_f32().is_infinite()))
+                    }
+                    Rf::IsFinite => {
+                        componentwise(&[argument], &|a| Value::Bool(a[0].as_f32().is_finite()))
+                    }
+                    Rf::IsNormal => {
+                        componentwise(&[argument], &|a| Value::Bool(a[0].as_f32().is_normal()))
+                    }
+                }
+            }
+            Expression::Math {
+                fun,
+                arg,
+                arg1,
+                arg2,
+                ..
+            } => {
+                let arg = self.value(frame, arg);
+                let arg1 = arg1.map(|arg| self.value(frame, arg));
+                let arg2 = arg2.map(|arg| self.value(frame, arg));
+                self.math(fun, arg, arg1, arg2)
+            }
+            Expression::As {
+                expr,
+                kind,
+                convert,
+            } => {
+                let value = self.value(frame, expr);
+                if convert {
+                    componentwise(&[value], &|args| self::convert(&args[0], kind))
+                } else {
+                    componentwise(&[value], &|args| bitcast(&args[0], kind))
+                }
+            }
+            Expression::ArrayLength(expr) => match self.value(frame, expr) {
+                Value::Pointer(Pointer {
+                    offset,
+                    size,
+                    ty: Ty::Handle(handle),
+                    ..
+                }) => match self.module.types[handle].inner {
+                    TypeInner::Array { stride, .. } => {
+                        Value::Uint(((size - offset) / u64::from(stride)) as u32)
+                    }
+                    ref other => panic!("Unable to get the array length of {:?}", other),
+                },
+                other => panic!("Unable to get the array length of {:?}", other),
+            },
+            ref other => panic!("Unsupported expression {:?}", other),
+        }
+    }
+
+    fn math(&self, fun: Mf, arg: Value, arg1: Option<Value>, arg2: Option<Value>) -> Value {
+        let arg1 = || arg1.clone().expect("Missing math argument");
+        let arg2 = || arg2.clone().expect("Missing math argument");
+        match fun {
+            Mf::Abs => componentwise(&[arg], &|a| match a[0] {
+                Value::Sint(v) => Value::Sint(v.wrapping_abs()),
+                Value::Float(v) => Value::Float(v.abs()),
+                ref other => other.clone(),
+            }),
+            Mf::Min => componentwise(&[arg, arg1()], &|a| {
+                if a[1].compare(&a[0]) == Some(Ordering::Less) {
+                    a[1].clone()
+                } else {
+                    a[0].clone()
+                }
+            }),
+            Mf::Max => componentwise(&[arg, arg1()], &|a| {
+                if a[1].compare(&a[0]) == Some(Ordering::Greater) {
+                    a[1].clone()
+                } else {
+                    a[0].clone()
+                }
+            }),
+            Mf::Clamp => {
+                let low = self.math(Mf::Max, arg, Some(arg1()), None);
+                self.math(Mf::Min, low, Some(arg2()), None)
+            }
+            Mf::Cos => float_fun(arg, f32::cos),
+            Mf::Cosh => float_fun(arg, f32::cosh),
+            Mf::Sin => float_fun(arg, f32::sin),
+            Mf::Sinh => float_fun(arg, f32::sinh),
+            Mf::Tan => float_fun(arg, f32::tan),
+            Mf::Tanh => float_fun(arg, f32::tanh),
+            Mf::Acos => float_fun(arg, f32::acos),
+            Mf::Asin => float_fun(arg, f32::asin),
+            Mf::Atan => float_fun(arg, f32::atan),
+            Mf::Atan2 => componentwise(&[arg, arg1()], &|a| {
+                Value::Float(a[0].as_f32().atan2(a[1].as_f32()))
+            }),
+            Mf::Ceil => float_fun(arg, f32::ceil),
+            Mf::Floor => float_fun(arg, f32::floor),
+            Mf::Round => float_fun(arg, f32::round),
+            Mf::Fract => float_fun(arg, f32::fract),
+            Mf::Trunc => float_fun(arg, f32::trunc),
+            Mf::Exp => float_fun(arg, f32::exp),
+            Mf::Exp2 => float_fun(arg, f32::exp2),
+            Mf::Log => float_fun(arg, f32::ln),
+            Mf::Log2 => float_fun(arg, f32::log2),
+            Mf::Pow => componentwise(&[arg, arg1()], &|a| {
+                Value::Float(a[0].as_f32().powf(a[1].as_f32()))
+            }),
+            Mf::Sqrt => float_fun(arg, f32::sqrt),
+            Mf::InverseSqrt => float_fun(arg, |v| 1.0 / v.sqrt()),
+            Mf::Sign => componentwise(&[arg], &|a| match a[0] {
+                Value::Sint(v) => Value::Sint(v.signum()),
+                Value::Float(v) => Value::Float(if v == 0.0 { 0.0 } else { v.signum() }),
+                ref other => other.clone(),
+            }),
+            Mf::Step => componentwise(&[arg, arg1()], &|a| {
+                Value::Float(if a[1].as_f32() < a[0].as_f32() {
+                    0.0
+                } else {
+                    1.0
+                })
+            }),
+            Mf::SmoothStep => componentwise(&[arg, arg1(), arg2()], &|a| {
+                let (low, high, x) = (a[0].as_f32(), a[1].as_f32(), a[2].as_f32());
+                let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+                Value::Float(t * t * (3.0 - 2.0 * t))
+            }),
+            Mf::Mix => componentwise(&[arg, arg1(), arg2()], &|a| {
+                let (x, y, t) = (a[0].as_f32(), a[1].as_f32(), a[2].as_f32());
+                Value::Float(x + (y - x) * t)
+            }),
+            Mf::Fma => componentwise(&[arg, arg1(), arg2()], &|a| {
+                Value::Float(a[0].as_f32().mul_add(a[1].as_f32(), a[2].as_f32()))
+            }),
+            Mf::Dot => dot(&arg, &arg1()),
+            Mf::Cross => {
+                let a = arg.into_components();
+                let b = arg1().into_components();
+                let (a, b) = (
+                    [a[0].as_f32(), a[1].as_f32(), a[2].as_f32()],
+                    [b[0].as_f32(), b[1].as_f32(), b[2].as_f32()],
+                );
+                Value::Composite(vec![
+                    Value::Float(a[1] * b[2] - a[2] * b[1]),
+                    Value::Float(a[2] * b[0] - a[0] * b[2]),
+                    Value::Float(a[0] * b[1] - a[1] * b[0]),
+                ])
+            }
+            Mf::Length => Value::Float(length(&arg)),
+            Mf::Distance => Value::Float(length(&binary(Bo::Subtract, arg, arg1()))),
+            Mf::Normalize => {
+                let length = Value::Float(length(&arg));
+                binary(Bo::Divide, arg, length)
+            }
+            Mf::Transpose => {
+                let columns = arg.into_components();
+                let rows = columns[0].clone().into_components().len();
+                Value::Composite(
+                    (0..rows)
+                        .map(|row| {
+                            Value::Composite(
+                                columns
+                                    .iter()
+                                    .map(|column| match *column {
+                                        Value::Composite(ref c) => c[row].clone(),
+                                        ref other => panic!("Expected a column, got {:?}", other),
+                                    })
+                                    .collect(),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            Mf::CountOneBits => componentwise(&[arg], &|a| match a[0] {
+                Value::Sint(v) => Value::Sint(v.count_ones() as i32),
+                Value::Uint(v) => Value::Uint(v.count_ones()),
+                ref other => panic!("Unable to count bits of {:?}", other),
+            }),
+            Mf::ReverseBits => componentwise(&[arg], &|a| match a[0] {
+                Value::Sint(v) => Value::Sint(v.reverse_bits()),
+                Value::Uint(v) => Value::Uint(v.reverse_bits()),
+                ref other => panic!("Unable to reverse bits of {:?}", other),
+            }),
+            other => panic!("Unsupported math function {:?}", other),
+        }
+    }
+
+    fn execute_block(&self, frame: &mut Frame, block: &Block) -> Flow {
+        for statement in block.iter() {
+            match self.execute(frame, statement) {
+                Flow::Next => {}
+                flow => return flow,
+            }
+        }
+        Flow::Next
+    }
+
+    fn execute(&self, frame: &mut Frame, statement: &Statement) -> Flow {
+        match *statement {
+            Statement::Emit(ref range) => {
+                for handle in range.clone() {
+                    let value = self.evaluate(frame, handle);
+                    frame.values[handle.index()] = Some(value);
+                }
+            }
+            Statement::Block(ref block) => return self.execute_block(frame, block),
+            Statement::If {
+                condition,
+                ref accept,
+                ref reject,
+            } => {
+                return if self.value(frame, condition).as_bool() {
+                    self.execute_block(frame, accept)
+                } else {
+                    self.execute_block(frame, reject)
+                };
+            }
+            Statement::Switch {
+                selector,
+                ref cases,
+                ref default,
+            } => {
+                let selector = self.value(frame, selector).as_u32() as i32;
+                let flow = match cases.iter().position(|case| case.value == selector) {
+                    Some(start) => {
+                        let mut flow = Flow::Next;
+                        for case in cases[start..].iter() {
+                            flow = self.execute_block(frame, &case.body);
+                            match flow {
+                                Flow::Next if case.fall_through => {}
+                                _ => break,
+                            }
+                        }
+                        flow
+                    }
+                    None => self.execute_block(frame, default),
+                };
+                return match flow {
+                    Flow::Break => Flow::Next,
+                    flow => flow,
+                };
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+            } => loop {
+                match self.execute_block(frame, body) {
+                    Flow::Break => return Flow::Next,
+                    Flow::Return(value) => return Flow::Return(value),
+                    Flow::Next | Flow::Continue => {}
+                }
+                if let Flow::Return(value) = self.execute_block(frame, continuing) {
+                    return Flow::Return(value);
+                }
+            },
+            Statement::Break => return Flow::Break,
+            Statement::Continue => return Flow::Continue,
+            Statement::Return { value } => {
+                return Flow::Return(value.map(|value| self.value(frame, value)))
+            }
+            Statement::Kill => return Flow::Return(None),
+            Statement::Barrier(_) => {
+                if let Some(barrier) = self.barrier {
+                    barrier.wait();
+                }
+            }
+            Statement::Store { pointer, value } => {
+                let value = self.value(frame, value);
+                match self.value(frame, pointer) {
+                    Value::Pointer(ref pointer) => self.store(pointer, &value),
+                    other => panic!("Unable to store to {:?}", other),
+                }
+            }
+            Statement::Call {
+                function,
+                ref arguments,
+                result,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.value(frame, argument))
+                    .collect();
+                let value = self.call(&self.module.functions[function], arguments);
+                if let Some(result) = result {
+                    frame.values[result.index()] = value;
+                }
+            }
+            ref other => panic!("Unsupported statement {:?}", other),
+        }
+        Flow::Next
+    }
+
+    fn call(&self, function: &Function, arguments: Vec<Value>) -> Option<Value> {
+        let mut frame = Frame {
+            function,
+            arguments,
+            locals: function
+                .local_variables
+                .iter()
+                .map(|(_, local)| self.allocate(local.ty, local.init))
+                .collect(),
+            values: vec![None; function.expressions.len()],
+        };
+        match self.execute_block(&mut frame, &function.body) {
+            Flow::Return(value) => value,
+            _ => None,
+        }
+    }
+
+    /// Builds the value of an entry point argument out of the built-in inputs.
+    fn input(
+        &self,
+        ty: Handle<Type>,
+        binding: Option<&naga::Binding>,
+        ids: &InvocationIds,
+    ) -> Value {
+        let inner = &self.module.types[ty].inner;
+        let value = match binding {
+            Some(&naga::Binding::BuiltIn(built_in)) => {
+                use naga::BuiltIn as Bi;
+                let vector = match built_in {
+                    Bi::GlobalInvocationId => ids.global,
+                    Bi::LocalInvocationId => ids.local,
+                    Bi::WorkGroupId => ids.workgroup,
+                    Bi::NumWorkGroups => ids.num_workgroups,
+                    Bi::WorkGroupSize => ids.workgroup_size,
+                    Bi::LocalInvocationIndex => return Value::Uint(ids.local_index),
+                    other => panic!("Unsupported compute input {:?}", other),
+                };
+                Value::Composite(vector.iter().map(|&v| Value::Uint(v)).collect())
+            }
+            Some(other) => panic!("Unsupported compute input {:?}", other),
+            None => match *inner {
+                TypeInner::Struct { ref members, .. } => {
+                    return Value::Composite(
+                        members
+                            .iter()
+                            .map(|member| self.input(member.ty, member.binding.as_ref(), ids))
+                            .collect(),
+                    )
+                }
+                ref other => panic!("Entry point argument {:?} has no binding", other),
+            },
+        };
+        match *inner {
+            TypeInner::Vector {
+                kind: ScalarKind::Sint,
+                ..
+            } => componentwise(&[value], &|args| convert(&args[0], ScalarKind::Sint)),
+            _ => value,
+        }
+    }
+
+    fn run(&mut self, entry_point: usize, ids: &InvocationIds) {
+        let module = self.module;
+        for (handle, var) in module.global_variables.iter() {
+            match var.class {
+                StorageClass::Private | StorageClass::Function => {
+                    self.globals[handle.index()] = Some(self.allocate(var.ty, var.init));
+                }
+                _ => {}
+            }
+        }
+        let function = &module.entry_points[entry_point].function;
+        let arguments = function
+            .arguments
+            .iter()
+            .map(|arg| self.input(arg.ty, arg.binding.as_ref(), ids))
+            .collect();
+        self.call(function, arguments);
+    }
+}
+
+fn uses_barriers(module: &Module, block: &Block) -> bool {
+    block.iter().any(|statement| match *statement {
+        Statement::Barrier(_) => true,
+        Statement::Block(ref block) => uses_barriers(module, block),
+        Statement::If {
+            ref accept,
+            ref reject,
+            ..
+        } => uses_barriers(module, accept) || uses_barriers(module, reject),
+        Statement::Switch {
+            ref cases,
+            ref default,
+            ..
+        } => {
+            cases.iter().any(|case| uses_barriers(module, &case.body))
+                || uses_barriers(module, default)
+        }
+        Statement::Loop {
+            ref body,
+            ref continuing,
+        } => uses_barriers(module, body) || uses_barriers(module, continuing),
+        Statement::Call { function, .. } => uses_barriers(module, &module.functions[function].body),
+        _ => false,
+    })
+}
+
+fn validate_type(module: &Module, ty: Handle<Type>) -> Result<(), String> {
+    match module.types[ty].inner {
+        TypeInner::Scalar {
+            kind: ScalarKind::Bool,
+            ..
+        }
+        | TypeInner::Vector {
+            kind: ScalarKind::Bool,
+            ..
+        }
+        | TypeInner::Scalar { width: 4, .. }
+        | TypeInner::Vector { width: 4, .. }
+        | TypeInner::Matrix { width: 4, .. } => Ok(()),
+        TypeInner::Array { base, size, .. } => {
+            if let ArraySize::Constant(count) = size {
+                match module.constants[count].inner {
+                    ConstantInner::Scalar {
+                        value: ScalarValue::Sint(_),
+                        ..
+                    }
+                    | ConstantInner::Scalar {
+                        value: ScalarValue::Uint(_),
+                        ..
+                    } => {}
+                    ref other => return Err(format!("Constant {:?} is not an index", other)),
+                }
+            }
+            validate_type(module, base)
+        }
+        TypeInner::Struct { ref members, .. } => members
+            .iter()
+            .try_for_each(|member| validate_type(module, member.ty)),
+        ref other => Err(format!("Type {:?} can't be stored in memory", other)),
+    }
+}
+
+fn validate_input(
+    module: &Module,
+    ty: Handle<Type>,
+    binding: Option<&naga::Binding>,
+) -> Result<(), String> {
+    use naga::BuiltIn as Bi;
+    match binding {
+        Some(&naga::Binding::BuiltIn(Bi::GlobalInvocationId))
+        | Some(&naga::Binding::BuiltIn(Bi::LocalInvocationId))
+        | Some(&naga::Binding::BuiltIn(Bi::WorkGroupId))
+        | Some(&naga::Binding::BuiltIn(Bi::NumWorkGroups))
+        | Some(&naga::Binding::BuiltIn(Bi::WorkGroupSize))
+        | Some(&naga::Binding::BuiltIn(Bi::LocalInvocationIndex)) => Ok(()),
+        Some(other) => Err(format!("Unsupported compute input {:?}", other)),
+        None => match module.types[ty].inner {
+            TypeInner::Struct { ref members, .. } => members
+                .iter()
+                .try_for_each(|member| validate_input(module, member.ty, member.binding.as_ref())),
+            ref other => Err(format!("Entry point argument {:?} has no binding", other)),
+        },
+    }
+}
+
+fn validate_block(module: &Module, block: &Block) -> Result<(), String> {
+    block.iter().try_for_each(|statement| match *statement {
+        Statement::Emit(_)
+        | Statement::Break
+        | Statement::Continue
+        | Statement::Return { .. }
+        | Statement::Kill
+        | Statement::Barrier(_)
+        | Statement::Store { .. } => Ok(()),
+        Statement::Block(ref block) => validate_block(module, block),
+        Statement::If {
+            ref accept,
+            ref reject,
+            ..
+        } => {
+            validate_block(module, accept)?;
+            validate_block(module, reject)
+        }
+        Statement::Switch {
+            ref cases,
+            ref default,
+            ..
+        } => {
+            for case in cases.iter() {
+                validate_block(module, &case.body)?;
+            }
+            validate_block(module, default)
+        }
+        Statement::Loop {
+            ref body,
+            ref continuing,
+        } => {
+            validate_block(module, body)?;
+            validate_block(module, continuing)
+        }
+        Statement::Call { function, .. } => validate_function(module, &module.functions[function]),
+        ref other => Err(format!("Unsupported statement {:?}", other)),
+    })
+}
+
+fn validate_function(module: &Module, function: &Function) -> Result<(), String> {
+    for (_, local) in function.local_variables.iter() {
+        validate_type(module, local.ty)?;
+    }
+    for (_, expression) in function.expressions.iter() {
+        match *expression {
+            Expression::GlobalVariable(global) => {
+                let var = &module.global_variables[global];
+                match var.class {
+                    StorageClass::Handle => {
+                        return Err(format!("Global variable {:?} is not supported", var))
+                    }
+                    _ => validate_type(module, var.ty)?,
+                }
+            }
+            Expression::Math { fun, .. } => match fun {
+                Mf::Abs
+                | Mf::Min
+                | Mf::Max
+                | Mf::Clamp
+                | Mf::Cos
+                | Mf::Cosh
+                | Mf::Sin
+                | Mf::Sinh
+                | Mf::Tan
+                | Mf::Tanh
+                | Mf::Acos
+                | Mf::Asin
+                | Mf::Atan
+                | Mf::Atan2
+                | Mf::Ceil
+                | Mf::Floor
+                | Mf::Round
+                | Mf::Fract
+                | Mf::Trunc
+                | Mf::Exp
+                | Mf::Exp2
+                | Mf::Log
+                | Mf::Log2
+                | Mf::Pow
+                | Mf::Sqrt
+                | Mf::InverseSqrt
+                | Mf::Sign
+                | Mf::Step
+                | Mf::SmoothStep
+                | Mf::Mix
+                | Mf::Fma
+                | Mf::Dot
+                | Mf::Cross
+                | Mf::Length
+                | Mf::Distance
+                | Mf::Normalize
+                | Mf::Transpose
+                | Mf::CountOneBits
+                | Mf::ReverseBits => {}
+                other => return Err(format!("Unsupported math function {:?}", other)),
+            },
+            Expression::Access { .. }
+            | Expression::AccessIndex { .. }
+            | Expression::Constant(_)
+            | Expression::Splat { .. }
+            | Expression::Swizzle { .. }
+            | Expression::Compose { .. }
+            | Expression::FunctionArgument(_)
+            | Expression::LocalVariable(_)
+            | Expression::Load { .. }
+            | Expression::Unary { .. }
+            | Expression::Binary { .. }
+            | Expression::Select { .. }
+            | Expression::Relational { .. }
+            | Expression::As { .. }
+            | Expression::CallResult(_)
+            | Expression::ArrayLength(_) => {}
+            ref other => return Err(format!("Unsupported expression {:?}", other)),
+        }
+    }
+    validate_block(module, &function.body)
+}
+
+/// Checks that the interpreter supports everything the compute entry point may run.
+///
+/// The module itself is assumed to be valid, e.g. the operands of every
+/// operation have matching types.
+pub(crate) fn validate(module: &Module, entry_point: usize) -> Result<(), String> {
+    let function = &module.entry_points[entry_point].function;
+    for argument in function.arguments.iter() {
+        validate_input(module, argument.ty, argument.binding.as_ref())?;
+    }
+    validate_function(module, function)
+}
+
+/// Runs the compute entry point of the module over the given number of workgroups.
+pub(crate) fn dispatch(
+    module: &Arc<Module>,
+    entry_point: usize,
+    resources: &Resources,
+    count: [u32; 3],
+) {
+    let ep = &module.entry_points[entry_point];
+    let workgroup_size = ep.workgroup_size;
+    let threaded =
+        workgroup_size.iter().product::<u32>() > 1 && uses_barriers(module, &ep.function.body);
+
+    let shared = Invocation {
+        module,
+        globals: vec![None; module.global_variables.len()],
+        barrier: None,
+    };
+    let mut globals = module
+        .global_variables
+        .iter()
+        .map(|(_, var)| match var.class {
+            StorageClass::Uniform | StorageClass::Storage => {
+                let br = var.binding.as_ref()?;
+                let range = resources.buffers.get(&(br.group, br.binding))?;
+                Some(Pointer {
+                    memory: range.memory.clone(),
+                    offset: 0,
+                    size: range.size,
+                    ty: shared.ty(var.ty),
+                })
+            }
+            StorageClass::PushConstant => {
+                let size = resources.push_constants.len() as u64 * 4;
+                let memory = Binding::zeroed(size);
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        resources.push_constants.as_ptr() as *const u8,
+                        memory.ptr(0, size),
+                        size as usize,
+                    );
+                }
+                Some(Pointer {
+                    memory,
+                    offset: 0,
+                    size,
+                    ty: shared.ty(var.ty),
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for z in 0..count[2] {
+        for y in 0..count[1] {
+            for x in 0..count[0] {
+                for (handle, var) in module.global_variables.iter() {
+                    if var.class == StorageClass::WorkGroup {
+                        globals[handle.index()] = Some(shared.allocate(var.ty, var.init));
+                    }
+                }
+
+                let workgroup = [x, y, z];
+                let invocations = (0..workgroup_size[2]).flat_map(move |lz| {
+                    (0..workgroup_size[1])
+                        .flat_map(move |ly| (0..workgroup_size[0]).map(move |lx| [lx, ly, lz]))
+                });
+                let ids = invocations.enumerate().map(|(index, local)| InvocationIds {
+                    global: [
+                        workgroup[0] * workgroup_size[0] + local[0],
+                        workgroup[1] * workgroup_size[1] + local[1],
+                        workgroup[2] * workgroup_size[2] + local[2],
+                    ],
+                    local,
+                    local_index: index as u32,
+                    workgroup,
+                    num_workgroups: count,
+                    workgroup_size,
+                });
+
+                if threaded {
+                    let barrier =
+                        Arc::new(Barrier::new(workgroup_size.iter().product::<u32>() as usize));
+                    let threads = ids
+                        .map(|ids| {
+                            let module = Arc::clone(module);
+                            let globals = globals.clone();
+                            let barrier = Arc::clone(&barrier);
+                            thread::spawn(move || {
+                                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                                    Invocation {
+                                        module: &module,
+                                        globals,
+                                        barrier: Some(&barrier),
+                                    }
+                                    .run(entry_point, &ids)
+                                }));
+                                if let Err(payload) = result {
+                                    // Release the invocations waiting for this one
+                                    barrier.break_all();
+                                    panic::resume_unwind(payload);
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    for thread in threads {
+                        thread.join().expect("Shader invocation panicked");
+                    }
+                } else {
+                    for ids in ids {
+                        Invocation {
+                            module,
+                            globals: globals.clone(),
+                            barrier: None,
+                        }
+                        .run(entry_point, &ids);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile};
+    use hal::{
+        buffer,
+        command::{CommandBuffer as _, CommandBufferFlags, Level},
+        device::{Device as _, DeviceLost, WaitError},
+        memory::{AllocationFlags, Segment, SparseFlags},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso::{self, DescriptorPool as _},
+        queue::{Queue as _, QueueFamily as _},
+        Instance as _, MemoryTypeId,
+    };
+    use std::{iter, ptr, slice};
+
+    #[test]
+    #[allow(clippy::let_unit_value)]
+    fn dispatch_with_barriers() {
+        let spirv = include_bytes!("../data/reverse.comp.spv")
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+
+        let instance = Instance::create("test", 1).unwrap();
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let shader = device.create_shader_module(&spirv).unwrap();
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    iter::once(pso::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Storage { read_only: false },
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: pso::ShaderStageFlags::COMPUTE,
+                        immutable_samplers: false,
+                    }),
+                    iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
+                )
+                .unwrap();
+            let layout = device
+                .create_pipeline_layout(
+                    iter::once(&set_layout),
+                    iter::once((pso::ShaderStageFlags::COMPUTE, 0..4)),
+                )
+                .unwrap();
+            let pipeline = device
+                .create_compute_pipeline(
+                    &pso::ComputePipelineDesc::new(
+                        pso::EntryPoint {
+                            entry: "main",
+                            module: &shader,
+                            specialization: pso::Specialization::default(),
+                        },
+                        &layout,
+                    ),
+                    None,
+                )
+                .unwrap();
+
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 32, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(32, buffer::Usage::STORAGE, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            let values = (0..8u32).collect::<Vec<_>>();
+            let mapping = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            ptr::copy_nonoverlapping(values.as_ptr() as *const u8, mapping, 32);
+
+            let mut desc_pool = device
+                .create_descriptor_pool(1, iter::empty(), pso::DescriptorPoolCreateFlags::empty())
+                .unwrap();
+            let mut set = desc_pool.allocate_one(&set_layout).unwrap();
+            device.write_descriptor_set(pso::DescriptorSetWrite {
+                set: &mut set,
+                binding: 0,
+                array_offset: 0,
+                descriptors: iter::once(pso::Descriptor::Buffer(&buffer, buffer::SubRange::WHOLE)),
+            });
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.bind_compute_pipeline(&pipeline);
+            cmd_buf.bind_compute_descriptor_sets(&layout, 0, iter::once(&set), iter::empty());
+            cmd_buf.push_compute_constants(&layout, 0, &[2]);
+            cmd_buf.dispatch([2, 1, 1]);
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+            queue.wait_idle().unwrap();
+
+            let data = slice::from_raw_parts(mapping as *const u32, 8);
+            assert_eq!(data, [6, 4, 2, 0, 14, 12, 10, 8]);
+            device.unmap_memory(&mut memory);
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            desc_pool.free(iter::once(set));
+            device.destroy_descriptor_pool(desc_pool);
+            device.destroy_compute_pipeline(pipeline);
+            device.destroy_pipeline_layout(layout);
+            device.destroy_descriptor_set_layout(set_layout);
+            device.destroy_shader_module(shader);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn dispatch_with_push_descriptor() {
+        let spirv = include_bytes!("../data/reverse.comp.spv")
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+
+        let profile = Profile::default().with_features(hal::Features::PUSH_DESCRIPTOR);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::PUSH_DESCRIPTOR);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let shader = device.create_shader_module(&spirv).unwrap();
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    iter::once(pso::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Storage { read_only: false },
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: pso::ShaderStageFlags::COMPUTE,
+                        immutable_samplers: false,
+                    }),
+                    iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR,
+                )
+                .unwrap();
+            let layout = device
+                .create_pipeline_layout(
+                    iter::once(&set_layout),
+                    iter::once((pso::ShaderStageFlags::COMPUTE, 0..4)),
+                )
+                .unwrap();
+            let pipeline = device
+                .create_compute_pipeline(
+                    &pso::ComputePipelineDesc::new(
+                        pso::EntryPoint {
+                            entry: "main",
+                            module: &shader,
+                            specialization: pso::Specialization::default(),
+                        },
+                        &layout,
+                    ),
+                    None,
+                )
+                .unwrap();
+
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 32, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(32, buffer::Usage::STORAGE, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            let values = (0..8u32).collect::<Vec<_>>();
+            let mapping = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            ptr::copy_nonoverlapping(values.as_ptr() as *const u8, mapping, 32);
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.bind_compute_pipeline(&pipeline);
+            cmd_buf.push_compute_descriptor_set(
+                &layout,
+                0,
+                0,
+                0,
+                iter::once(pso::Descriptor::Buffer(&buffer, buffer::SubRange::WHOLE)),
+            );
+            cmd_buf.push_compute_constants(&layout, 0, &[2]);
+            cmd_buf.dispatch([2, 1, 1]);
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+            queue.wait_idle().unwrap();
+
+            let data = slice::from_raw_parts(mapping as *const u32, 8);
+            assert_eq!(data, [6, 4, 2, 0, 14, 12, 10, 8]);
+            device.unmap_memory(&mut memory);
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_compute_pipeline(pipeline);
+            device.destroy_pipeline_layout(layout);
+            device.destroy_descriptor_set_layout(set_layout);
+            device.destroy_shader_module(shader);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn conditional_dispatch() {
+        let spirv = include_bytes!("../data/reverse.comp.spv")
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+
+        let features = hal::Features::PUSH_DESCRIPTOR | hal::Features::CONDITIONAL_RENDERING;
+        let profile = Profile::default().with_features(features);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, features);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let shader = device.create_shader_module(&spirv).unwrap();
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    iter::once(pso::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Storage { read_only: false },
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: pso::ShaderStageFlags::COMPUTE,
+                        immutable_samplers: false,
+                    }),
+                    iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR,
+                )
+                .unwrap();
+            let layout = device
+                .create_pipeline_layout(
+                    iter::once(&set_layout),
+                    iter::once((pso::ShaderStageFlags::COMPUTE, 0..4)),
+                )
+                .unwrap();
+            let pipeline = device
+                .create_compute_pipeline(
+                    &pso::ComputePipelineDesc::new(
+                        pso::EntryPoint {
+                            entry: "main",
+                            module: &shader,
+                            specialization: pso::Specialization::default(),
+                        },
+                        &layout,
+                    ),
+                    None,
+                )
+                .unwrap();
+
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 36, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(32, buffer::Usage::STORAGE, SparseFlags::empty())
+                .unwrap();
+            let mut predicate = device
+                .create_buffer(
+                    4,
+                    buffer::Usage::CONDITIONAL_RENDERING,
+                    SparseFlags::empty(),
+                )
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            device
+                .bind_buffer_memory(&memory, 32, &mut predicate)
+                .unwrap();
+            let values = (0..8u32).chain(iter::once(0)).collect::<Vec<_>>();
+            let mapping = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            ptr::copy_nonoverlapping(values.as_ptr() as *const u8, mapping, 36);
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.bind_compute_pipeline(&pipeline);
+            cmd_buf.push_compute_descriptor_set(
+                &layout,
+                0,
+                0,
+                0,
+                iter::once(pso::Descriptor::Buffer(&buffer, buffer::SubRange::WHOLE)),
+            );
+            cmd_buf.push_compute_constants(&layout, 0, &[2]);
+            // Skipped, since the predicate is zero
+            cmd_buf.begin_conditional_rendering(&predicate, 0, false);
+            cmd_buf.dispatch([2, 1, 1]);
+            cmd_buf.end_conditional_rendering();
+            // Only runs the first workgroup
+            cmd_buf.begin_conditional_rendering(&predicate, 0, true);
+            cmd_buf.dispatch([1, 1, 1]);
+            cmd_buf.end_conditional_rendering();
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+            queue.wait_idle().unwrap();
+
+            let data = slice::from_raw_parts(mapping as *const u32, 8);
+            assert_eq!(data, [6, 4, 2, 0, 4, 5, 6, 7]);
+            device.unmap_memory(&mut memory);
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_compute_pipeline(pipeline);
+            device.destroy_pipeline_layout(layout);
+            device.destroy_descriptor_set_layout(set_layout);
+            device.destroy_shader_module(shader);
+            device.destroy_buffer(predicate);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn unsupported_shaders_fail_the_pipeline_creation() {
+        let spirv = include_bytes!("../data/reflect.comp.spv")
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+
+        let instance = Instance::create("test", 1).unwrap();
+        let (_adapter, gpu) = crate::open_device(&instance, hal::Features::empty());
+        let device = &gpu.device;
+
+        unsafe {
+            let shader = device.create_shader_module(&spirv).unwrap();
+            let layout = device
+                .create_pipeline_layout(iter::empty(), iter::empty())
+                .unwrap();
+            let result = device.create_compute_pipeline(
+                &pso::ComputePipelineDesc::new(
+                    pso::EntryPoint {
+                        entry: "main",
+                        module: &shader,
+                        specialization: pso::Specialization::default(),
+                    },
+                    &layout,
+                ),
+                None,
+            );
+            match result {
+                Err(pso::CreationError::ShaderCreationError(pso::ShaderStageFlags::COMPUTE, e)) => {
+                    assert!(e.contains("Reflect"), "{}", e)
+                }
+                other => panic!("Unexpected pipeline creation result {:?}", other),
+            }
+
+            device.destroy_pipeline_layout(layout);
+            device.destroy_shader_module(shader);
+        }
+    }
+
+    #[test]
+    fn panicking_invocations_release_the_workgroup() {
+        let spirv = include_bytes!("../data/reverse.comp.spv")
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+
+        let instance = Instance::create("test", 1).unwrap();
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let shader = device.create_shader_module(&spirv).unwrap();
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    iter::once(pso::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Storage { read_only: false },
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: pso::ShaderStageFlags::COMPUTE,
+                        immutable_samplers: false,
+                    }),
+                    iter::empty(),
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
+                )
+                .unwrap();
+            let layout = device
+                .create_pipeline_layout(
+                    iter::once(&set_layout),
+                    iter::once((pso::ShaderStageFlags::COMPUTE, 0..4)),
+                )
+                .unwrap();
+            let pipeline = device
+                .create_compute_pipeline(
+                    &pso::ComputePipelineDesc::new(
+                        pso::EntryPoint {
+                            entry: "main",
+                            module: &shader,
+                            specialization: pso::Specialization::default(),
+                        },
+                        &layout,
+                    ),
+                    None,
+                )
+                .unwrap();
+
+            // Only holds half of the workgroup values, so the last invocations
+            // panic while the first ones wait at the barrier
+            let memory = device
+                .allocate_memory(MemoryTypeId(0), 8, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(8, buffer::Usage::STORAGE, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+
+            let mut desc_pool = device
+                .create_descriptor_pool(1, iter::empty(), pso::DescriptorPoolCreateFlags::empty())
+                .unwrap();
+            let mut set = desc_pool.allocate_one(&set_layout).unwrap();
+            device.write_descriptor_set(pso::DescriptorSetWrite {
+                set: &mut set,
+                binding: 0,
+                array_offset: 0,
+                descriptors: iter::once(pso::Descriptor::Buffer(&buffer, buffer::SubRange::WHOLE)),
+            });
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.bind_compute_pipeline(&pipeline);
+            cmd_buf.bind_compute_descriptor_sets(&layout, 0, iter::once(&set), iter::empty());
+            cmd_buf.push_compute_constants(&layout, 0, &[2]);
+            cmd_buf.dispatch([1, 1, 1]);
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(
+                device.wait_for_fence(&fence, !0),
+                Err(WaitError::DeviceLost(DeviceLost))
+            );
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            desc_pool.free(iter::once(set));
+            device.destroy_descriptor_pool(desc_pool);
+            device.destroy_compute_pipeline(pipeline);
+            device.destroy_pipeline_layout(layout);
+            device.destroy_descriptor_set_layout(set_layout);
+            device.destroy_shader_module(shader);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+}
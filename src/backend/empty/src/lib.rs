@@ -5,20 +5,53 @@ extern crate gfx_hal as hal;
 
 use crate::{
     buffer::Buffer,
-    descriptor::{DescriptorPool, DescriptorSet, DescriptorSetLayout},
-    image::Image,
+    commands::{Command, Condition},
+    descriptor::{DescriptorPool, DescriptorSet, DescriptorSetLayout, PipelineLayout},
+    fault::{BreadcrumbTrail, Operation},
+    image::{Image, ImageView},
+    interpreter::{BufferRange, Resources},
     memory::Memory,
+    query_pool::{Clock, Counter, UNSUPPORTED_HOST_CLOCK_FEATURES},
+    shader::{ComputePipeline, ShaderModule},
+    surface::SwapchainImage,
+    sync::{Notifier, SemaphoreOperation, Signal, Submission, Worker},
 };
 
+pub use crate::bvh::{AccelerationStructure, RayHit};
+pub use crate::fault::FaultInjector;
+#[cfg(feature = "profiles")]
+pub use crate::profile::ProfileError;
+pub use crate::profile::{Profile, QueueFamilyProfile};
+pub use crate::query_pool::QueryPool;
+pub use crate::surface::{FrameOutput, Surface};
+pub use crate::sync::{Event, Fence, Semaphore};
+
 use hal::{adapter, command, device, display, format, pass, pool, pso, query, queue, window};
 use log::debug;
 
-use std::{borrow::Borrow, ops::Range};
+use std::{
+    collections::BTreeMap,
+    iter,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 mod buffer;
+mod bvh;
+mod commands;
 mod descriptor;
+pub mod fault;
 mod image;
+mod interpreter;
 mod memory;
+mod profile;
+mod query_pool;
+mod shader;
+mod surface;
+mod sync;
 
 const NOT_SUPPORTED_MESSAGE: &str = "This function is not currently mocked by the empty backend";
 
@@ -38,110 +71,159 @@ impl hal::Backend for Backend {
     type Memory = Memory;
     type CommandPool = CommandPool;
 
-    type ShaderModule = ();
+    type ShaderModule = ShaderModule;
     type RenderPass = ();
     type Framebuffer = ();
 
     type Buffer = Buffer;
     type BufferView = ();
     type Image = Image;
-    type ImageView = ();
+    type ImageView = ImageView;
     type Sampler = ();
 
-    type ComputePipeline = ();
+    type ComputePipeline = ComputePipeline;
     type GraphicsPipeline = ();
     type PipelineCache = ();
-    type PipelineLayout = ();
+    type PipelineLayout = PipelineLayout;
     type DescriptorSetLayout = DescriptorSetLayout;
+    type DescriptorUpdateTemplate = pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = DescriptorPool;
     type DescriptorSet = DescriptorSet;
 
-    type Fence = ();
-    type Semaphore = ();
-    type Event = ();
-    type QueryPool = ();
+    type Fence = Fence;
+    type Semaphore = Semaphore;
+    type Event = Event;
+    type QueryPool = QueryPool;
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = AccelerationStructure;
 }
 
-/// Dummy physical device.
+/// Physical device reporting the capabilities of its profile.
 #[derive(Debug)]
-pub struct PhysicalDevice;
+pub struct PhysicalDevice {
+    profile: Arc<Profile>,
+    faults: FaultInjector,
+}
 impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     unsafe fn open(
         &self,
         families: &[(&QueueFamily, &[queue::QueuePriority])],
-        _requested_features: hal::Features,
+        requested_features: hal::Features,
     ) -> Result<adapter::Gpu<Backend>, device::CreationError> {
         // Validate the arguments
-        assert_eq!(
-            families.len(),
-            1,
-            "Empty backend doesn't have multiple queue families"
-        );
-        let (_family, priorities) = families[0];
-        assert_eq!(
-            priorities.len(),
-            1,
-            "Empty backend doesn't support multiple queues"
-        );
-        let priority = priorities[0];
-        assert!(
-            0.0 <= priority && priority <= 1.0,
-            "Queue priority is out of range"
-        );
-
-        // Create the queues
-        let queue_groups = {
-            let mut queue_group = queue::QueueGroup::new(QUEUE_FAMILY_ID);
-            queue_group.add_queue(Queue);
-            vec![queue_group]
+        if !(self.profile.features - UNSUPPORTED_HOST_CLOCK_FEATURES).contains(requested_features) {
+            return Err(device::CreationError::MissingFeature);
+        }
+        let notifier = Arc::new(Notifier::default());
+        self.faults.register(&notifier);
+        let clock = Clock::new(self.profile.timestamp_period);
+        let breadcrumbs = if requested_features.contains(hal::Features::DEVICE_FAULT_DIAGNOSTICS) {
+            Some(Arc::new(BreadcrumbTrail::default()))
+        } else {
+            None
         };
+        let mut queue_groups = Vec::with_capacity(families.len());
+        for &(family, priorities) in families {
+            assert!(
+                queue_groups
+                    .iter()
+                    .all(|group: &queue::QueueGroup<Backend>| group.family != family.id),
+                "Queue family {:?} is requested more than once",
+                family.id
+            );
+            assert!(
+                !priorities.is_empty() && priorities.len() <= family.profile.max_queues,
+                "Queue family {:?} doesn't support {} queues",
+                family.id,
+                priorities.len()
+            );
+            for &priority in priorities {
+                assert!(
+                    0.0 <= priority && priority <= 1.0,
+                    "Queue priority is out of range"
+                );
+            }
+
+            // Create the queues
+            let mut queue_group = queue::QueueGroup::new(family.id);
+            for index in 0..priorities.len() {
+                queue_group.add_queue(Queue {
+                    worker: Worker::spawn(
+                        format!("queue {}:{}", family.id.0, index),
+                        &notifier,
+                        self.faults.clone(),
+                        breadcrumbs.clone(),
+                    ),
+                    clock,
+                    faults: self.faults.clone(),
+                });
+            }
+            queue_groups.push(queue_group);
+        }
+
         let gpu = adapter::Gpu {
-            device: Device,
+            device: Device {
+                profile: Arc::clone(&self.profile),
+                notifier,
+                clock,
+                occlusion_samples: Arc::new(AtomicU64::new(0)),
+                acceleration_structures: Arc::default(),
+                heap_usage: self
+                    .profile
+                    .memory_properties
+                    .memory_heaps
+                    .iter()
+                    .map(|_| AtomicU64::new(0))
+                    .collect(),
+                breadcrumbs,
+                profiling_lock: AtomicBool::new(false),
+                faults: self.faults.clone(),
+            },
             queue_groups,
         };
         Ok(gpu)
     }
 
-    fn format_properties(&self, _: Option<format::Format>) -> format::Properties {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        match format {
+            Some(format) => {
+                let mut properties = self.profile.format_properties(format).clone();
+                // Attachments get cleared on the host, which doesn't encode every format
+                if !image::can_clear(format) {
+                    let attachment = format::ImageFeature::COLOR_ATTACHMENT
+                        | format::ImageFeature::COLOR_ATTACHMENT_BLEND
+                        | format::ImageFeature::DEPTH_STENCIL_ATTACHMENT;
+                    properties.linear_tiling.remove(attachment);
+                    properties.optimal_tiling.remove(attachment);
+                }
+                properties
+            }
+            None => format::Properties::default(),
+        }
     }
 
     fn image_format_properties(
         &self,
-        _: format::Format,
-        _dim: u8,
-        _: hal::image::Tiling,
-        _: hal::image::Usage,
-        _: hal::image::ViewCapabilities,
+        format: format::Format,
+        dimensions: u8,
+        tiling: hal::image::Tiling,
+        usage: hal::image::Usage,
+        view_caps: hal::image::ViewCapabilities,
     ) -> Option<hal::image::FormatProperties> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        let attachment =
+            hal::image::Usage::COLOR_ATTACHMENT | hal::image::Usage::DEPTH_STENCIL_ATTACHMENT;
+        if usage.intersects(attachment) && !image::can_clear(format) {
+            return None;
+        }
+        self.profile
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
     }
 
     fn memory_properties(&self) -> adapter::MemoryProperties {
-        let memory_types = {
-            use hal::memory::Properties;
-            let properties = Properties::DEVICE_LOCAL
-                | Properties::CPU_VISIBLE
-                | Properties::COHERENT
-                | Properties::CPU_CACHED;
-            let memory_type = adapter::MemoryType {
-                properties,
-                heap_index: 0,
-            };
-            vec![memory_type]
-        };
-        // TODO: perhaps get an estimate of free RAM to report here?
-        let memory_heaps = vec![adapter::MemoryHeap {
-            size: 64 * 1024,
-            flags: hal::memory::HeapFlags::empty(),
-        }];
-        adapter::MemoryProperties {
-            memory_types,
-            memory_heaps,
-        }
+        self.profile.memory_properties.clone()
     }
 
     fn external_buffer_properties(
@@ -169,17 +251,28 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     }
 
     fn features(&self) -> hal::Features {
-        hal::Features::empty()
+        self.profile.features - UNSUPPORTED_HOST_CLOCK_FEATURES
     }
 
     fn properties(&self) -> hal::PhysicalDeviceProperties {
-        hal::PhysicalDeviceProperties {
-            limits: hal::Limits {
-                non_coherent_atom_size: 1,
-                optimal_buffer_copy_pitch_alignment: 1,
-                ..Default::default()
-            },
-            ..Default::default()
+        self.profile.properties
+    }
+
+    fn performance_counters(
+        &self,
+        _family: queue::QueueFamilyId,
+    ) -> Vec<query::PerformanceCounter> {
+        if self
+            .profile
+            .features
+            .contains(hal::Features::PERFORMANCE_QUERY)
+        {
+            Counter::ALL
+                .iter()
+                .map(|counter| counter.describe())
+                .collect()
+        } else {
+            Vec::new()
         }
     }
 
@@ -212,44 +305,169 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     }
 }
 
-/// Dummy command queue doing nothing.
+/// Command queue executing the recorded commands on the CPU, in order,
+/// on a worker thread.
 #[derive(Debug)]
-pub struct Queue;
+pub struct Queue {
+    worker: Worker,
+    clock: Clock,
+    faults: FaultInjector,
+}
+impl Queue {
+    fn submit_operations<'a, Ic>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Vec<SemaphoreOperation>,
+        signal_semaphores: Vec<SemaphoreOperation>,
+        fence: Option<&mut Fence>,
+    ) where
+        Ic: Iterator<Item = &'a CommandBuffer>,
+    {
+        if self
+            .faults
+            .check::<device::DeviceLost>(Operation::Submit)
+            .is_err()
+        {
+            // The work of a lost device never completes, the fault woke up
+            // the host waits on the fence and semaphores to report the loss
+            return;
+        }
+        self.worker.submit(Submission {
+            command_buffers: command_buffers
+                .map(|cmd_buf| Arc::clone(&cmd_buf.commands))
+                .collect(),
+            wait_semaphores,
+            signal_semaphores,
+            fence: fence.map(|fence| Arc::clone(&fence.signal)),
+        });
+    }
+}
+
 impl queue::Queue<Backend> for Queue {
-    unsafe fn submit<'a, Ic, Iw, Is>(&mut self, _: Ic, _: Iw, _: Is, _: Option<&mut ()>)
-    where
+    unsafe fn submit<'a, Ic, Iw, Is>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut Fence>,
+    ) where
         Ic: Iterator<Item = &'a CommandBuffer>,
+        Iw: Iterator<Item = (&'a Semaphore, pso::PipelineStage)>,
+        Is: Iterator<Item = &'a Semaphore>,
     {
+        self.submit_operations(
+            command_buffers,
+            wait_semaphores
+                .map(|(semaphore, _)| semaphore.operation(None))
+                .collect(),
+            signal_semaphores
+                .map(|semaphore| semaphore.operation(None))
+                .collect(),
+            fence,
+        );
     }
 
-    unsafe fn present(
+    unsafe fn submit_timeline<'a, Ic, Iw, Is>(
         &mut self,
-        _surface: &mut Surface,
-        _image: SwapchainImage,
-        _wait_semaphore: Option<&mut ()>,
-    ) -> Result<Option<window::Suboptimal>, window::PresentError> {
-        Ok(None)
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut Fence>,
+    ) where
+        Ic: Iterator<Item = &'a CommandBuffer>,
+        Iw: Iterator<Item = (&'a Semaphore, u64, pso::PipelineStage)>,
+        Is: Iterator<Item = (&'a Semaphore, u64)>,
+    {
+        self.submit_operations(
+            command_buffers,
+            wait_semaphores
+                .map(|(semaphore, value, _)| semaphore.operation(Some(value)))
+                .collect(),
+            signal_semaphores
+                .map(|(semaphore, value)| semaphore.operation(Some(value)))
+                .collect(),
+            fence,
+        );
     }
 
-    fn wait_idle(&mut self) -> Result<(), device::OutOfMemory> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn present(
+        &mut self,
+        surface: &mut Surface,
+        image: SwapchainImage,
+        wait_semaphore: Option<&mut Semaphore>,
+    ) -> Result<Option<window::Suboptimal>, window::PresentError> {
+        // The semaphore and the submitted work of a lost device never complete
+        if let Some(semaphore) = wait_semaphore {
+            semaphore.operation(None).wait(&self.faults)?;
+        }
+        // Presentation is queued after the submitted work
+        self.worker.wait_idle(&self.faults)?;
+        let suboptimal = self
+            .faults
+            .check_swapchain::<window::PresentError>(Operation::Present)?;
+        surface.present(image);
+        Ok(suboptimal)
+    }
+
+    fn wait_idle(&mut self) -> Result<(), device::WaitError> {
+        self.faults
+            .check::<device::WaitError>(Operation::QueueWaitIdle)?;
+        self.worker.wait_idle(&self.faults)?;
+        Ok(())
     }
 
     fn timestamp_period(&self) -> f32 {
-        1.0
+        self.clock.period()
     }
 }
 
-/// Dummy device doing nothing.
+/// Device creating resources in host memory.
 #[derive(Debug)]
-pub struct Device;
+pub struct Device {
+    profile: Arc<Profile>,
+    /// Notifier shared with the queues and the synchronization primitives.
+    notifier: Arc<Notifier>,
+    /// Clock of the timestamp queries.
+    clock: Clock,
+    /// Samples counted by the occlusion queries.
+    occlusion_samples: Arc<AtomicU64>,
+    /// Acceleration structures, found by address when building top level ones.
+    acceleration_structures: Arc<bvh::Registry>,
+    /// Bytes allocated from every memory heap.
+    heap_usage: Vec<AtomicU64>,
+    /// Breadcrumbs executed by the queues, if fault diagnostics are enabled.
+    breadcrumbs: Option<Arc<BreadcrumbTrail>>,
+    /// Whether the profiling lock is held.
+    profiling_lock: AtomicBool,
+    faults: FaultInjector,
+}
+
+impl Device {
+    /// Returns the fault injector shared with the instance.
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+
+    /// Sets the number of samples reported by the occlusion queries ending from now on.
+    pub fn set_occlusion_samples(&self, samples: u64) {
+        self.occlusion_samples.store(samples, Ordering::Release);
+    }
+
+    fn heap_index(&self, memory_type: hal::MemoryTypeId) -> usize {
+        self.profile.memory_properties.memory_types[memory_type.0].heap_index
+    }
+}
 impl device::Device<Backend> for Device {
     unsafe fn create_command_pool(
         &self,
         _: queue::QueueFamilyId,
         _: pool::CommandPoolCreateFlags,
     ) -> Result<CommandPool, device::OutOfMemory> {
-        Ok(CommandPool)
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateCommandPool)?;
+        Ok(CommandPool {
+            breadcrumbs: self.breadcrumbs.is_some(),
+        })
     }
 
     unsafe fn destroy_command_pool(&self, _: CommandPool) {}
@@ -258,8 +476,18 @@ impl device::Device<Backend> for Device {
         &self,
         memory_type: hal::MemoryTypeId,
         size: u64,
+        _: hal::memory::AllocationFlags,
     ) -> Result<Memory, device::AllocationError> {
-        Memory::allocate(memory_type, size)
+        assert!(
+            memory_type.0 < self.profile.memory_properties.memory_types.len(),
+            "Memory type {:?} is out of range",
+            memory_type
+        );
+        self.faults
+            .check::<device::AllocationError>(Operation::AllocateMemory)?;
+        let memory = Memory::allocate(memory_type, size)?;
+        self.heap_usage[self.heap_index(memory_type)].fetch_add(size, Ordering::Relaxed);
+        Ok(memory)
     }
 
     unsafe fn create_render_pass<'a, Ia, Is, Id>(
@@ -271,18 +499,32 @@ impl device::Device<Backend> for Device {
     where
         Is: Iterator<Item = pass::SubpassDesc<'a>>,
     {
-        Ok(())
+        self.faults.check(Operation::CreateRenderPass)
     }
 
     unsafe fn create_pipeline_layout<'a, Is, Ic>(
         &self,
-        _: Is,
+        set_layouts: Is,
         _: Ic,
-    ) -> Result<(), device::OutOfMemory>
+    ) -> Result<PipelineLayout, device::OutOfMemory>
     where
         Is: Iterator<Item = &'a DescriptorSetLayout>,
     {
-        Ok(())
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreatePipelineLayout)?;
+        let push_sets = set_layouts
+            .map(|layout| {
+                if layout
+                    .flags
+                    .contains(pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR)
+                {
+                    Some(layout.bindings.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(PipelineLayout { push_sets })
     }
 
     unsafe fn create_pipeline_cache(
@@ -305,15 +547,17 @@ impl device::Device<Backend> for Device {
         _: &pso::GraphicsPipelineDesc<'a, Backend>,
         _: Option<&()>,
     ) -> Result<(), pso::CreationError> {
-        Ok(())
+        self.faults.check(Operation::CreateGraphicsPipeline)
     }
 
     unsafe fn create_compute_pipeline<'a>(
         &self,
-        _: &pso::ComputePipelineDesc<'a, Backend>,
+        desc: &pso::ComputePipelineDesc<'a, Backend>,
         _: Option<&()>,
-    ) -> Result<(), pso::CreationError> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    ) -> Result<ComputePipeline, pso::CreationError> {
+        self.faults
+            .check::<pso::CreationError>(Operation::CreateComputePipeline)?;
+        ComputePipeline::new(&desc.shader)
     }
 
     unsafe fn merge_pipeline_caches<'a, I>(
@@ -333,18 +577,66 @@ impl device::Device<Backend> for Device {
         _: I,
         _: hal::image::Extent,
     ) -> Result<(), device::OutOfMemory> {
-        Ok(())
+        self.faults.check(Operation::CreateFramebuffer)
     }
 
-    unsafe fn create_shader_module(&self, _: &[u32]) -> Result<(), device::ShaderError> {
-        Ok(())
+    unsafe fn create_shader_module(
+        &self,
+        raw_data: &[u32],
+    ) -> Result<ShaderModule, device::ShaderError> {
+        Ok(ShaderModule::from_spirv(raw_data))
+    }
+
+    unsafe fn create_shader_module_from_naga(
+        &self,
+        shader: device::NagaShader,
+    ) -> Result<ShaderModule, (device::ShaderError, device::NagaShader)> {
+        Ok(ShaderModule {
+            naga: Ok(Arc::new(shader.module)),
+        })
     }
 
     unsafe fn create_sampler(
         &self,
         _: &hal::image::SamplerDesc,
     ) -> Result<(), device::AllocationError> {
-        Ok(())
+        self.faults.check(Operation::CreateSampler)
+    }
+
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        desc: &hal::acceleration_structure::GeometryDesc<Backend>,
+        max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        bvh::size_requirements(desc, max_primitive_counts)
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        desc: &hal::acceleration_structure::CreateDesc<Backend>,
+    ) -> Result<AccelerationStructure, device::OutOfMemory> {
+        assert_eq!(
+            desc.buffer_offset % 256,
+            0,
+            "Acceleration structure offset must be a multiple of 256"
+        );
+        assert!(
+            desc.buffer_offset + desc.size <= desc.buffer.size,
+            "Acceleration structure doesn't fit into its buffer"
+        );
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateAccelerationStructure)?;
+        Ok(AccelerationStructure::new(
+            &self.acceleration_structures,
+            desc.level,
+        ))
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        accel_struct: &AccelerationStructure,
+    ) -> hal::acceleration_structure::DeviceAddress {
+        accel_struct.address()
     }
 
     unsafe fn create_buffer(
@@ -353,6 +645,8 @@ impl device::Device<Backend> for Device {
         _: hal::buffer::Usage,
         _: hal::memory::SparseFlags,
     ) -> Result<Buffer, hal::buffer::CreationError> {
+        self.faults
+            .check::<hal::buffer::CreationError>(Operation::CreateBuffer)?;
         Ok(Buffer::new(size))
     }
 
@@ -367,13 +661,21 @@ impl device::Device<Backend> for Device {
 
     unsafe fn bind_buffer_memory(
         &self,
-        _memory: &Memory,
-        _: u64,
-        _: &mut Buffer,
+        memory: &Memory,
+        offset: u64,
+        buffer: &mut Buffer,
     ) -> Result<(), device::BindError> {
+        self.faults
+            .check::<device::BindError>(Operation::BindBufferMemory)?;
+        buffer.memory = Some(memory.bind(offset));
         Ok(())
     }
 
+    unsafe fn get_buffer_device_address(&self, buffer: &Buffer) -> hal::buffer::DeviceAddress {
+        // Buffers live in host memory, so their device address is a host pointer
+        buffer.binding().ptr(0, buffer.size) as hal::buffer::DeviceAddress
+    }
+
     unsafe fn create_buffer_view(
         &self,
         _: &Buffer,
@@ -386,14 +688,16 @@ impl device::Device<Backend> for Device {
     unsafe fn create_image(
         &self,
         kind: hal::image::Kind,
-        _: hal::image::Level,
-        _: format::Format,
+        levels: hal::image::Level,
+        format: format::Format,
         _: hal::image::Tiling,
         _: hal::image::Usage,
         _: hal::memory::SparseFlags,
         _: hal::image::ViewCapabilities,
     ) -> Result<Image, hal::image::CreationError> {
-        Ok(Image::new(kind))
+        self.faults
+            .check::<hal::image::CreationError>(Operation::CreateImage)?;
+        Ok(Image::new(kind, levels, format))
     }
 
     unsafe fn get_image_requirements(&self, image: &Image) -> hal::memory::Requirements {
@@ -402,31 +706,41 @@ impl device::Device<Backend> for Device {
 
     unsafe fn get_image_subresource_footprint(
         &self,
-        _: &Image,
-        _: hal::image::Subresource,
+        image: &Image,
+        subresource: hal::image::Subresource,
     ) -> hal::image::SubresourceFootprint {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        image.footprint(subresource)
     }
 
     unsafe fn bind_image_memory(
         &self,
-        _memory: &Memory,
-        _: u64,
-        _: &mut Image,
+        memory: &Memory,
+        offset: u64,
+        image: &mut Image,
     ) -> Result<(), device::BindError> {
+        self.faults
+            .check::<device::BindError>(Operation::BindImageMemory)?;
+        image.memory = Some(memory.bind(offset));
         Ok(())
     }
 
     unsafe fn create_image_view(
         &self,
-        _: &Image,
+        image: &Image,
         _: hal::image::ViewKind,
-        _: format::Format,
+        format: format::Format,
         _: format::Swizzle,
         _: hal::image::Usage,
-        _: hal::image::SubresourceRange,
-    ) -> Result<(), hal::image::ViewCreationError> {
-        Ok(())
+        range: hal::image::SubresourceRange,
+        _: Option<hal::image::YcbcrConversionDesc>,
+    ) -> Result<ImageView, hal::image::ViewCreationError> {
+        self.faults
+            .check::<hal::image::ViewCreationError>(Operation::CreateImageView)?;
+        Ok(ImageView {
+            image: image.clone(),
+            format,
+            range,
+        })
     }
 
     unsafe fn create_descriptor_pool<I>(
@@ -435,78 +749,269 @@ impl device::Device<Backend> for Device {
         _: I,
         _: pso::DescriptorPoolCreateFlags,
     ) -> Result<DescriptorPool, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateDescriptorPool)?;
         Ok(DescriptorPool)
     }
 
     unsafe fn create_descriptor_set_layout<'a, I, J>(
         &self,
-        _bindings: I,
+        bindings: I,
         _samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<DescriptorSetLayout, device::OutOfMemory>
     where
+        I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
         J: Iterator<Item = &'a ()>,
     {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateDescriptorSetLayout)?;
+        let mut bindings = bindings.collect::<Vec<_>>();
+        bindings.sort_by_key(|b| b.binding);
         let layout = DescriptorSetLayout {
             name: String::new(),
+            bindings,
+            flags,
         };
         Ok(layout)
     }
 
-    unsafe fn write_descriptor_set<'a, I>(&self, _: pso::DescriptorSetWrite<'a, Backend, I>)
+    unsafe fn write_descriptor_set<'a, I>(&self, op: pso::DescriptorSetWrite<'a, Backend, I>)
     where
         I: Iterator<Item = pso::Descriptor<'a, Backend>>,
     {
+        op.set.write(op.binding, op.array_offset, op.descriptors);
     }
 
     unsafe fn copy_descriptor_set<'a>(&self, _: pso::DescriptorSetCopy<'a, Backend>) {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    fn create_semaphore(&self) -> Result<(), device::OutOfMemory> {
-        Ok(())
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _: &DescriptorSetLayout,
+        entries: I,
+    ) -> Result<pso::EmulatedDescriptorUpdateTemplate, device::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateDescriptorUpdateTemplate)?;
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
     }
 
-    fn create_fence(&self, _: bool) -> Result<(), device::OutOfMemory> {
-        Ok(())
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut DescriptorSet,
+        template: &pso::EmulatedDescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
     }
 
-    unsafe fn get_fence_status(&self, _: &()) -> Result<bool, device::DeviceLost> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    fn create_semaphore(&self) -> Result<Semaphore, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateSemaphore)?;
+        Ok(Semaphore {
+            signal: Signal::new(&self.notifier, false),
+            timeline: false,
+        })
     }
 
-    fn create_event(&self) -> Result<(), device::OutOfMemory> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: u64,
+    ) -> Result<Semaphore, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateSemaphore)?;
+        Ok(Semaphore {
+            signal: Signal::with_value(&self.notifier, initial_value),
+            timeline: true,
+        })
     }
 
-    unsafe fn get_event_status(&self, _: &()) -> Result<bool, device::WaitError> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        semaphore: &Semaphore,
+    ) -> Result<u64, device::DeviceLost> {
+        self.faults
+            .check::<device::DeviceLost>(Operation::GetSemaphoreCounterValue)?;
+        Ok(semaphore.signal.value())
     }
 
-    unsafe fn set_event(&self, _: &mut ()) -> Result<(), device::OutOfMemory> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        semaphores: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::WaitError>
+    where
+        I: Iterator<Item = (&'a Semaphore, u64)>,
+    {
+        self.faults
+            .check::<device::WaitError>(Operation::WaitSemaphores)?;
+        let targets = semaphores
+            .map(|(semaphore, value)| (&semaphore.signal, value))
+            .collect::<Vec<_>>();
+        // A queue losing the device interrupts the wait
+        let ready = self
+            .notifier
+            .wait_until_or_lost(&self.faults, timeout_ns, || match wait {
+                device::WaitFor::Any => targets
+                    .iter()
+                    .any(|&(signal, value)| signal.value() >= value),
+                device::WaitFor::All => targets
+                    .iter()
+                    .all(|&(signal, value)| signal.value() >= value),
+            })?;
+        Ok(ready)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &Semaphore,
+        value: u64,
+    ) -> Result<(), device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::SignalSemaphore)?;
+        semaphore.signal.advance(value);
+        Ok(())
     }
 
-    unsafe fn reset_event(&self, _: &mut ()) -> Result<(), device::OutOfMemory> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    fn create_fence(&self, signaled: bool) -> Result<Fence, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateFence)?;
+        Ok(Fence {
+            signal: Signal::new(&self.notifier, signaled),
+        })
     }
 
-    unsafe fn create_query_pool(&self, _: query::Type, _: u32) -> Result<(), query::CreationError> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn get_fence_status(&self, fence: &Fence) -> Result<bool, device::DeviceLost> {
+        self.faults
+            .check::<device::DeviceLost>(Operation::GetFenceStatus)?;
+        Ok(fence.signal.is_set())
     }
 
-    unsafe fn destroy_query_pool(&self, _: ()) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    fn create_event(&self) -> Result<Event, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateEvent)?;
+        Ok(Event {
+            signal: Signal::new(&self.notifier, false),
+        })
+    }
+
+    unsafe fn get_event_status(&self, event: &Event) -> Result<bool, device::WaitError> {
+        self.faults
+            .check::<device::WaitError>(Operation::GetEventStatus)?;
+        Ok(event.signal.is_set())
+    }
+
+    unsafe fn set_event(&self, event: &mut Event) -> Result<(), device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::SetEvent)?;
+        event.signal.set();
+        Ok(())
+    }
+
+    unsafe fn reset_event(&self, event: &mut Event) -> Result<(), device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::ResetEvent)?;
+        event.signal.reset();
+        Ok(())
     }
 
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<QueryPool, query::CreationError> {
+        match ty {
+            query::Type::Occlusion | query::Type::Timestamp => {}
+            query::Type::PipelineStatistics(_) | query::Type::PerformanceCounters => {
+                return Err(query::CreationError::Unsupported(ty))
+            }
+        }
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateQueryPool)?;
+        Ok(QueryPool::new(
+            ty,
+            count,
+            Vec::new(),
+            &self.notifier,
+            self.clock,
+            &self.occlusion_samples,
+        ))
+    }
+
+    unsafe fn create_performance_query_pool(
+        &self,
+        family: queue::QueueFamilyId,
+        counters: &[u32],
+        count: query::Id,
+    ) -> Result<QueryPool, query::CreationError> {
+        if !self
+            .profile
+            .features
+            .contains(hal::Features::PERFORMANCE_QUERY)
+        {
+            return Err(query::CreationError::Unsupported(
+                query::Type::PerformanceCounters,
+            ));
+        }
+        assert!(
+            family.0 < self.profile.queue_families.len(),
+            "Unknown queue family {:?}",
+            family
+        );
+        assert!(!counters.is_empty(), "No counter to sample");
+        let counters = counters
+            .iter()
+            .map(|&index| {
+                *Counter::ALL
+                    .get(index as usize)
+                    .unwrap_or_else(|| panic!("Unknown performance counter {}", index))
+            })
+            .collect();
+        self.faults
+            .check::<device::OutOfMemory>(Operation::CreateQueryPool)?;
+        Ok(QueryPool::new(
+            query::Type::PerformanceCounters,
+            count,
+            counters,
+            &self.notifier,
+            self.clock,
+            &self.occlusion_samples,
+        ))
+    }
+
+    unsafe fn acquire_profiling_lock(&self, timeout_ns: u64) -> Result<bool, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::AcquireProfilingLock)?;
+        let lock = &self.profiling_lock;
+        Ok(self.notifier.wait_until(timeout_ns, || {
+            lock.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        }))
+    }
+
+    unsafe fn release_profiling_lock(&self) {
+        self.notifier
+            .notify(|| self.profiling_lock.store(false, Ordering::Release));
+    }
+
+    unsafe fn destroy_query_pool(&self, _: QueryPool) {}
+
     unsafe fn get_query_pool_results(
         &self,
-        _: &(),
-        _: Range<query::Id>,
-        _: &mut [u8],
-        _: hal::buffer::Stride,
-        _: query::ResultFlags,
+        pool: &QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: hal::buffer::Stride,
+        flags: query::ResultFlags,
     ) -> Result<bool, device::WaitError> {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        self.faults
+            .check::<device::WaitError>(Operation::GetQueryPoolResults)?;
+        Ok(pool.queries.copy_results(queries, data, stride, flags))
     }
 
     unsafe fn map_memory(
@@ -514,6 +1019,8 @@ impl device::Device<Backend> for Device {
         memory: &mut Memory,
         segment: hal::memory::Segment,
     ) -> Result<*mut u8, device::MapError> {
+        self.faults
+            .check::<device::MapError>(Operation::MapMemory)?;
         memory.map(segment)
     }
 
@@ -523,31 +1030,46 @@ impl device::Device<Backend> for Device {
     where
         I: Iterator<Item = (&'a Memory, hal::memory::Segment)>,
     {
-        Ok(())
+        self.faults.check(Operation::FlushMappedMemory)
     }
 
     unsafe fn invalidate_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
     where
         I: Iterator<Item = (&'a Memory, hal::memory::Segment)>,
     {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        // All memory is host memory, so it's always coherent
+        self.faults.check(Operation::InvalidateMappedMemory)
     }
 
-    unsafe fn free_memory(&self, _memory: Memory) {
+    unsafe fn free_memory(&self, memory: Memory) {
+        self.heap_usage[self.heap_index(memory.memory_type())]
+            .fetch_sub(memory.size(), Ordering::Relaxed);
         // Let memory drop
     }
 
-    unsafe fn destroy_shader_module(&self, _: ()) {}
+    fn memory_budget(&self) -> Vec<adapter::MemoryHeapBudget> {
+        self.profile
+            .memory_properties
+            .memory_heaps
+            .iter()
+            .zip(&self.heap_usage)
+            .map(|(heap, usage)| adapter::MemoryHeapBudget {
+                budget: heap.size,
+                usage: usage.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    unsafe fn destroy_shader_module(&self, _: ShaderModule) {}
 
     unsafe fn destroy_render_pass(&self, _: ()) {}
 
-    unsafe fn destroy_pipeline_layout(&self, _: ()) {}
+    unsafe fn destroy_pipeline_layout(&self, _: PipelineLayout) {}
 
     unsafe fn destroy_graphics_pipeline(&self, _: ()) {}
 
-    unsafe fn destroy_compute_pipeline(&self, _: ()) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
-    }
+    unsafe fn destroy_compute_pipeline(&self, _: ComputePipeline) {}
+
     unsafe fn destroy_framebuffer(&self, _: ()) {}
 
     unsafe fn destroy_buffer(&self, _: Buffer) {}
@@ -558,26 +1080,58 @@ impl device::Device<Backend> for Device {
 
     unsafe fn destroy_image(&self, _: Image) {}
 
-    unsafe fn destroy_image_view(&self, _: ()) {}
+    unsafe fn destroy_image_view(&self, _: ImageView) {}
 
     unsafe fn destroy_sampler(&self, _: ()) {}
 
+    unsafe fn destroy_acceleration_structure(&self, _: AccelerationStructure) {}
+
     unsafe fn destroy_descriptor_pool(&self, _: DescriptorPool) {}
 
     unsafe fn destroy_descriptor_set_layout(&self, _: DescriptorSetLayout) {}
 
-    unsafe fn destroy_fence(&self, _: ()) {}
+    unsafe fn destroy_descriptor_update_template(&self, _: pso::EmulatedDescriptorUpdateTemplate) {}
 
-    unsafe fn destroy_semaphore(&self, _: ()) {}
+    unsafe fn destroy_fence(&self, _: Fence) {}
 
-    unsafe fn destroy_event(&self, _: ()) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
-    }
+    unsafe fn destroy_semaphore(&self, _: Semaphore) {}
+
+    unsafe fn destroy_event(&self, _: Event) {}
 
-    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+    fn get_calibrated_timestamps(
+        &self,
+    ) -> Result<device::CalibratedTimestamps, device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::GetCalibratedTimestamps)?;
+        // Timestamps are read from the host clock, so both come from a single reading.
+        let (device, host_ns) = self.clock.calibrate();
+        Ok(device::CalibratedTimestamps {
+            device,
+            host_ns,
+            max_deviation_ns: 0,
+        })
+    }
+
+    fn wait_idle(&self) -> Result<(), device::WaitError> {
+        self.faults
+            .check::<device::WaitError>(Operation::DeviceWaitIdle)?;
+        self.notifier.wait_idle(&self.faults)?;
         Ok(())
     }
 
+    unsafe fn get_fault_info(&self) -> Option<device::FaultInfo> {
+        let breadcrumbs = self.breadcrumbs.as_ref()?;
+        let description = if self.faults.is_device_lost() {
+            Some("Device lost by an injected fault".to_string())
+        } else {
+            None
+        };
+        Some(device::FaultInfo {
+            description,
+            last_breadcrumb: breadcrumbs.last(),
+        })
+    }
+
     unsafe fn set_image_name(&self, _: &mut Image, _: &str) {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
@@ -590,11 +1144,11 @@ impl device::Device<Backend> for Device {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn set_semaphore_name(&self, _: &mut (), _: &str) {
+    unsafe fn set_semaphore_name(&self, _: &mut Semaphore, _: &str) {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn set_fence_name(&self, _: &mut (), _: &str) {
+    unsafe fn set_fence_name(&self, _: &mut Fence, _: &str) {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
@@ -614,7 +1168,7 @@ impl device::Device<Backend> for Device {
         layout.name = name.to_string();
     }
 
-    unsafe fn set_pipeline_layout_name(&self, _pipeline_layout: &mut (), _name: &str) {
+    unsafe fn set_pipeline_layout_name(&self, _pipeline_layout: &mut PipelineLayout, _name: &str) {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
@@ -710,12 +1264,33 @@ impl device::Device<Backend> for Device {
         None
     }
 
-    unsafe fn reset_fence(&self, _: &mut ()) -> Result<(), device::OutOfMemory> {
+    unsafe fn reset_fence(&self, fence: &mut Fence) -> Result<(), device::OutOfMemory> {
+        self.faults
+            .check::<device::OutOfMemory>(Operation::ResetFence)?;
+        fence.signal.reset();
         Ok(())
     }
 
-    unsafe fn wait_for_fence(&self, _: &(), _: u64) -> Result<bool, device::WaitError> {
-        Ok(true)
+    unsafe fn wait_for_fences<'a, I>(
+        &self,
+        fences: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::WaitError>
+    where
+        I: Iterator<Item = &'a Fence>,
+    {
+        self.faults
+            .check::<device::WaitError>(Operation::WaitForFences)?;
+        let signals = fences.map(|fence| &fence.signal).collect::<Vec<_>>();
+        // A queue losing the device interrupts the wait
+        let ready = self
+            .notifier
+            .wait_until_or_lost(&self.faults, timeout_ns, || match wait {
+                device::WaitFor::Any => signals.iter().any(|signal| signal.is_set()),
+                device::WaitFor::All => signals.iter().all(|signal| signal.is_set()),
+            })?;
+        Ok(ready)
     }
 
     unsafe fn set_display_power_state(
@@ -753,27 +1328,31 @@ impl device::Device<Backend> for Device {
 }
 
 #[derive(Debug)]
-pub struct QueueFamily;
+pub struct QueueFamily {
+    id: queue::QueueFamilyId,
+    profile: QueueFamilyProfile,
+}
 impl queue::QueueFamily for QueueFamily {
     fn queue_type(&self) -> queue::QueueType {
-        queue::QueueType::General
+        self.profile.queue_type
     }
     fn max_queues(&self) -> usize {
-        1
+        self.profile.max_queues
     }
     fn id(&self) -> queue::QueueFamilyId {
-        QUEUE_FAMILY_ID
+        self.id
     }
     fn supports_sparse_binding(&self) -> bool {
-        true
+        self.profile.supports_sparse_binding
     }
 }
 
-const QUEUE_FAMILY_ID: queue::QueueFamilyId = queue::QueueFamilyId(0);
-
 /// Dummy raw command pool.
 #[derive(Debug)]
-pub struct CommandPool;
+pub struct CommandPool {
+    /// Whether the command buffers record breadcrumbs, for fault diagnostics.
+    breadcrumbs: bool,
+}
 impl pool::CommandPool<Backend> for CommandPool {
     unsafe fn allocate_one(&mut self, level: command::Level) -> CommandBuffer {
         assert_eq!(
@@ -781,31 +1360,140 @@ impl pool::CommandPool<Backend> for CommandPool {
             command::Level::Primary,
             "Only primary command buffers are supported"
         );
-        CommandBuffer
+        CommandBuffer {
+            breadcrumbs: self.breadcrumbs,
+            ..CommandBuffer::default()
+        }
     }
 
     unsafe fn reset(&mut self, _: bool) {}
 
     unsafe fn free<I>(&mut self, _: I) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        // Let the command buffers drop
     }
 }
 
-/// Dummy command buffer, which ignores all the calls.
-#[derive(Debug)]
-pub struct CommandBuffer;
+/// Command buffer recording the transfer, compute and event commands, along with
+/// the breadcrumbs of the debug markers and render passes if fault diagnostics are
+/// enabled, which get executed by the queue on submission. The rest of the calls are ignored.
+#[derive(Debug, Default)]
+pub struct CommandBuffer {
+    /// Recorded commands, shared with the queue while pending execution.
+    commands: Arc<Vec<Command>>,
+    /// Currently bound compute pipeline.
+    compute_pipeline: Option<ComputePipeline>,
+    /// Resources bound for compute dispatches.
+    compute_resources: Resources,
+    /// Predicate of the current conditional rendering block.
+    condition: Option<Condition>,
+    /// Index of the first command of the current performance query.
+    performance_query: Option<usize>,
+    /// Whether breadcrumbs are recorded, for fault diagnostics.
+    breadcrumbs: bool,
+}
+
+impl CommandBuffer {
+    fn recorded_commands(&mut self) -> &mut Vec<Command> {
+        Arc::get_mut(&mut self.commands)
+            .expect("Command buffer is recorded while pending execution")
+    }
+
+    fn record(&mut self, command: Command) {
+        self.recorded_commands().push(command);
+    }
+
+    /// Records a breadcrumb, if fault diagnostics are enabled.
+    fn record_breadcrumb(&mut self, breadcrumb: device::Breadcrumb) {
+        if self.breadcrumbs {
+            self.record(Command::Breadcrumb(breadcrumb));
+        }
+    }
+
+    /// Records the clear of the `aspects` of a rendering attachment
+    /// inside of the render area.
+    fn clear_attachment(
+        &mut self,
+        render_area: pso::Rect,
+        attachment: &command::RenderingAttachmentInfo<'_, Backend>,
+        aspects: format::Aspects,
+    ) {
+        let view = attachment.image_view;
+        let (texel, bytes) = image::clear_texel(view.format, aspects, attachment.clear_value);
+        self.record(Command::ClearImage {
+            image: view.image.clone(),
+            aspects,
+            level: view.range.level_start,
+            layers: view.layers(),
+            rect: render_area,
+            texel,
+            bytes,
+        });
+    }
+
+    /// Records a dispatch, which is skipped if the current conditional
+    /// rendering predicate doesn't pass.
+    fn record_predicated(&mut self, command: Command) {
+        let command = match self.condition {
+            Some(ref condition) => Command::Conditional {
+                condition: condition.clone(),
+                command: Box::new(command),
+            },
+            None => command,
+        };
+        self.record(command);
+    }
+
+    fn bound_compute_pipeline(&self) -> ComputePipeline {
+        self.compute_pipeline
+            .clone()
+            .expect("No compute pipeline is bound")
+    }
+
+    /// Binds the buffers of `set` to the given group of the compute resources.
+    fn bind_compute_buffers<J>(&mut self, group: u32, set: &DescriptorSet, offsets: &mut J)
+    where
+        J: Iterator<Item = command::DescriptorSetOffset>,
+    {
+        for (&(binding, array_index), desc) in set.buffers.iter() {
+            let offset = if desc.dynamic {
+                u64::from(offsets.next().expect("Missing dynamic offset"))
+            } else {
+                0
+            };
+            // Shaders can only address the first element of a buffer array
+            if array_index == 0 {
+                let range = BufferRange {
+                    memory: desc.memory.offset_by(offset),
+                    size: desc.size,
+                };
+                self.compute_resources
+                    .buffers
+                    .insert((group, binding), range);
+            }
+        }
+    }
+}
 impl command::CommandBuffer<Backend> for CommandBuffer {
     unsafe fn begin(
         &mut self,
         _: command::CommandBufferFlags,
         _: command::CommandBufferInheritanceInfo<Backend>,
     ) {
+        self.recorded_commands().clear();
+        self.compute_pipeline = None;
+        self.compute_resources = Resources::default();
+        self.condition = None;
+        self.performance_query = None;
     }
 
     unsafe fn finish(&mut self) {}
 
     unsafe fn reset(&mut self, _: bool) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        self.recorded_commands().clear();
+        self.compute_pipeline = None;
+        self.compute_resources = Resources::default();
+        self.condition = None;
+        self.performance_query = None;
     }
 
     unsafe fn pipeline_barrier<'a, T>(
@@ -818,12 +1506,23 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
     {
     }
 
-    unsafe fn fill_buffer(&mut self, _: &Buffer, _: hal::buffer::SubRange, _: u32) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn fill_buffer(&mut self, buffer: &Buffer, range: hal::buffer::SubRange, data: u32) {
+        // Whole size is rounded down to a multiple of 4
+        let size = range.size.unwrap_or((buffer.size - range.offset) & !3);
+        self.record(Command::FillBuffer {
+            dst: buffer.binding().clone(),
+            offset: range.offset,
+            size,
+            data,
+        });
     }
 
-    unsafe fn update_buffer(&mut self, _: &Buffer, _: hal::buffer::Offset, _: &[u8]) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn update_buffer(&mut self, buffer: &Buffer, offset: hal::buffer::Offset, data: &[u8]) {
+        self.record(Command::UpdateBuffer {
+            dst: buffer.binding().clone(),
+            offset,
+            data: data.to_vec(),
+        });
     }
 
     unsafe fn clear_image<T>(
@@ -878,6 +1577,12 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
     {
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(&mut self, _: u32, _: T)
+    where
+        T: Iterator<Item = (&'a Buffer, hal::buffer::SubRange, pso::ElemStride)>,
+    {
+    }
+
     unsafe fn set_viewports<T>(&mut self, _: u32, _: T) {}
 
     unsafe fn set_scissors<T>(&mut self, _: u32, _: T) {}
@@ -910,6 +1615,20 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
+    unsafe fn set_cull_mode(&mut self, _: pso::Face) {}
+
+    unsafe fn set_front_face(&mut self, _: pso::FrontFace) {}
+
+    unsafe fn set_primitive_topology(&mut self, _: pso::Primitive) {}
+
+    unsafe fn set_depth_test_enable(&mut self, _: bool) {}
+
+    unsafe fn set_depth_write_enable(&mut self, _: bool) {}
+
+    unsafe fn set_depth_compare_op(&mut self, _: pso::Comparison) {}
+
+    unsafe fn set_stencil_op(&mut self, _: pso::Face, _: pso::StencilFace) {}
+
     unsafe fn begin_render_pass<'a, T>(
         &mut self,
         _: &(),
@@ -926,68 +1645,218 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn end_render_pass(&mut self) {}
+    unsafe fn end_render_pass(&mut self) {
+        self.record_breadcrumb(device::Breadcrumb::EndRenderPass);
+    }
+
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        render_area: pso::Rect,
+        color_attachments: T,
+        depth_stencil_attachment: Option<command::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = command::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        // Store operations keep the rendered contents, which are
+        // the loaded or cleared ones as nothing gets rasterized.
+        for attachment in color_attachments {
+            if attachment.ops.load == pass::AttachmentLoadOp::Clear {
+                self.clear_attachment(render_area, &attachment, format::Aspects::COLOR);
+            }
+        }
+        if let Some(attachment) = depth_stencil_attachment {
+            let aspects = attachment.image_view.range.aspects;
+            if attachment.ops.load == pass::AttachmentLoadOp::Clear
+                && aspects.contains(format::Aspects::DEPTH)
+            {
+                self.clear_attachment(render_area, &attachment, format::Aspects::DEPTH);
+            }
+            if attachment.stencil_ops.load == pass::AttachmentLoadOp::Clear
+                && aspects.contains(format::Aspects::STENCIL)
+            {
+                self.clear_attachment(render_area, &attachment, format::Aspects::STENCIL);
+            }
+        }
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        self.record_breadcrumb(device::Breadcrumb::EndRenderPass);
+    }
 
     unsafe fn bind_graphics_pipeline(&mut self, _: &()) {}
 
-    unsafe fn bind_graphics_descriptor_sets<'a, I, J>(&mut self, _: &(), _: usize, _: I, _: J)
-    where
+    unsafe fn bind_graphics_descriptor_sets<'a, I, J>(
+        &mut self,
+        _: &PipelineLayout,
+        _: usize,
+        _: I,
+        _: J,
+    ) where
         I: Iterator<Item = &'a DescriptorSet>,
     {
         // Do nothing
     }
 
-    unsafe fn bind_compute_pipeline(&mut self, _: &()) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        self.compute_pipeline = Some(pipeline.clone());
     }
 
-    unsafe fn bind_compute_descriptor_sets<'a, I, J>(&mut self, _: &(), _: usize, _: I, _: J)
-    where
+    unsafe fn bind_compute_descriptor_sets<'a, I, J>(
+        &mut self,
+        _: &PipelineLayout,
+        first_set: usize,
+        sets: I,
+        mut offsets: J,
+    ) where
         I: Iterator<Item = &'a DescriptorSet>,
+        J: Iterator<Item = command::DescriptorSetOffset>,
+    {
+        for (i, set) in sets.enumerate() {
+            let group = (first_set + i) as u32;
+            self.compute_resources
+                .buffers
+                .retain(|&(g, _), _| g != group);
+            self.bind_compute_buffers(group, set, &mut offsets);
+        }
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        _: &PipelineLayout,
+        _: usize,
+        _: pso::DescriptorBinding,
+        _: pso::DescriptorArrayIndex,
+        _: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
     {
         // Do nothing
     }
 
-    unsafe fn dispatch(&mut self, _: hal::WorkGroupCount) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        layout: &PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        let bindings = layout.push_sets[set]
+            .as_ref()
+            .expect("Descriptor set layout isn't a push descriptor layout");
+        let mut pushed = DescriptorSet {
+            name: String::new(),
+            layout: bindings.clone(),
+            buffers: BTreeMap::new(),
+        };
+        pushed.write(binding, array_offset, descriptors);
+        self.bind_compute_buffers(set as u32, &pushed, &mut iter::empty());
     }
 
-    unsafe fn dispatch_indirect(&mut self, _: &Buffer, _: hal::buffer::Offset) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
+        self.record_predicated(Command::Dispatch {
+            pipeline: self.bound_compute_pipeline(),
+            resources: self.compute_resources.clone(),
+            count,
+        });
     }
 
-    unsafe fn copy_buffer<T>(&mut self, _: &Buffer, _: &Buffer, _: T) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn dispatch_indirect(&mut self, buffer: &Buffer, offset: hal::buffer::Offset) {
+        self.record_predicated(Command::DispatchIndirect {
+            pipeline: self.bound_compute_pipeline(),
+            resources: self.compute_resources.clone(),
+            buffer: buffer.binding().clone(),
+            offset,
+        });
+    }
+
+    unsafe fn copy_buffer<T>(&mut self, src: &Buffer, dst: &Buffer, regions: T)
+    where
+        T: Iterator<Item = command::BufferCopy>,
+    {
+        self.record(Command::CopyBuffer {
+            src: src.binding().clone(),
+            dst: dst.binding().clone(),
+            regions: regions.collect(),
+        });
     }
 
     unsafe fn copy_image<T>(
         &mut self,
-        _: &Image,
+        src: &Image,
         _: hal::image::Layout,
-        _: &Image,
+        dst: &Image,
         _: hal::image::Layout,
-        _: T,
-    ) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        regions: T,
+    ) where
+        T: Iterator<Item = command::ImageCopy>,
+    {
+        self.record(Command::CopyImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.collect(),
+        });
     }
 
     unsafe fn copy_buffer_to_image<T>(
         &mut self,
-        _: &Buffer,
-        _: &Image,
+        src: &Buffer,
+        dst: &Image,
         _: hal::image::Layout,
-        _: T,
-    ) {
+        regions: T,
+    ) where
+        T: Iterator<Item = command::BufferImageCopy>,
+    {
+        self.record(Command::CopyBufferToImage {
+            src: src.binding().clone(),
+            dst: dst.clone(),
+            regions: regions.collect(),
+        });
     }
 
     unsafe fn copy_image_to_buffer<T>(
         &mut self,
-        _: &Image,
+        src: &Image,
         _: hal::image::Layout,
-        _: &Buffer,
-        _: T,
+        dst: &Buffer,
+        regions: T,
+    ) where
+        T: Iterator<Item = command::BufferImageCopy>,
+    {
+        self.record(Command::CopyImageToBuffer {
+            src: src.clone(),
+            dst: dst.binding().clone(),
+            regions: regions.collect(),
+        });
+    }
+
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        for (desc, ranges) in descs {
+            self.record(Command::BuildAccelerationStructure(bvh::Build::new(
+                desc, ranges,
+            )));
+        }
+    }
+
+    unsafe fn copy_acceleration_structure(
+        &mut self,
+        src: &AccelerationStructure,
+        dst: &AccelerationStructure,
     ) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        self.record(Command::CopyAccelerationStructure {
+            src: src.clone(),
+            dst: dst.clone(),
+        });
     }
 
     unsafe fn draw(&mut self, _: Range<hal::VertexCount>, _: Range<hal::InstanceCount>) {}
@@ -1066,52 +1935,141 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn set_event(&mut self, _: &(), _: pso::PipelineStage) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn set_event(&mut self, event: &Event, _: pso::PipelineStage) {
+        self.record(Command::SetEvent(Arc::clone(&event.signal)));
     }
 
-    unsafe fn reset_event(&mut self, _: &(), _: pso::PipelineStage) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn reset_event(&mut self, event: &Event, _: pso::PipelineStage) {
+        self.record(Command::ResetEvent(Arc::clone(&event.signal)));
     }
 
-    unsafe fn wait_events<'a, I, J>(&mut self, _: I, _: Range<pso::PipelineStage>, _: J)
+    unsafe fn wait_events<'a, I, J>(&mut self, events: I, _: Range<pso::PipelineStage>, _: J)
     where
+        I: Iterator<Item = &'a Event>,
         J: Iterator<Item = hal::memory::Barrier<'a, Backend>>,
     {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        let signals = events.map(|event| Arc::clone(&event.signal)).collect();
+        self.record(Command::WaitEvents(signals));
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Backend>, _: query::ControlFlags) {
+        match query.pool.ty {
+            // Nothing is counted until the end of the query
+            query::Type::Occlusion => {}
+            query::Type::PerformanceCounters => {
+                assert!(
+                    self.performance_query.is_none(),
+                    "Performance queries can't be nested"
+                );
+                self.record(Command::BeginPerformanceQuery {
+                    queries: Arc::clone(&query.pool.queries),
+                    id: query.id,
+                });
+                self.performance_query = Some(self.commands.len());
+            }
+            _ => panic!("Only occlusion and performance queries can be begun"),
+        }
     }
 
-    unsafe fn begin_query(&mut self, _: query::Query<Backend>, _: query::ControlFlags) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn end_query(&mut self, query: query::Query<Backend>) {
+        let command = match query.pool.ty {
+            query::Type::Occlusion => Command::EndOcclusionQuery {
+                queries: Arc::clone(&query.pool.queries),
+                id: query.id,
+            },
+            query::Type::PerformanceCounters => {
+                let start = self
+                    .performance_query
+                    .take()
+                    .expect("Performance query was never begun");
+                let commands = self.commands[start..]
+                    .iter()
+                    .filter(|command| !matches!(command, Command::Breadcrumb(_)))
+                    .count();
+                Command::EndPerformanceQuery {
+                    queries: Arc::clone(&query.pool.queries),
+                    id: query.id,
+                    commands: commands as u32,
+                }
+            }
+            _ => panic!("Only occlusion and performance queries can be ended"),
+        };
+        self.record(command);
     }
 
-    unsafe fn end_query(&mut self, _: query::Query<Backend>) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn reset_query_pool(&mut self, pool: &QueryPool, queries: Range<query::Id>) {
+        self.record(Command::ResetQueries {
+            queries: Arc::clone(&pool.queries),
+            range: queries,
+        });
     }
 
-    unsafe fn reset_query_pool(&mut self, _: &(), _: Range<query::Id>) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &QueryPool,
+        queries: Range<query::Id>,
+        buffer: &Buffer,
+        offset: hal::buffer::Offset,
+        stride: hal::buffer::Stride,
+        flags: query::ResultFlags,
+    ) {
+        assert_ne!(
+            pool.ty,
+            query::Type::PerformanceCounters,
+            "Performance query results can only be read on the host"
+        );
+        self.record(Command::CopyQueryResults {
+            queries: Arc::clone(&pool.queries),
+            range: queries,
+            dst: buffer.binding().clone(),
+            offset,
+            stride,
+            flags,
+        });
     }
 
-    unsafe fn copy_query_pool_results(
+    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, query: query::Query<Backend>) {
+        assert_eq!(
+            query.pool.ty,
+            query::Type::Timestamp,
+            "Timestamps can only be written to timestamp query pools"
+        );
+        self.record(Command::WriteTimestamp {
+            queries: Arc::clone(&query.pool.queries),
+            id: query.id,
+        });
+    }
+
+    unsafe fn begin_conditional_rendering(
         &mut self,
-        _: &(),
-        _: Range<query::Id>,
-        _: &Buffer,
-        _: hal::buffer::Offset,
-        _: hal::buffer::Stride,
-        _: query::ResultFlags,
+        buffer: &Buffer,
+        offset: hal::buffer::Offset,
+        inverted: bool,
     ) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+        self.condition = Some(Condition {
+            buffer: buffer.binding().clone(),
+            offset,
+            inverted,
+        });
     }
 
-    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Backend>) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.condition = None;
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, _: u32, _: T)
+    where
+        T: Iterator<Item = (&'a Buffer, hal::buffer::SubRange)>,
+    {
     }
 
+    unsafe fn begin_transform_feedback(&mut self) {}
+
+    unsafe fn end_transform_feedback(&mut self) {}
+
     unsafe fn push_graphics_constants(
         &mut self,
-        _: &(),
+        _: &PipelineLayout,
         _: pso::ShaderStageFlags,
         _: u32,
         _: &[u32],
@@ -1119,8 +2077,18 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn push_compute_constants(&mut self, _: &(), _: u32, _: &[u32]) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn push_compute_constants(
+        &mut self,
+        _: &PipelineLayout,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        let start = offset as usize / 4;
+        let push_constants = &mut self.compute_resources.push_constants;
+        if push_constants.len() < start + constants.len() {
+            push_constants.resize(start + constants.len(), 0);
+        }
+        push_constants[start..start + constants.len()].copy_from_slice(constants);
     }
 
     unsafe fn execute_commands<'a, T>(&mut self, _: T)
@@ -1130,117 +2098,82 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
     }
 
-    unsafe fn insert_debug_marker(&mut self, _: &str, _: u32) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn insert_debug_marker(&mut self, name: &str, _: u32) {
+        self.record_breadcrumb(device::Breadcrumb::DebugMarker(name.to_owned()));
     }
-    unsafe fn begin_debug_marker(&mut self, _: &str, _: u32) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
-    }
-    unsafe fn end_debug_marker(&mut self) {
-        unimplemented!("{}", NOT_SUPPORTED_MESSAGE)
+    unsafe fn begin_debug_marker(&mut self, name: &str, _: u32) {
+        self.record_breadcrumb(device::Breadcrumb::DebugMarker(name.to_owned()));
     }
+    unsafe fn end_debug_marker(&mut self) {}
 }
 
-/// Dummy surface.
 #[derive(Debug)]
-pub struct Surface;
-impl window::Surface<Backend> for Surface {
-    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
-        true
-    }
-
-    fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
-        let extents = {
-            let min_extent = window::Extent2D {
-                width: 0,
-                height: 0,
-            };
-            let max_extent = window::Extent2D {
-                width: 8192,
-                height: 4096,
-            };
-            min_extent..=max_extent
-        };
-        let usage = hal::image::Usage::COLOR_ATTACHMENT;
-        let present_modes = window::PresentMode::all();
-        let composite_alpha_modes = window::CompositeAlphaMode::OPAQUE;
-        window::SurfaceCapabilities {
-            image_count: 1..=1,
-            current_extent: None,
-            extents,
-            max_image_layers: 1,
-            usage,
-            present_modes,
-            composite_alpha_modes,
-        }
-    }
-
-    fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<format::Format>> {
-        None
-    }
+pub struct Instance {
+    /// Profiles of the exposed adapters.
+    profiles: Vec<Arc<Profile>>,
+    /// Faults injected into the objects created from this instance.
+    faults: FaultInjector,
 }
 
-#[derive(Debug)]
-pub struct SwapchainImage;
-impl Borrow<Image> for SwapchainImage {
-    fn borrow(&self) -> &Image {
-        unimplemented!()
-    }
-}
-impl Borrow<()> for SwapchainImage {
-    fn borrow(&self) -> &() {
-        unimplemented!()
+impl Instance {
+    /// Creates an instance exposing an adapter for each of the given profiles.
+    pub fn with_profiles<I>(profiles: I) -> Self
+    where
+        I: IntoIterator<Item = Profile>,
+    {
+        Instance {
+            profiles: profiles.into_iter().map(Arc::new).collect(),
+            faults: FaultInjector::new(),
+        }
     }
-}
 
-impl window::PresentationSurface<Backend> for Surface {
-    type SwapchainImage = SwapchainImage;
-
-    unsafe fn configure_swapchain(
-        &mut self,
-        _: &Device,
-        _: window::SwapchainConfig,
-    ) -> Result<(), window::SwapchainError> {
-        Ok(())
+    /// Returns the fault injector shared by the objects created from this instance.
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
     }
 
-    unsafe fn unconfigure_swapchain(&mut self, _: &Device) {}
-
-    unsafe fn acquire_image(
-        &mut self,
-        _: u64,
-    ) -> Result<(SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
-        Ok((SwapchainImage, None))
+    /// Creates a surface that isn't tied to any window, with a fixed extent and format.
+    ///
+    /// The presented frames are written to `output`.
+    pub fn create_virtual_surface(
+        &self,
+        extent: window::Extent2D,
+        format: format::Format,
+        output: FrameOutput,
+    ) -> std::io::Result<Surface> {
+        Surface::new_virtual(extent, format, output, self.faults.clone())
     }
 }
 
-#[derive(Debug)]
-pub struct Instance;
-
 impl hal::Instance<Backend> for Instance {
     fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
         debug!(
             "Creating empty backend instance with name '{}' and version {}",
             name, version
         );
-        Ok(Instance)
+        Ok(Instance::with_profiles(Some(Profile::default())))
     }
 
     fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Backend>> {
-        // TODO: provide more mock adapters, with various qualities
-        let info = adapter::AdapterInfo {
-            name: "Mock Device".to_string(),
-            vendor: 0,
-            device: 1234,
-            device_type: adapter::DeviceType::Other,
-        };
-        let adapter = adapter::Adapter {
-            info,
-            physical_device: PhysicalDevice,
-            // TODO: multiple queue families
-            queue_families: vec![QueueFamily],
-        };
-        vec![adapter]
+        self.profiles
+            .iter()
+            .map(|profile| adapter::Adapter {
+                info: profile.info.clone(),
+                physical_device: PhysicalDevice {
+                    profile: Arc::clone(profile),
+                    faults: self.faults.clone(),
+                },
+                queue_families: profile
+                    .queue_families
+                    .iter()
+                    .enumerate()
+                    .map(|(id, family)| QueueFamily {
+                        id: queue::QueueFamilyId(id),
+                        profile: family.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
     }
 
     unsafe fn create_surface(
@@ -1249,7 +2182,7 @@ impl hal::Instance<Backend> for Instance {
     ) -> Result<Surface, hal::window::InitError> {
         // TODO: maybe check somehow that the given handle is valid?
         let _handle = raw_window_handle.raw_window_handle();
-        Ok(Surface)
+        Ok(Surface::new(self.faults.clone()))
     }
 
     unsafe fn destroy_surface(&self, _surface: Surface) {}
@@ -1265,3 +2198,23 @@ impl hal::Instance<Backend> for Instance {
         unimplemented!();
     }
 }
+
+/// Opens all the queues of the first queue family of the first adapter of `instance`.
+#[cfg(test)]
+fn open_device(
+    instance: &Instance,
+    features: hal::Features,
+) -> (adapter::Adapter<Backend>, adapter::Gpu<Backend>) {
+    use hal::{adapter::PhysicalDevice as _, queue::QueueFamily as _, Instance as _};
+
+    let adapter = instance.enumerate_adapters().remove(0);
+    let family = &adapter.queue_families[0];
+    let priorities = vec![1.0; family.max_queues()];
+    let gpu = unsafe {
+        adapter
+            .physical_device
+            .open(&[(family, &priorities)], features)
+            .unwrap()
+    };
+    (adapter, gpu)
+}
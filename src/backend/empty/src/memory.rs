@@ -3,8 +3,80 @@ use hal::{
     memory::Segment,
     MemoryTypeId,
 };
-use std::cell::UnsafeCell;
-use std::convert::TryInto;
+use std::{cell::UnsafeCell, convert::TryInto, ptr, sync::Arc};
+
+/// Host-side bytes backing a memory allocation.
+///
+/// Shared between the `Memory` object and every resource bound to it,
+/// so that recorded commands can keep accessing it on submission.
+#[derive(Debug)]
+pub(crate) struct Block {
+    data: UnsafeCell<Box<[u8]>>,
+}
+
+unsafe impl Send for Block {}
+unsafe impl Sync for Block {}
+
+impl Block {
+    fn len(&self) -> u64 {
+        let data = unsafe { &*self.data.get() };
+        data.len() as u64
+    }
+
+    fn as_mut_ptr(&self) -> *mut u8 {
+        let data = unsafe { &mut *self.data.get() };
+        data.as_mut_ptr()
+    }
+}
+
+/// A resource binding into a memory block.
+#[derive(Clone, Debug)]
+pub(crate) struct Binding {
+    block: Arc<Block>,
+    offset: u64,
+}
+
+impl Binding {
+    /// Creates a binding to a new zero-initialized block of host memory.
+    pub fn zeroed(size: u64) -> Self {
+        let block = Block {
+            data: UnsafeCell::new(vec![0u8; size as usize].into_boxed_slice()),
+        };
+        Binding {
+            block: Arc::new(block),
+            offset: 0,
+        }
+    }
+
+    /// Returns a binding to the same memory, starting `offset` bytes further.
+    pub fn offset_by(&self, offset: u64) -> Self {
+        Binding {
+            block: Arc::clone(&self.block),
+            offset: self.offset + offset,
+        }
+    }
+
+    /// Returns a pointer to `size` bytes at `offset` relative to the binding.
+    ///
+    /// Panics if the range doesn't fit into the memory block.
+    pub fn ptr(&self, offset: u64, size: u64) -> *mut u8 {
+        let start = self.offset + offset;
+        assert!(
+            start + size <= self.block.len(),
+            "Access of {} bytes at offset {} is out of memory bounds",
+            size,
+            start,
+        );
+        unsafe { self.block.as_mut_ptr().offset(start as isize) }
+    }
+
+    /// Copies `size` bytes between two bindings, which may alias the same memory.
+    pub fn copy(src: &Self, src_offset: u64, dst: &Self, dst_offset: u64, size: u64) {
+        let src = src.ptr(src_offset, size);
+        let dst = dst.ptr(dst_offset, size);
+        unsafe { ptr::copy(src, dst, size as usize) }
+    }
+}
 
 /// A memory allocation.
 #[derive(Debug)]
@@ -14,13 +86,11 @@ pub struct Memory {
     /// Size of the allocated memory block.
     size: u64,
     /// The backing memory allocation.
-    data: UnsafeCell<Box<[u8]>>,
+    block: Arc<Block>,
 }
 
 impl Memory {
     pub fn allocate(memory_type: MemoryTypeId, size: u64) -> Result<Self, AllocationError> {
-        assert_eq!(memory_type.0, 0, "We only support one memory type");
-
         // Allocate a buffer in RAM
         let data = {
             let size = size
@@ -35,11 +105,23 @@ impl Memory {
         let memory = Memory {
             memory_type,
             size,
-            data: UnsafeCell::new(data),
+            block: Arc::new(Block {
+                data: UnsafeCell::new(data),
+            }),
         };
         Ok(memory)
     }
 
+    /// Returns the type of memory this block was allocated from.
+    pub(crate) fn memory_type(&self) -> MemoryTypeId {
+        self.memory_type
+    }
+
+    /// Returns the size of the allocated memory block.
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
     pub fn map(&self, segment: Segment) -> Result<*mut u8, MapError> {
         if segment.offset >= self.size {
             return Err(MapError::OutOfBounds);
@@ -50,9 +132,58 @@ impl Memory {
             }
         }
 
-        let data = unsafe { &mut *self.data.get() };
-        Ok(data.as_mut_ptr())
+        let data = self.block.as_mut_ptr();
+        Ok(unsafe { data.offset(segment.offset as isize) })
+    }
+
+    /// Creates a binding for a resource placed at `offset` within this allocation.
+    pub(crate) fn bind(&self, offset: u64) -> Binding {
+        Binding {
+            block: Arc::clone(&self.block),
+            offset,
+        }
     }
 }
 
-unsafe impl Sync for Memory {}
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile};
+    use hal::{
+        adapter::{MemoryHeapBudget, PhysicalDevice as _},
+        device::Device as _,
+        memory::AllocationFlags,
+        MemoryTypeId,
+    };
+    use std::iter;
+
+    #[test]
+    fn heap_usage() {
+        let profile = Profile::default().with_features(hal::Features::MEMORY_BUDGET);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, gpu) = crate::open_device(&instance, hal::Features::MEMORY_BUDGET);
+        let device = &gpu.device;
+        let heap_size = adapter.physical_device.memory_properties().memory_heaps[0].size;
+        let budget = |usage| {
+            vec![MemoryHeapBudget {
+                budget: heap_size,
+                usage,
+            }]
+        };
+
+        assert_eq!(device.memory_budget(), budget(0));
+        unsafe {
+            let first = device
+                .allocate_memory(MemoryTypeId(0), 1024, AllocationFlags::empty())
+                .unwrap();
+            let second = device
+                .allocate_memory(MemoryTypeId(0), 256, AllocationFlags::empty())
+                .unwrap();
+            assert_eq!(device.memory_budget(), budget(1280));
+
+            device.free_memory(first);
+            assert_eq!(device.memory_budget(), budget(256));
+            device.free_memory(second);
+        }
+        assert_eq!(device.memory_budget(), budget(0));
+    }
+}
@@ -0,0 +1,1145 @@
+//! Adapter profiles, describing the capabilities a mock adapter reports.
+//!
+//! Profiles can be built in code, starting from the default mock device,
+//! or loaded from RON/JSON files and `vulkaninfo` dumps when the `profiles`
+//! feature is enabled.
+
+use hal::{adapter, format, image, memory, queue};
+
+use std::collections::HashMap;
+
+#[cfg(feature = "profiles")]
+use serde::{Deserialize, Serialize};
+
+/// Queue family exposed by a profiled adapter.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "profiles", derive(Serialize, Deserialize))]
+pub struct QueueFamilyProfile {
+    /// Capabilities of the queues in this family.
+    pub queue_type: queue::QueueType,
+    /// Number of queues that can be created in this family.
+    pub max_queues: usize,
+    /// Whether the queues support sparse binding.
+    #[cfg_attr(feature = "profiles", serde(default))]
+    pub supports_sparse_binding: bool,
+}
+
+impl QueueFamilyProfile {
+    /// Creates a queue family of the given type.
+    pub fn new(queue_type: queue::QueueType, max_queues: usize) -> Self {
+        QueueFamilyProfile {
+            queue_type,
+            max_queues,
+            supports_sparse_binding: false,
+        }
+    }
+}
+
+/// Description of a mock adapter.
+///
+/// Fields missing from a profile file keep their value from the default profile.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "profiles", derive(Serialize, Deserialize), serde(default))]
+pub struct Profile {
+    /// Adapter name, vendor and device type.
+    pub info: adapter::AdapterInfo,
+    /// Features supported by the adapter.
+    pub features: hal::Features,
+    /// Limits, downlevel properties and the rest of the device properties.
+    pub properties: hal::PhysicalDeviceProperties,
+    /// Memory types and heaps.
+    pub memory_properties: adapter::MemoryProperties,
+    /// Properties of the individual formats.
+    pub formats: HashMap<format::Format, format::Properties>,
+    /// Properties of the formats missing from `formats`.
+    pub default_format_properties: format::Properties,
+    /// Queue families, in the order of their IDs.
+    pub queue_families: Vec<QueueFamilyProfile>,
+    /// Nanoseconds it takes for a timestamp to increment by one.
+    pub timestamp_period: f32,
+}
+
+impl Default for Profile {
+    /// The mock device: a single general queue, a single memory type, no features,
+    /// and every format supporting everything.
+    fn default() -> Self {
+        let memory_type = adapter::MemoryType {
+            properties: memory::Properties::DEVICE_LOCAL
+                | memory::Properties::CPU_VISIBLE
+                | memory::Properties::COHERENT
+                | memory::Properties::CPU_CACHED,
+            heap_index: 0,
+        };
+        // TODO: perhaps get an estimate of free RAM to report here?
+        let memory_heap = adapter::MemoryHeap {
+            size: 64 * 1024,
+            flags: memory::HeapFlags::empty(),
+        };
+        Profile {
+            info: adapter::AdapterInfo {
+                name: "Mock Device".to_string(),
+                vendor: 0,
+                device: 1234,
+                device_type: adapter::DeviceType::Other,
+            },
+            features: hal::Features::empty(),
+            properties: hal::PhysicalDeviceProperties {
+                limits: hal::Limits {
+                    non_coherent_atom_size: 1,
+                    optimal_buffer_copy_pitch_alignment: 1,
+                    max_push_constants_size: 128,
+                    max_compute_shared_memory_size: 32768,
+                    max_compute_work_group_count: [65535; 3],
+                    max_compute_work_group_invocations: 1024,
+                    max_compute_work_group_size: [1024, 1024, 64],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            memory_properties: adapter::MemoryProperties {
+                memory_types: vec![memory_type],
+                memory_heaps: vec![memory_heap],
+            },
+            formats: HashMap::new(),
+            default_format_properties: format::Properties {
+                linear_tiling: format::ImageFeature::all(),
+                optimal_tiling: format::ImageFeature::all(),
+                buffer_features: format::BufferFeature::all(),
+                drm_format_properties: Vec::new(),
+            },
+            queue_families: vec![QueueFamilyProfile::new(queue::QueueType::General, 1)],
+            timestamp_period: 1.0,
+        }
+    }
+}
+
+impl Profile {
+    /// Sets the adapter name.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.info.name = name.to_string();
+        self
+    }
+
+    /// Sets the adapter info.
+    pub fn with_info(mut self, info: adapter::AdapterInfo) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Sets the supported features.
+    pub fn with_features(mut self, features: hal::Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Sets the device properties.
+    pub fn with_properties(mut self, properties: hal::PhysicalDeviceProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Sets the limits, keeping the rest of the device properties.
+    pub fn with_limits(mut self, limits: hal::Limits) -> Self {
+        self.properties.limits = limits;
+        self
+    }
+
+    /// Sets the downlevel properties, keeping the rest of the device properties.
+    pub fn with_downlevel(mut self, downlevel: hal::DownlevelProperties) -> Self {
+        self.properties.downlevel = downlevel;
+        self
+    }
+
+    /// Sets the subgroup properties, keeping the rest of the device properties.
+    pub fn with_subgroup(mut self, subgroup: hal::SubgroupProperties) -> Self {
+        self.properties.subgroup = subgroup;
+        self
+    }
+
+    /// Sets the memory types and heaps.
+    pub fn with_memory_properties(mut self, memory_properties: adapter::MemoryProperties) -> Self {
+        self.memory_properties = memory_properties;
+        self
+    }
+
+    /// Sets the properties of a single format.
+    pub fn with_format(mut self, format: format::Format, properties: format::Properties) -> Self {
+        self.formats.insert(format, properties);
+        self
+    }
+
+    /// Sets the properties of the formats that aren't explicitly listed.
+    ///
+    /// Use `format::Properties::default()` to make them unsupported.
+    pub fn with_default_format_properties(mut self, properties: format::Properties) -> Self {
+        self.default_format_properties = properties;
+        self
+    }
+
+    /// Sets the queue families.
+    pub fn with_queue_families(mut self, queue_families: Vec<QueueFamilyProfile>) -> Self {
+        self.queue_families = queue_families;
+        self
+    }
+
+    /// Sets the nanoseconds it takes for a timestamp to increment by one.
+    pub fn with_timestamp_period(mut self, timestamp_period: f32) -> Self {
+        self.timestamp_period = timestamp_period;
+        self
+    }
+
+    /// Returns the properties of a format.
+    pub fn format_properties(&self, format: format::Format) -> &format::Properties {
+        self.formats
+            .get(&format)
+            .unwrap_or(&self.default_format_properties)
+    }
+
+    /// Returns the limits of an image with the given parameters,
+    /// or `None` if the format doesn't support them.
+    pub fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        let properties = self.format_properties(format);
+        let features = match tiling {
+            image::Tiling::Linear => properties.linear_tiling,
+            image::Tiling::Optimal => properties.optimal_tiling,
+        };
+        let required = [
+            (
+                image::Usage::TRANSFER_SRC,
+                format::ImageFeature::TRANSFER_SRC,
+            ),
+            (
+                image::Usage::TRANSFER_DST,
+                format::ImageFeature::TRANSFER_DST,
+            ),
+            (image::Usage::SAMPLED, format::ImageFeature::SAMPLED),
+            (image::Usage::STORAGE, format::ImageFeature::STORAGE),
+            (
+                image::Usage::COLOR_ATTACHMENT,
+                format::ImageFeature::COLOR_ATTACHMENT,
+            ),
+            (
+                image::Usage::DEPTH_STENCIL_ATTACHMENT,
+                format::ImageFeature::DEPTH_STENCIL_ATTACHMENT,
+            ),
+        ]
+        .iter()
+        .filter(|&&(flag, _)| usage.contains(flag))
+        .fold(format::ImageFeature::empty(), |acc, &(_, feature)| {
+            acc | feature
+        });
+        if features.is_empty() || !features.contains(required) {
+            return None;
+        }
+        if view_caps.contains(image::ViewCapabilities::KIND_CUBE) && dimensions != 2 {
+            return None;
+        }
+
+        let limits = &self.properties.limits;
+        let max_extent = match dimensions {
+            1 => image::Extent {
+                width: limits.max_image_1d_size,
+                height: 1,
+                depth: 1,
+            },
+            2 => image::Extent {
+                width: limits.max_image_2d_size,
+                height: limits.max_image_2d_size,
+                depth: 1,
+            },
+            3 => image::Extent {
+                width: limits.max_image_3d_size,
+                height: limits.max_image_3d_size,
+                depth: limits.max_image_3d_size,
+            },
+            _ => return None,
+        };
+        let max_dimension = max_extent
+            .width
+            .max(max_extent.height)
+            .max(max_extent.depth);
+        let max_levels = 32 - max_dimension.leading_zeros();
+
+        let desc = format.surface_desc();
+        let sample_count_mask = if tiling == image::Tiling::Linear || dimensions != 2 {
+            1
+        } else if desc.aspects.contains(format::Aspects::DEPTH) {
+            limits.framebuffer_depth_sample_counts
+        } else if desc.aspects.contains(format::Aspects::STENCIL) {
+            limits.framebuffer_stencil_sample_counts
+        } else {
+            limits.framebuffer_color_sample_counts
+        };
+
+        let max_heap_size = self
+            .memory_properties
+            .memory_heaps
+            .iter()
+            .map(|heap| heap.size)
+            .max()
+            .unwrap_or(0);
+
+        Some(image::FormatProperties {
+            max_extent,
+            max_levels: max_levels.max(1) as image::Level,
+            // 3D images enforce a single layer
+            max_layers: if dimensions == 3 {
+                1
+            } else {
+                limits.max_image_array_layers
+            },
+            sample_count_mask: sample_count_mask.max(1),
+            max_resource_size: max_heap_size as usize,
+        })
+    }
+}
+
+/// Error loading a profile.
+#[cfg(feature = "profiles")]
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    /// The profile file couldn't be read.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The RON profile is malformed.
+    #[error(transparent)]
+    Ron(#[from] ron::Error),
+    /// The JSON profile is malformed.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A field of a `vulkaninfo` dump is missing or has an unexpected type.
+    #[error("Field `{0}` is missing or invalid")]
+    InvalidField(String),
+}
+
+#[cfg(feature = "profiles")]
+impl Profile {
+    /// Loads a profile from a file.
+    ///
+    /// Files with the `ron` extension are parsed as RON, others as JSON.
+    /// JSON files containing a `VkPhysicalDeviceProperties` object are
+    /// imported as `vulkaninfo` dumps.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ProfileError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        if path.extension().and_then(|ext| ext.to_str()) == Some("ron") {
+            return Self::from_ron(&text);
+        }
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        if value.get("VkPhysicalDeviceProperties").is_some() {
+            vulkaninfo::parse(&value)
+        } else {
+            Ok(serde_json::from_value(value)?)
+        }
+    }
+
+    /// Parses a RON profile.
+    pub fn from_ron(text: &str) -> Result<Self, ProfileError> {
+        Ok(ron::de::from_str(text)?)
+    }
+
+    /// Parses a JSON profile.
+    pub fn from_json(text: &str) -> Result<Self, ProfileError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Imports a device dump produced by `vulkaninfo --json`,
+    /// in the format used by the device simulation layer.
+    pub fn from_vulkaninfo(text: &str) -> Result<Self, ProfileError> {
+        vulkaninfo::parse(&serde_json::from_str(text)?)
+    }
+}
+
+#[cfg(feature = "profiles")]
+mod vulkaninfo {
+    use super::{Profile, ProfileError, QueueFamilyProfile};
+    use hal::{adapter, format, memory, queue, Features};
+    use serde_json::Value;
+
+    use std::{collections::HashMap, convert::TryFrom, mem};
+
+    /// Values that can be read from a JSON field.
+    trait FromJson: Sized {
+        fn from_json(value: &Value) -> Option<Self>;
+    }
+
+    macro_rules! impl_from_json_int {
+        ($($ty:ty),*) => {
+            $(
+                impl FromJson for $ty {
+                    fn from_json(value: &Value) -> Option<Self> {
+                        value.as_u64().and_then(|v| <$ty>::try_from(v).ok())
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_from_json_int!(u8, u16, u32, u64, usize);
+
+    impl FromJson for f32 {
+        fn from_json(value: &Value) -> Option<Self> {
+            value.as_f64().map(|v| v as f32)
+        }
+    }
+
+    impl FromJson for bool {
+        // Dumps store `VkBool32` values either as booleans or as numbers.
+        fn from_json(value: &Value) -> Option<Self> {
+            value.as_bool().or_else(|| value.as_u64().map(|v| v != 0))
+        }
+    }
+
+    impl<T: FromJson + Default + Copy> FromJson for [T; 2] {
+        fn from_json(value: &Value) -> Option<Self> {
+            let array = value.as_array().filter(|a| a.len() == 2)?;
+            Some([T::from_json(&array[0])?, T::from_json(&array[1])?])
+        }
+    }
+
+    impl<T: FromJson + Default + Copy> FromJson for [T; 3] {
+        fn from_json(value: &Value) -> Option<Self> {
+            let array = value.as_array().filter(|a| a.len() == 3)?;
+            Some([
+                T::from_json(&array[0])?,
+                T::from_json(&array[1])?,
+                T::from_json(&array[2])?,
+            ])
+        }
+    }
+
+    /// A JSON object, named for error reporting.
+    #[derive(Clone, Copy)]
+    struct Object<'a> {
+        name: &'a str,
+        value: &'a Value,
+    }
+
+    impl<'a> Object<'a> {
+        fn error(&self, key: &str) -> ProfileError {
+            ProfileError::InvalidField(format!("{}.{}", self.name, key))
+        }
+
+        fn object(&self, key: &'a str) -> Result<Self, ProfileError> {
+            match self.value.get(key) {
+                Some(value) if value.is_object() => Ok(Object { name: key, value }),
+                _ => Err(self.error(key)),
+            }
+        }
+
+        fn array(&self, key: &'a str) -> Result<Vec<Self>, ProfileError> {
+            let array = self
+                .value
+                .get(key)
+                .and_then(Value::as_array)
+                .ok_or_else(|| self.error(key))?;
+            Ok(array
+                .iter()
+                .map(|value| Object { name: key, value })
+                .collect())
+        }
+
+        /// Reads an optional field, failing if it has an unexpected type.
+        fn get<T: FromJson>(&self, key: &str) -> Result<Option<T>, ProfileError> {
+            match self.value.get(key) {
+                Some(value) => T::from_json(value).map(Some).ok_or_else(|| self.error(key)),
+                None => Ok(None),
+            }
+        }
+
+        fn require<T: FromJson>(&self, key: &str) -> Result<T, ProfileError> {
+            self.get(key)?.ok_or_else(|| self.error(key))
+        }
+    }
+
+    /// Assigns the fields present in the object, leaving the rest untouched.
+    macro_rules! read_fields {
+        ($object:expr, { $($target:expr => $key:expr,)* }) => {
+            $(
+                if let Some(value) = $object.get($key)? {
+                    $target = value;
+                }
+            )*
+        };
+    }
+
+    const VK_QUEUE_GRAPHICS_BIT: u32 = 0x1;
+    const VK_QUEUE_COMPUTE_BIT: u32 = 0x2;
+    const VK_QUEUE_TRANSFER_BIT: u32 = 0x4;
+    const VK_QUEUE_SPARSE_BINDING_BIT: u32 = 0x8;
+
+    const VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT: u32 = 0x1;
+    const VK_FORMAT_FEATURE_STORAGE_IMAGE_BIT: u32 = 0x2;
+    const VK_FORMAT_FEATURE_STORAGE_IMAGE_ATOMIC_BIT: u32 = 0x4;
+    const VK_FORMAT_FEATURE_COLOR_ATTACHMENT_BIT: u32 = 0x80;
+    const VK_FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT: u32 = 0x100;
+    const VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT: u32 = 0x200;
+    const VK_FORMAT_FEATURE_BLIT_SRC_BIT: u32 = 0x400;
+    const VK_FORMAT_FEATURE_BLIT_DST_BIT: u32 = 0x800;
+    const VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT: u32 = 0x1000;
+    const VK_FORMAT_FEATURE_TRANSFER_SRC_BIT: u32 = 0x4000;
+    const VK_FORMAT_FEATURE_TRANSFER_DST_BIT: u32 = 0x8000;
+    const VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_MINMAX_BIT: u32 = 0x10000;
+
+    /// Core Vulkan features and the HAL features they enable.
+    const FEATURES: &[(&str, Features)] = &[
+        ("robustBufferAccess", Features::ROBUST_BUFFER_ACCESS),
+        ("fullDrawIndexUint32", Features::FULL_DRAW_INDEX_U32),
+        ("imageCubeArray", Features::IMAGE_CUBE_ARRAY),
+        ("independentBlend", Features::INDEPENDENT_BLENDING),
+        ("geometryShader", Features::GEOMETRY_SHADER),
+        ("tessellationShader", Features::TESSELLATION_SHADER),
+        ("sampleRateShading", Features::SAMPLE_RATE_SHADING),
+        ("dualSrcBlend", Features::DUAL_SRC_BLENDING),
+        ("logicOp", Features::LOGIC_OP),
+        ("multiDrawIndirect", Features::MULTI_DRAW_INDIRECT),
+        (
+            "drawIndirectFirstInstance",
+            Features::DRAW_INDIRECT_FIRST_INSTANCE,
+        ),
+        ("depthClamp", Features::DEPTH_CLAMP),
+        ("depthBiasClamp", Features::DEPTH_BIAS_CLAMP),
+        ("fillModeNonSolid", Features::NON_FILL_POLYGON_MODE),
+        ("depthBounds", Features::DEPTH_BOUNDS),
+        ("wideLines", Features::LINE_WIDTH),
+        ("largePoints", Features::POINT_SIZE),
+        ("alphaToOne", Features::ALPHA_TO_ONE),
+        ("multiViewport", Features::MULTI_VIEWPORTS),
+        ("samplerAnisotropy", Features::SAMPLER_ANISOTROPY),
+        ("textureCompressionETC2", Features::FORMAT_ETC2),
+        ("textureCompressionASTC_LDR", Features::FORMAT_ASTC_LDR),
+        ("textureCompressionBC", Features::FORMAT_BC),
+        ("occlusionQueryPrecise", Features::PRECISE_OCCLUSION_QUERY),
+        (
+            "pipelineStatisticsQuery",
+            Features::PIPELINE_STATISTICS_QUERY,
+        ),
+        (
+            "vertexPipelineStoresAndAtomics",
+            Features::VERTEX_STORES_AND_ATOMICS,
+        ),
+        (
+            "fragmentStoresAndAtomics",
+            Features::FRAGMENT_STORES_AND_ATOMICS,
+        ),
+        (
+            "shaderTessellationAndGeometryPointSize",
+            Features::SHADER_TESSELLATION_AND_GEOMETRY_POINT_SIZE,
+        ),
+        (
+            "shaderImageGatherExtended",
+            Features::SHADER_IMAGE_GATHER_EXTENDED,
+        ),
+        (
+            "shaderStorageImageExtendedFormats",
+            Features::SHADER_STORAGE_IMAGE_EXTENDED_FORMATS,
+        ),
+        (
+            "shaderStorageImageMultisample",
+            Features::SHADER_STORAGE_IMAGE_MULTISAMPLE,
+        ),
+        (
+            "shaderStorageImageReadWithoutFormat",
+            Features::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT,
+        ),
+        (
+            "shaderStorageImageWriteWithoutFormat",
+            Features::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT,
+        ),
+        (
+            "shaderUniformBufferArrayDynamicIndexing",
+            Features::SHADER_UNIFORM_BUFFER_ARRAY_DYNAMIC_INDEXING,
+        ),
+        (
+            "shaderSampledImageArrayDynamicIndexing",
+            Features::SHADER_SAMPLED_IMAGE_ARRAY_DYNAMIC_INDEXING,
+        ),
+        (
+            "shaderStorageBufferArrayDynamicIndexing",
+            Features::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING,
+        ),
+        (
+            "shaderStorageImageArrayDynamicIndexing",
+            Features::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING,
+        ),
+        ("shaderClipDistance", Features::SHADER_CLIP_DISTANCE),
+        ("shaderCullDistance", Features::SHADER_CULL_DISTANCE),
+        ("shaderFloat64", Features::SHADER_FLOAT64),
+        ("shaderInt64", Features::SHADER_INT64),
+        ("shaderInt16", Features::SHADER_INT16),
+        (
+            "shaderResourceResidency",
+            Features::SHADER_RESOURCE_RESIDENCY,
+        ),
+        ("shaderResourceMinLod", Features::SHADER_RESOURCE_MIN_LOD),
+        ("sparseBinding", Features::SPARSE_BINDING),
+        ("sparseResidencyBuffer", Features::SPARSE_RESIDENCY_BUFFER),
+        (
+            "sparseResidencyImage2D",
+            Features::SPARSE_RESIDENCY_IMAGE_2D,
+        ),
+        (
+            "sparseResidencyImage3D",
+            Features::SPARSE_RESIDENCY_IMAGE_3D,
+        ),
+        (
+            "sparseResidency2Samples",
+            Features::SPARSE_RESIDENCY_2_SAMPLES,
+        ),
+        (
+            "sparseResidency4Samples",
+            Features::SPARSE_RESIDENCY_4_SAMPLES,
+        ),
+        (
+            "sparseResidency8Samples",
+            Features::SPARSE_RESIDENCY_8_SAMPLES,
+        ),
+        (
+            "sparseResidency16Samples",
+            Features::SPARSE_RESIDENCY_16_SAMPLES,
+        ),
+        ("sparseResidencyAliased", Features::SPARSE_RESIDENCY_ALIASED),
+        (
+            "variableMultisampleRate",
+            Features::VARIABLE_MULTISAMPLE_RATE,
+        ),
+        ("inheritedQueries", Features::INHERITED_QUERIES),
+    ];
+
+    pub(super) fn parse(root: &Value) -> Result<Profile, ProfileError> {
+        let root = Object {
+            name: "",
+            value: root,
+        };
+        let properties = root.object("VkPhysicalDeviceProperties")?;
+        // Vulkan 1.1 has separate bits for transfers, before that blits imply them
+        let api_version: u32 = properties.get("apiVersion")?.unwrap_or(0);
+        let supports_transfer_bits = api_version >= (1 << 22 | 1 << 12);
+
+        let limits = properties.object("limits")?;
+        let profile = Profile {
+            info: parse_info(properties)?,
+            features: parse_features(root.object("VkPhysicalDeviceFeatures")?)?,
+            properties: hal::PhysicalDeviceProperties {
+                limits: parse_limits(limits)?,
+                downlevel: hal::DownlevelProperties::all_enabled(),
+                dynamic_pipeline_states: parse_dynamic_states(root)?,
+                ..Default::default()
+            },
+            memory_properties: parse_memory(root.object("VkPhysicalDeviceMemoryProperties")?)?,
+            formats: parse_formats(
+                &root.array("ArrayOfVkFormatProperties")?,
+                supports_transfer_bits,
+            )?,
+            // Formats missing from the dump aren't supported
+            default_format_properties: format::Properties::default(),
+            queue_families: parse_queue_families(&root.array("ArrayOfVkQueueFamilyProperties")?)?,
+            timestamp_period: limits.get("timestampPeriod")?.unwrap_or(1.0),
+        };
+        Ok(profile)
+    }
+
+    fn parse_info(properties: Object) -> Result<adapter::AdapterInfo, ProfileError> {
+        let name = properties
+            .value
+            .get("deviceName")
+            .and_then(Value::as_str)
+            .ok_or_else(|| properties.error("deviceName"))?;
+        let device_type = match properties.require::<u32>("deviceType")? {
+            1 => adapter::DeviceType::IntegratedGpu,
+            2 => adapter::DeviceType::DiscreteGpu,
+            3 => adapter::DeviceType::VirtualGpu,
+            4 => adapter::DeviceType::Cpu,
+            _ => adapter::DeviceType::Other,
+        };
+        Ok(adapter::AdapterInfo {
+            name: name.to_string(),
+            vendor: properties.require("vendorID")?,
+            device: properties.require("deviceID")?,
+            device_type,
+        })
+    }
+
+    fn parse_features(features: Object) -> Result<Features, ProfileError> {
+        // Features gfx-backend-vulkan exposes on every device
+        let mut bits = Features::TRIANGLE_FAN
+            | Features::SEPARATE_STENCIL_REF_VALUES
+            | Features::SAMPLER_MIP_LOD_BIAS
+            | Features::SAMPLER_BORDER_COLOR
+            | Features::MUTABLE_COMPARISON_SAMPLER
+            | Features::MUTABLE_UNNORMALIZED_SAMPLER
+            | Features::TEXTURE_DESCRIPTOR_ARRAY
+            | Features::BUFFER_DESCRIPTOR_ARRAY;
+        for &(key, feature) in FEATURES {
+            if features.get(key)?.unwrap_or(false) {
+                bits |= feature;
+            }
+        }
+        Ok(bits)
+    }
+
+    fn parse_dynamic_states(root: Object) -> Result<hal::DynamicStates, ProfileError> {
+        use hal::DynamicStates as Ds;
+        // States gfx-backend-vulkan exposes on every device
+        let mut states = Ds::VIEWPORT
+            | Ds::SCISSOR
+            | Ds::LINE_WIDTH
+            | Ds::BLEND_CONSTANTS
+            | Ds::DEPTH_BIAS
+            | Ds::DEPTH_BOUNDS
+            | Ds::STENCIL_READ_MASK
+            | Ds::STENCIL_WRITE_MASK
+            | Ds::STENCIL_REFERENCE;
+        // Older dumps don't list the extensions
+        let extensions = match root.value.get("ArrayOfVkExtensionProperties") {
+            Some(_) => root.array("ArrayOfVkExtensionProperties")?,
+            None => Vec::new(),
+        };
+        let mut extended_dynamic_state = false;
+        for extension in extensions {
+            let name = extension
+                .value
+                .get("extensionName")
+                .and_then(Value::as_str)
+                .ok_or_else(|| extension.error("extensionName"))?;
+            extended_dynamic_state |= name == "VK_EXT_extended_dynamic_state";
+        }
+        // The feature struct is only in dumps of newer devices
+        let features_key = "VkPhysicalDeviceExtendedDynamicStateFeaturesEXT";
+        if root.value.get(features_key).is_some() {
+            let features = root.object(features_key)?;
+            extended_dynamic_state &= features.get("extendedDynamicState")?.unwrap_or(false);
+        }
+        if extended_dynamic_state {
+            states |= Ds::CULL_MODE
+                | Ds::FRONT_FACE
+                | Ds::PRIMITIVE_TOPOLOGY
+                | Ds::VERTEX_INPUT_BINDING_STRIDE
+                | Ds::DEPTH_TEST_ENABLE
+                | Ds::DEPTH_WRITE_ENABLE
+                | Ds::DEPTH_COMPARE_OP
+                | Ds::STENCIL_OP;
+        }
+        Ok(states)
+    }
+
+    fn parse_limits(limits: Object) -> Result<hal::Limits, ProfileError> {
+        let mut l = hal::Limits::default();
+        let d = &mut l.descriptor_limits;
+        read_fields!(limits, {
+            d.max_per_stage_descriptor_samplers => "maxPerStageDescriptorSamplers",
+            d.max_per_stage_descriptor_uniform_buffers => "maxPerStageDescriptorUniformBuffers",
+            d.max_per_stage_descriptor_storage_buffers => "maxPerStageDescriptorStorageBuffers",
+            d.max_per_stage_descriptor_sampled_images => "maxPerStageDescriptorSampledImages",
+            d.max_per_stage_descriptor_storage_images => "maxPerStageDescriptorStorageImages",
+            d.max_per_stage_descriptor_input_attachments => "maxPerStageDescriptorInputAttachments",
+            d.max_per_stage_resources => "maxPerStageResources",
+            d.max_descriptor_set_samplers => "maxDescriptorSetSamplers",
+            d.max_descriptor_set_uniform_buffers => "maxDescriptorSetUniformBuffers",
+            d.max_descriptor_set_uniform_buffers_dynamic => "maxDescriptorSetUniformBuffersDynamic",
+            d.max_descriptor_set_storage_buffers => "maxDescriptorSetStorageBuffers",
+            d.max_descriptor_set_storage_buffers_dynamic => "maxDescriptorSetStorageBuffersDynamic",
+            d.max_descriptor_set_sampled_images => "maxDescriptorSetSampledImages",
+            d.max_descriptor_set_storage_images => "maxDescriptorSetStorageImages",
+            d.max_descriptor_set_input_attachments => "maxDescriptorSetInputAttachments",
+        });
+        let mut framebuffer_extent = [0u32; 3];
+        read_fields!(limits, {
+            l.max_image_1d_size => "maxImageDimension1D",
+            l.max_image_2d_size => "maxImageDimension2D",
+            l.max_image_3d_size => "maxImageDimension3D",
+            l.max_image_cube_size => "maxImageDimensionCube",
+            l.max_image_array_layers => "maxImageArrayLayers",
+            l.max_texel_elements => "maxTexelBufferElements",
+            l.max_uniform_buffer_range => "maxUniformBufferRange",
+            l.max_storage_buffer_range => "maxStorageBufferRange",
+            l.max_push_constants_size => "maxPushConstantsSize",
+            l.max_memory_allocation_count => "maxMemoryAllocationCount",
+            l.max_sampler_allocation_count => "maxSamplerAllocationCount",
+            l.max_bound_descriptor_sets => "maxBoundDescriptorSets",
+            l.max_framebuffer_layers => "maxFramebufferLayers",
+            l.max_vertex_input_attributes => "maxVertexInputAttributes",
+            l.max_vertex_input_bindings => "maxVertexInputBindings",
+            l.max_vertex_input_attribute_offset => "maxVertexInputAttributeOffset",
+            l.max_vertex_input_binding_stride => "maxVertexInputBindingStride",
+            l.max_vertex_output_components => "maxVertexOutputComponents",
+            l.max_patch_size => "maxTessellationPatchSize",
+            l.max_geometry_shader_invocations => "maxGeometryShaderInvocations",
+            l.max_geometry_input_components => "maxGeometryInputComponents",
+            l.max_geometry_output_components => "maxGeometryOutputComponents",
+            l.max_geometry_output_vertices => "maxGeometryOutputVertices",
+            l.max_geometry_total_output_components => "maxGeometryTotalOutputComponents",
+            l.max_fragment_input_components => "maxFragmentInputComponents",
+            l.max_fragment_output_attachments => "maxFragmentOutputAttachments",
+            l.max_fragment_dual_source_attachments => "maxFragmentDualSrcAttachments",
+            l.max_fragment_combined_output_resources => "maxFragmentCombinedOutputResources",
+            l.max_compute_shared_memory_size => "maxComputeSharedMemorySize",
+            l.max_compute_work_group_count => "maxComputeWorkGroupCount",
+            l.max_compute_work_group_invocations => "maxComputeWorkGroupInvocations",
+            l.max_compute_work_group_size => "maxComputeWorkGroupSize",
+            l.max_draw_indexed_index_value => "maxDrawIndexedIndexValue",
+            l.max_draw_indirect_count => "maxDrawIndirectCount",
+            l.max_sampler_lod_bias => "maxSamplerLodBias",
+            l.max_sampler_anisotropy => "maxSamplerAnisotropy",
+            l.max_viewports => "maxViewports",
+            l.max_viewport_dimensions => "maxViewportDimensions",
+            framebuffer_extent[0] => "maxFramebufferWidth",
+            framebuffer_extent[1] => "maxFramebufferHeight",
+            framebuffer_extent[2] => "maxFramebufferLayers",
+            l.min_memory_map_alignment => "minMemoryMapAlignment",
+            l.buffer_image_granularity => "bufferImageGranularity",
+            l.min_texel_buffer_offset_alignment => "minTexelBufferOffsetAlignment",
+            l.min_uniform_buffer_offset_alignment => "minUniformBufferOffsetAlignment",
+            l.min_storage_buffer_offset_alignment => "minStorageBufferOffsetAlignment",
+            l.framebuffer_color_sample_counts => "framebufferColorSampleCounts",
+            l.framebuffer_depth_sample_counts => "framebufferDepthSampleCounts",
+            l.framebuffer_stencil_sample_counts => "framebufferStencilSampleCounts",
+            l.timestamp_compute_and_graphics => "timestampComputeAndGraphics",
+            l.max_color_attachments => "maxColorAttachments",
+            l.standard_sample_locations => "standardSampleLocations",
+            l.optimal_buffer_copy_offset_alignment => "optimalBufferCopyOffsetAlignment",
+            l.optimal_buffer_copy_pitch_alignment => "optimalBufferCopyRowPitchAlignment",
+            l.non_coherent_atom_size => "nonCoherentAtomSize",
+        });
+        l.max_framebuffer_extent = hal::image::Extent {
+            width: framebuffer_extent[0],
+            height: framebuffer_extent[1],
+            depth: framebuffer_extent[2],
+        };
+        l.min_vertex_input_binding_stride_alignment = 1;
+        Ok(l)
+    }
+
+    fn parse_memory(memory: Object) -> Result<adapter::MemoryProperties, ProfileError> {
+        let memory_types = memory
+            .array("memoryTypes")?
+            .iter()
+            .map(|ty| {
+                Ok(adapter::MemoryType {
+                    properties: memory::Properties::from_bits_truncate(
+                        ty.require("propertyFlags")?,
+                    ),
+                    heap_index: ty.require("heapIndex")?,
+                })
+            })
+            .collect::<Result<_, ProfileError>>()?;
+        let memory_heaps = memory
+            .array("memoryHeaps")?
+            .iter()
+            .map(|heap| {
+                Ok(adapter::MemoryHeap {
+                    size: heap.require("size")?,
+                    flags: memory::HeapFlags::from_bits_truncate(heap.require("flags")?),
+                })
+            })
+            .collect::<Result<_, ProfileError>>()?;
+        Ok(adapter::MemoryProperties {
+            memory_types,
+            memory_heaps,
+        })
+    }
+
+    fn parse_queue_families(families: &[Object]) -> Result<Vec<QueueFamilyProfile>, ProfileError> {
+        let mut queue_families = Vec::new();
+        for family in families {
+            let flags: u32 = family.require("queueFlags")?;
+            let queue_type = if flags & VK_QUEUE_GRAPHICS_BIT != 0 {
+                if flags & VK_QUEUE_COMPUTE_BIT != 0 {
+                    queue::QueueType::General
+                } else {
+                    queue::QueueType::Graphics
+                }
+            } else if flags & VK_QUEUE_COMPUTE_BIT != 0 {
+                queue::QueueType::Compute
+            } else if flags & VK_QUEUE_TRANSFER_BIT != 0 {
+                queue::QueueType::Transfer
+            } else {
+                return Err(family.error("queueFlags"));
+            };
+            queue_families.push(QueueFamilyProfile {
+                queue_type,
+                max_queues: family.require("queueCount")?,
+                supports_sparse_binding: flags & VK_QUEUE_SPARSE_BINDING_BIT != 0,
+            });
+        }
+        Ok(queue_families)
+    }
+
+    fn parse_formats(
+        formats: &[Object],
+        supports_transfer_bits: bool,
+    ) -> Result<HashMap<format::Format, format::Properties>, ProfileError> {
+        let mut map = HashMap::new();
+        for properties in formats {
+            let id: u32 = properties.require("formatID")?;
+            if id == 0 || id as usize >= format::NUM_FORMATS {
+                // Not expressible in HAL
+                continue;
+            }
+            // Safe due to equivalence of HAL format values and Vulkan values
+            let format: format::Format = unsafe { mem::transmute(id) };
+            let linear = properties.require("linearTilingFeatures")?;
+            let optimal = properties.require("optimalTilingFeatures")?;
+            let buffer = properties.require("bufferFeatures")?;
+            map.insert(
+                format,
+                format::Properties {
+                    linear_tiling: map_image_features(linear, supports_transfer_bits),
+                    optimal_tiling: map_image_features(optimal, supports_transfer_bits),
+                    buffer_features: format::BufferFeature::from_bits_truncate(buffer),
+                    drm_format_properties: Vec::new(),
+                },
+            );
+        }
+        Ok(map)
+    }
+
+    fn map_image_features(features: u32, supports_transfer_bits: bool) -> format::ImageFeature {
+        use format::ImageFeature as If;
+        let mut mapped = If::empty();
+        let pairs = [
+            (VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT, If::SAMPLED),
+            (
+                VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT,
+                If::SAMPLED_LINEAR,
+            ),
+            (
+                VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_MINMAX_BIT,
+                If::SAMPLED_MINMAX,
+            ),
+            (
+                VK_FORMAT_FEATURE_STORAGE_IMAGE_BIT,
+                If::STORAGE | If::STORAGE_READ_WRITE,
+            ),
+            (
+                VK_FORMAT_FEATURE_STORAGE_IMAGE_ATOMIC_BIT,
+                If::STORAGE_ATOMIC,
+            ),
+            (VK_FORMAT_FEATURE_COLOR_ATTACHMENT_BIT, If::COLOR_ATTACHMENT),
+            (
+                VK_FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT,
+                If::COLOR_ATTACHMENT_BLEND,
+            ),
+            (
+                VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT,
+                If::DEPTH_STENCIL_ATTACHMENT,
+            ),
+            (VK_FORMAT_FEATURE_BLIT_SRC_BIT, If::BLIT_SRC),
+            (VK_FORMAT_FEATURE_BLIT_DST_BIT, If::BLIT_DST),
+        ];
+        for &(bit, feature) in pairs.iter() {
+            if features & bit != 0 {
+                mapped |= feature;
+            }
+        }
+        if supports_transfer_bits {
+            if features & VK_FORMAT_FEATURE_TRANSFER_SRC_BIT != 0 {
+                mapped |= If::TRANSFER_SRC;
+            }
+            if features & VK_FORMAT_FEATURE_TRANSFER_DST_BIT != 0 {
+                mapped |= If::TRANSFER_DST;
+            }
+        } else {
+            if mapped.contains(If::BLIT_SRC) {
+                mapped |= If::TRANSFER_SRC;
+            }
+            if mapped.contains(If::BLIT_DST) {
+                mapped |= If::TRANSFER_DST;
+            }
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+    use hal::{format, image};
+    use std::iter;
+
+    #[test]
+    fn image_format_properties() {
+        let rgba8 = format::Properties {
+            optimal_tiling: format::ImageFeature::SAMPLED | format::ImageFeature::TRANSFER_DST,
+            ..Default::default()
+        };
+        let profile = Profile::default()
+            .with_limits(hal::Limits {
+                max_image_2d_size: 4096,
+                max_image_array_layers: 256,
+                framebuffer_color_sample_counts: 0b101,
+                ..Default::default()
+            })
+            .with_default_format_properties(format::Properties::default())
+            .with_format(format::Format::Rgba8Srgb, rgba8);
+
+        let properties = |format, tiling, usage| {
+            profile.image_format_properties(
+                format,
+                2,
+                tiling,
+                usage,
+                image::ViewCapabilities::empty(),
+            )
+        };
+        let sampled = image::Usage::SAMPLED | image::Usage::TRANSFER_DST;
+        let limits = properties(format::Format::Rgba8Srgb, image::Tiling::Optimal, sampled)
+            .expect("Sampled images are supported");
+        assert_eq!(limits.max_extent.width, 4096);
+        assert_eq!(limits.max_levels, 13);
+        assert_eq!(limits.max_layers, 256);
+        assert_eq!(limits.sample_count_mask, 0b101);
+        assert_eq!(
+            properties(format::Format::Rgba8Srgb, image::Tiling::Linear, sampled),
+            None
+        );
+        assert_eq!(
+            properties(
+                format::Format::Rgba8Srgb,
+                image::Tiling::Optimal,
+                image::Usage::STORAGE
+            ),
+            None
+        );
+        assert_eq!(
+            properties(format::Format::Bgra8Srgb, image::Tiling::Optimal, sampled),
+            None
+        );
+    }
+
+    #[test]
+    fn subgroup_properties() {
+        use hal::{adapter::PhysicalDevice as _, Instance as _};
+
+        // Subgroups aren't supported by default
+        let profile = Profile::default();
+        assert_eq!(profile.properties.subgroup.subgroup_size, 0);
+        assert!(profile.properties.subgroup.supported_stages.is_empty());
+
+        let subgroup = hal::SubgroupProperties {
+            subgroup_size: 32,
+            supported_stages: hal::pso::ShaderStageFlags::COMPUTE,
+            supported_operations: hal::SubgroupOperations::BASIC | hal::SubgroupOperations::BALLOT,
+            quad_operations_in_all_stages: false,
+        };
+        let max_image_2d_size = profile.properties.limits.max_image_2d_size;
+        let instance = crate::Instance::with_profiles(iter::once(profile.with_subgroup(subgroup)));
+        let adapter = instance.enumerate_adapters().remove(0);
+        let properties = adapter.physical_device.properties();
+        assert_eq!(properties.subgroup, subgroup);
+        assert_eq!(properties.limits.max_image_2d_size, max_image_2d_size);
+    }
+
+    #[cfg(feature = "profiles")]
+    #[test]
+    fn load_ron() {
+        let profile =
+            Profile::load(concat!(env!("CARGO_MANIFEST_DIR"), "/data/mobile.ron")).unwrap();
+        assert_eq!(profile.info.name, "Mobile GPU");
+        assert!(profile.features.contains(hal::Features::FORMAT_ETC2));
+        assert!(!profile.features.contains(hal::Features::FORMAT_BC));
+        assert_eq!(profile.properties.limits.max_image_2d_size, 4096);
+        assert!(!profile.properties.downlevel.compute_shaders);
+        assert_eq!(profile.properties.subgroup.subgroup_size, 16);
+        assert_eq!(
+            profile.properties.subgroup.supported_operations,
+            hal::SubgroupOperations::BASIC | hal::SubgroupOperations::VOTE
+        );
+        assert_eq!(
+            profile
+                .format_properties(format::Format::Rgba16Sfloat)
+                .optimal_tiling,
+            format::ImageFeature::SAMPLED
+        );
+        // Unlisted fields keep the default profile values
+        assert_eq!(profile.queue_families, Profile::default().queue_families);
+    }
+
+    #[cfg(feature = "profiles")]
+    #[test]
+    fn load_vulkaninfo() {
+        use super::QueueFamilyProfile;
+        use hal::queue;
+
+        let profile =
+            Profile::load(concat!(env!("CARGO_MANIFEST_DIR"), "/data/igpu.json")).unwrap();
+        assert_eq!(profile.info.vendor, 0x8086);
+        assert_eq!(
+            profile.info.device_type,
+            hal::adapter::DeviceType::IntegratedGpu
+        );
+        assert!(profile
+            .features
+            .contains(hal::Features::GEOMETRY_SHADER | hal::Features::FORMAT_BC));
+        assert!(!profile.features.contains(hal::Features::SHADER_FLOAT64));
+        let limits = &profile.properties.limits;
+        assert_eq!(limits.max_image_2d_size, 16384);
+        assert_eq!(limits.max_compute_work_group_size, [1024, 1024, 64]);
+        assert_eq!(limits.max_framebuffer_extent.height, 16384);
+        assert!((profile.timestamp_period - 52.083_332).abs() < 1e-3);
+        assert_eq!(profile.memory_properties.memory_types.len(), 2);
+        assert_eq!(
+            profile.queue_families,
+            vec![QueueFamilyProfile {
+                queue_type: queue::QueueType::General,
+                max_queues: 1,
+                supports_sparse_binding: true,
+            }]
+        );
+        let rgba8 = profile.format_properties(format::Format::Rgba8Unorm);
+        assert!(rgba8.optimal_tiling.contains(
+            format::ImageFeature::SAMPLED_LINEAR
+                | format::ImageFeature::COLOR_ATTACHMENT
+                | format::ImageFeature::TRANSFER_SRC
+        ));
+        assert!(rgba8
+            .buffer_features
+            .contains(format::BufferFeature::VERTEX));
+        assert_eq!(
+            profile.format_properties(format::Format::Etc2R8g8b8Unorm),
+            &format::Properties::default()
+        );
+        // The dump doesn't list `VK_EXT_extended_dynamic_state`
+        let states = profile.properties.dynamic_pipeline_states;
+        assert!(
+            states.contains(hal::DynamicStates::VIEWPORT | hal::DynamicStates::STENCIL_REFERENCE)
+        );
+        assert!(!states.intersects(hal::DynamicStates::CULL_MODE | hal::DynamicStates::STENCIL_OP));
+    }
+
+    #[cfg(feature = "profiles")]
+    #[test]
+    fn load_vulkaninfo_extended_dynamic_state() {
+        use hal::DynamicStates;
+        use serde_json::json;
+
+        let text = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/data/igpu.json"))
+            .unwrap();
+        let mut dump: serde_json::Value = serde_json::from_str(&text).unwrap();
+        dump["ArrayOfVkExtensionProperties"] = json!([{
+            "extensionName": "VK_EXT_extended_dynamic_state",
+            "specVersion": 1,
+        }]);
+        let profile = Profile::from_vulkaninfo(&dump.to_string()).unwrap();
+        assert!(profile
+            .properties
+            .dynamic_pipeline_states
+            .contains(DynamicStates::CULL_MODE | DynamicStates::STENCIL_OP));
+
+        // The feature can be disabled even if the extension is listed
+        dump["VkPhysicalDeviceExtendedDynamicStateFeaturesEXT"] =
+            json!({ "extendedDynamicState": false });
+        let profile = Profile::from_vulkaninfo(&dump.to_string()).unwrap();
+        assert!(!profile
+            .properties
+            .dynamic_pipeline_states
+            .contains(DynamicStates::CULL_MODE));
+    }
+}
@@ -0,0 +1,609 @@
+//! Query pools, written by the queue workers and read back on the host.
+
+use crate::sync::Notifier;
+
+#[cfg(any(unix, windows))]
+use gfx_auxil::host_monotonic_ns;
+use hal::{buffer, query};
+
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+/// Synthetic counter sampled by the performance queries of every queue family.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Counter {
+    ElapsedTime,
+    Commands,
+    AverageCommandTime,
+}
+
+impl Counter {
+    /// Counters in the order they're enumerated in.
+    pub const ALL: [Counter; 3] = [
+        Counter::ElapsedTime,
+        Counter::Commands,
+        Counter::AverageCommandTime,
+    ];
+
+    pub fn describe(self) -> query::PerformanceCounter {
+        let (name, category, description, unit, storage) = match self {
+            Counter::ElapsedTime => (
+                "Elapsed time",
+                "Timing",
+                "Host time spent between the start and end of the query",
+                query::PerformanceCounterUnit::Nanoseconds,
+                query::PerformanceCounterStorage::Uint64,
+            ),
+            Counter::Commands => (
+                "Commands",
+                "Work",
+                "Commands recorded between the start and end of the query",
+                query::PerformanceCounterUnit::Generic,
+                query::PerformanceCounterStorage::Uint32,
+            ),
+            Counter::AverageCommandTime => (
+                "Average command time",
+                "Timing",
+                "Elapsed time divided by the number of commands",
+                query::PerformanceCounterUnit::Nanoseconds,
+                query::PerformanceCounterStorage::Float64,
+            ),
+        };
+        query::PerformanceCounter {
+            name: name.to_string(),
+            category: category.to_string(),
+            description: description.to_string(),
+            unit,
+            storage,
+        }
+    }
+
+    /// Returns the raw result of the counter, in the layout of its storage.
+    fn sample(self, elapsed_ns: u64, commands: u32) -> u64 {
+        let mut raw = [0; query::PERFORMANCE_COUNTER_RESULT_SIZE];
+        match self {
+            Counter::ElapsedTime => raw.copy_from_slice(&elapsed_ns.to_ne_bytes()),
+            Counter::Commands => raw[..4].copy_from_slice(&commands.to_ne_bytes()),
+            Counter::AverageCommandTime => {
+                let average = elapsed_ns as f64 / f64::from(commands.max(1));
+                raw.copy_from_slice(&average.to_ne_bytes());
+            }
+        }
+        u64::from_ne_bytes(raw)
+    }
+}
+
+/// Features removed from the profile because the calibrated timestamps need a
+/// platform host clock to be matched with, which only Unix and Windows provide.
+#[cfg(any(unix, windows))]
+pub(crate) const UNSUPPORTED_HOST_CLOCK_FEATURES: hal::Features = hal::Features::empty();
+#[cfg(not(any(unix, windows)))]
+pub(crate) const UNSUPPORTED_HOST_CLOCK_FEATURES: hal::Features =
+    hal::Features::CALIBRATED_TIMESTAMPS;
+
+/// Reads the nanoseconds since the first reading, on the platforms without a
+/// platform host clock, where calibrated timestamps aren't supported.
+#[cfg(not(any(unix, windows)))]
+fn host_monotonic_ns() -> u64 {
+    static START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Host clock the timestamps are read from.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Clock {
+    /// Reading of the host clock at the creation of the clock, in nanoseconds.
+    epoch_ns: u64,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+impl Clock {
+    pub fn new(period: f32) -> Self {
+        Clock {
+            epoch_ns: host_monotonic_ns(),
+            period,
+        }
+    }
+
+    pub fn period(&self) -> f32 {
+        self.period
+    }
+
+    /// Returns the current timestamp, in ticks since the creation of the clock.
+    pub fn now(&self) -> u64 {
+        self.calibrate().0
+    }
+
+    /// Returns the current timestamp along with the reading of the host clock
+    /// in nanoseconds, both taken from a single reading.
+    pub fn calibrate(&self) -> (u64, u64) {
+        let host_ns = host_monotonic_ns();
+        let nanos = host_ns.saturating_sub(self.epoch_ns);
+        let ticks = (nanos as f64 / f64::from(self.period)) as u64;
+        (ticks, host_ns)
+    }
+}
+
+/// Returns the size of a single value and the number of values written per query.
+fn result_layout(flags: query::ResultFlags) -> (usize, usize) {
+    let value_size = if flags.contains(query::ResultFlags::BITS_64) {
+        8
+    } else {
+        4
+    };
+    let values_per_query = if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+        2
+    } else {
+        1
+    };
+    (value_size, values_per_query)
+}
+
+/// Returns the number of bytes written by a copy of the query results.
+pub(crate) fn results_size(
+    queries: &Range<query::Id>,
+    stride: buffer::Stride,
+    flags: query::ResultFlags,
+) -> u64 {
+    let (value_size, values_per_query) = result_layout(flags);
+    match queries.end - queries.start {
+        0 => 0,
+        count => u64::from(count - 1) * u64::from(stride) + (value_size * values_per_query) as u64,
+    }
+}
+
+/// Results of the queries of a pool, shared with the queues.
+#[derive(Debug)]
+pub(crate) struct Queries {
+    notifier: Arc<Notifier>,
+    clock: Clock,
+    /// Samples reported by the occlusion queries.
+    occlusion_samples: Arc<AtomicU64>,
+    /// Counters sampled by each query of a performance query pool.
+    counters: Vec<Counter>,
+    /// Start time of each running performance query.
+    starts: Mutex<Vec<Option<Instant>>>,
+    /// Result of each query, or `None` if it isn't available. Performance
+    /// queries have one result per counter.
+    results: Mutex<Vec<Option<u64>>>,
+}
+
+impl Queries {
+    /// Returns the indices of the results of the given queries.
+    fn slots(&self, queries: Range<query::Id>) -> Range<usize> {
+        let results_per_query = self.counters.len().max(1);
+        queries.start as usize * results_per_query..queries.end as usize * results_per_query
+    }
+
+    pub fn reset(&self, queries: Range<query::Id>) {
+        let mut results = self.results.lock().unwrap();
+        for result in &mut results[self.slots(queries)] {
+            *result = None;
+        }
+    }
+
+    fn write(&self, id: query::Id, value: u64) {
+        self.notifier
+            .notify(|| self.results.lock().unwrap()[id as usize] = Some(value));
+    }
+
+    pub fn end_occlusion(&self, id: query::Id) {
+        // Nothing gets rasterized, so the count is whatever the test set up
+        self.write(id, self.occlusion_samples.load(Ordering::Acquire));
+    }
+
+    pub fn write_timestamp(&self, id: query::Id) {
+        self.write(id, self.clock.now());
+    }
+
+    pub fn begin_performance(&self, id: query::Id) {
+        self.starts.lock().unwrap()[id as usize] = Some(Instant::now());
+    }
+
+    pub fn end_performance(&self, id: query::Id, commands: u32) {
+        let start = self.starts.lock().unwrap()[id as usize]
+            .take()
+            .expect("Performance query was never begun");
+        let elapsed_ns = start.elapsed().as_nanos() as u64;
+        let slots = self.slots(id..id + 1);
+        self.notifier.notify(|| {
+            let mut results = self.results.lock().unwrap();
+            for (result, counter) in results[slots].iter_mut().zip(&self.counters) {
+                *result = Some(counter.sample(elapsed_ns, commands));
+            }
+        });
+    }
+
+    /// Writes the results of the queries into `data`, following the Vulkan
+    /// layout rules, and returns whether all of them were available.
+    pub fn copy_results(
+        &self,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Stride,
+        flags: query::ResultFlags,
+    ) -> bool {
+        let range = self.slots(queries.clone());
+        if flags.contains(query::ResultFlags::WAIT) {
+            let results = &self.results;
+            self.notifier.wait_until(!0, || {
+                results.lock().unwrap()[range.clone()]
+                    .iter()
+                    .all(Option::is_some)
+            });
+        }
+        if !self.counters.is_empty() {
+            return self.copy_performance_results(range, data, stride, flags);
+        }
+
+        let (value_size, values_per_query) = result_layout(flags);
+        assert_eq!(
+            stride as usize % value_size,
+            0,
+            "Stride must be a multiple of the result size"
+        );
+
+        let results = self.results.lock().unwrap();
+        let mut all_available = true;
+        for (i, result) in results[range].iter().enumerate() {
+            let offset = i * stride as usize;
+            let query_data = &mut data[offset..offset + value_size * values_per_query];
+            let mut write = |index: usize, value: u64| {
+                let value_data = &mut query_data[index * value_size..(index + 1) * value_size];
+                if value_size == 8 {
+                    value_data.copy_from_slice(&value.to_ne_bytes());
+                } else {
+                    // Results that don't fit are truncated, like Vulkan allows
+                    value_data.copy_from_slice(&(value as u32).to_ne_bytes());
+                }
+            };
+            match *result {
+                Some(value) => write(0, value),
+                None => {
+                    all_available = false;
+                    if flags.contains(query::ResultFlags::PARTIAL) {
+                        write(0, 0);
+                    }
+                }
+            }
+            if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+                write(1, result.is_some() as u64);
+            }
+        }
+        all_available
+    }
+
+    /// Writes the results of performance queries, made of the raw result of
+    /// every counter, leaving the unavailable ones untouched.
+    fn copy_performance_results(
+        &self,
+        range: Range<usize>,
+        data: &mut [u8],
+        stride: buffer::Stride,
+        flags: query::ResultFlags,
+    ) -> bool {
+        assert!(
+            (flags - query::ResultFlags::WAIT).is_empty(),
+            "Performance query results can't be requested with {:?}",
+            flags
+        );
+        let value_size = query::PERFORMANCE_COUNTER_RESULT_SIZE;
+        assert_eq!(
+            stride as usize % value_size,
+            0,
+            "Stride must be a multiple of the result size"
+        );
+
+        let results = self.results.lock().unwrap();
+        let mut all_available = true;
+        for (i, query) in results[range].chunks(self.counters.len()).enumerate() {
+            if query.iter().any(Option::is_none) {
+                all_available = false;
+                continue;
+            }
+            let offset = i * stride as usize;
+            let query_data = &mut data[offset..offset + value_size * query.len()];
+            for (value_data, result) in query_data.chunks_mut(value_size).zip(query) {
+                value_data.copy_from_slice(&result.unwrap().to_ne_bytes());
+            }
+        }
+        all_available
+    }
+}
+
+#[derive(Debug)]
+pub struct QueryPool {
+    pub(crate) ty: query::Type,
+    pub(crate) queries: Arc<Queries>,
+}
+
+impl QueryPool {
+    /// Creates a pool of the given type. Performance query pools sample
+    /// `counters`, which is empty for the other types.
+    pub(crate) fn new(
+        ty: query::Type,
+        count: query::Id,
+        counters: Vec<Counter>,
+        notifier: &Arc<Notifier>,
+        clock: Clock,
+        occlusion_samples: &Arc<AtomicU64>,
+    ) -> Self {
+        let starts = match ty {
+            query::Type::PerformanceCounters => vec![None; count as usize],
+            _ => Vec::new(),
+        };
+        let results = count as usize * counters.len().max(1);
+        QueryPool {
+            ty,
+            queries: Arc::new(Queries {
+                notifier: Arc::clone(notifier),
+                clock,
+                occlusion_samples: Arc::clone(occlusion_samples),
+                counters,
+                starts: Mutex::new(starts),
+                results: Mutex::new(vec![None; results]),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile};
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::{CommandBuffer as _, CommandBufferFlags, Level},
+        device::Device as _,
+        memory::{AllocationFlags, Segment, SparseFlags},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso::PipelineStage,
+        query::{self, Query, ResultFlags},
+        queue::{Queue as _, QueueFamily as _},
+        MemoryTypeId,
+    };
+    use std::{iter, slice};
+
+    #[test]
+    fn occlusion_and_timestamps() {
+        let profile = Profile::default().with_timestamp_period(10.0);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+        assert_eq!(queue.timestamp_period(), 10.0);
+
+        unsafe {
+            let occlusion = device.create_query_pool(query::Type::Occlusion, 2).unwrap();
+            let timestamps = device.create_query_pool(query::Type::Timestamp, 2).unwrap();
+            let mut memory = device
+                .allocate_memory(MemoryTypeId(0), 16, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(16, buffer::Usage::TRANSFER_DST, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            device.set_occlusion_samples(0x1_0000_0007);
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.reset_query_pool(&occlusion, 0..2);
+            cmd_buf.reset_query_pool(&timestamps, 0..2);
+            cmd_buf.write_timestamp(
+                PipelineStage::TOP_OF_PIPE,
+                Query {
+                    pool: &timestamps,
+                    id: 0,
+                },
+            );
+            let query = Query {
+                pool: &occlusion,
+                id: 1,
+            };
+            cmd_buf.begin_query(query, query::ControlFlags::empty());
+            cmd_buf.end_query(Query {
+                pool: &occlusion,
+                id: 1,
+            });
+            cmd_buf.write_timestamp(
+                PipelineStage::BOTTOM_OF_PIPE,
+                Query {
+                    pool: &timestamps,
+                    id: 1,
+                },
+            );
+            cmd_buf.copy_query_pool_results(
+                &occlusion,
+                1..2,
+                &buffer,
+                0,
+                8,
+                ResultFlags::WAIT | ResultFlags::WITH_AVAILABILITY,
+            );
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+
+            // The second timestamp can't be earlier than the first one, and
+            // gets written after the occlusion query
+            let mut data = [0u64; 2];
+            let bytes = slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, 16);
+            let flags = ResultFlags::WAIT | ResultFlags::BITS_64;
+            assert_eq!(
+                device.get_query_pool_results(&timestamps, 0..2, bytes, 8, flags),
+                Ok(true)
+            );
+            assert!(data[0] <= data[1]);
+
+            // The first occlusion query never ran
+            let mut data = [!0u64; 4];
+            let bytes = slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, 32);
+            let flags = ResultFlags::BITS_64 | ResultFlags::WITH_AVAILABILITY;
+            assert_eq!(
+                device.get_query_pool_results(&occlusion, 0..2, bytes, 16, flags),
+                Ok(false)
+            );
+            assert_eq!(data, [!0, 0, 0x1_0000_0007, 1]);
+
+            queue.wait_idle().unwrap();
+            let ptr = device.map_memory(&mut memory, Segment::ALL).unwrap();
+            let results = slice::from_raw_parts(ptr as *const u32, 4);
+            assert_eq!(results, [7, 1, 0, 0]);
+            device.unmap_memory(&mut memory);
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+            device.destroy_query_pool(occlusion);
+            device.destroy_query_pool(timestamps);
+        }
+    }
+
+    #[test]
+    fn calibrated_timestamps() {
+        let profile = Profile::default()
+            .with_features(hal::Features::CALIBRATED_TIMESTAMPS)
+            .with_timestamp_period(10.0);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) =
+            crate::open_device(&instance, hal::Features::CALIBRATED_TIMESTAMPS);
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let timestamps = device.create_query_pool(query::Type::Timestamp, 1).unwrap();
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.reset_query_pool(&timestamps, 0..1);
+            cmd_buf.write_timestamp(
+                PipelineStage::BOTTOM_OF_PIPE,
+                Query {
+                    pool: &timestamps,
+                    id: 0,
+                },
+            );
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+            queue.wait_idle().unwrap();
+
+            // The host timestamp is a reading of the platform clock
+            let before = gfx_auxil::host_monotonic_ns();
+            let calibrated = device.get_calibrated_timestamps().unwrap();
+            let after = gfx_auxil::host_monotonic_ns();
+            assert_eq!(calibrated.max_deviation_ns, 0);
+            assert!(before <= calibrated.host_ns && calibrated.host_ns <= after);
+
+            // Both timestamps come from the same reading, so they advance together
+            let later = device.get_calibrated_timestamps().unwrap();
+            let host_ticks = ((later.host_ns - calibrated.host_ns) as f64 / 10.0) as u64;
+            let device_ticks = later.device - calibrated.device;
+            assert!(host_ticks.saturating_sub(1) <= device_ticks && device_ticks <= host_ticks + 1);
+
+            let mut data = [0u8; 8];
+            let flags = ResultFlags::WAIT | ResultFlags::BITS_64;
+            assert_eq!(
+                device.get_query_pool_results(&timestamps, 0..1, &mut data, 8, flags),
+                Ok(true)
+            );
+            assert!(u64::from_ne_bytes(data) <= calibrated.device);
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_query_pool(timestamps);
+        }
+    }
+
+    #[test]
+    fn performance_counters() {
+        let profile = Profile::default().with_features(hal::Features::PERFORMANCE_QUERY);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::PERFORMANCE_QUERY);
+        let family = &adapter.queue_families[0];
+        let counters = adapter.physical_device.performance_counters(family.id());
+        let names = counters
+            .iter()
+            .map(|counter| counter.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Elapsed time", "Commands", "Average command time"]);
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            assert_eq!(device.acquire_profiling_lock(0), Ok(true));
+            assert_eq!(device.acquire_profiling_lock(0), Ok(false));
+
+            let queries = device
+                .create_performance_query_pool(family.id(), &[1, 0, 2], 1)
+                .unwrap();
+            let memory = device
+                .allocate_memory(MemoryTypeId(0), 16, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(16, buffer::Usage::TRANSFER_DST, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.reset_query_pool(&queries, 0..1);
+            cmd_buf.begin_query(
+                Query {
+                    pool: &queries,
+                    id: 0,
+                },
+                query::ControlFlags::empty(),
+            );
+            cmd_buf.fill_buffer(&buffer, buffer::SubRange::WHOLE, 0);
+            cmd_buf.insert_debug_marker("fill", 0);
+            cmd_buf.fill_buffer(&buffer, buffer::SubRange::WHOLE, 1);
+            cmd_buf.end_query(Query {
+                pool: &queries,
+                id: 0,
+            });
+            cmd_buf.finish();
+            queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+
+            // Results are in the order the counters were requested in
+            let mut data = [0u8; 24];
+            assert_eq!(
+                device.get_query_pool_results(&queries, 0..1, &mut data, 24, ResultFlags::WAIT),
+                Ok(true)
+            );
+            assert_eq!(
+                counters[1].storage.decode(&data[..8]),
+                query::PerformanceCounterValue::Uint32(2)
+            );
+            let elapsed = counters[0].storage.decode(&data[8..16]).as_f64();
+            let average = counters[2].storage.decode(&data[16..]).as_f64();
+            assert_eq!(average, elapsed / 2.0);
+
+            device.release_profiling_lock();
+            assert_eq!(device.acquire_profiling_lock(0), Ok(true));
+            device.release_profiling_lock();
+
+            pool.free(iter::once(cmd_buf));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+            device.destroy_query_pool(queries);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::interpreter;
+use hal::pso;
+
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct ShaderModule {
+    /// Parsed module, or the reason it's not available.
+    ///
+    /// Modules are only needed for running compute work, so failing to parse
+    /// one doesn't fail the shader module creation.
+    pub(crate) naga: Result<Arc<naga::Module>, String>,
+}
+
+impl ShaderModule {
+    pub fn from_spirv(raw_data: &[u32]) -> Self {
+        let options = naga::front::spv::Options {
+            adjust_coordinate_space: false,
+            strict_capabilities: true,
+            flow_graph_dump_prefix: None,
+        };
+        let parser = naga::front::spv::Parser::new(raw_data.iter().cloned(), &options);
+        let naga = match parser.parse() {
+            Ok(module) => Ok(Arc::new(module)),
+            Err(e) => {
+                log::warn!("Naga parsing: {:?}", e);
+                Err(format!("Naga parsing: {:?}", e))
+            }
+        };
+        ShaderModule { naga }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComputePipeline {
+    pub(crate) module: Arc<naga::Module>,
+    /// Index of the entry point within the module.
+    pub(crate) entry_point: usize,
+}
+
+impl ComputePipeline {
+    pub fn new(shader: &pso::EntryPoint<crate::Backend>) -> Result<Self, pso::CreationError> {
+        let module = match shader.module.naga {
+            Ok(ref module) => Arc::clone(module),
+            Err(ref e) => {
+                return Err(pso::CreationError::ShaderCreationError(
+                    pso::ShaderStageFlags::COMPUTE,
+                    e.clone(),
+                ))
+            }
+        };
+        let entry_point = module
+            .entry_points
+            .iter()
+            .position(|ep| ep.stage == naga::ShaderStage::Compute && ep.name == shader.entry)
+            .ok_or_else(|| pso::CreationError::MissingEntryPoint(shader.entry.to_string()))?;
+        interpreter::validate(&module, entry_point).map_err(|e| {
+            pso::CreationError::ShaderCreationError(pso::ShaderStageFlags::COMPUTE, e)
+        })?;
+        Ok(ComputePipeline {
+            module,
+            entry_point,
+        })
+    }
+}
@@ -0,0 +1,493 @@
+//! Presentation surfaces, either backed by a window or virtual.
+//!
+//! Virtual surfaces aren't tied to any window, and write the presented
+//! frames to disk instead.
+
+use crate::{
+    fault::Operation,
+    image::{Image, ImageView},
+    memory::Binding,
+    Backend, Device, FaultInjector, PhysicalDevice, QueueFamily,
+};
+
+use hal::{format::Format, image, window};
+
+use std::{
+    borrow::Borrow,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    slice,
+};
+
+/// Where the frames presented to a virtual surface go.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameOutput {
+    /// Frames are dropped.
+    Discard,
+    /// Each frame is written to `frame_NNNNN.png` in the given directory.
+    ///
+    /// Requires an 8-bit RGBA or BGRA surface format.
+    #[cfg(feature = "png")]
+    Png(PathBuf),
+    /// Frames are appended to the given file as raw texels, in the surface format.
+    Raw(PathBuf),
+    /// Frames are appended to the given file as a YUV4MPEG2 stream, at `fps` frames
+    /// per second.
+    ///
+    /// Requires an 8-bit RGBA or BGRA surface format.
+    Y4m { path: PathBuf, fps: u32 },
+}
+
+/// Writes the presented frames to a `FrameOutput`.
+#[derive(Debug)]
+struct FrameWriter {
+    output: FrameOutput,
+    /// Stream the frames are appended to, if any.
+    stream: Option<BufWriter<File>>,
+    #[cfg(feature = "png")]
+    extent: window::Extent2D,
+    format: Format,
+    /// Number of frames written so far.
+    frame: usize,
+}
+
+impl FrameWriter {
+    fn new(output: FrameOutput, extent: window::Extent2D, format: Format) -> io::Result<Self> {
+        let needs_rgba = match output {
+            FrameOutput::Discard | FrameOutput::Raw(_) => false,
+            #[cfg(feature = "png")]
+            FrameOutput::Png(_) => true,
+            FrameOutput::Y4m { .. } => true,
+        };
+        if needs_rgba && channel_order(format).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Frames can't be encoded from {:?}", format),
+            ));
+        }
+        let stream = match output {
+            FrameOutput::Discard => None,
+            #[cfg(feature = "png")]
+            FrameOutput::Png(ref directory) => {
+                std::fs::create_dir_all(directory)?;
+                None
+            }
+            FrameOutput::Raw(ref path) => Some(BufWriter::new(File::create(path)?)),
+            FrameOutput::Y4m { ref path, fps } => {
+                let mut stream = BufWriter::new(File::create(path)?);
+                writeln!(
+                    stream,
+                    "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                    extent.width, extent.height, fps
+                )?;
+                Some(stream)
+            }
+        };
+        Ok(FrameWriter {
+            output,
+            stream,
+            #[cfg(feature = "png")]
+            extent,
+            format,
+            frame: 0,
+        })
+    }
+
+    /// Returns the texels of a frame as RGBA8.
+    fn to_rgba(&self, texels: &[u8]) -> Vec<u8> {
+        let [r, g, b, a] = channel_order(self.format).unwrap();
+        let mut rgba = Vec::with_capacity(texels.len());
+        for texel in texels.chunks_exact(4) {
+            rgba.extend_from_slice(&[texel[r], texel[g], texel[b], texel[a]]);
+        }
+        rgba
+    }
+
+    fn write(&mut self, texels: &[u8]) -> io::Result<()> {
+        match self.output {
+            FrameOutput::Discard => {}
+            #[cfg(feature = "png")]
+            FrameOutput::Png(ref directory) => {
+                let path = directory.join(format!("frame_{:05}.png", self.frame));
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = png::Encoder::new(file, self.extent.width, self.extent.height);
+                encoder.set_color(png::ColorType::RGBA);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder
+                    .write_header()?
+                    .write_image_data(&self.to_rgba(texels))?;
+            }
+            FrameOutput::Raw(_) => {
+                self.stream.as_mut().unwrap().write_all(texels)?;
+            }
+            FrameOutput::Y4m { .. } => {
+                let rgba = self.to_rgba(texels);
+                let stream = self.stream.as_mut().unwrap();
+                stream.write_all(b"FRAME\n")?;
+                // BT.601 with studio swing, which players assume for Y4M streams
+                let planes: [fn(i32, i32, i32) -> i32; 3] = [
+                    |r, g, b| ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16,
+                    |r, g, b| ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128,
+                    |r, g, b| ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128,
+                ];
+                for plane in planes.iter() {
+                    let bytes = rgba
+                        .chunks_exact(4)
+                        .map(|t| plane(i32::from(t[0]), i32::from(t[1]), i32::from(t[2])) as u8)
+                        .collect::<Vec<_>>();
+                    stream.write_all(&bytes)?;
+                }
+            }
+        }
+        if let Some(ref mut stream) = self.stream {
+            stream.flush()?;
+        }
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+/// Returns the byte offsets of the red, green, blue and alpha channels of a format
+/// frames can be encoded from.
+fn channel_order(format: Format) -> Option<[usize; 4]> {
+    match format {
+        Format::Rgba8Unorm | Format::Rgba8Srgb => Some([0, 1, 2, 3]),
+        Format::Bgra8Unorm | Format::Bgra8Srgb => Some([2, 1, 0, 3]),
+        _ => None,
+    }
+}
+
+/// Images of a configured swapchain.
+#[derive(Debug)]
+struct Swapchain {
+    images: Vec<Image>,
+    /// Whether each image is currently acquired by the application.
+    acquired: Vec<bool>,
+    /// Index of the image to try acquiring next.
+    next: usize,
+}
+
+/// Surface presenting the images of a swapchain ring.
+#[derive(Debug)]
+pub struct Surface {
+    /// Fixed extent and format of a virtual surface, `None` for window surfaces.
+    target: Option<(window::Extent2D, Format)>,
+    writer: Option<FrameWriter>,
+    swapchain: Option<Swapchain>,
+    faults: FaultInjector,
+}
+
+impl Surface {
+    pub(crate) fn new(faults: FaultInjector) -> Self {
+        Surface {
+            target: None,
+            writer: None,
+            swapchain: None,
+            faults,
+        }
+    }
+
+    pub(crate) fn new_virtual(
+        extent: window::Extent2D,
+        format: Format,
+        output: FrameOutput,
+        faults: FaultInjector,
+    ) -> io::Result<Self> {
+        Ok(Surface {
+            target: Some((extent, format)),
+            writer: Some(FrameWriter::new(output, extent, format)?),
+            swapchain: None,
+            faults,
+        })
+    }
+
+    /// Releases an image back into the swapchain, writing it out if the surface is virtual.
+    ///
+    /// The image contents must be final.
+    pub(crate) fn present(&mut self, image: SwapchainImage) {
+        let swapchain = self
+            .swapchain
+            .as_mut()
+            .expect("Swapchain is not configured");
+        let index = image.index as usize;
+        assert!(
+            swapchain.acquired[index],
+            "Swapchain image {} is not acquired",
+            index
+        );
+        swapchain.acquired[index] = false;
+
+        if let Some(ref mut writer) = self.writer {
+            let footprint = image.image.footprint(image::Subresource {
+                aspects: writer.format.surface_desc().aspects,
+                level: 0,
+                layer: 0,
+            });
+            let size = footprint.slice.end - footprint.slice.start;
+            let texels = unsafe {
+                slice::from_raw_parts(
+                    image.image.binding().ptr(footprint.slice.start, size),
+                    size as usize,
+                )
+            };
+            writer.write(texels).expect("Unable to write the frame");
+        }
+    }
+}
+
+impl window::Surface<Backend> for Surface {
+    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
+        true
+    }
+
+    fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
+        let (current_extent, extents) = match self.target {
+            Some((extent, _)) => (Some(extent), extent..=extent),
+            None => {
+                let min_extent = window::Extent2D {
+                    width: 0,
+                    height: 0,
+                };
+                let max_extent = window::Extent2D {
+                    width: 8192,
+                    height: 4096,
+                };
+                (None, min_extent..=max_extent)
+            }
+        };
+        let usage = image::Usage::COLOR_ATTACHMENT
+            | image::Usage::TRANSFER_SRC
+            | image::Usage::TRANSFER_DST
+            | image::Usage::STORAGE;
+        let present_modes = window::PresentMode::all();
+        let composite_alpha_modes = window::CompositeAlphaMode::OPAQUE;
+        window::SurfaceCapabilities {
+            image_count: 1..=8,
+            current_extent,
+            extents,
+            max_image_layers: 1,
+            usage,
+            present_modes,
+            composite_alpha_modes,
+        }
+    }
+
+    fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<Format>> {
+        self.target.map(|(_, format)| vec![format])
+    }
+}
+
+/// Image of a swapchain, shared with the surface.
+#[derive(Debug)]
+pub struct SwapchainImage {
+    index: window::SwapImageIndex,
+    image: Image,
+    view: ImageView,
+}
+impl Borrow<Image> for SwapchainImage {
+    fn borrow(&self) -> &Image {
+        &self.image
+    }
+}
+impl Borrow<ImageView> for SwapchainImage {
+    fn borrow(&self) -> &ImageView {
+        &self.view
+    }
+}
+
+impl window::PresentationSurface<Backend> for Surface {
+    type SwapchainImage = SwapchainImage;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        _: &Device,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::SwapchainError> {
+        self.faults
+            .check::<window::SwapchainError>(Operation::ConfigureSwapchain)?;
+        if let Some((extent, format)) = self.target {
+            assert_eq!(
+                (config.extent, config.format),
+                (extent, format),
+                "Swapchain doesn't match the virtual surface"
+            );
+        }
+        let images = (0..config.image_count)
+            .map(|_| {
+                let mut image = Image::new(
+                    image::Kind::D2(config.extent.width, config.extent.height, 1, 1),
+                    1,
+                    config.format,
+                );
+                image.memory = Some(Binding::zeroed(image.get_requirements().size));
+                image
+            })
+            .collect();
+        self.swapchain = Some(Swapchain {
+            images,
+            acquired: vec![false; config.image_count as usize],
+            next: 0,
+        });
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, _: &Device) {
+        self.swapchain = None;
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+    ) -> Result<(SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
+        let suboptimal = self
+            .faults
+            .check_swapchain::<window::AcquireError>(Operation::AcquireImage)?;
+        let swapchain = self
+            .swapchain
+            .as_mut()
+            .expect("Swapchain is not configured");
+        // Images are only released by presenting them, so there is nothing to wait for
+        let count = swapchain.images.len();
+        let index = (0..count)
+            .map(|i| (swapchain.next + i) % count)
+            .find(|&index| !swapchain.acquired[index])
+            .ok_or(window::AcquireError::NotReady {
+                timeout: timeout_ns != 0,
+            })?;
+        swapchain.acquired[index] = true;
+        swapchain.next = (index + 1) % count;
+        let image = SwapchainImage {
+            index: index as window::SwapImageIndex,
+            image: swapchain.images[index].clone(),
+            view: ImageView::of_image(&swapchain.images[index]),
+        };
+        Ok((image, suboptimal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameOutput;
+    use crate::Instance;
+    use hal::{
+        buffer,
+        command::{BufferImageCopy, CommandBuffer as _, CommandBufferFlags, Level},
+        device::Device as _,
+        format::{Aspects, Format},
+        image::{Extent, Layout, Offset, SubresourceLayers},
+        memory::{AllocationFlags, SparseFlags},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        queue::{Queue as _, QueueFamily as _},
+        window::{AcquireError, Extent2D, PresentationSurface as _, SwapchainConfig},
+        Instance as _, MemoryTypeId,
+    };
+    use std::{borrow::Borrow, fs, iter};
+
+    #[test]
+    fn virtual_frames() {
+        let instance = Instance::create("test", 1).unwrap();
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        let directory = std::env::temp_dir().join(format!("gfx-empty-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let extent = Extent2D {
+            width: 4,
+            height: 2,
+        };
+        let outputs = vec![
+            FrameOutput::Raw(directory.join("frames.raw")),
+            FrameOutput::Y4m {
+                path: directory.join("frames.y4m"),
+                fps: 60,
+            },
+            #[cfg(feature = "png")]
+            FrameOutput::Png(directory.join("png")),
+        ];
+
+        for output in outputs {
+            let mut surface = instance
+                .create_virtual_surface(extent, Format::Bgra8Unorm, output)
+                .unwrap();
+            unsafe {
+                let config = SwapchainConfig::new(4, 2, Format::Bgra8Unorm, 2);
+                surface.configure_swapchain(device, config).unwrap();
+                let (image, _) = surface.acquire_image(0).unwrap();
+                let (other, _) = surface.acquire_image(0).unwrap();
+                assert_eq!(
+                    surface.acquire_image(0).err(),
+                    Some(AcquireError::NotReady { timeout: false })
+                );
+
+                // Present a blue frame
+                let memory = device
+                    .allocate_memory(MemoryTypeId(0), 32, AllocationFlags::empty())
+                    .unwrap();
+                let mut texels = device
+                    .create_buffer(32, buffer::Usage::TRANSFER_SRC, SparseFlags::empty())
+                    .unwrap();
+                device.bind_buffer_memory(&memory, 0, &mut texels).unwrap();
+                let mut pool = device
+                    .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                    .unwrap();
+                let mut cmd_buf = pool.allocate_one(Level::Primary);
+                cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+                cmd_buf.fill_buffer(&texels, buffer::SubRange::WHOLE, 0xFF00_00FF);
+                cmd_buf.copy_buffer_to_image(
+                    &texels,
+                    image.borrow(),
+                    Layout::TransferDstOptimal,
+                    iter::once(BufferImageCopy {
+                        buffer_offset: 0,
+                        buffer_width: 0,
+                        buffer_height: 0,
+                        image_layers: SubresourceLayers {
+                            aspects: Aspects::COLOR,
+                            level: 0,
+                            layers: 0..1,
+                        },
+                        image_offset: Offset::ZERO,
+                        image_extent: Extent {
+                            width: 4,
+                            height: 2,
+                            depth: 1,
+                        },
+                    }),
+                );
+                cmd_buf.finish();
+                queue.submit(iter::once(&cmd_buf), iter::empty(), iter::empty(), None);
+                queue.present(&mut surface, image, None).unwrap();
+                queue.present(&mut surface, other, None).unwrap();
+                assert!(surface.acquire_image(0).is_ok());
+
+                pool.free(iter::once(cmd_buf));
+                device.destroy_command_pool(pool);
+                device.destroy_buffer(texels);
+                device.free_memory(memory);
+                surface.unconfigure_swapchain(device);
+            }
+        }
+
+        // Two frames, the second one cleared to zero
+        let raw = fs::read(directory.join("frames.raw")).unwrap();
+        let blue = [0xFF, 0, 0, 0xFF].repeat(8);
+        assert_eq!(raw, [blue, vec![0; 32]].concat());
+
+        let y4m = fs::read(directory.join("frames.y4m")).unwrap();
+        let header = b"YUV4MPEG2 W4 H2 F60:1 Ip A1:1 C444\n".to_vec();
+        let blue = [b"FRAME\n".to_vec(), vec![41; 8], vec![240; 8], vec![110; 8]].concat();
+        let black = [b"FRAME\n".to_vec(), vec![16; 8], vec![128; 8], vec![128; 8]].concat();
+        assert_eq!(y4m, [header, blue, black].concat());
+
+        #[cfg(feature = "png")]
+        for frame in 0..2 {
+            let path = directory.join(format!("png/frame_{:05}.png", frame));
+            assert!(fs::read(path).unwrap().starts_with(b"\x89PNG"));
+        }
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
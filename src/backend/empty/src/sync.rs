@@ -0,0 +1,569 @@
+//! Synchronization primitives and the queue workers signaling them.
+
+use crate::{
+    commands::Command,
+    fault::{BreadcrumbTrail, FaultInjector, Operation},
+};
+
+use hal::device;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Wakes up the host threads waiting on the signals of a device.
+#[derive(Debug, Default)]
+pub(crate) struct Notifier {
+    lock: Mutex<()>,
+    condvar: Condvar,
+    /// Number of submissions not completed yet, over all queues.
+    pending: AtomicUsize,
+}
+
+impl Notifier {
+    /// Runs `update` and wakes up the waiters, so they can observe its effects.
+    pub fn notify<F: FnOnce()>(&self, update: F) {
+        let _guard = self.lock.lock().unwrap();
+        update();
+        self.condvar.notify_all();
+    }
+
+    /// Waits until `ready` returns true or the timeout expires.
+    ///
+    /// Returns false on timeout.
+    pub fn wait_until<F: FnMut() -> bool>(&self, timeout_ns: u64, mut ready: F) -> bool {
+        // Deadlines too far away to represent are as good as infinite
+        let deadline = Instant::now().checked_add(Duration::from_nanos(timeout_ns));
+        let mut guard = self.lock.lock().unwrap();
+        loop {
+            if ready() {
+                return true;
+            }
+            guard = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    self.condvar.wait_timeout(guard, deadline - now).unwrap().0
+                }
+                None => self.condvar.wait(guard).unwrap(),
+            };
+        }
+    }
+
+    /// Waits until `ready` returns true, the device gets lost or the timeout expires.
+    ///
+    /// Returns false on timeout.
+    pub fn wait_until_or_lost<F: FnMut() -> bool>(
+        &self,
+        faults: &FaultInjector,
+        timeout_ns: u64,
+        mut ready: F,
+    ) -> Result<bool, device::DeviceLost> {
+        let mut lost = false;
+        let ready = self.wait_until(timeout_ns, || {
+            lost = faults.is_device_lost();
+            lost || ready()
+        });
+        if lost {
+            return Err(device::DeviceLost);
+        }
+        Ok(ready)
+    }
+
+    /// Waits until all the submissions of the device are completed, or the device gets lost.
+    pub fn wait_idle(&self, faults: &FaultInjector) -> Result<(), device::DeviceLost> {
+        self.wait_until_or_lost(faults, !0, || self.pending.load(Ordering::Acquire) == 0)
+            .map(|_| ())
+    }
+}
+
+/// A state shared between the host and the queues.
+///
+/// Binary for fences, events and binary semaphores, where any non-zero value
+/// is set, and a counter for timeline semaphores.
+#[derive(Debug)]
+pub(crate) struct Signal {
+    notifier: Arc<Notifier>,
+    state: AtomicU64,
+}
+
+impl Signal {
+    pub fn new(notifier: &Arc<Notifier>, state: bool) -> Arc<Self> {
+        Self::with_value(notifier, state as u64)
+    }
+
+    pub fn with_value(notifier: &Arc<Notifier>, value: u64) -> Arc<Self> {
+        Arc::new(Signal {
+            notifier: Arc::clone(notifier),
+            state: AtomicU64::new(value),
+        })
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.value() != 0
+    }
+
+    pub fn set(&self) {
+        self.notifier
+            .notify(|| self.state.store(1, Ordering::Release));
+    }
+
+    pub fn reset(&self) {
+        self.state.store(0, Ordering::Release);
+    }
+
+    pub fn value(&self) -> u64 {
+        self.state.load(Ordering::Acquire)
+    }
+
+    /// Raises the counter to `value`, if it's not past it already.
+    pub fn advance(&self, value: u64) {
+        self.notifier.notify(|| {
+            self.state.fetch_max(value, Ordering::AcqRel);
+        });
+    }
+
+    /// Blocks until the signal is set.
+    pub fn wait(&self) {
+        self.notifier.wait_until(!0, || self.is_set());
+    }
+}
+
+#[derive(Debug)]
+pub struct Fence {
+    pub(crate) signal: Arc<Signal>,
+}
+
+#[derive(Debug)]
+pub struct Semaphore {
+    pub(crate) signal: Arc<Signal>,
+    pub(crate) timeline: bool,
+}
+
+impl Semaphore {
+    /// Returns the operation of a submission on this semaphore.
+    ///
+    /// The value is ignored for binary semaphores, and required for timeline ones.
+    pub(crate) fn operation(&self, value: Option<u64>) -> SemaphoreOperation {
+        SemaphoreOperation {
+            signal: Arc::clone(&self.signal),
+            value: if self.timeline {
+                Some(value.expect("Timeline semaphores must be submitted with a value"))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Event {
+    pub(crate) signal: Arc<Signal>,
+}
+
+/// Semaphore waited on or signaled by a submission.
+#[derive(Debug)]
+pub(crate) struct SemaphoreOperation {
+    signal: Arc<Signal>,
+    /// Value of a timeline semaphore, `None` for binary ones.
+    value: Option<u64>,
+}
+
+impl SemaphoreOperation {
+    /// Blocks until the semaphore is signaled, consuming the signal of binary semaphores.
+    ///
+    /// Returns an error if the device got lost, as the semaphore may never get signaled.
+    pub fn wait(&self, faults: &FaultInjector) -> Result<(), device::DeviceLost> {
+        let signal = &self.signal;
+        signal
+            .notifier
+            .wait_until_or_lost(faults, !0, || match self.value {
+                Some(value) => signal.value() >= value,
+                None => signal.state.swap(0, Ordering::AcqRel) != 0,
+            })
+            .map(|_| ())
+    }
+
+    fn signal(&self) {
+        match self.value {
+            Some(value) => self.signal.advance(value),
+            None => self.signal.set(),
+        }
+    }
+}
+
+/// Work submitted to a queue.
+#[derive(Debug)]
+pub(crate) struct Submission {
+    pub command_buffers: Vec<Arc<Vec<Command>>>,
+    pub wait_semaphores: Vec<SemaphoreOperation>,
+    pub signal_semaphores: Vec<SemaphoreOperation>,
+    pub fence: Option<Arc<Signal>>,
+}
+
+/// Executes the commands of a submission, stopping at a breadcrumb losing the device.
+///
+/// Returns false if the device got lost.
+fn execute(
+    command_buffers: &[Arc<Vec<Command>>],
+    faults: &FaultInjector,
+    breadcrumbs: Option<&BreadcrumbTrail>,
+) -> bool {
+    for command in command_buffers.iter().flat_map(|commands| commands.iter()) {
+        match *command {
+            Command::Breadcrumb(ref breadcrumb) => {
+                if faults
+                    .check::<device::DeviceLost>(Operation::Breadcrumb)
+                    .is_err()
+                {
+                    return false;
+                }
+                if let Some(trail) = breadcrumbs {
+                    trail.complete(breadcrumb);
+                }
+            }
+            _ => command.execute(),
+        }
+    }
+    true
+}
+
+/// Thread executing the submissions of a queue in order.
+#[derive(Debug)]
+pub(crate) struct Worker {
+    sender: Mutex<mpsc::Sender<Submission>>,
+    notifier: Arc<Notifier>,
+    /// Number of submissions to this queue not completed yet.
+    pending: Arc<AtomicUsize>,
+}
+
+impl Worker {
+    /// Spawns the thread of a queue, reporting its breadcrumbs to `breadcrumbs`
+    /// if fault diagnostics are enabled.
+    pub fn spawn(
+        name: String,
+        notifier: &Arc<Notifier>,
+        faults: FaultInjector,
+        breadcrumbs: Option<Arc<BreadcrumbTrail>>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<Submission>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let worker = Worker {
+            sender: Mutex::new(sender),
+            notifier: Arc::clone(notifier),
+            pending: Arc::clone(&pending),
+        };
+        let notifier = Arc::clone(notifier);
+        // The thread exits once the queue is dropped and the submissions are done
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                for submission in receiver {
+                    // The semaphores of a lost device may never get signaled
+                    let completed = submission
+                        .wait_semaphores
+                        .iter()
+                        .all(|semaphore| semaphore.wait(&faults).is_ok())
+                        && panic::catch_unwind(AssertUnwindSafe(|| {
+                            execute(&submission.command_buffers, &faults, breadcrumbs.as_deref())
+                        }))
+                        .unwrap_or_else(|_| {
+                            // Commands failing to execute lose the device, so the
+                            // host waits report it instead of blocking forever
+                            faults.lose_device();
+                            false
+                        });
+                    // Command buffers are no longer pending once the fence is signaled
+                    drop(submission.command_buffers);
+                    // The work of a lost device never completes
+                    if completed {
+                        for semaphore in &submission.signal_semaphores {
+                            semaphore.signal();
+                        }
+                        if let Some(ref fence) = submission.fence {
+                            fence.set();
+                        }
+                    }
+                    notifier.notify(|| {
+                        pending.fetch_sub(1, Ordering::AcqRel);
+                        notifier.pending.fetch_sub(1, Ordering::AcqRel);
+                    });
+                }
+            })
+            .expect("Unable to spawn the queue worker");
+        worker
+    }
+
+    pub fn submit(&mut self, submission: Submission) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        self.notifier.pending.fetch_add(1, Ordering::AcqRel);
+        self.sender
+            .get_mut()
+            .unwrap()
+            .send(submission)
+            .expect("Queue worker has stopped");
+    }
+
+    /// Waits until all the submissions to this queue are completed, or the device gets lost.
+    pub fn wait_idle(&self, faults: &FaultInjector) -> Result<(), device::DeviceLost> {
+        self.notifier
+            .wait_until_or_lost(faults, !0, || self.pending.load(Ordering::Acquire) == 0)
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instance, Profile, QueueFamilyProfile};
+    use hal::{
+        buffer,
+        command::{BufferCopy, CommandBuffer as _, CommandBufferFlags, Level},
+        device::{Device as _, DeviceLost, WaitError, WaitFor},
+        memory::{AllocationFlags, SparseFlags},
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        pso::PipelineStage,
+        queue::{Queue as _, QueueFamily as _, QueueType},
+        MemoryTypeId,
+    };
+    use std::{iter, sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn cross_queue_signals() {
+        let profile = Profile::default()
+            .with_queue_families(vec![QueueFamilyProfile::new(QueueType::General, 2)]);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let (first, second) = gpu.queue_groups[0].queues.split_at_mut(1);
+        let (first, second) = (&mut first[0], &mut second[0]);
+
+        unsafe {
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut host_event = device.create_event().unwrap();
+            let device_event = device.create_event().unwrap();
+            let semaphore = device.create_semaphore().unwrap();
+            let mut fences = [
+                device.create_fence(false).unwrap(),
+                device.create_fence(false).unwrap(),
+            ];
+            assert_eq!(device.wait_for_fence(&fences[0], 1_000_000), Ok(false));
+
+            // The first queue is blocked on the host event, and the second
+            // one on the semaphore signaled by the first queue
+            let mut waiting = pool.allocate_one(Level::Primary);
+            waiting.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            waiting.wait_events(
+                iter::once(&host_event),
+                PipelineStage::HOST..PipelineStage::TRANSFER,
+                iter::empty(),
+            );
+            waiting.finish();
+            let mut setting = pool.allocate_one(Level::Primary);
+            setting.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            setting.set_event(&device_event, PipelineStage::TRANSFER);
+            setting.finish();
+
+            first.submit(
+                iter::once(&waiting),
+                iter::empty(),
+                iter::once(&semaphore),
+                Some(&mut fences[0]),
+            );
+            second.submit(
+                iter::once(&setting),
+                iter::once((&semaphore, PipelineStage::TRANSFER)),
+                iter::empty(),
+                Some(&mut fences[1]),
+            );
+            assert_eq!(device.get_fence_status(&fences[0]), Ok(false));
+            assert_eq!(
+                device.wait_for_fences(fences.iter(), WaitFor::Any, 1_000_000),
+                Ok(false)
+            );
+            assert_eq!(device.get_event_status(&device_event), Ok(false));
+
+            device.set_event(&mut host_event).unwrap();
+            assert_eq!(
+                device.wait_for_fences(fences.iter(), WaitFor::Any, !0),
+                Ok(true)
+            );
+            assert_eq!(
+                device.wait_for_fences(fences.iter(), WaitFor::All, !0),
+                Ok(true)
+            );
+            assert_eq!(device.get_event_status(&device_event), Ok(true));
+            device.wait_idle().unwrap();
+
+            // Command buffers can be recorded again once completed
+            waiting.reset(false);
+            device.reset_fence(&mut fences[0]).unwrap();
+            assert_eq!(device.get_fence_status(&fences[0]), Ok(false));
+
+            for fence in fences {
+                device.destroy_fence(fence);
+            }
+            device.destroy_semaphore(semaphore);
+            device.destroy_event(host_event);
+            device.destroy_event(device_event);
+            pool.free(vec![waiting, setting].into_iter());
+            device.destroy_command_pool(pool);
+        }
+    }
+
+    #[test]
+    fn timeline_semaphores() {
+        let profile = Profile::default()
+            .with_features(hal::Features::TIMELINE_SEMAPHORE)
+            .with_queue_families(vec![QueueFamilyProfile::new(QueueType::General, 2)]);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (_, mut gpu) = crate::open_device(&instance, hal::Features::TIMELINE_SEMAPHORE);
+        let device = &gpu.device;
+        let (first, second) = gpu.queue_groups[0].queues.split_at_mut(1);
+        let (first, second) = (&mut first[0], &mut second[0]);
+
+        unsafe {
+            let timeline = device.create_timeline_semaphore(1).unwrap();
+            let binary = device.create_semaphore().unwrap();
+            let mut fence = device.create_fence(false).unwrap();
+            assert_eq!(device.get_semaphore_counter_value(&timeline), Ok(1));
+
+            // The second queue waits on a value only the host signals, then
+            // on the binary semaphore signaled along with it by the first one
+            first.submit_timeline(
+                iter::empty(),
+                iter::once((&timeline, 3, PipelineStage::TOP_OF_PIPE)),
+                vec![(&timeline, 4), (&binary, 0)].into_iter(),
+                None,
+            );
+            second.submit_timeline(
+                iter::empty(),
+                iter::once((&binary, 0, PipelineStage::TOP_OF_PIPE)),
+                iter::once((&timeline, 5)),
+                Some(&mut fence),
+            );
+            assert_eq!(
+                device.wait_semaphores(iter::once((&timeline, 2)), WaitFor::All, 1_000_000),
+                Ok(false)
+            );
+
+            device.signal_semaphore(&timeline, 2).unwrap();
+            assert_eq!(
+                device.wait_semaphores(
+                    vec![(&timeline, 2), (&timeline, 4)].into_iter(),
+                    WaitFor::Any,
+                    !0
+                ),
+                Ok(true)
+            );
+            assert_eq!(
+                device.wait_semaphores(
+                    vec![(&timeline, 2), (&timeline, 4)].into_iter(),
+                    WaitFor::All,
+                    1_000_000
+                ),
+                Ok(false)
+            );
+            assert_eq!(device.get_fence_status(&fence), Ok(false));
+
+            device.signal_semaphore(&timeline, 3).unwrap();
+            assert_eq!(device.wait_for_fence(&fence, !0), Ok(true));
+            assert_eq!(device.get_semaphore_counter_value(&timeline), Ok(5));
+
+            device.destroy_fence(fence);
+            device.destroy_semaphore(binary);
+            device.destroy_semaphore(timeline);
+        }
+    }
+
+    #[test]
+    fn device_loss_wakes_waiters() {
+        let profile = Profile::default().with_features(hal::Features::TIMELINE_SEMAPHORE);
+        let instance = Instance::with_profiles(iter::once(profile));
+        let (_, gpu) = crate::open_device(&instance, hal::Features::TIMELINE_SEMAPHORE);
+        let device = Arc::new(gpu.device);
+
+        let fence = device.create_fence(false).unwrap();
+        let timeline = device.create_timeline_semaphore(0).unwrap();
+        let fence_waiter = {
+            let device = Arc::clone(&device);
+            thread::spawn(move || unsafe {
+                let result = device.wait_for_fence(&fence, !0);
+                device.destroy_fence(fence);
+                result
+            })
+        };
+        let semaphore_waiter = {
+            let device = Arc::clone(&device);
+            thread::spawn(move || unsafe {
+                let result = device.wait_semaphores(iter::once((&timeline, 1)), WaitFor::All, !0);
+                device.destroy_semaphore(timeline);
+                result
+            })
+        };
+
+        // Give the waiters time to block before losing the device
+        thread::sleep(Duration::from_millis(50));
+        device.faults().lose_device();
+        let lost = Err(WaitError::DeviceLost(DeviceLost));
+        assert_eq!(fence_waiter.join().unwrap(), lost);
+        assert_eq!(semaphore_waiter.join().unwrap(), lost);
+    }
+
+    #[test]
+    fn panicking_commands_lose_the_device() {
+        let instance = Instance::with_profiles(iter::once(Profile::default()));
+        let (adapter, mut gpu) = crate::open_device(&instance, hal::Features::empty());
+        let family = &adapter.queue_families[0];
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let memory = device
+                .allocate_memory(MemoryTypeId(0), 16, AllocationFlags::empty())
+                .unwrap();
+            let mut buffer = device
+                .create_buffer(16, buffer::Usage::TRANSFER_DST, SparseFlags::empty())
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+
+            // The copy is out of the memory bounds, which panics on the queue
+            let mut cmd_buf = pool.allocate_one(Level::Primary);
+            cmd_buf.begin_primary(CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buf.copy_buffer(
+                &buffer,
+                &buffer,
+                iter::once(BufferCopy {
+                    src: 0,
+                    dst: 8,
+                    size: 16,
+                }),
+            );
+            cmd_buf.finish();
+            let mut fence = device.create_fence(false).unwrap();
+            queue.submit(
+                iter::once(&cmd_buf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            assert_eq!(
+                device.wait_for_fence(&fence, !0),
+                Err(WaitError::DeviceLost(DeviceLost))
+            );
+            assert!(device.faults().is_device_lost());
+        }
+    }
+}
@@ -9,7 +9,7 @@ use hal::{
 };
 
 use crate::{
-    info, native as n,
+    conv, info, native as n, perf,
     pool::{self, BufferMemory},
     Backend, ColorSlot,
 };
@@ -83,6 +83,10 @@ pub enum Command {
         rasterizer: pso::Rasterizer,
     },
     BindDepth(Option<pso::Comparison>),
+    SetDepthTest(bool),
+    SetDepthFunc(pso::Comparison),
+    SetFrontFace(pso::FrontFace),
+    SetCullFace(pso::Face),
     SetViewports {
         first_viewport: u32,
         viewport_ptr: BufferSlice,
@@ -107,8 +111,29 @@ pub enum Command {
         framebuffer: n::RawFramebuffer,
         colors: ArrayVec<[n::ImageView; MAX_COLOR_ATTACHMENTS]>,
         depth_stencil: Option<n::ImageView>,
+        /// Layers of the attachments rendered at once, zero outside of multiview passes.
+        view_mask: u32,
     },
     SetDrawColorBuffers(ArrayVec<[u8; MAX_COLOR_ATTACHMENTS]>),
+    /// Discard the following draws and dispatches if the predicate is zero,
+    /// or non-zero if inverted.
+    BeginConditionalRendering {
+        buffer: n::RawBuffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    },
+    EndConditionalRendering,
+    BeginQuery(u32, n::Query),
+    EndQuery(u32),
+    BeginPerfMonitor(perf::Monitor),
+    EndPerfMonitor(perf::Monitor),
+    /// Copy the result of an occlusion query into the buffer.
+    CopyQueryResult {
+        query: n::Query,
+        buffer: n::RawBuffer,
+        offset: buffer::Offset,
+        flags: query::ResultFlags,
+    },
     SetPatchSize(i32),
     BindProgram(<GlContext as glow::HasContext>::Program),
     SetBlend(Option<pso::BlendState>),
@@ -160,7 +185,41 @@ pub enum Command {
     SetDepthMask(bool),
     SetStencilMask(pso::StencilValue),
     SetStencilMaskSeparate(pso::Sided<pso::StencilValue>),
+    SetStencilTest(bool),
+    /// Set the stencil function and operations of the given faces.
+    SetStencilFaces {
+        faces: pso::Face,
+        ops: pso::Sided<pso::StencilFace>,
+        read_masks: pso::Sided<pso::StencilValue>,
+        ref_values: pso::Sided<pso::StencilValue>,
+    },
     MemoryBarrier(u32),
+    BeginTransformFeedback(u32),
+    EndTransformFeedback,
+}
+
+impl Command {
+    /// Returns the buffer that the command may write to.
+    pub(crate) fn written_buffer(&self) -> Option<n::RawBuffer> {
+        match *self {
+            Command::FillBuffer(buffer, ..)
+            | Command::CopyBufferToBuffer {
+                dst_buffer: buffer, ..
+            }
+            | Command::CopyTextureToBuffer {
+                dst_buffer: buffer, ..
+            }
+            | Command::CopyRenderbufferToBuffer(_, buffer, _) => Some(buffer),
+            // Storage and transform feedback buffers are written by the shaders.
+            Command::BindBufferRange(target, _, buffer, ..)
+                if target == glow::SHADER_STORAGE_BUFFER
+                    || target == glow::TRANSFORM_FEEDBACK_BUFFER =>
+            {
+                Some(buffer)
+            }
+            _ => None,
+        }
+    }
 }
 
 pub type FrameBufferTarget = u32;
@@ -194,8 +253,14 @@ struct Cache {
     index_type_range: Option<(hal::IndexType, Range<buffer::Offset>)>,
     // Stencil reference values (front, back).
     stencil_ref: Option<(pso::StencilValue, pso::StencilValue)>,
+    // Stencil read masks.
+    stencil_read_mask: pso::Sided<pso::StencilValue>,
+    // Stencil operations, unknown until they're set by a pipeline or dynamically.
+    stencil_ops: Option<pso::Sided<pso::StencilFace>>,
     // Blend color.
     blend_color: Option<pso::ColorValue>,
+    // Target of the active occlusion query.
+    occlusion_query: Option<u32>,
     ///
     framebuffer: Option<(FrameBufferTarget, n::RawFramebuffer)>,
     ///
@@ -207,8 +272,8 @@ struct Cache {
     program: Option<n::Program>,
     // Blend per attachment.
     blend_targets: Vec<Option<pso::ColorBlendDesc>>,
-    // Maps bound vertex buffer offset (index) to handle / buffer range
-    vertex_buffers: Vec<Option<(n::RawBuffer, Range<buffer::Offset>)>>,
+    // Maps bound vertex buffer offset (index) to handle / buffer range / dynamic stride
+    vertex_buffers: Vec<Option<(n::RawBuffer, Range<buffer::Offset>, Option<pso::ElemStride>)>>,
     // Active vertex buffer descriptions.
     vertex_buffer_descs: Vec<Option<pso::VertexBufferDesc>>,
     // Active attributes.
@@ -231,7 +296,10 @@ impl Cache {
             primitive: None,
             index_type_range: None,
             stencil_ref: None,
+            stencil_read_mask: pso::Sided::new(!0),
+            stencil_ops: None,
             blend_color: None,
+            occlusion_query: None,
             framebuffer: None,
             error_state: false,
             patch_size: None,
@@ -481,6 +549,25 @@ impl CommandBuffer {
         }
     }
 
+    fn bind_vertex_buffer(
+        &mut self,
+        index: usize,
+        buffer: &n::Buffer,
+        sub: buffer::SubRange,
+        stride: Option<pso::ElemStride>,
+    ) {
+        if self.cache.vertex_buffers.len() <= index {
+            self.cache.vertex_buffers.resize(index + 1, None);
+        }
+
+        let bounded_buffer = buffer.as_bound();
+        self.cache.vertex_buffers[index] = Some((
+            bounded_buffer.raw,
+            crate::resolve_sub_range(&sub, bounded_buffer.range),
+            stride,
+        ));
+    }
+
     pub(crate) fn bind_attributes(&mut self, first_instance: u32) {
         let Cache {
             ref attributes,
@@ -496,21 +583,28 @@ impl CommandBuffer {
                 log::error!("No vertex buffer bound at {}", binding);
             }
 
-            let (handle, range) = vertex_buffers[binding].as_ref().unwrap();
+            let (handle, range, dynamic_stride) = vertex_buffers[binding].as_ref().unwrap();
 
             let mut attribute = attribute.clone();
             attribute.offset += range.start as u32;
 
             match vertex_buffer_descs.get(binding) {
                 Some(&Some(desc)) => {
+                    let stride = match desc.stride {
+                        pso::State::Static(stride) => stride,
+                        pso::State::Dynamic => dynamic_stride.unwrap_or_else(|| {
+                            log::error!("No vertex buffer stride bound at {}", binding);
+                            0
+                        }),
+                    };
                     if let pso::VertexInputRate::Instance(_) = desc.rate {
-                        attribute.offset += desc.stride * first_instance as u32;
+                        attribute.offset += stride * first_instance as u32;
                     }
 
                     self.data.push_cmd(Command::BindAttribute(
                         attribute,
                         *handle,
-                        desc.stride as _,
+                        stride as _,
                         desc.rate.as_uint() as u32,
                     ));
                 }
@@ -520,7 +614,7 @@ impl CommandBuffer {
     }
 
     fn begin_subpass(&mut self) {
-        let state = self.pass_cache.as_ref().unwrap();
+        let state = self.pass_cache.take().unwrap();
         let subpass = &state.render_pass.subpasses[self.cur_subpass as usize];
 
         // See `begin_renderpass_cache` for clearing strategy
@@ -556,28 +650,11 @@ impl CommandBuffer {
                     if rat.ops.load != pass::AttachmentLoadOp::Clear {
                         continue;
                     }
-                    let channel = view_format.base_format().1;
-                    let draw_color_index = draw_color_index as u32;
-
-                    self.data.push_cmd(match channel {
-                        ChannelType::Unorm
-                        | ChannelType::Snorm
-                        | ChannelType::Ufloat
-                        | ChannelType::Sfloat
-                        | ChannelType::Srgb
-                        | ChannelType::Uscaled
-                        | ChannelType::Sscaled => {
-                            Command::ClearBufferColorF(draw_color_index, unsafe {
-                                info.clear_value.color.float32
-                            })
-                        }
-                        ChannelType::Uint => Command::ClearBufferColorU(draw_color_index, unsafe {
-                            info.clear_value.color.uint32
-                        }),
-                        ChannelType::Sint => Command::ClearBufferColorI(draw_color_index, unsafe {
-                            info.clear_value.color.sint32
-                        }),
-                    });
+                    self.clear_color_target(
+                        draw_color_index,
+                        view_format.base_format().1,
+                        info.clear_value,
+                    );
                 }
                 // Clear depth-stencil target
                 None => {
@@ -588,10 +665,6 @@ impl CommandBuffer {
                     } else {
                         None
                     };
-
-                    // Only reset depth mask if it was non writable
-                    let depth_mask = self.cache.depth_mask.filter(|mask| !mask);
-
                     let stencil = if view_format.is_stencil()
                         && rat.stencil_ops.load == pass::AttachmentLoadOp::Clear
                     {
@@ -599,34 +672,86 @@ impl CommandBuffer {
                     } else {
                         None
                     };
+                    self.clear_depth_stencil_target(depth, stencil);
+                }
+            }
+        }
 
-                    let stencil_mask = self
-                        .cache
-                        .stencil_mask
-                        .filter(|mask| mask.front != !0 || mask.back != !0);
+        self.pass_cache = Some(state);
+    }
 
-                    // Temporarily reset masks as they may prevent buffer clear in gl
-                    if depth_mask.is_some() || self.cache.depth_mask.is_none() {
-                        self.data.push_cmd(Command::SetDepthMask(true));
-                    }
-                    if stencil_mask.is_some() || self.cache.stencil_mask.is_none() {
-                        self.data.push_cmd(Command::SetStencilMask(!0));
-                    }
+    fn clear_color_target(
+        &mut self,
+        draw_color_index: u8,
+        channel: ChannelType,
+        clear_value: command::ClearValue,
+    ) {
+        let draw_color_index = draw_color_index as u32;
+
+        self.data.push_cmd(match channel {
+            ChannelType::Unorm
+            | ChannelType::Snorm
+            | ChannelType::Ufloat
+            | ChannelType::Sfloat
+            | ChannelType::Srgb
+            | ChannelType::Uscaled
+            | ChannelType::Sscaled => {
+                Command::ClearBufferColorF(draw_color_index, unsafe { clear_value.color.float32 })
+            }
+            ChannelType::Uint => {
+                Command::ClearBufferColorU(draw_color_index, unsafe { clear_value.color.uint32 })
+            }
+            ChannelType::Sint => {
+                Command::ClearBufferColorI(draw_color_index, unsafe { clear_value.color.sint32 })
+            }
+        });
+    }
 
-                    if depth.is_some() || stencil.is_some() {
-                        self.data
-                            .push_cmd(Command::ClearBufferDepthStencil(depth, stencil));
-                    }
+    fn clear_depth_stencil_target(
+        &mut self,
+        depth: Option<pso::DepthValue>,
+        stencil: Option<pso::StencilValue>,
+    ) {
+        // Only reset depth mask if it was non writable
+        let depth_mask = self.cache.depth_mask.filter(|mask| !mask);
 
-                    // Restore masks if they were reset
-                    if let Some(mask) = depth_mask {
-                        self.data.push_cmd(Command::SetDepthMask(mask));
-                    }
-                    if let Some(mask) = stencil_mask {
-                        self.data.push_cmd(Command::SetStencilMaskSeparate(mask));
-                    }
-                }
-            }
+        let stencil_mask = self
+            .cache
+            .stencil_mask
+            .filter(|mask| mask.front != !0 || mask.back != !0);
+
+        // Temporarily reset masks as they may prevent buffer clear in gl
+        if depth_mask.is_some() || self.cache.depth_mask.is_none() {
+            self.data.push_cmd(Command::SetDepthMask(true));
+        }
+        if stencil_mask.is_some() || self.cache.stencil_mask.is_none() {
+            self.data.push_cmd(Command::SetStencilMask(!0));
+        }
+
+        if depth.is_some() || stencil.is_some() {
+            self.data
+                .push_cmd(Command::ClearBufferDepthStencil(depth, stencil));
+        }
+
+        // Restore masks if they were reset
+        if let Some(mask) = depth_mask {
+            self.data.push_cmd(Command::SetDepthMask(mask));
+        }
+        if let Some(mask) = stencil_mask {
+            self.data.push_cmd(Command::SetStencilMaskSeparate(mask));
+        }
+    }
+
+    /// Sets the cached stencil function and operations of `faces`, once the operations are known.
+    fn update_stencil_faces(&mut self, faces: pso::Face) {
+        if let Some(ops) = self.cache.stencil_ops {
+            let (front, back) = self.cache.stencil_ref.unwrap_or((0, 0));
+            self.data.push_cmd(Command::SetStencilFaces {
+                faces,
+                ops,
+                read_masks: self.cache.stencil_read_mask,
+                ref_values: pso::Sided { front, back },
+            });
         }
     }
 
@@ -676,43 +801,12 @@ impl CommandBuffer {
                 desc_set.layout.iter().zip(desc_set.bindings.iter())
             {
                 let binding = layout.sets[set].bindings[binding_layout.binding as usize] as u32;
-                match *new_binding {
-                    n::DescSetBindings::Buffer {
-                        register,
-                        buffer,
-                        offset,
-                        size,
-                    } => {
-                        let bind_point = match register {
-                            n::BindingRegister::UniformBuffers => glow::UNIFORM_BUFFER,
-                            n::BindingRegister::StorageBuffers => glow::SHADER_STORAGE_BUFFER,
-                            n::BindingRegister::Textures => panic!("Wrong desc set binding"),
-                        };
-                        self.data.push_cmd(Command::BindBufferRange(
-                            bind_point,
-                            binding,
-                            buffer,
-                            offset as i32,
-                            size as i32,
-                        ));
-                    }
-                    n::DescSetBindings::Texture(texture, textype) => {
-                        dirty_textures |= 1 << binding;
-                        self.cache.texture_slots[binding as usize].tex_target = textype;
-                        self.data
-                            .push_cmd(Command::BindTexture(binding, texture, textype));
-                    }
-                    n::DescSetBindings::Sampler(sampler) => {
-                        dirty_samplers |= 1 << binding;
-                        self.cache.samplers[binding as usize] =
-                            Some(n::FatSampler::Sampler(sampler));
-                    }
-                    n::DescSetBindings::SamplerDesc(ref info) => {
-                        dirty_samplers |= 1 << binding;
-                        self.cache.samplers[binding as usize] =
-                            Some(n::FatSampler::Info(info.clone()));
-                    }
-                }
+                self.bind_descriptor(
+                    binding,
+                    new_binding,
+                    &mut dirty_textures,
+                    &mut dirty_samplers,
+                );
             }
 
             set += 1;
@@ -720,6 +814,92 @@ impl CommandBuffer {
 
         self.update_sampler_states(dirty_textures, dirty_samplers);
     }
+
+    fn push_descriptor_set<'a, I>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        mut array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        let set_layout = &layout.sets[set];
+        let mut layout_index = set_layout
+            .layout
+            .binary_search_by_key(&binding, |b| b.binding)
+            .unwrap();
+
+        let mut dirty_textures = 0u32;
+        let mut dirty_samplers = 0u32;
+        let mut new_bindings = Vec::new();
+        for descriptor in descriptors {
+            let binding_layout = &set_layout.layout[layout_index];
+            let binding = set_layout.bindings[binding_layout.binding as usize] as u32;
+            crate::device::resolve_descriptor(binding_layout, descriptor, &mut new_bindings);
+            for new_binding in new_bindings.drain(..) {
+                self.bind_descriptor(
+                    binding,
+                    &new_binding,
+                    &mut dirty_textures,
+                    &mut dirty_samplers,
+                );
+            }
+
+            array_offset += 1;
+            if array_offset == binding_layout.count {
+                array_offset = 0;
+                layout_index += 1;
+            }
+        }
+
+        self.update_sampler_states(dirty_textures, dirty_samplers);
+    }
+
+    fn bind_descriptor(
+        &mut self,
+        binding: u32,
+        new_binding: &n::DescSetBindings,
+        dirty_textures: &mut u32,
+        dirty_samplers: &mut u32,
+    ) {
+        match *new_binding {
+            n::DescSetBindings::Buffer {
+                register,
+                buffer,
+                offset,
+                size,
+            } => {
+                let bind_point = match register {
+                    n::BindingRegister::UniformBuffers => glow::UNIFORM_BUFFER,
+                    n::BindingRegister::StorageBuffers => glow::SHADER_STORAGE_BUFFER,
+                    n::BindingRegister::Textures => panic!("Wrong desc set binding"),
+                };
+                self.data.push_cmd(Command::BindBufferRange(
+                    bind_point,
+                    binding,
+                    buffer,
+                    offset as i32,
+                    size as i32,
+                ));
+            }
+            n::DescSetBindings::Texture(texture, textype) => {
+                *dirty_textures |= 1 << binding;
+                self.cache.texture_slots[binding as usize].tex_target = textype;
+                self.data
+                    .push_cmd(Command::BindTexture(binding, texture, textype));
+            }
+            n::DescSetBindings::Sampler(sampler) => {
+                *dirty_samplers |= 1 << binding;
+                self.cache.samplers[binding as usize] = Some(n::FatSampler::Sampler(sampler));
+            }
+            n::DescSetBindings::SamplerDesc(ref info) => {
+                *dirty_samplers |= 1 << binding;
+                self.cache.samplers[binding as usize] = Some(n::FatSampler::Info(info.clone()));
+            }
+        }
+    }
 }
 
 impl command::CommandBuffer<Backend> for CommandBuffer {
@@ -863,6 +1043,7 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
             framebuffer: framebuffer.raw,
             colors,
             depth_stencil,
+            view_mask: render_pass.subpasses[0].view_mask,
         });
 
         // Enter first subpass
@@ -878,6 +1059,69 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         // TODO
     }
 
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        _render_area: pso::Rect,
+        color_attachments: T,
+        depth_stencil_attachment: Option<command::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = command::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        let fbo = self
+            .fbo
+            .expect("Framebuffer objects are required to begin rendering");
+
+        let color_attachments = color_attachments.collect::<ArrayVec<[_; MAX_COLOR_ATTACHMENTS]>>();
+        let colors = color_attachments
+            .iter()
+            .map(|info| info.image_view.clone())
+            .collect();
+        let depth_stencil = depth_stencil_attachment
+            .as_ref()
+            .map(|info| info.image_view.clone());
+
+        self.data.push_cmd(Command::BindFramebuffer {
+            target: glow::DRAW_FRAMEBUFFER,
+            framebuffer: fbo,
+            colors,
+            depth_stencil,
+            view_mask: 0,
+        });
+        self.data.push_cmd(Command::SetDrawColorBuffers(
+            (0..color_attachments.len() as u8).collect(),
+        ));
+        self.data
+            .push_cmd(Command::SetColorMask(None, pso::ColorMask::ALL));
+
+        for (index, info) in color_attachments.iter().enumerate() {
+            if info.ops.load == pass::AttachmentLoadOp::Clear {
+                self.clear_color_target(index as u8, info.image_view.channel(), info.clear_value);
+            }
+        }
+        if let Some(info) = depth_stencil_attachment {
+            let aspects = info.image_view.aspects();
+            let depth = if aspects.contains(Aspects::DEPTH)
+                && info.ops.load == pass::AttachmentLoadOp::Clear
+            {
+                Some(info.clear_value.depth_stencil.depth)
+            } else {
+                None
+            };
+            let stencil = if aspects.contains(Aspects::STENCIL)
+                && info.stencil_ops.load == pass::AttachmentLoadOp::Clear
+            {
+                Some(info.clear_value.depth_stencil.stencil)
+            } else {
+                None
+            };
+            self.clear_depth_stencil_target(depth, stencil);
+        }
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        // TODO
+    }
+
     unsafe fn clear_image<T>(
         &mut self,
         image: &n::Image,
@@ -901,6 +1145,7 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
                     n::ImageType::Renderbuffer { raw, .. } => n::ImageView::Renderbuffer {
                         raw,
                         aspects: image.format_desc.aspects,
+                        channel: image.channel,
                     },
                     n::ImageType::Texture {
                         target,
@@ -921,6 +1166,7 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
                                 level_start: 0,
                                 level_count: Some(level_count),
                             },
+                            channel: image.channel,
                         }
                     }
                 };
@@ -929,6 +1175,7 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
                     framebuffer: fbo,
                     colors: iter::once(view).collect(),
                     depth_stencil: None,
+                    view_mask: 0,
                 });
                 self.data
                     .push_cmd(Command::SetDrawColorBuffers(iter::once(0).collect()));
@@ -1017,16 +1264,19 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         T: Iterator<Item = (&'a n::Buffer, buffer::SubRange)>,
     {
         for (i, (buffer, sub)) in buffers.enumerate() {
-            let index = first_binding as usize + i;
-            if self.cache.vertex_buffers.len() <= index {
-                self.cache.vertex_buffers.resize(index + 1, None);
-            }
+            self.bind_vertex_buffer(first_binding as usize + i, buffer, sub, None);
+        }
+    }
 
-            let bounded_buffer = buffer.as_bound();
-            self.cache.vertex_buffers[index] = Some((
-                bounded_buffer.raw,
-                crate::resolve_sub_range(&sub, bounded_buffer.range),
-            ));
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        first_binding: pso::BufferIndex,
+        buffers: T,
+    ) where
+        T: Iterator<Item = (&'a n::Buffer, buffer::SubRange, pso::ElemStride)>,
+    {
+        for (i, (buffer, sub, stride)) in buffers.enumerate() {
+            self.bind_vertex_buffer(first_binding as usize + i, buffer, sub, Some(stride));
         }
     }
 
@@ -1127,19 +1377,33 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
             back = value;
         }
 
-        // Only cache the stencil references values until
-        // we assembled all the pieces to set the stencil state
-        // from the pipeline.
         self.cache.stencil_ref = Some((front, back));
+        self.update_stencil_faces(faces);
     }
 
-    unsafe fn set_stencil_read_mask(&mut self, _faces: pso::Face, _value: pso::StencilValue) {
-        unimplemented!();
+    unsafe fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        if faces.contains(pso::Face::FRONT) {
+            self.cache.stencil_read_mask.front = value;
+        }
+        if faces.contains(pso::Face::BACK) {
+            self.cache.stencil_read_mask.back = value;
+        }
+        self.update_stencil_faces(faces);
     }
 
-    unsafe fn set_stencil_write_mask(&mut self, _faces: pso::Face, _value: pso::StencilValue) {
-        // set self.cache.stencil_mask once implemented
-        unimplemented!();
+    unsafe fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        let mut mask = self
+            .cache
+            .stencil_mask
+            .unwrap_or_else(|| pso::Sided::new(!0));
+        if faces.contains(pso::Face::FRONT) {
+            mask.front = value;
+        }
+        if faces.contains(pso::Face::BACK) {
+            mask.back = value;
+        }
+        self.data.push_cmd(Command::SetStencilMaskSeparate(mask));
+        self.cache.stencil_mask = Some(mask);
     }
 
     unsafe fn set_blend_constants(&mut self, cv: pso::ColorValue) {
@@ -1161,9 +1425,48 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!()
     }
 
+    unsafe fn set_cull_mode(&mut self, faces: pso::Face) {
+        self.data.push_cmd(Command::SetCullFace(faces));
+    }
+
+    unsafe fn set_front_face(&mut self, front_face: pso::FrontFace) {
+        self.data.push_cmd(Command::SetFrontFace(front_face));
+    }
+
+    unsafe fn set_primitive_topology(&mut self, primitive: pso::Primitive) {
+        self.cache.primitive = Some(conv::input_assember_to_gl_primitive(
+            &pso::InputAssemblerDesc::new(primitive),
+        ));
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, enable: bool) {
+        self.data.push_cmd(Command::SetDepthTest(enable));
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, enable: bool) {
+        self.data.push_cmd(Command::SetDepthMask(enable));
+        self.cache.depth_mask = Some(enable);
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, fun: pso::Comparison) {
+        self.data.push_cmd(Command::SetDepthFunc(fun));
+    }
+
+    unsafe fn set_stencil_op(&mut self, faces: pso::Face, ops: pso::StencilFace) {
+        let mut sided_ops = self.cache.stencil_ops.unwrap_or_default();
+        if faces.contains(pso::Face::FRONT) {
+            sided_ops.front = ops;
+        }
+        if faces.contains(pso::Face::BACK) {
+            sided_ops.back = ops;
+        }
+        self.cache.stencil_ops = Some(sided_ops);
+        self.update_stencil_faces(faces);
+    }
+
     unsafe fn bind_graphics_pipeline(&mut self, pipeline: &n::GraphicsPipeline) {
-        if self.cache.primitive != Some(pipeline.primitive) {
-            self.cache.primitive = Some(pipeline.primitive);
+        if let pso::State::Static(primitive) = pipeline.primitive {
+            self.cache.primitive = Some(primitive);
         }
 
         if self.cache.patch_size != pipeline.patch_size {
@@ -1188,12 +1491,46 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         self.data.push_cmd(Command::BindRasterizer {
             rasterizer: pipeline.rasterizer,
         });
-        self.data
-            .push_cmd(Command::BindDepth(pipeline.depth.map(|d| d.fun)));
-        self.data.push_cmd(Command::SetDepthMask(
-            pipeline.depth.map_or(true, |d| d.write),
-        ));
-        self.cache.depth_mask = pipeline.depth.map(|d| d.write);
+        match pipeline.depth {
+            pso::State::Static(Some(depth)) => {
+                self.data.push_cmd(match depth.fun {
+                    pso::State::Static(fun) => Command::BindDepth(Some(fun)),
+                    pso::State::Dynamic => Command::SetDepthTest(true),
+                });
+                if let pso::State::Static(write) = depth.write {
+                    self.data.push_cmd(Command::SetDepthMask(write));
+                    self.cache.depth_mask = Some(write);
+                }
+            }
+            pso::State::Static(None) => {
+                self.data.push_cmd(Command::BindDepth(None));
+                self.data.push_cmd(Command::SetDepthMask(true));
+                self.cache.depth_mask = None;
+            }
+            // The depth state is entirely set through the command buffer.
+            pso::State::Dynamic => {}
+        }
+        match pipeline.stencil {
+            Some(ref stencil) => {
+                // The dynamic states keep the values set through the command buffer.
+                if let pso::State::Static(ops) = stencil.faces {
+                    self.cache.stencil_ops = Some(ops);
+                }
+                if let pso::State::Static(masks) = stencil.read_masks {
+                    self.cache.stencil_read_mask = masks;
+                }
+                if let pso::State::Static(values) = stencil.reference_values {
+                    self.cache.stencil_ref = Some((values.front, values.back));
+                }
+                if let pso::State::Static(masks) = stencil.write_masks {
+                    self.data.push_cmd(Command::SetStencilMaskSeparate(masks));
+                    self.cache.stencil_mask = Some(masks);
+                }
+                self.data.push_cmd(Command::SetStencilTest(true));
+                self.update_stencil_faces(pso::Face::all());
+            }
+            None => self.data.push_cmd(Command::SetStencilTest(false)),
+        }
 
         if let Some(ref vp) = pipeline.baked_states.viewport {
             self.set_viewports(0, iter::once(vp.clone()));
@@ -1259,6 +1596,32 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         self.bind_descriptor_sets(layout, first_set, sets, offsets)
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(layout, set, binding, array_offset, descriptors)
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(layout, set, binding, array_offset, descriptors)
+    }
+
     unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
         self.data.push_cmd(Command::Dispatch(count));
     }
@@ -1411,6 +1774,22 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, _descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn copy_acceleration_structure(&mut self, _src: &(), _dst: &()) {
+        unimplemented!()
+    }
+
     unsafe fn draw(
         &mut self,
         vertices: Range<hal::VertexCount>,
@@ -1582,34 +1961,131 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!()
     }
 
-    unsafe fn begin_query(&mut self, _query: query::Query<Backend>, _flags: query::ControlFlags) {
-        unimplemented!()
+    unsafe fn begin_query(&mut self, query: query::Query<Backend>, flags: query::ControlFlags) {
+        match *query.pool {
+            n::QueryPool::Occlusion(ref queries) => {
+                let target = if flags.contains(query::ControlFlags::PRECISE) {
+                    glow::SAMPLES_PASSED
+                } else {
+                    glow::ANY_SAMPLES_PASSED
+                };
+                self.cache.occlusion_query = Some(target);
+                self.data
+                    .push_cmd(Command::BeginQuery(target, queries[query.id as usize]));
+            }
+            n::QueryPool::PerformanceCounters { ref monitors, .. } => {
+                self.data
+                    .push_cmd(Command::BeginPerfMonitor(monitors[query.id as usize]));
+            }
+        }
     }
 
     unsafe fn copy_query_pool_results(
         &mut self,
-        _pool: &(),
-        _queries: Range<query::Id>,
-        _buffer: &n::Buffer,
-        _offset: buffer::Offset,
-        _stride: buffer::Stride,
-        _flags: query::ResultFlags,
+        pool: &n::QueryPool,
+        queries: Range<query::Id>,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        stride: buffer::Stride,
+        flags: query::ResultFlags,
     ) {
-        unimplemented!()
+        let bounded_buffer = buffer.as_bound();
+        match *pool {
+            n::QueryPool::Occlusion(ref raw_queries) => {
+                let raw_queries = &raw_queries[queries.start as usize..queries.end as usize];
+                for (i, &query) in raw_queries.iter().enumerate() {
+                    self.data.push_cmd(Command::CopyQueryResult {
+                        query,
+                        buffer: bounded_buffer.raw,
+                        offset: bounded_buffer.range.start + offset + (i as u64 * stride as u64),
+                        flags,
+                    });
+                }
+            }
+            n::QueryPool::PerformanceCounters { .. } => {
+                panic!("Performance query results can only be read by the host")
+            }
+        }
     }
 
-    unsafe fn end_query(&mut self, _query: query::Query<Backend>) {
-        unimplemented!()
+    unsafe fn end_query(&mut self, query: query::Query<Backend>) {
+        match *query.pool {
+            n::QueryPool::Occlusion(_) => {
+                let target = self
+                    .cache
+                    .occlusion_query
+                    .take()
+                    .expect("No occlusion query is active");
+                self.data.push_cmd(Command::EndQuery(target));
+            }
+            n::QueryPool::PerformanceCounters { ref monitors, .. } => {
+                self.data
+                    .push_cmd(Command::EndPerfMonitor(monitors[query.id as usize]));
+            }
+        }
     }
 
-    unsafe fn reset_query_pool(&mut self, _pool: &(), _queries: Range<query::Id>) {
-        unimplemented!()
+    unsafe fn reset_query_pool(&mut self, _pool: &n::QueryPool, _queries: Range<query::Id>) {
+        // Query objects are reset when they begin.
     }
 
     unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Backend>) {
         unimplemented!()
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        let bounded_buffer = buffer.as_bound();
+        self.data.push_cmd(Command::BeginConditionalRendering {
+            buffer: bounded_buffer.raw,
+            offset: bounded_buffer.range.start + offset,
+            inverted,
+        });
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.data.push_cmd(Command::EndConditionalRendering);
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, first_binding: u32, buffers: T)
+    where
+        T: Iterator<Item = (&'a n::Buffer, buffer::SubRange)>,
+    {
+        for (i, (buffer, sub)) in buffers.enumerate() {
+            let bounded_buffer = buffer.as_bound();
+            let range = bounded_buffer.range;
+            self.data.push_cmd(Command::BindBufferRange(
+                glow::TRANSFORM_FEEDBACK_BUFFER,
+                first_binding + i as u32,
+                bounded_buffer.raw,
+                (range.start + sub.offset) as i32,
+                sub.size_to(range.end - range.start) as i32,
+            ));
+        }
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        match self.cache.primitive {
+            Some(primitive) => {
+                self.data.push_cmd(Command::BeginTransformFeedback(
+                    conv::transform_feedback_primitive(primitive),
+                ));
+            }
+            None => {
+                log::warn!("No primitive bound. An active pipeline needs to be bound before calling `begin_transform_feedback`.");
+                self.cache.error_state = true;
+            }
+        }
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.data.push_cmd(Command::EndTransformFeedback);
+    }
+
     unsafe fn push_graphics_constants(
         &mut self,
         _layout: &n::PipelineLayout,
@@ -61,6 +61,36 @@ pub fn input_assember_to_gl_primitive(ia: &pso::InputAssemblerDesc) -> u32 {
     }
 }
 
+pub fn front_face_to_gl(front_face: pso::FrontFace) -> u32 {
+    match front_face {
+        pso::FrontFace::Clockwise => glow::CW,
+        pso::FrontFace::CounterClockwise => glow::CCW,
+    }
+}
+
+/// Returns the faces to pass to `glCullFace`, or `None` if culling is disabled.
+pub fn cull_face_to_gl(faces: pso::Face) -> Option<u32> {
+    match faces {
+        pso::Face::NONE => None,
+        pso::Face::FRONT => Some(glow::FRONT),
+        pso::Face::BACK => Some(glow::BACK),
+        _ => Some(glow::FRONT_AND_BACK),
+    }
+}
+
+/// Returns the primitive mode `glBeginTransformFeedback` expects for draws of `primitive`.
+pub fn transform_feedback_primitive(primitive: u32) -> u32 {
+    match primitive {
+        glow::POINTS => glow::POINTS,
+        glow::LINES
+        | glow::LINE_STRIP
+        | glow::LINE_LOOP
+        | glow::LINES_ADJACENCY
+        | glow::LINE_STRIP_ADJACENCY => glow::LINES,
+        _ => glow::TRIANGLES,
+    }
+}
+
 pub struct FormatDescription {
     pub tex_internal: u32,
     pub tex_external: u32,
@@ -264,3 +294,85 @@ pub fn map_naga_stage_to_cross(stage: naga::ShaderStage) -> spirv_cross::spirv::
         naga::ShaderStage::Compute => Em::GlCompute,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitive_to_gl() {
+        let cases = [
+            (pso::Primitive::PointList, false, glow::POINTS),
+            (pso::Primitive::LineList, false, glow::LINES),
+            (pso::Primitive::LineList, true, glow::LINES_ADJACENCY),
+            (pso::Primitive::LineStrip, false, glow::LINE_STRIP),
+            (pso::Primitive::LineStrip, true, glow::LINE_STRIP_ADJACENCY),
+            (pso::Primitive::TriangleList, false, glow::TRIANGLES),
+            (
+                pso::Primitive::TriangleList,
+                true,
+                glow::TRIANGLES_ADJACENCY,
+            ),
+            (pso::Primitive::TriangleStrip, false, glow::TRIANGLE_STRIP),
+            (
+                pso::Primitive::TriangleStrip,
+                true,
+                glow::TRIANGLE_STRIP_ADJACENCY,
+            ),
+            (pso::Primitive::PatchList(3), false, glow::PATCHES),
+        ];
+        for &(primitive, with_adjacency, expected) in cases.iter() {
+            let ia = pso::InputAssemblerDesc {
+                with_adjacency,
+                ..pso::InputAssemblerDesc::new(primitive)
+            };
+            assert_eq!(
+                input_assember_to_gl_primitive(&ia),
+                expected,
+                "{:?}",
+                primitive
+            );
+        }
+    }
+
+    #[test]
+    fn test_transform_feedback_primitive() {
+        let cases = [
+            (glow::POINTS, glow::POINTS),
+            (glow::LINES, glow::LINES),
+            (glow::LINE_STRIP, glow::LINES),
+            (glow::LINE_LOOP, glow::LINES),
+            (glow::LINES_ADJACENCY, glow::LINES),
+            (glow::LINE_STRIP_ADJACENCY, glow::LINES),
+            (glow::TRIANGLES, glow::TRIANGLES),
+            (glow::TRIANGLE_STRIP, glow::TRIANGLES),
+            (glow::TRIANGLE_FAN, glow::TRIANGLES),
+            (glow::TRIANGLES_ADJACENCY, glow::TRIANGLES),
+            (glow::TRIANGLE_STRIP_ADJACENCY, glow::TRIANGLES),
+        ];
+        for &(primitive, expected) in cases.iter() {
+            assert_eq!(
+                transform_feedback_primitive(primitive),
+                expected,
+                "{:#x}",
+                primitive
+            );
+        }
+    }
+
+    #[test]
+    fn test_rasterizer_state_to_gl() {
+        assert_eq!(front_face_to_gl(pso::FrontFace::Clockwise), glow::CW);
+        assert_eq!(
+            front_face_to_gl(pso::FrontFace::CounterClockwise),
+            glow::CCW
+        );
+        assert_eq!(cull_face_to_gl(pso::Face::NONE), None);
+        assert_eq!(cull_face_to_gl(pso::Face::FRONT), Some(glow::FRONT));
+        assert_eq!(cull_face_to_gl(pso::Face::BACK), Some(glow::BACK));
+        assert_eq!(
+            cull_face_to_gl(pso::Face::all()),
+            Some(glow::FRONT_AND_BACK)
+        );
+    }
+}
@@ -1,24 +1,33 @@
 use crate::{
     command as cmd, conv,
-    info::LegacyFeatures,
-    native as n,
+    info::{LegacyFeatures, GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX},
+    native as n, perf,
     pool::{BufferMemory, CommandPool, OwnedBuffer},
     state, Backend as B, FastHashMap, GlContainer, GlContext, MemoryUsage, Share, Starc,
-    MAX_TEXTURE_SLOTS,
+    CPU_VISIBLE_HEAP, DEVICE_LOCAL_HEAP, MAX_TEXTURE_SLOTS,
 };
 
 use hal::{
-    buffer, device as d,
+    adapter, buffer, device as d,
     format::{ChannelType, Format, Swizzle},
     image as i, memory, pass,
     pool::CommandPoolCreateFlags,
     pso, query, queue,
 };
 
+#[cfg(any(unix, windows))]
+use auxil::host_monotonic_ns;
 use glow::HasContext;
 use parking_lot::Mutex;
 
-use std::{ops::Range, slice, sync::Arc};
+use std::{
+    ops::Range,
+    slice,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 #[cfg(feature = "cross")]
 type CrossAst = spirv_cross::spirv::Ast<spirv_cross::glsl::Target>;
@@ -40,6 +49,10 @@ struct CompilationContext<'a> {
     layout: &'a n::PipelineLayout,
     sampler_map: &'a mut n::SamplerBindMap,
     name_binding_map: &'a mut FastHashMap<String, (n::BindingRegister, u8)>,
+    view_mask: u32,
+    /// Names of the vertex outputs by location, reflected when they are
+    /// captured by transform feedback.
+    varyings: Option<&'a mut FastHashMap<pso::Location, String>>,
 }
 
 impl<'a> CompilationContext<'a> {
@@ -48,6 +61,8 @@ impl<'a> CompilationContext<'a> {
             layout: self.layout,
             sampler_map: self.sampler_map,
             name_binding_map: self.name_binding_map,
+            view_mask: self.view_mask,
+            varyings: self.varyings.as_deref_mut(),
         }
     }
 }
@@ -57,6 +72,8 @@ impl<'a> CompilationContext<'a> {
 pub struct Device {
     pub(crate) share: Starc<Share>,
     features: hal::Features,
+    /// Bytes allocated from the device-local and CPU-visible heaps.
+    memory_usage: [AtomicU64; 2],
     #[cfg(feature = "cross")]
     spv_options: naga::back::spv::Options,
 }
@@ -73,6 +90,7 @@ impl Device {
         Device {
             share: share,
             features,
+            memory_usage: Default::default(),
             #[cfg(feature = "cross")]
             spv_options: {
                 use naga::back::spv;
@@ -130,12 +148,15 @@ impl Device {
         &self,
         shaders: &[(naga::ShaderStage, Option<&pso::EntryPoint<B>>)],
         layout: &n::PipelineLayout,
+        transform_feedback: &[pso::TransformFeedbackVarying],
+        view_mask: u32,
     ) -> Result<(glow::Program, n::SamplerBindMap), pso::CreationError> {
         let gl = &self.share.context;
         let program = unsafe { gl.create_program().unwrap() };
 
         let mut name_binding_map = FastHashMap::<String, (n::BindingRegister, u8)>::default();
         let mut sampler_map = [None; MAX_TEXTURE_SLOTS];
+        let mut varyings = FastHashMap::default();
 
         let mut has_vertex_stage = false;
         let mut has_fragment_stage = false;
@@ -143,6 +164,12 @@ impl Device {
             layout,
             sampler_map: &mut sampler_map,
             name_binding_map: &mut name_binding_map,
+            view_mask,
+            varyings: if transform_feedback.is_empty() {
+                None
+            } else {
+                Some(&mut varyings)
+            },
         };
 
         let mut shaders_to_delete = arrayvec::ArrayVec::<[_; 3]>::new();
@@ -194,6 +221,18 @@ impl Device {
             }
         }
 
+        if !transform_feedback.is_empty() {
+            let varyings =
+                transform_feedback_varyings(transform_feedback, &varyings).map_err(|location| {
+                    let error = format!("No vertex output at location {} to capture", location);
+                    pso::CreationError::ShaderCreationError(pso::ShaderStageFlags::VERTEX, error)
+                })?;
+            let names = varyings.iter().map(String::as_str).collect::<Vec<_>>();
+            unsafe {
+                gl.transform_feedback_varyings(program, &names, glow::INTERLEAVED_ATTRIBS);
+            }
+        }
+
         unsafe {
             gl.link_program(program);
         }
@@ -262,6 +301,7 @@ impl Device {
                 raw,
                 ref sub,
                 is_3d: false,
+                ..
             } => unsafe {
                 gl.bind_texture(target, Some(raw));
                 gl.framebuffer_texture_2d(
@@ -277,6 +317,7 @@ impl Device {
                 raw,
                 ref sub,
                 is_3d: true,
+                ..
             } => unsafe {
                 gl.bind_texture(target, Some(raw));
                 gl.framebuffer_texture_3d(
@@ -301,6 +342,7 @@ impl Device {
                 raw,
                 ref sub,
                 is_3d: false,
+                ..
             } => unsafe {
                 gl.framebuffer_texture_2d(
                     point,
@@ -315,6 +357,7 @@ impl Device {
                 raw,
                 ref sub,
                 is_3d: true,
+                ..
             } => unsafe {
                 gl.framebuffer_texture_layer(
                     point,
@@ -327,6 +370,37 @@ impl Device {
         }
     }
 
+    pub(crate) fn bind_target_multiview(
+        gl: &GlContainer,
+        point: u32,
+        attachment: u32,
+        view: &n::ImageView,
+        view_mask: u32,
+    ) {
+        match *view {
+            n::ImageView::Texture {
+                target: glow::TEXTURE_2D_ARRAY,
+                raw,
+                ref sub,
+                ..
+            } => unsafe {
+                let framebuffer_texture_multiview =
+                    gl.functions.framebuffer_texture_multiview.unwrap();
+                framebuffer_texture_multiview(
+                    point,
+                    attachment,
+                    raw,
+                    sub.level_start as _,
+                    (sub.layer_start as u32 + view_mask.trailing_zeros()) as _,
+                    view_count(view_mask) as _,
+                );
+            },
+            // Views without layers can only hold a single view, which renders
+            // like a regular attachment.
+            _ => Self::bind_target(gl, point, attachment, view),
+        }
+    }
+
     #[cfg(feature = "cross")]
     fn parse_spirv_cross(&self, raw_data: &[u32]) -> Result<CrossAst, d::ShaderError> {
         use spirv_cross::{spirv, ErrorCode as Ec};
@@ -596,7 +670,7 @@ impl Device {
         gl: &GlContainer,
         shader: &d::NagaShader,
         options: &naga::back::glsl::Options,
-        context: CompilationContext,
+        mut context: CompilationContext,
     ) -> Result<n::Shader, d::ShaderError> {
         let mut output = String::new();
         let mut writer =
@@ -614,14 +688,28 @@ impl Device {
                 options.entry_point
             )))?;
 
+        let view_mask = context.view_mask;
         match writer.write() {
             Ok(reflection_info) => {
+                if let Some(varyings) = context.varyings.as_deref_mut() {
+                    let entry_point = &shader.module.entry_points[entry_point_index];
+                    *varyings = reflect_varyings(&shader.module, entry_point, &output).ok_or_else(
+                        || {
+                            d::ShaderError::CompilationFailed(
+                                "Couldn't reflect the vertex outputs".to_string(),
+                            )
+                        },
+                    )?;
+                }
                 Self::reflect_shader(
                     &shader.module,
                     shader.info.get_entry_point(entry_point_index),
                     reflection_info,
                     context,
                 );
+                if view_mask != 0 {
+                    output = declare_views(&output, options.shader_stage, view_mask);
+                }
                 log::debug!("Naga generated shader:\n{}", output);
                 Self::create_shader_module_raw(gl, &output, options.shader_stage)
             }
@@ -665,15 +753,24 @@ impl Device {
         };
         #[cfg(feature = "cross")]
         if result.is_err() {
+            // The captured outputs are reflected from the GLSL generated by `naga`,
+            // which SPIRV-Cross doesn't follow.
+            if context.varyings.is_some() && stage == naga::ShaderStage::Vertex {
+                log::warn!("Transform feedback can't capture the outputs of SPIRV-Cross");
+                return result;
+            }
             let mut ast = self.parse_spirv_cross(&ep.module.spv).unwrap();
             auxil::spirv_cross_specialize_ast(&mut ast, &ep.specialization).unwrap();
             self.remap_bindings(&mut ast, context.layout, context.name_binding_map);
             self.combine_separate_images_and_samplers(&mut ast, context.reborrow());
             self.set_push_const_layout(&mut ast).unwrap();
 
-            let glsl = self
+            let mut glsl = self
                 .translate_spirv_cross(&mut ast, stage, ep.entry)
                 .unwrap();
+            if context.view_mask != 0 {
+                glsl = declare_views(&glsl, stage, context.view_mask);
+            }
             log::debug!("SPIRV-Cross generated shader:\n{}", glsl);
             result = Self::create_shader_module_raw(&self.share.context, &glsl, stage);
         }
@@ -681,6 +778,219 @@ impl Device {
     }
 }
 
+/// Returns the number of views rendered at once by a multiview pass,
+/// which are consecutive layers starting from the lowest bit of the mask.
+fn view_count(view_mask: u32) -> u32 {
+    32 - view_mask.leading_zeros() - view_mask.trailing_zeros()
+}
+
+/// Returns true if the view mask selects consecutive layers, as `OVR_multiview2` needs.
+fn is_contiguous_view_mask(view_mask: u32) -> bool {
+    let shifted = view_mask >> view_mask.trailing_zeros().min(31);
+    shifted & shifted.wrapping_add(1) == 0
+}
+
+/// Rewrites the GLSL of a multiview pipeline stage for `OVR_multiview2`,
+/// which also needs the vertex stage to declare the number of views.
+fn declare_views(source: &str, stage: naga::ShaderStage, view_mask: u32) -> String {
+    let mut lines = source.lines();
+    let mut output = String::with_capacity(source.len());
+    // Extensions can only be enabled after the version.
+    if let Some(version) = lines.next() {
+        output.push_str(version);
+        output.push('\n');
+    }
+    output.push_str("#extension GL_OVR_multiview2 : require\n");
+    if stage == naga::ShaderStage::Vertex {
+        output.push_str(&format!(
+            "layout(num_views = {}) in;\n",
+            view_count(view_mask)
+        ));
+    }
+    for line in lines {
+        // `EXT_multiview` is only available to Vulkan GLSL.
+        if line.starts_with("#extension GL_EXT_multiview") {
+            continue;
+        }
+        output.push_str(&replace_identifier(
+            line,
+            "gl_ViewIndex",
+            "int(gl_ViewID_OVR)",
+        ));
+        output.push('\n');
+    }
+    output
+}
+
+/// Replaces the occurrences of `name` in the GLSL text which aren't part of a longer identifier.
+fn replace_identifier(source: &str, name: &str, replacement: &str) -> String {
+    let is_identifier = |c: char| c == '_' || c.is_ascii_alphanumeric();
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(position) = rest.find(name) {
+        let (before, after) = (&rest[..position], &rest[position + name.len()..]);
+        output.push_str(before);
+        let is_whole = !output.ends_with(is_identifier) && !after.starts_with(is_identifier);
+        output.push_str(if is_whole { replacement } else { name });
+        rest = after;
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Maps the locations of the vertex outputs to their names in the GLSL generated by `naga`.
+///
+/// `naga` doesn't reflect these names, but declares the outputs in the order of the
+/// entry point results, so they are matched with the global `out` declarations.
+fn reflect_varyings(
+    module: &naga::Module,
+    entry_point: &naga::EntryPoint,
+    source: &str,
+) -> Option<FastHashMap<pso::Location, String>> {
+    let location = |binding: Option<&naga::Binding>| match binding {
+        Some(&naga::Binding::Location { location, .. }) => Some(location),
+        _ => None,
+    };
+    let locations = match entry_point.function.result {
+        Some(ref result) => match module.types[result.ty].inner {
+            naga::TypeInner::Struct { ref members, .. } if result.binding.is_none() => members
+                .iter()
+                .filter_map(|member| location(member.binding.as_ref()))
+                .collect(),
+            _ => location(result.binding.as_ref()).into_iter().collect(),
+        },
+        None => Vec::new(),
+    };
+    let names = source
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace) && line.ends_with(';'))
+        .filter_map(|line| {
+            let mut tokens = line.trim_end_matches(';').split_whitespace();
+            if tokens.any(|token| token == "out") {
+                tokens.last()
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if names.len() != locations.len() {
+        return None;
+    }
+    Some(
+        locations
+            .into_iter()
+            .zip(names.into_iter().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// Returns the names of the captured vertex outputs, grouped by buffer
+/// and separated with `gl_NextBuffer`, or the first location without an output.
+fn transform_feedback_varyings(
+    captured: &[pso::TransformFeedbackVarying],
+    outputs: &FastHashMap<pso::Location, String>,
+) -> Result<Vec<String>, pso::Location> {
+    let mut sorted = captured.to_vec();
+    sorted.sort_by_key(|varying| varying.buffer);
+    let mut names = Vec::with_capacity(sorted.len());
+    let mut buffer = 0;
+    for varying in sorted {
+        while buffer < varying.buffer {
+            names.push("gl_NextBuffer".to_string());
+            buffer += 1;
+        }
+        let name = outputs.get(&varying.location).ok_or(varying.location)?;
+        names.push(name.clone());
+    }
+    Ok(names)
+}
+
+/// Returns the vertex outputs captured by the `XfbBuffer` and `Offset` decorations
+/// of a SPIR-V module, in the order they are written to each buffer.
+fn decorated_transform_feedback(spv: &[u32]) -> Vec<pso::TransformFeedbackVarying> {
+    const OP_TYPE_POINTER: u32 = 32;
+    const OP_VARIABLE: u32 = 59;
+    const OP_DECORATE: u32 = 71;
+    const OP_MEMBER_DECORATE: u32 = 72;
+    const DECORATION_LOCATION: u32 = 30;
+    const DECORATION_OFFSET: u32 = 35;
+    const DECORATION_XFB_BUFFER: u32 = 36;
+    const STORAGE_CLASS_OUTPUT: u32 = 3;
+
+    #[derive(Clone, Copy, Default)]
+    struct Decorations {
+        location: Option<u32>,
+        offset: Option<u32>,
+        buffer: Option<u32>,
+    }
+    impl Decorations {
+        fn set(&mut self, decoration: u32, value: u32) {
+            match decoration {
+                DECORATION_LOCATION => self.location = Some(value),
+                DECORATION_OFFSET => self.offset = Some(value),
+                DECORATION_XFB_BUFFER => self.buffer = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    let mut decorations = FastHashMap::<u32, Decorations>::default();
+    let mut member_decorations = FastHashMap::<(u32, u32), Decorations>::default();
+    let mut pointees = FastHashMap::<u32, u32>::default();
+    let mut outputs = Vec::new();
+    // Skip the header, then walk the instructions.
+    let mut words = spv.get(5..).unwrap_or_default();
+    while let Some(&first) = words.first() {
+        let count = (first >> 16).max(1) as usize;
+        let operands = words.get(1..count).unwrap_or_default();
+        match (first & 0xFFFF, operands) {
+            (OP_TYPE_POINTER, &[id, _, pointee]) => {
+                pointees.insert(id, pointee);
+            }
+            (OP_VARIABLE, &[ty, id, STORAGE_CLASS_OUTPUT, ..]) => outputs.push((ty, id)),
+            (OP_DECORATE, &[id, decoration, value, ..]) => {
+                decorations.entry(id).or_default().set(decoration, value)
+            }
+            (OP_MEMBER_DECORATE, &[id, member, decoration, value, ..]) => member_decorations
+                .entry((id, member))
+                .or_default()
+                .set(decoration, value),
+            _ => {}
+        }
+        words = words.get(count..).unwrap_or_default();
+    }
+
+    let mut captured = Vec::new();
+    for (ty, id) in outputs {
+        let variable = decorations.get(&id).copied().unwrap_or_default();
+        if let (Some(location), Some(offset), Some(buffer)) =
+            (variable.location, variable.offset, variable.buffer)
+        {
+            captured.push((buffer, offset, location));
+        }
+        // Members of an output block inherit the buffer of the block.
+        let block = pointees.get(&ty).copied();
+        for member in member_decorations
+            .iter()
+            .filter(|&(&(block_ty, _), _)| Some(block_ty) == block)
+            .map(|(_, member)| member)
+        {
+            if let (Some(location), Some(offset), Some(buffer)) = (
+                member.location,
+                member.offset,
+                member.buffer.or(variable.buffer),
+            ) {
+                captured.push((buffer, offset, location));
+            }
+        }
+    }
+    captured.sort();
+    captured
+        .into_iter()
+        .map(|(buffer, _, location)| pso::TransformFeedbackVarying { location, buffer })
+        .collect()
+}
+
 pub(crate) unsafe fn set_sampler_info<SetParamFloat, SetParamFloatVec, SetParamInt>(
     info: &i::SamplerDesc,
     features: &hal::Features,
@@ -734,13 +1044,113 @@ pub(crate) unsafe fn set_sampler_info<SetParamFloat, SetParamFloatVec, SetParamI
     }
 }
 
+/// Resolves a descriptor written to a binding of `binding_layout` into the bindings
+/// applied when binding the descriptor set.
+pub(crate) fn resolve_descriptor(
+    binding_layout: &pso::DescriptorSetLayoutBinding,
+    descriptor: pso::Descriptor<'_, B>,
+    bindings: &mut Vec<n::DescSetBindings>,
+) {
+    let binding = match descriptor {
+        pso::Descriptor::Buffer(buffer, ref sub) => {
+            let bounded_buffer = buffer.as_bound();
+            let range = crate::resolve_sub_range(sub, bounded_buffer.range);
+
+            let register = match binding_layout.ty {
+                pso::DescriptorType::Buffer { ty, .. } => match ty {
+                    pso::BufferDescriptorType::Uniform => n::BindingRegister::UniformBuffers,
+                    pso::BufferDescriptorType::Storage { .. } => n::BindingRegister::StorageBuffers,
+                },
+                other => panic!("Can't write buffer into descriptor of type {:?}", other),
+            };
+
+            n::DescSetBindings::Buffer {
+                register,
+                buffer: bounded_buffer.raw,
+                offset: range.start as i32,
+                size: (range.end - range.start) as i32,
+            }
+        }
+        pso::Descriptor::CombinedImageSampler(view, _layout, sampler) => {
+            match *view {
+                n::ImageView::Texture { target, raw, .. } => {
+                    bindings.push(n::DescSetBindings::Texture(raw, target))
+                }
+                n::ImageView::Renderbuffer { .. } => {
+                    panic!("Texture doesn't support shader binding")
+                }
+            }
+            match *sampler {
+                n::FatSampler::Sampler(sampler) => n::DescSetBindings::Sampler(sampler),
+                n::FatSampler::Info(ref info) => n::DescSetBindings::SamplerDesc(info.clone()),
+            }
+        }
+        pso::Descriptor::Image(view, _layout) => match *view {
+            n::ImageView::Texture { target, raw, .. } => n::DescSetBindings::Texture(raw, target),
+            n::ImageView::Renderbuffer { .. } => {
+                panic!("Texture doesn't support shader binding")
+            }
+        },
+        pso::Descriptor::Sampler(sampler) => match *sampler {
+            n::FatSampler::Sampler(sampler) => n::DescSetBindings::Sampler(sampler),
+            n::FatSampler::Info(ref info) => n::DescSetBindings::SamplerDesc(info.clone()),
+        },
+        pso::Descriptor::TexelBuffer(_view) => unimplemented!(),
+        pso::Descriptor::AccelerationStructure(_) => unimplemented!(),
+    };
+    bindings.push(binding);
+}
+
+/// Reads the result of `query`, which is waited for with `ResultFlags::WAIT`.
+pub(crate) fn read_query_result(
+    gl: &GlContainer,
+    query: n::Query,
+    flags: query::ResultFlags,
+) -> Option<u32> {
+    let available = flags.contains(query::ResultFlags::WAIT)
+        || unsafe { gl.get_query_parameter_u32(query, glow::QUERY_RESULT_AVAILABLE) } != 0;
+    if available {
+        Some(unsafe { gl.get_query_parameter_u32(query, glow::QUERY_RESULT) })
+    } else {
+        None
+    }
+}
+
+/// Writes the result of a query at the start of `data`, in the layout given by `flags`.
+/// Unavailable results are left untouched unless `ResultFlags::PARTIAL` is set.
+pub(crate) fn write_query_result(data: &mut [u8], result: Option<u32>, flags: query::ResultFlags) {
+    let value_size = if flags.contains(query::ResultFlags::BITS_64) {
+        8
+    } else {
+        4
+    };
+    let mut write = |index: usize, value: u32| {
+        let value_data = &mut data[index * value_size..(index + 1) * value_size];
+        if value_size == 8 {
+            value_data.copy_from_slice(&u64::from(value).to_ne_bytes());
+        } else {
+            value_data.copy_from_slice(&value.to_ne_bytes());
+        }
+    };
+    match result {
+        Some(value) => write(0, value),
+        None if flags.contains(query::ResultFlags::PARTIAL) => write(0, 0),
+        None => {}
+    }
+    if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+        write(1, result.is_some() as u32);
+    }
+}
+
 impl d::Device<B> for Device {
     unsafe fn allocate_memory(
         &self,
         mem_type: hal::MemoryTypeId,
         size: u64,
+        _flags: memory::AllocationFlags,
     ) -> Result<n::Memory, d::AllocationError> {
         let (memory_type, memory_role) = self.share.memory_types[mem_type.0 as usize];
+        self.memory_usage[memory_type.heap_index].fetch_add(size, Ordering::Relaxed);
 
         let is_device_local_memory = memory_type
             .properties
@@ -823,6 +1233,7 @@ impl d::Device<B> for Device {
                     properties: memory_type.properties,
                     buffer: Some((raw, target)),
                     size,
+                    heap_index: memory_type.heap_index,
                     map_flags,
                     emulate_map_allocation: None,
                 })
@@ -834,6 +1245,7 @@ impl d::Device<B> for Device {
                     properties: memory::Properties::DEVICE_LOCAL,
                     buffer: None,
                     size,
+                    heap_index: memory_type.heap_index,
                     map_flags: 0,
                     emulate_map_allocation: None,
                 })
@@ -884,7 +1296,29 @@ impl d::Device<B> for Device {
         Ia: Iterator<Item = pass::Attachment>,
         Is: Iterator<Item = pass::SubpassDesc<'a>>,
     {
+        let subpasses = subpasses.collect::<Vec<_>>();
+        // `OVR_multiview2` renders to a range of consecutive layers, which are bound
+        // once for the whole render pass.
+        if let Some(first) = subpasses.first() {
+            let view_mask = first.view_mask;
+            if !is_contiguous_view_mask(view_mask) {
+                log::error!(
+                    "View mask {:#b} doesn't select consecutive layers",
+                    view_mask
+                );
+                return Err(d::OutOfMemory::Host);
+            }
+            if subpasses
+                .iter()
+                .any(|subpass| subpass.view_mask != view_mask)
+            {
+                log::error!("Subpasses of a render pass must have the same view mask");
+                return Err(d::OutOfMemory::Host);
+            }
+        }
+
         let subpasses = subpasses
+            .into_iter()
             .map(|subpass| {
                 assert!(
                     subpass.colors.len() <= self.share.public_caps.limits.max_color_attachments,
@@ -897,6 +1331,7 @@ impl d::Device<B> for Device {
                 n::SubpassDesc {
                     color_attachments,
                     depth_stencil,
+                    view_mask: subpass.view_mask,
                 }
             })
             .collect();
@@ -941,6 +1376,7 @@ impl d::Device<B> for Device {
                         pso::BufferDescriptorType::Uniform => &mut num_uniform_buffers,
                         pso::BufferDescriptorType::Storage { .. } => &mut num_storage_buffers,
                     },
+                    pso::DescriptorType::AccelerationStructure => unimplemented!(),
                 };
 
                 bindings[binding.binding as usize] = (*counter).try_into().unwrap();
@@ -1011,11 +1447,41 @@ impl d::Device<B> for Device {
             }
         };
 
+        if !self.share.private_caps.transform_feedback_next_buffer
+            && desc
+                .transform_feedback
+                .iter()
+                .any(|varying| varying.buffer != 0)
+        {
+            log::warn!("Transform feedback into several buffers needs `gl_NextBuffer`");
+            return Err(pso::CreationError::UnsupportedPipeline);
+        }
+        // Other backends capture the decorated outputs instead of the listed ones.
+        if let Some(ref decorated) = vs.module.decorated_transform_feedback {
+            let mut listed = desc.transform_feedback.to_vec();
+            listed.sort_by_key(|varying| varying.buffer);
+            debug_assert_eq!(
+                &listed, decorated,
+                "Captured outputs don't match the transform feedback decorations of the shader"
+            );
+        }
+
         let shaders = [
             (naga::ShaderStage::Vertex, Some(vs)),
             (naga::ShaderStage::Fragment, desc.fragment.as_ref()),
         ];
-        let (program, sampler_map) = self.create_shader_program(&shaders[..], &desc.layout)?;
+        let view_mask = match desc.target {
+            pass::PipelineTarget::Subpass(ref subpass) => {
+                subpass.main_pass.subpasses[subpass.index as usize].view_mask
+            }
+            pass::PipelineTarget::Rendering(_) => 0,
+        };
+        let (program, sampler_map) = self.create_shader_program(
+            &shaders[..],
+            &desc.layout,
+            desc.transform_feedback,
+            view_mask,
+        )?;
 
         let patch_size = match input_assembler.primitive {
             pso::Primitive::PatchList(size) => Some(size as _),
@@ -1049,7 +1515,11 @@ impl d::Device<B> for Device {
 
         Ok(n::GraphicsPipeline {
             program,
-            primitive: conv::input_assember_to_gl_primitive(input_assembler),
+            primitive: if input_assembler.dynamic_primitive {
+                pso::State::Dynamic
+            } else {
+                pso::State::Static(conv::input_assember_to_gl_primitive(input_assembler))
+            },
             patch_size,
             blend_targets: desc.blender.targets.clone(),
             vertex_buffers,
@@ -1070,6 +1540,7 @@ impl d::Device<B> for Device {
             uniforms,
             rasterizer: desc.rasterizer,
             depth: desc.depth_stencil.depth,
+            stencil: desc.depth_stencil.stencil,
             baked_states: desc.baked_states.clone(),
             sampler_map,
         })
@@ -1084,7 +1555,7 @@ impl d::Device<B> for Device {
             return Err(pso::CreationError::UnsupportedPipeline);
         }
         let shader = (naga::ShaderStage::Compute, Some(&desc.shader));
-        let (program, sampler_map) = self.create_shader_program(&[shader], &desc.layout)?;
+        let (program, sampler_map) = self.create_shader_program(&[shader], &desc.layout, &[], 0)?;
         Ok(n::ComputePipeline {
             program,
             sampler_map,
@@ -1093,14 +1564,35 @@ impl d::Device<B> for Device {
 
     unsafe fn create_framebuffer<I>(
         &self,
-        _render_pass: &n::RenderPass,
-        _attachments: I,
+        render_pass: &n::RenderPass,
+        attachments: I,
         _extent: i::Extent,
-    ) -> Result<n::Framebuffer, d::OutOfMemory> {
+    ) -> Result<n::Framebuffer, d::OutOfMemory>
+    where
+        I: Iterator<Item = i::FramebufferAttachment>,
+    {
         if !self.share.private_caps.framebuffer {
             return Err(d::OutOfMemory::Host);
         }
 
+        // Renderbuffers have a single layer, so rendering several views
+        // needs the attachments to be array textures.
+        let view_mask = render_pass.subpasses.first().map_or(0, |sub| sub.view_mask);
+        if view_count(view_mask) > 1 {
+            for attachment in attachments {
+                if !attachment
+                    .usage
+                    .intersects(i::Usage::SAMPLED | i::Usage::STORAGE)
+                {
+                    log::error!(
+                        "Multiview attachments need `SAMPLED` or `STORAGE` usage, got {:?}",
+                        attachment.usage
+                    );
+                    return Err(d::OutOfMemory::Host);
+                }
+            }
+        }
+
         let gl = &self.share.context;
         let raw = gl.create_framebuffer().unwrap();
 
@@ -1183,6 +1675,7 @@ impl d::Device<B> for Device {
         Ok(n::ShaderModule {
             #[cfg(feature = "cross")]
             spv: raw_data.to_vec(),
+            decorated_transform_feedback: Some(decorated_transform_feedback(raw_data)),
             naga: if cfg!(feature = "cross") {
                 Err("Cross is enabled".into())
             } else {
@@ -1223,6 +1716,7 @@ impl d::Device<B> for Device {
                     return Err((d::ShaderError::CompilationFailed(format!("{}", e)), shader))
                 }
             },
+            decorated_transform_feedback: None,
             naga: Ok(shader),
         })
     }
@@ -1319,6 +1813,10 @@ impl d::Device<B> for Device {
         Ok(())
     }
 
+    unsafe fn get_buffer_device_address(&self, _buffer: &n::Buffer) -> buffer::DeviceAddress {
+        unimplemented!()
+    }
+
     unsafe fn map_memory(
         &self,
         memory: &mut n::Memory,
@@ -1331,6 +1829,8 @@ impl d::Device<B> for Device {
         let size = segment.size.unwrap_or(memory.size - segment.offset);
 
         let (buffer, target) = memory.buffer.expect("cannot map image memory");
+        // The host may overwrite the query results copied into the buffer.
+        self.share.forget_buffer_predicates(buffer);
         let ptr = if caps.emulate_map {
             let ptr: *mut u8 = if let Some(ptr) = memory.emulate_map_allocation {
                 ptr
@@ -1384,6 +1884,8 @@ impl d::Device<B> for Device {
 
         for (mem, segment) in ranges {
             let (buffer, target) = mem.buffer.expect("cannot flush image memory");
+            // The host may have overwritten the query results copied into the buffer.
+            self.share.forget_buffer_predicates(buffer);
             gl.bind_buffer(target, Some(buffer));
 
             let offset = segment.offset;
@@ -1681,6 +2183,7 @@ impl d::Device<B> for Device {
         swizzle: Swizzle,
         _usage: i::Usage,
         range: i::SubresourceRange,
+        _ycbcr_conversion: Option<i::YcbcrConversionDesc>,
     ) -> Result<n::ImageView, i::ViewCreationError> {
         assert_eq!(swizzle, Swizzle::NO);
         match image.object_type {
@@ -1690,6 +2193,7 @@ impl d::Device<B> for Device {
                     Ok(n::ImageView::Renderbuffer {
                         raw,
                         aspects: image.format_desc.aspects,
+                        channel: image.channel,
                     })
                 } else if level != 0 {
                     Err(i::ViewCreationError::Level(level)) //TODO
@@ -1727,6 +2231,7 @@ impl d::Device<B> for Device {
                     raw,
                     is_3d,
                     sub: range,
+                    channel: view_format.base_format().1,
                 })
             }
         }
@@ -1748,6 +2253,7 @@ impl d::Device<B> for Device {
         &self,
         layout: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, d::OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
@@ -1772,66 +2278,8 @@ impl d::Device<B> for Device {
 
         for descriptor in op.descriptors {
             let binding_layout = &op.set.layout[layout_index];
-            let binding = match descriptor {
-                pso::Descriptor::Buffer(buffer, ref sub) => {
-                    let bounded_buffer = buffer.as_bound();
-                    let range = crate::resolve_sub_range(sub, bounded_buffer.range);
-
-                    let register = match binding_layout.ty {
-                        pso::DescriptorType::Buffer { ty, .. } => match ty {
-                            pso::BufferDescriptorType::Uniform => {
-                                n::BindingRegister::UniformBuffers
-                            }
-                            pso::BufferDescriptorType::Storage { .. } => {
-                                n::BindingRegister::StorageBuffers
-                            }
-                        },
-                        other => {
-                            panic!("Can't write buffer into descriptor of type {:?}", other)
-                        }
-                    };
-
-                    n::DescSetBindings::Buffer {
-                        register,
-                        buffer: bounded_buffer.raw,
-                        offset: range.start as i32,
-                        size: (range.end - range.start) as i32,
-                    }
-                }
-                pso::Descriptor::CombinedImageSampler(view, _layout, sampler) => {
-                    match *view {
-                        n::ImageView::Texture { target, raw, .. } => op
-                            .set
-                            .bindings
-                            .push(n::DescSetBindings::Texture(raw, target)),
-                        n::ImageView::Renderbuffer { .. } => {
-                            panic!("Texture doesn't support shader binding")
-                        }
-                    }
-                    match *sampler {
-                        n::FatSampler::Sampler(sampler) => n::DescSetBindings::Sampler(sampler),
-                        n::FatSampler::Info(ref info) => {
-                            n::DescSetBindings::SamplerDesc(info.clone())
-                        }
-                    }
-                }
-                pso::Descriptor::Image(view, _layout) => match *view {
-                    n::ImageView::Texture { target, raw, .. } => {
-                        n::DescSetBindings::Texture(raw, target)
-                    }
-                    n::ImageView::Renderbuffer { .. } => {
-                        panic!("Texture doesn't support shader binding")
-                    }
-                },
-                pso::Descriptor::Sampler(sampler) => match *sampler {
-                    n::FatSampler::Sampler(sampler) => n::DescSetBindings::Sampler(sampler),
-                    n::FatSampler::Info(ref info) => n::DescSetBindings::SamplerDesc(info.clone()),
-                },
-                pso::Descriptor::TexelBuffer(_view) => unimplemented!(),
-            };
-
             //TODO: overwrite instead of pushing on top
-            op.set.bindings.push(binding);
+            resolve_descriptor(binding_layout, descriptor, &mut op.set.bindings);
 
             array_offset += 1;
             if array_offset == binding_layout.count {
@@ -1860,6 +2308,26 @@ impl d::Device<B> for Device {
         op.dst_set.bindings[dst_start..dst_end].clone_from_slice(src_slice);
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _layout: &n::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<pso::EmulatedDescriptorUpdateTemplate, d::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut n::DescriptorSet,
+        template: &pso::EmulatedDescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
+    }
+
     fn create_semaphore(&self) -> Result<n::Semaphore, d::OutOfMemory> {
         Ok(n::Semaphore)
     }
@@ -1982,31 +2450,151 @@ impl d::Device<B> for Device {
 
     unsafe fn free_memory(&self, memory: n::Memory) {
         if let Some((buffer, _)) = memory.buffer {
+            self.share.forget_buffer_predicates(buffer);
             self.share.context.delete_buffer(buffer);
         }
+        self.memory_usage[memory.heap_index].fetch_sub(memory.size, Ordering::Relaxed);
+    }
+
+    fn memory_budget(&self) -> Vec<adapter::MemoryHeapBudget> {
+        let device_local_usage = self.memory_usage[DEVICE_LOCAL_HEAP].load(Ordering::Relaxed);
+        let cpu_visible_usage = self.memory_usage[CPU_VISIBLE_HEAP].load(Ordering::Relaxed);
+
+        // The heaps have no known size, so they are unbounded unless told otherwise.
+        let device_local_budget = if self.share.private_caps.gpu_memory_info {
+            let available_kib = unsafe {
+                self.share
+                    .context
+                    .get_parameter_i32(GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX)
+            };
+            device_local_usage + available_kib as u64 * 1024
+        } else {
+            !0
+        };
+
+        vec![
+            adapter::MemoryHeapBudget {
+                budget: device_local_budget,
+                usage: device_local_usage,
+            },
+            adapter::MemoryHeapBudget {
+                budget: !0,
+                usage: cpu_visible_usage,
+            },
+        ]
     }
 
     unsafe fn create_query_pool(
         &self,
         ty: query::Type,
-        _count: query::Id,
-    ) -> Result<(), query::CreationError> {
-        Err(query::CreationError::Unsupported(ty))
+        count: query::Id,
+    ) -> Result<n::QueryPool, query::CreationError> {
+        match ty {
+            query::Type::Occlusion if self.share.private_caps.occlusion_query => {
+                let gl = &self.share.context;
+                let mut queries = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    match gl.create_query() {
+                        Ok(query) => queries.push(query),
+                        Err(err) => {
+                            log::error!("Query creation failed: {}", err);
+                            for query in queries {
+                                gl.delete_query(query);
+                            }
+                            return Err(d::OutOfMemory::Host.into());
+                        }
+                    }
+                }
+                Ok(n::QueryPool::Occlusion(queries.into_boxed_slice()))
+            }
+            _ => Err(query::CreationError::Unsupported(ty)),
+        }
     }
 
-    unsafe fn destroy_query_pool(&self, _: ()) {
-        unimplemented!()
+    unsafe fn create_performance_query_pool(
+        &self,
+        _family: queue::QueueFamilyId,
+        counters: &[u32],
+        count: query::Id,
+    ) -> Result<n::QueryPool, query::CreationError> {
+        if !self.features.contains(hal::Features::PERFORMANCE_QUERY) {
+            return Err(query::CreationError::Unsupported(
+                query::Type::PerformanceCounters,
+            ));
+        }
+        let monitors = perf::create_monitors(
+            &self.share.context,
+            &self.share.perf_counters,
+            counters,
+            count,
+        )?;
+        Ok(n::QueryPool::PerformanceCounters {
+            monitors,
+            counters: counters.into(),
+        })
+    }
+
+    unsafe fn acquire_profiling_lock(&self, _timeout_ns: u64) -> Result<bool, d::OutOfMemory> {
+        // The performance monitor extensions don't need a lock to sample the counters.
+        Ok(true)
+    }
+
+    unsafe fn release_profiling_lock(&self) {}
+
+    unsafe fn destroy_query_pool(&self, pool: n::QueryPool) {
+        let gl = &self.share.context;
+        match pool {
+            n::QueryPool::Occlusion(queries) => {
+                for &query in queries.iter() {
+                    self.share.forget_query_predicates(query);
+                    gl.delete_query(query);
+                }
+            }
+            n::QueryPool::PerformanceCounters { monitors, .. } => {
+                for &monitor in monitors.iter() {
+                    perf::delete_monitor(gl, monitor);
+                }
+            }
+        }
     }
 
     unsafe fn get_query_pool_results(
         &self,
-        _pool: &(),
-        _queries: Range<query::Id>,
-        _data: &mut [u8],
-        _stride: buffer::Stride,
-        _flags: query::ResultFlags,
+        pool: &n::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Stride,
+        flags: query::ResultFlags,
     ) -> Result<bool, d::WaitError> {
-        unimplemented!()
+        let gl = &self.share.context;
+        let mut all_available = true;
+        match *pool {
+            n::QueryPool::Occlusion(ref raw_queries) => {
+                let raw_queries = &raw_queries[queries.start as usize..queries.end as usize];
+                for (i, &query) in raw_queries.iter().enumerate() {
+                    let available = read_query_result(gl, query, flags);
+                    all_available &= available.is_some();
+                    write_query_result(&mut data[i * stride as usize..], available, flags);
+                }
+            }
+            n::QueryPool::PerformanceCounters {
+                ref monitors,
+                ref counters,
+            } => {
+                let monitors = &monitors[queries.start as usize..queries.end as usize];
+                for (i, &monitor) in monitors.iter().enumerate() {
+                    all_available &= perf::read_monitor_results(
+                        gl,
+                        monitor,
+                        &self.share.perf_counters,
+                        counters,
+                        flags.contains(query::ResultFlags::WAIT),
+                        &mut data[i * stride as usize..],
+                    );
+                }
+            }
+        }
+        Ok(all_available)
     }
 
     unsafe fn destroy_shader_module(&self, _: n::ShaderModule) {
@@ -2061,6 +2649,32 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        _desc: &hal::acceleration_structure::GeometryDesc<B>,
+        _max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        unimplemented!()
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        _desc: &hal::acceleration_structure::CreateDesc<B>,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        _accel_struct: &(),
+    ) -> hal::acceleration_structure::DeviceAddress {
+        unimplemented!()
+    }
+
+    unsafe fn destroy_acceleration_structure(&self, _accel_struct: ()) {
+        unimplemented!()
+    }
+
     unsafe fn destroy_descriptor_pool(&self, _: n::DescriptorPool) {
         // Nothing to do
     }
@@ -2069,6 +2683,12 @@ impl d::Device<B> for Device {
         // Nothing to do
     }
 
+    unsafe fn destroy_descriptor_update_template(
+        &self,
+        _template: pso::EmulatedDescriptorUpdateTemplate,
+    ) {
+    }
+
     unsafe fn destroy_fence(&self, fence: n::Fence) {
         match fence {
             n::Fence::Idle { .. } => {}
@@ -2082,17 +2702,82 @@ impl d::Device<B> for Device {
         // Nothing to do
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: u64,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        _semaphore: &n::Semaphore,
+    ) -> Result<u64, d::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        _semaphores: I,
+        _wait: d::WaitFor,
+        _timeout_ns: u64,
+    ) -> Result<bool, d::WaitError>
+    where
+        I: Iterator<Item = (&'a n::Semaphore, u64)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _value: u64,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
     unsafe fn destroy_event(&self, _event: ()) {
         unimplemented!()
     }
 
-    fn wait_idle(&self) -> Result<(), d::OutOfMemory> {
+    #[cfg(any(unix, windows))]
+    fn get_calibrated_timestamps(&self) -> Result<d::CalibratedTimestamps, d::OutOfMemory> {
+        assert!(
+            self.features.contains(hal::Features::CALIBRATED_TIMESTAMPS),
+            "Feature CALIBRATED_TIMESTAMPS must be enabled to call get_calibrated_timestamps"
+        );
+        let get_integer64 = self.share.context.functions.get_integer64.unwrap();
+        // The query is synchronous, so the host clock is read on both sides of it
+        // and the device time is matched with the middle of that span.
+        let before = host_monotonic_ns();
+        let mut device = 0;
+        unsafe { get_integer64(glow::TIMESTAMP, &mut device) };
+        let after = host_monotonic_ns();
+        let half_span = (after - before + 1) / 2;
+        Ok(d::CalibratedTimestamps {
+            device: device as u64,
+            host_ns: before + half_span,
+            max_deviation_ns: half_span,
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn get_calibrated_timestamps(&self) -> Result<d::CalibratedTimestamps, d::OutOfMemory> {
+        // There is no host clock to match `GL_TIMESTAMP` with, so the feature is never exposed.
+        panic!("Feature CALIBRATED_TIMESTAMPS must be enabled to call get_calibrated_timestamps")
+    }
+
+    fn wait_idle(&self) -> Result<(), d::WaitError> {
         unsafe {
             self.share.context.finish();
         }
         Ok(())
     }
 
+    unsafe fn get_fault_info(&self) -> Option<d::FaultInfo> {
+        None
+    }
+
     unsafe fn set_image_name(&self, _image: &mut n::Image, _name: &str) {
         // TODO
     }
@@ -2244,3 +2929,152 @@ impl d::Device<B> for Device {
         //TODO
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        declare_views, decorated_transform_feedback, is_contiguous_view_mask,
+        transform_feedback_varyings, view_count, write_query_result,
+    };
+    use crate::FastHashMap;
+    use hal::{pso::TransformFeedbackVarying, query::ResultFlags};
+
+    #[test]
+    fn test_view_count() {
+        assert_eq!(view_count(0b1), 1);
+        assert_eq!(view_count(0b11), 2);
+        assert_eq!(view_count(0b1100), 2);
+    }
+
+    #[test]
+    fn test_is_contiguous_view_mask() {
+        assert!(is_contiguous_view_mask(0));
+        assert!(is_contiguous_view_mask(0b1));
+        assert!(is_contiguous_view_mask(0b110));
+        assert!(is_contiguous_view_mask(!0));
+        assert!(!is_contiguous_view_mask(0b101));
+        assert!(!is_contiguous_view_mask(0b1001_1000));
+    }
+
+    #[test]
+    fn test_write_query_result() {
+        let cases: &[(Option<u32>, ResultFlags, &[u8])] = &[
+            (Some(3), ResultFlags::empty(), &[3, 0, 0, 0, 9, 9, 9, 9]),
+            (None, ResultFlags::empty(), &[9, 9, 9, 9, 9, 9, 9, 9]),
+            (None, ResultFlags::PARTIAL, &[0, 0, 0, 0, 9, 9, 9, 9]),
+            (Some(3), ResultFlags::BITS_64, &[3, 0, 0, 0, 0, 0, 0, 0]),
+            (
+                Some(3),
+                ResultFlags::WITH_AVAILABILITY,
+                &[3, 0, 0, 0, 1, 0, 0, 0],
+            ),
+            (
+                None,
+                ResultFlags::WITH_AVAILABILITY,
+                &[9, 9, 9, 9, 0, 0, 0, 0],
+            ),
+        ];
+        for &(result, flags, expected) in cases {
+            let mut data = [9u8; 8];
+            write_query_result(&mut data, result, flags);
+            assert_eq!(&data[..], expected, "{:?} {:?}", result, flags);
+        }
+    }
+
+    #[test]
+    fn test_declare_views() {
+        let source = "#version 300 es\n\
+            #extension GL_EXT_multiview : require\n\
+            precision highp float;\n\
+            void main() {\n\
+            int view = gl_ViewIndex;\n\
+            int my_gl_ViewIndex = gl_ViewIndex2;\n\
+            }\n";
+        assert_eq!(
+            declare_views(source, naga::ShaderStage::Vertex, 0b11),
+            "#version 300 es\n\
+            #extension GL_OVR_multiview2 : require\n\
+            layout(num_views = 2) in;\n\
+            precision highp float;\n\
+            void main() {\n\
+            int view = int(gl_ViewID_OVR);\n\
+            int my_gl_ViewIndex = gl_ViewIndex2;\n\
+            }\n"
+        );
+        assert_eq!(
+            declare_views(source, naga::ShaderStage::Fragment, 0b11),
+            "#version 300 es\n\
+            #extension GL_OVR_multiview2 : require\n\
+            precision highp float;\n\
+            void main() {\n\
+            int view = int(gl_ViewID_OVR);\n\
+            int my_gl_ViewIndex = gl_ViewIndex2;\n\
+            }\n"
+        );
+    }
+
+    #[test]
+    fn test_transform_feedback_varyings() {
+        let varying = |location, buffer| TransformFeedbackVarying { location, buffer };
+        let outputs = (0..5)
+            .map(|location| (location, format!("out{}", location)))
+            .collect::<FastHashMap<_, _>>();
+        let cases: &[(&[TransformFeedbackVarying], Result<&[&str], u32>)] = &[
+            (&[], Ok(&[])),
+            (&[varying(2, 0), varying(0, 0)], Ok(&["out2", "out0"])),
+            (
+                &[varying(1, 1), varying(0, 0), varying(3, 1)],
+                Ok(&["out0", "gl_NextBuffer", "out1", "out3"]),
+            ),
+            (
+                &[varying(4, 2)],
+                Ok(&["gl_NextBuffer", "gl_NextBuffer", "out4"]),
+            ),
+            (&[varying(0, 0), varying(7, 0)], Err(7)),
+        ];
+        for &(captured, names) in cases {
+            assert_eq!(
+                transform_feedback_varyings(captured, &outputs),
+                names.map(|names| names.iter().map(|name| name.to_string()).collect()),
+                "{:?}",
+                captured
+            );
+        }
+    }
+
+    #[test]
+    fn test_decorated_transform_feedback() {
+        #[rustfmt::skip]
+        let spv = [
+            // Header.
+            0x0723_0203, 0x0001_0000, 0, 20, 0,
+            // `OpDecorate %10 Location 1`, `XfbBuffer 0`, `Offset 16`.
+            0x0004_0047, 10, 30, 1,
+            0x0004_0047, 10, 36, 0,
+            0x0004_0047, 10, 35, 16,
+            // `OpDecorate %11 Location 0`, `XfbBuffer 0`, `Offset 0`.
+            0x0004_0047, 11, 30, 0,
+            0x0004_0047, 11, 36, 0,
+            0x0004_0047, 11, 35, 0,
+            // `OpDecorate %12 Location 2` isn't captured.
+            0x0004_0047, 12, 30, 2,
+            // `OpMemberDecorate %13 0 Location 3`, `Offset 0`, in a block captured to buffer 1.
+            0x0005_0048, 13, 0, 30, 3,
+            0x0005_0048, 13, 0, 35, 0,
+            0x0004_0047, 15, 36, 1,
+            // `%14 = OpTypePointer Output %13`.
+            0x0004_0020, 14, 3, 13,
+            // Output variables `%10`, `%11`, `%12` of type `%5`, and `%15` of type `%14`.
+            0x0004_003B, 5, 10, 3,
+            0x0004_003B, 5, 11, 3,
+            0x0004_003B, 5, 12, 3,
+            0x0004_003B, 14, 15, 3,
+        ];
+        let varying = |location, buffer| TransformFeedbackVarying { location, buffer };
+        assert_eq!(
+            decorated_transform_feedback(&spv),
+            [varying(0, 0), varying(1, 0), varying(3, 1)]
+        );
+        assert_eq!(decorated_transform_feedback(&[]), []);
+    }
+}
@@ -1,8 +1,23 @@
 use crate::{Error, GlContainer, MAX_COLOR_ATTACHMENTS};
 use glow::HasContext;
-use hal::{DynamicStates, Features, Limits, PerformanceCaveats, PhysicalDeviceProperties};
+use hal::{
+    pso::ShaderStageFlags, DynamicStates, Features, Limits, PerformanceCaveats,
+    PhysicalDeviceProperties,
+};
 use std::{collections::HashSet, fmt, str};
 
+/// `GL_MAX_VIEWS_OVR` from `OVR_multiview`, which `glow` doesn't define.
+const MAX_VIEWS_OVR: u32 = 0x9631;
+
+// Queries from `KHR_shader_subgroup`, which `glow` doesn't define either.
+const SUBGROUP_SIZE_KHR: u32 = 0x9532;
+const SUBGROUP_SUPPORTED_STAGES_KHR: u32 = 0x9533;
+const SUBGROUP_SUPPORTED_FEATURES_KHR: u32 = 0x9534;
+const SUBGROUP_QUAD_ALL_STAGES_KHR: u32 = 0x9535;
+
+/// `GL_GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX` from `NVX_gpu_memory_info`, in KiB.
+pub(crate) const GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: u32 = 0x9049;
+
 /// A version number for a specific component of an OpenGL implementation
 #[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Version {
@@ -229,6 +244,14 @@ pub struct PrivateCaps {
     pub get_tex_image: bool,
     /// Inserting memory barriers.
     pub memory_barrier: bool,
+    /// Querying the video memory left available with `NVX_gpu_memory_info`.
+    pub gpu_memory_info: bool,
+    /// Capturing vertex outputs into several interleaved buffers with `gl_NextBuffer`.
+    pub transform_feedback_next_buffer: bool,
+    /// Occlusion queries with `GL_ANY_SAMPLES_PASSED`.
+    pub occlusion_query: bool,
+    /// Writing query results into buffers on the device with `GL_QUERY_BUFFER`.
+    pub query_buffer_object: bool,
 }
 
 /// OpenGL implementation information
@@ -636,6 +659,40 @@ pub(crate) fn query_all(
         // TODO: extension
         limits.max_viewports = get_usize(gl, glow::MAX_VIEWPORTS).unwrap_or(0);
     }
+    let multiview = gl.functions.framebuffer_texture_multiview.is_some()
+        && info.is_supported(&[Ext("GL_OVR_multiview2")]);
+    if multiview {
+        limits.max_multiview_view_count = get_usize(gl, MAX_VIEWS_OVR).unwrap_or(0) as _;
+    }
+
+    let occlusion_query = info.is_supported(&[
+        Core(3, 3),
+        Es(3, 0),
+        Ext("GL_ARB_occlusion_query2"),
+        Ext("GL_EXT_occlusion_query_boolean"),
+    ]);
+    let query_buffer_object = gl.functions.get_query_object_u32.is_some()
+        && gl.functions.get_query_object_u64.is_some()
+        && info.is_supported(&[Core(4, 4), Ext("GL_ARB_query_buffer_object")]);
+    // Inverted predicates are part of the feature, so they are required as well.
+    let conditional_render = gl.functions.begin_conditional_render.is_some()
+        && gl.functions.end_conditional_render.is_some()
+        && info.is_supported(&[Core(4, 5), Ext("GL_ARB_conditional_render_inverted")]);
+
+    let mut subgroup = hal::SubgroupProperties::default();
+    if info.is_supported(&[Ext("GL_KHR_shader_subgroup")]) {
+        subgroup = hal::SubgroupProperties {
+            subgroup_size: get_usize(gl, SUBGROUP_SIZE_KHR).unwrap_or(0) as u32,
+            supported_stages: subgroup_stages(
+                get_usize(gl, SUBGROUP_SUPPORTED_STAGES_KHR).unwrap_or(0) as u32,
+            ),
+            supported_operations: subgroup_operations(
+                get_usize(gl, SUBGROUP_SUPPORTED_FEATURES_KHR).unwrap_or(0) as u32,
+            ),
+            quad_operations_in_all_stages: get_usize(gl, SUBGROUP_QUAD_ALL_STAGES_KHR)
+                .map_or(false, |quad| quad != 0),
+        };
+    }
 
     //TODO: technically compute is exposed in Es(3, 1), but GLES requires 3.2
     // for any storage buffers. We need to investigate if this requirement
@@ -656,7 +713,9 @@ pub(crate) fn query_all(
         }
     }
 
-    let mut features = Features::NDC_Y_UP | Features::MUTABLE_COMPARISON_SAMPLER;
+    // Descriptor sets are bound with direct binds, so pushing descriptors comes for free.
+    let mut features =
+        Features::NDC_Y_UP | Features::MUTABLE_COMPARISON_SAMPLER | Features::PUSH_DESCRIPTOR;
     // TODO: Fill out downlevel features correctly.
     let mut downlevel = hal::DownlevelProperties::all_enabled();
     // TODO: Merge downlevel/legacy features?
@@ -689,6 +748,42 @@ pub(crate) fn query_all(
     {
         features |= Features::INDEPENDENT_BLENDING;
     }
+    if info.is_supported(&[Core(3, 0), Es(3, 0)]) {
+        // Framebuffer objects and `glClearBuffer` are enough to begin rendering.
+        features |= Features::DYNAMIC_RENDERING;
+    }
+    if occlusion_query && !info.version.is_embedded {
+        // `GL_SAMPLES_PASSED` counts the samples, `GL_ANY_SAMPLES_PASSED` doesn't.
+        features |= Features::PRECISE_OCCLUSION_QUERY;
+    }
+    if occlusion_query && query_buffer_object && conditional_render {
+        // `glBeginConditionalRender` is predicated on a query rather than buffer
+        // contents, so the predicates are the occlusion queries whose results
+        // were copied into the buffers on the device.
+        features |= Features::CONDITIONAL_RENDERING;
+    }
+    if info.is_supported(&[Core(3, 0), Es(3, 0)]) {
+        // Pipelines capturing into several buffers are refused without `gl_NextBuffer`.
+        features |= Features::TRANSFORM_FEEDBACK;
+    }
+    // Allocations are tracked by the device, and the budget is refined
+    // with `NVX_gpu_memory_info` where it's available.
+    features |= Features::MEMORY_BUDGET;
+    // `GL_TIMESTAMP` is matched against the host clock of `auxil`, which is
+    // `CLOCK_MONOTONIC` on Unix and `QueryPerformanceCounter` on Windows.
+    if cfg!(any(unix, windows))
+        && gl.functions.get_integer64.is_some()
+        && info.is_supported(&[
+            Core(3, 3),
+            Ext("GL_ARB_timer_query"),
+            Ext("GL_EXT_disjoint_timer_query"),
+        ])
+    {
+        features |= Features::CALIBRATED_TIMESTAMPS;
+    }
+    if multiview {
+        features |= Features::MULTIVIEW;
+    }
 
     // TODO
     if false && info.is_supported(&[Core(4, 3), Es(3, 1)]) {
@@ -757,8 +852,12 @@ pub(crate) fn query_all(
     }
     let properties = PhysicalDeviceProperties {
         limits,
+        subgroup,
         performance_caveats,
-        dynamic_pipeline_states: DynamicStates::all(),
+        // `glLineWidth` and `glPolygonOffset` aren't recorded yet.
+        dynamic_pipeline_states: DynamicStates::all()
+            - DynamicStates::LINE_WIDTH
+            - DynamicStates::DEPTH_BIAS,
         ..PhysicalDeviceProperties::default()
     };
 
@@ -790,6 +889,11 @@ pub(crate) fn query_all(
         per_slot_color_mask: info.is_supported(&[Core(3, 0)]),
         get_tex_image: !info.version.is_embedded,
         memory_barrier: info.is_supported(&[Core(4, 2), Es(3, 1)]),
+        gpu_memory_info: info.is_supported(&[Ext("GL_NVX_gpu_memory_info")]),
+        transform_feedback_next_buffer: info
+            .is_supported(&[Core(4, 0), Ext("GL_ARB_transform_feedback3")]),
+        occlusion_query,
+        query_buffer_object,
     };
 
     let filter = if info.is_supported(&[Es(3, 0)]) {
@@ -808,9 +912,72 @@ pub(crate) fn query_all(
     (info, features, legacy, properties, private, filter)
 }
 
+/// Maps the shader stage bits of `GL_SUBGROUP_SUPPORTED_STAGES_KHR`.
+fn subgroup_stages(bits: u32) -> ShaderStageFlags {
+    [
+        (glow::VERTEX_SHADER_BIT, ShaderStageFlags::VERTEX),
+        (glow::TESS_CONTROL_SHADER_BIT, ShaderStageFlags::HULL),
+        (glow::TESS_EVALUATION_SHADER_BIT, ShaderStageFlags::DOMAIN),
+        (glow::GEOMETRY_SHADER_BIT, ShaderStageFlags::GEOMETRY),
+        (glow::FRAGMENT_SHADER_BIT, ShaderStageFlags::FRAGMENT),
+        (glow::COMPUTE_SHADER_BIT, ShaderStageFlags::COMPUTE),
+    ]
+    .iter()
+    .filter(|&&(bit, _)| bits & bit != 0)
+    .fold(ShaderStageFlags::empty(), |acc, &(_, stage)| acc | stage)
+}
+
+/// Maps the feature bits of `GL_SUBGROUP_SUPPORTED_FEATURES_KHR`,
+/// which are the same as the `SubgroupOperations` ones.
+fn subgroup_operations(bits: u32) -> hal::SubgroupOperations {
+    hal::SubgroupOperations::from_bits_truncate(bits)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Version;
+    use super::{subgroup_operations, subgroup_stages, Version};
+    use hal::{pso::ShaderStageFlags, SubgroupOperations};
+
+    #[test]
+    fn test_subgroup_stages() {
+        let cases = [
+            (0, ShaderStageFlags::empty()),
+            (glow::VERTEX_SHADER_BIT, ShaderStageFlags::VERTEX),
+            (glow::TESS_CONTROL_SHADER_BIT, ShaderStageFlags::HULL),
+            (glow::TESS_EVALUATION_SHADER_BIT, ShaderStageFlags::DOMAIN),
+            (glow::GEOMETRY_SHADER_BIT, ShaderStageFlags::GEOMETRY),
+            (glow::FRAGMENT_SHADER_BIT, ShaderStageFlags::FRAGMENT),
+            (glow::COMPUTE_SHADER_BIT, ShaderStageFlags::COMPUTE),
+            (
+                glow::FRAGMENT_SHADER_BIT | glow::COMPUTE_SHADER_BIT,
+                ShaderStageFlags::FRAGMENT | ShaderStageFlags::COMPUTE,
+            ),
+            (!0, ShaderStageFlags::GRAPHICS | ShaderStageFlags::COMPUTE),
+        ];
+        for &(bits, stages) in cases.iter() {
+            assert_eq!(subgroup_stages(bits), stages, "{:#x}", bits);
+        }
+    }
+
+    #[test]
+    fn test_subgroup_operations() {
+        // `GL_SUBGROUP_FEATURE_*_BIT_KHR` values
+        let cases = [
+            (0x01, SubgroupOperations::BASIC),
+            (0x02, SubgroupOperations::VOTE),
+            (0x04, SubgroupOperations::ARITHMETIC),
+            (0x08, SubgroupOperations::BALLOT),
+            (0x10, SubgroupOperations::SHUFFLE),
+            (0x20, SubgroupOperations::SHUFFLE_RELATIVE),
+            (0x40, SubgroupOperations::CLUSTERED),
+            (0x80, SubgroupOperations::QUAD),
+            // Unknown bits, such as `GL_SUBGROUP_FEATURE_PARTITIONED_BIT_NV`
+            (0x100, SubgroupOperations::empty()),
+        ];
+        for &(bits, operations) in cases.iter() {
+            assert_eq!(subgroup_operations(bits), operations, "{:#x}", bits);
+        }
+    }
 
     #[test]
     fn test_version_parse() {
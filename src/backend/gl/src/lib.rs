@@ -23,7 +23,7 @@ will be minimal, if any.
 #![allow(missing_docs, missing_copy_implementations)]
 
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     fmt,
     hash::BuildHasherDefault,
@@ -42,6 +42,7 @@ mod conv;
 mod device;
 mod info;
 mod native;
+mod perf;
 mod pool;
 mod queue;
 mod state;
@@ -67,8 +68,38 @@ const MAX_SAMPLERS: usize = 16;
 const MAX_TEXTURE_SLOTS: usize = 16;
 const MAX_COLOR_ATTACHMENTS: usize = 16;
 
+type GetInteger64 = unsafe extern "system" fn(u32, *mut i64);
+type FramebufferTextureMultiview =
+    unsafe extern "system" fn(u32, u32, native::Texture, i32, i32, i32);
+type BeginConditionalRender = unsafe extern "system" fn(native::Query, u32);
+type EndConditionalRender = unsafe extern "system" fn();
+type GetQueryObjectU32 = unsafe extern "system" fn(native::Query, u32, *mut u32);
+type GetQueryObjectU64 = unsafe extern "system" fn(native::Query, u32, *mut u64);
+
+/// Entry points which `glow` doesn't expose, loaded by the window backend.
+#[derive(Default)]
+struct ExtraFunctions {
+    /// `glGetInteger64v`.
+    get_integer64: Option<GetInteger64>,
+    /// `glFramebufferTextureMultiviewOVR` from `OVR_multiview`.
+    framebuffer_texture_multiview: Option<FramebufferTextureMultiview>,
+    /// `glBeginConditionalRender`.
+    begin_conditional_render: Option<BeginConditionalRender>,
+    /// `glEndConditionalRender`.
+    end_conditional_render: Option<EndConditionalRender>,
+    /// `glGetQueryObjectuiv`, which writes into `GL_QUERY_BUFFER` when one is bound.
+    get_query_object_u32: Option<GetQueryObjectU32>,
+    /// `glGetQueryObjectui64v`, which writes into `GL_QUERY_BUFFER` when one is bound.
+    get_query_object_u64: Option<GetQueryObjectU64>,
+    /// `GL_AMD_performance_monitor`.
+    amd_performance_monitor: Option<perf::AmdPerformanceMonitor>,
+    /// `GL_INTEL_performance_query`.
+    intel_performance_query: Option<perf::IntelPerformanceQuery>,
+}
+
 struct GlContainer {
     context: GlContext,
+    functions: ExtraFunctions,
 }
 
 impl Deref for GlContainer {
@@ -110,16 +141,19 @@ impl hal::Backend for Backend {
     type PipelineLayout = native::PipelineLayout;
     type PipelineCache = ();
     type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorUpdateTemplate = hal::pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = native::DescriptorPool;
     type DescriptorSet = native::DescriptorSet;
 
     type Fence = native::Fence;
     type Semaphore = native::Semaphore;
     type Event = ();
-    type QueryPool = ();
+    type QueryPool = native::QueryPool;
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = ();
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -212,6 +246,12 @@ struct Share {
     open: Cell<bool>,
     memory_types: Vec<(adapter::MemoryType, MemoryUsage)>,
     texture_format_filter: info::TextureFormatFilter,
+    /// Occlusion queries whose results were copied into buffers, by the buffer and
+    /// offset they were copied to, so that conditional rendering can be predicated
+    /// on the query instead of the buffer contents.
+    query_predicates: RefCell<FastHashMap<(native::RawBuffer, buffer::Offset), native::Query>>,
+    /// Hardware counters which can be sampled by performance queries.
+    perf_counters: Vec<perf::Counter>,
 }
 
 impl Share {
@@ -261,6 +301,20 @@ impl Share {
         assert_ne!(type_mask, 0);
         type_mask
     }
+
+    /// Forgets the query results copied into `buffer`, which is written by other commands.
+    fn forget_buffer_predicates(&self, buffer: native::RawBuffer) {
+        self.query_predicates
+            .borrow_mut()
+            .retain(|&(predicate_buffer, _), _| predicate_buffer != buffer);
+    }
+
+    /// Forgets the copies of the results of `query`, which is written again.
+    fn forget_query_predicates(&self, query: native::Query) {
+        self.query_predicates
+            .borrow_mut()
+            .retain(|_, &mut predicate_query| predicate_query != query);
+    }
 }
 
 /// Single-threaded `Arc`.
@@ -354,17 +408,21 @@ unsafe impl<T: ?Sized> Sync for Wstarc<T> {}
 pub struct PhysicalDevice(Starc<Share>);
 
 impl PhysicalDevice {
-    fn new_adapter(context: GlContext) -> adapter::Adapter<Backend> {
-        let gl = GlContainer { context };
+    fn new_adapter(context: GlContext, functions: ExtraFunctions) -> adapter::Adapter<Backend> {
+        let gl = GlContainer { context, functions };
         // query information
         let (
             info,
-            supported_features,
+            mut supported_features,
             legacy_features,
             public_caps,
             private_caps,
             texture_format_filter,
         ) = info::query_all(&gl);
+        let perf_counters = perf::enumerate_counters(&gl, &info);
+        if !perf_counters.is_empty() {
+            supported_features |= hal::Features::PERFORMANCE_QUERY;
+        }
         log::info!("Vendor: {:?}", info.platform_name.vendor);
         log::info!("Renderer: {:?}", info.platform_name.renderer);
         log::info!("Version: {:?}", info.version);
@@ -450,6 +508,8 @@ impl PhysicalDevice {
             private_caps,
             open: Cell::new(false),
             memory_types,
+            query_predicates: RefCell::new(FastHashMap::default()),
+            perf_counters,
         };
         if let Err(err) = share.check() {
             panic!("Error querying info: {:?}", err);
@@ -702,6 +762,18 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         self.0.public_caps
     }
 
+    fn performance_counters(
+        &self,
+        _family: q::QueueFamilyId,
+    ) -> Vec<hal::query::PerformanceCounter> {
+        // There is a single queue family.
+        self.0
+            .perf_counters
+            .iter()
+            .map(|counter| counter.desc.clone())
+            .collect()
+    }
+
     unsafe fn enumerate_displays(&self) -> Vec<display::Display<crate::Backend>> {
         unimplemented!();
     }
@@ -1,4 +1,4 @@
-use crate::{Backend, GlContext, MAX_TEXTURE_SLOTS};
+use crate::{perf, Backend, GlContext, MAX_TEXTURE_SLOTS};
 
 use hal::{
     buffer, format, image as i,
@@ -21,6 +21,7 @@ pub type Renderbuffer = <GlContext as glow::HasContext>::Renderbuffer;
 pub type RawFramebuffer = <GlContext as glow::HasContext>::Framebuffer;
 pub type Texture = <GlContext as glow::HasContext>::Texture;
 pub type Sampler = <GlContext as glow::HasContext>::Sampler;
+pub type Query = <GlContext as glow::HasContext>::Query;
 // TODO: UniformLocation was copy in glow 0.3, but in 0.4 it isn't. Wrap it in a Starc for now
 // to make it `Sync + Send` instead.
 pub type UniformLocation = crate::Starc<<GlContext as glow::HasContext>::UniformLocation>;
@@ -80,6 +81,19 @@ pub enum Fence {
 unsafe impl Send for Fence {}
 unsafe impl Sync for Fence {}
 
+#[derive(Debug)]
+pub enum QueryPool {
+    Occlusion(Box<[Query]>),
+    PerformanceCounters {
+        monitors: Box<[perf::Monitor]>,
+        /// Indices of the sampled counters.
+        counters: Box<[u32]>,
+    },
+}
+
+unsafe impl Send for QueryPool {}
+unsafe impl Sync for QueryPool {}
+
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum BindingRegister {
     Textures,
@@ -94,14 +108,15 @@ pub(crate) type SamplerBindMap = [Option<u8>; MAX_TEXTURE_SLOTS];
 #[derive(Clone, Debug)]
 pub struct GraphicsPipeline {
     pub(crate) program: Program,
-    pub(crate) primitive: u32,
+    pub(crate) primitive: pso::State<u32>,
     pub(crate) patch_size: Option<i32>,
     pub(crate) blend_targets: Vec<pso::ColorBlendDesc>,
     pub(crate) attributes: Vec<AttributeDesc>,
     pub(crate) vertex_buffers: Vec<Option<pso::VertexBufferDesc>>,
     pub(crate) uniforms: Vec<UniformDesc>,
     pub(crate) rasterizer: pso::Rasterizer,
-    pub(crate) depth: Option<pso::DepthTest>,
+    pub(crate) depth: pso::State<Option<pso::DepthTest>>,
+    pub(crate) stencil: Option<pso::StencilTest>,
     pub(crate) baked_states: pso::BakedStates,
     pub(crate) sampler_map: SamplerBindMap,
 }
@@ -169,12 +184,14 @@ pub enum ImageView {
     Renderbuffer {
         raw: Renderbuffer,
         aspects: format::Aspects,
+        channel: format::ChannelType,
     },
     Texture {
         target: TextureTarget,
         raw: Texture,
         is_3d: bool,
         sub: i::SubresourceRange,
+        channel: format::ChannelType,
     },
 }
 
@@ -185,6 +202,12 @@ impl ImageView {
             ImageView::Texture { ref sub, .. } => sub.aspects,
         }
     }
+
+    pub(crate) fn channel(&self) -> format::ChannelType {
+        match *self {
+            ImageView::Renderbuffer { channel, .. } | ImageView::Texture { channel, .. } => channel,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -238,6 +261,7 @@ impl SwapchainImage {
             view: ImageView::Renderbuffer {
                 raw: renderbuffer,
                 aspects: format::Aspects::COLOR,
+                channel,
             },
         }
     }
@@ -294,6 +318,8 @@ impl pso::DescriptorPool<Backend> for DescriptorPool {
 pub struct ShaderModule {
     #[cfg(feature = "cross")]
     pub(crate) spv: Vec<u32>,
+    /// Vertex outputs captured by the transform feedback decorations of the SPIR-V.
+    pub(crate) decorated_transform_feedback: Option<Vec<pso::TransformFeedbackVarying>>,
     pub(crate) naga: Result<hal::device::NagaShader, String>,
 }
 
@@ -311,6 +337,8 @@ pub struct Memory {
     pub(crate) buffer: Option<(RawBuffer, u32)>,
     /// Allocation size
     pub(crate) size: u64,
+    /// Heap the allocation is counted against.
+    pub(crate) heap_index: usize,
     pub(crate) map_flags: u32,
     pub(crate) emulate_map_allocation: Option<*mut u8>,
 }
@@ -328,6 +356,7 @@ pub struct RenderPass {
 pub struct SubpassDesc {
     pub(crate) color_attachments: Vec<usize>,
     pub(crate) depth_stencil: Option<usize>,
+    pub(crate) view_mask: u32,
 }
 
 impl SubpassDesc {
@@ -0,0 +1,655 @@
+//! Hardware performance counters, sampled with `GL_AMD_performance_monitor`
+//! or `GL_INTEL_performance_query`.
+//!
+//! Neither extension is exposed by `glow`, so their entry points are loaded
+//! by the window backend along with the other `ExtraFunctions`.
+
+use crate::{info::Info, GlContainer};
+use glow::HasContext;
+use hal::query::{
+    self, PerformanceCounter, PerformanceCounterStorage as Storage, PerformanceCounterUnit as Unit,
+    PERFORMANCE_COUNTER_RESULT_SIZE,
+};
+use std::{mem, os::raw, ptr};
+
+const COUNTER_TYPE_AMD: u32 = 0x8BC0;
+const UNSIGNED_INT64_AMD: u32 = 0x8BC2;
+const PERCENTAGE_AMD: u32 = 0x8BC3;
+const PERFMON_RESULT_AVAILABLE_AMD: u32 = 0x8BC4;
+const PERFMON_RESULT_SIZE_AMD: u32 = 0x8BC5;
+const PERFMON_RESULT_AMD: u32 = 0x8BC6;
+
+const PERFQUERY_DONOT_FLUSH_INTEL: u32 = 0x83F9;
+const PERFQUERY_WAIT_INTEL: u32 = 0x83FB;
+const PERFQUERY_COUNTER_DURATION_NORM_INTEL: u32 = 0x94F1;
+const PERFQUERY_COUNTER_DURATION_RAW_INTEL: u32 = 0x94F2;
+const PERFQUERY_COUNTER_THROUGHPUT_INTEL: u32 = 0x94F3;
+const PERFQUERY_COUNTER_DATA_UINT32_INTEL: u32 = 0x94F8;
+const PERFQUERY_COUNTER_DATA_UINT64_INTEL: u32 = 0x94F9;
+const PERFQUERY_COUNTER_DATA_FLOAT_INTEL: u32 = 0x94FA;
+const PERFQUERY_COUNTER_DATA_DOUBLE_INTEL: u32 = 0x94FB;
+const PERFQUERY_COUNTER_DATA_BOOL32_INTEL: u32 = 0x94FC;
+const PERFQUERY_COUNTER_NAME_LENGTH_MAX_INTEL: u32 = 0x94FE;
+const PERFQUERY_COUNTER_DESC_LENGTH_MAX_INTEL: u32 = 0x94FF;
+const PERFQUERY_QUERY_NAME_LENGTH_MAX_INTEL: u32 = 0x9500;
+
+type GetPerfMonitorGroups = unsafe extern "system" fn(*mut i32, i32, *mut u32);
+type GetPerfMonitorCounters = unsafe extern "system" fn(u32, *mut i32, *mut i32, i32, *mut u32);
+type GetPerfMonitorGroupString = unsafe extern "system" fn(u32, i32, *mut i32, *mut raw::c_char);
+type GetPerfMonitorCounterString =
+    unsafe extern "system" fn(u32, u32, i32, *mut i32, *mut raw::c_char);
+type GetPerfMonitorCounterInfo = unsafe extern "system" fn(u32, u32, u32, *mut raw::c_void);
+type GenPerfMonitors = unsafe extern "system" fn(i32, *mut u32);
+type DeletePerfMonitors = unsafe extern "system" fn(i32, *mut u32);
+type SelectPerfMonitorCounters = unsafe extern "system" fn(u32, u8, u32, i32, *mut u32);
+type GetPerfMonitorCounterData = unsafe extern "system" fn(u32, u32, i32, *mut u32, *mut i32);
+
+type GetFirstPerfQueryId = unsafe extern "system" fn(*mut u32);
+type GetNextPerfQueryId = unsafe extern "system" fn(u32, *mut u32);
+type GetPerfQueryInfo =
+    unsafe extern "system" fn(u32, u32, *mut raw::c_char, *mut u32, *mut u32, *mut u32, *mut u32);
+type GetPerfCounterInfo = unsafe extern "system" fn(
+    u32,
+    u32,
+    u32,
+    *mut raw::c_char,
+    u32,
+    *mut raw::c_char,
+    *mut u32,
+    *mut u32,
+    *mut u32,
+    *mut u32,
+    *mut u64,
+);
+type CreatePerfQuery = unsafe extern "system" fn(u32, *mut u32);
+type GetPerfQueryData = unsafe extern "system" fn(u32, u32, i32, *mut raw::c_void, *mut u32);
+
+type MonitorCommand = unsafe extern "system" fn(u32);
+
+/// Entry points of `GL_AMD_performance_monitor`.
+pub(crate) struct AmdPerformanceMonitor {
+    get_groups: GetPerfMonitorGroups,
+    get_counters: GetPerfMonitorCounters,
+    get_group_string: GetPerfMonitorGroupString,
+    get_counter_string: GetPerfMonitorCounterString,
+    get_counter_info: GetPerfMonitorCounterInfo,
+    gen_monitors: GenPerfMonitors,
+    delete_monitors: DeletePerfMonitors,
+    select_counters: SelectPerfMonitorCounters,
+    begin_monitor: MonitorCommand,
+    end_monitor: MonitorCommand,
+    get_counter_data: GetPerfMonitorCounterData,
+}
+
+impl AmdPerformanceMonitor {
+    /// Loads the entry points, if all of them are available.
+    pub(crate) unsafe fn load(
+        get_proc_address: impl Fn(&str) -> Option<*const raw::c_void>,
+    ) -> Option<Self> {
+        Some(AmdPerformanceMonitor {
+            get_groups: mem::transmute(get_proc_address("glGetPerfMonitorGroupsAMD")?),
+            get_counters: mem::transmute(get_proc_address("glGetPerfMonitorCountersAMD")?),
+            get_group_string: mem::transmute(get_proc_address("glGetPerfMonitorGroupStringAMD")?),
+            get_counter_string: mem::transmute(get_proc_address(
+                "glGetPerfMonitorCounterStringAMD",
+            )?),
+            get_counter_info: mem::transmute(get_proc_address("glGetPerfMonitorCounterInfoAMD")?),
+            gen_monitors: mem::transmute(get_proc_address("glGenPerfMonitorsAMD")?),
+            delete_monitors: mem::transmute(get_proc_address("glDeletePerfMonitorsAMD")?),
+            select_counters: mem::transmute(get_proc_address("glSelectPerfMonitorCountersAMD")?),
+            begin_monitor: mem::transmute(get_proc_address("glBeginPerfMonitorAMD")?),
+            end_monitor: mem::transmute(get_proc_address("glEndPerfMonitorAMD")?),
+            get_counter_data: mem::transmute(get_proc_address("glGetPerfMonitorCounterDataAMD")?),
+        })
+    }
+}
+
+/// Entry points of `GL_INTEL_performance_query`.
+pub(crate) struct IntelPerformanceQuery {
+    get_first_query_id: GetFirstPerfQueryId,
+    get_next_query_id: GetNextPerfQueryId,
+    get_query_info: GetPerfQueryInfo,
+    get_counter_info: GetPerfCounterInfo,
+    create_query: CreatePerfQuery,
+    delete_query: MonitorCommand,
+    begin_query: MonitorCommand,
+    end_query: MonitorCommand,
+    get_query_data: GetPerfQueryData,
+}
+
+impl IntelPerformanceQuery {
+    /// Loads the entry points, if all of them are available.
+    pub(crate) unsafe fn load(
+        get_proc_address: impl Fn(&str) -> Option<*const raw::c_void>,
+    ) -> Option<Self> {
+        Some(IntelPerformanceQuery {
+            get_first_query_id: mem::transmute(get_proc_address("glGetFirstPerfQueryIdINTEL")?),
+            get_next_query_id: mem::transmute(get_proc_address("glGetNextPerfQueryIdINTEL")?),
+            get_query_info: mem::transmute(get_proc_address("glGetPerfQueryInfoINTEL")?),
+            get_counter_info: mem::transmute(get_proc_address("glGetPerfCounterInfoINTEL")?),
+            create_query: mem::transmute(get_proc_address("glCreatePerfQueryINTEL")?),
+            delete_query: mem::transmute(get_proc_address("glDeletePerfQueryINTEL")?),
+            begin_query: mem::transmute(get_proc_address("glBeginPerfQueryINTEL")?),
+            end_query: mem::transmute(get_proc_address("glEndPerfQueryINTEL")?),
+            get_query_data: mem::transmute(get_proc_address("glGetPerfQueryDataINTEL")?),
+        })
+    }
+}
+
+/// Where the values of a counter come from.
+#[derive(Clone, Copy, Debug)]
+enum Source {
+    Amd {
+        group: u32,
+        counter: u32,
+        /// `GL_COUNTER_TYPE_AMD` of the counter.
+        ty: u32,
+        /// Number of counters of the group which can be sampled together.
+        max_active: u32,
+    },
+    Intel {
+        /// Query type which the counter belongs to.
+        query: u32,
+        /// Size of the data of the query type.
+        data_size: u32,
+        /// Offset of the counter in the data of the query type.
+        offset: u32,
+        /// Size of the counter in the data of the query type.
+        size: u32,
+    },
+}
+
+/// A hardware counter of the device.
+#[derive(Debug)]
+pub(crate) struct Counter {
+    pub(crate) desc: PerformanceCounter,
+    source: Source,
+}
+
+/// Object sampling the counters of a performance query.
+#[derive(Clone, Copy, Debug)]
+pub enum Monitor {
+    Amd(u32),
+    Intel(u32),
+}
+
+/// Enumerates the counters of the device, with the first extension that is supported.
+pub(crate) fn enumerate_counters(gl: &GlContainer, info: &Info) -> Vec<Counter> {
+    use crate::info::Requirement::Ext;
+    if let Some(ref amd) = gl.functions.amd_performance_monitor {
+        if info.is_supported(&[Ext("GL_AMD_performance_monitor")]) {
+            return unsafe { enumerate_amd_counters(amd) };
+        }
+    }
+    if let Some(ref intel) = gl.functions.intel_performance_query {
+        if info.is_supported(&[Ext("GL_INTEL_performance_query")]) {
+            return unsafe { enumerate_intel_counters(gl, intel) };
+        }
+    }
+    Vec::new()
+}
+
+/// Converts a string written by the driver, up to its terminating zero.
+fn from_c_string(mut raw: Vec<u8>) -> String {
+    if let Some(end) = raw.iter().position(|&c| c == 0) {
+        raw.truncate(end);
+    }
+    String::from_utf8_lossy(&raw).into_owned()
+}
+
+unsafe fn enumerate_amd_counters(amd: &AmdPerformanceMonitor) -> Vec<Counter> {
+    let mut group_count = 0;
+    (amd.get_groups)(&mut group_count, 0, ptr::null_mut());
+    let mut groups = vec![0; group_count.max(0) as usize];
+    (amd.get_groups)(&mut group_count, groups.len() as i32, groups.as_mut_ptr());
+    groups.truncate(group_count.max(0) as usize);
+
+    let mut counters = Vec::new();
+    for group in groups {
+        let mut length = 0;
+        (amd.get_group_string)(group, 0, &mut length, ptr::null_mut());
+        let mut category = vec![0u8; length.max(0) as usize + 1];
+        (amd.get_group_string)(
+            group,
+            category.len() as i32,
+            ptr::null_mut(),
+            category.as_mut_ptr() as *mut raw::c_char,
+        );
+        let category = from_c_string(category);
+
+        let (mut counter_count, mut max_active) = (0, 0);
+        (amd.get_counters)(
+            group,
+            &mut counter_count,
+            &mut max_active,
+            0,
+            ptr::null_mut(),
+        );
+        let mut group_counters = vec![0; counter_count.max(0) as usize];
+        (amd.get_counters)(
+            group,
+            &mut counter_count,
+            &mut max_active,
+            group_counters.len() as i32,
+            group_counters.as_mut_ptr(),
+        );
+        group_counters.truncate(counter_count.max(0) as usize);
+
+        for counter in group_counters {
+            let mut ty = 0u32;
+            (amd.get_counter_info)(
+                group,
+                counter,
+                COUNTER_TYPE_AMD,
+                &mut ty as *mut u32 as *mut raw::c_void,
+            );
+            let (unit, storage) = match ty {
+                glow::UNSIGNED_INT => (Unit::Generic, Storage::Uint32),
+                UNSIGNED_INT64_AMD => (Unit::Generic, Storage::Uint64),
+                glow::FLOAT => (Unit::Generic, Storage::Float32),
+                PERCENTAGE_AMD => (Unit::Percentage, Storage::Float32),
+                _ => {
+                    log::warn!("Performance counter type {:#x} is unknown", ty);
+                    continue;
+                }
+            };
+
+            let mut length = 0;
+            (amd.get_counter_string)(group, counter, 0, &mut length, ptr::null_mut());
+            let mut name = vec![0u8; length.max(0) as usize + 1];
+            (amd.get_counter_string)(
+                group,
+                counter,
+                name.len() as i32,
+                ptr::null_mut(),
+                name.as_mut_ptr() as *mut raw::c_char,
+            );
+            let name = from_c_string(name);
+
+            counters.push(Counter {
+                desc: PerformanceCounter {
+                    // The extension doesn't describe the counters any further.
+                    description: name.clone(),
+                    name,
+                    category: category.clone(),
+                    unit,
+                    storage,
+                },
+                source: Source::Amd {
+                    group,
+                    counter,
+                    ty,
+                    max_active: max_active.max(0) as u32,
+                },
+            });
+        }
+    }
+    counters
+}
+
+unsafe fn enumerate_intel_counters(
+    gl: &GlContainer,
+    intel: &IntelPerformanceQuery,
+) -> Vec<Counter> {
+    let max_length = |pname| gl.get_parameter_i32(pname).max(0) as u32 + 1;
+    let query_name_length = max_length(PERFQUERY_QUERY_NAME_LENGTH_MAX_INTEL);
+    let counter_name_length = max_length(PERFQUERY_COUNTER_NAME_LENGTH_MAX_INTEL);
+    let counter_desc_length = max_length(PERFQUERY_COUNTER_DESC_LENGTH_MAX_INTEL);
+
+    let mut counters = Vec::new();
+    let mut query = 0;
+    (intel.get_first_query_id)(&mut query);
+    while query != 0 {
+        let mut category = vec![0u8; query_name_length as usize];
+        let (mut data_size, mut counter_count, mut instances, mut caps) = (0, 0, 0, 0);
+        (intel.get_query_info)(
+            query,
+            query_name_length,
+            category.as_mut_ptr() as *mut raw::c_char,
+            &mut data_size,
+            &mut counter_count,
+            &mut instances,
+            &mut caps,
+        );
+        let category = from_c_string(category);
+
+        // Counters are numbered from 1.
+        for counter in 1..=counter_count {
+            let mut name = vec![0u8; counter_name_length as usize];
+            let mut description = vec![0u8; counter_desc_length as usize];
+            let (mut offset, mut size, mut ty, mut data_type, mut max_value) = (0, 0, 0, 0, 0);
+            (intel.get_counter_info)(
+                query,
+                counter,
+                counter_name_length,
+                name.as_mut_ptr() as *mut raw::c_char,
+                counter_desc_length,
+                description.as_mut_ptr() as *mut raw::c_char,
+                &mut offset,
+                &mut size,
+                &mut ty,
+                &mut data_type,
+                &mut max_value,
+            );
+            let storage = match data_type {
+                PERFQUERY_COUNTER_DATA_UINT32_INTEL | PERFQUERY_COUNTER_DATA_BOOL32_INTEL => {
+                    Storage::Uint32
+                }
+                PERFQUERY_COUNTER_DATA_UINT64_INTEL => Storage::Uint64,
+                PERFQUERY_COUNTER_DATA_FLOAT_INTEL => Storage::Float32,
+                PERFQUERY_COUNTER_DATA_DOUBLE_INTEL => Storage::Float64,
+                _ => {
+                    log::warn!("Performance counter data type {:#x} is unknown", data_type);
+                    continue;
+                }
+            };
+            let unit = match ty {
+                PERFQUERY_COUNTER_DURATION_NORM_INTEL => Unit::Percentage,
+                PERFQUERY_COUNTER_DURATION_RAW_INTEL => Unit::Nanoseconds,
+                PERFQUERY_COUNTER_THROUGHPUT_INTEL => Unit::BytesPerSecond,
+                _ => Unit::Generic,
+            };
+            if offset + size > data_size || size as usize > PERFORMANCE_COUNTER_RESULT_SIZE {
+                log::warn!("Performance counter {} has an invalid layout", counter);
+                continue;
+            }
+
+            counters.push(Counter {
+                desc: PerformanceCounter {
+                    name: from_c_string(name),
+                    category: category.clone(),
+                    description: from_c_string(description),
+                    unit,
+                    storage,
+                },
+                source: Source::Intel {
+                    query,
+                    data_size,
+                    offset,
+                    size,
+                },
+            });
+        }
+
+        (intel.get_next_query_id)(query, &mut query);
+    }
+    counters
+}
+
+/// Entry points of the extension which the AMD counters were enumerated with.
+fn amd(gl: &GlContainer) -> &AmdPerformanceMonitor {
+    gl.functions.amd_performance_monitor.as_ref().unwrap()
+}
+
+/// Entry points of the extension which the Intel counters were enumerated with.
+fn intel(gl: &GlContainer) -> &IntelPerformanceQuery {
+    gl.functions.intel_performance_query.as_ref().unwrap()
+}
+
+/// Checks that the chosen counters can be sampled together in a single pass.
+fn is_single_pass(counters: &[Counter], indices: &[u32]) -> bool {
+    let sources = move || {
+        indices
+            .iter()
+            .map(move |&index| counters[index as usize].source)
+    };
+    sources().all(
+        |source| match (source, counters[indices[0] as usize].source) {
+            (
+                Source::Amd {
+                    group, max_active, ..
+                },
+                Source::Amd { .. },
+            ) => {
+                // Each group has its own limit of active counters.
+                let active = sources()
+                    .filter(|other| match *other {
+                        Source::Amd { group: g, .. } => g == group,
+                        Source::Intel { .. } => false,
+                    })
+                    .count();
+                active as u32 <= max_active
+            }
+            // Only the counters of a single query type are sampled together.
+            (Source::Intel { query, .. }, Source::Intel { query: first, .. }) => query == first,
+            _ => false,
+        },
+    )
+}
+
+/// Creates `count` monitors sampling the chosen counters.
+pub(crate) unsafe fn create_monitors(
+    gl: &GlContainer,
+    counters: &[Counter],
+    indices: &[u32],
+    count: query::Id,
+) -> Result<Box<[Monitor]>, query::CreationError> {
+    let unsupported = query::CreationError::Unsupported(query::Type::PerformanceCounters);
+    if indices.is_empty() || !is_single_pass(counters, indices) {
+        return Err(unsupported);
+    }
+
+    let mut monitors = Vec::with_capacity(count as usize);
+    match counters[indices[0] as usize].source {
+        Source::Amd { .. } => {
+            let amd = amd(gl);
+            let mut raw = vec![0; count as usize];
+            (amd.gen_monitors)(count as i32, raw.as_mut_ptr());
+            for monitor in raw {
+                for &index in indices {
+                    if let Source::Amd {
+                        group, mut counter, ..
+                    } = counters[index as usize].source
+                    {
+                        (amd.select_counters)(monitor, glow::TRUE as u8, group, 1, &mut counter);
+                    }
+                }
+                monitors.push(Monitor::Amd(monitor));
+            }
+        }
+        Source::Intel { query, .. } => {
+            let intel = intel(gl);
+            for _ in 0..count {
+                let mut handle = 0;
+                (intel.create_query)(query, &mut handle);
+                if handle == 0 {
+                    log::error!("Performance query creation failed");
+                    for monitor in monitors {
+                        delete_monitor(gl, monitor);
+                    }
+                    return Err(hal::device::OutOfMemory::Host.into());
+                }
+                monitors.push(Monitor::Intel(handle));
+            }
+        }
+    }
+    Ok(monitors.into_boxed_slice())
+}
+
+pub(crate) unsafe fn delete_monitor(gl: &GlContainer, monitor: Monitor) {
+    match monitor {
+        Monitor::Amd(mut raw) => (amd(gl).delete_monitors)(1, &mut raw),
+        Monitor::Intel(raw) => (intel(gl).delete_query)(raw),
+    }
+}
+
+pub(crate) unsafe fn begin_monitor(gl: &GlContainer, monitor: Monitor) {
+    match monitor {
+        Monitor::Amd(raw) => (amd(gl).begin_monitor)(raw),
+        Monitor::Intel(raw) => (intel(gl).begin_query)(raw),
+    }
+}
+
+pub(crate) unsafe fn end_monitor(gl: &GlContainer, monitor: Monitor) {
+    match monitor {
+        Monitor::Amd(raw) => (amd(gl).end_monitor)(raw),
+        Monitor::Intel(raw) => (intel(gl).end_query)(raw),
+    }
+}
+
+/// Writes the values of the chosen counters sampled by `monitor` at the start of `data`,
+/// in slots of `PERFORMANCE_COUNTER_RESULT_SIZE` bytes.
+///
+/// Returns `false` if the values aren't available yet.
+pub(crate) unsafe fn read_monitor_results(
+    gl: &GlContainer,
+    monitor: Monitor,
+    counters: &[Counter],
+    indices: &[u32],
+    wait: bool,
+    data: &mut [u8],
+) -> bool {
+    let sources = indices.iter().map(|&index| counters[index as usize].source);
+    match monitor {
+        Monitor::Amd(raw) => {
+            let amd = amd(gl);
+            let get_available = || {
+                let mut available = 0;
+                (amd.get_counter_data)(
+                    raw,
+                    PERFMON_RESULT_AVAILABLE_AMD,
+                    4,
+                    &mut available,
+                    ptr::null_mut(),
+                );
+                available != 0
+            };
+            let mut available = get_available();
+            if !available && wait {
+                // The extension can't wait on the results, so the whole queue is waited for.
+                gl.finish();
+                available = get_available();
+            }
+            if !available {
+                return false;
+            }
+
+            let mut size = 0;
+            (amd.get_counter_data)(raw, PERFMON_RESULT_SIZE_AMD, 4, &mut size, ptr::null_mut());
+            let mut result = vec![0; size as usize / 4];
+            let mut written = 0;
+            (amd.get_counter_data)(
+                raw,
+                PERFMON_RESULT_AMD,
+                size as i32,
+                result.as_mut_ptr(),
+                &mut written,
+            );
+            result.truncate(written.max(0) as usize / 4);
+            let selected = sources
+                .filter_map(|source| match source {
+                    Source::Amd {
+                        group, counter, ty, ..
+                    } => Some((group, counter, ty)),
+                    Source::Intel { .. } => None,
+                })
+                .collect::<Vec<_>>();
+            write_amd_results(&result, &selected, data);
+            true
+        }
+        Monitor::Intel(raw) => {
+            let intel = intel(gl);
+            let data_size = match counters[indices[0] as usize].source {
+                Source::Intel { data_size, .. } => data_size,
+                Source::Amd { .. } => unreachable!(),
+            };
+            let flags = if wait {
+                PERFQUERY_WAIT_INTEL
+            } else {
+                PERFQUERY_DONOT_FLUSH_INTEL
+            };
+            let mut result = vec![0u8; data_size as usize];
+            let mut written = 0;
+            (intel.get_query_data)(
+                raw,
+                flags,
+                data_size as i32,
+                result.as_mut_ptr() as *mut raw::c_void,
+                &mut written,
+            );
+            if written == 0 {
+                return false;
+            }
+            for (source, slot) in sources.zip(data.chunks_mut(PERFORMANCE_COUNTER_RESULT_SIZE)) {
+                if let Source::Intel { offset, size, .. } = source {
+                    let value = &result[offset as usize..(offset + size) as usize];
+                    slot[..value.len()].copy_from_slice(value);
+                    slot[value.len()..].fill(0);
+                }
+            }
+            true
+        }
+    }
+}
+
+/// Writes the values of the `(group, counter, type)` counters found in the result
+/// of an AMD performance monitor, which lists the group, counter and value of each
+/// of its counters in turn.
+fn write_amd_results(result: &[u32], selected: &[(u32, u32, u32)], data: &mut [u8]) {
+    let mut words = result;
+    while words.len() >= 2 {
+        let (group, counter) = (words[0], words[1]);
+        let (position, ty) = match selected
+            .iter()
+            .position(|&(g, c, _)| g == group && c == counter)
+        {
+            Some(position) => (position, selected[position].2),
+            // Only the selected counters are reported.
+            None => return,
+        };
+        let value_words = if ty == UNSIGNED_INT64_AMD { 2 } else { 1 };
+        let value = match words.get(2..2 + value_words) {
+            Some(value) => value,
+            None => return,
+        };
+        let slot = &mut data[position * PERFORMANCE_COUNTER_RESULT_SIZE..]
+            [..PERFORMANCE_COUNTER_RESULT_SIZE];
+        slot.fill(0);
+        for (word, bytes) in value.iter().zip(slot.chunks_mut(4)) {
+            bytes.copy_from_slice(&word.to_ne_bytes());
+        }
+        words = &words[2 + value_words..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_amd_results, PERCENTAGE_AMD, UNSIGNED_INT64_AMD};
+    use hal::query::{
+        PerformanceCounterStorage as Storage, PerformanceCounterValue as Value,
+        PERFORMANCE_COUNTER_RESULT_SIZE,
+    };
+
+    #[test]
+    fn test_write_amd_results() {
+        let big = 0x1_0000_0002u64.to_ne_bytes();
+        let word = |i: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&big[i * 4..][..4]);
+            u32::from_ne_bytes(bytes)
+        };
+        // The driver reports the counters in its own order.
+        let result = [3, 7, word(0), word(1), 1, 2, 50f32.to_bits(), 1, 4, 9];
+        let selected = [
+            (1, 2, PERCENTAGE_AMD),
+            (3, 7, UNSIGNED_INT64_AMD),
+            (1, 4, glow::UNSIGNED_INT),
+        ];
+        let mut data = [0xFF; 3 * PERFORMANCE_COUNTER_RESULT_SIZE];
+        write_amd_results(&result, &selected, &mut data);
+
+        let mut values = data.chunks(PERFORMANCE_COUNTER_RESULT_SIZE);
+        assert_eq!(
+            Storage::Float32.decode(values.next().unwrap()),
+            Value::Float32(50.0)
+        );
+        assert_eq!(
+            Storage::Uint64.decode(values.next().unwrap()),
+            Value::Uint64(0x1_0000_0002)
+        );
+        assert_eq!(
+            Storage::Uint32.decode(values.next().unwrap()),
+            Value::Uint32(9)
+        );
+    }
+}
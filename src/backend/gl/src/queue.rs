@@ -1,6 +1,6 @@
 use crate::{
-    command as com, device, info::LegacyFeatures, native, state, Backend, Device, GlContext, Share,
-    Starc, Surface, MAX_COLOR_ATTACHMENTS,
+    command as com, device, info::LegacyFeatures, native, perf, state, Backend, Device, GlContext,
+    Share, Starc, Surface, MAX_COLOR_ATTACHMENTS,
 };
 
 use arrayvec::ArrayVec;
@@ -27,6 +27,8 @@ struct State {
     num_viewports: usize,
     // Currently set scissor rects.
     num_scissors: usize,
+    // Indicate if conditional rendering is predicated on a query.
+    conditional_render: bool,
 }
 
 impl State {
@@ -38,6 +40,7 @@ impl State {
             index_buffer: None,
             num_viewports: 0,
             num_scissors: 0,
+            conditional_render: false,
         }
     }
 
@@ -171,8 +174,19 @@ impl Queue {
     }
     */
 
-    fn bind_target(&mut self, point: u32, attachment: u32, view: &native::ImageView) {
-        Device::bind_target(&self.share.context, point, attachment, view)
+    fn bind_target(
+        &mut self,
+        point: u32,
+        attachment: u32,
+        view: &native::ImageView,
+        view_mask: u32,
+    ) {
+        let gl = &self.share.context;
+        if view_mask == 0 {
+            Device::bind_target(gl, point, attachment, view)
+        } else {
+            Device::bind_target_multiview(gl, point, attachment, view, view_mask)
+        }
     }
 
     fn _unbind_target(&mut self, point: u32, attachment: u32) {
@@ -230,7 +244,116 @@ impl Queue {
         };
     }
 
+    /// Write the result of `query` to `buffer` at `offset`.
+    fn copy_query_result(
+        &self,
+        query: native::Query,
+        buffer: native::RawBuffer,
+        offset: i32,
+        flags: hal::query::ResultFlags,
+    ) {
+        use hal::query::ResultFlags;
+
+        self.share.forget_buffer_predicates(buffer);
+        if self.share.private_caps.query_buffer_object {
+            self.write_query_result_to_buffer(query, buffer, offset, flags);
+            // The query result is copied on the device, in order with the other commands,
+            // so the conditional rendering predicated on the copy can be predicated on
+            // the query itself.
+            self.share
+                .query_predicates
+                .borrow_mut()
+                .insert((buffer, offset as hal::buffer::Offset), query);
+            return;
+        }
+
+        // Without `GL_QUERY_BUFFER`, the result is read back by the host.
+        let result = device::read_query_result(&self.share.context, query, flags);
+        let value_size = if flags.contains(ResultFlags::BITS_64) {
+            8
+        } else {
+            4
+        };
+        let mut data = [0u8; 16];
+        device::write_query_result(&mut data, result, flags);
+        // Unavailable results are left untouched, unlike their availability.
+        let start = if result.is_some() || flags.contains(ResultFlags::PARTIAL) {
+            0
+        } else {
+            value_size
+        };
+        let end = if flags.contains(ResultFlags::WITH_AVAILABILITY) {
+            2 * value_size
+        } else {
+            value_size
+        };
+
+        if start < end {
+            // Like `FillBuffer`, the buffer may not be uploaded to directly.
+            let gl = &self.share.context;
+            unsafe {
+                gl.bind_buffer(glow::COPY_READ_BUFFER, Some(self.fill_buffer));
+                gl.buffer_sub_data_u8_slice(glow::COPY_READ_BUFFER, 0, &data[start..end]);
+                gl.bind_buffer(glow::COPY_WRITE_BUFFER, Some(buffer));
+                gl.copy_buffer_sub_data(
+                    glow::COPY_READ_BUFFER,
+                    glow::COPY_WRITE_BUFFER,
+                    0,
+                    offset + start as i32,
+                    (end - start) as i32,
+                );
+                gl.bind_buffer(glow::COPY_READ_BUFFER, None);
+                gl.bind_buffer(glow::COPY_WRITE_BUFFER, None);
+            }
+        }
+    }
+
+    /// Write the result of `query` to `buffer` at `offset` on the device, with `GL_QUERY_BUFFER`.
+    fn write_query_result_to_buffer(
+        &self,
+        query: native::Query,
+        buffer: native::RawBuffer,
+        offset: i32,
+        flags: hal::query::ResultFlags,
+    ) {
+        use hal::query::ResultFlags;
+
+        let gl = &self.share.context;
+        let functions = &gl.functions;
+        // Unavailable results are left untouched, unlike their availability.
+        let result = if flags.contains(ResultFlags::WAIT) {
+            glow::QUERY_RESULT
+        } else {
+            glow::QUERY_RESULT_NO_WAIT
+        };
+        let write = |pname, offset: i32| unsafe {
+            // The offset into the bound query buffer is passed as the pointer.
+            if flags.contains(ResultFlags::BITS_64) {
+                let get_query_object = functions.get_query_object_u64.unwrap();
+                get_query_object(query, pname, offset as usize as *mut u64);
+            } else {
+                let get_query_object = functions.get_query_object_u32.unwrap();
+                get_query_object(query, pname, offset as usize as *mut u32);
+            }
+        };
+        unsafe { gl.bind_buffer(glow::QUERY_BUFFER, Some(buffer)) };
+        write(result, offset);
+        if flags.contains(ResultFlags::WITH_AVAILABILITY) {
+            let value_size = if flags.contains(ResultFlags::BITS_64) {
+                8
+            } else {
+                4
+            };
+            write(glow::QUERY_RESULT_AVAILABLE, offset + value_size);
+        }
+        unsafe { gl.bind_buffer(glow::QUERY_BUFFER, None) };
+    }
+
     fn process(&mut self, cmd: &com::Command, data_buf: &[u8]) {
+        if let Some(buffer) = cmd.written_buffer() {
+            // The query results copied into the buffer may be overwritten.
+            self.share.forget_buffer_predicates(buffer);
+        }
         match *cmd {
             com::Command::BindIndexBuffer(buffer) => {
                 let gl = &self.share.context;
@@ -489,11 +612,13 @@ impl Queue {
                 framebuffer,
                 ref colors,
                 ref depth_stencil,
+                view_mask,
             } => {
                 let gl = &self.share.context;
                 unsafe { gl.bind_framebuffer(target, Some(framebuffer)) };
                 for (i, view) in colors.iter().enumerate() {
-                    self.bind_target(target, glow::COLOR_ATTACHMENT0 + i as u32, view);
+                    let attachment = glow::COLOR_ATTACHMENT0 + i as u32;
+                    self.bind_target(target, attachment, view, view_mask);
                 }
                 if let Some(ref view) = *depth_stencil {
                     let aspects = view.aspects();
@@ -504,7 +629,7 @@ impl Queue {
                     } else {
                         glow::DEPTH_STENCIL_ATTACHMENT
                     };
-                    self.bind_target(target, attachment, view);
+                    self.bind_target(target, attachment, view, view_mask);
                 }
             }
             com::Command::FillBuffer(buffer, ref range, value) => {
@@ -544,6 +669,69 @@ impl Queue {
                     gl.bind_buffer(glow::COPY_WRITE_BUFFER, None);
                 }
             }
+            com::Command::BeginConditionalRendering {
+                buffer,
+                offset,
+                inverted,
+            } => {
+                let query = self
+                    .share
+                    .query_predicates
+                    .borrow()
+                    .get(&(buffer, offset))
+                    .cloned();
+                match query {
+                    Some(query) => {
+                        let begin_conditional_render = self
+                            .share
+                            .context
+                            .functions
+                            .begin_conditional_render
+                            .unwrap();
+                        let mode = if inverted {
+                            glow::QUERY_WAIT_INVERTED
+                        } else {
+                            glow::QUERY_WAIT
+                        };
+                        unsafe { begin_conditional_render(query, mode) };
+                        self.state.conditional_render = true;
+                    }
+                    None => log::error!(
+                        "Conditional rendering isn't predicated on the copy of an occlusion query \
+                        result, so the commands are executed unconditionally"
+                    ),
+                }
+            }
+            com::Command::EndConditionalRendering => {
+                if self.state.conditional_render {
+                    let end_conditional_render =
+                        self.share.context.functions.end_conditional_render.unwrap();
+                    unsafe { end_conditional_render() };
+                    self.state.conditional_render = false;
+                }
+            }
+            com::Command::BeginQuery(target, query) => {
+                // The results copied before are no longer the ones of the query.
+                self.share.forget_query_predicates(query);
+                unsafe { self.share.context.begin_query(target, query) };
+            }
+            com::Command::EndQuery(target) => unsafe {
+                self.share.context.end_query(target);
+            },
+            com::Command::BeginPerfMonitor(monitor) => unsafe {
+                perf::begin_monitor(&self.share.context, monitor);
+            },
+            com::Command::EndPerfMonitor(monitor) => unsafe {
+                perf::end_monitor(&self.share.context, monitor);
+            },
+            com::Command::CopyQueryResult {
+                query,
+                buffer,
+                offset,
+                flags,
+            } => {
+                self.copy_query_result(query, buffer, offset as i32, flags);
+            }
             com::Command::SetDrawColorBuffers(ref indices) => {
                 let gl_indices = indices
                     .iter()
@@ -972,31 +1160,15 @@ impl Queue {
                 }
             }
             com::Command::BindRasterizer { rasterizer } => {
-                use hal::pso::FrontFace::*;
                 use hal::pso::PolygonMode::*;
 
                 let gl = &self.share.context;
 
-                unsafe {
-                    gl.front_face(match rasterizer.front_face {
-                        Clockwise => glow::CW,
-                        CounterClockwise => glow::CCW,
-                    })
-                };
-
-                if !rasterizer.cull_face.is_empty() {
-                    unsafe {
-                        gl.enable(glow::CULL_FACE);
-                        gl.cull_face(match rasterizer.cull_face {
-                            hal::pso::Face::FRONT => glow::FRONT,
-                            hal::pso::Face::BACK => glow::BACK,
-                            _ => glow::FRONT_AND_BACK,
-                        });
-                    }
-                } else {
-                    unsafe {
-                        gl.disable(glow::CULL_FACE);
-                    }
+                if let hal::pso::State::Static(front_face) = rasterizer.front_face {
+                    state::set_front_face(gl, front_face);
+                }
+                if let hal::pso::State::Static(cull_face) = rasterizer.cull_face {
+                    state::set_cull_face(gl, cull_face);
                 }
 
                 let (_gl_draw, gl_offset) = match rasterizer.polygon_mode {
@@ -1058,6 +1230,22 @@ impl Queue {
                     },
                 }
             }
+            com::Command::SetDepthTest(enable) => unsafe {
+                if enable {
+                    self.share.context.enable(glow::DEPTH_TEST);
+                } else {
+                    self.share.context.disable(glow::DEPTH_TEST);
+                }
+            },
+            com::Command::SetDepthFunc(fun) => unsafe {
+                self.share.context.depth_func(state::map_comparison(fun));
+            },
+            com::Command::SetFrontFace(front_face) => {
+                state::set_front_face(&self.share.context, front_face);
+            }
+            com::Command::SetCullFace(faces) => {
+                state::set_cull_face(&self.share.context, faces);
+            }
             com::Command::SetColorMask(slot, mask) => unsafe {
                 use hal::pso::ColorMask as Cm;
                 if let (true, Some(slot)) = (self.share.private_caps.per_slot_color_mask, slot) {
@@ -1088,6 +1276,21 @@ impl Queue {
             com::Command::SetStencilMask(value) => unsafe {
                 self.share.context.stencil_mask(value);
             },
+            com::Command::SetStencilTest(enable) => unsafe {
+                if enable {
+                    self.share.context.enable(glow::STENCIL_TEST);
+                } else {
+                    self.share.context.disable(glow::STENCIL_TEST);
+                }
+            },
+            com::Command::SetStencilFaces {
+                faces,
+                ref ops,
+                read_masks,
+                ref_values,
+            } => {
+                state::set_stencil_faces(&self.share.context, faces, ops, read_masks, ref_values);
+            }
             com::Command::SetStencilMaskSeparate(values) => unsafe {
                 self.share
                     .context
@@ -1103,6 +1306,12 @@ impl Queue {
                     }
                 }
             }
+            com::Command::BeginTransformFeedback(primitive) => unsafe {
+                self.share.context.begin_transform_feedback(primitive);
+            },
+            com::Command::EndTransformFeedback => unsafe {
+                self.share.context.end_transform_feedback();
+            },
         }
         if let Err(err) = self.share.check() {
             panic!("Error {:?} executing command: {:?}", err, cmd)
@@ -1171,7 +1380,7 @@ impl hal::queue::Queue<Backend> for Queue {
         surface.present(image, &self.share.context)
     }
 
-    fn wait_idle(&mut self) -> Result<(), hal::device::OutOfMemory> {
+    fn wait_idle(&mut self) -> Result<(), hal::device::WaitError> {
         unsafe {
             self.share.context.finish();
         }
@@ -1,4 +1,4 @@
-use crate::{ColorSlot, GlContainer};
+use crate::{conv, ColorSlot, GlContainer};
 use glow::HasContext;
 use hal::pso;
 
@@ -16,7 +16,6 @@ pub fn map_comparison(cmp: pso::Comparison) -> u32 {
     }
 }
 
-#[allow(dead_code)]
 fn map_operation(op: pso::StencilOp) -> u32 {
     use hal::pso::StencilOp::*;
     match op {
@@ -31,63 +30,44 @@ fn map_operation(op: pso::StencilOp) -> u32 {
     }
 }
 
-#[allow(dead_code)]
-pub(crate) fn bind_stencil(gl: &GlContainer, stencil: &Option<pso::StencilTest>, cull: pso::Face) {
-    fn bind_side(
-        gl: &GlContainer,
-        face: u32,
-        side: &pso::StencilFace,
-        read_mask: pso::StencilValue,
-        ref_value: pso::StencilValue,
-    ) {
+/// Sets the stencil function and operations of the given faces.
+pub(crate) fn set_stencil_faces(
+    gl: &GlContainer,
+    faces: pso::Face,
+    ops: &pso::Sided<pso::StencilFace>,
+    read_masks: pso::Sided<pso::StencilValue>,
+    ref_values: pso::Sided<pso::StencilValue>,
+) {
+    let sides = [
+        (
+            pso::Face::FRONT,
+            glow::FRONT,
+            &ops.front,
+            read_masks.front,
+            ref_values.front,
+        ),
+        (
+            pso::Face::BACK,
+            glow::BACK,
+            &ops.back,
+            read_masks.back,
+            ref_values.back,
+        ),
+    ];
+    for &(face, gl_face, side, read_mask, ref_value) in sides.iter() {
+        if !faces.contains(face) {
+            continue;
+        }
         unsafe {
-            gl.stencil_func_separate(face, map_comparison(side.fun), ref_value as _, read_mask);
+            gl.stencil_func_separate(gl_face, map_comparison(side.fun), ref_value as _, read_mask);
             gl.stencil_op_separate(
-                face,
+                gl_face,
                 map_operation(side.op_fail),
                 map_operation(side.op_depth_fail),
                 map_operation(side.op_pass),
             );
         }
     }
-    match *stencil {
-        Some(ref stencil) => {
-            unsafe { gl.enable(glow::STENCIL_TEST) };
-            let read_masks = stencil.read_masks.static_or(pso::Sided::new(!0));
-            let ref_values = stencil.reference_values.static_or(pso::Sided::new(0));
-            if !cull.contains(pso::Face::FRONT) {
-                bind_side(
-                    gl,
-                    glow::FRONT,
-                    &stencil.faces.front,
-                    read_masks.front,
-                    ref_values.front,
-                );
-                if let pso::State::Static(values) = stencil.write_masks {
-                    unsafe {
-                        gl.stencil_mask_separate(glow::FRONT, values.front);
-                    }
-                }
-            }
-            if !cull.contains(pso::Face::BACK) {
-                bind_side(
-                    gl,
-                    glow::BACK,
-                    &stencil.faces.back,
-                    read_masks.back,
-                    ref_values.back,
-                );
-                if let pso::State::Static(values) = stencil.write_masks {
-                    unsafe {
-                        gl.stencil_mask_separate(glow::BACK, values.back);
-                    }
-                }
-            }
-        }
-        None => unsafe {
-            gl.disable(glow::STENCIL_TEST);
-        },
-    }
 }
 
 fn map_factor(factor: pso::Factor) -> u32 {
@@ -175,6 +155,20 @@ pub(crate) fn _unlock_color_mask(gl: &GlContainer) {
     unsafe { gl.color_mask(true, true, true, true) };
 }
 
+pub(crate) fn set_front_face(gl: &GlContainer, front_face: pso::FrontFace) {
+    unsafe { gl.front_face(conv::front_face_to_gl(front_face)) };
+}
+
+pub(crate) fn set_cull_face(gl: &GlContainer, faces: pso::Face) {
+    match conv::cull_face_to_gl(faces) {
+        Some(faces) => unsafe {
+            gl.enable(glow::CULL_FACE);
+            gl.cull_face(faces);
+        },
+        None => unsafe { gl.disable(glow::CULL_FACE) },
+    }
+}
+
 pub(crate) fn set_blend_color(gl: &GlContainer, color: pso::ColorValue) {
     unsafe { gl.blend_color(color[0], color[1], color[2], color[3]) };
 }
@@ -1,10 +1,10 @@
 //! EGL-based surface and swapchain.
 
-use crate::{conv, native, GlContainer, PhysicalDevice, Starc};
+use crate::{conv, native, perf, ExtraFunctions, GlContainer, PhysicalDevice, Starc};
 use glow::HasContext;
 use hal::{image, window as w};
 use parking_lot::Mutex;
-use std::{os::raw, ptr};
+use std::{mem, os::raw, ptr};
 
 #[derive(Debug)]
 pub struct Swapchain {
@@ -332,8 +332,31 @@ impl hal::Instance<crate::Backend> for Instance {
                     .map_or(ptr::null(), |p| p as *const _)
             })
         };
+        let get_proc_address = |name: &str| {
+            inner
+                .egl
+                .get_proc_address(name)
+                .map(|p| p as *const raw::c_void)
+        };
+        let functions = unsafe {
+            ExtraFunctions {
+                get_integer64: get_proc_address("glGetInteger64v").map(|p| mem::transmute(p)),
+                framebuffer_texture_multiview: get_proc_address("glFramebufferTextureMultiviewOVR")
+                    .map(|p| mem::transmute(p)),
+                begin_conditional_render: get_proc_address("glBeginConditionalRender")
+                    .map(|p| mem::transmute(p)),
+                end_conditional_render: get_proc_address("glEndConditionalRender")
+                    .map(|p| mem::transmute(p)),
+                get_query_object_u32: get_proc_address("glGetQueryObjectuiv")
+                    .map(|p| mem::transmute(p)),
+                get_query_object_u64: get_proc_address("glGetQueryObjectui64v")
+                    .map(|p| mem::transmute(p)),
+                amd_performance_monitor: perf::AmdPerformanceMonitor::load(get_proc_address),
+                intel_performance_query: perf::IntelPerformanceQuery::load(get_proc_address),
+            }
+        };
         // Create physical device
-        vec![PhysicalDevice::new_adapter(context)]
+        vec![PhysicalDevice::new_adapter(context, functions)]
     }
 
     #[cfg_attr(target_os = "macos", allow(unused, unused_mut, unreachable_code))]
@@ -1,5 +1,6 @@
 use crate::{
-    conv, device::Device, native, Backend as B, GlContainer, PhysicalDevice, QueueFamily, Starc,
+    conv, device::Device, native, Backend as B, ExtraFunctions, GlContainer, PhysicalDevice,
+    QueueFamily, Starc,
 };
 use glow::HasContext;
 use hal::{adapter::Adapter, format as f, image, window};
@@ -59,7 +60,7 @@ impl hal::Instance<B> for Instance {
             None => return Vec::new(),
         };
 
-        let adapter = PhysicalDevice::new_adapter(context);
+        let adapter = PhysicalDevice::new_adapter(context, ExtraFunctions::default());
         vec![adapter]
     }
 
@@ -8,7 +8,7 @@ use crate::{
 
 use hal::{
     buffer, command as com,
-    device::OutOfMemory,
+    device::WaitError,
     format::{Aspects, FormatDesc},
     image as i, memory,
     pass::AttachmentLoadOp,
@@ -2515,7 +2515,7 @@ impl hal::queue::Queue<Backend> for Queue {
         Ok(None)
     }
 
-    fn wait_idle(&mut self) -> Result<(), OutOfMemory> {
+    fn wait_idle(&mut self) -> Result<(), WaitError> {
         QueueInner::wait_idle(&self.shared.queue);
         Ok(())
     }
@@ -3515,6 +3515,16 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        _first_binding: pso::BufferIndex,
+        _buffers: T,
+    ) where
+        T: Iterator<Item = (&'a native::Buffer, buffer::SubRange, pso::ElemStride)>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, vps: T)
     where
         T: Iterator<Item = pso::Viewport>,
@@ -3578,6 +3588,34 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.inner.borrow_mut().sink().pre_render().issue(com);
     }
 
+    unsafe fn set_cull_mode(&mut self, _faces: pso::Face) {
+        unimplemented!()
+    }
+
+    unsafe fn set_front_face(&mut self, _front_face: pso::FrontFace) {
+        unimplemented!()
+    }
+
+    unsafe fn set_primitive_topology(&mut self, _primitive: pso::Primitive) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, _enable: bool) {
+        unimplemented!()
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, _fun: pso::Comparison) {
+        unimplemented!()
+    }
+
+    unsafe fn set_stencil_op(&mut self, _faces: pso::Face, _ops: pso::StencilFace) {
+        unimplemented!()
+    }
+
     unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
         assign_sides(&mut self.state.stencil.reference_values, faces, value);
         let com =
@@ -3761,6 +3799,21 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.inner.borrow_mut().sink().stop_encoding();
     }
 
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        _render_area: pso::Rect,
+        _color_attachments: T,
+        _depth_stencil_attachment: Option<com::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = com::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn bind_graphics_pipeline(&mut self, pipeline: &native::GraphicsPipeline) {
         profiling::scope!("bind_graphics_pipeline");
         let mut inner = self.inner.borrow_mut();
@@ -4232,6 +4285,32 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &native::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &native::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         let mut inner = self.inner.borrow_mut();
         let (mut pre, init) = inner.sink().switch_compute();
@@ -4543,6 +4622,22 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, _descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn copy_acceleration_structure(&mut self, _src: &(), _dst: &()) {
+        unimplemented!()
+    }
+
     unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
         debug_assert!(self.state.render_pso_is_compatible);
         if instances.start == instances.end {
@@ -4930,6 +5025,34 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         // nothing to do, timestamps are unsupported on Metal
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &native::Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, _first_binding: u32, _buffers: T)
+    where
+        T: Iterator<Item = (&'a native::Buffer, buffer::SubRange)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn push_graphics_constants(
         &mut self,
         layout: &native::PipelineLayout,
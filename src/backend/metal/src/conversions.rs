@@ -1,6 +1,6 @@
 use hal;
 
-use crate::PrivateCapabilities;
+use crate::{native::RasterizerState, PrivateCapabilities};
 
 use hal::{
     format::{Format, Properties, Swizzle},
@@ -797,6 +797,42 @@ pub fn map_cull_face(face: pso::Face) -> Option<MTLCullMode> {
     }
 }
 
+/// Returns the rasterizer state of a pipeline.
+///
+/// The cull mode and front face set with
+/// [`set_cull_mode`][hal::command::CommandBuffer::set_cull_mode] and
+/// [`set_front_face`][hal::command::CommandBuffer::set_front_face] aren't supported,
+/// so dynamic ones fall back to the defaults of [`pso::Rasterizer::FILL`].
+pub fn map_rasterizer(rasterizer: &pso::Rasterizer, depth_clip_mode: bool) -> RasterizerState {
+    RasterizerState {
+        front_winding: map_winding(
+            rasterizer
+                .front_face
+                .static_or(pso::FrontFace::CounterClockwise),
+        ),
+        fill_mode: map_polygon_mode(rasterizer.polygon_mode),
+        cull_mode: match map_cull_face(rasterizer.cull_face.static_or(pso::Face::NONE)) {
+            Some(mode) => mode,
+            None => {
+                //TODO - Metal validation fails with
+                // RasterizationEnabled is false but the vertex shader's return type is not void
+                error!("Culling both sides is not yet supported");
+                //pipeline.set_rasterization_enabled(false);
+                MTLCullMode::None
+            }
+        },
+        depth_clip: if depth_clip_mode {
+            Some(if rasterizer.depth_clamping {
+                MTLDepthClipMode::Clamp
+            } else {
+                MTLDepthClipMode::Clip
+            })
+        } else {
+            None
+        },
+    }
+}
+
 #[cfg(feature = "cross")]
 pub fn map_naga_stage_to_cross(stage: naga::ShaderStage) -> spirv_cross::spirv::ExecutionModel {
     use spirv_cross::spirv::ExecutionModel as Em;
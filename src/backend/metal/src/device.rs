@@ -183,6 +183,19 @@ impl MemoryTypes {
     }
 }
 
+fn memory_heaps(shared: &Shared) -> Vec<adapter::MemoryHeap> {
+    vec![
+        adapter::MemoryHeap {
+            size: !0, //TODO: private memory limits
+            flags: memory::HeapFlags::DEVICE_LOCAL,
+        },
+        adapter::MemoryHeap {
+            size: shared.private_caps.max_buffer_size,
+            flags: memory::HeapFlags::empty(),
+        },
+    ]
+}
+
 #[derive(Debug)]
 pub struct PhysicalDevice {
     pub(crate) shared: Arc<Shared>,
@@ -378,16 +391,7 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
 
     fn memory_properties(&self) -> adapter::MemoryProperties {
         adapter::MemoryProperties {
-            memory_heaps: vec![
-                adapter::MemoryHeap {
-                    size: !0, //TODO: private memory limits
-                    flags: memory::HeapFlags::DEVICE_LOCAL,
-                },
-                adapter::MemoryHeap {
-                    size: self.shared.private_caps.max_buffer_size,
-                    flags: memory::HeapFlags::empty(),
-                },
-            ],
+            memory_heaps: memory_heaps(&self.shared),
             memory_types: self.memory_types.to_vec(),
         }
     }
@@ -568,12 +572,24 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             },
             downlevel: hal::DownlevelProperties::all_enabled(),
             performance_caveats: caveats,
-            dynamic_pipeline_states: hal::DynamicStates::all(),
+            dynamic_pipeline_states: hal::DynamicStates::VIEWPORT
+                | hal::DynamicStates::SCISSOR
+                | hal::DynamicStates::LINE_WIDTH
+                | hal::DynamicStates::BLEND_CONSTANTS
+                | hal::DynamicStates::DEPTH_BIAS
+                | hal::DynamicStates::DEPTH_BOUNDS
+                | hal::DynamicStates::STENCIL_READ_MASK
+                | hal::DynamicStates::STENCIL_WRITE_MASK
+                | hal::DynamicStates::STENCIL_REFERENCE,
 
             ..hal::PhysicalDeviceProperties::default()
         }
     }
 
+    fn performance_counters(&self, _family: QueueFamilyId) -> Vec<query::PerformanceCounter> {
+        Vec::new()
+    }
+
     unsafe fn enumerate_displays(&self) -> Vec<hal::display::Display<crate::Backend>> {
         unimplemented!();
     }
@@ -1686,7 +1702,10 @@ impl hal::device::Device<Backend> for Device {
         let pipeline = metal::RenderPipelineDescriptor::new();
         let pipeline_layout = &pipeline_desc.layout;
         let (rp_attachments, subpass) = {
-            let pass::Subpass { main_pass, index } = pipeline_desc.subpass;
+            let pass::Subpass { main_pass, index } = match pipeline_desc.target {
+                pass::PipelineTarget::Subpass(subpass) => subpass,
+                pass::PipelineTarget::Rendering(_) => unimplemented!(),
+            };
             (&main_pass.attachments, &main_pass.subpasses[index as usize])
         };
 
@@ -1843,17 +1862,17 @@ impl hal::device::Device<Backend> for Device {
                 .expect("no associated vertex buffer found");
             // handle wrapping offsets
             let elem_size = element.format.surface_desc().bits as pso::ElemOffset / 8;
-            let (cut_offset, base_offset) =
-                if original.stride == 0 || element.offset + elem_size <= original.stride {
-                    (element.offset, 0)
+            let stride = original.stride.static_or(0);
+            let (cut_offset, base_offset) = if stride == 0 || element.offset + elem_size <= stride {
+                (element.offset, 0)
+            } else {
+                let remainder = element.offset % stride;
+                if remainder + elem_size <= stride {
+                    (remainder, element.offset - remainder)
                 } else {
-                    let remainder = element.offset % original.stride;
-                    if remainder + elem_size <= original.stride {
-                        (remainder, element.offset - remainder)
-                    } else {
-                        (0, element.offset)
-                    }
-                };
+                    (0, element.offset)
+                }
+            };
             let relative_index = vertex_buffers
                 .iter()
                 .position(|(ref vb, offset)| vb.binding == binding && base_offset == *offset)
@@ -1866,7 +1885,7 @@ impl hal::device::Device<Backend> for Device {
                 - (relative_index as ResourceIndex);
             if mtl_buffer_index < pipeline_layout.total.vs.buffers {
                 error!("Attribute offset {} exceeds the stride {}, and there is no room for replacement.",
-                    element.offset, original.stride);
+                    element.offset, stride);
                 return Err(pso::CreationError::Other);
             }
             trace!("\tAttribute[{}] is mapped to vertex buffer[{}] with binding {} and offsets {} + {}",
@@ -1888,15 +1907,16 @@ impl hal::device::Device<Backend> for Device {
                 .layouts()
                 .object_at(self.shared.private_caps.max_buffers_per_stage as u64 - 1 - i as u64)
                 .expect("too many vertex descriptor layouts");
-            if vb.stride % STRIDE_GRANULARITY != 0 {
+            let stride = vb.stride.static_or(0);
+            if stride % STRIDE_GRANULARITY != 0 {
                 error!(
                     "Stride ({}) must be a multiple of {}",
-                    vb.stride, STRIDE_GRANULARITY
+                    stride, STRIDE_GRANULARITY
                 );
                 return Err(pso::CreationError::Other);
             }
-            if vb.stride != 0 {
-                mtl_buffer_desc.set_stride(vb.stride as u64);
+            if stride != 0 {
+                mtl_buffer_desc.set_stride(stride as u64);
                 match vb.rate {
                     VertexInputRate::Vertex => {
                         mtl_buffer_desc.set_step_function(MTLVertexStepFunction::PerVertex);
@@ -1922,29 +1942,10 @@ impl hal::device::Device<Backend> for Device {
             }
         }
 
-        let rasterizer_state = Some(n::RasterizerState {
-            front_winding: conv::map_winding(pipeline_desc.rasterizer.front_face),
-            fill_mode: conv::map_polygon_mode(pipeline_desc.rasterizer.polygon_mode),
-            cull_mode: match conv::map_cull_face(pipeline_desc.rasterizer.cull_face) {
-                Some(mode) => mode,
-                None => {
-                    //TODO - Metal validation fails with
-                    // RasterizationEnabled is false but the vertex shader's return type is not void
-                    error!("Culling both sides is not yet supported");
-                    //pipeline.set_rasterization_enabled(false);
-                    metal::MTLCullMode::None
-                }
-            },
-            depth_clip: if self.shared.private_caps.depth_clip_mode {
-                Some(if pipeline_desc.rasterizer.depth_clamping {
-                    metal::MTLDepthClipMode::Clamp
-                } else {
-                    metal::MTLDepthClipMode::Clip
-                })
-            } else {
-                None
-            },
-        });
+        let rasterizer_state = Some(conv::map_rasterizer(
+            &pipeline_desc.rasterizer,
+            self.shared.private_caps.depth_clip_mode,
+        ));
         let depth_bias = pipeline_desc
             .rasterizer
             .depth_bias
@@ -2198,6 +2199,32 @@ impl hal::device::Device<Backend> for Device {
 
     unsafe fn destroy_sampler(&self, _sampler: n::Sampler) {}
 
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        _desc: &hal::acceleration_structure::GeometryDesc<Backend>,
+        _max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        unimplemented!()
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        _desc: &hal::acceleration_structure::CreateDesc<Backend>,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        _accel_struct: &(),
+    ) -> hal::acceleration_structure::DeviceAddress {
+        unimplemented!()
+    }
+
+    unsafe fn destroy_acceleration_structure(&self, _accel_struct: ()) {
+        unimplemented!()
+    }
+
     unsafe fn map_memory(
         &self,
         memory: &mut n::Memory,
@@ -2354,6 +2381,7 @@ impl hal::device::Device<Backend> for Device {
         &self,
         binding_iter: I,
         immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, d::OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
@@ -2603,6 +2631,7 @@ impl hal::device::Device<Backend> for Device {
                                 },
                             );
                         }
+                        pso::Descriptor::AccelerationStructure(_) => unimplemented!(),
                     }
                     counters.add(layout.content);
                 }
@@ -2678,6 +2707,7 @@ impl hal::device::Device<Backend> for Device {
                             data.ptr = (&**buf_raw).as_ptr();
                             arg_index += 1;
                         }
+                        pso::Descriptor::AccelerationStructure(_) => unimplemented!(),
                     }
                 }
             }
@@ -2688,10 +2718,36 @@ impl hal::device::Device<Backend> for Device {
         unimplemented!()
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _layout: &n::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<pso::EmulatedDescriptorUpdateTemplate, d::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut n::DescriptorSet,
+        template: &pso::EmulatedDescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
+    }
+
     unsafe fn destroy_descriptor_pool(&self, _pool: n::DescriptorPool) {}
 
     unsafe fn destroy_descriptor_set_layout(&self, _layout: n::DescriptorSetLayout) {}
 
+    unsafe fn destroy_descriptor_update_template(
+        &self,
+        _template: pso::EmulatedDescriptorUpdateTemplate,
+    ) {
+    }
+
     unsafe fn destroy_pipeline_layout(&self, _pipeline_layout: n::PipelineLayout) {}
 
     unsafe fn destroy_shader_module(&self, _module: n::ShaderModule) {}
@@ -2706,10 +2762,45 @@ impl hal::device::Device<Backend> for Device {
 
     unsafe fn destroy_semaphore(&self, _semaphore: n::Semaphore) {}
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: u64,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        _semaphore: &n::Semaphore,
+    ) -> Result<u64, d::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        _semaphores: I,
+        _wait: d::WaitFor,
+        _timeout_ns: u64,
+    ) -> Result<bool, d::WaitError>
+    where
+        I: Iterator<Item = (&'a n::Semaphore, u64)>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _value: u64,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
     unsafe fn allocate_memory(
         &self,
         memory_type: hal::MemoryTypeId,
         size: u64,
+        _flags: memory::AllocationFlags,
     ) -> Result<n::Memory, d::AllocationError> {
         profiling::scope!("allocate_memory");
         let (storage, cache) = MemoryTypes::describe(memory_type.0);
@@ -2748,6 +2839,17 @@ impl hal::device::Device<Backend> for Device {
         }
     }
 
+    fn memory_budget(&self) -> Vec<adapter::MemoryHeapBudget> {
+        // The usage isn't tracked, so the whole heaps are available.
+        memory_heaps(&self.shared)
+            .iter()
+            .map(|heap| adapter::MemoryHeapBudget {
+                budget: heap.size,
+                usage: 0,
+            })
+            .collect()
+    }
+
     unsafe fn create_buffer(
         &self,
         size: u64,
@@ -2873,6 +2975,10 @@ impl hal::device::Device<Backend> for Device {
         Ok(())
     }
 
+    unsafe fn get_buffer_device_address(&self, _buffer: &n::Buffer) -> buffer::DeviceAddress {
+        unimplemented!()
+    }
+
     unsafe fn destroy_buffer(&self, buffer: n::Buffer) {
         if let n::Buffer::Bound { raw, range, .. } = buffer {
             debug!(
@@ -3214,6 +3320,7 @@ impl hal::device::Device<Backend> for Device {
         swizzle: format::Swizzle,
         _usage: image::Usage,
         range: image::SubresourceRange,
+        _ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<n::ImageView, image::ViewCreationError> {
         profiling::scope!("create_image_view");
 
@@ -3380,10 +3487,30 @@ impl hal::device::Device<Backend> for Device {
                 warn!("Timestamp queries are not really useful yet");
                 Ok(n::QueryPool::Timestamp)
             }
-            query::Type::PipelineStatistics(..) => Err(query::CreationError::Unsupported(ty)),
+            query::Type::PipelineStatistics(..) | query::Type::PerformanceCounters => {
+                Err(query::CreationError::Unsupported(ty))
+            }
         }
     }
 
+    unsafe fn create_performance_query_pool(
+        &self,
+        _family: QueueFamilyId,
+        _counters: &[u32],
+        _count: query::Id,
+    ) -> Result<n::QueryPool, query::CreationError> {
+        Err(query::CreationError::Unsupported(
+            query::Type::PerformanceCounters,
+        ))
+    }
+
+    unsafe fn acquire_profiling_lock(&self, _timeout_ns: u64) -> Result<bool, d::OutOfMemory> {
+        // Performance queries aren't supported, so there is no lock to hold.
+        Ok(false)
+    }
+
+    unsafe fn release_profiling_lock(&self) {}
+
     unsafe fn destroy_query_pool(&self, pool: n::QueryPool) {
         match pool {
             n::QueryPool::Occlusion(range) => {
@@ -3464,11 +3591,19 @@ impl hal::device::Device<Backend> for Device {
         Ok(is_ready)
     }
 
-    fn wait_idle(&self) -> Result<(), d::OutOfMemory> {
+    fn get_calibrated_timestamps(&self) -> Result<d::CalibratedTimestamps, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn wait_idle(&self) -> Result<(), d::WaitError> {
         command::QueueInner::wait_idle(&self.shared.queue);
         Ok(())
     }
 
+    unsafe fn get_fault_info(&self) -> Option<d::FaultInfo> {
+        None
+    }
+
     unsafe fn set_image_name(&self, image: &mut n::Image, name: &str) {
         match image {
             n::Image {
@@ -3668,3 +3803,37 @@ fn test_send_sync() {
     fn foo<T: Send + Sync>() {}
     foo::<Device>()
 }
+
+#[test]
+fn test_dynamic_rasterizer_state() {
+    let rasterizer = pso::Rasterizer {
+        cull_face: pso::State::Dynamic,
+        front_face: pso::State::Dynamic,
+        ..pso::Rasterizer::FILL
+    };
+    assert_eq!(
+        conv::map_rasterizer(&rasterizer, false),
+        n::RasterizerState {
+            front_winding: metal::MTLWinding::CounterClockwise,
+            fill_mode: metal::MTLTriangleFillMode::Fill,
+            cull_mode: metal::MTLCullMode::None,
+            depth_clip: None,
+        }
+    );
+
+    let rasterizer = pso::Rasterizer {
+        cull_face: pso::State::Static(pso::Face::BACK),
+        front_face: pso::State::Static(pso::FrontFace::Clockwise),
+        depth_clamping: true,
+        ..pso::Rasterizer::FILL
+    };
+    assert_eq!(
+        conv::map_rasterizer(&rasterizer, true),
+        n::RasterizerState {
+            front_winding: metal::MTLWinding::Clockwise,
+            fill_mode: metal::MTLTriangleFillMode::Fill,
+            cull_mode: metal::MTLCullMode::Back,
+            depth_clip: Some(metal::MTLDepthClipMode::Clamp),
+        }
+    );
+}
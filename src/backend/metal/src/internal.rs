@@ -117,15 +117,12 @@ pub struct DepthStencilStates {
 impl DepthStencilStates {
     fn new(device: &metal::DeviceRef) -> Self {
         let write_none = pso::DepthStencilDesc {
-            depth: None,
+            depth: pso::State::Static(None),
             depth_bounds: false,
             stencil: None,
         };
         let write_depth = pso::DepthStencilDesc {
-            depth: Some(pso::DepthTest {
-                fun: pso::Comparison::Always,
-                write: true,
-            }),
+            depth: pso::State::Static(Some(pso::DepthTest::PASS_WRITE)),
             depth_bounds: false,
             stencil: None,
         };
@@ -136,21 +133,18 @@ impl DepthStencilStates {
             op_pass: pso::StencilOp::Replace,
         };
         let write_stencil = pso::DepthStencilDesc {
-            depth: None,
+            depth: pso::State::Static(None),
             depth_bounds: false,
             stencil: Some(pso::StencilTest {
-                faces: pso::Sided::new(face),
+                faces: pso::State::Static(pso::Sided::new(face)),
                 ..pso::StencilTest::default()
             }),
         };
         let write_all = pso::DepthStencilDesc {
-            depth: Some(pso::DepthTest {
-                fun: pso::Comparison::Always,
-                write: true,
-            }),
+            depth: pso::State::Static(Some(pso::DepthTest::PASS_WRITE)),
             depth_bounds: false,
             stencil: Some(pso::StencilTest {
-                faces: pso::Sided::new(face),
+                faces: pso::State::Static(pso::Sided::new(face)),
                 ..pso::StencilTest::default()
             }),
         };
@@ -230,23 +224,26 @@ impl DepthStencilStates {
                 pso::State::Static(value) => value,
                 pso::State::Dynamic => return None,
             };
+            let faces = stencil.faces.static_or(pso::Sided::default());
             let front_desc =
-                Self::create_stencil(&stencil.faces.front, read_masks.front, write_masks.front);
+                Self::create_stencil(&faces.front, read_masks.front, write_masks.front);
             raw.set_front_face_stencil(Some(&front_desc));
-            let back_desc = if stencil.faces.front == stencil.faces.back
+            let back_desc = if faces.front == faces.back
                 && read_masks.front == read_masks.back
                 && write_masks.front == write_masks.back
             {
                 front_desc
             } else {
-                Self::create_stencil(&stencil.faces.back, read_masks.back, write_masks.back)
+                Self::create_stencil(&faces.back, read_masks.back, write_masks.back)
             };
             raw.set_back_face_stencil(Some(&back_desc));
         }
 
-        if let Some(ref depth) = desc.depth {
-            raw.set_depth_compare_function(conv::map_compare_function(depth.fun));
-            raw.set_depth_write_enabled(depth.write);
+        if let pso::State::Static(Some(ref depth)) = desc.depth {
+            raw.set_depth_compare_function(conv::map_compare_function(
+                depth.fun.static_or(pso::Comparison::Always),
+            ));
+            raw.set_depth_write_enabled(depth.write.static_or(false));
         }
 
         Some(raw)
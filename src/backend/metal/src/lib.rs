@@ -499,6 +499,7 @@ impl hal::Backend for Backend {
     type PipelineCache = native::PipelineCache;
     type PipelineLayout = native::PipelineLayout;
     type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorUpdateTemplate = hal::pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = native::DescriptorPool;
     type DescriptorSet = native::DescriptorSet;
 
@@ -509,6 +510,8 @@ impl hal::Backend for Backend {
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = ();
 }
 
 const RESOURCE_HEAP_SUPPORT: &[MTLFeatureSet] = &[
@@ -3,19 +3,21 @@ use ash::{
     vk,
 };
 use smallvec::SmallVec;
-use std::{collections::hash_map::Entry, ffi::CString, mem, ops::Range, slice, sync::Arc};
+use std::{collections::hash_map::Entry, ffi::CString, mem, ops::Range, ptr, slice, sync::Arc};
 
 use inplace_it::inplace_or_alloc_from_iter;
 
 use crate::{
-    conv, native as n, Backend, DebugMessenger, ExtensionFn, RawDevice, ROUGH_MAX_ATTACHMENT_COUNT,
+    conv, dynamic_rendering, native as n, synchronization2, Backend, DebugMessenger, ExtensionFn,
+    RawDevice, ROUGH_MAX_ATTACHMENT_COUNT,
 };
 use hal::{
-    buffer, command as com,
+    acceleration_structure as accel, buffer, command as com,
+    device::Breadcrumb,
     format::Aspects,
     image::{Filter, Layout, SubresourceRange},
-    memory, pso, query, DrawCount, IndexCount, IndexType, InstanceCount, TaskCount, VertexCount,
-    VertexOffset, WorkGroupCount,
+    memory, pass, pso, query, DrawCount, IndexCount, IndexType, InstanceCount, TaskCount,
+    VertexCount, VertexOffset, WorkGroupCount,
 };
 
 #[derive(Debug)]
@@ -147,7 +149,119 @@ where
     }
 }
 
+struct BarrierSet2 {
+    global: SmallVec<[synchronization2::MemoryBarrier2KHR; 4]>,
+    buffer: SmallVec<[synchronization2::BufferMemoryBarrier2KHR; 4]>,
+    image: SmallVec<[synchronization2::ImageMemoryBarrier2KHR; 4]>,
+}
+
+fn destructure_barriers2<'a, T>(barriers: T) -> BarrierSet2
+where
+    T: Iterator<Item = memory::Barrier2<'a, Backend>>,
+{
+    let mut global: SmallVec<[synchronization2::MemoryBarrier2KHR; 4]> = SmallVec::new();
+    let mut buffer: SmallVec<[synchronization2::BufferMemoryBarrier2KHR; 4]> = SmallVec::new();
+    let mut image: SmallVec<[synchronization2::ImageMemoryBarrier2KHR; 4]> = SmallVec::new();
+
+    for barrier in barriers {
+        match barrier {
+            memory::Barrier2::AllBuffers {
+                ref stages,
+                ref accesses,
+            }
+            | memory::Barrier2::AllImages {
+                ref stages,
+                ref accesses,
+            } => {
+                global.push(synchronization2::MemoryBarrier2KHR {
+                    src_stage_mask: conv::map_pipeline_stage2(stages.start),
+                    src_access_mask: conv::map_access2(accesses.start),
+                    dst_stage_mask: conv::map_pipeline_stage2(stages.end),
+                    dst_access_mask: conv::map_access2(accesses.end),
+                    ..Default::default()
+                });
+            }
+            memory::Barrier2::Buffer {
+                ref stages,
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => {
+                let families = match families {
+                    Some(f) => f.start.0 as u32..f.end.0 as u32,
+                    None => vk::QUEUE_FAMILY_IGNORED..vk::QUEUE_FAMILY_IGNORED,
+                };
+                buffer.push(synchronization2::BufferMemoryBarrier2KHR {
+                    src_stage_mask: conv::map_pipeline_stage2(stages.start),
+                    src_access_mask: conv::map_access2(states.start),
+                    dst_stage_mask: conv::map_pipeline_stage2(stages.end),
+                    dst_access_mask: conv::map_access2(states.end),
+                    src_queue_family_index: families.start,
+                    dst_queue_family_index: families.end,
+                    buffer: target.raw,
+                    offset: range.offset,
+                    size: range.size.unwrap_or(vk::WHOLE_SIZE),
+                    ..Default::default()
+                });
+            }
+            memory::Barrier2::Image {
+                ref stages,
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => {
+                let families = match families {
+                    Some(f) => f.start.0 as u32..f.end.0 as u32,
+                    None => vk::QUEUE_FAMILY_IGNORED..vk::QUEUE_FAMILY_IGNORED,
+                };
+                image.push(synchronization2::ImageMemoryBarrier2KHR {
+                    src_stage_mask: conv::map_pipeline_stage2(stages.start),
+                    src_access_mask: conv::map_access2(states.start.0),
+                    dst_stage_mask: conv::map_pipeline_stage2(stages.end),
+                    dst_access_mask: conv::map_access2(states.end.0),
+                    old_layout: conv::map_image_layout(states.start.1),
+                    new_layout: conv::map_image_layout(states.end.1),
+                    src_queue_family_index: families.start,
+                    dst_queue_family_index: families.end,
+                    image: target.raw,
+                    subresource_range: conv::map_subresource_range(range),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    BarrierSet2 {
+        global,
+        buffer,
+        image,
+    }
+}
+
 impl CommandBuffer {
+    /// Write a breadcrumb, if the device is in fault diagnostics mode.
+    ///
+    /// The value of the breadcrumb is assigned now, at record time.
+    unsafe fn write_breadcrumb(&mut self, breadcrumb: Breadcrumb) {
+        if let Some(ref breadcrumbs) = self.device.breadcrumbs {
+            let value = breadcrumbs.push(breadcrumb);
+            self.device
+                .extension_fns
+                .buffer_marker
+                .as_ref()
+                .unwrap()
+                .cmd_write_buffer_marker_amd(
+                    self.raw,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    breadcrumbs.buffer,
+                    0,
+                    value,
+                );
+        }
+    }
+
     fn bind_descriptor_sets<'a, I, J>(
         &mut self,
         bind_point: vk::PipelineBindPoint,
@@ -174,6 +288,63 @@ impl CommandBuffer {
             });
         });
     }
+
+    fn push_descriptor_set<'a, I>(
+        &mut self,
+        bind_point: vk::PipelineBindPoint,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        let push_descriptor = self
+            .device
+            .extension_fns
+            .push_descriptor
+            .as_ref()
+            .expect("Feature PUSH_DESCRIPTOR must be enabled");
+        unsafe {
+            crate::device::with_raw_descriptor_writes(
+                vk::DescriptorSet::null(),
+                &layout.set_bindings[set],
+                binding,
+                array_offset,
+                descriptors,
+                |raw_writes| {
+                    push_descriptor.cmd_push_descriptor_set(
+                        self.raw, bind_point, layout.raw, set as u32, raw_writes,
+                    )
+                },
+            )
+        }
+    }
+
+    fn extended_dynamic_state(&self) -> &vk::ExtExtendedDynamicStateFn {
+        self.device
+            .extension_fns
+            .extended_dynamic_state
+            .as_ref()
+            .expect("Extended dynamic states are not supported by the device")
+    }
+
+    fn conditional_rendering(&self) -> &vk::ExtConditionalRenderingFn {
+        self.device
+            .extension_fns
+            .conditional_rendering
+            .as_ref()
+            .expect("Conditional rendering is not supported by the device")
+    }
+
+    fn transform_feedback(&self) -> &vk::ExtTransformFeedbackFn {
+        self.device
+            .extension_fns
+            .transform_feedback
+            .as_ref()
+            .expect("Transform feedback is not supported by the device")
+    }
 }
 
 impl com::CommandBuffer<Backend> for CommandBuffer {
@@ -303,6 +474,83 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
 
     unsafe fn end_render_pass(&mut self) {
         self.device.raw.cmd_end_render_pass(self.raw);
+        self.write_breadcrumb(Breadcrumb::EndRenderPass);
+    }
+
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        render_area: pso::Rect,
+        color_attachments: T,
+        depth_stencil_attachment: Option<com::RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = com::RenderingAttachmentInfo<'a, Backend>>,
+    {
+        let map_attachment = |info: &com::RenderingAttachmentInfo<'a, Backend>,
+                              ops: pass::AttachmentOps| {
+            dynamic_rendering::RenderingAttachmentInfoKHR {
+                image_view: info.image_view.raw,
+                image_layout: conv::map_image_layout(info.layout),
+                load_op: conv::map_attachment_load_op(ops.load),
+                store_op: conv::map_attachment_store_op(ops.store),
+                clear_value: mem::transmute(info.clear_value),
+                ..Default::default()
+            }
+        };
+
+        // Render to as many layers as all the attachment views have.
+        let mut layer_count = !0;
+        let mut count_layers = |info: &com::RenderingAttachmentInfo<'a, Backend>| {
+            layer_count = layer_count.min(info.image_view.range.layer_count.unwrap_or(1));
+        };
+
+        let colors = color_attachments
+            .map(|info| {
+                count_layers(&info);
+                map_attachment(&info, info.ops)
+            })
+            .collect::<SmallVec<[_; ROUGH_MAX_ATTACHMENT_COUNT]>>();
+        if let Some(ref info) = depth_stencil_attachment {
+            count_layers(info);
+        }
+        let depth = depth_stencil_attachment
+            .as_ref()
+            .filter(|info| info.image_view.range.aspects.contains(Aspects::DEPTH))
+            .map(|info| map_attachment(info, info.ops));
+        let stencil = depth_stencil_attachment
+            .as_ref()
+            .filter(|info| info.image_view.range.aspects.contains(Aspects::STENCIL))
+            .map(|info| map_attachment(info, info.stencil_ops));
+
+        let info = dynamic_rendering::RenderingInfoKHR {
+            render_area: conv::map_rect(&render_area),
+            layer_count: if layer_count == !0 {
+                1
+            } else {
+                layer_count as u32
+            },
+            color_attachment_count: colors.len() as u32,
+            p_color_attachments: colors.as_ptr(),
+            p_depth_attachment: depth.as_ref().map_or(ptr::null(), |at| at as *const _),
+            p_stencil_attachment: stencil.as_ref().map_or(ptr::null(), |at| at as *const _),
+            ..Default::default()
+        };
+
+        self.device
+            .extension_fns
+            .dynamic_rendering
+            .as_ref()
+            .expect("Feature DYNAMIC_RENDERING must be enabled to call begin_rendering")
+            .cmd_begin_rendering_khr(self.raw, &info);
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        self.device
+            .extension_fns
+            .dynamic_rendering
+            .as_ref()
+            .expect("Feature DYNAMIC_RENDERING must be enabled to call end_rendering")
+            .cmd_end_rendering_khr(self.raw);
+        self.write_breadcrumb(Breadcrumb::EndRenderPass);
     }
 
     unsafe fn pipeline_barrier<'a, T>(
@@ -330,6 +578,39 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn pipeline_barrier2<'a, T>(&mut self, dependencies: memory::Dependencies, barriers: T)
+    where
+        T: Iterator<Item = memory::Barrier2<'a, Backend>>,
+    {
+        let extension = match self.device.extension_fns.synchronization2 {
+            Some(ref extension) => extension,
+            None => {
+                for (stages, barriers) in memory::lower_barriers(barriers) {
+                    self.pipeline_barrier(stages, dependencies, barriers.into_iter());
+                }
+                return;
+            }
+        };
+
+        let BarrierSet2 {
+            global,
+            buffer,
+            image,
+        } = destructure_barriers2(barriers);
+
+        let info = synchronization2::DependencyInfoKHR {
+            dependency_flags: mem::transmute(dependencies),
+            memory_barrier_count: global.len() as u32,
+            p_memory_barriers: global.as_ptr(),
+            buffer_memory_barrier_count: buffer.len() as u32,
+            p_buffer_memory_barriers: buffer.as_ptr(),
+            image_memory_barrier_count: image.len() as u32,
+            p_image_memory_barriers: image.as_ptr(),
+            ..Default::default()
+        };
+        extension.cmd_pipeline_barrier2_khr(self.raw, &info);
+    }
+
     unsafe fn fill_buffer(&mut self, buffer: &n::Buffer, range: buffer::SubRange, data: u32) {
         self.device.raw.cmd_fill_buffer(
             self.raw,
@@ -541,6 +822,35 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
             .cmd_bind_vertex_buffers(self.raw, first_binding, &buffers, &offsets);
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        first_binding: pso::BufferIndex,
+        buffers: T,
+    ) where
+        T: Iterator<Item = (&'a n::Buffer, buffer::SubRange, pso::ElemStride)>,
+    {
+        let mut raw_buffers = SmallVec::<[vk::Buffer; 16]>::new();
+        let mut offsets = SmallVec::<[vk::DeviceSize; 16]>::new();
+        let mut sizes = SmallVec::<[vk::DeviceSize; 16]>::new();
+        let mut strides = SmallVec::<[vk::DeviceSize; 16]>::new();
+        for (buffer, sub, stride) in buffers {
+            raw_buffers.push(buffer.raw);
+            offsets.push(sub.offset);
+            sizes.push(sub.size.unwrap_or(vk::WHOLE_SIZE));
+            strides.push(stride as vk::DeviceSize);
+        }
+
+        self.extended_dynamic_state().cmd_bind_vertex_buffers2_ext(
+            self.raw,
+            first_binding,
+            raw_buffers.len() as u32,
+            raw_buffers.as_ptr(),
+            offsets.as_ptr(),
+            sizes.as_ptr(),
+            strides.as_ptr(),
+        );
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
     where
         T: Iterator<Item = pso::Viewport>,
@@ -611,6 +921,48 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn set_cull_mode(&mut self, faces: pso::Face) {
+        self.extended_dynamic_state()
+            .cmd_set_cull_mode_ext(self.raw, conv::map_cull_face(faces));
+    }
+
+    unsafe fn set_front_face(&mut self, front_face: pso::FrontFace) {
+        self.extended_dynamic_state()
+            .cmd_set_front_face_ext(self.raw, conv::map_front_face(front_face));
+    }
+
+    unsafe fn set_primitive_topology(&mut self, primitive: pso::Primitive) {
+        let topology = conv::map_topology(&pso::InputAssemblerDesc::new(primitive));
+        self.extended_dynamic_state()
+            .cmd_set_primitive_topology_ext(self.raw, topology);
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, enable: bool) {
+        self.extended_dynamic_state()
+            .cmd_set_depth_test_enable_ext(self.raw, enable as vk::Bool32);
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, enable: bool) {
+        self.extended_dynamic_state()
+            .cmd_set_depth_write_enable_ext(self.raw, enable as vk::Bool32);
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, fun: pso::Comparison) {
+        self.extended_dynamic_state()
+            .cmd_set_depth_compare_op_ext(self.raw, conv::map_comparison(fun));
+    }
+
+    unsafe fn set_stencil_op(&mut self, faces: pso::Face, ops: pso::StencilFace) {
+        self.extended_dynamic_state().cmd_set_stencil_op_ext(
+            self.raw,
+            mem::transmute(faces),
+            conv::map_stencil_op(ops.op_fail),
+            conv::map_stencil_op(ops.op_pass),
+            conv::map_stencil_op(ops.op_depth_fail),
+            conv::map_comparison(ops.fun),
+        );
+    }
+
     unsafe fn bind_graphics_pipeline(&mut self, pipeline: &n::GraphicsPipeline) {
         self.device
             .raw
@@ -661,6 +1013,46 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(
+            vk::PipelineBindPoint::GRAPHICS,
+            layout,
+            set,
+            binding,
+            array_offset,
+            descriptors,
+        );
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(
+            vk::PipelineBindPoint::COMPUTE,
+            layout,
+            set,
+            binding,
+            array_offset,
+            descriptors,
+        );
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.device
             .raw
@@ -764,6 +1156,93 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         });
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a accel::BuildDesc<'a, Backend>,
+                &'a [accel::BuildRangeDesc],
+            ),
+        >,
+    {
+        //TODO: avoid heap allocations
+        let descs = descs.collect::<Vec<_>>();
+        let geometries = descs
+            .iter()
+            .map(|&(desc, _)| {
+                desc.geometry
+                    .geometries
+                    .iter()
+                    .map(|geometry| self.device.map_acceleration_structure_geometry(geometry))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let ranges = descs
+            .iter()
+            .map(|&(desc, ranges)| {
+                assert_eq!(desc.geometry.geometries.len(), ranges.len());
+                ranges
+                    .iter()
+                    .map(conv::map_acceleration_structure_build_range)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let infos = descs
+            .iter()
+            .zip(&geometries)
+            .map(|(&(desc, _), geometries)| {
+                let info = vk::AccelerationStructureBuildGeometryInfoKHR::builder()
+                    .ty(conv::map_acceleration_structure_level(desc.geometry.level))
+                    .flags(conv::map_acceleration_structure_build_flags(
+                        desc.geometry.flags,
+                    ))
+                    .dst_acceleration_structure(desc.dst.0)
+                    .geometries(geometries)
+                    .scratch_data(vk::DeviceOrHostAddressKHR {
+                        device_address: self
+                            .device
+                            .buffer_device_address(desc.scratch, desc.scratch_offset),
+                    });
+                match desc.src {
+                    Some(src) => info
+                        .mode(vk::BuildAccelerationStructureModeKHR::UPDATE)
+                        .src_acceleration_structure(src.0),
+                    None => info.mode(vk::BuildAccelerationStructureModeKHR::BUILD),
+                }
+                .build()
+            })
+            .collect::<Vec<_>>();
+        let range_ptrs = ranges
+            .iter()
+            .map(|ranges| ranges.as_ptr())
+            .collect::<Vec<_>>();
+
+        self.device
+            .acceleration_structure_fn()
+            .cmd_build_acceleration_structures_khr(
+                self.raw,
+                infos.len() as u32,
+                infos.as_ptr(),
+                range_ptrs.as_ptr(),
+            );
+    }
+
+    unsafe fn copy_acceleration_structure(
+        &mut self,
+        src: &n::AccelerationStructure,
+        dst: &n::AccelerationStructure,
+    ) {
+        let info = vk::CopyAccelerationStructureInfoKHR::builder()
+            .src(src.0)
+            .dst(dst.0)
+            .mode(vk::CopyAccelerationStructureModeKHR::CLONE);
+
+        self.device
+            .acceleration_structure_fn()
+            .cmd_copy_acceleration_structure_khr(self.raw, &*info);
+    }
+
     unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
         self.device.raw.cmd_draw(
             self.raw,
@@ -1048,6 +1527,73 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         )
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        let info = vk::ConditionalRenderingBeginInfoEXT::builder()
+            .buffer(buffer.raw)
+            .offset(offset)
+            .flags(if inverted {
+                vk::ConditionalRenderingFlagsEXT::INVERTED
+            } else {
+                vk::ConditionalRenderingFlagsEXT::empty()
+            });
+        self.conditional_rendering()
+            .cmd_begin_conditional_rendering_ext(self.raw, &*info);
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.conditional_rendering()
+            .cmd_end_conditional_rendering_ext(self.raw);
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, first_binding: u32, buffers: T)
+    where
+        T: Iterator<Item = (&'a n::Buffer, buffer::SubRange)>,
+    {
+        let mut raw_buffers = SmallVec::<[vk::Buffer; 4]>::new();
+        let mut offsets = SmallVec::<[vk::DeviceSize; 4]>::new();
+        let mut sizes = SmallVec::<[vk::DeviceSize; 4]>::new();
+        for (buffer, sub) in buffers {
+            raw_buffers.push(buffer.raw);
+            offsets.push(sub.offset);
+            sizes.push(sub.size.unwrap_or(vk::WHOLE_SIZE));
+        }
+
+        self.transform_feedback()
+            .cmd_bind_transform_feedback_buffers_ext(
+                self.raw,
+                first_binding,
+                raw_buffers.len() as u32,
+                raw_buffers.as_ptr(),
+                offsets.as_ptr(),
+                sizes.as_ptr(),
+            );
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        self.transform_feedback().cmd_begin_transform_feedback_ext(
+            self.raw,
+            0,
+            0,
+            ptr::null(),
+            ptr::null(),
+        );
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.transform_feedback().cmd_end_transform_feedback_ext(
+            self.raw,
+            0,
+            0,
+            ptr::null(),
+            ptr::null(),
+        );
+    }
+
     unsafe fn push_compute_constants(
         &mut self,
         layout: &n::PipelineLayout,
@@ -1101,6 +1647,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
                 .build();
             ext.cmd_insert_debug_utils_label(self.raw, &label);
         }
+        self.write_breadcrumb(Breadcrumb::DebugMarker(name.to_owned()));
     }
     unsafe fn begin_debug_marker(&mut self, name: &str, color: u32) {
         if let Some(&DebugMessenger::Utils(ref ext, _)) = self.device.debug_messenger() {
@@ -1111,6 +1658,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
                 .build();
             ext.cmd_begin_debug_utils_label(self.raw, &label);
         }
+        self.write_breadcrumb(Breadcrumb::DebugMarker(name.to_owned()));
     }
     unsafe fn end_debug_marker(&mut self) {
         if let Some(&DebugMessenger::Utils(ref ext, _)) = self.device.debug_messenger() {
@@ -1,9 +1,9 @@
-use crate::native as n;
+use crate::{native as n, synchronization2};
 
 use ash::vk;
 
 use hal::{
-    buffer, command, format, image, memory,
+    acceleration_structure as accel, buffer, command, format, image, memory,
     memory::Segment,
     pass, pso, query,
     window::{CompositeAlphaMode, PresentMode},
@@ -12,13 +12,29 @@ use hal::{
 
 use std::mem;
 
+/// First HAL format of the `VK_KHR_sampler_ycbcr_conversion` range.
+const FIRST_YCBCR_FORMAT: format::Format = format::Format::G8b8g8r8_422Unorm;
+
 pub fn map_format(format: format::Format) -> vk::Format {
-    vk::Format::from_raw(format as i32)
+    if format >= FIRST_YCBCR_FORMAT {
+        let offset = format as i32 - FIRST_YCBCR_FORMAT as i32;
+        vk::Format::from_raw(vk::Format::G8B8G8R8_422_UNORM.as_raw() + offset)
+    } else {
+        vk::Format::from_raw(format as i32)
+    }
 }
 
 pub fn map_vk_format(vk_format: vk::Format) -> Option<format::Format> {
-    if (vk_format.as_raw() as usize) < format::NUM_FORMATS && vk_format != vk::Format::UNDEFINED {
-        Some(unsafe { mem::transmute(vk_format) })
+    let raw = if vk_format >= vk::Format::G8B8G8R8_422_UNORM {
+        let offset = vk_format.as_raw() - vk::Format::G8B8G8R8_422_UNORM.as_raw();
+        FIRST_YCBCR_FORMAT as i32 + offset
+    } else if vk_format.as_raw() < FIRST_YCBCR_FORMAT as i32 {
+        vk_format.as_raw()
+    } else {
+        return None;
+    };
+    if (raw as usize) < format::NUM_FORMATS && vk_format != vk::Format::UNDEFINED {
+        Some(unsafe { mem::transmute(raw as u32) })
     } else {
         None
     }
@@ -152,10 +168,22 @@ pub fn map_pipeline_stage(stage: pso::PipelineStage) -> vk::PipelineStageFlags {
     vk::PipelineStageFlags::from_raw(stage.bits())
 }
 
+pub fn map_access2(access: memory::Access2) -> synchronization2::AccessFlags2KHR {
+    access.bits()
+}
+
+pub fn map_pipeline_stage2(stage: pso::PipelineStage2) -> synchronization2::PipelineStageFlags2KHR {
+    stage.bits()
+}
+
 pub fn map_buffer_usage(usage: buffer::Usage) -> vk::BufferUsageFlags {
     vk::BufferUsageFlags::from_raw(usage.bits())
 }
 
+pub fn map_memory_allocation_flags(flags: memory::AllocationFlags) -> vk::MemoryAllocateFlags {
+    vk::MemoryAllocateFlags::from_raw(flags.bits())
+}
+
 pub fn map_buffer_create_flags(sparse: memory::SparseFlags) -> vk::BufferCreateFlags {
     vk::BufferCreateFlags::from_raw(sparse.bits())
 }
@@ -199,6 +227,9 @@ pub fn map_descriptor_type(ty: pso::DescriptorType) -> vk::DescriptorType {
             },
         },
         pso::DescriptorType::InputAttachment => vk::DescriptorType::INPUT_ATTACHMENT,
+        pso::DescriptorType::AccelerationStructure => {
+            vk::DescriptorType::ACCELERATION_STRUCTURE_KHR
+        }
     }
 }
 
@@ -234,6 +265,31 @@ pub fn map_reduction(reduction: image::ReductionMode) -> vk::SamplerReductionMod
     }
 }
 
+pub fn map_ycbcr_model(model: image::YcbcrModel) -> vk::SamplerYcbcrModelConversion {
+    use hal::image::YcbcrModel as Ym;
+    match model {
+        Ym::RgbIdentity => vk::SamplerYcbcrModelConversion::RGB_IDENTITY,
+        Ym::YcbcrIdentity => vk::SamplerYcbcrModelConversion::YCBCR_IDENTITY,
+        Ym::Ycbcr709 => vk::SamplerYcbcrModelConversion::YCBCR_709,
+        Ym::Ycbcr601 => vk::SamplerYcbcrModelConversion::YCBCR_601,
+        Ym::Ycbcr2020 => vk::SamplerYcbcrModelConversion::YCBCR_2020,
+    }
+}
+
+pub fn map_ycbcr_range(range: image::YcbcrRange) -> vk::SamplerYcbcrRange {
+    match range {
+        image::YcbcrRange::ItuFull => vk::SamplerYcbcrRange::ITU_FULL,
+        image::YcbcrRange::ItuNarrow => vk::SamplerYcbcrRange::ITU_NARROW,
+    }
+}
+
+pub fn map_chroma_location(location: image::ChromaLocation) -> vk::ChromaLocation {
+    match location {
+        image::ChromaLocation::CositedEven => vk::ChromaLocation::COSITED_EVEN,
+        image::ChromaLocation::Midpoint => vk::ChromaLocation::MIDPOINT,
+    }
+}
+
 pub fn map_border_color(border_color: image::BorderColor) -> vk::BorderColor {
     match border_color {
         image::BorderColor::TransparentBlack => vk::BorderColor::FLOAT_TRANSPARENT_BLACK,
@@ -388,6 +444,47 @@ pub fn map_query_result_flags(flags: query::ResultFlags) -> vk::QueryResultFlags
     vk::QueryResultFlags::from_raw(flags.bits() & vk::QueryResultFlags::all().as_raw())
 }
 
+pub fn map_vk_performance_counter_unit(
+    unit: vk::PerformanceCounterUnitKHR,
+) -> query::PerformanceCounterUnit {
+    use hal::query::PerformanceCounterUnit as Pcu;
+    match unit {
+        vk::PerformanceCounterUnitKHR::GENERIC => Pcu::Generic,
+        vk::PerformanceCounterUnitKHR::PERCENTAGE => Pcu::Percentage,
+        vk::PerformanceCounterUnitKHR::NANOSECONDS => Pcu::Nanoseconds,
+        vk::PerformanceCounterUnitKHR::BYTES => Pcu::Bytes,
+        vk::PerformanceCounterUnitKHR::BYTES_PER_SECOND => Pcu::BytesPerSecond,
+        vk::PerformanceCounterUnitKHR::KELVIN => Pcu::Kelvin,
+        vk::PerformanceCounterUnitKHR::WATTS => Pcu::Watts,
+        vk::PerformanceCounterUnitKHR::VOLTS => Pcu::Volts,
+        vk::PerformanceCounterUnitKHR::AMPS => Pcu::Amps,
+        vk::PerformanceCounterUnitKHR::HERTZ => Pcu::Hertz,
+        vk::PerformanceCounterUnitKHR::CYCLES => Pcu::Cycles,
+        _ => {
+            warn!("Unrecognized performance counter unit {:?}", unit);
+            Pcu::Generic
+        }
+    }
+}
+
+pub fn map_vk_performance_counter_storage(
+    storage: vk::PerformanceCounterStorageKHR,
+) -> query::PerformanceCounterStorage {
+    use hal::query::PerformanceCounterStorage as Pcs;
+    match storage {
+        vk::PerformanceCounterStorageKHR::INT32 => Pcs::Int32,
+        vk::PerformanceCounterStorageKHR::INT64 => Pcs::Int64,
+        vk::PerformanceCounterStorageKHR::UINT32 => Pcs::Uint32,
+        vk::PerformanceCounterStorageKHR::UINT64 => Pcs::Uint64,
+        vk::PerformanceCounterStorageKHR::FLOAT32 => Pcs::Float32,
+        vk::PerformanceCounterStorageKHR::FLOAT64 => Pcs::Float64,
+        _ => {
+            warn!("Unrecognized performance counter storage {:?}", storage);
+            Pcs::Uint64
+        }
+    }
+}
+
 pub fn map_image_features(
     features: vk::FormatFeatureFlags,
     supports_transfer_bits: bool,
@@ -445,6 +542,13 @@ pub fn map_image_features(
         }
     }
 
+    if features.contains(vk::FormatFeatureFlags::MIDPOINT_CHROMA_SAMPLES) {
+        mapped_flags |= format::ImageFeature::MIDPOINT_CHROMA_SAMPLES;
+    }
+    if features.contains(vk::FormatFeatureFlags::COSITED_CHROMA_SAMPLES) {
+        mapped_flags |= format::ImageFeature::COSITED_CHROMA_SAMPLES;
+    }
+
     mapped_flags
 }
 
@@ -586,6 +690,12 @@ pub fn map_descriptor_pool_create_flags(
     vk::DescriptorPoolCreateFlags::from_raw(flags.bits())
 }
 
+pub fn map_descriptor_set_layout_create_flags(
+    flags: pso::DescriptorSetLayoutCreateFlags,
+) -> vk::DescriptorSetLayoutCreateFlags {
+    vk::DescriptorSetLayoutCreateFlags::from_raw(flags.bits())
+}
+
 pub fn map_sample_count_flags(samples: image::NumSamples) -> vk::SampleCountFlags {
     vk::SampleCountFlags::from_raw((samples as u32) & vk::SampleCountFlags::all().as_raw())
 }
@@ -623,3 +733,47 @@ pub fn map_vk_memory_heap_flags(flags: vk::MemoryHeapFlags) -> hal::memory::Heap
 
     hal_flags
 }
+
+pub fn map_vk_subgroup_properties(
+    properties: &vk::PhysicalDeviceSubgroupProperties,
+) -> hal::SubgroupProperties {
+    // Both the stage and the operation bits match the Vulkan ones
+    hal::SubgroupProperties {
+        subgroup_size: properties.subgroup_size,
+        supported_stages: pso::ShaderStageFlags::from_bits_truncate(
+            properties.supported_stages.as_raw(),
+        ),
+        supported_operations: hal::SubgroupOperations::from_bits_truncate(
+            properties.supported_operations.as_raw(),
+        ),
+        quad_operations_in_all_stages: properties.quad_operations_in_all_stages == vk::TRUE,
+    }
+}
+
+pub fn map_acceleration_structure_level(level: accel::Level) -> vk::AccelerationStructureTypeKHR {
+    match level {
+        accel::Level::Top => vk::AccelerationStructureTypeKHR::TOP_LEVEL,
+        accel::Level::Bottom => vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+    }
+}
+
+pub fn map_acceleration_structure_build_flags(
+    flags: accel::BuildFlags,
+) -> vk::BuildAccelerationStructureFlagsKHR {
+    vk::BuildAccelerationStructureFlagsKHR::from_raw(flags.bits())
+}
+
+pub fn map_acceleration_structure_build_range(
+    range: &accel::BuildRangeDesc,
+) -> vk::AccelerationStructureBuildRangeInfoKHR {
+    vk::AccelerationStructureBuildRangeInfoKHR {
+        primitive_count: range.primitive_count,
+        primitive_offset: range.primitive_offset,
+        first_vertex: range.first_vertex,
+        transform_offset: range.transform_offset,
+    }
+}
+
+pub fn map_geometry_flags(flags: accel::GeometryFlags) -> vk::GeometryFlagsKHR {
+    vk::GeometryFlagsKHR::from_raw(flags.bits())
+}
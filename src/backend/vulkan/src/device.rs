@@ -1,10 +1,14 @@
 use arrayvec::ArrayVec;
-use ash::{extensions::khr, version::DeviceV1_0, vk};
+use ash::{
+    extensions::khr,
+    version::{DeviceV1_0, DeviceV1_1, DeviceV1_2},
+    vk,
+};
 use inplace_it::inplace_or_alloc_from_iter;
 use smallvec::SmallVec;
 
 use hal::{
-    memory,
+    acceleration_structure as accel, adapter, memory,
     memory::{Requirements, Segment},
     pool::CommandPoolCreateFlags,
     pso::VertexInputRate,
@@ -14,13 +18,18 @@ use hal::{
 
 use std::{ffi::CString, marker::PhantomData, mem, ops::Range, ptr, sync::Arc};
 
-use crate::{command as cmd, conv, native as n, pool::RawCommandPool, window as w, Backend as B};
+use crate::{
+    command as cmd, conv, device_fault, dynamic_rendering, native as n, pool::RawCommandPool,
+    window as w, Backend as B, ExtensionFn, HOST_TIME_DOMAIN,
+};
 use ash::vk::Handle;
+#[cfg(windows)]
+use auxil::host_ticks_to_ns;
 
 #[derive(Debug, Default)]
 struct GraphicsPipelineInfoBuf<'a> {
     // 10 is the max amount of dynamic states
-    dynamic_states: ArrayVec<[vk::DynamicState; 10]>,
+    dynamic_states: ArrayVec<[vk::DynamicState; 17]>,
 
     // 5 is the amount of stages
     c_strings: ArrayVec<[CString; 5]>,
@@ -46,6 +55,9 @@ struct GraphicsPipelineInfoBuf<'a> {
     viewports: [vk::Viewport; 1],
     scissors: [vk::Rect2D; 1],
 
+    color_formats: Vec<vk::Format>,
+    rendering_info: dynamic_rendering::PipelineRenderingCreateInfoKHR,
+
     lifetime: PhantomData<&'a vk::Pipeline>,
 }
 impl<'a> GraphicsPipelineInfoBuf<'a> {
@@ -111,10 +123,14 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
                     this.add_stage(vk::ShaderStageFlags::TESSELLATION_CONTROL, &ts.0);
                     this.add_stage(vk::ShaderStageFlags::TESSELLATION_EVALUATION, &ts.1);
                 }
+                if buffers.iter().any(|vbuf| vbuf.stride.is_dynamic()) {
+                    this.dynamic_states
+                        .push(vk::DynamicState::VERTEX_INPUT_BINDING_STRIDE_EXT);
+                }
                 this.vertex_bindings = buffers.iter().map(|vbuf| {
                     vk::VertexInputBindingDescription {
                         binding: vbuf.binding,
-                        stride: vbuf.stride as u32,
+                        stride: vbuf.stride.static_or(0) as u32,
                         input_rate: match vbuf.rate {
                             VertexInputRate::Vertex => vk::VertexInputRate::VERTEX,
                             VertexInputRate::Instance(divisor) => {
@@ -141,6 +157,10 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
                     .vertex_attribute_descriptions(&this.vertex_attributes)
                     .build();
 
+                if input_assembler.dynamic_primitive {
+                    this.dynamic_states
+                        .push(vk::DynamicState::PRIMITIVE_TOPOLOGY_EXT);
+                }
                 this.input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
                     .flags(vk::PipelineInputAssemblyStateCreateFlags::empty())
                     .topology(conv::map_topology(&input_assembler))
@@ -191,6 +211,22 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
             }
         };
 
+        let cull_mode = match desc.rasterizer.cull_face {
+            pso::State::Static(cf) => conv::map_cull_face(cf),
+            pso::State::Dynamic => {
+                this.dynamic_states.push(vk::DynamicState::CULL_MODE_EXT);
+                vk::CullModeFlags::NONE
+            }
+        };
+
+        let front_face = match desc.rasterizer.front_face {
+            pso::State::Static(ff) => conv::map_front_face(ff),
+            pso::State::Dynamic => {
+                this.dynamic_states.push(vk::DynamicState::FRONT_FACE_EXT);
+                vk::FrontFace::COUNTER_CLOCKWISE
+            }
+        };
+
         this.rasterization_conservative_state =
             vk::PipelineRasterizationConservativeStateCreateInfoEXT::builder()
                 .conservative_rasterization_mode(match desc.rasterizer.conservative {
@@ -215,12 +251,12 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
                     })
                     .rasterizer_discard_enable(
                         desc.fragment.is_none()
-                            && desc.depth_stencil.depth.is_none()
+                            && desc.depth_stencil.depth == pso::State::Static(None)
                             && desc.depth_stencil.stencil.is_none(),
                     )
                     .polygon_mode(polygon_mode)
-                    .cull_mode(conv::map_cull_face(desc.rasterizer.cull_face))
-                    .front_face(conv::map_front_face(desc.rasterizer.front_face))
+                    .cull_mode(cull_mode)
+                    .front_face(front_face)
                     .depth_bias_enable(desc.rasterizer.depth_bias.is_some())
                     .depth_bias_constant_factor(depth_bias.const_factor)
                     .depth_bias_clamp(depth_bias.clamp)
@@ -305,13 +341,48 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
 
         let depth_stencil = desc.depth_stencil;
         let (depth_test_enable, depth_write_enable, depth_compare_op) = match depth_stencil.depth {
-            Some(ref depth) => (true, depth.write as _, conv::map_comparison(depth.fun)),
-            None => (false, false, vk::CompareOp::NEVER),
+            pso::State::Static(Some(ref depth)) => {
+                let depth_write_enable = match depth.write {
+                    pso::State::Static(write) => write,
+                    pso::State::Dynamic => {
+                        this.dynamic_states
+                            .push(vk::DynamicState::DEPTH_WRITE_ENABLE_EXT);
+                        false
+                    }
+                };
+                let depth_compare_op = match depth.fun {
+                    pso::State::Static(fun) => conv::map_comparison(fun),
+                    pso::State::Dynamic => {
+                        this.dynamic_states
+                            .push(vk::DynamicState::DEPTH_COMPARE_OP_EXT);
+                        vk::CompareOp::NEVER
+                    }
+                };
+                (true, depth_write_enable, depth_compare_op)
+            }
+            pso::State::Static(None) => (false, false, vk::CompareOp::NEVER),
+            pso::State::Dynamic => {
+                this.dynamic_states
+                    .push(vk::DynamicState::DEPTH_TEST_ENABLE_EXT);
+                this.dynamic_states
+                    .push(vk::DynamicState::DEPTH_WRITE_ENABLE_EXT);
+                this.dynamic_states
+                    .push(vk::DynamicState::DEPTH_COMPARE_OP_EXT);
+                (false, false, vk::CompareOp::NEVER)
+            }
         };
         let (stencil_test_enable, front, back) = match depth_stencil.stencil {
             Some(ref stencil) => {
-                let mut front = conv::map_stencil_side(&stencil.faces.front);
-                let mut back = conv::map_stencil_side(&stencil.faces.back);
+                let (mut front, mut back) = match stencil.faces {
+                    pso::State::Static(ref faces) => (
+                        conv::map_stencil_side(&faces.front),
+                        conv::map_stencil_side(&faces.back),
+                    ),
+                    pso::State::Dynamic => {
+                        this.dynamic_states.push(vk::DynamicState::STENCIL_OP_EXT);
+                        mem::zeroed()
+                    }
+                };
                 match stencil.read_masks {
                     pso::State::Static(ref sides) => {
                         front.compare_mask = sides.front;
@@ -418,6 +489,24 @@ impl<'a> GraphicsPipelineInfoBuf<'a> {
             .dynamic_states(&this.dynamic_states)
             .build();
 
+        if let pass::PipelineTarget::Rendering(ref formats) = desc.target {
+            this.color_formats = formats
+                .colors
+                .iter()
+                .map(|&format| conv::map_format(format))
+                .collect();
+            this.rendering_info.color_attachment_count = this.color_formats.len() as u32;
+            this.rendering_info.p_color_attachment_formats = this.color_formats.as_ptr();
+            if let Some(format) = formats.depth_stencil {
+                if format.is_depth() {
+                    this.rendering_info.depth_attachment_format = conv::map_format(format);
+                }
+                if format.is_stencil() {
+                    this.rendering_info.stencil_attachment_format = conv::map_format(format);
+                }
+            }
+        }
+
         this
     }
 }
@@ -454,15 +543,255 @@ impl<'a> ComputePipelineInfoBuf<'a> {
     }
 }
 
+/// Converts the descriptors written from `binding` and `array_offset` on into raw writes
+/// to `set`, and calls `f` with them while the storage they point to is alive.
+pub(crate) unsafe fn with_raw_descriptor_writes<'a, I, R>(
+    set: vk::DescriptorSet,
+    bindings: &[pso::DescriptorSetLayoutBinding],
+    binding: pso::DescriptorBinding,
+    array_offset: pso::DescriptorArrayIndex,
+    descriptors: I,
+    f: impl FnOnce(&[vk::WriteDescriptorSet]) -> R,
+) -> R
+where
+    I: Iterator<Item = pso::Descriptor<'a, B>>,
+{
+    let mut raw_writes = Vec::<vk::WriteDescriptorSet>::with_capacity(descriptors.size_hint().0);
+    let mut image_infos = Vec::new();
+    let mut buffer_infos = Vec::new();
+    let mut texel_buffer_views = Vec::new();
+    let mut acceleration_structures = Vec::new();
+
+    // gfx-hal allows the type and stages to be different between the descriptor
+    // in a single write, while Vulkan requires them to be the same.
+    let mut last_type = vk::DescriptorType::SAMPLER;
+    let mut last_stages = pso::ShaderStageFlags::empty();
+
+    let mut binding_pos = bindings
+        .binary_search_by_key(&binding, |b| b.binding)
+        .expect("Descriptor set writes don't match the set layout!");
+    let first_array_offset = array_offset;
+    let mut array_offset = array_offset;
+
+    for descriptor in descriptors {
+        let layout_binding = &bindings[binding_pos];
+        array_offset += 1;
+        if array_offset == layout_binding.count {
+            array_offset = 0;
+            binding_pos += 1;
+        }
+
+        let descriptor_type = conv::map_descriptor_type(layout_binding.ty);
+        if descriptor_type == last_type && layout_binding.stage_flags == last_stages {
+            raw_writes.last_mut().unwrap().descriptor_count += 1;
+        } else {
+            last_type = descriptor_type;
+            last_stages = layout_binding.stage_flags;
+            raw_writes.push(vk::WriteDescriptorSet {
+                s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                p_next: if descriptor_type == vk::DescriptorType::ACCELERATION_STRUCTURE_KHR {
+                    acceleration_structures.len() as _
+                } else {
+                    ptr::null()
+                },
+                dst_set: set,
+                dst_binding: layout_binding.binding,
+                dst_array_element: if layout_binding.binding == binding {
+                    first_array_offset as _
+                } else {
+                    0
+                },
+                descriptor_count: 1,
+                descriptor_type,
+                p_image_info: image_infos.len() as _,
+                p_buffer_info: buffer_infos.len() as _,
+                p_texel_buffer_view: texel_buffer_views.len() as _,
+            });
+        }
+
+        match descriptor {
+            pso::Descriptor::Sampler(sampler) => {
+                image_infos.push(
+                    vk::DescriptorImageInfo::builder()
+                        .sampler(sampler.raw)
+                        .image_view(vk::ImageView::null())
+                        .image_layout(vk::ImageLayout::GENERAL)
+                        .build(),
+                );
+            }
+            pso::Descriptor::Image(view, layout) => {
+                image_infos.push(
+                    vk::DescriptorImageInfo::builder()
+                        .sampler(vk::Sampler::null())
+                        .image_view(view.raw)
+                        .image_layout(conv::map_image_layout(layout))
+                        .build(),
+                );
+            }
+            pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+                image_infos.push(
+                    vk::DescriptorImageInfo::builder()
+                        .sampler(sampler.raw)
+                        .image_view(view.raw)
+                        .image_layout(conv::map_image_layout(layout))
+                        .build(),
+                );
+            }
+            pso::Descriptor::Buffer(buffer, ref sub) => {
+                buffer_infos.push(
+                    vk::DescriptorBufferInfo::builder()
+                        .buffer(buffer.raw)
+                        .offset(sub.offset)
+                        .range(sub.size.unwrap_or(vk::WHOLE_SIZE))
+                        .build(),
+                );
+            }
+            pso::Descriptor::TexelBuffer(view) => {
+                texel_buffer_views.push(view.raw);
+            }
+            pso::Descriptor::AccelerationStructure(accel_struct) => {
+                acceleration_structures.push(accel_struct.0);
+            }
+        }
+    }
+
+    // Reserved upfront, so that pushing doesn't move the structures the writes point to.
+    let mut acceleration_structure_writes = Vec::with_capacity(raw_writes.len());
+
+    // Patch the pointers now that we have all the storage allocated.
+    for raw in raw_writes.iter_mut() {
+        use crate::vk::DescriptorType as Dt;
+        match raw.descriptor_type {
+            Dt::SAMPLER
+            | Dt::SAMPLED_IMAGE
+            | Dt::STORAGE_IMAGE
+            | Dt::COMBINED_IMAGE_SAMPLER
+            | Dt::INPUT_ATTACHMENT => {
+                raw.p_buffer_info = ptr::null();
+                raw.p_texel_buffer_view = ptr::null();
+                raw.p_image_info = image_infos[raw.p_image_info as usize..].as_ptr();
+            }
+            Dt::UNIFORM_TEXEL_BUFFER | Dt::STORAGE_TEXEL_BUFFER => {
+                raw.p_buffer_info = ptr::null();
+                raw.p_image_info = ptr::null();
+                raw.p_texel_buffer_view =
+                    texel_buffer_views[raw.p_texel_buffer_view as usize..].as_ptr();
+            }
+            Dt::UNIFORM_BUFFER
+            | Dt::STORAGE_BUFFER
+            | Dt::STORAGE_BUFFER_DYNAMIC
+            | Dt::UNIFORM_BUFFER_DYNAMIC => {
+                raw.p_image_info = ptr::null();
+                raw.p_texel_buffer_view = ptr::null();
+                raw.p_buffer_info = buffer_infos[raw.p_buffer_info as usize..].as_ptr();
+            }
+            Dt::ACCELERATION_STRUCTURE_KHR => {
+                raw.p_buffer_info = ptr::null();
+                raw.p_image_info = ptr::null();
+                raw.p_texel_buffer_view = ptr::null();
+                let start = raw.p_next as usize;
+                let end = start + raw.descriptor_count as usize;
+                acceleration_structure_writes.push(
+                    vk::WriteDescriptorSetAccelerationStructureKHR::builder()
+                        .acceleration_structures(&acceleration_structures[start..end])
+                        .build(),
+                );
+                raw.p_next = acceleration_structure_writes.last().unwrap() as *const _ as _;
+            }
+            _ => panic!("unknown descriptor type"),
+        }
+    }
+
+    f(&raw_writes)
+}
+
+/// Size of the structure a descriptor of the given type is converted to
+/// in the data of a descriptor update template.
+fn descriptor_info_size(ty: vk::DescriptorType) -> usize {
+    match ty {
+        vk::DescriptorType::SAMPLER
+        | vk::DescriptorType::SAMPLED_IMAGE
+        | vk::DescriptorType::STORAGE_IMAGE
+        | vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        | vk::DescriptorType::INPUT_ATTACHMENT => mem::size_of::<vk::DescriptorImageInfo>(),
+        vk::DescriptorType::UNIFORM_TEXEL_BUFFER | vk::DescriptorType::STORAGE_TEXEL_BUFFER => {
+            mem::size_of::<vk::BufferView>()
+        }
+        vk::DescriptorType::UNIFORM_BUFFER
+        | vk::DescriptorType::STORAGE_BUFFER
+        | vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+        | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC => mem::size_of::<vk::DescriptorBufferInfo>(),
+        vk::DescriptorType::ACCELERATION_STRUCTURE_KHR => {
+            mem::size_of::<vk::AccelerationStructureKHR>()
+        }
+        _ => panic!("unknown descriptor type"),
+    }
+}
+
+/// Write the Vulkan structure of a descriptor to the data of a descriptor update template.
+unsafe fn write_descriptor_info(dst: *mut u8, descriptor: pso::Descriptor<B>) {
+    match descriptor {
+        pso::Descriptor::Sampler(sampler) => ptr::write(
+            dst as *mut vk::DescriptorImageInfo,
+            vk::DescriptorImageInfo {
+                sampler: sampler.raw,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::GENERAL,
+            },
+        ),
+        pso::Descriptor::Image(view, layout) => ptr::write(
+            dst as *mut vk::DescriptorImageInfo,
+            vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: view.raw,
+                image_layout: conv::map_image_layout(layout),
+            },
+        ),
+        pso::Descriptor::CombinedImageSampler(view, layout, sampler) => ptr::write(
+            dst as *mut vk::DescriptorImageInfo,
+            vk::DescriptorImageInfo {
+                sampler: sampler.raw,
+                image_view: view.raw,
+                image_layout: conv::map_image_layout(layout),
+            },
+        ),
+        pso::Descriptor::Buffer(buffer, ref sub) => ptr::write(
+            dst as *mut vk::DescriptorBufferInfo,
+            vk::DescriptorBufferInfo {
+                buffer: buffer.raw,
+                offset: sub.offset,
+                range: sub.size.unwrap_or(vk::WHOLE_SIZE),
+            },
+        ),
+        pso::Descriptor::TexelBuffer(view) => ptr::write(dst as *mut vk::BufferView, view.raw),
+        pso::Descriptor::AccelerationStructure(accel_struct) => {
+            ptr::write(dst as *mut vk::AccelerationStructureKHR, accel_struct.0)
+        }
+    }
+}
+
+/// Convert a reading of the host time domain into nanoseconds,
+/// which `CLOCK_MONOTONIC` readings already are.
+#[cfg(not(windows))]
+fn host_ticks_to_ns(ticks: u64) -> u64 {
+    ticks
+}
+
 impl d::Device<B> for super::Device {
     unsafe fn allocate_memory(
         &self,
         mem_type: MemoryTypeId,
         size: u64,
+        flags: memory::AllocationFlags,
     ) -> Result<n::Memory, d::AllocationError> {
-        let info = vk::MemoryAllocateInfo::builder()
+        let raw_flags = conv::map_memory_allocation_flags(flags);
+        let mut flags_info = vk::MemoryAllocateFlagsInfo::builder().flags(raw_flags);
+        let mut info = vk::MemoryAllocateInfo::builder()
             .allocation_size(size)
             .memory_type_index(self.get_ash_memory_type_index(mem_type));
+        if !raw_flags.is_empty() {
+            info = info.push_next(&mut flags_info);
+        }
 
         let result = self.shared.raw.allocate_memory(&info, None);
 
@@ -549,9 +878,19 @@ impl d::Device<B> for super::Device {
                 dependency_flags: mem::transmute(sdep.flags),
             });
 
+        let mut view_masks = Vec::new();
+        let mut correlation_masks = Vec::new();
+
         let result = inplace_or_alloc_from_iter(attachments_iter, |attachments| {
             let attachment_refs = subpasses
                 .map(|subpass| {
+                    view_masks.push(subpass.view_mask);
+                    if subpass.correlation_mask != 0
+                        && !correlation_masks.contains(&subpass.correlation_mask)
+                    {
+                        correlation_masks.push(subpass.correlation_mask);
+                    }
+
                     fn make_ref(&(id, layout): &pass::AttachmentRef) -> vk::AttachmentReference {
                         vk::AttachmentReference {
                             attachment: id as _,
@@ -597,12 +936,19 @@ impl d::Device<B> for super::Device {
                 })
                 .collect::<Box<[_]>>();
 
+            let mut multiview_info = vk::RenderPassMultiviewCreateInfo::builder()
+                .view_masks(&view_masks)
+                .correlation_masks(&correlation_masks);
+
             inplace_or_alloc_from_iter(dependencies_iter, |dependencies| {
-                let info = vk::RenderPassCreateInfo::builder()
+                let mut info = vk::RenderPassCreateInfo::builder()
                     .flags(vk::RenderPassCreateFlags::empty())
                     .attachments(&attachments)
                     .subpasses(&subpasses)
                     .dependencies(&dependencies);
+                if view_masks.iter().any(|&mask| mask != 0) {
+                    info = info.push_next(&mut multiview_info);
+                }
 
                 self.shared
                     .raw
@@ -631,7 +977,11 @@ impl d::Device<B> for super::Device {
         Is: Iterator<Item = &'a n::DescriptorSetLayout>,
         Ic: Iterator<Item = (pso::ShaderStageFlags, Range<u32>)>,
     {
-        let vk_set_layouts_iter = set_layouts.map(|set| set.raw);
+        let mut set_bindings = Vec::new();
+        let vk_set_layouts_iter = set_layouts.map(|set| {
+            set_bindings.push(Arc::clone(&set.bindings));
+            set.raw
+        });
 
         let push_constant_ranges_iter =
             push_constant_ranges.map(|(s, ref r)| vk::PushConstantRange {
@@ -655,7 +1005,7 @@ impl d::Device<B> for super::Device {
         });
 
         match result {
-            Ok(raw) => Ok(n::PipelineLayout { raw }),
+            Ok(raw) => Ok(n::PipelineLayout { raw, set_bindings }),
             Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
             Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
             _ => unreachable!(),
@@ -736,7 +1086,7 @@ impl d::Device<B> for super::Device {
         cache: Option<&n::PipelineCache>,
     ) -> Result<n::GraphicsPipeline, pso::CreationError> {
         debug!("create_graphics_pipeline {:?}", desc);
-        let buf = GraphicsPipelineInfoBuf::new(desc, &self.shared);
+        let mut buf = GraphicsPipelineInfoBuf::new(desc, &self.shared);
 
         let info = {
             let (base_handle, base_index) = match desc.parent {
@@ -775,17 +1125,21 @@ impl d::Device<B> for super::Device {
                 Some(t) => builder.tessellation_state(t),
                 None => builder,
             };
-            builder
+            let builder = builder
                 .viewport_state(&buf.viewport_state)
                 .multisample_state(&buf.multisample_state)
                 .depth_stencil_state(&buf.depth_stencil_state)
                 .color_blend_state(&buf.color_blend_state)
                 .dynamic_state(&buf.pipeline_dynamic_state)
                 .layout(desc.layout.raw)
-                .render_pass(desc.subpass.main_pass.raw)
-                .subpass(desc.subpass.index as _)
                 .base_pipeline_handle(base_handle)
-                .base_pipeline_index(base_index)
+                .base_pipeline_index(base_index);
+            match desc.target {
+                pass::PipelineTarget::Subpass(subpass) => builder
+                    .render_pass(subpass.main_pass.raw)
+                    .subpass(subpass.index as _),
+                pass::PipelineTarget::Rendering(_) => builder.push_next(&mut buf.rendering_info),
+            }
         };
 
         let mut pipeline = vk::Pipeline::null();
@@ -989,7 +1343,24 @@ impl d::Device<B> for super::Device {
                 }
             });
 
+        let ycbcr_conversion = match desc.ycbcr_conversion {
+            Some(ref conversion)
+                if self
+                    .shared
+                    .features
+                    .contains(Features::SAMPLER_YCBCR_CONVERSION) =>
+            {
+                Some(self.create_ycbcr_conversion(conversion)?)
+            }
+            Some(_) => {
+                warn!("Y'CbCr conversion was requested on a device with disabled feature");
+                None
+            }
+            None => None,
+        };
+
         let mut reduction_info;
+        let mut ycbcr_conversion_info;
         let mut info = vk::SamplerCreateInfo::builder()
             .flags(vk::SamplerCreateFlags::empty())
             .mag_filter(conv::map_filter(desc.mag_filter))
@@ -1017,10 +1388,23 @@ impl d::Device<B> for super::Device {
             info = info.push_next(&mut reduction_info);
         }
 
+        if let Some(conversion) = ycbcr_conversion {
+            ycbcr_conversion_info = vk::SamplerYcbcrConversionInfo::builder()
+                .conversion(conversion)
+                .build();
+            info = info.push_next(&mut ycbcr_conversion_info);
+        }
+
         let result = self.shared.raw.create_sampler(&info, None);
+        if let (Err(_), Some(conversion)) = (result, ycbcr_conversion) {
+            self.destroy_ycbcr_conversion(conversion);
+        }
 
         match result {
-            Ok(sampler) => Ok(n::Sampler(sampler)),
+            Ok(raw) => Ok(n::Sampler {
+                raw,
+                ycbcr_conversion,
+            }),
             Err(vk::Result::ERROR_TOO_MANY_OBJECTS) => Err(d::AllocationError::TooManyObjects),
             Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
             Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
@@ -1035,10 +1419,24 @@ impl d::Device<B> for super::Device {
         usage: buffer::Usage,
         sparse: memory::SparseFlags,
     ) -> Result<n::Buffer, buffer::CreationError> {
+        let mut raw_usage = conv::map_buffer_usage(usage);
+        // Buffers holding acceleration structures, their build inputs,
+        // or used as scratch memory are accessed through their device address.
+        if usage.intersects(
+            buffer::Usage::ACCELERATION_STRUCTURE_BUILD_INPUT
+                | buffer::Usage::ACCELERATION_STRUCTURE_STORAGE,
+        ) {
+            raw_usage |= vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
+        }
+        // Scratch memory of the builds has to be a storage buffer.
+        if usage.contains(buffer::Usage::ACCELERATION_STRUCTURE_STORAGE) {
+            raw_usage |= vk::BufferUsageFlags::STORAGE_BUFFER;
+        }
+
         let info = vk::BufferCreateInfo::builder()
             .flags(conv::map_buffer_create_flags(sparse))
             .size(size)
-            .usage(conv::map_buffer_usage(usage))
+            .usage(raw_usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE); // TODO:
 
         let result = self.shared.raw.create_buffer(&info, None);
@@ -1080,6 +1478,10 @@ impl d::Device<B> for super::Device {
         }
     }
 
+    unsafe fn get_buffer_device_address(&self, buffer: &n::Buffer) -> buffer::DeviceAddress {
+        self.shared.buffer_device_address(buffer, 0)
+    }
+
     unsafe fn create_buffer_view(
         &self,
         buffer: &n::Buffer,
@@ -1212,6 +1614,7 @@ impl d::Device<B> for super::Device {
         swizzle: format::Swizzle,
         usage: image::Usage,
         range: image::SubresourceRange,
+        ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<n::ImageView, image::ViewCreationError> {
         let is_cube = image
             .flags
@@ -1221,7 +1624,35 @@ impl d::Device<B> for super::Device {
             None => return Err(image::ViewCreationError::BadKind(kind)),
         };
 
-        self.image_view_from_raw(image.raw, view_type, format, swizzle, usage, range)
+        let ycbcr_conversion = match ycbcr_conversion {
+            Some(ref conversion)
+                if self
+                    .shared
+                    .features
+                    .contains(Features::SAMPLER_YCBCR_CONVERSION) =>
+            {
+                Some(self.create_ycbcr_conversion(conversion)?)
+            }
+            Some(_) => {
+                warn!("Y'CbCr conversion was requested on a device with disabled feature");
+                None
+            }
+            None => None,
+        };
+
+        let result = self.image_view_from_raw(
+            image.raw,
+            view_type,
+            format,
+            swizzle,
+            usage,
+            range,
+            ycbcr_conversion,
+        );
+        if let (Err(_), Some(conversion)) = (&result, ycbcr_conversion) {
+            self.destroy_ycbcr_conversion(conversion);
+        }
+        result
     }
 
     unsafe fn create_descriptor_pool<T>(
@@ -1259,12 +1690,13 @@ impl d::Device<B> for super::Device {
         &self,
         binding_iter: I,
         immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, d::OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
         J: Iterator<Item = &'a n::Sampler>,
     {
-        let vk_immutable_samplers_iter = immutable_samplers.map(|is| is.0);
+        let vk_immutable_samplers_iter = immutable_samplers.map(|is| is.raw);
         let mut sampler_offset = 0;
 
         let mut bindings = binding_iter.collect::<Vec<_>>();
@@ -1291,7 +1723,7 @@ impl d::Device<B> for super::Device {
                 // debug!("create_descriptor_set_layout {:?}", raw_bindings);
 
                 let info = vk::DescriptorSetLayoutCreateInfo::builder()
-                    .flags(vk::DescriptorSetLayoutCreateFlags::empty())
+                    .flags(conv::map_descriptor_set_layout_create_flags(flags))
                     .bindings(&raw_bindings);
 
                 self.shared.raw.create_descriptor_set_layout(&info, None)
@@ -1313,132 +1745,14 @@ impl d::Device<B> for super::Device {
     where
         I: Iterator<Item = pso::Descriptor<'a, B>>,
     {
-        let descriptors = op.descriptors;
-        let mut raw_writes =
-            Vec::<vk::WriteDescriptorSet>::with_capacity(descriptors.size_hint().0);
-        let mut image_infos = Vec::new();
-        let mut buffer_infos = Vec::new();
-        let mut texel_buffer_views = Vec::new();
-
-        // gfx-hal allows the type and stages to be different between the descriptor
-        // in a single write, while Vulkan requires them to be the same.
-        let mut last_type = vk::DescriptorType::SAMPLER;
-        let mut last_stages = pso::ShaderStageFlags::empty();
-
-        let mut binding_pos = op
-            .set
-            .bindings
-            .binary_search_by_key(&op.binding, |b| b.binding)
-            .expect("Descriptor set writes don't match the set layout!");
-        let mut array_offset = op.array_offset;
-
-        for descriptor in descriptors {
-            let layout_binding = &op.set.bindings[binding_pos];
-            array_offset += 1;
-            if array_offset == layout_binding.count {
-                array_offset = 0;
-                binding_pos += 1;
-            }
-
-            let descriptor_type = conv::map_descriptor_type(layout_binding.ty);
-            if descriptor_type == last_type && layout_binding.stage_flags == last_stages {
-                raw_writes.last_mut().unwrap().descriptor_count += 1;
-            } else {
-                last_type = descriptor_type;
-                last_stages = layout_binding.stage_flags;
-                raw_writes.push(vk::WriteDescriptorSet {
-                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                    p_next: ptr::null(),
-                    dst_set: op.set.raw,
-                    dst_binding: layout_binding.binding,
-                    dst_array_element: if layout_binding.binding == op.binding {
-                        op.array_offset as _
-                    } else {
-                        0
-                    },
-                    descriptor_count: 1,
-                    descriptor_type,
-                    p_image_info: image_infos.len() as _,
-                    p_buffer_info: buffer_infos.len() as _,
-                    p_texel_buffer_view: texel_buffer_views.len() as _,
-                });
-            }
-
-            match descriptor {
-                pso::Descriptor::Sampler(sampler) => {
-                    image_infos.push(
-                        vk::DescriptorImageInfo::builder()
-                            .sampler(sampler.0)
-                            .image_view(vk::ImageView::null())
-                            .image_layout(vk::ImageLayout::GENERAL)
-                            .build(),
-                    );
-                }
-                pso::Descriptor::Image(view, layout) => {
-                    image_infos.push(
-                        vk::DescriptorImageInfo::builder()
-                            .sampler(vk::Sampler::null())
-                            .image_view(view.raw)
-                            .image_layout(conv::map_image_layout(layout))
-                            .build(),
-                    );
-                }
-                pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
-                    image_infos.push(
-                        vk::DescriptorImageInfo::builder()
-                            .sampler(sampler.0)
-                            .image_view(view.raw)
-                            .image_layout(conv::map_image_layout(layout))
-                            .build(),
-                    );
-                }
-                pso::Descriptor::Buffer(buffer, ref sub) => {
-                    buffer_infos.push(
-                        vk::DescriptorBufferInfo::builder()
-                            .buffer(buffer.raw)
-                            .offset(sub.offset)
-                            .range(sub.size.unwrap_or(vk::WHOLE_SIZE))
-                            .build(),
-                    );
-                }
-                pso::Descriptor::TexelBuffer(view) => {
-                    texel_buffer_views.push(view.raw);
-                }
-            }
-        }
-
-        // Patch the pointers now that we have all the storage allocated.
-        for raw in raw_writes.iter_mut() {
-            use crate::vk::DescriptorType as Dt;
-            match raw.descriptor_type {
-                Dt::SAMPLER
-                | Dt::SAMPLED_IMAGE
-                | Dt::STORAGE_IMAGE
-                | Dt::COMBINED_IMAGE_SAMPLER
-                | Dt::INPUT_ATTACHMENT => {
-                    raw.p_buffer_info = ptr::null();
-                    raw.p_texel_buffer_view = ptr::null();
-                    raw.p_image_info = image_infos[raw.p_image_info as usize..].as_ptr();
-                }
-                Dt::UNIFORM_TEXEL_BUFFER | Dt::STORAGE_TEXEL_BUFFER => {
-                    raw.p_buffer_info = ptr::null();
-                    raw.p_image_info = ptr::null();
-                    raw.p_texel_buffer_view =
-                        texel_buffer_views[raw.p_texel_buffer_view as usize..].as_ptr();
-                }
-                Dt::UNIFORM_BUFFER
-                | Dt::STORAGE_BUFFER
-                | Dt::STORAGE_BUFFER_DYNAMIC
-                | Dt::UNIFORM_BUFFER_DYNAMIC => {
-                    raw.p_image_info = ptr::null();
-                    raw.p_texel_buffer_view = ptr::null();
-                    raw.p_buffer_info = buffer_infos[raw.p_buffer_info as usize..].as_ptr();
-                }
-                _ => panic!("unknown descriptor type"),
-            }
-        }
-
-        self.shared.raw.update_descriptor_sets(&raw_writes, &[]);
+        with_raw_descriptor_writes(
+            op.set.raw,
+            &op.set.bindings,
+            op.binding,
+            op.array_offset,
+            op.descriptors,
+            |raw_writes| self.shared.raw.update_descriptor_sets(raw_writes, &[]),
+        )
     }
 
     unsafe fn copy_descriptor_set<'a>(&self, op: pso::DescriptorSetCopy<'a, B>) {
@@ -1455,6 +1769,147 @@ impl d::Device<B> for super::Device {
         self.shared.raw.update_descriptor_sets(&[], &[copy]);
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        layout: &n::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<n::DescriptorUpdateTemplate, d::OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        let functor = match self.shared.extension_fns.descriptor_update_template {
+            Some(ref functor) => functor,
+            None => {
+                return Ok(n::DescriptorUpdateTemplate::Emulated(
+                    pso::EmulatedDescriptorUpdateTemplate::new(entries),
+                ))
+            }
+        };
+
+        // Split the entries at binding boundaries, since Vulkan requires
+        // the descriptors spilling over to the next bindings to have the same type.
+        let mut raw_entries = Vec::new();
+        let mut template_entries = Vec::new();
+        let mut data_size = 0;
+        for entry in entries {
+            let mut binding_pos = layout
+                .bindings
+                .binary_search_by_key(&entry.binding, |b| b.binding)
+                .expect("Descriptor update template entries don't match the set layout!");
+            let mut array_offset = entry.array_offset;
+            let mut written = 0;
+            while written < entry.count {
+                let layout_binding = &layout.bindings[binding_pos];
+                let count = (layout_binding.count - array_offset).min(entry.count - written);
+                if count != 0 {
+                    let descriptor_type = conv::map_descriptor_type(layout_binding.ty);
+                    let stride = descriptor_info_size(descriptor_type);
+                    raw_entries.push(vk::DescriptorUpdateTemplateEntry {
+                        dst_binding: layout_binding.binding,
+                        dst_array_element: array_offset as u32,
+                        descriptor_count: count as u32,
+                        descriptor_type,
+                        offset: data_size,
+                        stride,
+                    });
+                    template_entries.push(n::TemplateEntry {
+                        src: pso::DescriptorUpdateTemplateEntry {
+                            binding: layout_binding.binding,
+                            array_offset,
+                            count,
+                            offset: entry.offset + written * entry.stride,
+                            stride: entry.stride,
+                        },
+                        dst_offset: data_size,
+                        dst_stride: stride,
+                    });
+                    data_size += count * stride;
+                    written += count;
+                }
+                array_offset = 0;
+                binding_pos += 1;
+            }
+        }
+
+        let info = vk::DescriptorUpdateTemplateCreateInfo::builder()
+            .descriptor_update_entries(&raw_entries)
+            .template_type(vk::DescriptorUpdateTemplateType::DESCRIPTOR_SET)
+            .descriptor_set_layout(layout.raw);
+
+        let result = match functor {
+            ExtensionFn::Extension(t) => {
+                let mut raw = vk::DescriptorUpdateTemplate::null();
+                match t.create_descriptor_update_template_khr(
+                    self.shared.raw.handle(),
+                    &*info,
+                    ptr::null(),
+                    &mut raw,
+                ) {
+                    vk::Result::SUCCESS => Ok(raw),
+                    err => Err(err),
+                }
+            }
+            ExtensionFn::Promoted => self
+                .shared
+                .raw
+                .create_descriptor_update_template(&info, None),
+        };
+
+        match result {
+            Ok(raw) => Ok(n::DescriptorUpdateTemplate::Native {
+                raw,
+                entries: template_entries,
+                data_words: data_size / mem::size_of::<u64>(),
+            }),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut n::DescriptorSet,
+        template: &n::DescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        let (raw, entries, data_words) = match *template {
+            n::DescriptorUpdateTemplate::Native {
+                raw,
+                ref entries,
+                data_words,
+            } => (raw, entries, data_words),
+            n::DescriptorUpdateTemplate::Emulated(ref emulated) => {
+                return emulated.update(self, set, data)
+            }
+        };
+
+        // Convert the descriptors to the structures Vulkan expects.
+        let mut raw_data = SmallVec::<[u64; 64]>::from_elem(0, data_words);
+        let raw_data_ptr = raw_data.as_mut_ptr() as *mut u8;
+        for entry in entries {
+            for i in 0..entry.src.count {
+                let dst = raw_data_ptr.add(entry.dst_offset + i * entry.dst_stride);
+                write_descriptor_info(dst, entry.src.read_descriptor(data, i));
+            }
+        }
+
+        match self.shared.extension_fns.descriptor_update_template {
+            Some(ExtensionFn::Extension(ref t)) => t.update_descriptor_set_with_template_khr(
+                self.shared.raw.handle(),
+                set.raw,
+                raw,
+                raw_data.as_ptr() as *const _,
+            ),
+            Some(ExtensionFn::Promoted) => self.shared.raw.update_descriptor_set_with_template(
+                set.raw,
+                raw,
+                raw_data.as_ptr() as *const _,
+            ),
+            None => unreachable!(),
+        };
+    }
+
     unsafe fn map_memory(
         &self,
         memory: &mut n::Memory,
@@ -1527,6 +1982,25 @@ impl d::Device<B> for super::Device {
         }
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: u64,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(initial_value);
+        let info = vk::SemaphoreCreateInfo::builder().push_next(&mut type_info);
+
+        let result = unsafe { self.shared.raw.create_semaphore(&info, None) };
+
+        match result {
+            Ok(semaphore) => Ok(n::Semaphore(semaphore)),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
     fn create_fence(&self, signaled: bool) -> Result<n::Fence, d::OutOfMemory> {
         let info = vk::FenceCreateInfo::builder().flags(if signaled {
             vk::FenceCreateFlags::SIGNALED
@@ -1640,6 +2114,68 @@ impl d::Device<B> for super::Device {
         self.shared.raw.free_memory(memory.raw, None);
     }
 
+    fn memory_budget(&self) -> Vec<adapter::MemoryHeapBudget> {
+        let get_physical_device_properties =
+            match self.shared.instance.get_physical_device_properties {
+                Some(ref get_physical_device_properties)
+                    if self.shared.features.contains(Features::MEMORY_BUDGET) =>
+                {
+                    get_physical_device_properties
+                }
+                _ => {
+                    // Without `VK_EXT_memory_budget`, the whole heaps are available.
+                    use ash::version::InstanceV1_0;
+                    let memory_properties = unsafe {
+                        self.shared
+                            .instance
+                            .inner
+                            .get_physical_device_memory_properties(self.physical_device)
+                    };
+                    return memory_properties.memory_heaps
+                        [..memory_properties.memory_heap_count as usize]
+                        .iter()
+                        .map(|heap| adapter::MemoryHeapBudget {
+                            budget: heap.size,
+                            usage: 0,
+                        })
+                        .collect();
+                }
+            };
+
+        let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::builder();
+        let mut memory_properties2 = vk::PhysicalDeviceMemoryProperties2::builder()
+            .push_next(&mut budget_properties)
+            .build();
+
+        match *get_physical_device_properties {
+            ExtensionFn::Promoted => {
+                use ash::version::InstanceV1_1;
+                unsafe {
+                    self.shared
+                        .instance
+                        .inner
+                        .get_physical_device_memory_properties2(
+                            self.physical_device,
+                            &mut memory_properties2,
+                        );
+                }
+            }
+            ExtensionFn::Extension(ref extension) => unsafe {
+                extension.get_physical_device_memory_properties2_khr(
+                    self.physical_device,
+                    &mut memory_properties2,
+                );
+            },
+        }
+
+        let heap_count = memory_properties2.memory_properties.memory_heap_count as usize;
+        budget_properties.heap_budget[..heap_count]
+            .iter()
+            .zip(&budget_properties.heap_usage[..heap_count])
+            .map(|(&budget, &usage)| adapter::MemoryHeapBudget { budget, usage })
+            .collect()
+    }
+
     unsafe fn create_query_pool(
         &self,
         ty: query::Type,
@@ -1658,6 +2194,7 @@ impl d::Device<B> for super::Device {
                 vk::QueryType::TIMESTAMP,
                 vk::QueryPipelineStatisticFlags::empty(),
             ),
+            query::Type::PerformanceCounters => return Err(query::CreationError::Unsupported(ty)),
         };
 
         let info = vk::QueryPoolCreateInfo::builder()
@@ -1676,6 +2213,68 @@ impl d::Device<B> for super::Device {
         }
     }
 
+    unsafe fn create_performance_query_pool(
+        &self,
+        family: queue::QueueFamilyId,
+        counters: &[u32],
+        count: query::Id,
+    ) -> Result<n::QueryPool, query::CreationError> {
+        let unsupported = query::CreationError::Unsupported(query::Type::PerformanceCounters);
+        if !self.shared.features.contains(Features::PERFORMANCE_QUERY) {
+            return Err(unsupported);
+        }
+        let ext = self.shared.instance.performance_query.as_ref().unwrap();
+
+        let mut performance_info = vk::QueryPoolPerformanceCreateInfoKHR::builder()
+            .queue_family_index(family.0 as u32)
+            .counter_indices(counters);
+
+        // Counters needing several passes require the command buffers to be
+        // submitted once per pass, which isn't exposed.
+        let mut passes = 0;
+        ext.get_physical_device_queue_family_performance_query_passes_khr(
+            self.physical_device,
+            &*performance_info,
+            &mut passes,
+        );
+        if passes != 1 {
+            return Err(unsupported);
+        }
+
+        let info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::PERFORMANCE_QUERY_KHR)
+            .query_count(count)
+            .push_next(&mut performance_info);
+
+        match self.shared.raw.create_query_pool(&info, None) {
+            Ok(pool) => Ok(n::QueryPool(pool)),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn acquire_profiling_lock(&self, timeout_ns: u64) -> Result<bool, d::OutOfMemory> {
+        let ext = match self.shared.extension_fns.performance_query {
+            Some(ref ext) => ext,
+            None => return Ok(false),
+        };
+        let info = vk::AcquireProfilingLockInfoKHR::builder().timeout(timeout_ns);
+
+        match ext.acquire_profiling_lock_khr(self.shared.raw.handle(), &*info) {
+            vk::Result::SUCCESS => Ok(true),
+            vk::Result::TIMEOUT => Ok(false),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn release_profiling_lock(&self) {
+        if let Some(ref ext) = self.shared.extension_fns.performance_query {
+            ext.release_profiling_lock_khr(self.shared.raw.handle());
+        }
+    }
+
     unsafe fn get_query_pool_results(
         &self,
         pool: &n::QueryPool,
@@ -1756,10 +2355,99 @@ impl d::Device<B> for super::Device {
 
     unsafe fn destroy_image_view(&self, view: n::ImageView) {
         self.shared.raw.destroy_image_view(view.raw, None);
+        if let Some(conversion) = view.ycbcr_conversion {
+            self.destroy_ycbcr_conversion(conversion);
+        }
     }
 
     unsafe fn destroy_sampler(&self, sampler: n::Sampler) {
-        self.shared.raw.destroy_sampler(sampler.0, None);
+        self.shared.raw.destroy_sampler(sampler.raw, None);
+        if let Some(conversion) = sampler.ycbcr_conversion {
+            self.destroy_ycbcr_conversion(conversion);
+        }
+    }
+
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        desc: &accel::GeometryDesc<B>,
+        max_primitive_counts: &[u32],
+    ) -> accel::SizeRequirements {
+        assert_eq!(desc.geometries.len(), max_primitive_counts.len());
+        let acceleration_structure_fn = self.shared.acceleration_structure_fn();
+
+        let geometries = desc
+            .geometries
+            .iter()
+            .map(|geometry| self.shared.map_acceleration_structure_geometry(geometry))
+            .collect::<Vec<_>>();
+        let info = vk::AccelerationStructureBuildGeometryInfoKHR::builder()
+            .ty(conv::map_acceleration_structure_level(desc.level))
+            .flags(conv::map_acceleration_structure_build_flags(desc.flags))
+            .geometries(&geometries);
+        let mut sizes = vk::AccelerationStructureBuildSizesInfoKHR::default();
+
+        acceleration_structure_fn.get_acceleration_structure_build_sizes_khr(
+            self.shared.raw.handle(),
+            vk::AccelerationStructureBuildTypeKHR::DEVICE,
+            &*info,
+            max_primitive_counts.as_ptr(),
+            &mut sizes,
+        );
+
+        accel::SizeRequirements {
+            acceleration_structure_size: sizes.acceleration_structure_size,
+            update_scratch_size: sizes.update_scratch_size,
+            build_scratch_size: sizes.build_scratch_size,
+        }
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        desc: &accel::CreateDesc<B>,
+    ) -> Result<n::AccelerationStructure, d::OutOfMemory> {
+        let acceleration_structure_fn = self.shared.acceleration_structure_fn();
+
+        let info = vk::AccelerationStructureCreateInfoKHR::builder()
+            .buffer(desc.buffer.raw)
+            .offset(desc.buffer_offset)
+            .size(desc.size)
+            .ty(conv::map_acceleration_structure_level(desc.level));
+        let mut raw = vk::AccelerationStructureKHR::null();
+
+        let result = acceleration_structure_fn.create_acceleration_structure_khr(
+            self.shared.raw.handle(),
+            &*info,
+            ptr::null(),
+            &mut raw,
+        );
+
+        match result {
+            vk::Result::SUCCESS => Ok(n::AccelerationStructure(raw)),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        accel_struct: &n::AccelerationStructure,
+    ) -> accel::DeviceAddress {
+        let acceleration_structure_fn = self.shared.acceleration_structure_fn();
+
+        let info = vk::AccelerationStructureDeviceAddressInfoKHR::builder()
+            .acceleration_structure(accel_struct.0);
+        acceleration_structure_fn
+            .get_acceleration_structure_device_address_khr(self.shared.raw.handle(), &*info)
+    }
+
+    unsafe fn destroy_acceleration_structure(&self, accel_struct: n::AccelerationStructure) {
+        let acceleration_structure_fn = self.shared.acceleration_structure_fn();
+
+        acceleration_structure_fn.destroy_acceleration_structure_khr(
+            self.shared.raw.handle(),
+            accel_struct.0,
+            ptr::null(),
+        );
     }
 
     unsafe fn destroy_descriptor_pool(&self, pool: n::DescriptorPool) {
@@ -1772,6 +2460,23 @@ impl d::Device<B> for super::Device {
             .destroy_descriptor_set_layout(layout.raw, None);
     }
 
+    unsafe fn destroy_descriptor_update_template(&self, template: n::DescriptorUpdateTemplate) {
+        let raw = match template {
+            n::DescriptorUpdateTemplate::Native { raw, .. } => raw,
+            n::DescriptorUpdateTemplate::Emulated(_) => return,
+        };
+        match self.shared.extension_fns.descriptor_update_template {
+            Some(ExtensionFn::Extension(ref t)) => {
+                t.destroy_descriptor_update_template_khr(self.shared.raw.handle(), raw, ptr::null())
+            }
+            Some(ExtensionFn::Promoted) => self
+                .shared
+                .raw
+                .destroy_descriptor_update_template(raw, None),
+            None => unreachable!(),
+        };
+    }
+
     unsafe fn destroy_fence(&self, fence: n::Fence) {
         self.shared.raw.destroy_fence(fence.0, None);
     }
@@ -1780,19 +2485,214 @@ impl d::Device<B> for super::Device {
         self.shared.raw.destroy_semaphore(semaphore.0, None);
     }
 
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        semaphore: &n::Semaphore,
+    ) -> Result<u64, d::DeviceLost> {
+        let result = match self
+            .shared
+            .extension_fns
+            .timeline_semaphore
+            .as_ref()
+            .expect(
+                "Feature TIMELINE_SEMAPHORE must be enabled to call get_semaphore_counter_value",
+            ) {
+            ExtensionFn::Extension(t) => {
+                let mut value = 0;
+                match t.get_semaphore_counter_value_khr(
+                    self.shared.raw.handle(),
+                    semaphore.0,
+                    &mut value,
+                ) {
+                    vk::Result::SUCCESS => Ok(value),
+                    err => Err(err),
+                }
+            }
+            ExtensionFn::Promoted => self.shared.raw.get_semaphore_counter_value(semaphore.0),
+        };
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(vk::Result::ERROR_DEVICE_LOST) => Err(d::DeviceLost),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        semaphores: I,
+        wait: d::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, d::WaitError>
+    where
+        I: Iterator<Item = (&'a n::Semaphore, u64)>,
+    {
+        let (raw_semaphores, values): (SmallVec<[_; 4]>, SmallVec<[_; 4]>) = semaphores
+            .map(|(semaphore, value)| (semaphore.0, value))
+            .unzip();
+
+        let flags = match wait {
+            d::WaitFor::Any => vk::SemaphoreWaitFlags::ANY,
+            d::WaitFor::All => vk::SemaphoreWaitFlags::empty(),
+        };
+        let info = vk::SemaphoreWaitInfo::builder()
+            .flags(flags)
+            .semaphores(&raw_semaphores)
+            .values(&values);
+
+        let result = match self
+            .shared
+            .extension_fns
+            .timeline_semaphore
+            .as_ref()
+            .expect("Feature TIMELINE_SEMAPHORE must be enabled to call wait_semaphores")
+        {
+            ExtensionFn::Extension(t) => {
+                match t.wait_semaphores_khr(self.shared.raw.handle(), &*info, timeout_ns) {
+                    vk::Result::SUCCESS => Ok(()),
+                    err => Err(err),
+                }
+            }
+            ExtensionFn::Promoted => self.shared.raw.wait_semaphores(&info, timeout_ns),
+        };
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(vk::Result::TIMEOUT) => Ok(false),
+            Err(vk::Result::ERROR_DEVICE_LOST) => Err(d::DeviceLost.into()),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &n::Semaphore,
+        value: u64,
+    ) -> Result<(), d::OutOfMemory> {
+        let info = vk::SemaphoreSignalInfo::builder()
+            .semaphore(semaphore.0)
+            .value(value);
+
+        let result = match self
+            .shared
+            .extension_fns
+            .timeline_semaphore
+            .as_ref()
+            .expect("Feature TIMELINE_SEMAPHORE must be enabled to call signal_semaphore")
+        {
+            ExtensionFn::Extension(t) => {
+                match t.signal_semaphore_khr(self.shared.raw.handle(), &*info) {
+                    vk::Result::SUCCESS => Ok(()),
+                    err => Err(err),
+                }
+            }
+            ExtensionFn::Promoted => self.shared.raw.signal_semaphore(&info),
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
     unsafe fn destroy_event(&self, event: n::Event) {
         self.shared.raw.destroy_event(event.0, None);
     }
 
-    fn wait_idle(&self) -> Result<(), d::OutOfMemory> {
+    fn get_calibrated_timestamps(&self) -> Result<d::CalibratedTimestamps, d::OutOfMemory> {
+        let ext = self
+            .shared
+            .extension_fns
+            .calibrated_timestamps
+            .as_ref()
+            .expect(
+                "Feature CALIBRATED_TIMESTAMPS must be enabled to call get_calibrated_timestamps",
+            );
+        let infos = [
+            vk::CalibratedTimestampInfoEXT::builder()
+                .time_domain(vk::TimeDomainEXT::DEVICE)
+                .build(),
+            vk::CalibratedTimestampInfoEXT::builder()
+                .time_domain(HOST_TIME_DOMAIN)
+                .build(),
+        ];
+        let mut timestamps = [0; 2];
+        let mut max_deviation_ns = 0;
+
+        let result = unsafe {
+            ext.get_calibrated_timestamps_ext(
+                self.shared.raw.handle(),
+                infos.len() as u32,
+                infos.as_ptr(),
+                timestamps.as_mut_ptr(),
+                &mut max_deviation_ns,
+            )
+        };
+        match result {
+            vk::Result::SUCCESS => Ok(d::CalibratedTimestamps {
+                device: timestamps[0],
+                host_ns: host_ticks_to_ns(timestamps[1]),
+                max_deviation_ns,
+            }),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host),
+            vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
+    fn wait_idle(&self) -> Result<(), d::WaitError> {
         match unsafe { self.shared.raw.device_wait_idle() } {
             Ok(()) => Ok(()),
-            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
-            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            Err(vk::Result::ERROR_DEVICE_LOST) => Err(d::DeviceLost.into()),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
             _ => unreachable!(),
         }
     }
 
+    unsafe fn get_fault_info(&self) -> Option<d::FaultInfo> {
+        if !self
+            .shared
+            .features
+            .contains(Features::DEVICE_FAULT_DIAGNOSTICS)
+        {
+            return None;
+        }
+        let description = match self.shared.extension_fns.device_fault {
+            Some(ref ext) => {
+                // Only the description is queried, leaving out the address and vendor information
+                let mut counts = device_fault::DeviceFaultCountsEXT::default();
+                let mut info = device_fault::DeviceFaultInfoEXT::default();
+                match ext.get_device_fault_info_ext(
+                    self.shared.raw.handle(),
+                    &mut counts,
+                    &mut info,
+                ) {
+                    vk::Result::SUCCESS | vk::Result::INCOMPLETE => {
+                        Some(info.description_as_c_str().to_string_lossy().into_owned())
+                    }
+                    err => {
+                        error!("Unable to get the fault info: {:?}", err);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        Some(d::FaultInfo {
+            description,
+            last_breadcrumb: self
+                .shared
+                .breadcrumbs
+                .as_ref()
+                .and_then(|breadcrumbs| breadcrumbs.last()),
+        })
+    }
+
     unsafe fn set_image_name(&self, image: &mut n::Image, name: &str) {
         self.shared
             .set_object_name(vk::ObjectType::IMAGE, image.raw, name)
@@ -3176,8 +4076,10 @@ impl super::Device {
         swizzle: format::Swizzle,
         usage: image::Usage,
         range: image::SubresourceRange,
+        ycbcr_conversion: Option<vk::SamplerYcbcrConversion>,
     ) -> Result<n::ImageView, image::ViewCreationError> {
         let mut image_view_info;
+        let mut ycbcr_conversion_info;
         let mut info = vk::ImageViewCreateInfo::builder()
             .flags(vk::ImageViewCreateFlags::empty())
             .image(raw_image)
@@ -3193,6 +4095,13 @@ impl super::Device {
             info = info.push_next(&mut image_view_info);
         }
 
+        if let Some(conversion) = ycbcr_conversion {
+            ycbcr_conversion_info = vk::SamplerYcbcrConversionInfo::builder()
+                .conversion(conversion)
+                .build();
+            info = info.push_next(&mut ycbcr_conversion_info);
+        }
+
         let result = self.shared.raw.create_image_view(&info, None);
 
         match result {
@@ -3200,12 +4109,71 @@ impl super::Device {
                 image: raw_image,
                 raw,
                 range,
+                ycbcr_conversion,
             }),
             Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
             Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
             _ => unreachable!(),
         }
     }
+
+    /// Create the conversion object attached to the samplers and image views
+    /// sampling with a Y'CbCr conversion.
+    unsafe fn create_ycbcr_conversion(
+        &self,
+        desc: &image::YcbcrConversionDesc,
+    ) -> Result<vk::SamplerYcbcrConversion, d::OutOfMemory> {
+        let info = vk::SamplerYcbcrConversionCreateInfo::builder()
+            .format(conv::map_format(desc.format))
+            .ycbcr_model(conv::map_ycbcr_model(desc.model))
+            .ycbcr_range(conv::map_ycbcr_range(desc.range))
+            .components(conv::map_swizzle(desc.components))
+            .x_chroma_offset(conv::map_chroma_location(desc.x_chroma_offset))
+            .y_chroma_offset(conv::map_chroma_location(desc.y_chroma_offset))
+            .chroma_filter(conv::map_filter(desc.chroma_filter))
+            .force_explicit_reconstruction(desc.force_explicit_reconstruction);
+
+        let result = match self.shared.extension_fns.sampler_ycbcr_conversion {
+            Some(ExtensionFn::Extension(ref t)) => {
+                let mut raw = vk::SamplerYcbcrConversion::null();
+                match t.create_sampler_ycbcr_conversion_khr(
+                    self.shared.raw.handle(),
+                    &*info,
+                    ptr::null(),
+                    &mut raw,
+                ) {
+                    vk::Result::SUCCESS => Ok(raw),
+                    err => Err(err),
+                }
+            }
+            Some(ExtensionFn::Promoted) => {
+                self.shared.raw.create_sampler_ycbcr_conversion(&info, None)
+            }
+            None => unreachable!(),
+        };
+
+        match result {
+            Ok(raw) => Ok(raw),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn destroy_ycbcr_conversion(&self, conversion: vk::SamplerYcbcrConversion) {
+        match self.shared.extension_fns.sampler_ycbcr_conversion {
+            Some(ExtensionFn::Extension(ref t)) => t.destroy_sampler_ycbcr_conversion_khr(
+                self.shared.raw.handle(),
+                conversion,
+                ptr::null(),
+            ),
+            Some(ExtensionFn::Promoted) => self
+                .shared
+                .raw
+                .destroy_sampler_ycbcr_conversion(conversion, None),
+            None => unreachable!(),
+        }
+    }
 }
 
 #[test]
@@ -3213,3 +4181,38 @@ fn test_send_sync() {
     fn foo<T: Send + Sync>() {}
     foo::<super::Device>()
 }
+
+#[test]
+fn test_subgroup_properties() {
+    let properties = vk::PhysicalDeviceSubgroupProperties::builder()
+        .subgroup_size(32)
+        .supported_stages(
+            vk::ShaderStageFlags::VERTEX
+                | vk::ShaderStageFlags::FRAGMENT
+                | vk::ShaderStageFlags::COMPUTE
+                | vk::ShaderStageFlags::MESH_NV,
+        )
+        .supported_operations(
+            vk::SubgroupFeatureFlags::BASIC
+                | vk::SubgroupFeatureFlags::VOTE
+                | vk::SubgroupFeatureFlags::BALLOT
+                | vk::SubgroupFeatureFlags::QUAD,
+        )
+        .quad_operations_in_all_stages(true)
+        .build();
+    assert_eq!(
+        conv::map_vk_subgroup_properties(&properties),
+        hal::SubgroupProperties {
+            subgroup_size: 32,
+            supported_stages: pso::ShaderStageFlags::VERTEX
+                | pso::ShaderStageFlags::FRAGMENT
+                | pso::ShaderStageFlags::COMPUTE
+                | pso::ShaderStageFlags::MESH,
+            supported_operations: hal::SubgroupOperations::BASIC
+                | hal::SubgroupOperations::VOTE
+                | hal::SubgroupOperations::BALLOT
+                | hal::SubgroupOperations::QUAD,
+            quad_operations_in_all_stages: true,
+        }
+    );
+}
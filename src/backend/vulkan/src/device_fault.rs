@@ -0,0 +1,150 @@
+//! Raw definitions of `VK_EXT_device_fault`, which the `ash` version in use doesn't expose.
+//!
+//! Only the parts needed to read the description of a fault are defined, following the
+//! layout and naming of the generated `ash` bindings.
+
+use ash::vk;
+use std::{
+    ffi::CStr,
+    mem,
+    os::raw::{c_char, c_void},
+    ptr,
+};
+
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_FAULT_FEATURES_EXT: vk::StructureType =
+    vk::StructureType::from_raw(1_000_341_000);
+pub const STRUCTURE_TYPE_DEVICE_FAULT_COUNTS_EXT: vk::StructureType =
+    vk::StructureType::from_raw(1_000_341_001);
+pub const STRUCTURE_TYPE_DEVICE_FAULT_INFO_EXT: vk::StructureType =
+    vk::StructureType::from_raw(1_000_341_002);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicalDeviceFaultFeaturesEXT {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub device_fault: vk::Bool32,
+    pub device_fault_vendor_binary: vk::Bool32,
+}
+
+impl Default for PhysicalDeviceFaultFeaturesEXT {
+    fn default() -> Self {
+        PhysicalDeviceFaultFeaturesEXT {
+            s_type: STRUCTURE_TYPE_PHYSICAL_DEVICE_FAULT_FEATURES_EXT,
+            p_next: ptr::null_mut(),
+            device_fault: vk::FALSE,
+            device_fault_vendor_binary: vk::FALSE,
+        }
+    }
+}
+
+unsafe impl vk::ExtendsDeviceCreateInfo for PhysicalDeviceFaultFeaturesEXT {}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DeviceFaultCountsEXT {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub address_info_count: u32,
+    pub vendor_info_count: u32,
+    pub vendor_binary_size: vk::DeviceSize,
+}
+
+impl Default for DeviceFaultCountsEXT {
+    fn default() -> Self {
+        DeviceFaultCountsEXT {
+            s_type: STRUCTURE_TYPE_DEVICE_FAULT_COUNTS_EXT,
+            p_next: ptr::null_mut(),
+            address_info_count: 0,
+            vendor_info_count: 0,
+            vendor_binary_size: 0,
+        }
+    }
+}
+
+/// The address and vendor information arrays are left out, so their pointers are untyped.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct DeviceFaultInfoEXT {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub description: [c_char; vk::MAX_DESCRIPTION_SIZE],
+    pub p_address_infos: *mut c_void,
+    pub p_vendor_infos: *mut c_void,
+    pub p_vendor_binary_data: *mut c_void,
+}
+
+impl Default for DeviceFaultInfoEXT {
+    fn default() -> Self {
+        DeviceFaultInfoEXT {
+            s_type: STRUCTURE_TYPE_DEVICE_FAULT_INFO_EXT,
+            p_next: ptr::null_mut(),
+            description: [0; vk::MAX_DESCRIPTION_SIZE],
+            p_address_infos: ptr::null_mut(),
+            p_vendor_infos: ptr::null_mut(),
+            p_vendor_binary_data: ptr::null_mut(),
+        }
+    }
+}
+
+impl DeviceFaultInfoEXT {
+    pub fn description_as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.description.as_ptr()) }
+    }
+}
+
+#[allow(non_camel_case_types)]
+pub type PFN_vkGetDeviceFaultInfoEXT = extern "system" fn(
+    device: vk::Device,
+    p_fault_counts: *mut DeviceFaultCountsEXT,
+    p_fault_info: *mut DeviceFaultInfoEXT,
+) -> vk::Result;
+
+#[derive(Clone)]
+pub struct ExtDeviceFaultFn {
+    pub get_device_fault_info_ext: PFN_vkGetDeviceFaultInfoEXT,
+}
+
+unsafe impl Send for ExtDeviceFaultFn {}
+unsafe impl Sync for ExtDeviceFaultFn {}
+
+impl ExtDeviceFaultFn {
+    pub fn name() -> &'static CStr {
+        CStr::from_bytes_with_nul(b"VK_EXT_device_fault\0").expect("Wrong extension string")
+    }
+
+    pub fn load<F>(mut f: F) -> Self
+    where
+        F: FnMut(&CStr) -> *const c_void,
+    {
+        extern "system" fn get_device_fault_info_ext(
+            _device: vk::Device,
+            _p_fault_counts: *mut DeviceFaultCountsEXT,
+            _p_fault_info: *mut DeviceFaultInfoEXT,
+        ) -> vk::Result {
+            panic!("Unable to load get_device_fault_info_ext")
+        }
+
+        unsafe {
+            let get_info = f(CStr::from_bytes_with_nul_unchecked(
+                b"vkGetDeviceFaultInfoEXT\0",
+            ));
+            ExtDeviceFaultFn {
+                get_device_fault_info_ext: if get_info.is_null() {
+                    get_device_fault_info_ext
+                } else {
+                    mem::transmute(get_info)
+                },
+            }
+        }
+    }
+
+    pub unsafe fn get_device_fault_info_ext(
+        &self,
+        device: vk::Device,
+        p_fault_counts: *mut DeviceFaultCountsEXT,
+        p_fault_info: *mut DeviceFaultInfoEXT,
+    ) -> vk::Result {
+        (self.get_device_fault_info_ext)(device, p_fault_counts, p_fault_info)
+    }
+}
@@ -0,0 +1,199 @@
+//! Raw definitions of `VK_KHR_dynamic_rendering`, which the `ash` version in use doesn't expose.
+//!
+//! They follow the layout and naming of the generated `ash` bindings, so switching over
+//! once `ash` is updated only requires changing the paths.
+
+use ash::vk;
+use std::{ffi::CStr, mem, os::raw::c_void, ptr};
+
+pub const STRUCTURE_TYPE_RENDERING_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_044_000);
+pub const STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_044_001);
+pub const STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_044_002);
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_044_003);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicalDeviceDynamicRenderingFeaturesKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub dynamic_rendering: vk::Bool32,
+}
+
+impl Default for PhysicalDeviceDynamicRenderingFeaturesKHR {
+    fn default() -> Self {
+        PhysicalDeviceDynamicRenderingFeaturesKHR {
+            s_type: STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES_KHR,
+            p_next: ptr::null_mut(),
+            dynamic_rendering: vk::FALSE,
+        }
+    }
+}
+
+unsafe impl vk::ExtendsDeviceCreateInfo for PhysicalDeviceDynamicRenderingFeaturesKHR {}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RenderingAttachmentInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub image_view: vk::ImageView,
+    pub image_layout: vk::ImageLayout,
+    pub resolve_mode: vk::ResolveModeFlags,
+    pub resolve_image_view: vk::ImageView,
+    pub resolve_image_layout: vk::ImageLayout,
+    pub load_op: vk::AttachmentLoadOp,
+    pub store_op: vk::AttachmentStoreOp,
+    pub clear_value: vk::ClearValue,
+}
+
+impl Default for RenderingAttachmentInfoKHR {
+    fn default() -> Self {
+        RenderingAttachmentInfoKHR {
+            s_type: STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO_KHR,
+            p_next: ptr::null(),
+            image_view: vk::ImageView::null(),
+            image_layout: vk::ImageLayout::UNDEFINED,
+            resolve_mode: vk::ResolveModeFlags::NONE,
+            resolve_image_view: vk::ImageView::null(),
+            resolve_image_layout: vk::ImageLayout::UNDEFINED,
+            load_op: vk::AttachmentLoadOp::LOAD,
+            store_op: vk::AttachmentStoreOp::STORE,
+            clear_value: unsafe { mem::zeroed() },
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RenderingInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub flags: vk::Flags,
+    pub render_area: vk::Rect2D,
+    pub layer_count: u32,
+    pub view_mask: u32,
+    pub color_attachment_count: u32,
+    pub p_color_attachments: *const RenderingAttachmentInfoKHR,
+    pub p_depth_attachment: *const RenderingAttachmentInfoKHR,
+    pub p_stencil_attachment: *const RenderingAttachmentInfoKHR,
+}
+
+impl Default for RenderingInfoKHR {
+    fn default() -> Self {
+        RenderingInfoKHR {
+            s_type: STRUCTURE_TYPE_RENDERING_INFO_KHR,
+            p_next: ptr::null(),
+            flags: 0,
+            render_area: vk::Rect2D::default(),
+            layer_count: 0,
+            view_mask: 0,
+            color_attachment_count: 0,
+            p_color_attachments: ptr::null(),
+            p_depth_attachment: ptr::null(),
+            p_stencil_attachment: ptr::null(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PipelineRenderingCreateInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub view_mask: u32,
+    pub color_attachment_count: u32,
+    pub p_color_attachment_formats: *const vk::Format,
+    pub depth_attachment_format: vk::Format,
+    pub stencil_attachment_format: vk::Format,
+}
+
+impl Default for PipelineRenderingCreateInfoKHR {
+    fn default() -> Self {
+        PipelineRenderingCreateInfoKHR {
+            s_type: STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO_KHR,
+            p_next: ptr::null(),
+            view_mask: 0,
+            color_attachment_count: 0,
+            p_color_attachment_formats: ptr::null(),
+            depth_attachment_format: vk::Format::UNDEFINED,
+            stencil_attachment_format: vk::Format::UNDEFINED,
+        }
+    }
+}
+
+unsafe impl vk::ExtendsGraphicsPipelineCreateInfo for PipelineRenderingCreateInfoKHR {}
+
+#[allow(non_camel_case_types)]
+pub type PFN_vkCmdBeginRenderingKHR = extern "system" fn(
+    command_buffer: vk::CommandBuffer,
+    p_rendering_info: *const RenderingInfoKHR,
+);
+#[allow(non_camel_case_types)]
+pub type PFN_vkCmdEndRenderingKHR = extern "system" fn(command_buffer: vk::CommandBuffer);
+
+#[derive(Clone)]
+pub struct KhrDynamicRenderingFn {
+    pub cmd_begin_rendering_khr: PFN_vkCmdBeginRenderingKHR,
+    pub cmd_end_rendering_khr: PFN_vkCmdEndRenderingKHR,
+}
+
+unsafe impl Send for KhrDynamicRenderingFn {}
+unsafe impl Sync for KhrDynamicRenderingFn {}
+
+impl KhrDynamicRenderingFn {
+    pub fn name() -> &'static CStr {
+        CStr::from_bytes_with_nul(b"VK_KHR_dynamic_rendering\0").expect("Wrong extension string")
+    }
+
+    pub fn load<F>(mut f: F) -> Self
+    where
+        F: FnMut(&CStr) -> *const c_void,
+    {
+        extern "system" fn cmd_begin_rendering_khr(
+            _command_buffer: vk::CommandBuffer,
+            _p_rendering_info: *const RenderingInfoKHR,
+        ) {
+            panic!("Unable to load cmd_begin_rendering_khr")
+        }
+        extern "system" fn cmd_end_rendering_khr(_command_buffer: vk::CommandBuffer) {
+            panic!("Unable to load cmd_end_rendering_khr")
+        }
+
+        unsafe {
+            let begin = f(CStr::from_bytes_with_nul_unchecked(
+                b"vkCmdBeginRenderingKHR\0",
+            ));
+            let end = f(CStr::from_bytes_with_nul_unchecked(
+                b"vkCmdEndRenderingKHR\0",
+            ));
+            KhrDynamicRenderingFn {
+                cmd_begin_rendering_khr: if begin.is_null() {
+                    cmd_begin_rendering_khr
+                } else {
+                    mem::transmute(begin)
+                },
+                cmd_end_rendering_khr: if end.is_null() {
+                    cmd_end_rendering_khr
+                } else {
+                    mem::transmute(end)
+                },
+            }
+        }
+    }
+
+    pub unsafe fn cmd_begin_rendering_khr(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        p_rendering_info: *const RenderingInfoKHR,
+    ) {
+        (self.cmd_begin_rendering_khr)(command_buffer, p_rendering_info)
+    }
+
+    pub unsafe fn cmd_end_rendering_khr(&self, command_buffer: vk::CommandBuffer) {
+        (self.cmd_end_rendering_khr)(command_buffer)
+    }
+}
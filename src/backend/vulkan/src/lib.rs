@@ -32,15 +32,15 @@ use ash::Entry;
 type Entry = ash::EntryCustom<()>;
 use ash::{
     extensions::{ext, khr, nv::MeshShader},
-    version::{DeviceV1_0, EntryV1_0, InstanceV1_0},
+    version::{DeviceV1_0, DeviceV1_2, EntryV1_0, InstanceV1_0},
     vk,
 };
 
 use hal::{
-    adapter,
-    device::{DeviceLost, OutOfMemory},
+    acceleration_structure as accel, adapter, buffer,
+    device::{Breadcrumb, DeviceLost, OutOfMemory, WaitError},
     display, image, memory,
-    pso::PipelineStage,
+    pso::{PipelineStage, PipelineStage2},
     queue,
     window::{OutOfDate, PresentError, Suboptimal, SurfaceLost},
     Features,
@@ -50,18 +50,26 @@ use std::{
     borrow::Cow,
     cmp,
     ffi::{CStr, CString},
-    fmt, slice,
-    sync::Arc,
+    fmt,
+    ptr::NonNull,
+    slice,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
     thread, unreachable,
 };
 
 mod command;
 mod conv;
 mod device;
+mod device_fault;
+mod dynamic_rendering;
 mod info;
 mod native;
 mod physical_device;
 mod pool;
+mod synchronization2;
 mod window;
 
 pub use physical_device::*;
@@ -69,6 +77,12 @@ pub use physical_device::*;
 // Sets up the maximum count we expect in most cases, but maybe not all of them.
 const ROUGH_MAX_ATTACHMENT_COUNT: usize = 5;
 
+// The host clock that calibrated timestamps are matched with.
+#[cfg(windows)]
+const HOST_TIME_DOMAIN: vk::TimeDomainEXT = vk::TimeDomainEXT::QUERY_PERFORMANCE_COUNTER;
+#[cfg(not(windows))]
+const HOST_TIME_DOMAIN: vk::TimeDomainEXT = vk::TimeDomainEXT::CLOCK_MONOTONIC;
+
 pub struct RawInstance {
     inner: ash::Instance,
     handle_is_external: bool,
@@ -76,6 +90,10 @@ pub struct RawInstance {
     get_physical_device_properties: Option<ExtensionFn<vk::KhrGetPhysicalDeviceProperties2Fn>>,
     display: Option<khr::Display>,
     external_memory_capabilities: Option<ExtensionFn<vk::KhrExternalMemoryCapabilitiesFn>>,
+    /// Physical device level functions of `VK_KHR_performance_query`, which enumerate the counters.
+    performance_query: Option<vk::KhrPerformanceQueryFn>,
+    /// Physical device level functions of `VK_EXT_calibrated_timestamps`, which list the time domains.
+    calibrated_timestamps: Option<vk::ExtCalibratedTimestampsFn>,
 }
 
 pub enum DebugMessenger {
@@ -502,6 +520,27 @@ impl Instance {
                 })
         };
 
+        // `VK_KHR_performance_query` is a device extension, which relies on `VK_KHR_get_physical_device_properties2`.
+        let performance_query = get_physical_device_properties.as_ref().map(|_| {
+            vk::KhrPerformanceQueryFn::load(|name| unsafe {
+                std::mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+            })
+        });
+
+        // `VK_EXT_calibrated_timestamps` is a device extension, so its physical device level
+        // function only gets resolved if one of the drivers provides it.
+        let list_time_domains = unsafe {
+            entry.get_instance_proc_addr(
+                instance.handle(),
+                b"vkGetPhysicalDeviceCalibrateableTimeDomainsEXT\0".as_ptr() as *const _,
+            )
+        };
+        let calibrated_timestamps = list_time_domains.map(|_| {
+            vk::ExtCalibratedTimestampsFn::load(|name| unsafe {
+                std::mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+            })
+        });
+
         let display = extensions
             .iter()
             .find(|&&ext| ext == khr::Display::name())
@@ -562,6 +601,8 @@ impl Instance {
                 get_physical_device_properties,
                 display,
                 external_memory_capabilities,
+                performance_query,
+                calibrated_timestamps,
             }),
             extensions,
             entry,
@@ -829,6 +870,21 @@ impl queue::QueueFamily for QueueFamily {
 struct DeviceExtensionFunctions {
     mesh_shaders: Option<ExtensionFn<MeshShader>>,
     draw_indirect_count: Option<ExtensionFn<khr::DrawIndirectCount>>,
+    timeline_semaphore: Option<ExtensionFn<vk::KhrTimelineSemaphoreFn>>,
+    buffer_device_address: Option<ExtensionFn<vk::KhrBufferDeviceAddressFn>>,
+    dynamic_rendering: Option<dynamic_rendering::KhrDynamicRenderingFn>,
+    acceleration_structure: Option<vk::KhrAccelerationStructureFn>,
+    push_descriptor: Option<khr::PushDescriptor>,
+    descriptor_update_template: Option<ExtensionFn<vk::KhrDescriptorUpdateTemplateFn>>,
+    sampler_ycbcr_conversion: Option<ExtensionFn<vk::KhrSamplerYcbcrConversionFn>>,
+    extended_dynamic_state: Option<vk::ExtExtendedDynamicStateFn>,
+    conditional_rendering: Option<vk::ExtConditionalRenderingFn>,
+    transform_feedback: Option<vk::ExtTransformFeedbackFn>,
+    synchronization2: Option<synchronization2::KhrSynchronization2Fn>,
+    buffer_marker: Option<vk::AmdBufferMarkerFn>,
+    device_fault: Option<device_fault::ExtDeviceFaultFn>,
+    performance_query: Option<vk::KhrPerformanceQueryFn>,
+    calibrated_timestamps: Option<vk::ExtCalibratedTimestampsFn>,
     display_control: Option<vk::ExtDisplayControlFn>,
     memory_requirements2: Option<ExtensionFn<vk::KhrGetMemoryRequirements2Fn>>,
     // The extension does not have its own functions.
@@ -866,6 +922,125 @@ impl<T> ExtensionFn<T> {
     }
 }
 
+/// Number of breadcrumbs a device remembers, to be able to tell which one the
+/// value written last by the device belongs to.
+const BREADCRUMB_HISTORY: usize = 1024;
+
+/// Host visible buffer the command buffers write breadcrumbs into with
+/// `VK_AMD_buffer_marker`, when `hal::Features::DEVICE_FAULT_DIAGNOSTICS` is enabled.
+///
+/// The values are baked into the command buffers, so they get assigned in recording order
+/// rather than in submission order. The value in the buffer is the one written last by any
+/// queue, which tells which breadcrumb the device got past last, but not how far along the
+/// other submissions are. Breadcrumbs of command buffers recorded before the last
+/// `BREADCRUMB_HISTORY` ones, e.g. resubmitted ones, are forgotten and can't be reported.
+struct BreadcrumbTrail {
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    /// Persistent mapping of the value of the last breadcrumb the device got past.
+    mapping: NonNull<u32>,
+    /// Value of the next breadcrumb. Values start from 1, 0 meaning that none was written yet.
+    next_value: AtomicU32,
+    /// Breadcrumbs along with their values, indexed by value modulo `BREADCRUMB_HISTORY`.
+    history: parking_lot::Mutex<Vec<Option<(u32, Breadcrumb)>>>,
+}
+
+// The mapping is only ever read.
+unsafe impl Send for BreadcrumbTrail {}
+unsafe impl Sync for BreadcrumbTrail {}
+
+impl BreadcrumbTrail {
+    unsafe fn new(
+        device: &ash::Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> Result<Self, vk::Result> {
+        let info = vk::BufferCreateInfo::builder()
+            .size(4)
+            .usage(vk::BufferUsageFlags::TRANSFER_DST)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = device.create_buffer(&info, None)?;
+
+        let requirements = device.get_buffer_memory_requirements(buffer);
+        let flags = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+        let memory_type = memory_properties.memory_types
+            [..memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+            .find(|&(i, ty)| {
+                requirements.memory_type_bits & (1 << i) != 0 && ty.property_flags.contains(flags)
+            });
+        let memory_type = match memory_type {
+            Some((index, _)) => index as u32,
+            None => {
+                device.destroy_buffer(buffer, None);
+                return Err(vk::Result::ERROR_FEATURE_NOT_PRESENT);
+            }
+        };
+        let info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type);
+        let memory = match device.allocate_memory(&info, None) {
+            Ok(memory) => memory,
+            Err(e) => {
+                device.destroy_buffer(buffer, None);
+                return Err(e);
+            }
+        };
+        let mapping = device
+            .bind_buffer_memory(buffer, memory, 0)
+            .and_then(|()| device.map_memory(memory, 0, 4, vk::MemoryMapFlags::empty()));
+        match mapping {
+            Ok(ptr) => {
+                let mapping = NonNull::new_unchecked(ptr as *mut u32);
+                mapping.as_ptr().write_volatile(0);
+                Ok(BreadcrumbTrail {
+                    buffer,
+                    memory,
+                    mapping,
+                    next_value: AtomicU32::new(1),
+                    history: parking_lot::Mutex::new(vec![None; BREADCRUMB_HISTORY]),
+                })
+            }
+            Err(e) => {
+                device.destroy_buffer(buffer, None);
+                device.free_memory(memory, None);
+                Err(e)
+            }
+        }
+    }
+
+    /// Remember `breadcrumb` and return the value to write for it.
+    fn push(&self, breadcrumb: Breadcrumb) -> u32 {
+        let mut value = self.next_value.fetch_add(1, Ordering::Relaxed);
+        if value == 0 {
+            // Skip the value meaning "none" on wrap around
+            value = self.next_value.fetch_add(1, Ordering::Relaxed);
+        }
+        self.history.lock()[value as usize % BREADCRUMB_HISTORY] = Some((value, breadcrumb));
+        value
+    }
+
+    /// Return the last breadcrumb the device got past, if it's still remembered.
+    fn last(&self) -> Option<Breadcrumb> {
+        let value = unsafe { self.mapping.as_ptr().read_volatile() };
+        if value == 0 {
+            return None;
+        }
+        match self.history.lock()[value as usize % BREADCRUMB_HISTORY] {
+            Some((v, ref breadcrumb)) if v == value => Some(breadcrumb.clone()),
+            _ => {
+                warn!("Breadcrumb {} is too old to be reported", value);
+                None
+            }
+        }
+    }
+
+    unsafe fn destroy(&self, device: &ash::Device) {
+        device.destroy_buffer(self.buffer, None);
+        device.free_memory(self.memory, None);
+    }
+}
+
 #[doc(hidden)]
 pub struct RawDevice {
     raw: ash::Device,
@@ -880,6 +1055,7 @@ pub struct RawDevice {
     imageless_framebuffers: bool,
     image_view_usage: bool,
     timestamp_period: f32,
+    breadcrumbs: Option<BreadcrumbTrail>,
 }
 
 impl fmt::Debug for RawDevice {
@@ -889,6 +1065,11 @@ impl fmt::Debug for RawDevice {
 }
 impl Drop for RawDevice {
     fn drop(&mut self) {
+        if let Some(ref breadcrumbs) = self.breadcrumbs {
+            unsafe {
+                breadcrumbs.destroy(&self.raw);
+            }
+        }
         if !self.handle_is_external {
             unsafe {
                 self.raw.destroy_device(None);
@@ -908,6 +1089,100 @@ impl RawDevice {
         conv::map_viewport(rect, flip_y, shift_y)
     }
 
+    fn acceleration_structure_fn(&self) -> &vk::KhrAccelerationStructureFn {
+        self.extension_fns
+            .acceleration_structure
+            .as_ref()
+            .expect("Feature ACCELERATION_STRUCTURE must be enabled")
+    }
+
+    unsafe fn buffer_device_address(
+        &self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+    ) -> vk::DeviceAddress {
+        let info = vk::BufferDeviceAddressInfo::builder().buffer(buffer.raw);
+        let address = match self
+            .extension_fns
+            .buffer_device_address
+            .as_ref()
+            .expect("Feature BUFFER_DEVICE_ADDRESS must be enabled")
+        {
+            ExtensionFn::Extension(t) => t.get_buffer_device_address_khr(self.raw.handle(), &*info),
+            ExtensionFn::Promoted => self.raw.get_buffer_device_address(&info),
+        };
+        address + offset
+    }
+
+    unsafe fn map_acceleration_structure_geometry(
+        &self,
+        geometry: &accel::Geometry<Backend>,
+    ) -> vk::AccelerationStructureGeometryKHR {
+        let (geometry_type, data) = match geometry.data {
+            accel::GeometryData::Triangles(ref triangles) => {
+                let mut data = vk::AccelerationStructureGeometryTrianglesDataKHR::builder()
+                    .vertex_format(conv::map_format(triangles.vertex_format))
+                    .vertex_data(vk::DeviceOrHostAddressConstKHR {
+                        device_address: self.buffer_device_address(
+                            triangles.vertex_buffer,
+                            triangles.vertex_buffer_offset,
+                        ),
+                    })
+                    .vertex_stride(triangles.vertex_buffer_stride as _)
+                    .max_vertex(triangles.max_vertex);
+                data = match triangles.index_buffer {
+                    Some((buffer, offset, index_type)) => data
+                        .index_type(conv::map_index_type(index_type))
+                        .index_data(vk::DeviceOrHostAddressConstKHR {
+                            device_address: self.buffer_device_address(buffer, offset),
+                        }),
+                    None => data.index_type(vk::IndexType::NONE_KHR),
+                };
+                if let Some((buffer, offset)) = triangles.transform {
+                    data = data.transform_data(vk::DeviceOrHostAddressConstKHR {
+                        device_address: self.buffer_device_address(buffer, offset),
+                    });
+                }
+                (
+                    vk::GeometryTypeKHR::TRIANGLES,
+                    vk::AccelerationStructureGeometryDataKHR {
+                        triangles: data.build(),
+                    },
+                )
+            }
+            accel::GeometryData::Aabbs(ref aabbs) => (
+                vk::GeometryTypeKHR::AABBS,
+                vk::AccelerationStructureGeometryDataKHR {
+                    aabbs: vk::AccelerationStructureGeometryAabbsDataKHR::builder()
+                        .data(vk::DeviceOrHostAddressConstKHR {
+                            device_address: self
+                                .buffer_device_address(aabbs.buffer, aabbs.buffer_offset),
+                        })
+                        .stride(aabbs.buffer_stride as _)
+                        .build(),
+                },
+            ),
+            accel::GeometryData::Instances(ref instances) => (
+                vk::GeometryTypeKHR::INSTANCES,
+                vk::AccelerationStructureGeometryDataKHR {
+                    instances: vk::AccelerationStructureGeometryInstancesDataKHR::builder()
+                        .array_of_pointers(false)
+                        .data(vk::DeviceOrHostAddressConstKHR {
+                            device_address: self
+                                .buffer_device_address(instances.buffer, instances.buffer_offset),
+                        })
+                        .build(),
+                },
+            ),
+        };
+
+        vk::AccelerationStructureGeometryKHR::builder()
+            .geometry_type(geometry_type)
+            .geometry(data)
+            .flags(conv::map_geometry_flags(geometry.flags))
+            .build()
+    }
+
     unsafe fn set_object_name(
         &self,
         object_type: vk::ObjectType,
@@ -1007,6 +1282,120 @@ impl queue::Queue<Backend> for Queue {
         }
     }
 
+    unsafe fn submit_timeline<'a, Ic, Iw, Is>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut native::Fence>,
+    ) where
+        Ic: Iterator<Item = &'a command::CommandBuffer>,
+        Iw: Iterator<Item = (&'a native::Semaphore, u64, PipelineStage)>,
+        Is: Iterator<Item = (&'a native::Semaphore, u64)>,
+    {
+        assert!(
+            self.device.features.contains(Features::TIMELINE_SEMAPHORE),
+            "Feature TIMELINE_SEMAPHORE must be enabled to call submit_timeline"
+        );
+        //TODO: avoid heap allocations
+        let mut waits = Vec::new();
+        let mut wait_values = Vec::new();
+        let mut stages = Vec::new();
+        let mut signals = Vec::new();
+        let mut signal_values = Vec::new();
+
+        let buffers = command_buffers.map(|cmd| cmd.raw).collect::<Vec<_>>();
+        for (semaphore, value, stage) in wait_semaphores {
+            waits.push(semaphore.0);
+            wait_values.push(value);
+            stages.push(conv::map_pipeline_stage(stage));
+        }
+        for (semaphore, value) in signal_semaphores {
+            signals.push(semaphore.0);
+            signal_values.push(value);
+        }
+
+        // Values of binary semaphores are ignored by the driver
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .wait_semaphore_values(&wait_values)
+            .signal_semaphore_values(&signal_values);
+        let mut info = vk::SubmitInfo::builder()
+            .wait_semaphores(&waits)
+            .command_buffers(&buffers)
+            .signal_semaphores(&signals)
+            .push_next(&mut timeline_info);
+        // If count is zero, AMD driver crashes if nullptr is not set for stage masks
+        if !stages.is_empty() {
+            info = info.wait_dst_stage_mask(&stages);
+        }
+
+        let fence_raw = fence.map(|fence| fence.0).unwrap_or(vk::Fence::null());
+
+        let result = self.device.raw.queue_submit(*self.raw, &[*info], fence_raw);
+        if let Err(e) = result {
+            error!("Submit resulted in {:?}", e);
+        }
+    }
+
+    unsafe fn submit2<'a, Ic, Iw, Is>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut native::Fence>,
+    ) where
+        Ic: Iterator<Item = &'a command::CommandBuffer>,
+        Iw: Iterator<Item = (&'a native::Semaphore, PipelineStage2)>,
+        Is: Iterator<Item = (&'a native::Semaphore, PipelineStage2)>,
+    {
+        let extension = match self.device.extension_fns.synchronization2 {
+            Some(ref extension) => extension,
+            None => {
+                return self.submit(
+                    command_buffers,
+                    wait_semaphores.map(|(semaphore, stages)| (semaphore, stages.lower())),
+                    signal_semaphores.map(|(semaphore, _)| semaphore),
+                    fence,
+                )
+            }
+        };
+
+        let map_semaphore = |(semaphore, stages): (&native::Semaphore, PipelineStage2)| {
+            synchronization2::SemaphoreSubmitInfoKHR {
+                semaphore: semaphore.0,
+                stage_mask: conv::map_pipeline_stage2(stages),
+                ..Default::default()
+            }
+        };
+
+        //TODO: avoid heap allocations
+        let waits = wait_semaphores.map(map_semaphore).collect::<Vec<_>>();
+        let signals = signal_semaphores.map(map_semaphore).collect::<Vec<_>>();
+        let buffers = command_buffers
+            .map(|cmd| synchronization2::CommandBufferSubmitInfoKHR {
+                command_buffer: cmd.raw,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let info = synchronization2::SubmitInfo2KHR {
+            wait_semaphore_info_count: waits.len() as u32,
+            p_wait_semaphore_infos: waits.as_ptr(),
+            command_buffer_info_count: buffers.len() as u32,
+            p_command_buffer_infos: buffers.as_ptr(),
+            signal_semaphore_info_count: signals.len() as u32,
+            p_signal_semaphore_infos: signals.as_ptr(),
+            ..Default::default()
+        };
+
+        let fence_raw = fence.map(|fence| fence.0).unwrap_or(vk::Fence::null());
+
+        let result = extension.queue_submit2_khr(*self.raw, 1, &info, fence_raw);
+        if result != vk::Result::SUCCESS {
+            error!("Submit resulted in {:?}", result);
+        }
+    }
+
     unsafe fn bind_sparse<'a, Iw, Is, Ibi, Ib, Iii, Io, Ii>(
         &mut self,
         wait_semaphores: Iw,
@@ -1183,11 +1572,12 @@ impl queue::Queue<Backend> for Queue {
         }
     }
 
-    fn wait_idle(&mut self) -> Result<(), OutOfMemory> {
+    fn wait_idle(&mut self) -> Result<(), WaitError> {
         match unsafe { self.device.raw.queue_wait_idle(*self.raw) } {
             Ok(()) => Ok(()),
-            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(OutOfMemory::Host),
-            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(OutOfMemory::Device),
+            Err(vk::Result::ERROR_DEVICE_LOST) => Err(DeviceLost.into()),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(OutOfMemory::Device.into()),
             Err(_) => unreachable!(),
         }
     }
@@ -1200,6 +1590,7 @@ impl queue::Queue<Backend> for Queue {
 #[derive(Debug)]
 pub struct Device {
     shared: Arc<RawDevice>,
+    physical_device: vk::PhysicalDevice,
     vendor_id: u32,
     valid_ash_memory_types: u32,
     render_doc: gfx_renderdoc::RenderDoc,
@@ -1237,6 +1628,7 @@ impl hal::Backend for Backend {
     type PipelineLayout = native::PipelineLayout;
     type PipelineCache = native::PipelineCache;
     type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorUpdateTemplate = native::DescriptorUpdateTemplate;
     type DescriptorPool = native::DescriptorPool;
     type DescriptorSet = native::DescriptorSet;
 
@@ -1247,4 +1639,6 @@ impl hal::Backend for Backend {
 
     type Display = native::Display;
     type DisplayMode = native::DisplayMode;
+
+    type AccelerationStructure = native::AccelerationStructure;
 }
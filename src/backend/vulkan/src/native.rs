@@ -56,10 +56,14 @@ pub struct ImageView {
     pub(crate) image: vk::Image,
     pub(crate) raw: vk::ImageView,
     pub(crate) range: SubresourceRange,
+    pub(crate) ycbcr_conversion: Option<vk::SamplerYcbcrConversion>,
 }
 
 #[derive(Debug, Hash)]
-pub struct Sampler(pub vk::Sampler);
+pub struct Sampler {
+    pub(crate) raw: vk::Sampler,
+    pub(crate) ycbcr_conversion: Option<vk::SamplerYcbcrConversion>,
+}
 
 #[derive(Debug, Hash)]
 pub struct RenderPass {
@@ -93,9 +97,34 @@ pub struct DescriptorSet {
     pub(crate) bindings: SortedBindings,
 }
 
-#[derive(Debug, Hash)]
+/// A descriptor update template entry, restricted to a single binding.
+#[derive(Debug)]
+pub(crate) struct TemplateEntry {
+    /// Location of the descriptors in the user data.
+    pub(crate) src: pso::DescriptorUpdateTemplateEntry,
+    /// Offset of the converted descriptors in the raw data.
+    pub(crate) dst_offset: usize,
+    /// Size of a converted descriptor.
+    pub(crate) dst_stride: usize,
+}
+
+#[derive(Debug)]
+pub enum DescriptorUpdateTemplate {
+    Native {
+        raw: vk::DescriptorUpdateTemplate,
+        entries: Vec<TemplateEntry>,
+        /// Size of the raw data, in 8-byte words.
+        data_words: usize,
+    },
+    /// Fallback for Vulkan 1.0 devices without `VK_KHR_descriptor_update_template`.
+    Emulated(pso::EmulatedDescriptorUpdateTemplate),
+}
+
+#[derive(Debug)]
 pub struct PipelineLayout {
     pub(crate) raw: vk::PipelineLayout,
+    /// Bindings of the set layouts, needed to push descriptors.
+    pub(crate) set_bindings: Vec<SortedBindings>,
 }
 
 #[derive(Debug)]
@@ -240,3 +269,6 @@ pub struct Display(pub vk::DisplayKHR);
 
 #[derive(Debug, Hash)]
 pub struct DisplayMode(pub vk::DisplayModeKHR);
+
+#[derive(Debug, Hash)]
+pub struct AccelerationStructure(pub vk::AccelerationStructureKHR);
@@ -1,5 +1,8 @@
 use ash::{
-    extensions::{khr::DrawIndirectCount, khr::Swapchain, nv::MeshShader},
+    extensions::{
+        khr::{DrawIndirectCount, PushDescriptor, Swapchain},
+        nv::MeshShader,
+    },
     version::{DeviceV1_0, InstanceV1_0},
     vk,
 };
@@ -12,15 +15,16 @@ use hal::{
     device::{CreationError, OutOfMemory},
     display, external_memory, format, image,
     pso::PatchSize,
-    queue, DescriptorLimits, DownlevelProperties, DynamicStates, ExternalMemoryLimits, Features,
-    Limits, PhysicalDeviceProperties,
+    query, queue, DescriptorLimits, DownlevelProperties, DynamicStates, ExternalMemoryLimits,
+    Features, Limits, PhysicalDeviceProperties,
 };
 
 use std::{ffi::CStr, fmt, mem, ptr, sync::Arc};
 
 use crate::{
-    conv, info, native, Backend, Device, DeviceExtensionFunctions, ExtensionFn, Queue, QueueFamily,
-    RawDevice, RawInstance, Version,
+    conv, device_fault, dynamic_rendering, info, native, synchronization2, Backend,
+    BreadcrumbTrail, Device, DeviceExtensionFunctions, ExtensionFn, Queue, QueueFamily, RawDevice,
+    RawInstance, Version, HOST_TIME_DOMAIN,
 };
 
 /// Aggregate of the `vk::PhysicalDevice*Features` structs used by `gfx`.
@@ -31,6 +35,20 @@ pub struct PhysicalDeviceFeatures {
     descriptor_indexing: Option<vk::PhysicalDeviceDescriptorIndexingFeaturesEXT>,
     mesh_shader: Option<vk::PhysicalDeviceMeshShaderFeaturesNV>,
     imageless_framebuffer: Option<vk::PhysicalDeviceImagelessFramebufferFeaturesKHR>,
+    timeline_semaphore: Option<vk::PhysicalDeviceTimelineSemaphoreFeaturesKHR>,
+    buffer_device_address: Option<vk::PhysicalDeviceBufferDeviceAddressFeaturesKHR>,
+    multiview: Option<vk::PhysicalDeviceMultiviewFeatures>,
+    sampler_ycbcr_conversion: Option<vk::PhysicalDeviceSamplerYcbcrConversionFeatures>,
+    dynamic_rendering: Option<dynamic_rendering::PhysicalDeviceDynamicRenderingFeaturesKHR>,
+    acceleration_structure: Option<vk::PhysicalDeviceAccelerationStructureFeaturesKHR>,
+    ray_query: Option<vk::PhysicalDeviceRayQueryFeaturesKHR>,
+    ray_tracing_pipeline: Option<vk::PhysicalDeviceRayTracingPipelineFeaturesKHR>,
+    extended_dynamic_state: Option<vk::PhysicalDeviceExtendedDynamicStateFeaturesEXT>,
+    conditional_rendering: Option<vk::PhysicalDeviceConditionalRenderingFeaturesEXT>,
+    transform_feedback: Option<vk::PhysicalDeviceTransformFeedbackFeaturesEXT>,
+    synchronization2: Option<synchronization2::PhysicalDeviceSynchronization2FeaturesKHR>,
+    device_fault: Option<device_fault::PhysicalDeviceFaultFeaturesEXT>,
+    performance_query: Option<vk::PhysicalDevicePerformanceQueryFeaturesKHR>,
 }
 
 // This is safe because the structs have `p_next: *mut c_void`, which we null out/never read.
@@ -57,6 +75,48 @@ impl PhysicalDeviceFeatures {
         if let Some(ref mut feature) = self.imageless_framebuffer {
             info = info.push_next(feature);
         }
+        if let Some(ref mut feature) = self.timeline_semaphore {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.buffer_device_address {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.multiview {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.sampler_ycbcr_conversion {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.dynamic_rendering {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.acceleration_structure {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.ray_query {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.ray_tracing_pipeline {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.extended_dynamic_state {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.conditional_rendering {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.transform_feedback {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.synchronization2 {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.device_fault {
+            info = info.push_next(feature);
+        }
+        if let Some(ref mut feature) = self.performance_query {
+            info = info.push_next(feature);
+        }
 
         info
     }
@@ -187,6 +247,10 @@ impl PhysicalDeviceFeatures {
                         )
                         .sampler_filter_minmax(features.contains(Features::SAMPLER_REDUCTION))
                         .imageless_framebuffer(supports_vulkan12_imageless_framebuffer)
+                        .timeline_semaphore(features.contains(Features::TIMELINE_SEMAPHORE))
+                        .buffer_device_address(features.intersects(
+                            Features::BUFFER_DEVICE_ADDRESS | Features::ACCELERATION_STRUCTURE,
+                        ))
                         .build(),
                 )
             } else {
@@ -238,6 +302,158 @@ impl PhysicalDeviceFeatures {
             } else {
                 None
             },
+            timeline_semaphore: if enabled_extensions.contains(&vk::KhrTimelineSemaphoreFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceTimelineSemaphoreFeaturesKHR::builder()
+                        .timeline_semaphore(features.contains(Features::TIMELINE_SEMAPHORE))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            buffer_device_address: if enabled_extensions
+                .contains(&vk::KhrBufferDeviceAddressFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceBufferDeviceAddressFeaturesKHR::builder()
+                        .buffer_device_address(features.contains(Features::BUFFER_DEVICE_ADDRESS))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            multiview: if api_version >= Version::V1_1
+                || enabled_extensions.contains(&vk::KhrMultiviewFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceMultiviewFeatures::builder()
+                        .multiview(features.contains(Features::MULTIVIEW))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            sampler_ycbcr_conversion: if api_version >= Version::V1_1
+                || enabled_extensions.contains(&vk::KhrSamplerYcbcrConversionFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceSamplerYcbcrConversionFeatures::builder()
+                        .sampler_ycbcr_conversion(
+                            features.contains(Features::SAMPLER_YCBCR_CONVERSION),
+                        )
+                        .build(),
+                )
+            } else {
+                None
+            },
+            dynamic_rendering: if enabled_extensions
+                .contains(&dynamic_rendering::KhrDynamicRenderingFn::name())
+            {
+                Some(
+                    dynamic_rendering::PhysicalDeviceDynamicRenderingFeaturesKHR {
+                        dynamic_rendering: features.contains(Features::DYNAMIC_RENDERING) as _,
+                        ..Default::default()
+                    },
+                )
+            } else {
+                None
+            },
+            acceleration_structure: if enabled_extensions
+                .contains(&vk::KhrAccelerationStructureFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceAccelerationStructureFeaturesKHR::builder()
+                        .acceleration_structure(features.contains(Features::ACCELERATION_STRUCTURE))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            ray_query: if enabled_extensions.contains(&vk::KhrRayQueryFn::name()) {
+                Some(
+                    vk::PhysicalDeviceRayQueryFeaturesKHR::builder()
+                        .ray_query(features.contains(Features::RAY_QUERY))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            ray_tracing_pipeline: if enabled_extensions
+                .contains(&vk::KhrRayTracingPipelineFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::builder()
+                        .ray_tracing_pipeline(features.contains(Features::RAY_TRACING_PIPELINE))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            // The feature is required to be supported along with the extension.
+            extended_dynamic_state: if enabled_extensions
+                .contains(&vk::ExtExtendedDynamicStateFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceExtendedDynamicStateFeaturesEXT::builder()
+                        .extended_dynamic_state(true)
+                        .build(),
+                )
+            } else {
+                None
+            },
+            conditional_rendering: if enabled_extensions
+                .contains(&vk::ExtConditionalRenderingFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceConditionalRenderingFeaturesEXT::builder()
+                        .conditional_rendering(features.contains(Features::CONDITIONAL_RENDERING))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            transform_feedback: if enabled_extensions.contains(&vk::ExtTransformFeedbackFn::name())
+            {
+                Some(
+                    vk::PhysicalDeviceTransformFeedbackFeaturesEXT::builder()
+                        .transform_feedback(features.contains(Features::TRANSFORM_FEEDBACK))
+                        .build(),
+                )
+            } else {
+                None
+            },
+            synchronization2: if enabled_extensions
+                .contains(&synchronization2::KhrSynchronization2Fn::name())
+            {
+                Some(
+                    synchronization2::PhysicalDeviceSynchronization2FeaturesKHR {
+                        synchronization2: features.contains(Features::SYNCHRONIZATION_2) as _,
+                        ..Default::default()
+                    },
+                )
+            } else {
+                None
+            },
+            device_fault: if enabled_extensions.contains(&device_fault::ExtDeviceFaultFn::name()) {
+                Some(device_fault::PhysicalDeviceFaultFeaturesEXT {
+                    device_fault: features.contains(Features::DEVICE_FAULT_DIAGNOSTICS) as _,
+                    ..Default::default()
+                })
+            } else {
+                None
+            },
+            performance_query: if enabled_extensions.contains(&vk::KhrPerformanceQueryFn::name()) {
+                Some(
+                    vk::PhysicalDevicePerformanceQueryFeaturesKHR::builder()
+                        .performance_counter_query_pools(
+                            features.contains(Features::PERFORMANCE_QUERY),
+                        )
+                        .build(),
+                )
+            } else {
+                None
+            },
         }
     }
 
@@ -438,6 +654,10 @@ impl PhysicalDeviceFeatures {
             bits |= Features::DRAW_INDIRECT_COUNT
         }
 
+        if info.supports_extension(PushDescriptor::name()) {
+            bits |= Features::PUSH_DESCRIPTOR
+        }
+
         if info.supports_extension(vk::ExtConservativeRasterizationFn::name()) {
             bits |= Features::CONSERVATIVE_RASTERIZATION
         }
@@ -474,6 +694,12 @@ impl PhysicalDeviceFeatures {
             if vulkan_1_2.draw_indirect_count != 0 {
                 bits |= Features::DRAW_INDIRECT_COUNT
             }
+            if vulkan_1_2.timeline_semaphore != 0 {
+                bits |= Features::TIMELINE_SEMAPHORE;
+            }
+            if vulkan_1_2.buffer_device_address != 0 {
+                bits |= Features::BUFFER_DEVICE_ADDRESS;
+            }
         }
 
         if let Some(ref descriptor_indexing) = self.descriptor_indexing {
@@ -494,6 +720,87 @@ impl PhysicalDeviceFeatures {
             }
         }
 
+        if let Some(ref timeline_semaphore) = self.timeline_semaphore {
+            if timeline_semaphore.timeline_semaphore != 0 {
+                bits |= Features::TIMELINE_SEMAPHORE;
+            }
+        }
+
+        if let Some(ref buffer_device_address) = self.buffer_device_address {
+            if buffer_device_address.buffer_device_address != 0 {
+                bits |= Features::BUFFER_DEVICE_ADDRESS;
+            }
+        }
+
+        if let Some(ref multiview) = self.multiview {
+            if multiview.multiview != 0 {
+                bits |= Features::MULTIVIEW;
+            }
+        }
+
+        if let Some(ref sampler_ycbcr_conversion) = self.sampler_ycbcr_conversion {
+            if sampler_ycbcr_conversion.sampler_ycbcr_conversion != 0 {
+                bits |= Features::SAMPLER_YCBCR_CONVERSION;
+            }
+        }
+
+        if let Some(ref conditional_rendering) = self.conditional_rendering {
+            if conditional_rendering.conditional_rendering != 0 {
+                bits |= Features::CONDITIONAL_RENDERING;
+            }
+        }
+
+        if let Some(ref transform_feedback) = self.transform_feedback {
+            if transform_feedback.transform_feedback != 0 {
+                bits |= Features::TRANSFORM_FEEDBACK;
+            }
+        }
+
+        if let Some(ref synchronization2) = self.synchronization2 {
+            if synchronization2.synchronization2 != 0 {
+                bits |= Features::SYNCHRONIZATION_2;
+            }
+        }
+
+        if let Some(ref device_fault) = self.device_fault {
+            if device_fault.device_fault != 0 {
+                bits |= Features::DEVICE_FAULT_DIAGNOSTICS;
+            }
+        }
+
+        if let Some(ref performance_query) = self.performance_query {
+            if performance_query.performance_counter_query_pools != 0 {
+                bits |= Features::PERFORMANCE_QUERY;
+            }
+        }
+
+        if let Some(ref dynamic_rendering) = self.dynamic_rendering {
+            if dynamic_rendering.dynamic_rendering != 0 {
+                bits |= Features::DYNAMIC_RENDERING;
+            }
+        }
+
+        // Acceleration structures are built from buffer device addresses.
+        let buffer_device_address = self
+            .vulkan_1_2
+            .map_or(false, |vulkan_1_2| vulkan_1_2.buffer_device_address != 0);
+        if let Some(ref acceleration_structure) = self.acceleration_structure {
+            if acceleration_structure.acceleration_structure != 0 && buffer_device_address {
+                bits |= Features::ACCELERATION_STRUCTURE;
+
+                if let Some(ref ray_query) = self.ray_query {
+                    if ray_query.ray_query != 0 {
+                        bits |= Features::RAY_QUERY;
+                    }
+                }
+                if let Some(ref ray_tracing_pipeline) = self.ray_tracing_pipeline {
+                    if ray_tracing_pipeline.ray_tracing_pipeline != 0 {
+                        bits |= Features::RAY_TRACING_PIPELINE;
+                    }
+                }
+            }
+        }
+
         if let Some(ref mesh_shader) = self.mesh_shader {
             if mesh_shader.task_shader != 0 {
                 bits |= Features::TASK_SHADER;
@@ -587,6 +894,90 @@ impl PhysicalDeviceInfo {
             requested_extensions.push(DrawIndirectCount::name());
         }
 
+        if self.api_version() < Version::V1_2
+            && requested_features.contains(Features::TIMELINE_SEMAPHORE)
+        {
+            requested_extensions.push(vk::KhrTimelineSemaphoreFn::name());
+        }
+
+        if self.api_version() < Version::V1_2
+            && requested_features.contains(Features::BUFFER_DEVICE_ADDRESS)
+        {
+            requested_extensions.push(vk::KhrBufferDeviceAddressFn::name());
+        }
+
+        if self.api_version() < Version::V1_1 && requested_features.contains(Features::MULTIVIEW) {
+            requested_extensions.push(vk::KhrMultiviewFn::name());
+        }
+
+        if self.api_version() < Version::V1_1
+            && requested_features.contains(Features::SAMPLER_YCBCR_CONVERSION)
+        {
+            requested_extensions.push(vk::KhrGetMemoryRequirements2Fn::name());
+            requested_extensions.push(vk::KhrBindMemory2Fn::name());
+            requested_extensions.push(vk::KhrSamplerYcbcrConversionFn::name());
+        }
+
+        if requested_features.contains(Features::DYNAMIC_RENDERING) {
+            requested_extensions.push(dynamic_rendering::KhrDynamicRenderingFn::name());
+        }
+
+        if requested_features.intersects(
+            Features::ACCELERATION_STRUCTURE | Features::RAY_QUERY | Features::RAY_TRACING_PIPELINE,
+        ) {
+            requested_extensions.push(vk::KhrAccelerationStructureFn::name());
+            requested_extensions.push(vk::KhrDeferredHostOperationsFn::name());
+        }
+
+        if requested_features.contains(Features::RAY_QUERY) {
+            requested_extensions.push(vk::KhrRayQueryFn::name());
+        }
+
+        if requested_features.contains(Features::RAY_TRACING_PIPELINE) {
+            requested_extensions.push(vk::KhrRayTracingPipelineFn::name());
+        }
+
+        if requested_features.contains(Features::PUSH_DESCRIPTOR) {
+            requested_extensions.push(PushDescriptor::name());
+        }
+
+        if requested_features.contains(Features::CONDITIONAL_RENDERING) {
+            requested_extensions.push(vk::ExtConditionalRenderingFn::name());
+        }
+
+        if requested_features.contains(Features::TRANSFORM_FEEDBACK) {
+            requested_extensions.push(vk::ExtTransformFeedbackFn::name());
+        }
+
+        if requested_features.contains(Features::MEMORY_BUDGET) {
+            requested_extensions.push(vk::ExtMemoryBudgetFn::name());
+        }
+
+        if requested_features.contains(Features::SYNCHRONIZATION_2) {
+            requested_extensions.push(synchronization2::KhrSynchronization2Fn::name());
+        }
+
+        if requested_features.contains(Features::DEVICE_FAULT_DIAGNOSTICS) {
+            // Breadcrumbs and fault descriptions are reported independently,
+            // so only the extensions of the device are requested.
+            for &extension in &[
+                vk::AmdBufferMarkerFn::name(),
+                device_fault::ExtDeviceFaultFn::name(),
+            ] {
+                if self.supports_extension(extension) {
+                    requested_extensions.push(extension);
+                }
+            }
+        }
+
+        if requested_features.contains(Features::PERFORMANCE_QUERY) {
+            requested_extensions.push(vk::KhrPerformanceQueryFn::name());
+        }
+
+        if requested_features.contains(Features::CALIBRATED_TIMESTAMPS) {
+            requested_extensions.push(vk::ExtCalibratedTimestampsFn::name());
+        }
+
         if requested_features.contains(Features::CONSERVATIVE_RASTERIZATION) {
             requested_extensions.push(vk::ExtConservativeRasterizationFn::name());
             requested_extensions.push(vk::KhrGetDisplayProperties2Fn::name()); // TODO NOT NEEDED, RIGHT?
@@ -596,6 +987,16 @@ impl PhysicalDeviceInfo {
             requested_extensions.push(vk::ExtDisplayControlFn::name());
         }
 
+        if self.supports_extension(vk::ExtExtendedDynamicStateFn::name()) {
+            requested_extensions.push(vk::ExtExtendedDynamicStateFn::name());
+        }
+
+        if self.api_version() < Version::V1_1
+            && self.supports_extension(vk::KhrDescriptorUpdateTemplateFn::name())
+        {
+            requested_extensions.push(vk::KhrDescriptorUpdateTemplateFn::name());
+        }
+
         if requested_features.contains(Features::EXTERNAL_MEMORY) {
             if self.api_version() < Version::V1_1 {
                 requested_extensions.push(vk::KhrGetPhysicalDeviceProperties2Fn::name());
@@ -621,6 +1022,10 @@ impl PhysicalDeviceInfo {
                 requested_extensions.push(vk::ExtImageDrmFormatModifierFn::name());
             }
         }
+
+        // Some extensions are required by several features.
+        requested_extensions.sort();
+        requested_extensions.dedup();
         requested_extensions
     }
 
@@ -679,6 +1084,125 @@ impl PhysicalDeviceInfo {
                 mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
             }
 
+            if device_properties.api_version() < Version::V1_2
+                && device_properties.supports_extension(vk::KhrTimelineSemaphoreFn::name())
+            {
+                features.timeline_semaphore =
+                    Some(vk::PhysicalDeviceTimelineSemaphoreFeaturesKHR::builder().build());
+
+                let mut_ref = features.timeline_semaphore.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.api_version() < Version::V1_2
+                && device_properties.supports_extension(vk::KhrBufferDeviceAddressFn::name())
+            {
+                features.buffer_device_address =
+                    Some(vk::PhysicalDeviceBufferDeviceAddressFeaturesKHR::builder().build());
+
+                let mut_ref = features.buffer_device_address.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.api_version() >= Version::V1_1
+                || device_properties.supports_extension(vk::KhrMultiviewFn::name())
+            {
+                features.multiview = Some(vk::PhysicalDeviceMultiviewFeatures::builder().build());
+
+                let mut_ref = features.multiview.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.api_version() >= Version::V1_1
+                || device_properties.supports_extension(vk::KhrSamplerYcbcrConversionFn::name())
+            {
+                features.sampler_ycbcr_conversion =
+                    Some(vk::PhysicalDeviceSamplerYcbcrConversionFeatures::builder().build());
+
+                let mut_ref = features.sampler_ycbcr_conversion.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.supports_extension(vk::ExtConditionalRenderingFn::name()) {
+                features.conditional_rendering =
+                    Some(vk::PhysicalDeviceConditionalRenderingFeaturesEXT::builder().build());
+
+                let mut_ref = features.conditional_rendering.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.supports_extension(vk::ExtTransformFeedbackFn::name()) {
+                features.transform_feedback =
+                    Some(vk::PhysicalDeviceTransformFeedbackFeaturesEXT::builder().build());
+
+                let mut_ref = features.transform_feedback.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.supports_extension(synchronization2::KhrSynchronization2Fn::name())
+            {
+                features.synchronization2 = Some(Default::default());
+
+                let mut_ref = features.synchronization2.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.supports_extension(device_fault::ExtDeviceFaultFn::name()) {
+                features.device_fault = Some(Default::default());
+
+                let mut_ref = features.device_fault.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            if device_properties.supports_extension(vk::KhrPerformanceQueryFn::name()) {
+                features.performance_query =
+                    Some(vk::PhysicalDevicePerformanceQueryFeaturesKHR::builder().build());
+
+                let mut_ref = features.performance_query.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            // `VK_KHR_dynamic_rendering` depends on `VK_KHR_depth_stencil_resolve`, which is part of 1.2.
+            if device_properties.api_version() >= Version::V1_2
+                && device_properties
+                    .supports_extension(dynamic_rendering::KhrDynamicRenderingFn::name())
+            {
+                features.dynamic_rendering = Some(Default::default());
+
+                let mut_ref = features.dynamic_rendering.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+            }
+
+            // The ray tracing extensions rely on buffer device addresses, which are part of 1.2.
+            if device_properties.api_version() >= Version::V1_2
+                && device_properties.supports_extension(vk::KhrAccelerationStructureFn::name())
+                && device_properties.supports_extension(vk::KhrDeferredHostOperationsFn::name())
+            {
+                features.acceleration_structure =
+                    Some(vk::PhysicalDeviceAccelerationStructureFeaturesKHR::builder().build());
+
+                let mut_ref = features.acceleration_structure.as_mut().unwrap();
+                mut_ref.p_next = mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+
+                if device_properties.supports_extension(vk::KhrRayQueryFn::name()) {
+                    features.ray_query =
+                        Some(vk::PhysicalDeviceRayQueryFeaturesKHR::builder().build());
+
+                    let mut_ref = features.ray_query.as_mut().unwrap();
+                    mut_ref.p_next =
+                        mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+                }
+
+                if device_properties.supports_extension(vk::KhrRayTracingPipelineFn::name()) {
+                    features.ray_tracing_pipeline =
+                        Some(vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::builder().build());
+
+                    let mut_ref = features.ray_tracing_pipeline.as_mut().unwrap();
+                    mut_ref.p_next =
+                        mem::replace(&mut features2.p_next, mut_ref as *mut _ as *mut _);
+                }
+            }
+
             match get_device_properties {
                 ExtensionFn::Promoted => {
                     use ash::version::InstanceV1_1;
@@ -713,6 +1237,19 @@ impl PhysicalDeviceInfo {
             null_p_next(&mut features.descriptor_indexing);
             null_p_next(&mut features.mesh_shader);
             null_p_next(&mut features.imageless_framebuffer);
+            null_p_next(&mut features.timeline_semaphore);
+            null_p_next(&mut features.buffer_device_address);
+            null_p_next(&mut features.multiview);
+            null_p_next(&mut features.sampler_ycbcr_conversion);
+            null_p_next(&mut features.conditional_rendering);
+            null_p_next(&mut features.transform_feedback);
+            null_p_next(&mut features.synchronization2);
+            null_p_next(&mut features.device_fault);
+            null_p_next(&mut features.performance_query);
+            null_p_next(&mut features.dynamic_rendering);
+            null_p_next(&mut features.acceleration_structure);
+            null_p_next(&mut features.ray_query);
+            null_p_next(&mut features.ray_tracing_pipeline);
         }
 
         (device_properties, features)
@@ -800,6 +1337,232 @@ impl PhysicalDevice {
             None
         };
 
+        let timeline_semaphore_fn =
+            if enabled_extensions.contains(&vk::KhrTimelineSemaphoreFn::name()) {
+                Some(ExtensionFn::Extension(vk::KhrTimelineSemaphoreFn::load(
+                    |name| {
+                        std::mem::transmute(
+                            self.instance
+                                .inner
+                                .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                        )
+                    },
+                )))
+            } else if self.device_info.api_version() >= Version::V1_2
+                && requested_features.contains(Features::TIMELINE_SEMAPHORE)
+            {
+                Some(ExtensionFn::Promoted)
+            } else {
+                None
+            };
+
+        let buffer_device_address_fn =
+            if enabled_extensions.contains(&vk::KhrBufferDeviceAddressFn::name()) {
+                Some(ExtensionFn::Extension(vk::KhrBufferDeviceAddressFn::load(
+                    |name| {
+                        std::mem::transmute(
+                            self.instance
+                                .inner
+                                .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                        )
+                    },
+                )))
+            } else if self.device_info.api_version() >= Version::V1_2
+                && requested_features
+                    .intersects(Features::BUFFER_DEVICE_ADDRESS | Features::ACCELERATION_STRUCTURE)
+            {
+                Some(ExtensionFn::Promoted)
+            } else {
+                None
+            };
+
+        let dynamic_rendering_fn =
+            if enabled_extensions.contains(&dynamic_rendering::KhrDynamicRenderingFn::name()) {
+                Some(dynamic_rendering::KhrDynamicRenderingFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let acceleration_structure_fn =
+            if enabled_extensions.contains(&vk::KhrAccelerationStructureFn::name()) {
+                Some(vk::KhrAccelerationStructureFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let push_descriptor_fn = if enabled_extensions.contains(&PushDescriptor::name()) {
+            Some(PushDescriptor::new(&self.instance.inner, &device_raw))
+        } else {
+            None
+        };
+
+        let descriptor_update_template_fn =
+            if enabled_extensions.contains(&vk::KhrDescriptorUpdateTemplateFn::name()) {
+                Some(ExtensionFn::Extension(
+                    vk::KhrDescriptorUpdateTemplateFn::load(|name| {
+                        std::mem::transmute(
+                            self.instance
+                                .inner
+                                .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                        )
+                    }),
+                ))
+            } else if self.device_info.api_version() >= Version::V1_1 {
+                Some(ExtensionFn::Promoted)
+            } else {
+                None
+            };
+
+        let sampler_ycbcr_conversion_fn =
+            if enabled_extensions.contains(&vk::KhrSamplerYcbcrConversionFn::name()) {
+                Some(ExtensionFn::Extension(
+                    vk::KhrSamplerYcbcrConversionFn::load(|name| {
+                        std::mem::transmute(
+                            self.instance
+                                .inner
+                                .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                        )
+                    }),
+                ))
+            } else if self.device_info.api_version() >= Version::V1_1 {
+                Some(ExtensionFn::Promoted)
+            } else {
+                None
+            };
+
+        let extended_dynamic_state_fn =
+            if enabled_extensions.contains(&vk::ExtExtendedDynamicStateFn::name()) {
+                Some(vk::ExtExtendedDynamicStateFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let conditional_rendering_fn =
+            if enabled_extensions.contains(&vk::ExtConditionalRenderingFn::name()) {
+                Some(vk::ExtConditionalRenderingFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let transform_feedback_fn =
+            if enabled_extensions.contains(&vk::ExtTransformFeedbackFn::name()) {
+                Some(vk::ExtTransformFeedbackFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let synchronization2_fn =
+            if enabled_extensions.contains(&synchronization2::KhrSynchronization2Fn::name()) {
+                Some(synchronization2::KhrSynchronization2Fn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let buffer_marker_fn = if enabled_extensions.contains(&vk::AmdBufferMarkerFn::name()) {
+            Some(vk::AmdBufferMarkerFn::load(|name| {
+                std::mem::transmute(
+                    self.instance
+                        .inner
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let device_fault_fn =
+            if enabled_extensions.contains(&device_fault::ExtDeviceFaultFn::name()) {
+                Some(device_fault::ExtDeviceFaultFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let performance_query_fn =
+            if enabled_extensions.contains(&vk::KhrPerformanceQueryFn::name()) {
+                Some(vk::KhrPerformanceQueryFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let calibrated_timestamps_fn =
+            if enabled_extensions.contains(&vk::ExtCalibratedTimestampsFn::name()) {
+                Some(vk::ExtCalibratedTimestampsFn::load(|name| {
+                    std::mem::transmute(
+                        self.instance
+                            .inner
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let breadcrumbs = if buffer_marker_fn.is_some()
+            && requested_features.contains(Features::DEVICE_FAULT_DIAGNOSTICS)
+        {
+            let memory_properties = self
+                .instance
+                .inner
+                .get_physical_device_memory_properties(self.handle);
+            match BreadcrumbTrail::new(&device_raw, &memory_properties) {
+                Ok(breadcrumbs) => Some(breadcrumbs),
+                Err(e) => {
+                    error!("Unable to create the breadcrumb buffer: {:?}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let display_control = if enabled_extensions.contains(&vk::ExtDisplayControlFn::name()) {
             Some(vk::ExtDisplayControlFn::load(|name| {
                 std::mem::transmute(
@@ -981,6 +1744,21 @@ impl PhysicalDevice {
                 extension_fns: DeviceExtensionFunctions {
                     mesh_shaders: mesh_fn,
                     draw_indirect_count: indirect_count_fn,
+                    timeline_semaphore: timeline_semaphore_fn,
+                    buffer_device_address: buffer_device_address_fn,
+                    dynamic_rendering: dynamic_rendering_fn,
+                    acceleration_structure: acceleration_structure_fn,
+                    push_descriptor: push_descriptor_fn,
+                    descriptor_update_template: descriptor_update_template_fn,
+                    sampler_ycbcr_conversion: sampler_ycbcr_conversion_fn,
+                    extended_dynamic_state: extended_dynamic_state_fn,
+                    conditional_rendering: conditional_rendering_fn,
+                    transform_feedback: transform_feedback_fn,
+                    synchronization2: synchronization2_fn,
+                    buffer_marker: buffer_marker_fn,
+                    device_fault: device_fault_fn,
+                    performance_query: performance_query_fn,
+                    calibrated_timestamps: calibrated_timestamps_fn,
                     display_control,
                     memory_requirements2: memory_requirements2,
                     dedicated_allocation: dedicated_allocation,
@@ -1008,7 +1786,9 @@ impl PhysicalDevice {
                         .device_info
                         .supports_extension(vk::KhrMaintenance2Fn::name()),
                 timestamp_period: self.device_info.properties.limits.timestamp_period,
+                breadcrumbs,
             }),
+            physical_device: self.handle,
             vendor_id: self.device_info.properties.vendor_id,
             valid_ash_memory_types,
             render_doc: Default::default(),
@@ -1068,6 +1848,43 @@ impl PhysicalDevice {
     }
 }
 
+fn calibrateable_time_domains(
+    instance: &RawInstance,
+    device: vk::PhysicalDevice,
+) -> Vec<vk::TimeDomainEXT> {
+    let ext = match instance.calibrated_timestamps {
+        Some(ref ext) => ext,
+        None => return Vec::new(),
+    };
+    let mut count = 0;
+    let result = unsafe {
+        ext.get_physical_device_calibrateable_time_domains_ext(device, &mut count, ptr::null_mut())
+    };
+    if result != vk::Result::SUCCESS {
+        error!("Unable to enumerate the time domains: {:?}", result);
+        return Vec::new();
+    }
+
+    let mut time_domains = vec![vk::TimeDomainEXT::default(); count as usize];
+    let result = unsafe {
+        ext.get_physical_device_calibrateable_time_domains_ext(
+            device,
+            &mut count,
+            time_domains.as_mut_ptr(),
+        )
+    };
+    match result {
+        vk::Result::SUCCESS | vk::Result::INCOMPLETE => {
+            time_domains.truncate(count as usize);
+            time_domains
+        }
+        _ => {
+            error!("Unable to enumerate the time domains: {:?}", result);
+            Vec::new()
+        }
+    }
+}
+
 pub(crate) fn load_adapter(
     instance: &Arc<RawInstance>,
     device: vk::PhysicalDevice,
@@ -1107,6 +1924,29 @@ pub(crate) fn load_adapter(
             bits.set(Features::DUAL_SRC_BLENDING, false);
         }
 
+        // The budget is queried by chaining into `vkGetPhysicalDeviceMemoryProperties2`.
+        if instance.get_physical_device_properties.is_some()
+            && device_info.supports_extension(vk::ExtMemoryBudgetFn::name())
+        {
+            bits |= Features::MEMORY_BUDGET;
+        }
+
+        // Breadcrumbs are written with `VK_AMD_buffer_marker`, while the fault
+        // descriptions of `VK_EXT_device_fault` are covered by `to_hal_features`.
+        if device_info.supports_extension(vk::AmdBufferMarkerFn::name()) {
+            bits |= Features::DEVICE_FAULT_DIAGNOSTICS;
+        }
+
+        // Device ticks are only useful when they can be matched with the host clock.
+        if device_info.supports_extension(vk::ExtCalibratedTimestampsFn::name()) {
+            let time_domains = calibrateable_time_domains(instance, device);
+            if time_domains.contains(&vk::TimeDomainEXT::DEVICE)
+                && time_domains.contains(&HOST_TIME_DOMAIN)
+            {
+                bits |= Features::CALIBRATED_TIMESTAMPS;
+            }
+        }
+
         bits
     };
 
@@ -1595,7 +2435,7 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     }
 
     fn properties(&self) -> PhysicalDeviceProperties {
-        let limits = {
+        let mut limits = {
             let limits = &self.device_info.properties.limits;
 
             let max_group_count = limits.max_compute_work_group_count;
@@ -1610,6 +2450,8 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
                 max_texel_elements: limits.max_texel_buffer_elements as _,
                 max_patch_size: limits.max_tessellation_patch_size as PatchSize,
                 max_viewports: limits.max_viewports as _,
+                // Filled from the multiview properties below, if they can be queried
+                max_multiview_view_count: 0,
                 max_viewport_dimensions: limits.max_viewport_dimensions,
                 max_framebuffer_extent: image::Extent {
                     width: limits.max_framebuffer_width,
@@ -1709,6 +2551,7 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         let mut descriptor_indexing_capabilities = hal::DescriptorIndexingProperties::default();
         let mut mesh_shader_capabilities = hal::MeshShaderProperties::default();
         let mut sampler_reduction_capabilities = hal::SamplerReductionProperties::default();
+        let mut subgroup_capabilities = hal::SubgroupProperties::default();
         let mut external_memory_limits = hal::ExternalMemoryLimits::default();
 
         if let Some(get_physical_device_properties) =
@@ -1721,12 +2564,16 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
                 vk::PhysicalDeviceSamplerFilterMinmaxProperties::builder();
             let mut memory_host_properties =
                 vk::PhysicalDeviceExternalMemoryHostPropertiesEXT::builder();
+            let mut multiview_properties = vk::PhysicalDeviceMultiviewProperties::builder();
+            let mut subgroup_properties = vk::PhysicalDeviceSubgroupProperties::builder();
 
             let mut physical_device_properties2 = vk::PhysicalDeviceProperties2::builder()
                 .push_next(&mut descriptor_indexing_properties)
                 .push_next(&mut mesh_shader_properties)
                 .push_next(&mut sampler_reduction_properties)
                 .push_next(&mut memory_host_properties)
+                .push_next(&mut multiview_properties)
+                .push_next(&mut subgroup_properties)
                 .build();
 
             match get_physical_device_properties {
@@ -1802,10 +2649,37 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
                     == vk::TRUE,
             };
 
+            subgroup_capabilities = conv::map_vk_subgroup_properties(&subgroup_properties);
+
             external_memory_limits = ExternalMemoryLimits {
                 min_imported_host_pointer_alignment: memory_host_properties
                     .min_imported_host_pointer_alignment,
             };
+
+            limits.max_multiview_view_count = multiview_properties.max_multiview_view_count;
+        }
+
+        let mut dynamic_pipeline_states = DynamicStates::VIEWPORT
+            | DynamicStates::SCISSOR
+            | DynamicStates::LINE_WIDTH
+            | DynamicStates::BLEND_CONSTANTS
+            | DynamicStates::DEPTH_BIAS
+            | DynamicStates::DEPTH_BOUNDS
+            | DynamicStates::STENCIL_READ_MASK
+            | DynamicStates::STENCIL_WRITE_MASK
+            | DynamicStates::STENCIL_REFERENCE;
+        if self
+            .device_info
+            .supports_extension(vk::ExtExtendedDynamicStateFn::name())
+        {
+            dynamic_pipeline_states |= DynamicStates::CULL_MODE
+                | DynamicStates::FRONT_FACE
+                | DynamicStates::PRIMITIVE_TOPOLOGY
+                | DynamicStates::VERTEX_INPUT_BINDING_STRIDE
+                | DynamicStates::DEPTH_TEST_ENABLE
+                | DynamicStates::DEPTH_WRITE_ENABLE
+                | DynamicStates::DEPTH_COMPARE_OP
+                | DynamicStates::STENCIL_OP;
         }
 
         PhysicalDeviceProperties {
@@ -1813,13 +2687,79 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             descriptor_indexing: descriptor_indexing_capabilities,
             mesh_shader: mesh_shader_capabilities,
             sampler_reduction: sampler_reduction_capabilities,
+            subgroup: subgroup_capabilities,
             performance_caveats: Default::default(),
-            dynamic_pipeline_states: DynamicStates::all(),
+            dynamic_pipeline_states,
             downlevel: DownlevelProperties::all_enabled(),
             external_memory_limits,
         }
     }
 
+    fn performance_counters(&self, family: queue::QueueFamilyId) -> Vec<query::PerformanceCounter> {
+        if !self
+            .available_features
+            .contains(Features::PERFORMANCE_QUERY)
+        {
+            return Vec::new();
+        }
+        // The feature is only exposed with `VK_KHR_get_physical_device_properties2`.
+        let ext = self.instance.performance_query.as_ref().unwrap();
+        let family_index = family.0 as u32;
+
+        let mut count = 0;
+        let result = unsafe {
+            ext.enumerate_physical_device_queue_family_performance_query_counters_khr(
+                self.handle,
+                family_index,
+                &mut count,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if result != vk::Result::SUCCESS {
+            error!("Unable to enumerate the performance counters: {:?}", result);
+            return Vec::new();
+        }
+
+        let mut counters = vec![vk::PerformanceCounterKHR::default(); count as usize];
+        let mut descriptions =
+            vec![vk::PerformanceCounterDescriptionKHR::default(); count as usize];
+        let result = unsafe {
+            ext.enumerate_physical_device_queue_family_performance_query_counters_khr(
+                self.handle,
+                family_index,
+                &mut count,
+                counters.as_mut_ptr(),
+                descriptions.as_mut_ptr(),
+            )
+        };
+        match result {
+            vk::Result::SUCCESS | vk::Result::INCOMPLETE => {}
+            _ => {
+                error!("Unable to enumerate the performance counters: {:?}", result);
+                return Vec::new();
+            }
+        }
+
+        let to_string = |chars: &[std::os::raw::c_char]| {
+            unsafe { CStr::from_ptr(chars.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        counters
+            .iter()
+            .zip(&descriptions)
+            .take(count as usize)
+            .map(|(counter, description)| query::PerformanceCounter {
+                name: to_string(&description.name[..]),
+                category: to_string(&description.category[..]),
+                description: to_string(&description.description[..]),
+                unit: conv::map_vk_performance_counter_unit(counter.unit),
+                storage: conv::map_vk_performance_counter_storage(counter.storage),
+            })
+            .collect()
+    }
+
     fn is_valid_cache(&self, cache: &[u8]) -> bool {
         const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
 
@@ -0,0 +1,330 @@
+//! Raw definitions of `VK_KHR_synchronization2`, which the `ash` version in use doesn't expose.
+//!
+//! Like the ones of `VK_KHR_dynamic_rendering`, they follow the layout and naming
+//! of the generated `ash` bindings.
+
+use ash::vk;
+use std::{ffi::CStr, mem, os::raw::c_void, ptr};
+
+pub type PipelineStageFlags2KHR = u64;
+pub type AccessFlags2KHR = u64;
+
+pub const STRUCTURE_TYPE_MEMORY_BARRIER_2_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_000);
+pub const STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER_2_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_001);
+pub const STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER_2_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_002);
+pub const STRUCTURE_TYPE_DEPENDENCY_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_003);
+pub const STRUCTURE_TYPE_SUBMIT_INFO_2_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_004);
+pub const STRUCTURE_TYPE_SEMAPHORE_SUBMIT_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_005);
+pub const STRUCTURE_TYPE_COMMAND_BUFFER_SUBMIT_INFO_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_006);
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_SYNCHRONIZATION_2_FEATURES_KHR: vk::StructureType =
+    vk::StructureType::from_raw(1_000_314_007);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicalDeviceSynchronization2FeaturesKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub synchronization2: vk::Bool32,
+}
+
+impl Default for PhysicalDeviceSynchronization2FeaturesKHR {
+    fn default() -> Self {
+        PhysicalDeviceSynchronization2FeaturesKHR {
+            s_type: STRUCTURE_TYPE_PHYSICAL_DEVICE_SYNCHRONIZATION_2_FEATURES_KHR,
+            p_next: ptr::null_mut(),
+            synchronization2: vk::FALSE,
+        }
+    }
+}
+
+unsafe impl vk::ExtendsDeviceCreateInfo for PhysicalDeviceSynchronization2FeaturesKHR {}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryBarrier2KHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub src_stage_mask: PipelineStageFlags2KHR,
+    pub src_access_mask: AccessFlags2KHR,
+    pub dst_stage_mask: PipelineStageFlags2KHR,
+    pub dst_access_mask: AccessFlags2KHR,
+}
+
+impl Default for MemoryBarrier2KHR {
+    fn default() -> Self {
+        MemoryBarrier2KHR {
+            s_type: STRUCTURE_TYPE_MEMORY_BARRIER_2_KHR,
+            p_next: ptr::null(),
+            src_stage_mask: 0,
+            src_access_mask: 0,
+            dst_stage_mask: 0,
+            dst_access_mask: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct BufferMemoryBarrier2KHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub src_stage_mask: PipelineStageFlags2KHR,
+    pub src_access_mask: AccessFlags2KHR,
+    pub dst_stage_mask: PipelineStageFlags2KHR,
+    pub dst_access_mask: AccessFlags2KHR,
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+    pub buffer: vk::Buffer,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+}
+
+impl Default for BufferMemoryBarrier2KHR {
+    fn default() -> Self {
+        BufferMemoryBarrier2KHR {
+            s_type: STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER_2_KHR,
+            p_next: ptr::null(),
+            src_stage_mask: 0,
+            src_access_mask: 0,
+            dst_stage_mask: 0,
+            dst_access_mask: 0,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer: vk::Buffer::null(),
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ImageMemoryBarrier2KHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub src_stage_mask: PipelineStageFlags2KHR,
+    pub src_access_mask: AccessFlags2KHR,
+    pub dst_stage_mask: PipelineStageFlags2KHR,
+    pub dst_access_mask: AccessFlags2KHR,
+    pub old_layout: vk::ImageLayout,
+    pub new_layout: vk::ImageLayout,
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+    pub image: vk::Image,
+    pub subresource_range: vk::ImageSubresourceRange,
+}
+
+impl Default for ImageMemoryBarrier2KHR {
+    fn default() -> Self {
+        ImageMemoryBarrier2KHR {
+            s_type: STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER_2_KHR,
+            p_next: ptr::null(),
+            src_stage_mask: 0,
+            src_access_mask: 0,
+            dst_stage_mask: 0,
+            dst_access_mask: 0,
+            old_layout: vk::ImageLayout::UNDEFINED,
+            new_layout: vk::ImageLayout::UNDEFINED,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            image: vk::Image::null(),
+            subresource_range: vk::ImageSubresourceRange::default(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DependencyInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub dependency_flags: vk::DependencyFlags,
+    pub memory_barrier_count: u32,
+    pub p_memory_barriers: *const MemoryBarrier2KHR,
+    pub buffer_memory_barrier_count: u32,
+    pub p_buffer_memory_barriers: *const BufferMemoryBarrier2KHR,
+    pub image_memory_barrier_count: u32,
+    pub p_image_memory_barriers: *const ImageMemoryBarrier2KHR,
+}
+
+impl Default for DependencyInfoKHR {
+    fn default() -> Self {
+        DependencyInfoKHR {
+            s_type: STRUCTURE_TYPE_DEPENDENCY_INFO_KHR,
+            p_next: ptr::null(),
+            dependency_flags: vk::DependencyFlags::empty(),
+            memory_barrier_count: 0,
+            p_memory_barriers: ptr::null(),
+            buffer_memory_barrier_count: 0,
+            p_buffer_memory_barriers: ptr::null(),
+            image_memory_barrier_count: 0,
+            p_image_memory_barriers: ptr::null(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SemaphoreSubmitInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub semaphore: vk::Semaphore,
+    pub value: u64,
+    pub stage_mask: PipelineStageFlags2KHR,
+    pub device_index: u32,
+}
+
+impl Default for SemaphoreSubmitInfoKHR {
+    fn default() -> Self {
+        SemaphoreSubmitInfoKHR {
+            s_type: STRUCTURE_TYPE_SEMAPHORE_SUBMIT_INFO_KHR,
+            p_next: ptr::null(),
+            semaphore: vk::Semaphore::null(),
+            value: 0,
+            stage_mask: 0,
+            device_index: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct CommandBufferSubmitInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub command_buffer: vk::CommandBuffer,
+    pub device_mask: u32,
+}
+
+impl Default for CommandBufferSubmitInfoKHR {
+    fn default() -> Self {
+        CommandBufferSubmitInfoKHR {
+            s_type: STRUCTURE_TYPE_COMMAND_BUFFER_SUBMIT_INFO_KHR,
+            p_next: ptr::null(),
+            command_buffer: vk::CommandBuffer::null(),
+            device_mask: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SubmitInfo2KHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub flags: vk::Flags,
+    pub wait_semaphore_info_count: u32,
+    pub p_wait_semaphore_infos: *const SemaphoreSubmitInfoKHR,
+    pub command_buffer_info_count: u32,
+    pub p_command_buffer_infos: *const CommandBufferSubmitInfoKHR,
+    pub signal_semaphore_info_count: u32,
+    pub p_signal_semaphore_infos: *const SemaphoreSubmitInfoKHR,
+}
+
+impl Default for SubmitInfo2KHR {
+    fn default() -> Self {
+        SubmitInfo2KHR {
+            s_type: STRUCTURE_TYPE_SUBMIT_INFO_2_KHR,
+            p_next: ptr::null(),
+            flags: 0,
+            wait_semaphore_info_count: 0,
+            p_wait_semaphore_infos: ptr::null(),
+            command_buffer_info_count: 0,
+            p_command_buffer_infos: ptr::null(),
+            signal_semaphore_info_count: 0,
+            p_signal_semaphore_infos: ptr::null(),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+pub type PFN_vkCmdPipelineBarrier2KHR = extern "system" fn(
+    command_buffer: vk::CommandBuffer,
+    p_dependency_info: *const DependencyInfoKHR,
+);
+#[allow(non_camel_case_types)]
+pub type PFN_vkQueueSubmit2KHR = extern "system" fn(
+    queue: vk::Queue,
+    submit_count: u32,
+    p_submits: *const SubmitInfo2KHR,
+    fence: vk::Fence,
+) -> vk::Result;
+
+#[derive(Clone)]
+pub struct KhrSynchronization2Fn {
+    pub cmd_pipeline_barrier2_khr: PFN_vkCmdPipelineBarrier2KHR,
+    pub queue_submit2_khr: PFN_vkQueueSubmit2KHR,
+}
+
+unsafe impl Send for KhrSynchronization2Fn {}
+unsafe impl Sync for KhrSynchronization2Fn {}
+
+impl KhrSynchronization2Fn {
+    pub fn name() -> &'static CStr {
+        CStr::from_bytes_with_nul(b"VK_KHR_synchronization2\0").expect("Wrong extension string")
+    }
+
+    pub fn load<F>(mut f: F) -> Self
+    where
+        F: FnMut(&CStr) -> *const c_void,
+    {
+        extern "system" fn cmd_pipeline_barrier2_khr(
+            _command_buffer: vk::CommandBuffer,
+            _p_dependency_info: *const DependencyInfoKHR,
+        ) {
+            panic!("Unable to load cmd_pipeline_barrier2_khr")
+        }
+        extern "system" fn queue_submit2_khr(
+            _queue: vk::Queue,
+            _submit_count: u32,
+            _p_submits: *const SubmitInfo2KHR,
+            _fence: vk::Fence,
+        ) -> vk::Result {
+            panic!("Unable to load queue_submit2_khr")
+        }
+
+        unsafe {
+            let barrier = f(CStr::from_bytes_with_nul_unchecked(
+                b"vkCmdPipelineBarrier2KHR\0",
+            ));
+            let submit = f(CStr::from_bytes_with_nul_unchecked(b"vkQueueSubmit2KHR\0"));
+            KhrSynchronization2Fn {
+                cmd_pipeline_barrier2_khr: if barrier.is_null() {
+                    cmd_pipeline_barrier2_khr
+                } else {
+                    mem::transmute(barrier)
+                },
+                queue_submit2_khr: if submit.is_null() {
+                    queue_submit2_khr
+                } else {
+                    mem::transmute(submit)
+                },
+            }
+        }
+    }
+
+    pub unsafe fn cmd_pipeline_barrier2_khr(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        p_dependency_info: *const DependencyInfoKHR,
+    ) {
+        (self.cmd_pipeline_barrier2_khr)(command_buffer, p_dependency_info)
+    }
+
+    pub unsafe fn queue_submit2_khr(
+        &self,
+        queue: vk::Queue,
+        submit_count: u32,
+        p_submits: *const SubmitInfo2KHR,
+        fence: vk::Fence,
+    ) -> vk::Result {
+        (self.queue_submit2_khr)(queue, submit_count, p_submits, fence)
+    }
+}
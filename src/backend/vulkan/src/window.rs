@@ -419,6 +419,7 @@ impl w::PresentationSurface<Backend> for Surface {
                                 aspects: hal::format::Aspects::COLOR,
                                 ..Default::default()
                             },
+                            None,
                         )
                         .unwrap();
                     SurfaceFrame {
@@ -470,6 +471,7 @@ impl w::PresentationSurface<Backend> for Surface {
                             aspects: hal::format::Aspects::COLOR,
                             ..Default::default()
                         },
+                        ycbcr_conversion: None,
                     },
                 };
                 Ok((image, suboptimal))
@@ -5,9 +5,9 @@ use hal::{
     command::{
         AttachmentClear, BufferCopy, BufferImageCopy, ClearValue, CommandBufferFlags,
         CommandBufferInheritanceInfo, DescriptorSetOffset, ImageBlit, ImageCopy, ImageResolve,
-        Level, SubpassContents,
+        Level, RenderingAttachmentInfo, SubpassContents,
     },
-    device::OutOfMemory,
+    device::WaitError,
     image::{Filter, Layout, SubresourceRange},
     memory::{Barrier, Dependencies},
     pso, query,
@@ -44,7 +44,7 @@ impl hal::queue::Queue<Backend> for Queue {
         todo!()
     }
 
-    fn wait_idle(&mut self) -> Result<(), OutOfMemory> {
+    fn wait_idle(&mut self) -> Result<(), WaitError> {
         todo!()
     }
 }
@@ -189,6 +189,22 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        _first_binding: pso::BufferIndex,
+        _buffers: T,
+    ) where
+        T: Iterator<
+            Item = (
+                &'a <Backend as hal::Backend>::Buffer,
+                buffer::SubRange,
+                pso::ElemStride,
+            ),
+        >,
+    {
+        todo!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, _first_viewport: u32, _viewports: T)
     where
         T: Iterator,
@@ -233,6 +249,34 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn set_cull_mode(&mut self, _faces: pso::Face) {
+        todo!()
+    }
+
+    unsafe fn set_front_face(&mut self, _front_face: pso::FrontFace) {
+        todo!()
+    }
+
+    unsafe fn set_primitive_topology(&mut self, _primitive: pso::Primitive) {
+        todo!()
+    }
+
+    unsafe fn set_depth_test_enable(&mut self, _enable: bool) {
+        todo!()
+    }
+
+    unsafe fn set_depth_write_enable(&mut self, _enable: bool) {
+        todo!()
+    }
+
+    unsafe fn set_depth_compare_op(&mut self, _fun: pso::Comparison) {
+        todo!()
+    }
+
+    unsafe fn set_stencil_op(&mut self, _faces: pso::Face, _ops: pso::StencilFace) {
+        todo!()
+    }
+
     unsafe fn begin_render_pass<T>(
         &mut self,
         _render_pass: &<Backend as hal::Backend>::RenderPass,
@@ -255,6 +299,21 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        _render_area: pso::Rect,
+        _color_attachments: T,
+        _depth_stencil_attachment: Option<RenderingAttachmentInfo<'a, Backend>>,
+    ) where
+        T: Iterator<Item = RenderingAttachmentInfo<'a, Backend>>,
+    {
+        todo!()
+    }
+
+    unsafe fn end_rendering(&mut self) {
+        todo!()
+    }
+
     unsafe fn bind_graphics_pipeline(
         &mut self,
         _pipeline: &<Backend as hal::Backend>::GraphicsPipeline,
@@ -299,6 +358,32 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &<Backend as hal::Backend>::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        todo!()
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        _layout: &<Backend as hal::Backend>::PipelineLayout,
+        _set: usize,
+        _binding: pso::DescriptorBinding,
+        _array_offset: pso::DescriptorArrayIndex,
+        _descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, Backend>>,
+    {
+        todo!()
+    }
+
     unsafe fn dispatch(&mut self, _count: WorkGroupCount) {
         todo!()
     }
@@ -363,6 +448,26 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, _descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a hal::acceleration_structure::BuildDesc<'a, Backend>,
+                &'a [hal::acceleration_structure::BuildRangeDesc],
+            ),
+        >,
+    {
+        todo!()
+    }
+
+    unsafe fn copy_acceleration_structure(
+        &mut self,
+        _src: &<Backend as hal::Backend>::AccelerationStructure,
+        _dst: &<Backend as hal::Backend>::AccelerationStructure,
+    ) {
+        todo!()
+    }
+
     unsafe fn draw(&mut self, _vertices: Range<VertexCount>, _instances: Range<InstanceCount>) {
         todo!()
     }
@@ -511,6 +616,34 @@ impl hal::command::CommandBuffer<Backend> for CommandBuffer {
         todo!()
     }
 
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &<Backend as hal::Backend>::Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        todo!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        todo!()
+    }
+
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, _first_binding: u32, _buffers: T)
+    where
+        T: Iterator<Item = (&'a <Backend as hal::Backend>::Buffer, buffer::SubRange)>,
+    {
+        todo!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        todo!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        todo!()
+    }
+
     unsafe fn push_graphics_constants(
         &mut self,
         _layout: &<Backend as hal::Backend>::PipelineLayout,
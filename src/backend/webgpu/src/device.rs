@@ -26,6 +26,7 @@ impl hal::device::Device<Backend> for Device {
         &self,
         _memory_type: MemoryTypeId,
         _size: u64,
+        _flags: memory::AllocationFlags,
     ) -> Result<<Backend as hal::Backend>::Memory, AllocationError> {
         todo!()
     }
@@ -34,6 +35,10 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    fn memory_budget(&self) -> Vec<hal::adapter::MemoryHeapBudget> {
+        todo!()
+    }
+
     unsafe fn create_command_pool(
         &self,
         _family: QueueFamilyId,
@@ -188,6 +193,13 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    unsafe fn get_buffer_device_address(
+        &self,
+        _buffer: &<Backend as hal::Backend>::Buffer,
+    ) -> buffer::DeviceAddress {
+        todo!()
+    }
+
     unsafe fn destroy_buffer(&self, _buffer: <Backend as hal::Backend>::Buffer) {
         todo!()
     }
@@ -253,6 +265,7 @@ impl hal::device::Device<Backend> for Device {
         _format: format::Format,
         _swizzle: format::Swizzle,
         _range: image::SubresourceRange,
+        _ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<<Backend as hal::Backend>::ImageView, image::ViewCreationError> {
         todo!()
     }
@@ -272,6 +285,35 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        _desc: &hal::acceleration_structure::GeometryDesc<Backend>,
+        _max_primitive_counts: &[u32],
+    ) -> hal::acceleration_structure::SizeRequirements {
+        todo!()
+    }
+
+    unsafe fn create_acceleration_structure(
+        &self,
+        _desc: &hal::acceleration_structure::CreateDesc<Backend>,
+    ) -> Result<<Backend as hal::Backend>::AccelerationStructure, OutOfMemory> {
+        todo!()
+    }
+
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        _accel_struct: &<Backend as hal::Backend>::AccelerationStructure,
+    ) -> hal::acceleration_structure::DeviceAddress {
+        todo!()
+    }
+
+    unsafe fn destroy_acceleration_structure(
+        &self,
+        _accel_struct: <Backend as hal::Backend>::AccelerationStructure,
+    ) {
+        todo!()
+    }
+
     unsafe fn create_descriptor_pool<I>(
         &self,
         _max_sets: usize,
@@ -289,6 +331,7 @@ impl hal::device::Device<Backend> for Device {
         &self,
         _bindings: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<<Backend as hal::Backend>::DescriptorSetLayout, OutOfMemory>
     where
         J: Iterator<Item = &'a <Backend as hal::Backend>::Sampler>,
@@ -303,6 +346,12 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    unsafe fn destroy_descriptor_update_template(
+        &self,
+        _template: <Backend as hal::Backend>::DescriptorUpdateTemplate,
+    ) {
+    }
+
     unsafe fn write_descriptor_set<'a, I>(&self, _op: pso::DescriptorSetWrite<'a, Backend, I>)
     where
         I: Iterator<Item = pso::Descriptor<'a, Backend>>,
@@ -314,6 +363,26 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        _layout: &<Backend as hal::Backend>::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<<Backend as hal::Backend>::DescriptorUpdateTemplate, OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>,
+    {
+        Ok(pso::EmulatedDescriptorUpdateTemplate::new(entries))
+    }
+
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut <Backend as hal::Backend>::DescriptorSet,
+        template: &<Backend as hal::Backend>::DescriptorUpdateTemplate,
+        data: *const u8,
+    ) {
+        template.update(self, set, data)
+    }
+
     unsafe fn map_memory(
         &self,
         _memory: &mut <Backend as hal::Backend>::Memory,
@@ -348,6 +417,40 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: u64,
+    ) -> Result<<Backend as hal::Backend>::Semaphore, OutOfMemory> {
+        todo!()
+    }
+
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        _semaphore: &<Backend as hal::Backend>::Semaphore,
+    ) -> Result<u64, DeviceLost> {
+        todo!()
+    }
+
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        _semaphores: I,
+        _wait: hal::device::WaitFor,
+        _timeout_ns: u64,
+    ) -> Result<bool, WaitError>
+    where
+        I: Iterator<Item = (&'a <Backend as hal::Backend>::Semaphore, u64)>,
+    {
+        todo!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &<Backend as hal::Backend>::Semaphore,
+        _value: u64,
+    ) -> Result<(), OutOfMemory> {
+        todo!()
+    }
+
     fn create_fence(
         &self,
         _signaled: bool,
@@ -430,6 +533,23 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
+    unsafe fn create_performance_query_pool(
+        &self,
+        _family: QueueFamilyId,
+        _counters: &[u32],
+        _count: query::Id,
+    ) -> Result<<Backend as hal::Backend>::QueryPool, query::CreationError> {
+        todo!()
+    }
+
+    unsafe fn acquire_profiling_lock(&self, _timeout_ns: u64) -> Result<bool, OutOfMemory> {
+        todo!()
+    }
+
+    unsafe fn release_profiling_lock(&self) {
+        todo!()
+    }
+
     unsafe fn destroy_query_pool(&self, _pool: <Backend as hal::Backend>::QueryPool) {
         todo!()
     }
@@ -445,7 +565,15 @@ impl hal::device::Device<Backend> for Device {
         todo!()
     }
 
-    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+    fn get_calibrated_timestamps(&self) -> Result<hal::device::CalibratedTimestamps, OutOfMemory> {
+        todo!()
+    }
+
+    fn wait_idle(&self) -> Result<(), WaitError> {
+        todo!()
+    }
+
+    unsafe fn get_fault_info(&self) -> Option<hal::device::FaultInfo> {
         todo!()
     }
 
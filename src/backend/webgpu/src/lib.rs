@@ -49,6 +49,7 @@ impl hal::Backend for Backend {
     type PipelineCache = ();
     type PipelineLayout = ();
     type DescriptorSetLayout = ();
+    type DescriptorUpdateTemplate = hal::pso::EmulatedDescriptorUpdateTemplate;
     type DescriptorPool = DescriptorPool;
     type DescriptorSet = ();
 
@@ -59,6 +60,8 @@ impl hal::Backend for Backend {
 
     type Display = ();
     type DisplayMode = ();
+
+    type AccelerationStructure = ();
 }
 
 #[derive(Debug)]
@@ -215,6 +218,10 @@ impl hal::adapter::PhysicalDevice<Backend> for PhysicalDevice {
     fn properties(&self) -> hal::PhysicalDeviceProperties {
         todo!()
     }
+
+    fn performance_counters(&self, _family: QueueFamilyId) -> Vec<hal::query::PerformanceCounter> {
+        Vec::new()
+    }
 }
 
 unsafe impl std::marker::Send for PhysicalDevice {}
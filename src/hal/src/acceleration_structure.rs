@@ -0,0 +1,300 @@
+//! Acceleration structures for ray tracing.
+//!
+//! An acceleration structure is an opaque spatial index built by the device over some geometry,
+//! which ray queries and ray tracing pipelines traverse to find ray intersections.
+//!
+//! Structures come in two [levels][Level]: bottom level structures contain triangles
+//! or axis-aligned bounding boxes, and top level structures contain [instances][Instance]
+//! of bottom level structures, each with its own transform.
+//!
+//! An acceleration structure lives in a range of a buffer created with
+//! [`ACCELERATION_STRUCTURE_STORAGE`][buffer::Usage::ACCELERATION_STRUCTURE_STORAGE] usage,
+//! and is built on the device with
+//! [`build_acceleration_structures`][crate::command::CommandBuffer::build_acceleration_structures].
+
+use crate::{buffer, format::Format, Backend, IndexType};
+
+/// The address of an acceleration structure on the device.
+///
+/// Top level structures refer to bottom level ones by address,
+/// see [`Instance::acceleration_structure_reference`].
+pub use crate::buffer::DeviceAddress;
+
+/// Level of an acceleration structure.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Level {
+    /// Contains instances of bottom level acceleration structures.
+    Top,
+    /// Contains triangles or axis-aligned bounding boxes.
+    Bottom,
+}
+
+bitflags!(
+    /// Flags controlling the build of an acceleration structure.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct BuildFlags: u32 {
+        /// The acceleration structure can be used as the source of an update.
+        const ALLOW_UPDATE = 0x1;
+        /// Prioritize traversal performance over build time.
+        const PREFER_FAST_TRACE = 0x4;
+        /// Prioritize build time over traversal performance.
+        const PREFER_FAST_BUILD = 0x8;
+        /// Minimize the size of the acceleration structure and of the scratch memory,
+        /// at the cost of build time or traversal performance.
+        const LOW_MEMORY = 0x10;
+    }
+);
+
+bitflags!(
+    /// Flags affecting how a geometry is traversed.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct GeometryFlags: u32 {
+        /// The geometry doesn't invoke any-hit shaders.
+        const OPAQUE = 0x1;
+        /// Any-hit shaders are invoked at most once per primitive of this geometry.
+        const NO_DUPLICATE_ANY_HIT_INVOCATION = 0x2;
+    }
+);
+
+bitflags!(
+    /// Flags affecting how an instance is traversed.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct InstanceFlags: u8 {
+        /// Disables face culling for this instance.
+        const TRIANGLE_FACING_CULL_DISABLE = 0x1;
+        /// Treats counter-clockwise triangles as front facing, instead of clockwise ones.
+        const TRIANGLE_FLIP_FACING = 0x2;
+        /// Treats all the geometries of this instance as if they were `OPAQUE`.
+        const FORCE_OPAQUE = 0x4;
+        /// Treats all the geometries of this instance as if they were not `OPAQUE`.
+        const FORCE_NO_OPAQUE = 0x8;
+    }
+);
+
+/// Description of an acceleration structure to create.
+#[derive(Debug)]
+pub struct CreateDesc<'a, B: Backend> {
+    /// Buffer storing the acceleration structure.
+    pub buffer: &'a B::Buffer,
+    /// Offset of the acceleration structure in the buffer, which must be a multiple of 256.
+    pub buffer_offset: buffer::Offset,
+    /// Size of the acceleration structure, as returned by
+    /// [`get_acceleration_structure_build_requirements`][crate::device::Device::get_acceleration_structure_build_requirements].
+    pub size: u64,
+    /// Level of the acceleration structure.
+    pub level: Level,
+}
+
+/// Triangle geometry of a bottom level acceleration structure.
+#[derive(Debug)]
+pub struct GeometryTriangles<'a, B: Backend> {
+    /// Format of the vertex positions. Only the first three components are used.
+    pub vertex_format: Format,
+    /// Buffer holding the vertices.
+    pub vertex_buffer: &'a B::Buffer,
+    /// Offset of the first vertex in the vertex buffer.
+    pub vertex_buffer_offset: buffer::Offset,
+    /// Stride between consecutive vertices.
+    pub vertex_buffer_stride: buffer::Stride,
+    /// Highest index of a vertex referenced by the geometry.
+    pub max_vertex: u32,
+    /// Buffer, offset and type of the indices, if the triangles are indexed.
+    pub index_buffer: Option<(&'a B::Buffer, buffer::Offset, IndexType)>,
+    /// Buffer and offset of a 3x4 row-major transform applied to the vertices, if any.
+    pub transform: Option<(&'a B::Buffer, buffer::Offset)>,
+}
+
+/// Axis-aligned bounding box geometry of a bottom level acceleration structure.
+///
+/// Each box is made of six `f32` values: the minimum corner followed by the maximum one.
+#[derive(Debug)]
+pub struct GeometryAabbs<'a, B: Backend> {
+    /// Buffer holding the boxes.
+    pub buffer: &'a B::Buffer,
+    /// Offset of the first box in the buffer.
+    pub buffer_offset: buffer::Offset,
+    /// Stride between consecutive boxes.
+    pub buffer_stride: buffer::Stride,
+}
+
+/// Instance geometry of a top level acceleration structure.
+///
+/// The buffer holds tightly packed [`Instance`] values.
+#[derive(Debug)]
+pub struct GeometryInstances<'a, B: Backend> {
+    /// Buffer holding the instances.
+    pub buffer: &'a B::Buffer,
+    /// Offset of the first instance in the buffer.
+    pub buffer_offset: buffer::Offset,
+}
+
+/// Data of a geometry contained in an acceleration structure.
+#[derive(Debug)]
+pub enum GeometryData<'a, B: Backend> {
+    /// Triangles, for bottom level structures.
+    Triangles(GeometryTriangles<'a, B>),
+    /// Axis-aligned bounding boxes, for bottom level structures.
+    Aabbs(GeometryAabbs<'a, B>),
+    /// Instances, for top level structures.
+    Instances(GeometryInstances<'a, B>),
+}
+
+/// A geometry contained in an acceleration structure.
+#[derive(Debug)]
+pub struct Geometry<'a, B: Backend> {
+    /// Geometry flags.
+    pub flags: GeometryFlags,
+    /// Geometry data.
+    pub data: GeometryData<'a, B>,
+}
+
+/// Description of the geometries an acceleration structure is built over.
+#[derive(Debug)]
+pub struct GeometryDesc<'a, B: Backend> {
+    /// Build flags.
+    pub flags: BuildFlags,
+    /// Level of the acceleration structure.
+    pub level: Level,
+    /// Geometries of the acceleration structure.
+    ///
+    /// Top level structures have a single geometry of instances,
+    /// bottom level structures have only triangles or only boxes.
+    pub geometries: &'a [Geometry<'a, B>],
+}
+
+/// Description of an acceleration structure build.
+#[derive(Debug)]
+pub struct BuildDesc<'a, B: Backend> {
+    /// Acceleration structure to update, or `None` for a full build.
+    ///
+    /// It must have been built with [`BuildFlags::ALLOW_UPDATE`],
+    /// and can be the same as `dst` to update in place.
+    pub src: Option<&'a B::AccelerationStructure>,
+    /// Acceleration structure to build.
+    pub dst: &'a B::AccelerationStructure,
+    /// Geometries to build the acceleration structure over.
+    pub geometry: &'a GeometryDesc<'a, B>,
+    /// Buffer used as scratch memory during the build.
+    pub scratch: &'a B::Buffer,
+    /// Offset of the scratch memory in the buffer.
+    pub scratch_offset: buffer::Offset,
+}
+
+/// Range of primitives of a geometry to build an acceleration structure over.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BuildRangeDesc {
+    /// Number of triangles, boxes or instances.
+    pub primitive_count: u32,
+    /// Offset in bytes added to the offset of the primitive data:
+    /// the indices, or the vertices if the triangles are not indexed, the boxes, or the instances.
+    pub primitive_offset: u32,
+    /// Index of the first vertex, added to the indices of indexed triangles.
+    pub first_vertex: u32,
+    /// Offset in bytes added to the offset of the triangles transform.
+    pub transform_offset: u32,
+}
+
+/// Memory needed to build an acceleration structure.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SizeRequirements {
+    /// Size of the acceleration structure.
+    pub acceleration_structure_size: u64,
+    /// Size of the scratch memory needed for an update.
+    pub update_scratch_size: u64,
+    /// Size of the scratch memory needed for a full build.
+    pub build_scratch_size: u64,
+}
+
+/// An instance of a bottom level acceleration structure, as stored in
+/// the instance buffer of a top level one.
+///
+/// The layout matches `VkAccelerationStructureInstanceKHR`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Instance {
+    /// 3x4 row-major transform from the instance space to the world space.
+    pub transform: [[f32; 4]; 3],
+    custom_index_and_mask: u32,
+    shader_binding_table_record_offset_and_flags: u32,
+    /// Address of the bottom level acceleration structure, as returned by
+    /// [`get_acceleration_structure_address`][crate::device::Device::get_acceleration_structure_address].
+    pub acceleration_structure_reference: DeviceAddress,
+}
+
+impl Instance {
+    /// Identity transform.
+    pub const IDENTITY: [[f32; 4]; 3] = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+    ];
+
+    /// Create a new instance of the acceleration structure at `reference`,
+    /// visible to all the rays.
+    pub fn new(transform: [[f32; 4]; 3], reference: DeviceAddress) -> Self {
+        Instance {
+            transform,
+            custom_index_and_mask: 0xFF << 24,
+            shader_binding_table_record_offset_and_flags: 0,
+            acceleration_structure_reference: reference,
+        }
+    }
+
+    /// Set the 24-bit index reported to shaders.
+    pub fn with_custom_index(mut self, custom_index: u32) -> Self {
+        self.custom_index_and_mask =
+            (self.custom_index_and_mask & !0x00FF_FFFF) | (custom_index & 0x00FF_FFFF);
+        self
+    }
+
+    /// Set the visibility mask, which is tested against the cull mask of the rays.
+    pub fn with_mask(mut self, mask: u8) -> Self {
+        self.custom_index_and_mask =
+            (self.custom_index_and_mask & 0x00FF_FFFF) | (mask as u32) << 24;
+        self
+    }
+
+    /// Set the 24-bit offset of the instance hit groups in the shader binding table.
+    pub fn with_shader_binding_table_record_offset(mut self, offset: u32) -> Self {
+        self.shader_binding_table_record_offset_and_flags =
+            (self.shader_binding_table_record_offset_and_flags & !0x00FF_FFFF)
+                | (offset & 0x00FF_FFFF);
+        self
+    }
+
+    /// Set the instance flags.
+    pub fn with_flags(mut self, flags: InstanceFlags) -> Self {
+        self.shader_binding_table_record_offset_and_flags =
+            (self.shader_binding_table_record_offset_and_flags & 0x00FF_FFFF)
+                | (flags.bits() as u32) << 24;
+        self
+    }
+
+    /// Get the index reported to shaders.
+    pub fn custom_index(&self) -> u32 {
+        self.custom_index_and_mask & 0x00FF_FFFF
+    }
+
+    /// Get the visibility mask.
+    pub fn mask(&self) -> u8 {
+        (self.custom_index_and_mask >> 24) as u8
+    }
+
+    /// Get the offset of the instance hit groups in the shader binding table.
+    pub fn shader_binding_table_record_offset(&self) -> u32 {
+        self.shader_binding_table_record_offset_and_flags & 0x00FF_FFFF
+    }
+
+    /// Get the instance flags.
+    pub fn flags(&self) -> InstanceFlags {
+        InstanceFlags::from_bits_truncate(
+            (self.shader_binding_table_record_offset_and_flags >> 24) as u8,
+        )
+    }
+}
@@ -9,8 +9,8 @@
 //! of that [backend][crate::Backend].
 
 use crate::{
-    buffer, device, display, external_memory, format, image, memory,
-    queue::{QueueGroup, QueuePriority},
+    buffer, device, display, external_memory, format, image, memory, query,
+    queue::{QueueFamilyId, QueueGroup, QueuePriority},
     Backend, Features, PhysicalDeviceProperties,
 };
 
@@ -37,6 +37,20 @@ pub struct MemoryHeap {
     pub flags: memory::HeapFlags,
 }
 
+/// Budget and current usage of a memory heap, as reported by
+/// [`memory_budget`][crate::device::Device::memory_budget].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryHeapBudget {
+    /// Estimate of how much memory the process can allocate from the heap
+    /// before allocations fail or performance degrades, including what it already uses.
+    ///
+    /// `!0` if the backend has no estimate, e.g. when the heap size isn't known either.
+    pub budget: u64,
+    /// Estimate of how much memory the process currently uses from the heap.
+    pub usage: u64,
+}
+
 /// Types of memory supported by this adapter and available memory.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -160,6 +174,14 @@ pub trait PhysicalDevice<B: Backend>: fmt::Debug + Any + Send + Sync {
     // depend on the platform, but unlike features, these are immutable and can't be switched on.
     fn properties(&self) -> PhysicalDeviceProperties;
 
+    /// Enumerate the hardware counters which the queues of the given family can sample
+    /// with [performance queries][query::Type::PerformanceCounters].
+    ///
+    /// The counters are referred to by their index in the returned list.
+    /// Returns an empty list if the [`PERFORMANCE_QUERY`][Features::PERFORMANCE_QUERY]
+    /// feature isn't supported.
+    fn performance_counters(&self, family: QueueFamilyId) -> Vec<query::PerformanceCounter>;
+
     /// Check cache compatibility with the `PhysicalDevice`.
     fn is_valid_cache(&self, _cache: &[u8]) -> bool {
         false
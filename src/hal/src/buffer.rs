@@ -13,6 +13,9 @@ pub type Offset = u64;
 /// An stride between elements inside a buffer, in bytes.
 pub type Stride = u32;
 
+/// The address of a buffer on the device.
+pub type DeviceAddress = u64;
+
 /// A subrange of the buffer.
 #[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -85,6 +88,17 @@ bitflags!(
         const VERTEX = 0x80;
         ///
         const INDIRECT = 0x100;
+        /// Predicate of [conditional rendering](crate::command::CommandBuffer::begin_conditional_rendering).
+        const CONDITIONAL_RENDERING = 0x200;
+        /// Destination of [transform feedback](crate::command::CommandBuffer::begin_transform_feedback).
+        const TRANSFORM_FEEDBACK = 0x800;
+        /// The device address of the buffer can be queried.
+        /// Requires the [`BUFFER_DEVICE_ADDRESS`][crate::Features::BUFFER_DEVICE_ADDRESS] feature.
+        const DEVICE_ADDRESS = 0x2_0000;
+        /// Read-only geometry, instances or transforms of an acceleration structure build.
+        const ACCELERATION_STRUCTURE_BUILD_INPUT = 0x8_0000;
+        /// Storage of acceleration structures, or scratch memory of their builds.
+        const ACCELERATION_STRUCTURE_STORAGE = 0x10_0000;
     }
 );
 
@@ -144,5 +158,16 @@ bitflags!(
         const MEMORY_READ = 0x8000;
         ///
         const MEMORY_WRITE = 0x10000;
+        /// Read the conditional rendering predicate in the [`CONDITIONAL_RENDERING`](../pso/struct.PipelineStage.html#associatedconstant.CONDITIONAL_RENDERING) stage.
+        const CONDITIONAL_RENDERING_READ = 0x10_0000;
+        /// Write captured vertex outputs in the [`TRANSFORM_FEEDBACK`](../pso/struct.PipelineStage.html#associatedconstant.TRANSFORM_FEEDBACK) stage.
+        const TRANSFORM_FEEDBACK_WRITE = 0x200_0000;
+        /// Read acceleration structures, or build inputs and scratch memory,
+        /// in the [`ACCELERATION_STRUCTURE_BUILD`](../pso/struct.PipelineStage.html#associatedconstant.ACCELERATION_STRUCTURE_BUILD) stage,
+        /// or from shaders doing ray queries.
+        const ACCELERATION_STRUCTURE_READ = 0x20_0000;
+        /// Write acceleration structures or scratch memory
+        /// in the [`ACCELERATION_STRUCTURE_BUILD`](../pso/struct.PipelineStage.html#associatedconstant.ACCELERATION_STRUCTURE_BUILD) stage.
+        const ACCELERATION_STRUCTURE_WRITE = 0x40_0000;
     }
 );
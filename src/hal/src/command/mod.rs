@@ -17,9 +17,9 @@ mod clear;
 mod structs;
 
 use crate::{
-    buffer,
+    acceleration_structure, buffer,
     image::{Filter, Layout, SubresourceRange},
-    memory::{Barrier, Dependencies},
+    memory::{self, Barrier, Barrier2, Dependencies},
     pass, pso, query, Backend, DrawCount, IndexCount, IndexType, InstanceCount, TaskCount,
     VertexCount, VertexOffset, WorkGroupCount,
 };
@@ -81,6 +81,21 @@ pub struct RenderAttachmentInfo<'a, B: Backend> {
     pub clear_value: ClearValue,
 }
 
+/// An attachment rendered to between `begin_rendering` and `end_rendering`.
+#[derive(Debug)]
+pub struct RenderingAttachmentInfo<'a, B: Backend> {
+    /// View of the attachment image.
+    pub image_view: &'a B::ImageView,
+    /// Layout of the image during rendering.
+    pub layout: Layout,
+    /// Load and store operations of the color or depth aspect.
+    pub ops: pass::AttachmentOps,
+    /// Load and store operations of the stencil aspect, if any.
+    pub stencil_ops: pass::AttachmentOps,
+    /// Clear value, used by the `Clear` load operations.
+    pub clear_value: ClearValue,
+}
+
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct CommandBufferInheritanceInfo<'a, B: Backend> {
@@ -138,6 +153,22 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     ) where
         T: Iterator<Item = Barrier<'a, B>>;
 
+    /// Inserts a set of barriers, each with its own source and destination stages,
+    /// in the command buffer.
+    ///
+    /// Without the [`SYNCHRONIZATION_2`][crate::Features::SYNCHRONIZATION_2] feature,
+    /// the barriers are [lowered][memory::lower_barriers], and the ones with the same
+    /// lowered stages are passed to a single
+    /// [`pipeline_barrier`][CommandBuffer::pipeline_barrier] call.
+    unsafe fn pipeline_barrier2<'a, T>(&mut self, dependencies: Dependencies, barriers: T)
+    where
+        T: Iterator<Item = Barrier2<'a, B>>,
+    {
+        for (stages, barriers) in memory::lower_barriers(barriers) {
+            self.pipeline_barrier(stages, dependencies, barriers.into_iter());
+        }
+    }
+
     /// Fill a buffer with the given `u32` value.
     unsafe fn fill_buffer(&mut self, buffer: &B::Buffer, range: buffer::SubRange, data: u32);
 
@@ -214,6 +245,20 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     where
         T: Iterator<Item = (&'a B::Buffer, buffer::SubRange)>;
 
+    /// Bind the vertex buffer set along with the stride of each buffer.
+    ///
+    /// This behaves like `bind_vertex_buffers`, with the strides overriding
+    /// the ones of the bound pipeline, which must have a dynamic stride
+    /// for the bound vertex buffers.
+    ///
+    /// Only valid to call if `DynamicStates::VERTEX_INPUT_BINDING_STRIDE` is supported.
+    unsafe fn bind_vertex_buffers_with_strides<'a, T>(
+        &mut self,
+        first_binding: pso::BufferIndex,
+        buffers: T,
+    ) where
+        T: Iterator<Item = (&'a B::Buffer, buffer::SubRange, pso::ElemStride)>;
+
     /// Set the [viewport][crate::pso::Viewport] parameters for the rasterizer.
     ///
     /// Each viewport passed corresponds to the viewport with the same index,
@@ -281,6 +326,42 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Set the depth bias dynamically.
     unsafe fn set_depth_bias(&mut self, depth_bias: pso::DepthBias);
 
+    /// Set the faces to cull dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::CULL_MODE` is supported.
+    unsafe fn set_cull_mode(&mut self, faces: pso::Face);
+
+    /// Set the front face winding order dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::FRONT_FACE` is supported.
+    unsafe fn set_front_face(&mut self, front_face: pso::FrontFace);
+
+    /// Set the primitive topology dynamically.
+    ///
+    /// The primitive must be of the same class as the one of the bound pipeline.
+    /// Only valid to call if `DynamicStates::PRIMITIVE_TOPOLOGY` is supported.
+    unsafe fn set_primitive_topology(&mut self, primitive: pso::Primitive);
+
+    /// Enable or disable the depth test dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::DEPTH_TEST_ENABLE` is supported.
+    unsafe fn set_depth_test_enable(&mut self, enable: bool);
+
+    /// Enable or disable the depth writes dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::DEPTH_WRITE_ENABLE` is supported.
+    unsafe fn set_depth_write_enable(&mut self, enable: bool);
+
+    /// Set the depth comparison function dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::DEPTH_COMPARE_OP` is supported.
+    unsafe fn set_depth_compare_op(&mut self, fun: pso::Comparison);
+
+    /// Set the stencil comparison function and operations dynamically.
+    ///
+    /// Only valid to call if `DynamicStates::STENCIL_OP` is supported.
+    unsafe fn set_stencil_op(&mut self, faces: pso::Face, ops: pso::StencilFace);
+
     /// Begins recording commands for a render pass on the given framebuffer.
     ///
     /// # Arguments
@@ -307,6 +388,31 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Finishes recording commands for the current a render pass.
     unsafe fn end_render_pass(&mut self);
 
+    /// Begins rendering to the given attachments, without render pass and
+    /// framebuffer objects.
+    ///
+    /// Graphics pipelines bound until [`end_rendering`][CommandBuffer::end_rendering]
+    /// must be created with a [`PipelineTarget::Rendering`][pass::PipelineTarget::Rendering]
+    /// matching the formats of the attachments.
+    ///
+    /// Requires the [`DYNAMIC_RENDERING`][crate::Features::DYNAMIC_RENDERING] feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `render_area` - section of the attachments to render.
+    /// * `color_attachments` - color attachments, in the order of the pipeline outputs.
+    /// * `depth_stencil_attachment` - depth-stencil attachment, if any.
+    unsafe fn begin_rendering<'a, T>(
+        &mut self,
+        render_area: pso::Rect,
+        color_attachments: T,
+        depth_stencil_attachment: Option<RenderingAttachmentInfo<'a, B>>,
+    ) where
+        T: Iterator<Item = RenderingAttachmentInfo<'a, B>>;
+
+    /// Finishes rendering started with [`begin_rendering`][CommandBuffer::begin_rendering].
+    unsafe fn end_rendering(&mut self);
+
     /// Bind a graphics pipeline.
     ///
     /// # Errors
@@ -353,6 +459,35 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
         I: Iterator<Item = &'a B::DescriptorSet>,
         J: Iterator<Item = DescriptorSetOffset>;
 
+    /// Pushes descriptors into the graphics descriptor set `set` of `layout`,
+    /// without allocating a descriptor set from a pool.
+    ///
+    /// The descriptor set layout must have been created with
+    /// [`PUSH_DESCRIPTOR`][pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR].
+    /// Descriptors are written starting at `binding` and `array_offset`, and spill over
+    /// onto the next bindings like in [`DescriptorSetWrite`][pso::DescriptorSetWrite].
+    unsafe fn push_graphics_descriptor_set<'a, I>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, B>>;
+
+    /// Pushes descriptors into the compute descriptor set `set` of `layout`,
+    /// see [`push_graphics_descriptor_set`][CommandBuffer::push_graphics_descriptor_set].
+    unsafe fn push_compute_descriptor_set<'a, I>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: I,
+    ) where
+        I: Iterator<Item = pso::Descriptor<'a, B>>;
+
     /// Execute a workgroup in the compute pipeline. `x`, `y` and `z` are the
     /// number of local workgroups to dispatch along each "axis"; a total of `x`*`y`*`z`
     /// local workgroups will be created.
@@ -414,6 +549,31 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     ) where
         T: Iterator<Item = BufferImageCopy>;
 
+    /// Builds or updates acceleration structures, each over the given ranges of primitives,
+    /// one per geometry of the build description.
+    ///
+    /// Builds happen in the
+    /// [`ACCELERATION_STRUCTURE_BUILD`][pso::PipelineStage::ACCELERATION_STRUCTURE_BUILD] stage,
+    /// and can't depend on each other: a top level structure must be built after its
+    /// bottom level structures with a barrier in between.
+    ///
+    /// Requires the [`ACCELERATION_STRUCTURE`][crate::Features::ACCELERATION_STRUCTURE] feature.
+    unsafe fn build_acceleration_structures<'a, I>(&mut self, descs: I)
+    where
+        I: Iterator<
+            Item = (
+                &'a acceleration_structure::BuildDesc<'a, B>,
+                &'a [acceleration_structure::BuildRangeDesc],
+            ),
+        >;
+
+    /// Copies an acceleration structure into another one of the same level and size.
+    unsafe fn copy_acceleration_structure(
+        &mut self,
+        src: &B::AccelerationStructure,
+        dst: &B::AccelerationStructure,
+    );
+
     // TODO: This explanation needs improvement.
     /// Performs a non-indexed drawing operation, fetching vertex attributes
     /// from the currently bound vertex buffers.  It performs instanced
@@ -574,6 +734,49 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Requests a timestamp to be written.
     unsafe fn write_timestamp(&mut self, stage: pso::PipelineStage, query: query::Query<B>);
 
+    /// Begins a conditional rendering block, in which draws and dispatches are discarded
+    /// if the `u32` at `offset` in `buffer` is zero, or non-zero when `inverted` is set.
+    /// Copies, clears and the other commands aren't affected.
+    ///
+    /// The buffer must have the [`CONDITIONAL_RENDERING`][buffer::Usage::CONDITIONAL_RENDERING]
+    /// usage, and `offset` must be a multiple of 4. The predicate is read in the
+    /// [`CONDITIONAL_RENDERING`][pso::PipelineStage::CONDITIONAL_RENDERING] stage.
+    ///
+    /// The GL backend predicates the rendering on the occlusion query itself, so the predicate
+    /// must be a result copied by [`copy_query_pool_results`][CommandBuffer::copy_query_pool_results],
+    /// the query must not begin again, and the buffer must not be written by the host through
+    /// a coherent mapping in the meantime.
+    ///
+    /// Requires the [`CONDITIONAL_RENDERING`][crate::Features::CONDITIONAL_RENDERING] feature.
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    );
+
+    /// Ends the current conditional rendering block.
+    unsafe fn end_conditional_rendering(&mut self);
+
+    /// Binds buffers to the transform feedback bindings, starting at `first_binding`.
+    ///
+    /// The buffers must have the [`TRANSFORM_FEEDBACK`][buffer::Usage::TRANSFORM_FEEDBACK] usage.
+    unsafe fn bind_transform_feedback_buffers<'a, T>(&mut self, first_binding: u32, buffers: T)
+    where
+        T: Iterator<Item = (&'a B::Buffer, buffer::SubRange)>;
+
+    /// Begins capturing the outputs of the vertex processing stages into the bound
+    /// transform feedback buffers, until `end_transform_feedback`. Each capture
+    /// starts writing at the beginning of the bound ranges.
+    ///
+    /// This must be called inside a render pass, with a graphics pipeline bound.
+    ///
+    /// Requires the [`TRANSFORM_FEEDBACK`][crate::Features::TRANSFORM_FEEDBACK] feature.
+    unsafe fn begin_transform_feedback(&mut self);
+
+    /// Ends capturing vertex outputs.
+    unsafe fn end_transform_feedback(&mut self);
+
     /// Modify constant data in a graphics pipeline. Push constants are intended to modify data in a
     /// pipeline more quickly than a updating the values inside a descriptor set.
     ///
@@ -12,12 +12,12 @@
 //! and is used to actually do things.
 
 use crate::{
-    buffer, display, external_memory, format, image, memory,
+    acceleration_structure, adapter, buffer, display, external_memory, format, image, memory,
     memory::{Requirements, Segment},
     pass,
     pool::CommandPoolCreateFlags,
     pso,
-    pso::DescriptorPoolCreateFlags,
+    pso::{DescriptorPoolCreateFlags, DescriptorSetLayoutCreateFlags},
     query,
     queue::QueueFamilyId,
     Backend, MemoryTypeId,
@@ -30,6 +30,44 @@ use std::{any::Any, fmt, iter, ops::Range};
 #[error("Device lost")]
 pub struct DeviceLost;
 
+/// Point of a command buffer recorded in fault diagnostics mode.
+///
+/// See [`DEVICE_FAULT_DIAGNOSTICS`][crate::Features::DEVICE_FAULT_DIAGNOSTICS].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Breadcrumb {
+    /// A debug marker was inserted, or a debug marker region began, with this name.
+    DebugMarker(String),
+    /// A render pass, or a dynamic rendering instance, ended.
+    EndRenderPass,
+}
+
+/// What is known about the loss of a device.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaultInfo {
+    /// Description of the fault, if the driver reports one.
+    pub description: Option<String>,
+    /// Last breadcrumb the device got past before getting lost, if the
+    /// backend is able to record them.
+    pub last_breadcrumb: Option<Breadcrumb>,
+}
+
+/// A device timestamp and a host timestamp, sampled at the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CalibratedTimestamps {
+    /// Device timestamp, in the ticks written by
+    /// [`write_timestamp`][crate::command::CommandBuffer::write_timestamp], which last
+    /// [`timestamp_period`][crate::queue::Queue::timestamp_period] nanoseconds.
+    pub device: u64,
+    /// Host timestamp, in nanoseconds of the monotonic clock of the platform:
+    /// `CLOCK_MONOTONIC` on Unix, and `QueryPerformanceCounter` on Windows.
+    pub host_ns: u64,
+    /// Upper bound of the time between the samplings of both timestamps, in nanoseconds.
+    pub max_deviation_ns: u64,
+}
+
 /// Error allocating memory.
 #[derive(Clone, Debug, PartialEq, thiserror::Error)]
 pub enum OutOfMemory {
@@ -195,15 +233,27 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     ///
     /// * `memory_type` - Index of the memory type in the memory properties of the associated physical device.
     /// * `size` - Size of the allocation.
+    /// * `flags` - Additional capabilities of the allocation.
     unsafe fn allocate_memory(
         &self,
         memory_type: MemoryTypeId,
         size: u64,
+        flags: memory::AllocationFlags,
     ) -> Result<B::Memory, AllocationError>;
 
     /// Free device memory
     unsafe fn free_memory(&self, memory: B::Memory);
 
+    /// Get the budget and current usage of every memory heap, in the order of
+    /// [`memory_heaps`][crate::adapter::MemoryProperties::memory_heaps].
+    ///
+    /// The budget changes as other processes allocate and free memory, so it has to be
+    /// queried again, e.g. once per frame.
+    ///
+    /// Without the [`MEMORY_BUDGET`][crate::Features::MEMORY_BUDGET] feature, the budget
+    /// of every heap is its whole size, and the usage may be reported as zero.
+    fn memory_budget(&self) -> Vec<adapter::MemoryHeapBudget>;
+
     /// Create a new [command pool][crate::pool::CommandPool] for a given queue family.
     ///
     /// *Note*: the family has to be associated with one of [the queue groups
@@ -393,6 +443,13 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         buf: &mut B::Buffer,
     ) -> Result<(), BindError>;
 
+    /// Get the address of a buffer on the device, which shaders can use to access it.
+    ///
+    /// The buffer must have been created with [`DEVICE_ADDRESS`][buffer::Usage::DEVICE_ADDRESS] usage,
+    /// and bound to memory allocated with [`DEVICE_ADDRESS`][memory::AllocationFlags::DEVICE_ADDRESS].
+    /// Requires the [`BUFFER_DEVICE_ADDRESS`][crate::Features::BUFFER_DEVICE_ADDRESS] feature.
+    unsafe fn get_buffer_device_address(&self, buffer: &B::Buffer) -> buffer::DeviceAddress;
+
     /// Destroy a buffer.
     ///
     /// The buffer shouldn't be destroyed before any submitted command buffer,
@@ -449,6 +506,9 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     unsafe fn destroy_image(&self, image: B::Image);
 
     /// Create an image view from an existing image
+    ///
+    /// Views sampled with a Y'CbCr conversion must be created with the
+    /// [conversion][image::SamplerDesc::ycbcr_conversion] of the sampler.
     unsafe fn create_image_view(
         &self,
         image: &B::Image,
@@ -457,6 +517,7 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         swizzle: format::Swizzle,
         usage: image::Usage,
         range: image::SubresourceRange,
+        ycbcr_conversion: Option<image::YcbcrConversionDesc>,
     ) -> Result<B::ImageView, image::ViewCreationError>;
 
     /// Destroy an image view object
@@ -471,6 +532,39 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Destroy a sampler object
     unsafe fn destroy_sampler(&self, sampler: B::Sampler);
 
+    /// Get the memory needed to build an acceleration structure over `desc`,
+    /// where the geometry at index `i` contains up to `max_primitive_counts[i]` primitives.
+    ///
+    /// The buffers referenced by `desc` are not accessed.
+    ///
+    /// Requires the [`ACCELERATION_STRUCTURE`][crate::Features::ACCELERATION_STRUCTURE] feature.
+    unsafe fn get_acceleration_structure_build_requirements(
+        &self,
+        desc: &acceleration_structure::GeometryDesc<B>,
+        max_primitive_counts: &[u32],
+    ) -> acceleration_structure::SizeRequirements;
+
+    /// Create a new acceleration structure, stored in a range of a buffer.
+    ///
+    /// The acceleration structure contents are undefined until it's built with
+    /// [`build_acceleration_structures`][crate::command::CommandBuffer::build_acceleration_structures].
+    unsafe fn create_acceleration_structure(
+        &self,
+        desc: &acceleration_structure::CreateDesc<B>,
+    ) -> Result<B::AccelerationStructure, OutOfMemory>;
+
+    /// Get the device address of a bottom level acceleration structure,
+    /// to reference it from the [instances][acceleration_structure::Instance] of a top level one.
+    unsafe fn get_acceleration_structure_address(
+        &self,
+        accel_struct: &B::AccelerationStructure,
+    ) -> acceleration_structure::DeviceAddress;
+
+    /// Destroy an acceleration structure.
+    ///
+    /// The buffer storing it is left untouched.
+    unsafe fn destroy_acceleration_structure(&self, accel_struct: B::AccelerationStructure);
+
     /// Create a descriptor pool.
     ///
     /// Descriptor pools allow allocation of descriptor sets.
@@ -501,6 +595,7 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         &self,
         bindings: I,
         immutable_samplers: J,
+        flags: DescriptorSetLayoutCreateFlags,
     ) -> Result<B::DescriptorSetLayout, OutOfMemory>
     where
         I: Iterator<Item = pso::DescriptorSetLayoutBinding>,
@@ -517,6 +612,33 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Structure specifying a copy descriptor set operation.
     unsafe fn copy_descriptor_set<'a>(&self, op: pso::DescriptorSetCopy<'a, B>);
 
+    /// Create a descriptor update template, which writes descriptors stored in user memory
+    /// to descriptor sets allocated with `layout`.
+    ///
+    /// Applying a template built once is cheaper than writing the same bindings over and over
+    /// with [`write_descriptor_set`][Device::write_descriptor_set].
+    unsafe fn create_descriptor_update_template<I>(
+        &self,
+        layout: &B::DescriptorSetLayout,
+        entries: I,
+    ) -> Result<B::DescriptorUpdateTemplate, OutOfMemory>
+    where
+        I: Iterator<Item = pso::DescriptorUpdateTemplateEntry>;
+
+    /// Destroy a descriptor update template.
+    unsafe fn destroy_descriptor_update_template(&self, template: B::DescriptorUpdateTemplate);
+
+    /// Write the descriptors stored in `data` to `set`, at the locations described by
+    /// the [entries][pso::DescriptorUpdateTemplateEntry] of `template`.
+    ///
+    /// `set` must have been allocated with the layout the template was created with.
+    unsafe fn update_descriptor_set_with_template(
+        &self,
+        set: &mut B::DescriptorSet,
+        template: &B::DescriptorUpdateTemplate,
+        data: *const u8,
+    );
+
     /// Map a memory object into application address space
     ///
     /// Call `map_memory()` to retrieve a host virtual address pointer to a region of a mappable memory object
@@ -542,9 +664,45 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Create a new semaphore object.
     fn create_semaphore(&self) -> Result<B::Semaphore, OutOfMemory>;
 
+    /// Create a new timeline semaphore object, holding `initial_value`.
+    ///
+    /// Timeline semaphores are signaled and waited on with a value rather than
+    /// a binary state: a wait completes once the semaphore value is greater than
+    /// or equal to the requested one.
+    ///
+    /// Requires the [`TIMELINE_SEMAPHORE`][crate::Features::TIMELINE_SEMAPHORE] feature.
+    fn create_timeline_semaphore(&self, initial_value: u64) -> Result<B::Semaphore, OutOfMemory>;
+
     /// Destroy a semaphore object.
     unsafe fn destroy_semaphore(&self, semaphore: B::Semaphore);
 
+    /// Query the current value of a timeline semaphore.
+    unsafe fn get_semaphore_counter_value(
+        &self,
+        semaphore: &B::Semaphore,
+    ) -> Result<u64, DeviceLost>;
+
+    /// Blocks until all or one of the given timeline semaphores reach their paired value.
+    /// Returns true if the semaphores were signaled before the timeout.
+    unsafe fn wait_semaphores<'a, I>(
+        &self,
+        semaphores: I,
+        wait: WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, WaitError>
+    where
+        I: Iterator<Item = (&'a B::Semaphore, u64)>;
+
+    /// Sets a timeline semaphore to `value` from the host.
+    ///
+    /// The value must be greater than the current one, and than any value
+    /// the semaphore has pending signal operations for.
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        value: u64,
+    ) -> Result<(), OutOfMemory>;
+
     /// Create a new fence object.
     ///
     /// Fences are a synchronization primitive that **can** be used to insert a dependency from
@@ -659,6 +817,41 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         count: query::Id,
     ) -> Result<B::QueryPool, query::CreationError>;
 
+    /// Create a new query pool object, which samples the given hardware counters
+    /// of a queue family.
+    ///
+    /// The counters are indices into the list returned by
+    /// [`performance_counters`][adapter::PhysicalDevice::performance_counters] for the family,
+    /// and the queries of the pool may only be used on the queues of that family.
+    /// The results of a query are made of [`PERFORMANCE_COUNTER_RESULT_SIZE`] bytes per counter,
+    /// in the order of `counters`, which can be decoded with their
+    /// [storage][query::PerformanceCounterStorage::decode]. They are retrieved with
+    /// [`get_query_pool_results`][Device::get_query_pool_results] and no other flag than `WAIT`.
+    ///
+    /// Returns [`Unsupported`][query::CreationError::Unsupported] if the counters can't be
+    /// sampled together in a single pass.
+    ///
+    /// Requires the [`PERFORMANCE_QUERY`][crate::Features::PERFORMANCE_QUERY] feature.
+    ///
+    /// [`PERFORMANCE_COUNTER_RESULT_SIZE`]: query::PERFORMANCE_COUNTER_RESULT_SIZE
+    unsafe fn create_performance_query_pool(
+        &self,
+        family: QueueFamilyId,
+        counters: &[u32],
+        count: query::Id,
+    ) -> Result<B::QueryPool, query::CreationError>;
+
+    /// Acquire the profiling lock, which must be held while the command buffers recording
+    /// performance queries are recorded and executed.
+    ///
+    /// Returns `Ok(false)` if the lock couldn't be acquired before the timeout, in nanoseconds,
+    /// which is always the case without the
+    /// [`PERFORMANCE_QUERY`][crate::Features::PERFORMANCE_QUERY] feature.
+    unsafe fn acquire_profiling_lock(&self, timeout_ns: u64) -> Result<bool, OutOfMemory>;
+
+    /// Release the profiling lock. Does nothing if the lock isn't held.
+    unsafe fn release_profiling_lock(&self);
+
     /// Destroy a query pool object
     unsafe fn destroy_query_pool(&self, pool: B::QueryPool);
 
@@ -673,10 +866,31 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         flags: query::ResultFlags,
     ) -> Result<bool, WaitError>;
 
+    /// Sample the timestamp counter of the device along with the host clock, to
+    /// correlate the timestamp queries with the timeline of the host.
+    ///
+    /// The clocks may drift apart, so they should be calibrated again from time to time.
+    ///
+    /// Requires the [`CALIBRATED_TIMESTAMPS`][crate::Features::CALIBRATED_TIMESTAMPS] feature.
+    fn get_calibrated_timestamps(&self) -> Result<CalibratedTimestamps, OutOfMemory>;
+
     /// Wait for all queues associated with this device to idle.
     ///
     /// Host access to all queues needs to be **externally** sycnhronized!
-    fn wait_idle(&self) -> Result<(), OutOfMemory>;
+    ///
+    /// Returns `DeviceLost` instead of waiting on work that never completes
+    /// once the device is lost.
+    fn wait_idle(&self) -> Result<(), WaitError>;
+
+    /// Get the diagnostics of a lost device.
+    ///
+    /// Returns `None` if the [`DEVICE_FAULT_DIAGNOSTICS`][crate::Features::DEVICE_FAULT_DIAGNOSTICS]
+    /// feature isn't enabled.
+    ///
+    /// # Safety
+    ///
+    /// The device must have been reported as lost by one of its calls, or by one of its queues.
+    unsafe fn get_fault_info(&self) -> Option<FaultInfo>;
 
     /// Associate a name with an image, for easier debugging in external tools or with validation
     /// layers that can print a friendly name when referring to objects in error messages
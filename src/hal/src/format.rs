@@ -26,6 +26,12 @@ bitflags!(
         const DEPTH = 0x2;
         /// Stencil aspect.
         const STENCIL = 0x4;
+        /// First plane of a multi-planar format.
+        const PLANE_0 = 0x10;
+        /// Second plane of a multi-planar format.
+        const PLANE_1 = 0x20;
+        /// Third plane of a multi-planar format.
+        const PLANE_2 = 0x40;
     }
 );
 
@@ -40,6 +46,8 @@ pub struct FormatDesc {
     /// * The total number can be larger than the sum of individual format bits
     ///   (`color`, `alpha`, `depth` and `stencil`) for packed formats.
     /// * For compressed formats, this denotes the number of bits per block.
+    /// * For multi-planar formats, this denotes the number of bits of a texel
+    ///   of the first plane, see [`Format::plane`] for the other ones.
     pub bits: u16,
     /// Dimensions (width, height) of the texel blocks.
     pub dim: (u8, u8),
@@ -151,7 +159,7 @@ impl Default for DrmFormatProperties {
 
 /// Format properties of the physical device.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct Properties {
     /// A bitmask of the features supported when an image with linear tiling is requested.
     /// Linear tiling has a known layout in-memory so data can be copied to and from host
@@ -201,6 +209,11 @@ bitflags!(
         const TRANSFER_SRC = 0x4000;
         /// Image can be copied to.
         const TRANSFER_DST = 0x8000;
+
+        /// Image can be sampled with a Y'CbCr conversion using `ChromaLocation::Midpoint`.
+        const MIDPOINT_CHROMA_SAMPLES = 0x1_0000;
+        /// Image can be sampled with a Y'CbCr conversion using `ChromaLocation::CositedEven`.
+        const COSITED_CHROMA_SAMPLES = 0x2_0000;
     }
 );
 
@@ -354,6 +367,71 @@ surface_types! {
     ASTC_10x10          { 128, COLOR, (10, 10) },
     ASTC_12x10          { 128, COLOR, (12, 10) },
     ASTC_12x12          { 128, COLOR, (12, 12) },
+    G8_B8_G8_R8_422     {  32, COLOR, (2, 1), color: 24 },
+    B8_G8_R8_G8_422     {  32, COLOR, (2, 1), color: 24 },
+    G8_B8_R8_3PLANE_420 {   8, COLOR, (1, 1), color: 24 },
+    G8_B8R8_2PLANE_420  {   8, COLOR, (1, 1), color: 24 },
+    G8_B8_R8_3PLANE_422 {   8, COLOR, (1, 1), color: 24 },
+    G8_B8R8_2PLANE_422  {   8, COLOR, (1, 1), color: 24 },
+    G8_B8_R8_3PLANE_444 {   8, COLOR, (1, 1), color: 24 },
+    R10X6               { !16, COLOR, (1, 1), color: 10 },
+    R10X6_G10X6         { !32, COLOR, (1, 1), color: 20 },
+    R10X6_G10X6_B10X6_A10X6 { !64, COLOR, (1, 1), color: 30, alpha: 10 },
+    G10X6_B10X6_G10X6_R10X6_422 { !64, COLOR, (2, 1), color: 30 },
+    B10X6_G10X6_R10X6_G10X6_422 { !64, COLOR, (2, 1), color: 30 },
+    G10X6_B10X6_R10X6_3PLANE_420 { !16, COLOR, (1, 1), color: 30 },
+    G10X6_B10X6R10X6_2PLANE_420 { !16, COLOR, (1, 1), color: 30 },
+    G10X6_B10X6_R10X6_3PLANE_422 { !16, COLOR, (1, 1), color: 30 },
+    G10X6_B10X6R10X6_2PLANE_422 { !16, COLOR, (1, 1), color: 30 },
+    G10X6_B10X6_R10X6_3PLANE_444 { !16, COLOR, (1, 1), color: 30 },
+    R12X4               { !16, COLOR, (1, 1), color: 12 },
+    R12X4_G12X4         { !32, COLOR, (1, 1), color: 24 },
+    R12X4_G12X4_B12X4_A12X4 { !64, COLOR, (1, 1), color: 36, alpha: 12 },
+    G12X4_B12X4_G12X4_R12X4_422 { !64, COLOR, (2, 1), color: 36 },
+    B12X4_G12X4_R12X4_G12X4_422 { !64, COLOR, (2, 1), color: 36 },
+    G12X4_B12X4_R12X4_3PLANE_420 { !16, COLOR, (1, 1), color: 36 },
+    G12X4_B12X4R12X4_2PLANE_420 { !16, COLOR, (1, 1), color: 36 },
+    G12X4_B12X4_R12X4_3PLANE_422 { !16, COLOR, (1, 1), color: 36 },
+    G12X4_B12X4R12X4_2PLANE_422 { !16, COLOR, (1, 1), color: 36 },
+    G12X4_B12X4_R12X4_3PLANE_444 { !16, COLOR, (1, 1), color: 36 },
+    G16_B16_G16_R16_422 {  64, COLOR, (2, 1), color: 48 },
+    B16_G16_R16_G16_422 {  64, COLOR, (2, 1), color: 48 },
+    G16_B16_R16_3PLANE_420 { 16, COLOR, (1, 1), color: 48 },
+    G16_B16R16_2PLANE_420 { 16, COLOR, (1, 1), color: 48 },
+    G16_B16_R16_3PLANE_422 { 16, COLOR, (1, 1), color: 48 },
+    G16_B16R16_2PLANE_422 { 16, COLOR, (1, 1), color: 48 },
+    G16_B16_R16_3PLANE_444 { 16, COLOR, (1, 1), color: 48 },
+}
+
+impl SurfaceType {
+    /// Returns the bit depth, the number of planes and the chroma subsampling
+    /// of a multi-planar surface type.
+    fn planar_layout(&self) -> Option<(u8, usize, (u8, u8))> {
+        use self::SurfaceType::*;
+        Some(match *self {
+            G8_B8_R8_3PLANE_420 => (8, 3, (2, 2)),
+            G8_B8R8_2PLANE_420 => (8, 2, (2, 2)),
+            G8_B8_R8_3PLANE_422 => (8, 3, (2, 1)),
+            G8_B8R8_2PLANE_422 => (8, 2, (2, 1)),
+            G8_B8_R8_3PLANE_444 => (8, 3, (1, 1)),
+            G10X6_B10X6_R10X6_3PLANE_420 => (10, 3, (2, 2)),
+            G10X6_B10X6R10X6_2PLANE_420 => (10, 2, (2, 2)),
+            G10X6_B10X6_R10X6_3PLANE_422 => (10, 3, (2, 1)),
+            G10X6_B10X6R10X6_2PLANE_422 => (10, 2, (2, 1)),
+            G10X6_B10X6_R10X6_3PLANE_444 => (10, 3, (1, 1)),
+            G12X4_B12X4_R12X4_3PLANE_420 => (12, 3, (2, 2)),
+            G12X4_B12X4R12X4_2PLANE_420 => (12, 2, (2, 2)),
+            G12X4_B12X4_R12X4_3PLANE_422 => (12, 3, (2, 1)),
+            G12X4_B12X4R12X4_2PLANE_422 => (12, 2, (2, 1)),
+            G12X4_B12X4_R12X4_3PLANE_444 => (12, 3, (1, 1)),
+            G16_B16_R16_3PLANE_420 => (16, 3, (2, 2)),
+            G16_B16R16_2PLANE_420 => (16, 2, (2, 2)),
+            G16_B16_R16_3PLANE_422 => (16, 3, (2, 1)),
+            G16_B16R16_2PLANE_422 => (16, 2, (2, 1)),
+            G16_B16_R16_3PLANE_444 => (16, 3, (1, 1)),
+            _ => return None,
+        })
+    }
 }
 
 /// Generic run-time base format.
@@ -613,6 +691,52 @@ formats! {
     Astc12x10Srgb = (ASTC_12x10, Srgb),
     Astc12x12Unorm = (ASTC_12x12, Unorm),
     Astc12x12Srgb = (ASTC_12x12, Srgb),
+    // Formats of `VK_KHR_sampler_ycbcr_conversion`, which have contiguous Vulkan values.
+    G8b8g8r8_422Unorm = (G8_B8_G8_R8_422, Unorm),
+    B8g8r8g8_422Unorm = (B8_G8_R8_G8_422, Unorm),
+    G8b8r8_3Plane420Unorm = (G8_B8_R8_3PLANE_420, Unorm),
+    G8b8r8_2Plane420Unorm = (G8_B8R8_2PLANE_420, Unorm),
+    G8b8r8_3Plane422Unorm = (G8_B8_R8_3PLANE_422, Unorm),
+    G8b8r8_2Plane422Unorm = (G8_B8R8_2PLANE_422, Unorm),
+    G8b8r8_3Plane444Unorm = (G8_B8_R8_3PLANE_444, Unorm),
+    R10x6Unorm = (R10X6, Unorm),
+    R10x6g10x6Unorm = (R10X6_G10X6, Unorm),
+    R10x6g10x6b10x6a10x6Unorm = (R10X6_G10X6_B10X6_A10X6, Unorm),
+    G10x6b10x6g10x6r10x6_422Unorm = (G10X6_B10X6_G10X6_R10X6_422, Unorm),
+    B10x6g10x6r10x6g10x6_422Unorm = (B10X6_G10X6_R10X6_G10X6_422, Unorm),
+    G10x6b10x6r10x6_3Plane420Unorm = (G10X6_B10X6_R10X6_3PLANE_420, Unorm),
+    G10x6b10x6r10x6_2Plane420Unorm = (G10X6_B10X6R10X6_2PLANE_420, Unorm),
+    G10x6b10x6r10x6_3Plane422Unorm = (G10X6_B10X6_R10X6_3PLANE_422, Unorm),
+    G10x6b10x6r10x6_2Plane422Unorm = (G10X6_B10X6R10X6_2PLANE_422, Unorm),
+    G10x6b10x6r10x6_3Plane444Unorm = (G10X6_B10X6_R10X6_3PLANE_444, Unorm),
+    R12x4Unorm = (R12X4, Unorm),
+    R12x4g12x4Unorm = (R12X4_G12X4, Unorm),
+    R12x4g12x4b12x4a12x4Unorm = (R12X4_G12X4_B12X4_A12X4, Unorm),
+    G12x4b12x4g12x4r12x4_422Unorm = (G12X4_B12X4_G12X4_R12X4_422, Unorm),
+    B12x4g12x4r12x4g12x4_422Unorm = (B12X4_G12X4_R12X4_G12X4_422, Unorm),
+    G12x4b12x4r12x4_3Plane420Unorm = (G12X4_B12X4_R12X4_3PLANE_420, Unorm),
+    G12x4b12x4r12x4_2Plane420Unorm = (G12X4_B12X4R12X4_2PLANE_420, Unorm),
+    G12x4b12x4r12x4_3Plane422Unorm = (G12X4_B12X4_R12X4_3PLANE_422, Unorm),
+    G12x4b12x4r12x4_2Plane422Unorm = (G12X4_B12X4R12X4_2PLANE_422, Unorm),
+    G12x4b12x4r12x4_3Plane444Unorm = (G12X4_B12X4_R12X4_3PLANE_444, Unorm),
+    G16b16g16r16_422Unorm = (G16_B16_G16_R16_422, Unorm),
+    B16g16r16g16_422Unorm = (B16_G16_R16_G16_422, Unorm),
+    G16b16r16_3Plane420Unorm = (G16_B16_R16_3PLANE_420, Unorm),
+    G16b16r16_2Plane420Unorm = (G16_B16R16_2PLANE_420, Unorm),
+    G16b16r16_3Plane422Unorm = (G16_B16_R16_3PLANE_422, Unorm),
+    G16b16r16_2Plane422Unorm = (G16_B16R16_2PLANE_422, Unorm),
+    G16b16r16_3Plane444Unorm = (G16_B16_R16_3PLANE_444, Unorm),
+}
+
+/// A plane of a multi-planar format.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plane {
+    /// Format of the plane, used to view or copy the plane on its own.
+    pub format: Format,
+    /// Factors the width and height of the image are divided by
+    /// to get the extent of the plane.
+    pub subsampling: (u8, u8),
 }
 
 impl Format {
@@ -643,6 +767,39 @@ impl Format {
     pub fn is_stencil(self) -> bool {
         self.surface_desc().aspects.contains(Aspects::STENCIL)
     }
+
+    /// Returns the number of planes of the format,
+    /// which is 2 or 3 for multi-planar formats and 1 for the other ones.
+    pub fn plane_count(self) -> usize {
+        self.base_format()
+            .0
+            .planar_layout()
+            .map_or(1, |(_, count, _)| count)
+    }
+
+    /// Returns the plane `index` of a multi-planar format, which is selected
+    /// by the aspect `PLANE_<index>`.
+    ///
+    /// Returns `None` if the format is not multi-planar or has fewer planes.
+    pub fn plane(self, index: usize) -> Option<Plane> {
+        let (depth, count, subsampling) = self.base_format().0.planar_layout()?;
+        let (single, pair) = match depth {
+            8 => (Format::R8Unorm, Format::Rg8Unorm),
+            10 => (Format::R10x6Unorm, Format::R10x6g10x6Unorm),
+            12 => (Format::R12x4Unorm, Format::R12x4g12x4Unorm),
+            _ => (Format::R16Unorm, Format::Rg16Unorm),
+        };
+        let (format, subsampling) = match (index, count) {
+            (0, _) => (single, (1, 1)),
+            (1, 2) => (pair, subsampling),
+            (1, 3) | (2, 3) => (single, subsampling),
+            _ => return None,
+        };
+        Some(Plane {
+            format,
+            subsampling,
+        })
+    }
 }
 
 // Common vertex attribute formats
@@ -511,6 +511,12 @@ pub struct SamplerDesc {
     ///
     /// Can be `Some(_)` only if `Features::SAMPLER_ANISOTROPY` is enabled.
     pub anisotropy_clamp: Option<u8>,
+    /// Y'CbCr conversion applied to the sampled texels.
+    ///
+    /// Can be `Some(_)` only if `Features::SAMPLER_YCBCR_CONVERSION` is enabled.
+    /// Such samplers can only be used as immutable samplers, with image views
+    /// created with the same conversion.
+    pub ycbcr_conversion: Option<YcbcrConversionDesc>,
 }
 
 impl SamplerDesc {
@@ -529,10 +535,71 @@ impl SamplerDesc {
             border: BorderColor::TransparentBlack,
             normalized: true,
             anisotropy_clamp: None,
+            ycbcr_conversion: None,
         }
     }
 }
 
+/// Color model of the source of a Y'CbCr conversion.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum YcbcrModel {
+    /// The texels are RGB, and are only range expanded.
+    RgbIdentity,
+    /// The texels are Y'CbCr, and are only range expanded.
+    YcbcrIdentity,
+    /// Converts from Y'CbCr to R'G'B' as defined in BT.709.
+    Ycbcr709,
+    /// Converts from Y'CbCr to R'G'B' as defined in BT.601.
+    Ycbcr601,
+    /// Converts from Y'CbCr to R'G'B' as defined in BT.2020.
+    Ycbcr2020,
+}
+
+/// Range of the encoded values of a Y'CbCr conversion.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum YcbcrRange {
+    /// The full range of the encoded values is used.
+    ItuFull,
+    /// Headroom and footroom are reserved, as defined in ITU.
+    ItuNarrow,
+}
+
+/// Location of the downsampled chroma samples, relative to the luma samples.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChromaLocation {
+    /// Chroma samples are aligned with the even luma samples.
+    CositedEven,
+    /// Chroma samples are located halfway between each even luma sample
+    /// and the following odd one.
+    Midpoint,
+}
+
+/// Describes how the texels of an image in a Y'CbCr format
+/// are converted to RGB when sampled.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct YcbcrConversionDesc {
+    /// Format of the sampled image.
+    pub format: format::Format,
+    /// Color model to convert from.
+    pub model: YcbcrModel,
+    /// Range of the encoded values.
+    pub range: YcbcrRange,
+    /// Swizzle applied before the conversion. It replaces the swizzle of the image view.
+    pub components: format::Swizzle,
+    /// Horizontal location of the chroma samples.
+    pub x_chroma_offset: ChromaLocation,
+    /// Vertical location of the chroma samples.
+    pub y_chroma_offset: ChromaLocation,
+    /// Filter used to reconstruct the chroma samples of subsampled formats.
+    pub chroma_filter: Filter,
+    /// Reconstruct the chroma samples explicitly, even when the implementation could do it implicitly.
+    pub force_explicit_reconstruction: bool,
+}
+
 /// Specifies options for how memory for an image is arranged.
 /// These are hints to the GPU driver and may or may not have actual
 /// performance effects, but describe constraints on how the data
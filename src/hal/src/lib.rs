@@ -48,6 +48,7 @@ extern crate serde;
 
 use std::{any::Any, fmt, hash::Hash};
 
+pub mod acceleration_structure;
 pub mod adapter;
 pub mod buffer;
 pub mod command;
@@ -293,6 +294,55 @@ bitflags! {
         const SAMPLER_REDUCTION = 0x0004 << 96;
         /// Supports external memory import and export.
         const EXTERNAL_MEMORY = 0x0008 << 96;
+        /// Supports semaphores carrying a monotonically increasing 64-bit value.
+        const TIMELINE_SEMAPHORE = 0x0010 << 96;
+        /// Supports rendering without render pass and framebuffer objects,
+        /// with [`begin_rendering`][command::CommandBuffer::begin_rendering].
+        const DYNAMIC_RENDERING = 0x0020 << 96;
+        /// Supports building [acceleration structures][acceleration_structure]
+        /// and binding them to descriptor sets.
+        const ACCELERATION_STRUCTURE = 0x0040 << 96;
+        /// Supports ray queries against acceleration structures from any shader stage.
+        const RAY_QUERY = 0x0080 << 96;
+        /// Supports ray tracing pipelines.
+        const RAY_TRACING_PIPELINE = 0x0100 << 96;
+        /// Supports pushing descriptors directly into command buffers,
+        /// see [`DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR`][pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR].
+        const PUSH_DESCRIPTOR = 0x0200 << 96;
+        /// Supports querying the [device address][device::Device::get_buffer_device_address]
+        /// of buffers, so that shaders can access them through pointers.
+        const BUFFER_DEVICE_ADDRESS = 0x0400 << 96;
+        /// Supports rendering to several array layers of the attachments at once,
+        /// see [`SubpassDesc::view_mask`][pass::SubpassDesc::view_mask].
+        const MULTIVIEW = 0x0800 << 96;
+        /// Supports sampling images in Y'CbCr formats with a
+        /// [conversion][image::SamplerDesc::ycbcr_conversion] to RGB.
+        const SAMPLER_YCBCR_CONVERSION = 0x1000 << 96;
+        /// Supports skipping draws and dispatches based on a value in a buffer, with
+        /// [`begin_conditional_rendering`][command::CommandBuffer::begin_conditional_rendering].
+        const CONDITIONAL_RENDERING = 0x2000 << 96;
+        /// Supports capturing vertex shader outputs into buffers, see
+        /// [`begin_transform_feedback`][command::CommandBuffer::begin_transform_feedback].
+        const TRANSFORM_FEEDBACK = 0x4000 << 96;
+        /// Supports [barriers][command::CommandBuffer::pipeline_barrier2] and
+        /// [submissions][queue::Queue::submit2] with per-barrier and per-semaphore
+        /// stages natively, without lowering them to the coarser first synchronization API.
+        const SYNCHRONIZATION_2 = 0x8000 << 96;
+        /// Supports querying the [budget and usage][device::Device::memory_budget]
+        /// of the memory heaps.
+        const MEMORY_BUDGET = 0x1_0000 << 96;
+        /// Supports recording breadcrumbs after the debug markers and render passes of the
+        /// command buffers, which get reported with a description of the fault by
+        /// [`get_fault_info`][device::Device::get_fault_info] once the device is lost.
+        ///
+        /// Enabling it turns the diagnostics on, which has a cost on every command buffer.
+        const DEVICE_FAULT_DIAGNOSTICS = 0x2_0000 << 96;
+        /// Supports sampling the hardware [performance counters][adapter::PhysicalDevice::performance_counters]
+        /// of the queue families with [performance queries][query::Type::PerformanceCounters].
+        const PERFORMANCE_QUERY = 0x4_0000 << 96;
+        /// Supports sampling the timestamp counter of the device along with the host clock,
+        /// with [`get_calibrated_timestamps`][device::Device::get_calibrated_timestamps].
+        const CALIBRATED_TIMESTAMPS = 0x8_0000 << 96;
     }
 }
 
@@ -330,6 +380,22 @@ bitflags! {
         const STENCIL_WRITE_MASK = 0x0200;
         /// Supports `StencilTest::reference_values == State::Dynamic(_)`
         const STENCIL_REFERENCE = 0x0400;
+        /// Supports `Rasterizer::cull_face == State::Dynamic`
+        const CULL_MODE = 0x0800;
+        /// Supports `Rasterizer::front_face == State::Dynamic`
+        const FRONT_FACE = 0x1000;
+        /// Supports `InputAssemblerDesc::dynamic_primitive == true`
+        const PRIMITIVE_TOPOLOGY = 0x2000;
+        /// Supports `VertexBufferDesc::stride == State::Dynamic`
+        const VERTEX_INPUT_BINDING_STRIDE = 0x4000;
+        /// Supports `DepthStencilDesc::depth == State::Dynamic`
+        const DEPTH_TEST_ENABLE = 0x8000;
+        /// Supports `DepthTest::write == State::Dynamic`
+        const DEPTH_WRITE_ENABLE = 0x1_0000;
+        /// Supports `DepthTest::fun == State::Dynamic`
+        const DEPTH_COMPARE_OP = 0x2_0000;
+        /// Supports `StencilTest::faces == State::Dynamic`
+        const STENCIL_OP = 0x4_0000;
     }
 }
 
@@ -338,7 +404,7 @@ bitflags! {
 /// This contains things like resource limits, alignment requirements, and finer-grained feature
 /// capabilities.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct PhysicalDeviceProperties {
     /// Core limits.
     pub limits: Limits,
@@ -348,6 +414,8 @@ pub struct PhysicalDeviceProperties {
     pub mesh_shader: MeshShaderProperties,
     /// Sampler reduction modes.
     pub sampler_reduction: SamplerReductionProperties,
+    /// Subgroup operations.
+    pub subgroup: SubgroupProperties,
     /// Downlevel properties.
     pub downlevel: DownlevelProperties,
     /// Performance caveats.
@@ -360,7 +428,7 @@ pub struct PhysicalDeviceProperties {
 
 ///
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct DescriptorLimits {
     ///
     pub max_per_stage_descriptor_samplers: u32,
@@ -396,7 +464,7 @@ pub struct DescriptorLimits {
 
 /// Resource limits of a particular graphics device.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct Limits {
     /// Maximum supported image 1D size.
     pub max_image_1d_size: image::Size,
@@ -480,6 +548,8 @@ pub struct Limits {
 
     /// Maximum number of viewports.
     pub max_viewports: usize,
+    /// Maximum number of views in the [view mask][pass::SubpassDesc::view_mask] of a subpass.
+    pub max_multiview_view_count: u32,
     ///
     pub max_viewport_dimensions: [image::Size; 2],
     ///
@@ -521,7 +591,7 @@ pub struct Limits {
 
 /// Feature capabilities related to Descriptor Indexing.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct DescriptorIndexingProperties {
     ///
     pub shader_uniform_buffer_array_non_uniform_indexing_native: bool,
@@ -539,7 +609,7 @@ pub struct DescriptorIndexingProperties {
 
 /// Resource limits related to the Mesh Shaders.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct MeshShaderProperties {
     /// The maximum number of local workgroups that can be launched by a single draw mesh tasks command
     pub max_draw_mesh_tasks_count: u32,
@@ -583,7 +653,7 @@ pub struct MeshShaderProperties {
 
 /// Resource limits related to the reduction samplers.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct SamplerReductionProperties {
     /// Support for the minimum set of required formats support min/max filtering
     pub single_component_formats: bool,
@@ -591,9 +661,49 @@ pub struct SamplerReductionProperties {
     pub image_component_mapping: bool,
 }
 
+bitflags! {
+    /// Categories of subgroup operations supported by shaders.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct SubgroupOperations: u32 {
+        /// Subgroup barriers and electing a single invocation.
+        const BASIC = 0x0001;
+        /// Checking whether a predicate holds for all or any of the invocations.
+        const VOTE = 0x0002;
+        /// Reductions and scans, like sums or minimums over the invocations.
+        const ARITHMETIC = 0x0004;
+        /// Broadcasting values and collecting predicates into bit masks.
+        const BALLOT = 0x0008;
+        /// Reading values from arbitrary invocations.
+        const SHUFFLE = 0x0010;
+        /// Reading values from invocations at a relative index.
+        const SHUFFLE_RELATIVE = 0x0020;
+        /// Arithmetic operations over clusters of invocations.
+        const CLUSTERED = 0x0040;
+        /// Operations between the invocations of a quad.
+        const QUAD = 0x0080;
+    }
+}
+
+/// Properties of subgroups, the groups of shader invocations executed together,
+/// also known as waves or warps.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct SubgroupProperties {
+    /// Number of invocations in each subgroup, or 0 if subgroups aren't supported.
+    pub subgroup_size: u32,
+    /// Shader stages supporting subgroup operations.
+    pub supported_stages: pso::ShaderStageFlags,
+    /// Subgroup operations supported by these stages.
+    pub supported_operations: SubgroupOperations,
+    /// Quad operations are supported in all the supported stages,
+    /// and not only in fragment and compute shaders.
+    pub quad_operations_in_all_stages: bool,
+}
+
 /// Propterties to indicate when the backend does not support full vulkan compliance.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct DownlevelProperties {
     /// Supports compute shaders.
     pub compute_shaders: bool,
@@ -658,7 +768,7 @@ pub enum IndexType {
 pub struct UnsupportedBackend;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 /// Physical device limits for external memory management
 pub struct ExternalMemoryLimits {
     /// Alignment required for an imported host pointer
@@ -831,6 +941,8 @@ pub trait Backend: 'static + Sized + Eq + Clone + Hash + fmt::Debug + Any + Send
     type DescriptorSet: fmt::Debug + Any + Send + Sync;
     /// The corresponding descriptor set layout type for this backend.
     type DescriptorSetLayout: fmt::Debug + Any + Send + Sync;
+    /// The corresponding descriptor update template type for this backend.
+    type DescriptorUpdateTemplate: fmt::Debug + Any + Send + Sync;
 
     /// The corresponding fence type for this backend.
     type Fence: fmt::Debug + Any + Send + Sync;
@@ -844,4 +956,7 @@ pub trait Backend: 'static + Sized + Eq + Clone + Hash + fmt::Debug + Any + Send
     type Display: fmt::Debug + Any + Send + Sync;
     /// The corresponding display mode type for this backend
     type DisplayMode: fmt::Debug + Any + Send + Sync;
+
+    /// The corresponding [acceleration structure][acceleration_structure] type for this backend.
+    type AccelerationStructure: fmt::Debug + Any + Send + Sync;
 }
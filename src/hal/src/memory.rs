@@ -1,6 +1,6 @@
 //! Types to describe the properties of memory allocated for graphics resources.
 
-use crate::{buffer, image, queue, Backend};
+use crate::{buffer, image, pso, queue, Backend};
 use std::ops::Range;
 
 bitflags!(
@@ -100,6 +100,234 @@ impl<'a, B: Backend> Barrier<'a, B> {
     }
 }
 
+bitflags!(
+    /// Memory access types of the second synchronization API, for both buffers and images.
+    ///
+    /// This is a superset of [`buffer::Access`] and [`image::Access`], with the same
+    /// values for the shared access types.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct Access2: u64 {
+        /// Read commands instruction for indirect execution.
+        const INDIRECT_COMMAND_READ = 0x1;
+        /// Read index values for indexed draw commands.
+        const INDEX_BUFFER_READ = 0x2;
+        /// Read vertices from vertex buffer for draw commands.
+        const VERTEX_BUFFER_READ = 0x4;
+        /// Read uniform buffers from shaders.
+        const UNIFORM_READ = 0x8;
+        /// Read access to an input attachment from within a fragment shader.
+        const INPUT_ATTACHMENT_READ = 0x10;
+        /// Any read access from shaders.
+        const SHADER_READ = 0x20;
+        /// Any write access from shaders.
+        const SHADER_WRITE = 0x40;
+        /// Read access to a color attachment.
+        const COLOR_ATTACHMENT_READ = 0x80;
+        /// Write access to a color attachment.
+        const COLOR_ATTACHMENT_WRITE = 0x100;
+        /// Read access to a depth/stencil attachment in a depth or stencil operation.
+        const DEPTH_STENCIL_ATTACHMENT_READ = 0x200;
+        /// Write access to a depth/stencil attachment in a depth or stencil operation.
+        const DEPTH_STENCIL_ATTACHMENT_WRITE = 0x400;
+        /// Read access in a copy, resolve or blit operation.
+        const TRANSFER_READ = 0x800;
+        /// Write access in a copy, resolve, blit or clear operation.
+        const TRANSFER_WRITE = 0x1000;
+        /// Read access from the host.
+        const HOST_READ = 0x2000;
+        /// Write access from the host.
+        const HOST_WRITE = 0x4000;
+        /// Any read access.
+        const MEMORY_READ = 0x8000;
+        /// Any write access.
+        const MEMORY_WRITE = 0x10000;
+        /// Read the conditional rendering predicate.
+        const CONDITIONAL_RENDERING_READ = 0x10_0000;
+        /// Read acceleration structures, or build inputs and scratch memory.
+        const ACCELERATION_STRUCTURE_READ = 0x20_0000;
+        /// Write acceleration structures or scratch memory.
+        const ACCELERATION_STRUCTURE_WRITE = 0x40_0000;
+        /// Write captured vertex outputs.
+        const TRANSFORM_FEEDBACK_WRITE = 0x200_0000;
+        /// Read sampled images and uniform texel buffers from shaders.
+        const SHADER_SAMPLED_READ = 0x1_0000_0000;
+        /// Read storage buffers, storage images and storage texel buffers from shaders.
+        const SHADER_STORAGE_READ = 0x2_0000_0000;
+        /// Write storage buffers, storage images and storage texel buffers from shaders.
+        const SHADER_STORAGE_WRITE = 0x4_0000_0000;
+    }
+);
+
+impl Access2 {
+    fn lower_bits(self) -> u32 {
+        let mut bits = self.bits() as u32;
+        if self.intersects(Self::SHADER_SAMPLED_READ | Self::SHADER_STORAGE_READ) {
+            bits |= Self::SHADER_READ.bits() as u32;
+        }
+        if self.contains(Self::SHADER_STORAGE_WRITE) {
+            bits |= Self::SHADER_WRITE.bits() as u32;
+        }
+        bits
+    }
+
+    /// Convert into the closest set of buffer access types, dropping image-only ones.
+    pub fn lower_buffer(self) -> buffer::Access {
+        buffer::Access::from_bits_truncate(self.lower_bits())
+    }
+
+    /// Convert into the closest set of image access types, dropping buffer-only ones.
+    pub fn lower_image(self) -> image::Access {
+        image::Access::from_bits_truncate(self.lower_bits())
+    }
+}
+
+impl From<buffer::Access> for Access2 {
+    fn from(access: buffer::Access) -> Self {
+        Access2::from_bits_truncate(access.bits() as u64)
+    }
+}
+
+impl From<image::Access> for Access2 {
+    fn from(access: image::Access) -> Self {
+        Access2::from_bits_truncate(access.bits() as u64)
+    }
+}
+
+/// A memory barrier of the second synchronization API, which carries its own
+/// source and destination stages.
+///
+/// See [`pipeline_barrier2`][crate::command::CommandBuffer::pipeline_barrier2].
+#[derive(Clone, Debug)]
+pub enum Barrier2<'a, B: Backend> {
+    /// Applies the given access flags to all buffers in the range.
+    AllBuffers {
+        /// The stages to wait on, and the stages which wait.
+        stages: Range<pso::PipelineStage2>,
+        /// The access flags to make available, and to make visible.
+        accesses: Range<Access2>,
+    },
+    /// Applies the given access flags to all images in the range.
+    AllImages {
+        /// The stages to wait on, and the stages which wait.
+        stages: Range<pso::PipelineStage2>,
+        /// The access flags to make available, and to make visible.
+        accesses: Range<Access2>,
+    },
+    /// A memory barrier that defines access to a buffer.
+    Buffer {
+        /// The stages to wait on, and the stages which wait.
+        stages: Range<pso::PipelineStage2>,
+        /// The access flags controlling the buffer.
+        states: Range<Access2>,
+        /// The buffer the barrier controls.
+        target: &'a B::Buffer,
+        /// Subrange of the buffer the barrier applies to.
+        range: buffer::SubRange,
+        /// The source and destination Queue family IDs, or `None` to indicate no ownership transfer.
+        families: Option<Range<queue::QueueFamilyId>>,
+    },
+    /// A memory barrier that defines access to (a subset of) an image.
+    Image {
+        /// The stages to wait on, and the stages which wait.
+        stages: Range<pso::PipelineStage2>,
+        /// The access flags and layouts of the image.
+        states: Range<(Access2, image::Layout)>,
+        /// The image the barrier controls.
+        target: &'a B::Image,
+        /// A `SubresourceRange` that defines which section of an image the barrier applies to.
+        range: image::SubresourceRange,
+        /// The source and destination Queue family IDs, or `None` to indicate no ownership transfer.
+        families: Option<Range<queue::QueueFamilyId>>,
+    },
+}
+
+impl<'a, B: Backend> Barrier2<'a, B> {
+    /// Create a barrier for the whole buffer between the given stages and states.
+    pub fn whole_buffer(
+        target: &'a B::Buffer,
+        stages: Range<pso::PipelineStage2>,
+        states: Range<Access2>,
+    ) -> Self {
+        Barrier2::Buffer {
+            stages,
+            states,
+            target,
+            families: None,
+            range: buffer::SubRange::WHOLE,
+        }
+    }
+
+    /// Convert into a [`Barrier`] and the stages to pass along with it to
+    /// [`pipeline_barrier`][crate::command::CommandBuffer::pipeline_barrier].
+    pub fn lower(self) -> (Range<pso::PipelineStage>, Barrier<'a, B>) {
+        match self {
+            Barrier2::AllBuffers { stages, accesses } => (
+                stages.start.lower()..stages.end.lower(),
+                Barrier::AllBuffers(accesses.start.lower_buffer()..accesses.end.lower_buffer()),
+            ),
+            Barrier2::AllImages { stages, accesses } => (
+                stages.start.lower()..stages.end.lower(),
+                Barrier::AllImages(accesses.start.lower_image()..accesses.end.lower_image()),
+            ),
+            Barrier2::Buffer {
+                stages,
+                states,
+                target,
+                range,
+                families,
+            } => (
+                stages.start.lower()..stages.end.lower(),
+                Barrier::Buffer {
+                    states: states.start.lower_buffer()..states.end.lower_buffer(),
+                    target,
+                    range,
+                    families,
+                },
+            ),
+            Barrier2::Image {
+                stages,
+                states,
+                target,
+                range,
+                families,
+            } => (
+                stages.start.lower()..stages.end.lower(),
+                Barrier::Image {
+                    states: (states.start.0.lower_image(), states.start.1)
+                        ..(states.end.0.lower_image(), states.end.1),
+                    target,
+                    range,
+                    families,
+                },
+            ),
+        }
+    }
+}
+
+/// Lowers barriers of the second synchronization API with [`Barrier2::lower`],
+/// batching the ones with the same lowered stages together.
+///
+/// Each batch can be recorded with a single
+/// [`pipeline_barrier`][crate::command::CommandBuffer::pipeline_barrier] call.
+/// Batches are in the order of their first barrier.
+pub fn lower_barriers<'a, B, T>(
+    barriers: T,
+) -> Vec<(Range<pso::PipelineStage>, Vec<Barrier<'a, B>>)>
+where
+    B: Backend,
+    T: Iterator<Item = Barrier2<'a, B>>,
+{
+    let mut batches: Vec<(Range<pso::PipelineStage>, Vec<Barrier<'a, B>>)> = Vec::new();
+    for barrier in barriers {
+        let (stages, barrier) = barrier.lower();
+        match batches.iter_mut().find(|batch| batch.0 == stages) {
+            Some(batch) => batch.1.push(barrier),
+            None => batches.push((stages, vec![barrier])),
+        }
+    }
+    batches
+}
+
 /// Memory requirements for a certain resource (buffer/image).
 #[derive(Clone, Copy, Debug)]
 pub struct Requirements {
@@ -165,6 +393,20 @@ pub struct SparseImageBind<M> {
     pub memory: Option<(M, u64)>,
 }
 
+bitflags!(
+    /// Flags for allocating memory.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct AllocationFlags: u32 {
+        /// Buffers bound to the memory can be created with
+        /// [`DEVICE_ADDRESS`][crate::buffer::Usage::DEVICE_ADDRESS] usage, or with one
+        /// of the `ACCELERATION_STRUCTURE_*` usages, which need it as well.
+        /// Requires the [`BUFFER_DEVICE_ADDRESS`][crate::Features::BUFFER_DEVICE_ADDRESS]
+        /// or [`ACCELERATION_STRUCTURE`][crate::Features::ACCELERATION_STRUCTURE] feature.
+        const DEVICE_ADDRESS = 0x2;
+    }
+);
+
 bitflags!(
     /// Sparse flags for creating images and buffers.
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -179,3 +421,71 @@ bitflags!(
         const SPARSE_ALIASED = 0x0000_0004;
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::Access2;
+    use crate::{buffer, image};
+
+    #[test]
+    fn lower_access2() {
+        let cases = [
+            (
+                Access2::empty(),
+                buffer::Access::empty(),
+                image::Access::empty(),
+            ),
+            (
+                Access2::TRANSFER_READ | Access2::TRANSFER_WRITE,
+                buffer::Access::TRANSFER_READ | buffer::Access::TRANSFER_WRITE,
+                image::Access::TRANSFER_READ | image::Access::TRANSFER_WRITE,
+            ),
+            (
+                Access2::SHADER_READ,
+                buffer::Access::SHADER_READ,
+                image::Access::SHADER_READ,
+            ),
+            (
+                Access2::SHADER_SAMPLED_READ,
+                buffer::Access::SHADER_READ,
+                image::Access::SHADER_READ,
+            ),
+            (
+                Access2::SHADER_STORAGE_READ,
+                buffer::Access::SHADER_READ,
+                image::Access::SHADER_READ,
+            ),
+            (
+                Access2::SHADER_STORAGE_WRITE,
+                buffer::Access::SHADER_WRITE,
+                image::Access::SHADER_WRITE,
+            ),
+            (
+                Access2::INDEX_BUFFER_READ | Access2::UNIFORM_READ,
+                buffer::Access::INDEX_BUFFER_READ | buffer::Access::UNIFORM_READ,
+                image::Access::empty(),
+            ),
+            (
+                Access2::COLOR_ATTACHMENT_WRITE | Access2::DEPTH_STENCIL_ATTACHMENT_READ,
+                buffer::Access::empty(),
+                image::Access::COLOR_ATTACHMENT_WRITE
+                    | image::Access::DEPTH_STENCIL_ATTACHMENT_READ,
+            ),
+            (
+                Access2::TRANSFORM_FEEDBACK_WRITE | Access2::ACCELERATION_STRUCTURE_READ,
+                buffer::Access::TRANSFORM_FEEDBACK_WRITE
+                    | buffer::Access::ACCELERATION_STRUCTURE_READ,
+                image::Access::empty(),
+            ),
+            (
+                Access2::MEMORY_READ | Access2::HOST_WRITE,
+                buffer::Access::MEMORY_READ | buffer::Access::HOST_WRITE,
+                image::Access::MEMORY_READ | image::Access::HOST_WRITE,
+            ),
+        ];
+        for &(access, buffer_access, image_access) in cases.iter() {
+            assert_eq!(access.lower_buffer(), buffer_access, "{:?}", access);
+            assert_eq!(access.lower_image(), image_access, "{:?}", access);
+        }
+    }
+}
@@ -167,6 +167,22 @@ pub struct SubpassDesc<'a> {
     /// Attachments that are not used by the subpass but must be preserved to be
     /// passed on to subsequent passes.
     pub preserves: &'a [AttachmentId],
+    /// Bit mask of the views rendered by the subpass, each bit being an array layer
+    /// of the attachments. Zero disables multiview.
+    ///
+    /// Either all the subpasses of a render pass have a non-zero view mask, or none of them.
+    /// Requires [`Features::MULTIVIEW`][crate::Features::MULTIVIEW].
+    ///
+    /// The GL backend only renders to consecutive layers, with the same view mask
+    /// in every subpass. Rendering more than one view there also needs framebuffer
+    /// attachments with `SAMPLED` or `STORAGE` usage.
+    pub view_mask: u32,
+    /// Bit mask of the views that are spatially correlated, which the implementation
+    /// may render more efficiently at once. This is only a hint, and can be zero.
+    ///
+    /// Correlation masks of all the subpasses are combined for the whole render pass,
+    /// so a view can only be part of one distinct mask.
+    pub correlation_mask: u32,
 }
 
 /// A sub-pass borrow of a pass.
@@ -195,3 +211,45 @@ impl<'a, B: Backend> PartialEq for Subpass<'a, B> {
 
 impl<'a, B: Backend> Copy for Subpass<'a, B> {}
 impl<'a, B: Backend> Eq for Subpass<'a, B> {}
+
+/// Formats of the attachments rendered to between
+/// [`begin_rendering`][crate::command::CommandBuffer::begin_rendering] and
+/// [`end_rendering`][crate::command::CommandBuffer::end_rendering].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct RenderingFormats<'a> {
+    /// Formats of the color attachments, in order.
+    pub colors: &'a [Format],
+    /// Format of the depth-stencil attachment, if any.
+    pub depth_stencil: Option<Format>,
+}
+
+/// Attachments a graphics pipeline renders to.
+#[derive(Debug)]
+pub enum PipelineTarget<'a, B: Backend> {
+    /// A subpass of a render pass object.
+    Subpass(Subpass<'a, B>),
+    /// Attachments bound by `begin_rendering`, described by their formats.
+    ///
+    /// Requires the [`DYNAMIC_RENDERING`][crate::Features::DYNAMIC_RENDERING] feature.
+    Rendering(RenderingFormats<'a>),
+}
+
+impl<'a, B: Backend> Clone for PipelineTarget<'a, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, B: Backend> Copy for PipelineTarget<'a, B> {}
+
+impl<'a, B: Backend> From<Subpass<'a, B>> for PipelineTarget<'a, B> {
+    fn from(subpass: Subpass<'a, B>) -> Self {
+        PipelineTarget::Subpass(subpass)
+    }
+}
+
+impl<'a, B: Backend> From<RenderingFormats<'a>> for PipelineTarget<'a, B> {
+    fn from(formats: RenderingFormats<'a>) -> Self {
+        PipelineTarget::Rendering(formats)
+    }
+}
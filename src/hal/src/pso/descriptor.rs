@@ -17,10 +17,14 @@
 //! [`DescriptorSetCopy`]: struct.DescriptorSetWrite.html
 
 use crate::{
-    buffer::SubRange, device::OutOfMemory, image::Layout, pso::ShaderStageFlags, Backend, PseudoVec,
+    buffer::SubRange,
+    device::{Device, OutOfMemory},
+    image::Layout,
+    pso::ShaderStageFlags,
+    Backend, PseudoVec,
 };
 
-use std::{fmt, iter};
+use std::{fmt, iter, ptr};
 
 ///
 pub type DescriptorSetIndex = u16;
@@ -94,6 +98,10 @@ pub enum DescriptorType {
     },
     /// A descriptor associated with an input attachment.
     InputAttachment,
+    /// A descriptor associated with an acceleration structure.
+    ///
+    /// Requires the [`ACCELERATION_STRUCTURE`][crate::Features::ACCELERATION_STRUCTURE] feature.
+    AccelerationStructure,
 }
 
 /// Information about the contents of and in which stages descriptors may be bound to a descriptor
@@ -249,6 +257,7 @@ pub enum Descriptor<'a, B: Backend> {
     CombinedImageSampler(&'a B::ImageView, Layout, &'a B::Sampler),
     Buffer(&'a B::Buffer, SubRange),
     TexelBuffer(&'a B::BufferView),
+    AccelerationStructure(&'a B::AccelerationStructure),
 }
 
 /// Copies a range of descriptors to be bound from one descriptor set to another.
@@ -282,6 +291,86 @@ pub struct DescriptorSetCopy<'a, B: Backend> {
     pub count: usize,
 }
 
+/// An entry of a descriptor update template, locating the descriptors written to
+/// a range of bindings in the data passed to
+/// [`update_descriptor_set_with_template`][crate::device::Device::update_descriptor_set_with_template].
+///
+/// The data holds a [`Descriptor`] at `offset + i * stride` for each `i` in `0..count`,
+/// typically as fields of a user-defined struct.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DescriptorUpdateTemplateEntry {
+    /// Binding index to start writing at.
+    ///
+    /// *Note*: like with [`DescriptorSetWrite`], when there are more descriptors
+    /// than array elements left in the binding, the updates are spilled onto
+    /// the next binding (starting with offset 0), and so on.
+    pub binding: DescriptorBinding,
+    /// Offset into the descriptor array to write to.
+    pub array_offset: DescriptorArrayIndex,
+    /// How many descriptors to write.
+    pub count: usize,
+    /// Offset in bytes of the first descriptor in the data.
+    pub offset: usize,
+    /// Stride in bytes between consecutive descriptors in the data.
+    pub stride: usize,
+}
+
+impl DescriptorUpdateTemplateEntry {
+    /// Read the descriptor at `index` in this entry from the template data.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to a valid [`Descriptor`] at `offset + index * stride`.
+    pub unsafe fn read_descriptor<'a, B: Backend>(
+        &self,
+        data: *const u8,
+        index: usize,
+    ) -> Descriptor<'a, B> {
+        ptr::read(data.add(self.offset + index * self.stride) as *const Descriptor<'a, B>)
+    }
+}
+
+/// Descriptor update template for backends without a native equivalent,
+/// applying each entry with [`write_descriptor_set`][crate::device::Device::write_descriptor_set].
+#[derive(Clone, Debug, Default)]
+pub struct EmulatedDescriptorUpdateTemplate {
+    entries: Vec<DescriptorUpdateTemplateEntry>,
+}
+
+impl EmulatedDescriptorUpdateTemplate {
+    /// Create a template from its entries.
+    pub fn new<I>(entries: I) -> Self
+    where
+        I: Iterator<Item = DescriptorUpdateTemplateEntry>,
+    {
+        EmulatedDescriptorUpdateTemplate {
+            entries: entries.collect(),
+        }
+    }
+
+    /// Write the descriptors stored in `data` to `set`.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to valid [`Descriptor`]s at the locations of all the entries.
+    pub unsafe fn update<B: Backend, D: Device<B>>(
+        &self,
+        device: &D,
+        set: &mut B::DescriptorSet,
+        data: *const u8,
+    ) {
+        for entry in &self.entries {
+            device.write_descriptor_set(DescriptorSetWrite {
+                set: &mut *set,
+                binding: entry.binding,
+                array_offset: entry.array_offset,
+                descriptors: (0..entry.count).map(|i| entry.read_descriptor(data, i)),
+            });
+        }
+    }
+}
+
 bitflags! {
     /// Descriptor pool creation flags.
     pub struct DescriptorPoolCreateFlags: u32 {
@@ -290,3 +379,16 @@ bitflags! {
         const FREE_DESCRIPTOR_SET = 0x1;
     }
 }
+
+bitflags! {
+    /// Descriptor set layout creation flags.
+    pub struct DescriptorSetLayoutCreateFlags: u32 {
+        /// Specifies that descriptors are pushed directly into the command buffer
+        /// with [`push_graphics_descriptor_set`][crate::command::CommandBuffer::push_graphics_descriptor_set]
+        /// or [`push_compute_descriptor_set`][crate::command::CommandBuffer::push_compute_descriptor_set],
+        /// instead of being written to descriptor sets allocated from a pool.
+        ///
+        /// Requires the [`PUSH_DESCRIPTOR`][crate::Features::PUSH_DESCRIPTOR] feature.
+        const PUSH_DESCRIPTOR = 0x1;
+    }
+}
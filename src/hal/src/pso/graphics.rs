@@ -3,7 +3,7 @@
 use crate::{
     image, pass,
     pso::{
-        input_assembler::{AttributeDesc, InputAssemblerDesc, VertexBufferDesc},
+        input_assembler::{AttributeDesc, InputAssemblerDesc, Location, VertexBufferDesc},
         output_merger::{ColorBlendDesc, DepthStencilDesc, Face},
         BasePipeline, EntryPoint, PipelineCreationFlags, State,
     },
@@ -104,6 +104,16 @@ pub enum PrimitiveAssemblerDesc<'a, B: Backend> {
         mesh: EntryPoint<'a, B>,
     },
 }
+/// A vertex shader output captured by transform feedback.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransformFeedbackVarying {
+    /// Location of the output.
+    pub location: Location,
+    /// Index of the transform feedback buffer the output is written to.
+    pub buffer: u32,
+}
+
 /// A description of all the settings that can be altered
 /// when creating a graphics pipeline.
 #[derive(Debug)]
@@ -134,8 +144,16 @@ pub struct GraphicsPipelineDesc<'a, B: Backend> {
     pub baked_states: BakedStates,
     /// Pipeline layout.
     pub layout: &'a B::PipelineLayout,
-    /// Subpass in which the pipeline can be executed.
-    pub subpass: pass::Subpass<'a, B>,
+    /// Subpass or dynamic rendering attachments the pipeline can be executed with.
+    pub target: pass::PipelineTarget<'a, B>,
+    /// Vertex shader outputs captured by transform feedback, tightly packed
+    /// into their buffers in the listed order.
+    ///
+    /// Backends consuming SPIR-V directly take the captured outputs from the
+    /// `XfbBuffer` and `Offset` decorations of the shader instead, and ignore this.
+    /// The list must therefore match these decorations, ordered by offset within
+    /// each buffer, which the GL backend checks in debug builds.
+    pub transform_feedback: &'a [TransformFeedbackVarying],
     /// Options that may be set to alter pipeline properties.
     pub flags: PipelineCreationFlags,
     /// The parent pipeline, which may be
@@ -150,7 +168,7 @@ impl<'a, B: Backend> GraphicsPipelineDesc<'a, B> {
         rasterizer: Rasterizer,
         fragment: Option<EntryPoint<'a, B>>,
         layout: &'a B::PipelineLayout,
-        subpass: pass::Subpass<'a, B>,
+        target: impl Into<pass::PipelineTarget<'a, B>>,
     ) -> Self {
         GraphicsPipelineDesc {
             label: None,
@@ -162,7 +180,8 @@ impl<'a, B: Backend> GraphicsPipelineDesc<'a, B> {
             multisampling: None,
             baked_states: BakedStates::default(),
             layout,
-            subpass,
+            target: target.into(),
+            transform_feedback: &[],
             flags: PipelineCreationFlags::empty(),
             parent: BasePipeline::None,
         }
@@ -219,9 +238,9 @@ pub struct Rasterizer {
     /// How to rasterize this primitive.
     pub polygon_mode: PolygonMode,
     /// Which face should be culled.
-    pub cull_face: Face,
+    pub cull_face: State<Face>,
     /// Which vertex winding is considered to be the front face for culling.
-    pub front_face: FrontFace,
+    pub front_face: State<FrontFace>,
     /// Whether or not to enable depth clamping; when enabled, instead of
     /// fragments being omitted when they are outside the bounds of the z-plane,
     /// they will be clamped to the min or max z value.
@@ -238,8 +257,8 @@ impl Rasterizer {
     /// Simple polygon-filling rasterizer state
     pub const FILL: Self = Rasterizer {
         polygon_mode: PolygonMode::Fill,
-        cull_face: Face::NONE,
-        front_face: FrontFace::CounterClockwise,
+        cull_face: State::Static(Face::NONE),
+        front_face: State::Static(FrontFace::CounterClockwise),
         depth_clamping: false,
         depth_bias: None,
         conservative: false,
@@ -1,7 +1,7 @@
 //! Input Assembler (IA) stage description.
 //! The input assembler collects raw vertex and index data.
 
-use crate::{format, IndexType};
+use crate::{format, pso::State, IndexType};
 
 /// Shader binding location.
 pub type Location = u32;
@@ -57,7 +57,10 @@ pub struct VertexBufferDesc {
     pub binding: BufferIndex,
     /// Total container size, in bytes.
     /// Specifies the byte distance between two consecutive elements.
-    pub stride: ElemStride,
+    ///
+    /// When dynamic, the stride is provided with
+    /// [`bind_vertex_buffers_with_strides`][crate::command::CommandBuffer::bind_vertex_buffers_with_strides].
+    pub stride: State<ElemStride>,
     /// The rate at which to advance data for the given buffer
     ///
     /// i.e. the rate at which data passed to shaders will get advanced by
@@ -113,6 +116,12 @@ pub enum Primitive {
 pub struct InputAssemblerDesc {
     /// Type of the primitive
     pub primitive: Primitive,
+    /// Whether the primitive topology is set with
+    /// [`set_primitive_topology`][crate::command::CommandBuffer::set_primitive_topology].
+    ///
+    /// The topology set must then belong to the same class as `primitive`:
+    /// points, lines, triangles or patches, and adjacency information isn't supported.
+    pub dynamic_primitive: bool,
     /// When adjacency information is enabled, every even-numbered vertex
     /// (every other starting from the first) represents an additional
     /// vertex for the primitive, while odd-numbered vertices (every other starting from the
@@ -138,6 +147,7 @@ impl InputAssemblerDesc {
     pub fn new(primitive: Primitive) -> Self {
         InputAssemblerDesc {
             primitive,
+            dynamic_primitive: false,
             with_adjacency: false,
             restart_index: None,
         }
@@ -79,13 +79,116 @@ bitflags!(
         /// Read/Write access from host.
         /// (Not a real pipeline stage)
         const HOST = 0x4000;
+        /// Reading of the conditional rendering predicate.
+        const CONDITIONAL_RENDERING = 0x4_0000;
         /// Task shader stage.
         const TASK_SHADER = 0x80000;
         /// Mesh shader stage.
         const MESH_SHADER = 0x100000;
+        /// Writing of transform feedback buffers.
+        const TRANSFORM_FEEDBACK = 0x100_0000;
+        /// Acceleration structure build and copy commands.
+        const ACCELERATION_STRUCTURE_BUILD = 0x200_0000;
     }
 );
 
+bitflags!(
+    /// Stages of the logical pipeline, as used by the barriers and submissions
+    /// of the second synchronization API.
+    ///
+    /// This is a superset of [`PipelineStage`], with the same values for the shared
+    /// stages, which splits some of them up into finer grained ones.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct PipelineStage2: u64 {
+        /// Beginning of the command queue.
+        const TOP_OF_PIPE = 0x1;
+        /// Indirect data consumption.
+        const DRAW_INDIRECT = 0x2;
+        /// Vertex data consumption, both index and vertex attribute input.
+        const VERTEX_INPUT = 0x4;
+        /// Vertex shader execution.
+        const VERTEX_SHADER = 0x8;
+        /// Hull shader execution.
+        const HULL_SHADER = 0x10;
+        /// Domain shader execution.
+        const DOMAIN_SHADER = 0x20;
+        /// Geometry shader execution.
+        const GEOMETRY_SHADER = 0x40;
+        /// Fragment shader execution.
+        const FRAGMENT_SHADER = 0x80;
+        /// Stage of early depth and stencil test.
+        const EARLY_FRAGMENT_TESTS = 0x100;
+        /// Stage of late depth and stencil test.
+        const LATE_FRAGMENT_TESTS = 0x200;
+        /// Stage of final color value calculation.
+        const COLOR_ATTACHMENT_OUTPUT = 0x400;
+        /// Compute shader execution,
+        const COMPUTE_SHADER = 0x800;
+        /// All copy, resolve, blit and clear commands.
+        const TRANSFER = 0x1000;
+        /// End of the command queue.
+        const BOTTOM_OF_PIPE = 0x2000;
+        /// Read/Write access from host.
+        /// (Not a real pipeline stage)
+        const HOST = 0x4000;
+        /// Reading of the conditional rendering predicate.
+        const CONDITIONAL_RENDERING = 0x4_0000;
+        /// Task shader stage.
+        const TASK_SHADER = 0x80000;
+        /// Mesh shader stage.
+        const MESH_SHADER = 0x100000;
+        /// Writing of transform feedback buffers.
+        const TRANSFORM_FEEDBACK = 0x100_0000;
+        /// Acceleration structure build and copy commands.
+        const ACCELERATION_STRUCTURE_BUILD = 0x200_0000;
+        /// Buffer and image copy commands.
+        const COPY = 0x1_0000_0000;
+        /// Image resolve commands.
+        const RESOLVE = 0x2_0000_0000;
+        /// Image blit commands.
+        const BLIT = 0x4_0000_0000;
+        /// Buffer fill and update commands, and image clears outside of render passes.
+        const CLEAR = 0x8_0000_0000;
+        /// Index buffer consumption.
+        const INDEX_INPUT = 0x10_0000_0000;
+        /// Vertex buffer consumption.
+        const VERTEX_ATTRIBUTE_INPUT = 0x20_0000_0000;
+        /// All shader stages before rasterization: vertex, hull, domain, geometry,
+        /// task and mesh shaders.
+        const PRE_RASTERIZATION_SHADERS = 0x40_0000_0000;
+    }
+);
+
+impl PipelineStage2 {
+    /// Convert into the closest set of [`PipelineStage`] flags, which covers at least
+    /// the same work.
+    ///
+    /// [`PRE_RASTERIZATION_SHADERS`][Self::PRE_RASTERIZATION_SHADERS] doesn't lower to
+    /// the task and mesh shader stages, which have to be named explicitly when in use.
+    pub fn lower(self) -> PipelineStage {
+        let mut stages = PipelineStage::from_bits_truncate(self.bits() as u32);
+        if self.intersects(Self::COPY | Self::RESOLVE | Self::BLIT | Self::CLEAR) {
+            stages |= PipelineStage::TRANSFER;
+        }
+        if self.intersects(Self::INDEX_INPUT | Self::VERTEX_ATTRIBUTE_INPUT) {
+            stages |= PipelineStage::VERTEX_INPUT;
+        }
+        if self.contains(Self::PRE_RASTERIZATION_SHADERS) {
+            stages |= PipelineStage::VERTEX_SHADER
+                | PipelineStage::HULL_SHADER
+                | PipelineStage::DOMAIN_SHADER
+                | PipelineStage::GEOMETRY_SHADER;
+        }
+        stages
+    }
+}
+
+impl From<PipelineStage> for PipelineStage2 {
+    fn from(stages: PipelineStage) -> Self {
+        PipelineStage2::from_bits_truncate(stages.bits() as u64)
+    }
+}
+
 bitflags!(
     /// Combination of different shader pipeline stages.
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -210,3 +313,54 @@ impl<T> State<T> {
         !self.is_static()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{PipelineStage, PipelineStage2};
+
+    #[test]
+    fn lower_pipeline_stage2() {
+        let cases = [
+            (PipelineStage2::empty(), PipelineStage::empty()),
+            (PipelineStage2::TOP_OF_PIPE, PipelineStage::TOP_OF_PIPE),
+            (PipelineStage2::VERTEX_INPUT, PipelineStage::VERTEX_INPUT),
+            (
+                PipelineStage2::FRAGMENT_SHADER | PipelineStage2::COMPUTE_SHADER,
+                PipelineStage::FRAGMENT_SHADER | PipelineStage::COMPUTE_SHADER,
+            ),
+            (PipelineStage2::HOST, PipelineStage::HOST),
+            (PipelineStage2::MESH_SHADER, PipelineStage::MESH_SHADER),
+            (
+                PipelineStage2::ACCELERATION_STRUCTURE_BUILD,
+                PipelineStage::ACCELERATION_STRUCTURE_BUILD,
+            ),
+            (PipelineStage2::COPY, PipelineStage::TRANSFER),
+            (PipelineStage2::RESOLVE, PipelineStage::TRANSFER),
+            (PipelineStage2::BLIT, PipelineStage::TRANSFER),
+            (PipelineStage2::CLEAR, PipelineStage::TRANSFER),
+            (
+                PipelineStage2::COPY | PipelineStage2::TRANSFER,
+                PipelineStage::TRANSFER,
+            ),
+            (PipelineStage2::INDEX_INPUT, PipelineStage::VERTEX_INPUT),
+            (
+                PipelineStage2::VERTEX_ATTRIBUTE_INPUT,
+                PipelineStage::VERTEX_INPUT,
+            ),
+            (
+                PipelineStage2::PRE_RASTERIZATION_SHADERS,
+                PipelineStage::VERTEX_SHADER
+                    | PipelineStage::HULL_SHADER
+                    | PipelineStage::DOMAIN_SHADER
+                    | PipelineStage::GEOMETRY_SHADER,
+            ),
+            (
+                PipelineStage2::CLEAR | PipelineStage2::FRAGMENT_SHADER,
+                PipelineStage::TRANSFER | PipelineStage::FRAGMENT_SHADER,
+            ),
+        ];
+        for &(stages, lowered) in cases.iter() {
+            assert_eq!(stages.lower(), lowered, "{:?}", stages);
+        }
+    }
+}
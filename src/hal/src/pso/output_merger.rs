@@ -193,29 +193,29 @@ impl ColorBlendDesc {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DepthTest {
     /// Comparison function to use.
-    pub fun: Comparison,
+    pub fun: State<Comparison>,
     /// Specify whether to write to the depth buffer or not.
-    pub write: bool,
+    pub write: State<bool>,
 }
 
 impl DepthTest {
     /// A depth test that always fails.
     pub const FAIL: Self = DepthTest {
-        fun: Comparison::Never,
-        write: false,
+        fun: State::Static(Comparison::Never),
+        write: State::Static(false),
     };
     /// A depth test that always succeeds but doesn't
     /// write to the depth buffer
     // DOC TODO: Not a terribly helpful description there...
     pub const PASS_TEST: Self = DepthTest {
-        fun: Comparison::Always,
-        write: false,
+        fun: State::Static(Comparison::Always),
+        write: State::Static(false),
     };
     /// A depth test that always succeeds and writes its result
     /// to the depth buffer.
     pub const PASS_WRITE: Self = DepthTest {
-        fun: Comparison::Always,
-        write: true,
+        fun: State::Static(Comparison::Always),
+        write: State::Static(true),
     };
 }
 
@@ -301,7 +301,7 @@ pub type StencilValues = State<Sided<StencilValue>>;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StencilTest {
     /// Operations for stencil faces.
-    pub faces: Sided<StencilFace>,
+    pub faces: State<Sided<StencilFace>>,
     /// Masks that are ANDd with both the stencil buffer value and the reference value when they
     /// are read before doing the stencil test.
     pub read_masks: StencilValues,
@@ -314,7 +314,7 @@ pub struct StencilTest {
 impl Default for StencilTest {
     fn default() -> Self {
         StencilTest {
-            faces: Sided::default(),
+            faces: State::Static(Sided::default()),
             read_masks: State::Static(Sided::new(!0)),
             write_masks: State::Static(Sided::new(!0)),
             reference_values: State::Static(Sided::new(0)),
@@ -323,21 +323,34 @@ impl Default for StencilTest {
 }
 
 /// PSO depth-stencil target descriptor.
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DepthStencilDesc {
     /// Optional depth testing/writing.
-    pub depth: Option<DepthTest>,
+    ///
+    /// When dynamic, whether the depth test is enabled, its comparison function
+    /// and the depth writes are all set through the command buffer.
+    pub depth: State<Option<DepthTest>>,
     /// Enable depth bounds testing.
     pub depth_bounds: bool,
     /// Stencil test/write.
     pub stencil: Option<StencilTest>,
 }
 
+impl Default for DepthStencilDesc {
+    fn default() -> Self {
+        DepthStencilDesc {
+            depth: State::Static(None),
+            depth_bounds: false,
+            stencil: None,
+        }
+    }
+}
+
 impl DepthStencilDesc {
     /// Returns true if the descriptor assumes the depth attachment.
     pub fn uses_depth(&self) -> bool {
-        self.depth.is_some() || self.depth_bounds
+        self.depth != State::Static(None) || self.depth_bounds
     }
     /// Returns true if the descriptor assumes the stencil attachment.
     pub fn uses_stencil(&self) -> bool {
@@ -70,6 +70,12 @@ pub enum Type {
     /// Timestamp query. Timestamps can be recorded to the
     /// query pool by calling `write_timestamp()`.
     Timestamp,
+    /// Performance counter query. Samples the hardware counters chosen at the
+    /// creation of the pool between the start and end of the query command.
+    ///
+    /// Pools of this type are created by
+    /// [`create_performance_query_pool`][crate::device::Device::create_performance_query_pool].
+    PerformanceCounters,
 }
 
 bitflags!(
@@ -100,3 +106,140 @@ bitflags!(
         const COMPUTE_SHADER_INVOCATIONS = 0x400;
     }
 );
+
+/// A hardware counter, which can be sampled by the queries of a
+/// [performance query pool][Type::PerformanceCounters].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PerformanceCounter {
+    /// Short name of the counter.
+    pub name: String,
+    /// Group of related counters this one belongs to.
+    pub category: String,
+    /// Longer description of what the counter measures.
+    pub description: String,
+    /// Unit of the values of the counter.
+    pub unit: PerformanceCounterUnit,
+    /// How the values of the counter are stored in the query results.
+    pub storage: PerformanceCounterStorage,
+}
+
+/// Unit of the values of a [performance counter][PerformanceCounter].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PerformanceCounterUnit {
+    /// A dimensionless value, e.g. a number of invocations.
+    Generic,
+    /// A percentage, from 0 to 100.
+    Percentage,
+    /// A duration in nanoseconds.
+    Nanoseconds,
+    /// An amount of bytes.
+    Bytes,
+    /// A throughput in bytes per second.
+    BytesPerSecond,
+    /// A temperature in kelvins.
+    Kelvin,
+    /// A power in watts.
+    Watts,
+    /// A voltage in volts.
+    Volts,
+    /// A current in amperes.
+    Amps,
+    /// A frequency in hertz.
+    Hertz,
+    /// A number of clock cycles.
+    Cycles,
+}
+
+/// Storage of the values of a [performance counter][PerformanceCounter].
+///
+/// Every counter takes 8 bytes in the query results, whatever its storage.
+/// The 32-bit values are stored in the first 4 of them.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PerformanceCounterStorage {
+    /// 32-bit signed integer.
+    Int32,
+    /// 64-bit signed integer.
+    Int64,
+    /// 32-bit unsigned integer.
+    Uint32,
+    /// 64-bit unsigned integer.
+    Uint64,
+    /// 32-bit floating point number.
+    Float32,
+    /// 64-bit floating point number.
+    Float64,
+}
+
+/// Size of the result of a single counter, in bytes.
+pub const PERFORMANCE_COUNTER_RESULT_SIZE: usize = 8;
+
+impl PerformanceCounterStorage {
+    /// Decode the result of a counter, as written in the query results
+    /// in the native byte order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `raw` is shorter than [`PERFORMANCE_COUNTER_RESULT_SIZE`].
+    pub fn decode(self, raw: &[u8]) -> PerformanceCounterValue {
+        let mut low = [0; 4];
+        low.copy_from_slice(&raw[..4]);
+        let mut full = [0; PERFORMANCE_COUNTER_RESULT_SIZE];
+        full.copy_from_slice(&raw[..PERFORMANCE_COUNTER_RESULT_SIZE]);
+        match self {
+            PerformanceCounterStorage::Int32 => {
+                PerformanceCounterValue::Int32(i32::from_ne_bytes(low))
+            }
+            PerformanceCounterStorage::Int64 => {
+                PerformanceCounterValue::Int64(i64::from_ne_bytes(full))
+            }
+            PerformanceCounterStorage::Uint32 => {
+                PerformanceCounterValue::Uint32(u32::from_ne_bytes(low))
+            }
+            PerformanceCounterStorage::Uint64 => {
+                PerformanceCounterValue::Uint64(u64::from_ne_bytes(full))
+            }
+            PerformanceCounterStorage::Float32 => {
+                PerformanceCounterValue::Float32(f32::from_ne_bytes(low))
+            }
+            PerformanceCounterStorage::Float64 => {
+                PerformanceCounterValue::Float64(f64::from_ne_bytes(full))
+            }
+        }
+    }
+}
+
+/// A decoded value of a [performance counter][PerformanceCounter].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PerformanceCounterValue {
+    ///
+    Int32(i32),
+    ///
+    Int64(i64),
+    ///
+    Uint32(u32),
+    ///
+    Uint64(u64),
+    ///
+    Float32(f32),
+    ///
+    Float64(f64),
+}
+
+impl PerformanceCounterValue {
+    /// Convert the value to a floating point number, e.g. for display.
+    ///
+    /// Large 64-bit integers lose precision.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            PerformanceCounterValue::Int32(value) => value as f64,
+            PerformanceCounterValue::Int64(value) => value as f64,
+            PerformanceCounterValue::Uint32(value) => value as f64,
+            PerformanceCounterValue::Uint64(value) => value as f64,
+            PerformanceCounterValue::Float32(value) => value as f64,
+            PerformanceCounterValue::Float64(value) => value,
+        }
+    }
+}
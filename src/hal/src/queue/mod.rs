@@ -9,7 +9,7 @@
 pub mod family;
 
 use crate::{
-    device::OutOfMemory,
+    device::WaitError,
     pso,
     window::{PresentError, PresentationSurface, Suboptimal},
     Backend,
@@ -129,6 +129,73 @@ pub trait Queue<B: Backend>: fmt::Debug + Any + Send + Sync {
         Iw: Iterator<Item = (&'a B::Semaphore, pso::PipelineStage)>,
         Is: Iterator<Item = &'a B::Semaphore>;
 
+    /// Submit command buffers to queue for execution, waiting on and signaling
+    /// semaphores with values.
+    ///
+    /// This is the same as [`submit`][Queue::submit], except that every semaphore
+    /// is paired with a value. Timeline semaphores are waited on until they reach
+    /// that value, and set to it once the submission has finished execution.
+    /// Values are ignored for binary semaphores.
+    ///
+    /// Requires the [`TIMELINE_SEMAPHORE`][crate::Features::TIMELINE_SEMAPHORE] feature.
+    /// Without it, all the semaphores are binary, so the values are dropped and the
+    /// submission is forwarded to [`submit`][Queue::submit].
+    ///
+    /// # Safety
+    ///
+    /// Unsafe for the same reasons as [`submit`][Queue::submit].
+    unsafe fn submit_timeline<'a, Ic, Iw, Is>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut B::Fence>,
+    ) where
+        Ic: Iterator<Item = &'a B::CommandBuffer>,
+        Iw: Iterator<Item = (&'a B::Semaphore, u64, pso::PipelineStage)>,
+        Is: Iterator<Item = (&'a B::Semaphore, u64)>,
+    {
+        self.submit(
+            command_buffers,
+            wait_semaphores.map(|(semaphore, _, stages)| (semaphore, stages)),
+            signal_semaphores.map(|(semaphore, _)| semaphore),
+            fence,
+        )
+    }
+
+    /// Submit command buffers to queue for execution, with the stages to wait on
+    /// paired with every semaphore to signal.
+    ///
+    /// This is the same as [`submit`][Queue::submit], except that stages are given
+    /// as [`PipelineStage2`][pso::PipelineStage2], and that every signal semaphore
+    /// is signaled as soon as the given stages of all command buffers have finished.
+    ///
+    /// Without the [`SYNCHRONIZATION_2`][crate::Features::SYNCHRONIZATION_2] feature,
+    /// the wait stages are [lowered][pso::PipelineStage2::lower], and semaphores are
+    /// signaled once the submission has finished execution.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe for the same reasons as [`submit`][Queue::submit].
+    unsafe fn submit2<'a, Ic, Iw, Is>(
+        &mut self,
+        command_buffers: Ic,
+        wait_semaphores: Iw,
+        signal_semaphores: Is,
+        fence: Option<&mut B::Fence>,
+    ) where
+        Ic: Iterator<Item = &'a B::CommandBuffer>,
+        Iw: Iterator<Item = (&'a B::Semaphore, pso::PipelineStage2)>,
+        Is: Iterator<Item = (&'a B::Semaphore, pso::PipelineStage2)>,
+    {
+        self.submit(
+            command_buffers,
+            wait_semaphores.map(|(semaphore, stages)| (semaphore, stages.lower())),
+            signal_semaphores.map(|(semaphore, _)| semaphore),
+            fence,
+        )
+    }
+
     /// Present a swapchain image directly to a surface, after waiting on `wait_semaphore`.
     ///
     /// # Safety
@@ -143,7 +210,10 @@ pub trait Queue<B: Backend>: fmt::Debug + Any + Send + Sync {
     ) -> Result<Option<Suboptimal>, PresentError>;
 
     /// Wait for the queue to be idle.
-    fn wait_idle(&mut self) -> Result<(), OutOfMemory>;
+    ///
+    /// Returns `DeviceLost` instead of waiting on work that never completes
+    /// once the device is lost.
+    fn wait_idle(&mut self) -> Result<(), WaitError>;
 
     /// The amount of nanoseconds that causes a timestamp query value to increment by one.
     fn timestamp_period(&self) -> f32;
@@ -0,0 +1,236 @@
+//! Lowering of the barriers of the second synchronization API.
+
+use gfx_backend_empty::{Backend, Instance};
+use gfx_hal::{
+    adapter::{Gpu, PhysicalDevice as _},
+    buffer,
+    device::Device as _,
+    format, image,
+    memory::{self, Access2, Barrier, Barrier2, SparseFlags},
+    pso::{PipelineStage, PipelineStage2},
+    Instance as _,
+};
+use std::{ops::Range, ptr};
+
+fn open_device() -> Gpu<Backend> {
+    let instance = Instance::create("barriers", 1).unwrap();
+    let adapter = instance.enumerate_adapters().remove(0);
+    let family = &adapter.queue_families[0];
+    unsafe {
+        adapter
+            .physical_device
+            .open(&[(family, &[1.0])], gfx_hal::Features::empty())
+            .unwrap()
+    }
+}
+
+#[test]
+fn lower_global_barriers() {
+    let cases: Vec<(Barrier2<Backend>, Range<PipelineStage>)> = vec![
+        (
+            Barrier2::AllBuffers {
+                stages: PipelineStage2::COPY..PipelineStage2::VERTEX_ATTRIBUTE_INPUT,
+                accesses: Access2::TRANSFER_WRITE..Access2::VERTEX_BUFFER_READ,
+            },
+            PipelineStage::TRANSFER..PipelineStage::VERTEX_INPUT,
+        ),
+        (
+            Barrier2::AllImages {
+                stages: PipelineStage2::CLEAR..PipelineStage2::PRE_RASTERIZATION_SHADERS,
+                accesses: Access2::TRANSFER_WRITE..Access2::SHADER_SAMPLED_READ,
+            },
+            PipelineStage::TRANSFER
+                ..PipelineStage::VERTEX_SHADER
+                    | PipelineStage::HULL_SHADER
+                    | PipelineStage::DOMAIN_SHADER
+                    | PipelineStage::GEOMETRY_SHADER,
+        ),
+    ];
+    for (barrier, stages) in cases {
+        let name = format!("{:?}", barrier);
+        let (lowered_stages, lowered) = barrier.lower();
+        assert_eq!(lowered_stages, stages, "{}", name);
+        match lowered {
+            Barrier::AllBuffers(accesses) => assert_eq!(
+                accesses,
+                buffer::Access::TRANSFER_WRITE..buffer::Access::VERTEX_BUFFER_READ
+            ),
+            Barrier::AllImages(accesses) => assert_eq!(
+                accesses,
+                image::Access::TRANSFER_WRITE..image::Access::SHADER_READ
+            ),
+            _ => panic!("{} is lowered to {:?}", name, lowered),
+        }
+    }
+}
+
+#[test]
+fn lower_resource_barriers() {
+    let gpu = open_device();
+    let device = &gpu.device;
+    unsafe {
+        let buffer = device
+            .create_buffer(16, buffer::Usage::STORAGE, SparseFlags::empty())
+            .unwrap();
+        let image = device
+            .create_image(
+                image::Kind::D2(1, 1, 1, 1),
+                1,
+                format::Format::Rgba8Unorm,
+                image::Tiling::Optimal,
+                image::Usage::COLOR_ATTACHMENT | image::Usage::SAMPLED,
+                SparseFlags::empty(),
+                image::ViewCapabilities::empty(),
+            )
+            .unwrap();
+        let range = image::SubresourceRange {
+            aspects: format::Aspects::COLOR,
+            ..image::SubresourceRange::default()
+        };
+
+        let barrier: Barrier2<Backend> = Barrier2::Buffer {
+            stages: PipelineStage2::COMPUTE_SHADER..PipelineStage2::DRAW_INDIRECT,
+            states: Access2::SHADER_STORAGE_WRITE..Access2::INDIRECT_COMMAND_READ,
+            target: &buffer,
+            range: buffer::SubRange {
+                offset: 4,
+                size: Some(8),
+            },
+            families: None,
+        };
+        let (stages, lowered) = barrier.lower();
+        assert_eq!(
+            stages,
+            PipelineStage::COMPUTE_SHADER..PipelineStage::DRAW_INDIRECT
+        );
+        match lowered {
+            Barrier::Buffer {
+                states,
+                target,
+                range,
+                families,
+            } => {
+                assert_eq!(
+                    states,
+                    buffer::Access::SHADER_WRITE..buffer::Access::INDIRECT_COMMAND_READ
+                );
+                assert!(ptr::eq(target, &buffer));
+                assert_eq!(
+                    range,
+                    buffer::SubRange {
+                        offset: 4,
+                        size: Some(8)
+                    }
+                );
+                assert!(families.is_none());
+            }
+            _ => panic!("Buffer barrier is lowered to {:?}", lowered),
+        }
+
+        let barrier: Barrier2<Backend> = Barrier2::Image {
+            stages: PipelineStage2::COLOR_ATTACHMENT_OUTPUT..PipelineStage2::FRAGMENT_SHADER,
+            states: (
+                Access2::COLOR_ATTACHMENT_WRITE,
+                image::Layout::ColorAttachmentOptimal,
+            )
+                ..(
+                    Access2::SHADER_SAMPLED_READ,
+                    image::Layout::ShaderReadOnlyOptimal,
+                ),
+            target: &image,
+            range: range.clone(),
+            families: None,
+        };
+        let (stages, lowered) = barrier.lower();
+        assert_eq!(
+            stages,
+            PipelineStage::COLOR_ATTACHMENT_OUTPUT..PipelineStage::FRAGMENT_SHADER
+        );
+        match lowered {
+            Barrier::Image {
+                states,
+                target,
+                range: lowered_range,
+                families,
+            } => {
+                assert_eq!(
+                    states,
+                    (
+                        image::Access::COLOR_ATTACHMENT_WRITE,
+                        image::Layout::ColorAttachmentOptimal
+                    )
+                        ..(
+                            image::Access::SHADER_READ,
+                            image::Layout::ShaderReadOnlyOptimal
+                        )
+                );
+                assert!(ptr::eq(target, &image));
+                assert_eq!(lowered_range, range);
+                assert!(families.is_none());
+            }
+            _ => panic!("Image barrier is lowered to {:?}", lowered),
+        }
+
+        device.destroy_image(image);
+        device.destroy_buffer(buffer);
+    }
+}
+
+#[test]
+fn lower_barriers_in_batches() {
+    let gpu = open_device();
+    let device = &gpu.device;
+    unsafe {
+        let buffer = device
+            .create_buffer(16, buffer::Usage::VERTEX, SparseFlags::empty())
+            .unwrap();
+        let barriers: Vec<Barrier2<Backend>> = vec![
+            Barrier2::AllBuffers {
+                stages: PipelineStage2::COPY..PipelineStage2::VERTEX_ATTRIBUTE_INPUT,
+                accesses: Access2::TRANSFER_WRITE..Access2::VERTEX_BUFFER_READ,
+            },
+            Barrier2::AllImages {
+                stages: PipelineStage2::CLEAR..PipelineStage2::FRAGMENT_SHADER,
+                accesses: Access2::TRANSFER_WRITE..Access2::SHADER_SAMPLED_READ,
+            },
+            // Same stages as the first barrier once lowered
+            Barrier2::whole_buffer(
+                &buffer,
+                PipelineStage2::TRANSFER..PipelineStage2::VERTEX_INPUT,
+                Access2::TRANSFER_WRITE..Access2::VERTEX_BUFFER_READ,
+            ),
+        ];
+
+        let batches = memory::lower_barriers(barriers.into_iter());
+        let batches = batches
+            .iter()
+            .map(|(stages, barriers)| {
+                let kinds = barriers
+                    .iter()
+                    .map(|barrier| match *barrier {
+                        Barrier::AllBuffers(_) => "all buffers",
+                        Barrier::AllImages(_) => "all images",
+                        Barrier::Buffer { .. } => "buffer",
+                        Barrier::Image { .. } => "image",
+                    })
+                    .collect::<Vec<_>>();
+                (stages.clone(), kinds)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            vec![
+                (
+                    PipelineStage::TRANSFER..PipelineStage::VERTEX_INPUT,
+                    vec!["all buffers", "buffer"],
+                ),
+                (
+                    PipelineStage::TRANSFER..PipelineStage::FRAGMENT_SHADER,
+                    vec!["all images"],
+                ),
+            ]
+        );
+
+        device.destroy_buffer(buffer);
+    }
+}
@@ -5,6 +5,7 @@
         feature = "dx11",
         feature = "metal",
         feature = "gl",
+        feature = "empty",
     )),
     allow(dead_code)
 )]
@@ -236,6 +237,10 @@ fn main() {
     {
         num_failures += harness.run::<gfx_backend_gl::Backend>("GL", Disabilities::default());
     }
+    #[cfg(feature = "empty")]
+    {
+        num_failures += harness.run::<gfx_backend_empty::Backend>("Empty", Disabilities::default());
+    }
     let _ = harness;
     num_failures += 0; // mark as mutated
     process::exit(num_failures as _);
@@ -305,8 +305,14 @@ impl<B: hal::Backend> Scene<B> {
                         })
                         .unwrap()
                         .into();
-                    let gpu_memory =
-                        unsafe { device.allocate_memory(memory_type, requirements.size) }.unwrap();
+                    let gpu_memory = unsafe {
+                        device.allocate_memory(
+                            memory_type,
+                            requirements.size,
+                            hal::memory::AllocationFlags::empty(),
+                        )
+                    }
+                    .unwrap();
 
                     unsafe {
                         device
@@ -349,9 +355,14 @@ impl<B: hal::Backend> Scene<B> {
                             .iter()
                             .find(|i| upload_req.type_mask & (1 << i.0) != 0)
                             .unwrap();
-                        let mut upload_memory =
-                            unsafe { device.allocate_memory(upload_type, upload_req.size) }
-                                .unwrap();
+                        let mut upload_memory = unsafe {
+                            device.allocate_memory(
+                                upload_type,
+                                upload_req.size,
+                                hal::memory::AllocationFlags::empty(),
+                            )
+                        }
+                        .unwrap();
 
                         unsafe { device.bind_buffer_memory(&upload_memory, 0, &mut upload_buffer) }
                             .unwrap();
@@ -444,8 +455,14 @@ impl<B: hal::Backend> Scene<B> {
                         })
                         .unwrap()
                         .into();
-                    let gpu_memory =
-                        unsafe { device.allocate_memory(memory_type, requirements.size) }.unwrap();
+                    let gpu_memory = unsafe {
+                        device.allocate_memory(
+                            memory_type,
+                            requirements.size,
+                            hal::memory::AllocationFlags::empty(),
+                        )
+                    }
+                    .unwrap();
                     unsafe { device.bind_image_memory(&gpu_memory, 0, &mut image) }.unwrap();
 
                     // process initial data for the image
@@ -514,9 +531,14 @@ impl<B: hal::Backend> Scene<B> {
                             .iter()
                             .find(|i| upload_req.type_mask & (1 << i.0) != 0)
                             .unwrap();
-                        let mut upload_memory =
-                            unsafe { device.allocate_memory(upload_type, upload_req.size) }
-                                .unwrap();
+                        let mut upload_memory = unsafe {
+                            device.allocate_memory(
+                                upload_type,
+                                upload_req.size,
+                                hal::memory::AllocationFlags::empty(),
+                            )
+                        }
+                        .unwrap();
                         unsafe { device.bind_buffer_memory(&upload_memory, 0, &mut upload_buffer) }
                             .unwrap();
                         // write the data
@@ -659,6 +681,8 @@ impl<B: hal::Backend> Scene<B> {
                         inputs: &t.2,
                         preserves: &t.3,
                         resolves: &t.4,
+                        view_mask: 0,
+                        correlation_mask: 0,
                     });
                     let raw_deps = dependencies.iter().map(|dep| hal::pass::SubpassDependency {
                         passes: subpass_ref(&dep.passes.start)..subpass_ref(&dep.passes.end),
@@ -709,7 +733,11 @@ impl<B: hal::Backend> Scene<B> {
                     assert!(!bindings.is_empty());
                     // since samples are expect to be all read by this point
                     let layout = unsafe {
-                        device.create_descriptor_set_layout(bindings.iter().cloned(), iter::empty())
+                        device.create_descriptor_set_layout(
+                            bindings.iter().cloned(),
+                            iter::empty(),
+                            pso::DescriptorSetLayoutCreateFlags::empty(),
+                        )
                     }
                     .expect("Descriptor set layout creation failure!");
                     let binding_indices = bindings.iter().map(|dsb| dsb.binding).collect();
@@ -750,7 +778,15 @@ impl<B: hal::Backend> Scene<B> {
                 } => {
                     let img = &resources.images[image].handle;
                     let view = unsafe {
-                        device.create_image_view(img, kind, format, swizzle, usage, range.clone())
+                        device.create_image_view(
+                            img,
+                            kind,
+                            format,
+                            swizzle,
+                            usage,
+                            range.clone(),
+                            None,
+                        )
                     }
                     .unwrap();
                     resources.image_views.insert(
@@ -943,14 +979,15 @@ impl<B: hal::Backend> Scene<B> {
                         baked_states: pso::BakedStates::default(), //TODO
                         multisampling: None,                       // TODO
                         layout: &resources.pipeline_layouts[layout],
-                        subpass: hal::pass::Subpass {
+                        target: hal::pass::PipelineTarget::Subpass(hal::pass::Subpass {
                             main_pass: &resources
                                 .render_passes
                                 .get(&subpass.parent)
                                 .expect(&format!("Missing render pass: {}", subpass.parent))
                                 .handle,
                             index: subpass.index,
-                        },
+                        }),
+                        transform_feedback: &[],
                         flags: pso::PipelineCreationFlags::empty(),
                         parent: pso::BasePipeline::None,
                     };
@@ -1547,8 +1584,14 @@ impl<B: hal::Backend> Scene<B> {
             .iter()
             .find(|i| down_req.type_mask & (1 << i.0) != 0)
             .unwrap();
-        let mut down_memory =
-            unsafe { self.device.allocate_memory(download_type, down_req.size) }.unwrap();
+        let mut down_memory = unsafe {
+            self.device.allocate_memory(
+                download_type,
+                down_req.size,
+                hal::memory::AllocationFlags::empty(),
+            )
+        }
+        .unwrap();
 
         unsafe {
             self.device
@@ -1672,8 +1715,14 @@ impl<B: hal::Backend> Scene<B> {
             .iter()
             .find(|i| down_req.type_mask & (1 << i.0) != 0)
             .unwrap();
-        let mut down_memory =
-            unsafe { self.device.allocate_memory(download_type, down_req.size) }.unwrap();
+        let mut down_memory = unsafe {
+            self.device.allocate_memory(
+                download_type,
+                down_req.size,
+                hal::memory::AllocationFlags::empty(),
+            )
+        }
+        .unwrap();
         unsafe {
             self.device
                 .bind_buffer_memory(&mut down_memory, 0, &mut down_buffer)